@@ -21,6 +21,9 @@
  * To do so, paging is enabled using a page directory that remaps the whole
  * kernel.
  *
+ * If the CPU supports Physical Address Extension (PAE), it is enabled and the
+ * kernel uses it for the rest of its execution. Else, 32 bits paging is used.
+ *
  * The created page directory has to be replaced when kernel memory management
  * is ready.
  */
@@ -31,17 +34,27 @@
 
 .type kernel_remap, @function
 .type pse_enable, @function
+.type pae_remap, @function
 
 .extern gdt_move
 
 /*
  * Remaps the first gigabyte of memory to the last one.
  *
- * This function enables PSE.
+ * This function enables PSE, or PAE if supported.
  */
 kernel_remap:
 	push %ebx
 
+	// Check for PAE support
+	mov $1, %eax
+	cpuid
+	test $(1 << 6), %edx
+	jz L3
+	call pae_remap
+	jmp L4
+
+L3:
 	// Zero page directory
 	xor %eax, %eax
 	mov $remap_dir, %esi
@@ -73,6 +86,7 @@ L2:
 	call pse_enable
 	add $4, %esp
 
+L4:
 	call gdt_move
 
 	pop %ebx
@@ -102,8 +116,64 @@ pse_enable:
 	pop %ebp
 	ret
 
+/*
+ * Enables paging with PAE, using a single page directory of large pages for
+ * both the first and the last gigabyte of memory.
+ */
+pae_remap:
+	// Fill entries
+	xor %eax, %eax
+	mov $remap_dir, %esi
+L5:
+	// (i * PAGE_SIZE * 512)
+	mov %eax, %ebx
+	mov $21, %cl
+	shl %cl, %ebx
+	// PAGE_SIZE | WRITE | PRESENT
+	or $(128 + 2 + 1), %ebx
+	movl %ebx, (%esi)
+	movl $0, 4(%esi)
+	add $8, %esi
+	add $1, %eax
+	cmp $512, %eax
+	jne L5
+
+	// Fill the PDPT
+	mov $remap_pdpt, %esi
+	mov $remap_dir, %ebx
+	or $1, %ebx
+	movl %ebx, (%esi)
+	movl $0, 4(%esi)
+	movl $0, 8(%esi)
+	movl $0, 12(%esi)
+	movl $0, 16(%esi)
+	movl $0, 20(%esi)
+	movl %ebx, 24(%esi)
+	movl $0, 28(%esi)
+
+	// Enable PAE and paging
+	mov %esi, %cr3
+
+	mov %cr4, %eax
+	or $0x00000020, %eax
+	mov %eax, %cr4
+
+	mov %cr0, %eax
+	or $0x80010000, %eax
+	mov %eax, %cr0
+
+	ret
+
 .section .boot.data, "aw"
 
+/*
+ * The Page Directory Pointer Table used for kernel remapping with PAE.
+ */
+.align 32
+remap_pdpt:
+.size remap_pdpt, 32
+.skip 32
+
 /*
  * The page directory used for kernel remapping.
  */
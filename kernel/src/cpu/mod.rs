@@ -22,6 +22,9 @@ use core::arch::asm;
 
 pub mod sse;
 
+/// Model Specific Register: Extended Feature Enable Register.
+pub const MSR_EFER: u32 = 0xc0000080;
+
 /// Returns the value stored into the specified register.
 #[macro_export]
 macro_rules! register_get {
@@ -85,6 +88,104 @@ pub fn get_hwcap() -> u32 {
 	cpuid(1, 0, 0, 0).3
 }
 
+/// HWCAP2 bit: `MONITOR` and `MWAIT` are usable from userspace.
+pub const HWCAP2_RING3MWAIT: u32 = 1 << 0;
+/// HWCAP2 bit: `RDFSBASE`, `RDGSBASE`, `WRFSBASE` and `WRGSBASE` are usable from userspace.
+pub const HWCAP2_FSGSBASE: u32 = 1 << 1;
+
+/// Returns HWCAP2 bitmask for ELF.
+///
+/// A feature is reported only if the CPU supports it and it is enabled for userspace. Since the
+/// kernel never enables ring 3 `MWAIT`, [`HWCAP2_RING3MWAIT`] is never reported.
+pub fn get_hwcap2() -> u32 {
+	let mut hwcap2 = 0;
+	let (_, flags, ..) = cpuid(7, 0, 0, 0);
+	// The instructions also have to be enabled in CR4
+	if flags & 1 != 0 && register_get!("cr4") & (1 << 16) != 0 {
+		hwcap2 |= HWCAP2_FSGSBASE;
+	}
+	hwcap2
+}
+
+/// Returns the platform name for ELF, according to the CPU family.
+pub fn get_platform() -> &'static [u8] {
+	let family = (cpuid(1, 0, 0, 0).0 >> 8) & 0xf;
+	match family {
+		..=3 => b"i386",
+		4 => b"i486",
+		5 => b"i586",
+		_ => b"i686",
+	}
+}
+
+/// Returns the highest extended function supported by the CPUID instruction.
+#[inline]
+fn cpuid_ext_max() -> u32 {
+	cpuid(0x80000000, 0, 0, 0).0
+}
+
+/// Tells whether Physical Address Extension (PAE) is supported.
+#[inline]
+pub fn supports_pae() -> bool {
+	get_hwcap() & (1 << 6) != 0
+}
+
+/// Tells whether the Execute Disable (XD, also called NX) bit is supported in paging entries.
+pub fn supports_nx() -> bool {
+	if cpuid_ext_max() < 0x80000001 {
+		return false;
+	}
+	cpuid(0x80000001, 0, 0, 0).3 & (1 << 20) != 0
+}
+
+/// Returns the number of bits of physical addresses supported by the CPU.
+pub fn phys_addr_bits() -> u32 {
+	if cpuid_ext_max() >= 0x80000008 {
+		cpuid(0x80000008, 0, 0, 0).0 & 0xff
+	} else if supports_pae() {
+		36
+	} else {
+		32
+	}
+}
+
+/// Reads the value of the Model Specific Register `msr`.
+///
+/// # Safety
+///
+/// If the register does not exist on the CPU, a General Protection Fault is raised.
+#[inline]
+pub unsafe fn rdmsr(msr: u32) -> u64 {
+	let low: u32;
+	let high: u32;
+	asm!(
+		"rdmsr",
+		in("ecx") msr,
+		out("eax") low,
+		out("edx") high,
+		options(nomem, nostack)
+	);
+	((high as u64) << 32) | low as u64
+}
+
+/// Writes `val` in the Model Specific Register `msr`.
+///
+/// # Safety
+///
+/// If the register does not exist on the CPU, a General Protection Fault is raised.
+///
+/// Modifying a register may change the behaviour of the CPU in ways that break memory safety.
+#[inline]
+pub unsafe fn wrmsr(msr: u32, val: u64) {
+	asm!(
+		"wrmsr",
+		in("ecx") msr,
+		in("eax") val as u32,
+		in("edx") (val >> 32) as u32,
+		options(nostack)
+	);
+}
+
 /// Tells whether SMEP and SMAP are supported (in that order).
 #[inline]
 pub fn supports_supervisor_prot() -> (bool, bool) {
@@ -17,14 +17,131 @@
  */
 
 //! SSE-related features.
+//!
+//! If the CPU supports it, the XSAVE feature set is enabled as well, to allow saving and
+//! restoring the extended processor state (AVX, AVX-512, etc...).
 
-use crate::{cpu::get_hwcap, register_get, register_set};
+use crate::{
+	cpu::{cpuid, get_hwcap},
+	register_get, register_set,
+};
+use core::{
+	arch::asm,
+	sync::atomic::{
+		AtomicBool, AtomicU32, AtomicUsize,
+		Ordering::{Acquire, Release},
+	},
+};
+
+/// The default mask of supported MXCSR bits, if the CPU reports none.
+const DEFAULT_MXCSR_MASK: u32 = 0xffbf;
+
+/// The size of the XSAVE area in bytes. If zero, XSAVE is not enabled.
+static XSAVE_SIZE: AtomicUsize = AtomicUsize::new(0);
+/// Tells whether the XSAVEOPT instruction is supported.
+static XSAVEOPT: AtomicBool = AtomicBool::new(false);
+/// The mask of supported MXCSR bits.
+static MXCSR_MASK: AtomicU32 = AtomicU32::new(DEFAULT_MXCSR_MASK);
 
 /// Tells whether the CPU supports SSE.
 pub fn is_present() -> bool {
 	get_hwcap() & (1 << 25) != 0
 }
 
+/// Tells whether the CPU supports the XSAVE feature set.
+pub fn has_xsave() -> bool {
+	cpuid(1, 0, 0, 0).2 & (1 << 26) != 0
+}
+
+/// Reads the value of the extended control register `xcr`.
+///
+/// # Safety
+///
+/// XSAVE must be enabled.
+#[inline]
+pub unsafe fn xgetbv(xcr: u32) -> u64 {
+	let low: u32;
+	let high: u32;
+	asm!(
+		"xgetbv",
+		in("ecx") xcr,
+		out("eax") low,
+		out("edx") high,
+		options(nomem, nostack)
+	);
+	((high as u64) << 32) | low as u64
+}
+
+/// Writes `val` in the extended control register `xcr`.
+///
+/// # Safety
+///
+/// XSAVE must be enabled and `val` must be a valid value for the register.
+#[inline]
+pub unsafe fn xsetbv(xcr: u32, val: u64) {
+	asm!(
+		"xsetbv",
+		in("ecx") xcr,
+		in("eax") val as u32,
+		in("edx") (val >> 32) as u32,
+		options(nostack)
+	);
+}
+
+/// Returns the size of the XSAVE area in bytes.
+///
+/// If XSAVE is not enabled, the function returns `0`.
+#[inline]
+pub fn xsave_size() -> usize {
+	XSAVE_SIZE.load(Acquire)
+}
+
+/// Tells whether the XSAVEOPT instruction can be used instead of XSAVE.
+#[inline]
+pub fn has_xsaveopt() -> bool {
+	XSAVEOPT.load(Acquire)
+}
+
+/// Returns the set of state components enabled in XCR0.
+///
+/// If XSAVE is not enabled, the function returns `0`.
+pub fn xfeatures() -> u64 {
+	if xsave_size() == 0 {
+		return 0;
+	}
+	unsafe { xgetbv(0) }
+}
+
+/// Returns the mask of the MXCSR bits supported by the CPU.
+#[inline]
+pub fn mxcsr_mask() -> u32 {
+	MXCSR_MASK.load(Acquire)
+}
+
+/// Wrapper allowing to align the buffer used to probe the FXSAVE area.
+#[repr(align(16))]
+struct FXArea([u8; 512]);
+
+/// Enables the XSAVE feature set along with every state component supported by the CPU.
+fn enable_xsave() {
+	// Enable XSAVE
+	let cr4 = register_get!("cr4") | (1 << 18);
+	unsafe {
+		register_set!("cr4", cr4);
+	}
+	// Enable every state component supported for XCR0
+	let (low, _, _, high) = cpuid(0xd, 0, 0, 0);
+	let features = ((high as u64) << 32) | low as u64;
+	unsafe {
+		xsetbv(0, features);
+	}
+	// The size of the area depends on the components enabled in XCR0
+	let (_, size, ..) = cpuid(0xd, 0, 0, 0);
+	let (opt, ..) = cpuid(0xd, 0, 1, 0);
+	XSAVEOPT.store(opt & 1 != 0, Release);
+	XSAVE_SIZE.store(size as usize, Release);
+}
+
 /// Enables SSE.
 pub fn enable() {
 	// Enable x87 FPU
@@ -35,4 +152,49 @@ pub fn enable() {
 		register_set!("cr0", cr0);
 		register_set!("cr4", cr4);
 	}
+	// Retrieve the mask of supported MXCSR bits
+	let mut area = FXArea([0; 512]);
+	unsafe {
+		asm!("fxsave [{}]", in(reg) area.0.as_mut_ptr());
+	}
+	let mask = u32::from_le_bytes(area.0[28..32].try_into().unwrap());
+	if mask != 0 {
+		MXCSR_MASK.store(mask, Release);
+	}
+	if has_xsave() {
+		enable_xsave();
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::cpu::{get_hwcap2, HWCAP2_FSGSBASE, HWCAP2_RING3MWAIT};
+
+	#[test_case]
+	fn xsave_area_size() {
+		let size = xsave_size();
+		if size == 0 {
+			assert_eq!(xfeatures(), 0);
+			return;
+		}
+		// The legacy region and the header are always present
+		assert!(size >= 576);
+		// x87 and SSE are always enabled
+		assert_eq!(xfeatures() & 0b11, 0b11);
+		// The size matches the components enabled in XCR0
+		assert_eq!(size, cpuid(0xd, 0, 0, 0).1 as usize);
+	}
+
+	#[test_case]
+	fn hwcap2() {
+		let hwcap2 = get_hwcap2();
+		assert_eq!(hwcap2 & !(HWCAP2_FSGSBASE | HWCAP2_RING3MWAIT), 0);
+		// Ring 3 `MWAIT` is never enabled
+		assert_eq!(hwcap2 & HWCAP2_RING3MWAIT, 0);
+		// FSGSBASE is reported only if supported
+		if cpuid(7, 0, 0, 0).1 & 1 == 0 {
+			assert_eq!(hwcap2 & HWCAP2_FSGSBASE, 0);
+		}
+	}
 }
@@ -148,6 +148,15 @@ impl EntropyPool {
 /// The entropy pool.
 pub static ENTROPY_POOL: IntMutex<Option<EntropyPool>> = IntMutex::new(None);
 
+/// Fills `buf` with random bytes.
+///
+/// If the pool does not contain enough entropy, pseudo-random bytes are used instead.
+pub fn fill(buf: &mut [u8]) {
+	if let Some(pool) = &mut *ENTROPY_POOL.lock() {
+		pool.read(buf, true);
+	}
+}
+
 /// Initializes randomness sources.
 pub(super) fn init() -> AllocResult<()> {
 	*ENTROPY_POOL.lock() = Some(EntropyPool::new()?);
@@ -296,7 +296,7 @@ impl PCIDevice {
 			let prefetchable = value & 0b1000 != 0;
 			// Create MMIO
 			let pages = size.div_ceil(PAGE_SIZE);
-			let mmio = MMIO::new(PhysAddr(phys_addr as _), pages, prefetchable)?;
+			let mmio = MMIO::new(PhysAddr(phys_addr), pages, prefetchable)?;
 			Ok(Some((
 				BAR::MemorySpace {
 					type_,
@@ -48,6 +48,7 @@ use crate::{
 		perm::AccessProfile,
 		vfs,
 		vfs::{ResolutionSettings, Resolved},
+		wait_queue::WaitQueue,
 		FileType, Mode, Stat,
 	},
 	syscall::ioctl,
@@ -182,12 +183,24 @@ pub trait DeviceIO {
 		Ok(buf_off)
 	}
 
+	/// Hook called when the current process opens the device file with the given `flags`.
+	fn open(&self, flags: i32) -> EResult<()> {
+		let _ = flags;
+		Ok(())
+	}
+
 	/// Polls the device with the given mask.
 	fn poll(&self, mask: u32) -> EResult<u32> {
 		let _ = mask;
 		Err(errno!(EINVAL))
 	}
 
+	/// Calls `f` on each wait queue on which the device signals events.
+	fn wait_queues(&self, f: &mut dyn FnMut(&WaitQueue) -> EResult<()>) -> EResult<()> {
+		let _ = f;
+		Ok(())
+	}
+
 	/// Performs an ioctl operation on the device.
 	///
 	/// Arguments:
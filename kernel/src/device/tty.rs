@@ -21,6 +21,7 @@
 
 use crate::{
 	device::DeviceIO,
+	file::{perm::CAP_SYS_ADMIN, wait_queue::WaitQueue, O_NOCTTY},
 	process::{
 		mem_space::copy::SyscallPtr,
 		pid::Pid,
@@ -123,12 +124,33 @@ impl DeviceIO for TTYDeviceHandle {
 		self.write(off, buf)
 	}
 
+	fn open(&self, flags: i32) -> EResult<()> {
+		if flags & O_NOCTTY != 0 {
+			return Ok(());
+		}
+		let (sid, pgid, session_leader) = {
+			let proc_mutex = Process::current();
+			let proc = proc_mutex.lock();
+			(proc.sid, proc.pgid, proc.is_session_leader())
+		};
+		// A session leader acquires the TTY as its controlling terminal if it is not attached yet
+		let mut tty = TTY.display.lock();
+		if session_leader && tty.get_sid() == 0 {
+			tty.set_session(sid, pgid);
+		}
+		Ok(())
+	}
+
 	fn poll(&self, mask: u32) -> EResult<u32> {
 		let input = TTY.has_input_available();
 		let res = (if input { POLLIN } else { 0 } | POLLOUT) & mask;
 		Ok(res)
 	}
 
+	fn wait_queues(&self, f: &mut dyn FnMut(&WaitQueue) -> EResult<()>) -> EResult<()> {
+		f(TTY.get_read_queue())
+	}
+
 	fn ioctl(&self, request: ioctl::Request, argp: *const c_void) -> EResult<u32> {
 		let mut tty = TTY.display.lock();
 		match request.get_old_format() {
@@ -156,9 +178,69 @@ impl DeviceIO for TTYDeviceHandle {
 				self.check_sigttou(&tty)?;
 				let pgid_ptr = SyscallPtr::<Pid>::from_syscall_arg(argp as usize);
 				let pgid = pgid_ptr.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+				// If attached to a session, the group must belong to it
+				if tty.get_sid() != 0 {
+					let sid = Process::current().lock().sid;
+					if tty.get_sid() != sid {
+						return Err(errno!(ENOTTY));
+					}
+					let leader = Process::get_by_pid(pgid).ok_or_else(|| errno!(EPERM))?;
+					if leader.lock().sid != sid {
+						return Err(errno!(EPERM));
+					}
+				}
 				tty.set_pgrp(pgid);
 				Ok(0)
 			}
+			ioctl::TIOCSCTTY => {
+				let (sid, pgid, session_leader, privileged) = {
+					let proc_mutex = Process::current();
+					let proc = proc_mutex.lock();
+					(
+						proc.sid,
+						proc.pgid,
+						proc.is_session_leader(),
+						proc.access_profile.has_cap(CAP_SYS_ADMIN),
+					)
+				};
+				if !session_leader {
+					return Err(errno!(EPERM));
+				}
+				if tty.get_sid() == sid {
+					return Ok(0);
+				}
+				// Stealing the TTY from another session requires privileges
+				let force = argp as usize == 1;
+				if tty.get_sid() != 0 && !(force && privileged) {
+					return Err(errno!(EPERM));
+				}
+				tty.set_session(sid, pgid);
+				Ok(0)
+			}
+			ioctl::TIOCNOTTY => {
+				let (sid, session_leader) = {
+					let proc_mutex = Process::current();
+					let proc = proc_mutex.lock();
+					(proc.sid, proc.is_session_leader())
+				};
+				if tty.get_sid() == 0 || tty.get_sid() != sid {
+					return Err(errno!(ENOTTY));
+				}
+				// If the session leader gives up the TTY, the whole session loses it
+				if session_leader {
+					tty.hangup();
+				}
+				Ok(0)
+			}
+			ioctl::TIOCGSID => {
+				let sid = Process::current().lock().sid;
+				if tty.get_sid() == 0 || tty.get_sid() != sid {
+					return Err(errno!(ENOTTY));
+				}
+				let sid_ptr = SyscallPtr::<Pid>::from_syscall_arg(argp as usize);
+				sid_ptr.copy_to_user(sid)?;
+				Ok(0)
+			}
 			ioctl::TIOCGWINSZ => {
 				let winsize = SyscallPtr::<WinSize>::from_syscall_arg(argp as usize);
 				winsize.copy_to_user(tty.get_winsize().clone())?;
@@ -64,7 +64,7 @@ pub fn get_section_name(section: &ELF32SectionHeader) -> Option<&'static [u8]> {
 	let boot_info = multiboot::get_boot_info();
 	// `unwrap` cannot fail because the ELF will always have this section
 	let names_section = get_section_by_offset(boot_info.elf_shndx).unwrap();
-	let ptr = PhysAddr(names_section.sh_addr as usize + section.sh_name as usize)
+	let ptr = PhysAddr(names_section.sh_addr as u64 + section.sh_name as u64)
 		.kernel_to_virtual()
 		.unwrap()
 		.as_ptr();
@@ -86,7 +86,7 @@ pub fn symbols() -> impl Iterator<Item = &'static ELF32Sym> {
 	let symtab = sections()
 		.find(|section| section.sh_type == SHT_SYMTAB)
 		.unwrap();
-	let begin: *const u8 = PhysAddr(symtab.sh_addr as usize)
+	let begin: *const u8 = PhysAddr(symtab.sh_addr as _)
 		.kernel_to_virtual()
 		.unwrap()
 		.as_ptr();
@@ -101,7 +101,7 @@ pub fn symbols() -> impl Iterator<Item = &'static ELF32Sym> {
 ///
 /// If the name of the symbol could not be found, the function returns `None`.
 pub fn get_symbol_name(symbol: &ELF32Sym) -> Option<&'static [u8]> {
-	let ptr = PhysAddr(STRTAB.get().sh_addr as usize + symbol.st_name as usize)
+	let ptr = PhysAddr(STRTAB.get().sh_addr as u64 + symbol.st_name as u64)
 		.kernel_to_virtual()
 		.unwrap()
 		.as_ptr();
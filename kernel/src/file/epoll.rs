@@ -0,0 +1,481 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `epoll` is an event notification mechanism, allowing to monitor a set of files.
+//!
+//! Instead of polling each monitored file at every call, an epoll instance registers a watcher on
+//! the wait queues of the files, so that only the files on which something happened are polled.
+
+use crate::{
+	file::{
+		fd::FileDescriptorTable,
+		wait_queue::{WaitQueue, Watcher},
+		File, FileOps, Stat,
+	},
+	process::{scheduler, Process},
+	syscall::{ioctl, poll::POLLIN},
+	time::{
+		clock,
+		clock::CLOCK_MONOTONIC,
+		unit::{Timestamp, TimestampScale},
+	},
+};
+use core::{
+	ffi::{c_int, c_void},
+	ptr,
+	sync::atomic::{AtomicBool, Ordering},
+};
+use utils::{
+	collections::{btreemap::BTreeMap, vec::Vec},
+	errno,
+	errno::{AllocResult, EResult},
+	lock::Mutex,
+	ptr::arc::Arc,
+};
+
+/// `epoll_ctl` operation: Add a file to the interest list.
+pub const EPOLL_CTL_ADD: c_int = 1;
+/// `epoll_ctl` operation: Remove a file from the interest list.
+pub const EPOLL_CTL_DEL: c_int = 2;
+/// `epoll_ctl` operation: Change the events monitored on a file.
+pub const EPOLL_CTL_MOD: c_int = 3;
+
+/// Event: The file is available for reading.
+pub const EPOLLIN: u32 = 0x001;
+/// Event: There is an exceptional condition on the file.
+pub const EPOLLPRI: u32 = 0x002;
+/// Event: The file is available for writing.
+pub const EPOLLOUT: u32 = 0x004;
+/// Event: An error occurred on the file. Always monitored.
+pub const EPOLLERR: u32 = 0x008;
+/// Event: Hang up happened on the file. Always monitored.
+pub const EPOLLHUP: u32 = 0x010;
+/// Event: Equivalent to [`EPOLLIN`].
+pub const EPOLLRDNORM: u32 = 0x040;
+/// Event: Priority band data can be read.
+pub const EPOLLRDBAND: u32 = 0x080;
+/// Event: Equivalent to [`EPOLLOUT`].
+pub const EPOLLWRNORM: u32 = 0x100;
+/// Event: Priority data may be written.
+pub const EPOLLWRBAND: u32 = 0x200;
+/// Event: Stream socket peer closed connection, or shut down writing half of connection.
+pub const EPOLLRDHUP: u32 = 0x2000;
+
+/// Flag: Only one of the epoll instances monitoring the file is woken up.
+pub const EPOLLEXCLUSIVE: u32 = 1 << 28;
+/// Flag: Prevents system suspend while the event is pending.
+pub const EPOLLWAKEUP: u32 = 1 << 29;
+/// Flag: The file is disabled after an event has been reported for it.
+pub const EPOLLONESHOT: u32 = 1 << 30;
+/// Flag: Edge-triggered mode. An event is reported only when the state of the file changes.
+pub const EPOLLET: u32 = 1 << 31;
+
+/// The bits of an event mask that are flags, not events.
+const EPOLL_FLAGS: u32 = EPOLLEXCLUSIVE | EPOLLWAKEUP | EPOLLONESHOT | EPOLLET;
+/// The maximum depth of nested epoll instances.
+const EP_MAX_NESTS: usize = 4;
+
+/// An event, as exchanged with userspace.
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EpollEvent {
+	/// The mask of events.
+	pub events: u32,
+	/// User data, returned along with the events.
+	pub data: u64,
+}
+
+/// A file in the interest list of an epoll instance.
+#[derive(Debug)]
+struct Item {
+	/// The file descriptor of the file.
+	fd: c_int,
+	/// The address of the open file description, used to detect that `fd` has been closed or
+	/// reused.
+	file: usize,
+	/// The monitored events, along with user data.
+	event: Mutex<EpollEvent>,
+	/// Tells whether an event may have occurred since the last time the file was polled.
+	ready: AtomicBool,
+	/// Tells whether the item has been removed from the interest list.
+	removed: AtomicBool,
+	/// The queue of the epoll instance.
+	queue: Arc<WaitQueue>,
+}
+
+impl Watcher for Item {
+	fn notify(&self) -> bool {
+		if self.removed.load(Ordering::Acquire) {
+			return false;
+		}
+		self.ready.store(true, Ordering::Release);
+		self.queue.wake_all();
+		true
+	}
+}
+
+/// Returns the file associated with the given item, if still open.
+fn item_file(fds: &Mutex<FileDescriptorTable>, item: &Item) -> Option<Arc<File>> {
+	let fds = fds.lock();
+	let file = fds.get_fd(item.fd).ok()?.get_file();
+	(file.as_ptr() as usize == item.file).then(|| file.clone())
+}
+
+/// An epoll instance.
+#[derive(Debug)]
+pub struct EventPoll {
+	/// The interest list, by file descriptor.
+	items: Mutex<BTreeMap<c_int, Arc<Item>>>,
+	/// The queue of processes waiting for events.
+	queue: Arc<WaitQueue>,
+}
+
+impl EventPoll {
+	/// Creates a new instance.
+	pub fn new() -> AllocResult<Self> {
+		Ok(Self {
+			items: Mutex::new(BTreeMap::new()),
+			queue: Arc::new(WaitQueue::new())?,
+		})
+	}
+
+	/// Adds `file`, with file descriptor `fd`, to the interest list.
+	///
+	/// If the file is already in the list, the function returns [`errno::EEXIST`].
+	pub fn add(&self, fd: c_int, file: &Arc<File>, mut event: EpollEvent) -> EResult<()> {
+		event.events |= EPOLLERR | EPOLLHUP;
+		let mut items = self.items.lock();
+		if items.contains_key(&fd) {
+			return Err(errno!(EEXIST));
+		}
+		let item = Arc::new(Item {
+			fd,
+			file: file.as_ptr() as usize,
+			event: Mutex::new(event),
+			// Poll the file at least once
+			ready: AtomicBool::new(true),
+			removed: AtomicBool::new(false),
+			queue: self.queue.clone(),
+		})?;
+		items.insert(fd, item.clone())?;
+		let res = file
+			.ops
+			.wait_queues(file, &mut |queue| Ok(queue.add_watcher(item.clone())?));
+		if let Err(e) = res {
+			items.remove(&fd);
+			item.removed.store(true, Ordering::Release);
+			return Err(e);
+		}
+		drop(items);
+		self.queue.wake_all();
+		Ok(())
+	}
+
+	/// Changes the events monitored on the file with descriptor `fd`.
+	///
+	/// If the file is not in the list, the function returns [`errno::ENOENT`].
+	pub fn modify(&self, fd: c_int, mut event: EpollEvent) -> EResult<()> {
+		event.events |= EPOLLERR | EPOLLHUP;
+		{
+			let items = self.items.lock();
+			let item = items.get(&fd).ok_or_else(|| errno!(ENOENT))?;
+			*item.event.lock() = event;
+			item.ready.store(true, Ordering::Release);
+		}
+		self.queue.wake_all();
+		Ok(())
+	}
+
+	/// Removes the file with descriptor `fd` from the interest list.
+	///
+	/// If the file is not in the list, the function returns [`errno::ENOENT`].
+	pub fn remove(&self, fd: c_int) -> EResult<()> {
+		let item = self
+			.items
+			.lock()
+			.remove(&fd)
+			.ok_or_else(|| errno!(ENOENT))?;
+		item.removed.store(true, Ordering::Release);
+		Ok(())
+	}
+
+	/// Checks that adding the instance `target` to the interest list of `self` does not create
+	/// a loop, or a nesting deeper than allowed.
+	///
+	/// If it does, the function returns [`errno::ELOOP`].
+	pub fn check_loop(&self, fds: &FileDescriptorTable, target: &EventPoll) -> EResult<()> {
+		target.check_loop_impl(fds, self, 1)
+	}
+
+	/// Implementation of [`Self::check_loop`]: checks whether `self`, at nesting level `depth`,
+	/// monitors `epoll`.
+	fn check_loop_impl(
+		&self,
+		fds: &FileDescriptorTable,
+		epoll: &EventPoll,
+		depth: usize,
+	) -> EResult<()> {
+		if depth >= EP_MAX_NESTS {
+			return Err(errno!(ELOOP));
+		}
+		let items = self.items.lock();
+		for (_, item) in items.iter() {
+			let Ok(fd) = fds.get_fd(item.fd) else {
+				continue;
+			};
+			let file = fd.get_file();
+			if file.as_ptr() as usize != item.file {
+				continue;
+			}
+			let Some(nested) = file.get_buffer::<EventPoll>() else {
+				continue;
+			};
+			if ptr::eq(nested, epoll) {
+				return Err(errno!(ELOOP));
+			}
+			nested.check_loop_impl(fds, epoll, depth + 1)?;
+		}
+		Ok(())
+	}
+
+	/// Polls the files on which an event may have occurred and returns the events, at most
+	/// `max`.
+	///
+	/// `fds` is the file descriptors table the interest list refers to.
+	fn collect(&self, fds: &Mutex<FileDescriptorTable>, max: usize) -> EResult<Vec<EpollEvent>> {
+		// Do not keep the interest list locked while polling files
+		let mut candidates = Vec::new();
+		for (_, item) in self.items.lock().iter() {
+			if item.ready.load(Ordering::Acquire) {
+				candidates.push(item.clone())?;
+			}
+		}
+		let mut events = Vec::new();
+		for item in candidates {
+			if events.len() >= max {
+				break;
+			}
+			if !item.ready.swap(false, Ordering::AcqRel) {
+				continue;
+			}
+			// The file has been closed: remove it
+			let Some(file) = item_file(fds, &item) else {
+				let mut items = self.items.lock();
+				if items
+					.get(&item.fd)
+					.is_some_and(|i| ptr::eq(i.as_ref(), item.as_ref()))
+				{
+					items.remove(&item.fd);
+					item.removed.store(true, Ordering::Release);
+				}
+				continue;
+			};
+			let mut event = item.event.lock();
+			let mask = event.events & !EPOLL_FLAGS;
+			if mask == 0 {
+				continue;
+			}
+			let revents = file.ops.poll(&file, mask)? & mask;
+			if revents == 0 {
+				continue;
+			}
+			events.push(EpollEvent {
+				events: revents,
+				data: event.data,
+			})?;
+			if event.events & EPOLLONESHOT != 0 {
+				// Disable the file until it is modified
+				event.events &= EPOLL_FLAGS;
+			} else if event.events & EPOLLET == 0 {
+				// Level-triggered: the file must be polled again next time
+				item.ready.store(true, Ordering::Release);
+			}
+		}
+		Ok(events)
+	}
+
+	/// Waits for events on the files of the interest list, and returns at most `max` of them.
+	///
+	/// Arguments:
+	/// - `fds` is the file descriptors table the interest list refers to.
+	/// - `timeout` is the timeout in milliseconds. If `None`, the function waits indefinitely.
+	///
+	/// If interrupted by a signal, the function returns [`errno::EINTR`].
+	pub fn wait(
+		&self,
+		fds: &Mutex<FileDescriptorTable>,
+		max: usize,
+		timeout: Option<Timestamp>,
+	) -> EResult<Vec<EpollEvent>> {
+		let Some(timeout) = timeout else {
+			return self
+				.queue
+				.wait_until(|| match self.collect(fds, max) {
+					Ok(events) if events.is_empty() => None,
+					res => Some(res),
+				})
+				// Never restarted, regardless of `SA_RESTART`
+				.map_err(|e| {
+					if e.as_int() == errno::ERESTARTSYS {
+						errno!(EINTR)
+					} else {
+						e
+					}
+				})?;
+		};
+		let start = clock::current_time(CLOCK_MONOTONIC, TimestampScale::Millisecond)?;
+		loop {
+			let events = self.collect(fds, max)?;
+			if !events.is_empty() {
+				return Ok(events);
+			}
+			let now = clock::current_time(CLOCK_MONOTONIC, TimestampScale::Millisecond)?;
+			if now >= start + timeout {
+				return Ok(events);
+			}
+			if Process::current().lock().next_signal(true).is_some() {
+				return Err(errno!(EINTR));
+			}
+			scheduler::end_tick();
+		}
+	}
+}
+
+impl FileOps for EventPoll {
+	fn get_stat(&self, _file: &File) -> EResult<Stat> {
+		Ok(Stat {
+			mode: 0o600,
+			..Default::default()
+		})
+	}
+
+	fn acquire(&self, _file: &File) {}
+
+	fn release(&self, _file: &File) {
+		let mut items = self.items.lock();
+		for (_, item) in items.iter() {
+			item.removed.store(true, Ordering::Release);
+		}
+		items.clear();
+	}
+
+	fn poll(&self, _file: &File, mask: u32) -> EResult<u32> {
+		let ready = self
+			.items
+			.lock()
+			.iter()
+			.any(|(_, item)| item.ready.load(Ordering::Acquire));
+		Ok(if ready { POLLIN } else { 0 } & mask)
+	}
+
+	fn wait_queues(
+		&self,
+		_file: &File,
+		f: &mut dyn FnMut(&WaitQueue) -> EResult<()>,
+	) -> EResult<()> {
+		f(&self.queue)
+	}
+
+	fn ioctl(&self, _file: &File, _request: ioctl::Request, _argp: *const c_void) -> EResult<u32> {
+		Err(errno!(ENOTTY))
+	}
+
+	fn read(&self, _file: &File, _off: u64, _buf: &mut [u8]) -> EResult<usize> {
+		Err(errno!(EINVAL))
+	}
+
+	fn write(&self, _file: &File, _off: u64, _buf: &[u8]) -> EResult<usize> {
+		Err(errno!(EINVAL))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::file::{pipe::PipeBuffer, O_RDONLY, O_WRONLY};
+
+	/// Creates a pipe in a new file descriptors table, and returns the table along with the
+	/// reading and writing ends.
+	fn pipe() -> (Mutex<FileDescriptorTable>, Arc<File>, Arc<File>) {
+		let ops = Arc::new(PipeBuffer::new().unwrap()).unwrap();
+		let rd = File::open_floating(ops.clone(), O_RDONLY).unwrap();
+		let wr = File::open_floating(ops, O_WRONLY).unwrap();
+		let mut fds = FileDescriptorTable::default();
+		fds.create_fd_pair(rd.clone(), wr.clone()).unwrap();
+		(Mutex::new(fds), rd, wr)
+	}
+
+	#[test_case]
+	fn epoll_level_triggered() {
+		let (fds, rd, wr) = pipe();
+		let epoll = EventPoll::new().unwrap();
+		let event = EpollEvent {
+			events: EPOLLIN,
+			data: 42,
+		};
+		epoll.add(0, &rd, event).unwrap();
+		assert_eq!(
+			epoll.add(0, &rd, event).unwrap_err().as_int(),
+			errno::EEXIST
+		);
+		assert!(epoll.collect(&fds, 16).unwrap().is_empty());
+		wr.ops.write(&wr, 0, b"abc").unwrap();
+		let events = epoll.collect(&fds, 16).unwrap();
+		assert_eq!(events.len(), 1);
+		assert_eq!({ events[0].data }, 42);
+		// The data has not been read, thus the event is reported again
+		assert_eq!(epoll.collect(&fds, 16).unwrap().len(), 1);
+		epoll.remove(0).unwrap();
+		assert!(epoll.collect(&fds, 16).unwrap().is_empty());
+	}
+
+	#[test_case]
+	fn epoll_edge_triggered() {
+		let (fds, rd, wr) = pipe();
+		let epoll = EventPoll::new().unwrap();
+		let event = EpollEvent {
+			events: EPOLLIN | EPOLLET,
+			data: 0,
+		};
+		epoll.add(0, &rd, event).unwrap();
+		wr.ops.write(&wr, 0, b"abc").unwrap();
+		assert_eq!(epoll.collect(&fds, 16).unwrap().len(), 1);
+		assert!(epoll.collect(&fds, 16).unwrap().is_empty());
+		// New data triggers a new event
+		wr.ops.write(&wr, 0, b"abc").unwrap();
+		assert_eq!(epoll.collect(&fds, 16).unwrap().len(), 1);
+	}
+
+	#[test_case]
+	fn epoll_oneshot() {
+		let (fds, rd, wr) = pipe();
+		let epoll = EventPoll::new().unwrap();
+		let event = EpollEvent {
+			events: EPOLLIN | EPOLLONESHOT,
+			data: 0,
+		};
+		epoll.add(0, &rd, event).unwrap();
+		wr.ops.write(&wr, 0, b"abc").unwrap();
+		assert_eq!(epoll.collect(&fds, 16).unwrap().len(), 1);
+		wr.ops.write(&wr, 0, b"abc").unwrap();
+		assert!(epoll.collect(&fds, 16).unwrap().is_empty());
+		// Re-arm
+		epoll.modify(0, event).unwrap();
+		assert_eq!(epoll.collect(&fds, 16).unwrap().len(), 1);
+	}
+}
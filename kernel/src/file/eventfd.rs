@@ -0,0 +1,191 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! An eventfd is a file descriptor holding a counter, used as an event wait/notify mechanism.
+//!
+//! Writing to the file adds to the counter, and reading from it returns and resets the counter.
+
+use crate::{
+	file::{wait_queue::WaitQueue, File, FileOps, Stat, O_NONBLOCK},
+	syscall::{
+		ioctl,
+		poll::{POLLIN, POLLOUT},
+	},
+};
+use core::{ffi::c_void, intrinsics::unlikely, mem::size_of};
+use utils::{errno, errno::EResult, lock::IntMutex};
+
+/// Flag: Reading decrements the counter by one instead of resetting it.
+pub const EFD_SEMAPHORE: i32 = 1;
+
+/// The maximum value of the counter.
+const COUNTER_MAX: u64 = u64::MAX - 1;
+
+/// An eventfd.
+#[derive(Debug)]
+pub struct EventFd {
+	/// The counter.
+	counter: IntMutex<u64>,
+	/// Tells whether the eventfd has semaphore semantics.
+	semaphore: bool,
+	/// The queue of processes waiting to read or to write.
+	queue: WaitQueue,
+}
+
+impl EventFd {
+	/// Creates an eventfd with the given initial value.
+	///
+	/// If `semaphore` is set, reading decrements the counter by one instead of resetting it.
+	pub fn new(initval: u32, semaphore: bool) -> Self {
+		Self {
+			counter: IntMutex::new(initval as _),
+			semaphore,
+			queue: WaitQueue::new(),
+		}
+	}
+}
+
+impl FileOps for EventFd {
+	fn get_stat(&self, _file: &File) -> EResult<Stat> {
+		Ok(Stat {
+			mode: 0o600,
+			..Default::default()
+		})
+	}
+
+	fn acquire(&self, _file: &File) {}
+
+	fn release(&self, _file: &File) {}
+
+	fn poll(&self, _file: &File, mask: u32) -> EResult<u32> {
+		let counter = *self.counter.lock();
+		let mut res = 0;
+		if counter > 0 {
+			res |= POLLIN;
+		}
+		if counter < COUNTER_MAX {
+			res |= POLLOUT;
+		}
+		Ok(res & mask)
+	}
+
+	fn wait_queues(
+		&self,
+		_file: &File,
+		f: &mut dyn FnMut(&WaitQueue) -> EResult<()>,
+	) -> EResult<()> {
+		f(&self.queue)
+	}
+
+	fn ioctl(&self, _file: &File, _request: ioctl::Request, _argp: *const c_void) -> EResult<u32> {
+		Err(errno!(ENOTTY))
+	}
+
+	fn read(&self, file: &File, _off: u64, buf: &mut [u8]) -> EResult<usize> {
+		if unlikely(buf.len() < size_of::<u64>()) {
+			return Err(errno!(EINVAL));
+		}
+		let nonblock = file.get_flags() & O_NONBLOCK != 0;
+		let val = self.queue.wait_until(|| {
+			let mut counter = self.counter.lock();
+			match *counter {
+				0 if nonblock => Some(Err(errno!(EAGAIN))),
+				0 => None,
+				_ if self.semaphore => {
+					*counter -= 1;
+					Some(Ok(1))
+				}
+				n => {
+					*counter = 0;
+					Some(Ok(n))
+				}
+			}
+		})??;
+		// Wake writers
+		self.queue.wake_all();
+		buf[..size_of::<u64>()].copy_from_slice(&val.to_ne_bytes());
+		Ok(size_of::<u64>())
+	}
+
+	fn write(&self, file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		let Some(val) = buf.get(..size_of::<u64>()) else {
+			return Err(errno!(EINVAL));
+		};
+		let val = u64::from_ne_bytes(val.try_into().unwrap());
+		if unlikely(val == u64::MAX) {
+			return Err(errno!(EINVAL));
+		}
+		let nonblock = file.get_flags() & O_NONBLOCK != 0;
+		self.queue.wait_until(|| {
+			let mut counter = self.counter.lock();
+			// Block until the value can be added without overflowing
+			if COUNTER_MAX - *counter >= val {
+				*counter += val;
+				Some(Ok(()))
+			} else if nonblock {
+				Some(Err(errno!(EAGAIN)))
+			} else {
+				None
+			}
+		})??;
+		// Wake readers
+		if val > 0 {
+			self.queue.wake_all();
+		}
+		Ok(size_of::<u64>())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::file::O_RDWR;
+	use utils::ptr::arc::Arc;
+
+	/// Reads the counter of the given eventfd.
+	fn read(file: &File) -> EResult<u64> {
+		let mut buf = [0; 8];
+		file.ops.read(file, 0, &mut buf)?;
+		Ok(u64::from_ne_bytes(buf))
+	}
+
+	#[test_case]
+	fn eventfd_counter() {
+		let ops = Arc::new(EventFd::new(2, false)).unwrap();
+		let file = File::open_floating(ops, O_RDWR | O_NONBLOCK).unwrap();
+		file.ops.write(&file, 0, &3u64.to_ne_bytes()).unwrap();
+		assert_eq!(read(&file).unwrap(), 5);
+		assert_eq!(read(&file).unwrap_err().as_int(), errno::EAGAIN);
+		assert_eq!(
+			file.ops
+				.write(&file, 0, &u64::MAX.to_ne_bytes())
+				.unwrap_err()
+				.as_int(),
+			errno::EINVAL
+		);
+	}
+
+	#[test_case]
+	fn eventfd_semaphore() {
+		let ops = Arc::new(EventFd::new(2, true)).unwrap();
+		let file = File::open_floating(ops, O_RDWR | O_NONBLOCK).unwrap();
+		assert_eq!(read(&file).unwrap(), 1);
+		assert_eq!(read(&file).unwrap(), 1);
+		assert_eq!(read(&file).unwrap_err().as_int(), errno::EAGAIN);
+	}
+}
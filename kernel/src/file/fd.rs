@@ -119,10 +119,32 @@ impl FileDescriptor {
 }
 
 /// A table of file descriptors.
-#[derive(Default)]
-pub struct FileDescriptorTable(Vec<Option<FileDescriptor>>);
+pub struct FileDescriptorTable {
+	/// The file descriptors, indexed by ID.
+	fds: Vec<Option<FileDescriptor>>,
+	/// A value one greater than the maximum ID a file descriptor can have.
+	///
+	/// This is the soft `RLIMIT_NOFILE` limit of the process owning the table.
+	limit: u32,
+}
+
+impl Default for FileDescriptorTable {
+	fn default() -> Self {
+		Self {
+			fds: Vec::new(),
+			limit: OPEN_MAX,
+		}
+	}
+}
 
 impl FileDescriptorTable {
+	/// Sets the limit on file descriptor IDs.
+	///
+	/// The limit is capped to [`OPEN_MAX`]. Already open file descriptors are not affected.
+	pub fn set_limit(&mut self, limit: u64) {
+		self.limit = limit.try_into().unwrap_or(OPEN_MAX).min(OPEN_MAX);
+	}
+
 	/// Returns the available file descriptor with the lowest ID.
 	///
 	/// If no ID is available, the function returns an error.
@@ -131,8 +153,8 @@ impl FileDescriptorTable {
 	fn get_available_fd(&self, min: Option<u32>) -> EResult<u32> {
 		let min = min.unwrap_or(0) as usize;
 		// Find a hole in the table
-		let fd = if min < self.0.len() {
-			self.0[min..]
+		let fd = if min < self.fds.len() {
+			self.fds[min..]
 				.iter()
 				.enumerate()
 				.find(|(_, fd)| fd.is_none())
@@ -144,8 +166,8 @@ impl FileDescriptorTable {
 			Some(fd) => Ok(fd),
 			// No hole found, place the new FD at the end
 			None => {
-				let id = max(self.0.len(), min) as u32;
-				if id < OPEN_MAX {
+				let id = max(self.fds.len(), min) as u32;
+				if id < self.limit {
 					Ok(id)
 				} else {
 					Err(errno!(EMFILE))
@@ -160,10 +182,10 @@ impl FileDescriptorTable {
 	fn extend(&mut self, id: u32) -> AllocResult<()> {
 		let id = id as usize;
 		// The ID fits. Do nothing
-		if id < self.0.len() {
+		if id < self.fds.len() {
 			return Ok(());
 		}
-		self.0.resize(id + 1, None)
+		self.fds.resize(id + 1, None)
 	}
 
 	/// Creates a file descriptor.
@@ -178,7 +200,7 @@ impl FileDescriptorTable {
 		let fd = FileDescriptor::new(flags, file)?;
 		// Insert the FD
 		self.extend(id)?;
-		let fd = self.0[id as usize].insert(fd);
+		let fd = self.fds[id as usize].insert(fd);
 		Ok((id, fd))
 	}
 
@@ -200,8 +222,8 @@ impl FileDescriptorTable {
 		let fd1 = FileDescriptor::new(0, file1)?;
 		// Insert the FDs
 		self.extend(id1)?; // `id1` is always larger than `id0`
-		self.0[id0 as usize] = Some(fd0);
-		self.0[id1 as usize] = Some(fd1);
+		self.fds[id0 as usize] = Some(fd0);
+		self.fds[id1 as usize] = Some(fd1);
 		Ok((id0, id1))
 	}
 
@@ -210,7 +232,7 @@ impl FileDescriptorTable {
 	/// If the file descriptor does not exist, the function returns [`errno::EBADF`].
 	pub fn get_fd(&self, id: c_int) -> EResult<&FileDescriptor> {
 		let id: usize = id.try_into().map_err(|_| errno!(EBADF))?;
-		self.0
+		self.fds
 			.get(id)
 			.and_then(Option::as_ref)
 			.ok_or_else(|| errno!(EBADF))
@@ -221,7 +243,7 @@ impl FileDescriptorTable {
 	/// If the file descriptor does not exist, the function returns [`errno::EBADF`].
 	pub fn get_fd_mut(&mut self, id: c_int) -> EResult<&mut FileDescriptor> {
 		let id: usize = id.try_into().map_err(|_| errno!(EBADF))?;
-		self.0
+		self.fds
 			.get_mut(id)
 			.and_then(Option::as_mut)
 			.ok_or_else(|| errno!(EBADF))
@@ -245,7 +267,7 @@ impl FileDescriptorTable {
 			NewFDConstraint::None => self.get_available_fd(None)?,
 			NewFDConstraint::Fixed(id) => {
 				let id: u32 = id.try_into().map_err(|_| errno!(EBADF))?;
-				if id >= OPEN_MAX {
+				if id >= self.limit {
 					return Err(errno!(EMFILE));
 				}
 				id
@@ -261,7 +283,7 @@ impl FileDescriptorTable {
 		// Make sure the table is large enough
 		self.extend(new_id)?;
 		// If there was a file descriptor in the slot, close it
-		let slot = &mut self.0[new_id as usize];
+		let slot = &mut self.fds[new_id as usize];
 		if let Some(prev) = slot.take() {
 			let _ = prev.close();
 		}
@@ -276,7 +298,7 @@ impl FileDescriptorTable {
 	/// when executing a program.
 	pub fn duplicate(&self, cloexec: bool) -> EResult<Self> {
 		let fds = self
-			.0
+			.fds
 			.iter()
 			.cloned()
 			.map(|fd| {
@@ -287,7 +309,10 @@ impl FileDescriptorTable {
 			})
 			.collect::<CollectResult<Vec<_>>>()
 			.0?;
-		Ok(Self(fds))
+		Ok(Self {
+			fds,
+			limit: self.limit,
+		})
 	}
 
 	/// Closes the file descriptor with the ID `id`.
@@ -295,20 +320,20 @@ impl FileDescriptorTable {
 	/// If the file descriptor does not exist, the function returns [`errno::EBADF`].
 	pub fn close_fd(&mut self, id: c_int) -> EResult<()> {
 		let id: usize = id.try_into().map_err(|_| errno!(EBADF))?;
-		let fd = self.0.get_mut(id).ok_or_else(|| errno!(EBADF))?;
+		let fd = self.fds.get_mut(id).ok_or_else(|| errno!(EBADF))?;
 		// Remove FD from table
 		let Some(fd) = fd.take() else {
 			return Err(errno!(EBADF));
 		};
 		// Shrink the table if necessary
 		let new_len = self
-			.0
+			.fds
 			.iter()
 			.enumerate()
 			.rfind(|(_, fd)| fd.is_some())
 			.map(|(i, _)| i + 1)
 			.unwrap_or(0);
-		self.0.truncate(new_len);
+		self.fds.truncate(new_len);
 		// Close FD
 		fd.close()
 	}
@@ -316,7 +341,7 @@ impl FileDescriptorTable {
 
 impl Drop for FileDescriptorTable {
 	fn drop(&mut self) {
-		let fds = mem::take(&mut self.0);
+		let fds = mem::take(&mut self.fds);
 		for fd in fds.into_iter().flatten() {
 			let _ = fd.close();
 		}
@@ -400,4 +425,19 @@ mod test {
 		assert!(id3 >= 8);
 		assert_ne!(id3, id2);
 	}
+
+	#[test_case]
+	fn fd_limit() {
+		let mut fds = FileDescriptorTable::default();
+		fds.set_limit(2);
+		fds.create_fd(0, dummy_file()).unwrap();
+		fds.create_fd(0, dummy_file()).unwrap();
+		assert!(fds.create_fd(0, dummy_file()).is_err());
+		assert!(fds
+			.duplicate_fd(0, NewFDConstraint::Fixed(2), false)
+			.is_err());
+		fds.close_fd(1).unwrap();
+		let (id, _) = fds.create_fd(0, dummy_file()).unwrap();
+		assert_eq!(id, 1);
+	}
 }
@@ -22,6 +22,7 @@
 pub mod ext2;
 pub mod initramfs;
 pub mod kernfs;
+pub mod mqueue;
 pub mod proc;
 pub mod tmp;
 
@@ -33,7 +34,7 @@ use crate::{device::DeviceIO, time::unit::Timestamp};
 use core::{any::Any, ffi::c_int, fmt::Debug};
 use utils::{
 	boxed::Box,
-	collections::{hashmap::HashMap, path::PathBuf, string::String},
+	collections::{hashmap::HashMap, path::PathBuf, string::String, vec::Vec},
 	errno,
 	errno::{EResult, ENOTDIR},
 	lock::Mutex,
@@ -98,7 +99,7 @@ pub struct StatSet {
 }
 
 /// Filesystem node operations.
-pub trait NodeOps: Debug {
+pub trait NodeOps: Any + Debug {
 	/// Returns the file's status.
 	///
 	/// `loc` is the location of the file.
@@ -167,6 +168,17 @@ pub trait NodeOps: Debug {
 		Err(errno!(EINVAL))
 	}
 
+	/// Returns the value of the extended attribute `name` of the file.
+	///
+	/// If the attribute does not exist, the function returns `None`.
+	///
+	/// The default implementation of this function returns `None`, for filesystems that do not
+	/// support extended attributes.
+	fn get_xattr(&self, loc: &FileLocation, name: &[u8]) -> EResult<Option<Vec<u8>>> {
+		let _ = (loc, name);
+		Ok(None)
+	}
+
 	/// Returns the directory entry with the given `name`, along with its offset and the handle of
 	/// the file.
 	///
@@ -386,6 +398,7 @@ pub fn register_defaults() -> EResult<()> {
 	register(ext2::Ext2FsType {})?;
 	register(tmp::TmpFsType {})?;
 	register(proc::ProcFsType {})?;
+	register(mqueue::MqueueFsType {})?;
 	// TODO sysfs
 	Ok(())
 }
@@ -0,0 +1,188 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mqueue` filesystem exposes POSIX message queues.
+//!
+//! Each file in the root directory is a queue. Reading it returns the queue's status, and removing
+//! it is equivalent to `mq_unlink`.
+
+use super::{kernfs, Filesystem, FilesystemType, NodeOps, StatSet, Statfs};
+use crate::{
+	device::DeviceIO,
+	file::{DirEntry, FileLocation, FileType, INode, Stat},
+	ipc::{mqueue, mqueue::MessageQueue, IpcNamespace},
+	process::{namespace::Namespaces, Process},
+};
+use utils::{
+	boxed::Box,
+	collections::path::PathBuf,
+	errno,
+	errno::EResult,
+	ptr::{arc::Arc, cow::Cow},
+};
+
+/// The magic number of the filesystem.
+const MQUEUE_MAGIC: u32 = 0x19800202;
+
+/// A message queue node.
+#[derive(Debug)]
+struct QueueNode(Arc<MessageQueue>);
+
+impl NodeOps for QueueNode {
+	fn get_stat(&self, _loc: &FileLocation) -> EResult<Stat> {
+		Ok(self.0.stat())
+	}
+
+	fn set_stat(&self, _loc: &FileLocation, set: StatSet) -> EResult<()> {
+		self.0.set_stat(&set);
+		Ok(())
+	}
+
+	fn read_content(&self, _loc: &FileLocation, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		self.0.read_status(off, buf)
+	}
+
+	fn remove_node(&self, _loc: &FileLocation) -> EResult<()> {
+		// The queue is freed when the last reference to it is dropped
+		Ok(())
+	}
+}
+
+/// The root directory of the filesystem.
+#[derive(Debug)]
+struct RootDir(Arc<IpcNamespace>);
+
+impl NodeOps for RootDir {
+	fn get_stat(&self, _loc: &FileLocation) -> EResult<Stat> {
+		Ok(Stat {
+			mode: FileType::Directory.to_mode() | 0o1777,
+			..Default::default()
+		})
+	}
+
+	fn entry_by_name<'n>(
+		&self,
+		_loc: &FileLocation,
+		name: &'n [u8],
+	) -> EResult<Option<(DirEntry<'n>, Box<dyn NodeOps>)>> {
+		let Some(queue) = mqueue::get(&self.0, name) else {
+			return Ok(None);
+		};
+		Ok(Some((
+			DirEntry {
+				inode: 0,
+				entry_type: FileType::Regular,
+				name: Cow::Borrowed(name),
+			},
+			Box::new(QueueNode(queue))? as _,
+		)))
+	}
+
+	fn next_entry(
+		&self,
+		_loc: &FileLocation,
+		off: u64,
+	) -> EResult<Option<(DirEntry<'static>, u64)>> {
+		let off: usize = off.try_into().map_err(|_| errno!(EINVAL))?;
+		let Some(name) = mqueue::name_at(&self.0, off)? else {
+			return Ok(None);
+		};
+		Ok(Some((
+			DirEntry {
+				inode: 0,
+				entry_type: FileType::Regular,
+				name: Cow::Owned(name),
+			},
+			off as u64 + 1,
+		)))
+	}
+
+	fn unlink(&self, _parent: &FileLocation, name: &[u8]) -> EResult<()> {
+		mqueue::remove(&self.0, name)
+	}
+}
+
+/// An instance of the `mqueue` filesystem.
+///
+/// An instance shows the queues of the IPC namespace of the process that mounted it.
+#[derive(Debug)]
+pub struct MqueueFs(Arc<IpcNamespace>);
+
+impl Filesystem for MqueueFs {
+	fn get_name(&self) -> &[u8] {
+		b"mqueue"
+	}
+
+	fn use_cache(&self) -> bool {
+		false
+	}
+
+	fn get_root_inode(&self) -> INode {
+		kernfs::ROOT_INODE
+	}
+
+	fn get_stat(&self) -> EResult<Statfs> {
+		Ok(Statfs {
+			f_type: MQUEUE_MAGIC,
+			f_bsize: 0,
+			f_blocks: 0,
+			f_bfree: 0,
+			f_bavail: 0,
+			f_files: 0,
+			f_ffree: 0,
+			f_fsid: Default::default(),
+			f_namelen: 255,
+			f_frsize: 0,
+			f_flags: 0,
+		})
+	}
+
+	fn node_from_inode(&self, inode: INode) -> EResult<Box<dyn NodeOps>> {
+		if inode == kernfs::ROOT_INODE {
+			Ok(Box::new(RootDir(self.0.clone()))? as _)
+		} else {
+			Err(errno!(ENOENT))
+		}
+	}
+}
+
+/// The `mqueue` filesystem type.
+pub struct MqueueFsType;
+
+impl FilesystemType for MqueueFsType {
+	fn get_name(&self) -> &'static [u8] {
+		b"mqueue"
+	}
+
+	fn detect(&self, _io: &dyn DeviceIO) -> EResult<bool> {
+		Ok(false)
+	}
+
+	fn load_filesystem(
+		&self,
+		_io: Option<Arc<dyn DeviceIO>>,
+		_mountpath: PathBuf,
+		_readonly: bool,
+	) -> EResult<Arc<dyn Filesystem>> {
+		let ns = match Process::current_opt() {
+			Some(proc) => proc.lock().namespaces.ipc.clone(),
+			None => Namespaces::initial()?.ipc,
+		};
+		Ok(Arc::new(MqueueFs(ns))?)
+	}
+}
@@ -20,7 +20,7 @@
 //! processes.
 
 mod mem_info;
-mod proc_dir;
+pub mod proc_dir;
 mod self_link;
 mod sys_dir;
 mod uptime;
@@ -45,10 +45,11 @@ use crate::{
 };
 use mem_info::MemInfo;
 use proc_dir::{
-	cmdline::Cmdline, cwd::Cwd, exe::Exe, mounts::Mounts, stat::StatNode, status::Status,
+	cmdline::Cmdline, cwd::Cwd, exe::Exe, mounts::Mounts, oom_score::OomScore,
+	oom_score_adj::OomScoreAdj, stat::StatNode, status::Status,
 };
 use self_link::SelfNode;
-use sys_dir::OsRelease;
+use sys_dir::{BinfmtRegister, BinfmtStatus, CorePattern, OsRelease, RandomizeVaSpace};
 use uptime::Uptime;
 use utils::{
 	boxed::Box,
@@ -105,20 +106,66 @@ impl RootDir {
 				entry_type: FileType::Directory,
 				init: |_| {
 					box_wrap(StaticDir {
-						entries: &[(StaticEntryBuilder {
-							name: b"kernel",
-							entry_type: FileType::Directory,
-							init: |_| {
-								box_wrap(StaticDir {
-									entries: &[StaticEntryBuilder {
-										name: b"osrelease",
-										entry_type: FileType::Regular,
-										init: entry_init_default::<OsRelease>,
-									}],
-									data: (),
-								})
+						entries: &[
+							StaticEntryBuilder {
+								name: b"fs",
+								entry_type: FileType::Directory,
+								init: |_| {
+									box_wrap(StaticDir {
+										entries: &[StaticEntryBuilder {
+											name: b"binfmt_misc",
+											entry_type: FileType::Directory,
+											init: |_| {
+												box_wrap(StaticDir {
+													entries: &[
+														StaticEntryBuilder {
+															name: b"register",
+															entry_type: FileType::Regular,
+															init: entry_init_default::<
+																BinfmtRegister,
+															>,
+														},
+														StaticEntryBuilder {
+															name: b"status",
+															entry_type: FileType::Regular,
+															init: entry_init_default::<BinfmtStatus>,
+														},
+													],
+													data: (),
+												})
+											},
+										}],
+										data: (),
+									})
+								},
 							},
-						})],
+							StaticEntryBuilder {
+								name: b"kernel",
+								entry_type: FileType::Directory,
+								init: |_| {
+									box_wrap(StaticDir {
+										entries: &[
+											StaticEntryBuilder {
+												name: b"core_pattern",
+												entry_type: FileType::Regular,
+												init: entry_init_default::<CorePattern>,
+											},
+											StaticEntryBuilder {
+												name: b"osrelease",
+												entry_type: FileType::Regular,
+												init: entry_init_default::<OsRelease>,
+											},
+											StaticEntryBuilder {
+												name: b"randomize_va_space",
+												entry_type: FileType::Regular,
+												init: entry_init_default::<RandomizeVaSpace>,
+											},
+										],
+										data: (),
+									})
+								},
+							},
+						],
 						data: (),
 					})
 				},
@@ -193,6 +240,26 @@ impl NodeOps for RootDir {
 						entry_type: FileType::Regular,
 						init: entry_init_from::<Mounts, Pid>,
 					},
+					StaticEntryBuilder {
+						name: b"ns",
+						entry_type: FileType::Directory,
+						init: |pid| {
+							box_wrap(StaticDir {
+								entries: proc_dir::ns::ENTRIES,
+								data: pid,
+							})
+						},
+					},
+					StaticEntryBuilder {
+						name: b"oom_score",
+						entry_type: FileType::Regular,
+						init: entry_init_from::<OomScore, Pid>,
+					},
+					StaticEntryBuilder {
+						name: b"oom_score_adj",
+						entry_type: FileType::Regular,
+						init: entry_init_from::<OomScoreAdj, Pid>,
+					},
 					StaticEntryBuilder {
 						name: b"stat",
 						entry_type: FileType::Regular,
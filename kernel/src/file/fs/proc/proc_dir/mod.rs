@@ -23,5 +23,8 @@ pub mod cwd;
 pub mod environ;
 pub mod exe;
 pub mod mounts;
+pub mod ns;
+pub mod oom_score;
+pub mod oom_score_adj;
 pub mod stat;
 pub mod status;
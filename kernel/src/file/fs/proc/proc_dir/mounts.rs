@@ -21,12 +21,10 @@
 use crate::{
 	file::{
 		fs::{proc::get_proc_owner, NodeOps},
-		vfs,
-		vfs::mountpoint,
-		FileLocation, FileType, Stat,
+		vfs, FileLocation, FileType, Stat,
 	},
 	format_content,
-	process::pid::Pid,
+	process::{pid::Pid, Process},
 };
 use core::{fmt, fmt::Formatter};
 use utils::{errno::EResult, DisplayableStr};
@@ -59,11 +57,16 @@ impl NodeOps for Mounts {
 
 impl fmt::Display for Mounts {
 	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-		let mps = mountpoint::MOUNT_POINTS.lock();
-		for (_, mp) in mps.iter() {
-			let Ok(target) = vfs::Entry::get_path(&mp.root_entry) else {
+		let Some(proc_mutex) = Process::get_by_pid(self.0) else {
+			return Ok(());
+		};
+		let ns = proc_mutex.lock().namespaces.mnt.clone();
+		let mounts = ns.mounts.lock();
+		for (_, mount) in mounts.iter() {
+			let Ok(target) = vfs::Entry::get_path(&mount.root_entry) else {
 				continue;
 			};
+			let mp = &mount.mp;
 			let fs_type = mp.fs.get_name();
 			let flags = "TODO"; // TODO
 			writeln!(
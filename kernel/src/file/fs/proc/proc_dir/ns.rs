@@ -0,0 +1,110 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Implementation of the `ns` directory, which contains a node for each namespace of the process.
+//!
+//! Reading a node returns the kind and ID of the namespace, and an open node can be passed to
+//! `setns` to enter the namespace.
+
+use crate::{
+	file::{
+		fs::{
+			kernfs::{box_wrap, StaticEntryBuilder},
+			proc::get_proc_owner,
+			NodeOps,
+		},
+		FileLocation, FileType, Stat,
+	},
+	format_content,
+	process::{namespace::NsType, pid::Pid, Process},
+};
+use utils::{errno, errno::EResult};
+
+/// The entries of the `ns` directory, sorted by name.
+pub const ENTRIES: &[StaticEntryBuilder<Pid>] = &[
+	StaticEntryBuilder {
+		name: b"ipc",
+		entry_type: FileType::Regular,
+		init: |pid| box_wrap(NsNode::new(pid, NsType::Ipc)),
+	},
+	StaticEntryBuilder {
+		name: b"mnt",
+		entry_type: FileType::Regular,
+		init: |pid| box_wrap(NsNode::new(pid, NsType::Mnt)),
+	},
+	StaticEntryBuilder {
+		name: b"net",
+		entry_type: FileType::Regular,
+		init: |pid| box_wrap(NsNode::new(pid, NsType::Net)),
+	},
+	StaticEntryBuilder {
+		name: b"pid",
+		entry_type: FileType::Regular,
+		init: |pid| box_wrap(NsNode::new(pid, NsType::Pid)),
+	},
+	StaticEntryBuilder {
+		name: b"pid_for_children",
+		entry_type: FileType::Regular,
+		init: |pid| box_wrap(NsNode::new(pid, NsType::PidForChildren)),
+	},
+	StaticEntryBuilder {
+		name: b"uts",
+		entry_type: FileType::Regular,
+		init: |pid| box_wrap(NsNode::new(pid, NsType::Uts)),
+	},
+];
+
+/// A namespace node.
+#[derive(Debug)]
+pub struct NsNode {
+	/// The PID of the process.
+	pub pid: Pid,
+	/// The kind of namespace.
+	pub ty: NsType,
+}
+
+impl NsNode {
+	/// Creates a node for the namespace of kind `ty` of the process with PID `pid`.
+	fn new(pid: Pid, ty: NsType) -> Self {
+		Self {
+			pid,
+			ty,
+		}
+	}
+}
+
+impl NodeOps for NsNode {
+	fn get_stat(&self, _loc: &FileLocation) -> EResult<Stat> {
+		let (uid, gid) = get_proc_owner(self.pid);
+		Ok(Stat {
+			mode: FileType::Regular.to_mode() | 0o444,
+			uid,
+			gid,
+			..Default::default()
+		})
+	}
+
+	fn read_content(&self, _loc: &FileLocation, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let id = {
+			let proc_mutex = Process::get_by_pid(self.pid).ok_or_else(|| errno!(ENOENT))?;
+			let proc = proc_mutex.lock();
+			self.ty.get_id(&proc.namespaces, proc.pid_ns())
+		};
+		format_content!(off, buf, "{}:[{id}]\n", self.ty.get_name())
+	}
+}
@@ -0,0 +1,58 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `oom_score` node allows to retrieve the score used by the OOM killer to select the process
+//! to kill.
+
+use crate::{
+	file::{
+		fs::{proc::get_proc_owner, NodeOps},
+		FileLocation, FileType, Stat,
+	},
+	format_content,
+	process::{pid::Pid, Process},
+};
+use utils::{errno, errno::EResult};
+
+/// The `oom_score` node of the proc.
+#[derive(Clone, Debug)]
+pub struct OomScore(Pid);
+
+impl From<Pid> for OomScore {
+	fn from(pid: Pid) -> Self {
+		Self(pid)
+	}
+}
+
+impl NodeOps for OomScore {
+	fn get_stat(&self, _loc: &FileLocation) -> EResult<Stat> {
+		let (uid, gid) = get_proc_owner(self.0);
+		Ok(Stat {
+			mode: FileType::Regular.to_mode() | 0o444,
+			uid,
+			gid,
+			..Default::default()
+		})
+	}
+
+	fn read_content(&self, _loc: &FileLocation, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let proc_mutex = Process::get_by_pid(self.0).ok_or_else(|| errno!(ENOENT))?;
+		let score = proc_mutex.lock().get_oom_score();
+		format_content!(off, buf, "{score}\n")
+	}
+}
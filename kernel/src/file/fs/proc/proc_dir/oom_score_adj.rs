@@ -0,0 +1,91 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `oom_score_adj` node allows to adjust the score used by the OOM killer to select the
+//! process to kill.
+//!
+//! The value is between `-1000` and `1000`. A value of `-1000` prevents the process from being
+//! killed.
+
+use crate::{
+	file::{
+		fs::{proc::get_proc_owner, NodeOps},
+		perm::CAP_SYS_RESOURCE,
+		FileLocation, FileType, Stat,
+	},
+	format_content,
+	process::{
+		oom::{OOM_SCORE_ADJ_MAX, OOM_SCORE_ADJ_MIN},
+		pid::Pid,
+		Process,
+	},
+};
+use utils::{errno, errno::EResult};
+
+/// The `oom_score_adj` node of the proc.
+#[derive(Clone, Debug)]
+pub struct OomScoreAdj(Pid);
+
+impl From<Pid> for OomScoreAdj {
+	fn from(pid: Pid) -> Self {
+		Self(pid)
+	}
+}
+
+impl NodeOps for OomScoreAdj {
+	fn get_stat(&self, _loc: &FileLocation) -> EResult<Stat> {
+		let (uid, gid) = get_proc_owner(self.0);
+		Ok(Stat {
+			mode: FileType::Regular.to_mode() | 0o644,
+			uid,
+			gid,
+			..Default::default()
+		})
+	}
+
+	fn read_content(&self, _loc: &FileLocation, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let proc_mutex = Process::get_by_pid(self.0).ok_or_else(|| errno!(ENOENT))?;
+		let adj = proc_mutex.lock().oom_score_adj;
+		format_content!(off, buf, "{adj}\n")
+	}
+
+	fn write_content(&self, _loc: &FileLocation, off: u64, buf: &[u8]) -> EResult<usize> {
+		if off != 0 {
+			return Err(errno!(EINVAL));
+		}
+		let adj: i16 = core::str::from_utf8(buf)
+			.ok()
+			.and_then(|s| s.trim().parse().ok())
+			.filter(|adj| (OOM_SCORE_ADJ_MIN..=OOM_SCORE_ADJ_MAX).contains(adj))
+			.ok_or_else(|| errno!(EINVAL))?;
+		let ap = Process::current().lock().access_profile;
+		let proc_mutex = Process::get_by_pid(self.0).ok_or_else(|| errno!(ESRCH))?;
+		let mut proc = proc_mutex.lock();
+		// Making the process less likely to be killed is a privileged operation
+		if adj < proc.oom_score_adj && !ap.has_cap(CAP_SYS_RESOURCE) {
+			return Err(errno!(EACCES));
+		}
+		proc.oom_score_adj = adj;
+		Ok(buf.len())
+	}
+
+	fn truncate_content(&self, _loc: &FileLocation, _size: u64) -> EResult<()> {
+		// The content is replaced on write
+		Ok(())
+	}
+}
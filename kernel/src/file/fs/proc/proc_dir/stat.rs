@@ -25,7 +25,8 @@ use crate::{
 		FileLocation, FileType, Stat,
 	},
 	format_content,
-	process::{pid::Pid, Process},
+	process::{pid::Pid, rusage, Process},
+	time::unit::TimeUnit,
 };
 use core::{fmt, fmt::Formatter};
 use utils::{collections::string::String, errno, errno::EResult, DisplayableStr};
@@ -40,24 +41,33 @@ impl<'p> fmt::Display for StatDisp<'p> {
 		let vmem_usage = 0;
 		let esp = self.0.regs.esp;
 		let eip = self.0.regs.eip;
+		let rusage = self.0.get_rusage();
+		let children_rusage = self.0.get_children_rusage();
 		// TODO Fill every fields with process's data
 		write!(
 			f,
 			"{pid} ({name}) {state_char} {ppid} {pgid} {sid} TODO TODO 0 \
-0 0 0 0 {user_jiffies} {kernel_jiffies} TODO TODO {priority} {nice} {num_threads} 0 {vmem_usage} \
-TODO TODO TODO TODO {esp} {eip} TODO TODO TODO TODO 0 0 0 TODO TODO TODO TODO TODO TODO TODO TODO \
-TODO TODO TODO TODO TODO TODO TODO TODO TODO",
+{minflt} {cminflt} {majflt} {cmajflt} {utime} {stime} {cutime} {cstime} {priority} {nice} \
+{num_threads} 0 {start_time} {vmem_usage} TODO TODO TODO TODO TODO {esp} {eip} TODO TODO TODO TODO \
+0 0 0 TODO TODO TODO TODO TODO TODO TODO TODO TODO TODO TODO TODO TODO TODO TODO",
 			pid = self.0.get_pid(),
 			name = DisplayableStr(name),
 			state_char = self.0.get_state().as_char(),
 			ppid = self.0.get_parent_pid(),
 			pgid = self.0.pgid,
-			sid = 0,            // TODO
-			user_jiffies = 0,   // TODO
-			kernel_jiffies = 0, // TODO
+			sid = self.0.sid,
+			minflt = rusage.ru_minflt,
+			cminflt = children_rusage.ru_minflt,
+			majflt = rusage.ru_majflt,
+			cmajflt = children_rusage.ru_majflt,
+			utime = rusage::to_clock_ticks(rusage.ru_utime.to_nano()),
+			stime = rusage::to_clock_ticks(rusage.ru_stime.to_nano()),
+			cutime = rusage::to_clock_ticks(children_rusage.ru_utime.to_nano()),
+			cstime = rusage::to_clock_ticks(children_rusage.ru_stime.to_nano()),
 			priority = self.0.priority,
 			nice = self.0.nice,
 			num_threads = 1, // TODO
+			start_time = rusage::to_clock_ticks(self.0.get_start_time()),
 		)
 	}
 }
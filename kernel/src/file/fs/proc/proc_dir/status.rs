@@ -22,6 +22,7 @@
 use crate::{
 	file::{
 		fs::{proc::get_proc_owner, NodeOps},
+		perm::Gid,
 		FileLocation, FileType, Stat,
 	},
 	format_content,
@@ -30,6 +31,18 @@ use crate::{
 use core::{fmt, fmt::Formatter};
 use utils::{collections::string::String, errno, errno::EResult, DisplayableStr};
 
+/// Displays a list of group IDs, each followed by a space.
+struct GroupsDisp<'g>(&'g [Gid]);
+
+impl<'g> fmt::Display for GroupsDisp<'g> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		for gid in self.0 {
+			write!(f, "{gid} ")?;
+		}
+		Ok(())
+	}
+}
+
 struct StatusDisp<'p>(&'p Process);
 
 impl<'p> fmt::Display for StatusDisp<'p> {
@@ -50,11 +63,11 @@ TracerPid: 0
 Uid: {uid} {euid} {suid} {ruid}
 Gid: {gid} {egid} {sgid} {rgid}
 FDSize: TODO
-Groups: TODO
+Groups: {groups}
 NStgid: TODO
 NSpid: TODO
-NSpgid: TODO
-NSsid: TODO
+NSpgid: {pgid}
+NSsid: {sid}
 VmPeak: TODO kB
 VmSize: TODO kB
 VmLck: TODO kB
@@ -85,23 +98,25 @@ CapPrm: 0000000000000000
 CapEff: 0000000000000000
 CapBnd: 000001ffffffffff
 CapAmb: 0000000000000000
-NoNewPrivs: 0
-Seccomp: 0
-Seccomp_filters: 0
+NoNewPrivs: {no_new_privs}
+Seccomp: {seccomp}
+Seccomp_filters: {seccomp_filters}
 Speculation_Store_Bypass: thread vulnerable
 SpeculationIndirectBranch: conditional enabled
 Cpus_allowed: ff
 Cpus_allowed_list: 0-7
 Mems_allowed: 00000001
 Mems_allowed_list: 0
-voluntary_ctxt_switches: 0
-nonvoluntary_ctxt_switches: 0",
+voluntary_ctxt_switches: {nvcsw}
+nonvoluntary_ctxt_switches: {nivcsw}",
 			name = DisplayableStr(name),
 			umask = self.0.umask,
 			state_char = state.as_char(),
 			state_name = state.as_str(),
 			pid = self.0.get_pid(),
 			ppid = self.0.get_parent_pid(),
+			pgid = self.0.pgid,
+			sid = self.0.sid,
 			uid = self.0.access_profile.uid,
 			euid = self.0.access_profile.euid,
 			suid = self.0.access_profile.suid,
@@ -110,6 +125,12 @@ nonvoluntary_ctxt_switches: 0",
 			egid = self.0.access_profile.egid,
 			sgid = self.0.access_profile.sgid,
 			rgid = self.0.access_profile.gid,
+			groups = GroupsDisp(self.0.access_profile.get_groups()),
+			no_new_privs = self.0.access_profile.no_new_privs as u8,
+			seccomp = self.0.seccomp.get_mode(),
+			seccomp_filters = self.0.seccomp.filters_count(),
+			nvcsw = self.0.get_rusage().ru_nvcsw,
+			nivcsw = self.0.get_rusage().ru_nivcsw,
 		)
 	}
 }
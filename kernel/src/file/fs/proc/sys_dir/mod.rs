@@ -21,8 +21,107 @@
 use crate::{
 	file::{fs::NodeOps, FileLocation, FileType, Stat},
 	format_content,
+	process::{aslr, coredump, exec::misc},
 };
-use utils::errno::EResult;
+use utils::{errno, errno::EResult, DisplayableStr};
+
+/// The `binfmt_misc/register` file, on which rules for miscellaneous binary formats are written.
+#[derive(Debug, Default)]
+pub struct BinfmtRegister;
+
+impl NodeOps for BinfmtRegister {
+	fn get_stat(&self, _loc: &FileLocation) -> EResult<Stat> {
+		Ok(Stat {
+			mode: FileType::Regular.to_mode() | 0o200,
+			..Default::default()
+		})
+	}
+
+	fn write_content(&self, _loc: &FileLocation, off: u64, buf: &[u8]) -> EResult<usize> {
+		if off != 0 {
+			return Err(errno!(EINVAL));
+		}
+		misc::register(misc::Rule::parse(buf)?)?;
+		Ok(buf.len())
+	}
+
+	fn truncate_content(&self, _loc: &FileLocation, _size: u64) -> EResult<()> {
+		Ok(())
+	}
+}
+
+/// The `binfmt_misc/status` file, which tells whether miscellaneous binary formats are enabled.
+///
+/// Writing `1` enables them, `0` disables them and `-1` removes every rule.
+#[derive(Debug, Default)]
+pub struct BinfmtStatus;
+
+impl NodeOps for BinfmtStatus {
+	fn get_stat(&self, _loc: &FileLocation) -> EResult<Stat> {
+		Ok(Stat {
+			mode: FileType::Regular.to_mode() | 0o644,
+			..Default::default()
+		})
+	}
+
+	fn read_content(&self, _loc: &FileLocation, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let status = if misc::is_enabled() {
+			"enabled"
+		} else {
+			"disabled"
+		};
+		format_content!(off, buf, "{status}\n")
+	}
+
+	fn write_content(&self, _loc: &FileLocation, off: u64, buf: &[u8]) -> EResult<usize> {
+		if off != 0 {
+			return Err(errno!(EINVAL));
+		}
+		match buf.strip_suffix(b"\n").unwrap_or(buf) {
+			b"0" => misc::set_enabled(false),
+			b"1" => misc::set_enabled(true),
+			b"-1" => misc::clear(),
+			_ => return Err(errno!(EINVAL)),
+		}
+		Ok(buf.len())
+	}
+
+	fn truncate_content(&self, _loc: &FileLocation, _size: u64) -> EResult<()> {
+		Ok(())
+	}
+}
+
+/// The `core_pattern` file.
+#[derive(Debug, Default)]
+pub struct CorePattern;
+
+impl NodeOps for CorePattern {
+	fn get_stat(&self, _loc: &FileLocation) -> EResult<Stat> {
+		Ok(Stat {
+			mode: FileType::Regular.to_mode() | 0o644,
+			..Default::default()
+		})
+	}
+
+	fn read_content(&self, _loc: &FileLocation, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let pattern = coredump::get_pattern()?;
+		format_content!(off, buf, "{}\n", DisplayableStr(&pattern))
+	}
+
+	fn write_content(&self, _loc: &FileLocation, off: u64, buf: &[u8]) -> EResult<usize> {
+		if off != 0 {
+			return Err(errno!(EINVAL));
+		}
+		let pattern = buf.strip_suffix(b"\n").unwrap_or(buf);
+		coredump::set_pattern(pattern)?;
+		Ok(buf.len())
+	}
+
+	fn truncate_content(&self, _loc: &FileLocation, _size: u64) -> EResult<()> {
+		// The content is replaced on write
+		Ok(())
+	}
+}
 
 /// The `osrelease` file.
 #[derive(Debug, Default)]
@@ -40,3 +139,36 @@ impl NodeOps for OsRelease {
 		format_content!(off, buf, "{}\n", crate::VERSION)
 	}
 }
+
+/// The `randomize_va_space` file, which sets the level of address space layout randomization.
+#[derive(Debug, Default)]
+pub struct RandomizeVaSpace;
+
+impl NodeOps for RandomizeVaSpace {
+	fn get_stat(&self, _loc: &FileLocation) -> EResult<Stat> {
+		Ok(Stat {
+			mode: FileType::Regular.to_mode() | 0o644,
+			..Default::default()
+		})
+	}
+
+	fn read_content(&self, _loc: &FileLocation, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		format_content!(off, buf, "{}\n", aslr::get_level())
+	}
+
+	fn write_content(&self, _loc: &FileLocation, off: u64, buf: &[u8]) -> EResult<usize> {
+		if off != 0 {
+			return Err(errno!(EINVAL));
+		}
+		let level = core::str::from_utf8(buf)
+			.ok()
+			.and_then(|s| s.trim_end().parse().ok())
+			.ok_or_else(|| errno!(EINVAL))?;
+		aslr::set_level(level)?;
+		Ok(buf.len())
+	}
+
+	fn truncate_content(&self, _loc: &FileLocation, _size: u64) -> EResult<()> {
+		Ok(())
+	}
+}
@@ -24,11 +24,16 @@
 //! The root filesystem is passed to the kernel as an argument on boot.
 //! Other filesystems are mounted into subdirectories.
 
+pub mod epoll;
+pub mod eventfd;
 pub mod fd;
 pub mod fs;
 pub mod perm;
+pub mod pidfd;
 pub mod pipe;
+pub mod signalfd;
 pub mod socket;
+pub mod timerfd;
 pub mod util;
 pub mod vfs;
 pub mod wait_queue;
@@ -37,7 +42,8 @@ use crate::{
 	device::{DeviceID, DeviceType},
 	file::{
 		fs::Filesystem,
-		perm::{Gid, Uid},
+		perm::{CapSet, Gid, Uid},
+		wait_queue::WaitQueue,
 	},
 	syscall::ioctl,
 	time::{
@@ -362,6 +368,19 @@ pub trait FileOps: Any + Debug {
 	/// On success, the function returns the mask events that occurred.
 	fn poll(&self, file: &File, mask: u32) -> EResult<u32>;
 
+	/// Calls `f` on each wait queue on which the file signals events.
+	///
+	/// This allows event notification mechanisms, such as `epoll`, to watch the file. By default,
+	/// the file has no wait queue.
+	fn wait_queues(
+		&self,
+		file: &File,
+		f: &mut dyn FnMut(&WaitQueue) -> EResult<()>,
+	) -> EResult<()> {
+		let _ = (file, f);
+		Ok(())
+	}
+
 	/// Performs an ioctl operation on the device file.
 	///
 	/// Arguments:
@@ -526,33 +545,39 @@ impl File {
 }
 
 impl AccessProfile {
-	fn check_read_access_impl(uid: Uid, gid: Gid, stat: &Stat) -> bool {
-		// If root, bypass checks
-		if uid == perm::ROOT_UID || gid == perm::ROOT_GID {
+	/// Returns the user ID and capabilities to use for permission checks.
+	///
+	/// `effective` tells whether to use effective IDs. If not, real IDs are used, along with the
+	/// capabilities the agent would have if its effective user ID was its real user ID.
+	fn check_ids(&self, effective: bool) -> (Uid, CapSet) {
+		if effective {
+			(self.euid, self.cap_effective)
+		} else if self.uid == perm::ROOT_UID {
+			(self.uid, self.cap_permitted)
+		} else {
+			(self.uid, CapSet::default())
+		}
+	}
+
+	/// Tells whether the agent can read a file with the given status.
+	///
+	/// `effective` tells whether to use effective IDs. If not, real IDs are used.
+	pub fn check_read_access(&self, stat: &Stat, effective: bool) -> bool {
+		let (uid, caps) = self.check_ids(effective);
+		// If privileged, bypass checks
+		if caps.has(perm::CAP_DAC_OVERRIDE) || caps.has(perm::CAP_DAC_READ_SEARCH) {
 			return true;
 		}
 		// Check permissions
 		if stat.mode & perm::S_IRUSR != 0 && stat.uid == uid {
 			return true;
 		}
-		if stat.mode & perm::S_IRGRP != 0 && stat.gid == gid {
+		if stat.mode & perm::S_IRGRP != 0 && self.is_in_group(stat.gid, effective) {
 			return true;
 		}
 		stat.mode & perm::S_IROTH != 0
 	}
 
-	/// Tells whether the agent can read a file with the given status.
-	///
-	/// `effective` tells whether to use effective IDs. If not, real IDs are used.
-	pub fn check_read_access(&self, stat: &Stat, effective: bool) -> bool {
-		let (uid, gid) = if effective {
-			(self.euid, self.egid)
-		} else {
-			(self.uid, self.gid)
-		};
-		Self::check_read_access_impl(uid, gid, stat)
-	}
-
 	/// Tells whether the agent can read a file with the given status.
 	///
 	/// This function is the preferred from `check_read_access` for general cases.
@@ -567,33 +592,25 @@ impl AccessProfile {
 		self.can_read_file(stat)
 	}
 
-	fn check_write_access_impl(uid: Uid, gid: Gid, stat: &Stat) -> bool {
-		// If root, bypass checks
-		if uid == perm::ROOT_UID || gid == perm::ROOT_GID {
+	/// Tells whether the agent can write a file with the given status.
+	///
+	/// `effective` tells whether to use effective IDs. If not, real IDs are used.
+	pub fn check_write_access(&self, stat: &Stat, effective: bool) -> bool {
+		let (uid, caps) = self.check_ids(effective);
+		// If privileged, bypass checks
+		if caps.has(perm::CAP_DAC_OVERRIDE) {
 			return true;
 		}
 		// Check permissions
 		if stat.mode & perm::S_IWUSR != 0 && stat.uid == uid {
 			return true;
 		}
-		if stat.mode & perm::S_IWGRP != 0 && stat.gid == gid {
+		if stat.mode & perm::S_IWGRP != 0 && self.is_in_group(stat.gid, effective) {
 			return true;
 		}
 		stat.mode & perm::S_IWOTH != 0
 	}
 
-	/// Tells whether the agent can write a file with the given status.
-	///
-	/// `effective` tells whether to use effective IDs. If not, real IDs are used.
-	pub fn check_write_access(&self, stat: &Stat, effective: bool) -> bool {
-		let (uid, gid) = if effective {
-			(self.euid, self.egid)
-		} else {
-			(self.uid, self.gid)
-		};
-		Self::check_write_access_impl(uid, gid, stat)
-	}
-
 	/// Tells whether the agent can write a file with the given status.
 	pub fn can_write_file(&self, stat: &Stat) -> bool {
 		self.check_write_access(stat, true)
@@ -606,35 +623,30 @@ impl AccessProfile {
 		self.can_write_file(stat) && self.can_execute_file(stat)
 	}
 
-	fn check_execute_access_impl(uid: Uid, gid: Gid, stat: &Stat) -> bool {
-		// If root, bypass checks (unless the file is a regular file)
-		if stat.get_type() != Some(FileType::Regular)
-			&& (uid == perm::ROOT_UID || gid == perm::ROOT_GID)
-		{
+	/// Tells whether the agent can execute a file with the given status.
+	///
+	/// `effective` tells whether to use effective IDs. If not, real IDs are used.
+	pub fn check_execute_access(&self, stat: &Stat, effective: bool) -> bool {
+		let (uid, caps) = self.check_ids(effective);
+		// If privileged, bypass checks. A regular file still requires at least one execute bit
+		let exec_bits = perm::S_IXUSR | perm::S_IXGRP | perm::S_IXOTH;
+		let regular = stat.get_type() == Some(FileType::Regular);
+		if caps.has(perm::CAP_DAC_OVERRIDE) && (!regular || stat.mode & exec_bits != 0) {
+			return true;
+		}
+		if !regular && caps.has(perm::CAP_DAC_READ_SEARCH) {
 			return true;
 		}
 		// Check permissions
 		if stat.mode & perm::S_IXUSR != 0 && stat.uid == uid {
 			return true;
 		}
-		if stat.mode & perm::S_IXGRP != 0 && stat.gid == gid {
+		if stat.mode & perm::S_IXGRP != 0 && self.is_in_group(stat.gid, effective) {
 			return true;
 		}
 		stat.mode & perm::S_IXOTH != 0
 	}
 
-	/// Tells whether the agent can execute a file with the given status.
-	///
-	/// `effective` tells whether to use effective IDs. If not, real IDs are used.
-	pub fn check_execute_access(&self, stat: &Stat, effective: bool) -> bool {
-		let (uid, gid) = if effective {
-			(self.euid, self.egid)
-		} else {
-			(self.uid, self.gid)
-		};
-		Self::check_execute_access_impl(uid, gid, stat)
-	}
-
 	/// Tells whether the agent can execute a file with the given status.
 	pub fn can_execute_file(&self, stat: &Stat) -> bool {
 		self.check_execute_access(stat, true)
@@ -649,7 +661,7 @@ impl AccessProfile {
 
 	/// Tells whether the agent can set permissions for a file with the given status.
 	pub fn can_set_file_permissions(&self, stat: &Stat) -> bool {
-		self.euid == perm::ROOT_UID || self.euid == stat.uid
+		self.has_cap(perm::CAP_FOWNER) || self.euid == stat.uid
 	}
 }
 
@@ -19,9 +19,17 @@
 //! UNIX permissions are detailed in the POSIX specification.
 //!
 //! This module implements management of such permissions.
+//!
+//! The privileges of the superuser are split into capabilities, which can be granted
+//! independently. Each agent has several sets of capabilities:
+//! - permitted: the capabilities the agent may enable
+//! - effective: the capabilities currently enabled, used for permission checks
+//! - inheritable: the capabilities that may be preserved across a program execution
+//! - bounding: the limit on the capabilities that may be gained at program execution
+//! - ambient: the capabilities preserved across the execution of unprivileged programs
 
-use super::Mode;
-use utils::{errno, errno::EResult};
+use super::{Mode, Stat};
+use utils::{errno, errno::EResult, limits::NGROUPS_MAX};
 
 /// Type representing a user ID.
 pub type Uid = u16;
@@ -64,6 +72,178 @@ pub const S_ISGID: Mode = 0o2000;
 /// Sticky bit.
 pub const S_ISVTX: Mode = 0o1000;
 
+/// Type representing a capability.
+pub type Cap = u8;
+
+/// Capability: Change the owner of files.
+pub const CAP_CHOWN: Cap = 0;
+/// Capability: Bypass file read, write and execute permission checks.
+pub const CAP_DAC_OVERRIDE: Cap = 1;
+/// Capability: Bypass file read and directory search permission checks.
+pub const CAP_DAC_READ_SEARCH: Cap = 2;
+/// Capability: Bypass checks requiring the agent to be the owner of the file.
+pub const CAP_FOWNER: Cap = 3;
+/// Capability: Keep set-user-ID and set-group-ID bits when modifying a file.
+pub const CAP_FSETID: Cap = 4;
+/// Capability: Bypass permission checks for sending signals.
+pub const CAP_KILL: Cap = 5;
+/// Capability: Manipulate group IDs.
+pub const CAP_SETGID: Cap = 6;
+/// Capability: Manipulate user IDs.
+pub const CAP_SETUID: Cap = 7;
+/// Capability: Transfer capabilities and drop capabilities from the bounding set.
+pub const CAP_SETPCAP: Cap = 8;
+/// Capability: Set immutable and append-only file attributes.
+pub const CAP_LINUX_IMMUTABLE: Cap = 9;
+/// Capability: Bind a socket to a privileged port.
+pub const CAP_NET_BIND_SERVICE: Cap = 10;
+/// Capability: Broadcast and listen to multicast.
+pub const CAP_NET_BROADCAST: Cap = 11;
+/// Capability: Perform network administration operations.
+pub const CAP_NET_ADMIN: Cap = 12;
+/// Capability: Use raw and packet sockets.
+pub const CAP_NET_RAW: Cap = 13;
+/// Capability: Lock memory.
+pub const CAP_IPC_LOCK: Cap = 14;
+/// Capability: Bypass permission checks on IPC objects.
+pub const CAP_IPC_OWNER: Cap = 15;
+/// Capability: Load and unload kernel modules.
+pub const CAP_SYS_MODULE: Cap = 16;
+/// Capability: Perform I/O port operations.
+pub const CAP_SYS_RAWIO: Cap = 17;
+/// Capability: Use `chroot`.
+pub const CAP_SYS_CHROOT: Cap = 18;
+/// Capability: Trace arbitrary processes.
+pub const CAP_SYS_PTRACE: Cap = 19;
+/// Capability: Use `acct`.
+pub const CAP_SYS_PACCT: Cap = 20;
+/// Capability: Perform system administration operations.
+pub const CAP_SYS_ADMIN: Cap = 21;
+/// Capability: Reboot the system.
+pub const CAP_SYS_BOOT: Cap = 22;
+/// Capability: Raise the priority of processes.
+pub const CAP_SYS_NICE: Cap = 23;
+/// Capability: Override resource limits.
+pub const CAP_SYS_RESOURCE: Cap = 24;
+/// Capability: Set the system clock.
+pub const CAP_SYS_TIME: Cap = 25;
+/// Capability: Configure terminals.
+pub const CAP_SYS_TTY_CONFIG: Cap = 26;
+/// Capability: Create special files with `mknod`.
+pub const CAP_MKNOD: Cap = 27;
+/// Capability: Establish leases on files.
+pub const CAP_LEASE: Cap = 28;
+/// Capability: Write records to the audit log.
+pub const CAP_AUDIT_WRITE: Cap = 29;
+/// Capability: Configure auditing.
+pub const CAP_AUDIT_CONTROL: Cap = 30;
+/// Capability: Set file capabilities.
+pub const CAP_SETFCAP: Cap = 31;
+/// Capability: Override Mandatory Access Control.
+pub const CAP_MAC_OVERRIDE: Cap = 32;
+/// Capability: Configure Mandatory Access Control.
+pub const CAP_MAC_ADMIN: Cap = 33;
+/// Capability: Perform privileged `syslog` operations.
+pub const CAP_SYSLOG: Cap = 34;
+/// Capability: Trigger something that will wake up the system.
+pub const CAP_WAKE_ALARM: Cap = 35;
+/// Capability: Block system suspend.
+pub const CAP_BLOCK_SUSPEND: Cap = 36;
+/// Capability: Read the audit log.
+pub const CAP_AUDIT_READ: Cap = 37;
+/// Capability: Use performance monitoring.
+pub const CAP_PERFMON: Cap = 38;
+/// Capability: Use BPF.
+pub const CAP_BPF: Cap = 39;
+/// Capability: Use checkpoint and restore operations.
+pub const CAP_CHECKPOINT_RESTORE: Cap = 40;
+/// The highest valid capability.
+pub const CAP_LAST_CAP: Cap = CAP_CHECKPOINT_RESTORE;
+
+/// A set of capabilities.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CapSet(pub u64);
+
+impl CapSet {
+	/// The set containing all capabilities.
+	pub const FULL: Self = Self((1 << (CAP_LAST_CAP + 1)) - 1);
+
+	/// Tells whether the set contains the capability `cap`.
+	pub fn has(&self, cap: Cap) -> bool {
+		cap <= CAP_LAST_CAP && self.0 & (1 << cap) != 0
+	}
+
+	/// Adds the capability `cap` to the set.
+	pub fn add(&mut self, cap: Cap) {
+		self.0 |= 1 << cap;
+	}
+
+	/// Removes the capability `cap` from the set.
+	pub fn remove(&mut self, cap: Cap) {
+		self.0 &= !(1 << cap);
+	}
+
+	/// Tells whether all the capabilities of the set are in `other`.
+	pub fn is_subset_of(&self, other: Self) -> bool {
+		self.0 & !other.0 == 0
+	}
+
+	/// Returns the intersection of the two sets.
+	pub fn intersect(self, other: Self) -> Self {
+		Self(self.0 & other.0)
+	}
+
+	/// Returns the union of the two sets.
+	pub fn union(self, other: Self) -> Self {
+		Self(self.0 | other.0)
+	}
+}
+
+/// The capabilities granted by a program file at execution.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileCaps {
+	/// The capabilities added to the permitted set, if allowed by the bounding set.
+	pub permitted: CapSet,
+	/// The capabilities added to the permitted set if they are in the inheritable set.
+	pub inheritable: CapSet,
+	/// If `true`, the permitted set is enabled when the program starts.
+	pub effective: bool,
+}
+
+impl FileCaps {
+	/// The name of the extended attribute storing file capabilities.
+	pub const XATTR_NAME: &'static [u8] = b"security.capability";
+
+	/// Parses file capabilities from the content of the extended attribute (`vfs_cap_data`).
+	///
+	/// If the content is invalid, the function returns `None`.
+	pub fn from_xattr(buf: &[u8]) -> Option<Self> {
+		let word = |i: usize| -> Option<u32> {
+			let b = buf.get((i * 4)..(i * 4 + 4))?;
+			Some(u32::from_le_bytes(b.try_into().ok()?))
+		};
+		let magic_etc = word(0)?;
+		let words = match magic_etc & 0xff000000 {
+			// Version 1
+			0x01000000 => 1,
+			// Versions 2 and 3
+			0x02000000 | 0x03000000 => 2,
+			_ => return None,
+		};
+		let mut permitted = 0;
+		let mut inheritable = 0;
+		for i in 0..words {
+			permitted |= (word(1 + i * 2)? as u64) << (i * 32);
+			inheritable |= (word(2 + i * 2)? as u64) << (i * 32);
+		}
+		Some(Self {
+			permitted: CapSet(permitted).intersect(CapSet::FULL),
+			inheritable: CapSet(inheritable).intersect(CapSet::FULL),
+			effective: magic_etc & 1 != 0,
+		})
+	}
+}
+
 /// A set of informations determining whether an agent (example: a process) can access a resource.
 ///
 /// Implementations of this structure may contain functions to check access to an object. Custom
@@ -98,6 +278,25 @@ pub struct AccessProfile {
 	pub suid: Uid,
 	/// The saved group ID.
 	pub sgid: Gid,
+	/// The supplementary group IDs. Only the first `groups_len` elements are used.
+	groups: [Gid; NGROUPS_MAX],
+	/// The number of supplementary group IDs.
+	groups_len: usize,
+
+	/// The permitted capabilities set.
+	pub cap_permitted: CapSet,
+	/// The effective capabilities set.
+	pub cap_effective: CapSet,
+	/// The inheritable capabilities set.
+	pub cap_inheritable: CapSet,
+	/// The capabilities bounding set.
+	pub cap_bounding: CapSet,
+	/// The ambient capabilities set.
+	pub cap_ambient: CapSet,
+	/// If `true`, the permitted set is kept when all the user IDs stop being root.
+	pub keep_caps: bool,
+	/// If `true`, executing a program cannot grant privileges. Once set, this cannot be unset.
+	pub no_new_privs: bool,
 }
 
 impl AccessProfile {
@@ -111,10 +310,27 @@ impl AccessProfile {
 
 		suid: 0,
 		sgid: 0,
+		groups: [0; NGROUPS_MAX],
+		groups_len: 0,
+
+		cap_permitted: CapSet::FULL,
+		cap_effective: CapSet::FULL,
+		cap_inheritable: CapSet(0),
+		cap_bounding: CapSet::FULL,
+		cap_ambient: CapSet(0),
+		keep_caps: false,
+		no_new_privs: false,
 	};
 
 	/// Creates a profile from the given IDs.
+	///
+	/// The root user is granted all capabilities.
 	pub fn new(uid: Uid, gid: Gid) -> Self {
+		let caps = if uid == ROOT_UID {
+			CapSet::FULL
+		} else {
+			CapSet::default()
+		};
 		Self {
 			uid,
 			gid,
@@ -124,38 +340,192 @@ impl AccessProfile {
 
 			suid: uid,
 			sgid: gid,
+			groups: [0; NGROUPS_MAX],
+			groups_len: 0,
+
+			cap_permitted: caps,
+			cap_effective: caps,
+			cap_inheritable: CapSet::default(),
+			cap_bounding: CapSet::FULL,
+			cap_ambient: CapSet::default(),
+			keep_caps: false,
+			no_new_privs: false,
 		}
 	}
 
-	/// Tells whether the agent is privileged (root).
-	pub fn is_privileged(&self) -> bool {
-		self.euid == ROOT_UID || self.egid == ROOT_GID
+	/// Returns the supplementary group IDs.
+	pub fn get_groups(&self) -> &[Gid] {
+		&self.groups[..self.groups_len]
+	}
+
+	/// Sets the supplementary group IDs, in the same way the `setgroups` system call does.
+	///
+	/// If the agent is not privileged enough to make the change, the function returns an error.
+	pub fn set_groups(&mut self, groups: &[Gid]) -> EResult<()> {
+		if !self.has_cap(CAP_SETGID) {
+			return Err(errno!(EPERM));
+		}
+		if groups.len() > NGROUPS_MAX {
+			return Err(errno!(EINVAL));
+		}
+		self.groups[..groups.len()].copy_from_slice(groups);
+		self.groups_len = groups.len();
+		Ok(())
+	}
+
+	/// Tells whether the agent is a member of the group `gid`.
+	///
+	/// `effective` tells whether to use the effective group ID. If not, the real group ID is used.
+	/// Supplementary groups are taken into account in both cases.
+	pub fn is_in_group(&self, gid: Gid, effective: bool) -> bool {
+		let primary = if effective { self.egid } else { self.gid };
+		primary == gid || self.get_groups().contains(&gid)
+	}
+
+	/// Tells whether the user and group IDs of the agent are the same as the ones of `other`.
+	///
+	/// Supplementary groups are not taken into account.
+	pub fn same_ids(&self, other: &AccessProfile) -> bool {
+		(self.uid, self.euid, self.suid) == (other.uid, other.euid, other.suid)
+			&& (self.gid, self.egid, self.sgid) == (other.gid, other.egid, other.sgid)
+	}
+
+	/// Tells whether the agent can trace an agent with the access profile `target`.
+	///
+	/// Without [`CAP_SYS_PTRACE`], every user and group ID of both agents must be the same, so
+	/// that a program that gained privileges cannot be traced.
+	pub fn can_trace(&self, target: &AccessProfile) -> bool {
+		if self.has_cap(CAP_SYS_PTRACE) {
+			return true;
+		}
+		let uid = target.uid;
+		let gid = target.gid;
+		[self.uid, self.euid, self.suid, target.euid, target.suid]
+			.iter()
+			.all(|id| *id == uid)
+			&& [self.gid, self.egid, self.sgid, target.egid, target.sgid]
+				.iter()
+				.all(|id| *id == gid)
+	}
+
+	/// Tells whether the agent has the capability `cap` in its effective set.
+	pub fn has_cap(&self, cap: Cap) -> bool {
+		self.cap_effective.has(cap)
+	}
+
+	/// Updates capabilities after a change of user IDs, from the IDs of the profile `old`.
+	///
+	/// The rules are the following:
+	/// - If a user ID was root and none of them is root anymore, the permitted, effective and
+	///   ambient sets are cleared, unless [`Self::keep_caps`] is set (the ambient set is cleared
+	///   anyway)
+	/// - If the effective user ID stops being root, the effective set is cleared
+	/// - If the effective user ID becomes root, the permitted set is copied to the effective set
+	pub fn update_caps(&mut self, old: &Self) {
+		let was_root = old.uid == ROOT_UID || old.euid == ROOT_UID || old.suid == ROOT_UID;
+		let is_root = self.uid == ROOT_UID || self.euid == ROOT_UID || self.suid == ROOT_UID;
+		if was_root && !is_root {
+			self.cap_ambient = CapSet::default();
+			if !self.keep_caps {
+				self.cap_permitted = CapSet::default();
+				self.cap_effective = CapSet::default();
+			}
+		}
+		if old.euid == ROOT_UID && self.euid != ROOT_UID {
+			self.cap_effective = CapSet::default();
+		} else if old.euid != ROOT_UID && self.euid == ROOT_UID {
+			self.cap_effective = self.cap_permitted;
+		}
+	}
+
+	/// Computes the IDs of the agent after the execution of the program whose status is `stat`,
+	/// according to its set-user-ID and set-group-ID bits.
+	///
+	/// If `ignore_setid` is set, the bits are ignored. This is the case when the program is
+	/// located on a `nosuid` mountpoint or when the process is being traced.
+	pub fn exec_ids(&mut self, stat: &Stat, ignore_setid: bool) {
+		if !ignore_setid && !self.no_new_privs {
+			if stat.mode & S_ISUID != 0 {
+				self.euid = stat.uid;
+			}
+			// Without the group execute bit, the set-group-ID bit denotes mandatory locking
+			if stat.mode & S_ISGID != 0 && stat.mode & S_IXGRP != 0 {
+				self.egid = stat.gid;
+			}
+		}
+		self.suid = self.euid;
+		self.sgid = self.egid;
+	}
+
+	/// Computes the capabilities of the agent after the execution of a program.
+	///
+	/// `file_caps` is the set of capabilities attached to the program file, if any.
+	///
+	/// This function must be called after [`Self::exec_ids`]. It returns `true` if the program
+	/// gained privileges, in which case it must run in secure mode.
+	pub fn exec_caps(&mut self, file_caps: Option<FileCaps>) -> bool {
+		let old_permitted = self.cap_permitted;
+		let mut file_caps = file_caps.unwrap_or_default();
+		// A program executed by root is granted all capabilities
+		if self.uid == ROOT_UID || self.euid == ROOT_UID {
+			file_caps.permitted = CapSet::FULL;
+			file_caps.inheritable = CapSet::FULL;
+			file_caps.effective = self.euid == ROOT_UID;
+		}
+		// Ambient capabilities are lost when executing a privileged program
+		let privileged = file_caps.permitted.0 != 0 || file_caps.inheritable.0 != 0;
+		if privileged {
+			self.cap_ambient = CapSet::default();
+		}
+		self.cap_permitted = self
+			.cap_inheritable
+			.intersect(file_caps.inheritable)
+			.union(file_caps.permitted.intersect(self.cap_bounding))
+			.union(self.cap_ambient);
+		// With `no_new_privs`, the program cannot gain capabilities
+		if self.no_new_privs {
+			self.cap_permitted = self.cap_permitted.intersect(old_permitted);
+		}
+		self.cap_effective = if file_caps.effective {
+			self.cap_permitted
+		} else {
+			self.cap_ambient.intersect(self.cap_permitted)
+		};
+		self.keep_caps = false;
+		// Root is not considered as gaining privileges since it already has all of them
+		let root = self.uid == ROOT_UID && self.euid == ROOT_UID;
+		self.euid != self.uid
+			|| self.egid != self.gid
+			|| (!root && !self.cap_permitted.is_subset_of(old_permitted))
 	}
 
 	/// Sets the user ID in the same way the `setgid` system call does.
 	///
 	/// If the agent is not privileged enough to make the change, the function returns an error.
 	pub fn set_uid(&mut self, uid: Uid) -> EResult<()> {
-		if self.euid == ROOT_UID {
+		let old = *self;
+		if self.has_cap(CAP_SETUID) {
 			// privileged
 			self.uid = uid;
 			self.euid = uid;
 			self.suid = uid;
-			Ok(())
 		} else if uid == self.uid || uid == self.euid || uid == self.suid {
 			self.euid = uid;
-			Ok(())
 		} else {
-			Err(errno!(EPERM))
+			return Err(errno!(EPERM));
 		}
+		self.update_caps(&old);
+		Ok(())
 	}
 
 	/// Sets the effective user ID.
 	///
 	/// If the agent is not privileged enough to make the change, the function returns an error.
 	pub fn set_euid(&mut self, uid: Uid) -> EResult<()> {
-		if uid == ROOT_UID || uid == self.uid || uid == self.euid || uid == self.suid {
+		if self.has_cap(CAP_SETUID) || uid == self.uid || uid == self.euid || uid == self.suid {
+			let old = *self;
 			self.euid = uid;
+			self.update_caps(&old);
 			Ok(())
 		} else {
 			Err(errno!(EPERM))
@@ -166,7 +536,7 @@ impl AccessProfile {
 	///
 	/// If the agent is not privileged enough to make the change, the function returns an error.
 	pub fn set_gid(&mut self, gid: Gid) -> EResult<()> {
-		if self.egid == ROOT_GID {
+		if self.has_cap(CAP_SETGID) {
 			// privileged
 			self.gid = gid;
 			self.egid = gid;
@@ -184,7 +554,7 @@ impl AccessProfile {
 	///
 	/// If the agent is not privileged enough to make the change, the function returns an error.
 	pub fn set_egid(&mut self, gid: Uid) -> EResult<()> {
-		if gid == ROOT_GID || gid == self.gid || gid == self.egid || gid == self.sgid {
+		if self.has_cap(CAP_SETGID) || gid == self.gid || gid == self.egid || gid == self.sgid {
 			self.egid = gid;
 			Ok(())
 		} else {
@@ -192,3 +562,140 @@ impl AccessProfile {
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn caps_transitions() {
+		// Root loses its capabilities when switching to another user
+		let mut ap = AccessProfile::new(ROOT_UID, ROOT_GID);
+		assert!(ap.has_cap(CAP_SYS_ADMIN));
+		ap.set_uid(1000).unwrap();
+		assert_eq!(ap.cap_permitted, CapSet::default());
+		assert!(!ap.has_cap(CAP_SYS_ADMIN));
+		// Unless capabilities are kept, in which case only the effective set is cleared
+		let mut ap = AccessProfile::new(ROOT_UID, ROOT_GID);
+		ap.keep_caps = true;
+		ap.set_uid(1000).unwrap();
+		assert_eq!(ap.cap_permitted, CapSet::FULL);
+		assert_eq!(ap.cap_effective, CapSet::default());
+		// File capabilities are granted at execution
+		let mut ap = AccessProfile::new(1000, 1000);
+		let mut xattr = [0u8; 20];
+		xattr[0..4].copy_from_slice(&0x02000001u32.to_le_bytes());
+		xattr[4..8].copy_from_slice(&(1u32 << CAP_NET_RAW).to_le_bytes());
+		let caps = FileCaps::from_xattr(&xattr).unwrap();
+		ap.exec_caps(Some(caps));
+		assert!(ap.has_cap(CAP_NET_RAW));
+		assert!(!ap.has_cap(CAP_SYS_ADMIN));
+		// ...and lost when executing another program
+		ap.exec_caps(None);
+		assert!(!ap.has_cap(CAP_NET_RAW));
+	}
+
+	#[test_case]
+	fn exec_setid() {
+		let stat = Stat {
+			mode: S_ISUID | S_ISGID | 0o755,
+			uid: ROOT_UID,
+			gid: 42,
+			..Default::default()
+		};
+		// Set-user-ID root program
+		let mut ap = AccessProfile::new(1000, 1000);
+		ap.exec_ids(&stat, false);
+		assert_eq!((ap.uid, ap.euid, ap.suid), (1000, ROOT_UID, ROOT_UID));
+		assert_eq!((ap.gid, ap.egid, ap.sgid), (1000, 42, 42));
+		assert!(ap.exec_caps(None));
+		assert!(ap.has_cap(CAP_SYS_ADMIN));
+		// Set-ID bits are ignored
+		let mut ap = AccessProfile::new(1000, 1000);
+		ap.exec_ids(&stat, true);
+		assert_eq!((ap.euid, ap.egid), (1000, 1000));
+		assert!(!ap.exec_caps(None));
+		let mut ap = AccessProfile::new(1000, 1000);
+		ap.no_new_privs = true;
+		ap.exec_ids(&stat, false);
+		assert_eq!((ap.euid, ap.egid), (1000, 1000));
+		// Root executing a program does not gain privileges
+		let mut ap = AccessProfile::new(ROOT_UID, ROOT_GID);
+		ap.exec_ids(&Stat::default(), false);
+		assert!(!ap.exec_caps(None));
+	}
+
+	#[test_case]
+	fn supplementary_groups() {
+		// Only privileged agents may change their supplementary groups
+		let mut ap = AccessProfile::new(1000, 1000);
+		assert_eq!(ap.set_groups(&[42]), Err(errno!(EPERM)));
+		let mut ap = AccessProfile::new(ROOT_UID, ROOT_GID);
+		assert_eq!(ap.set_groups(&[0; NGROUPS_MAX + 1]), Err(errno!(EINVAL)));
+		ap.set_groups(&[42, 43]).unwrap();
+		assert_eq!(ap.get_groups(), &[42, 43]);
+		// Supplementary groups count for both real and effective checks
+		ap.set_egid(7).unwrap();
+		assert!(ap.is_in_group(43, true));
+		assert!(ap.is_in_group(43, false));
+		assert!(ap.is_in_group(7, true));
+		assert!(!ap.is_in_group(7, false));
+		assert!(ap.is_in_group(ROOT_GID, false));
+		assert!(!ap.is_in_group(44, true));
+		// Group permission bits are granted through a supplementary group
+		ap.set_groups(&[42]).unwrap();
+		ap.set_uid(1000).unwrap();
+		let stat = Stat {
+			mode: 0o060,
+			uid: 0,
+			gid: 42,
+			..Default::default()
+		};
+		assert!(ap.check_read_access(&stat, true));
+		assert!(ap.check_write_access(&stat, true));
+		assert!(!ap.check_execute_access(&stat, true));
+		let stat = Stat {
+			gid: 43,
+			..stat
+		};
+		assert!(!ap.check_read_access(&stat, true));
+		// Unprivileged now
+		assert_eq!(ap.set_groups(&[]), Err(errno!(EPERM)));
+	}
+
+	#[test_case]
+	fn trace_ids() {
+		let tracer = AccessProfile::new(1000, 1000);
+		assert!(tracer.can_trace(&AccessProfile::new(1000, 1000)));
+		assert!(!tracer.can_trace(&AccessProfile::new(1001, 1000)));
+		assert!(!tracer.can_trace(&AccessProfile::new(1000, 1001)));
+		// A set-user-ID program keeps the real user ID of its caller
+		let mut target = AccessProfile::new(1000, 1000);
+		target.euid = ROOT_UID;
+		target.suid = ROOT_UID;
+		assert!(!tracer.can_trace(&target));
+		// Same for set-group-ID programs
+		let mut target = AccessProfile::new(1000, 1000);
+		target.egid = 42;
+		assert!(!tracer.can_trace(&target));
+		// The tracer must not have different saved IDs either
+		let mut tracer = AccessProfile::new(1000, 1000);
+		tracer.sgid = 42;
+		assert!(!tracer.can_trace(&AccessProfile::new(1000, 1000)));
+		// Privileged tracers bypass the check
+		let root = AccessProfile::new(ROOT_UID, ROOT_GID);
+		assert!(root.can_trace(&target));
+	}
+
+	#[test_case]
+	fn same_ids() {
+		let ap = AccessProfile::new(1000, 1000);
+		assert!(ap.same_ids(&AccessProfile::new(1000, 1000)));
+		let mut other = ap;
+		other.suid = 0;
+		assert!(!ap.same_ids(&other));
+		let mut other = ap;
+		other.egid = 0;
+		assert!(!ap.same_ids(&other));
+	}
+}
@@ -0,0 +1,113 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A pidfd is a file descriptor referring to a process.
+//!
+//! Unlike a PID, a pidfd keeps referring to the same process after it has been reaped, even if its
+//! PID gets reused. The file becomes readable for polling when the process terminates.
+
+use crate::{
+	file::{wait_queue::WaitQueue, File, FileOps, Stat, O_NONBLOCK},
+	process::{pid::Pid, Process, State},
+	syscall::{ioctl, poll::POLLIN},
+};
+use core::ffi::c_void;
+use utils::{errno, errno::EResult, lock::IntMutex, ptr::arc::Arc};
+
+/// Flag: the pidfd is opened in non-blocking mode.
+pub const PIDFD_NONBLOCK: i32 = O_NONBLOCK;
+
+/// A pidfd.
+#[derive(Debug)]
+pub struct PidFd {
+	/// The global PID of the process.
+	pid: Pid,
+	/// The exit queue of the process, also used to identify it.
+	queue: Arc<WaitQueue>,
+}
+
+impl PidFd {
+	/// Creates a pidfd referring to the process `proc`.
+	pub fn new(proc: &Process) -> Self {
+		Self {
+			pid: proc.get_pid(),
+			queue: proc.exit_queue.clone(),
+		}
+	}
+
+	/// Returns the global PID of the process.
+	pub fn get_pid(&self) -> Pid {
+		self.pid
+	}
+
+	/// Tells whether `proc` is the process referred to by the pidfd.
+	pub fn is_process(&self, proc: &Process) -> bool {
+		Arc::as_ptr(&self.queue) == Arc::as_ptr(&proc.exit_queue)
+	}
+
+	/// Returns the process referred to by the pidfd.
+	///
+	/// If the process has been reaped, the function returns [`errno::ESRCH`].
+	pub fn get_process(&self) -> EResult<Arc<IntMutex<Process>>> {
+		Process::get_by_pid(self.pid)
+			.filter(|proc| self.is_process(&proc.lock()))
+			.ok_or_else(|| errno!(ESRCH))
+	}
+}
+
+impl FileOps for PidFd {
+	fn get_stat(&self, _file: &File) -> EResult<Stat> {
+		Ok(Stat {
+			mode: 0o600,
+			..Default::default()
+		})
+	}
+
+	fn acquire(&self, _file: &File) {}
+
+	fn release(&self, _file: &File) {}
+
+	fn poll(&self, _file: &File, mask: u32) -> EResult<u32> {
+		let exited = match self.get_process() {
+			Ok(proc) => matches!(proc.lock().get_state(), State::Zombie),
+			// Reaped
+			Err(_) => true,
+		};
+		Ok(if exited { POLLIN } else { 0 } & mask)
+	}
+
+	fn wait_queues(
+		&self,
+		_file: &File,
+		f: &mut dyn FnMut(&WaitQueue) -> EResult<()>,
+	) -> EResult<()> {
+		f(&self.queue)
+	}
+
+	fn ioctl(&self, _file: &File, _request: ioctl::Request, _argp: *const c_void) -> EResult<u32> {
+		Err(errno!(ENOTTY))
+	}
+
+	fn read(&self, _file: &File, _off: u64, _buf: &mut [u8]) -> EResult<usize> {
+		Err(errno!(EINVAL))
+	}
+
+	fn write(&self, _file: &File, _off: u64, _buf: &[u8]) -> EResult<usize> {
+		Err(errno!(EINVAL))
+	}
+}
@@ -22,7 +22,11 @@
 use crate::{
 	file::{wait_queue::WaitQueue, File, FileOps, FileType, Stat},
 	process::{mem_space::copy::SyscallPtr, signal::Signal, Process},
-	syscall::{ioctl, FromSyscallArg},
+	syscall::{
+		ioctl,
+		poll::{POLLERR, POLLHUP, POLLIN, POLLOUT, POLLRDNORM, POLLWRNORM},
+		FromSyscallArg,
+	},
 };
 use core::{
 	ffi::{c_int, c_void},
@@ -110,8 +114,35 @@ impl FileOps for PipeBuffer {
 		}
 	}
 
-	fn poll(&self, _file: &File, _mask: u32) -> EResult<u32> {
-		todo!()
+	fn poll(&self, file: &File, mask: u32) -> EResult<u32> {
+		let inner = self.inner.lock();
+		let mut res = 0;
+		if file.can_read() {
+			if !inner.buffer.is_empty() {
+				res |= POLLIN | POLLRDNORM;
+			}
+			if inner.writers == 0 {
+				res |= POLLHUP;
+			}
+		}
+		if file.can_write() {
+			if inner.readers == 0 {
+				res |= POLLERR;
+			} else if !inner.buffer.is_full() {
+				res |= POLLOUT | POLLWRNORM;
+			}
+		}
+		// Errors and hang ups are always reported
+		Ok(res & (mask | POLLERR | POLLHUP))
+	}
+
+	fn wait_queues(
+		&self,
+		_file: &File,
+		f: &mut dyn FnMut(&WaitQueue) -> EResult<()>,
+	) -> EResult<()> {
+		f(&self.rd_queue)?;
+		f(&self.wr_queue)
 	}
 
 	fn ioctl(&self, _file: &File, request: ioctl::Request, argp: *const c_void) -> EResult<u32> {
@@ -0,0 +1,223 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A signalfd is a file descriptor allowing to accept signals.
+//!
+//! Reading from the file dequeues pending signals of the reading process in the file's mask, as
+//! `signalfd_siginfo` records.
+
+use crate::{
+	file::{wait_queue::WaitQueue, File, FileOps, Stat, O_NONBLOCK},
+	process::{
+		signal::{SigInfo, SigSet, Signal, SI_TIMER},
+		Process,
+	},
+	syscall::{ioctl, poll::POLLIN},
+};
+use core::{ffi::c_void, intrinsics::unlikely, mem::size_of};
+use macros::AnyRepr;
+use utils::{bytes::as_bytes, errno, errno::EResult, lock::Mutex};
+
+/// A signal read from a signalfd.
+#[repr(C)]
+#[derive(AnyRepr, Clone, Copy, Debug)]
+pub struct SignalFdSigInfo {
+	/// Signal number.
+	pub ssi_signo: u32,
+	/// An errno value.
+	pub ssi_errno: i32,
+	/// Signal code.
+	pub ssi_code: i32,
+	/// PID of the sender.
+	pub ssi_pid: u32,
+	/// Real user ID of the sender.
+	pub ssi_uid: u32,
+	/// File descriptor, for `SIGIO`.
+	pub ssi_fd: i32,
+	/// ID of the timer, for timer signals.
+	pub ssi_tid: u32,
+	/// Band event, for `SIGIO`.
+	pub ssi_band: u32,
+	/// Overrun count, for timer signals.
+	pub ssi_overrun: u32,
+	/// Trap number that caused the signal.
+	pub ssi_trapno: u32,
+	/// Exit status or signal, for `SIGCHLD`.
+	pub ssi_status: i32,
+	/// Integer sent along with the signal.
+	pub ssi_int: i32,
+	/// Pointer sent along with the signal.
+	pub ssi_ptr: u64,
+	/// User CPU time consumed, for `SIGCHLD`.
+	pub ssi_utime: u64,
+	/// System CPU time consumed, for `SIGCHLD`.
+	pub ssi_stime: u64,
+	/// Address that generated the signal, for hardware-generated signals.
+	pub ssi_addr: u64,
+	/// Least significant bit of address, for `SIGBUS`.
+	pub ssi_addr_lsb: u16,
+	/// Padding.
+	__pad2: u16,
+	/// System call number, for `SIGSYS`.
+	pub ssi_syscall: i32,
+	/// System call address, for `SIGSYS`.
+	pub ssi_call_addr: u64,
+	/// System call architecture, for `SIGSYS`.
+	pub ssi_arch: u32,
+	/// Padding.
+	__pad: [u8; 28],
+}
+
+impl From<&SigInfo> for SignalFdSigInfo {
+	fn from(info: &SigInfo) -> Self {
+		let mut ssi = Self {
+			ssi_signo: info.si_signo as _,
+			ssi_errno: info.si_errno,
+			ssi_code: info.si_code,
+			ssi_pid: 0,
+			ssi_uid: 0,
+			ssi_fd: 0,
+			ssi_tid: 0,
+			ssi_band: 0,
+			ssi_overrun: 0,
+			ssi_trapno: 0,
+			ssi_status: 0,
+			ssi_int: 0,
+			ssi_ptr: 0,
+			ssi_utime: 0,
+			ssi_stime: 0,
+			ssi_addr: 0,
+			ssi_addr_lsb: 0,
+			__pad2: 0,
+			ssi_syscall: 0,
+			ssi_call_addr: 0,
+			ssi_arch: 0,
+			__pad: [0; 28],
+		};
+		let fault = matches!(
+			Signal::try_from(info.si_signo),
+			Ok(Signal::SIGILL | Signal::SIGFPE | Signal::SIGSEGV | Signal::SIGBUS)
+		);
+		if info.si_code == SI_TIMER {
+			ssi.ssi_tid = info.si_timerid() as _;
+			ssi.ssi_overrun = info.si_overrun() as _;
+			ssi.ssi_int = info.si_value() as _;
+			ssi.ssi_ptr = info.si_value() as _;
+		} else if fault && info.si_code > 0 {
+			ssi.ssi_addr = info.si_addr().0 as _;
+		} else {
+			ssi.ssi_pid = info.si_pid() as _;
+			ssi.ssi_uid = info.si_uid() as _;
+			ssi.ssi_int = info.si_value() as _;
+			ssi.ssi_ptr = info.si_value() as _;
+		}
+		ssi
+	}
+}
+
+/// A signalfd.
+#[derive(Debug)]
+pub struct SignalFd {
+	/// The set of signals accepted by the file.
+	mask: Mutex<SigSet>,
+}
+
+impl SignalFd {
+	/// Creates a signalfd accepting the signals in `mask`.
+	pub fn new(mask: SigSet) -> Self {
+		Self {
+			mask: Mutex::new(mask),
+		}
+	}
+
+	/// Sets the set of signals accepted by the file.
+	pub fn set_mask(&self, mask: SigSet) {
+		*self.mask.lock() = mask;
+	}
+}
+
+impl FileOps for SignalFd {
+	fn get_stat(&self, _file: &File) -> EResult<Stat> {
+		Ok(Stat {
+			mode: 0o600,
+			..Default::default()
+		})
+	}
+
+	fn acquire(&self, _file: &File) {}
+
+	fn release(&self, _file: &File) {}
+
+	fn poll(&self, _file: &File, mask: u32) -> EResult<u32> {
+		let sigmask = *self.mask.lock();
+		let pending = Process::current().lock().get_pending_signals();
+		let ready = pending.0 & sigmask.0 != 0;
+		Ok(if ready { POLLIN } else { 0 } & mask)
+	}
+
+	fn wait_queues(
+		&self,
+		_file: &File,
+		f: &mut dyn FnMut(&WaitQueue) -> EResult<()>,
+	) -> EResult<()> {
+		// Signals are read from the process using the file
+		let queue = Process::current().lock().signalfd_queue.clone();
+		f(&queue)
+	}
+
+	fn ioctl(&self, _file: &File, _request: ioctl::Request, _argp: *const c_void) -> EResult<u32> {
+		Err(errno!(ENOTTY))
+	}
+
+	fn read(&self, file: &File, _off: u64, buf: &mut [u8]) -> EResult<usize> {
+		const SIZE: usize = size_of::<SignalFdSigInfo>();
+		let max = buf.len() / SIZE;
+		if unlikely(max == 0) {
+			return Err(errno!(EINVAL));
+		}
+		let nonblock = file.get_flags() & O_NONBLOCK != 0;
+		let proc_mutex = Process::current();
+		let queue = proc_mutex.lock().signalfd_queue.clone();
+		let mut count = 0;
+		// Only the first signal is waited for
+		let first = queue.wait_until(|| {
+			let mask = *self.mask.lock();
+			match proc_mutex.lock().dequeue_signal(mask) {
+				Some((_, info)) => Some(Ok(info)),
+				None if nonblock => Some(Err(errno!(EAGAIN))),
+				None => None,
+			}
+		})??;
+		let mut info = Some(first);
+		while let Some(i) = info {
+			let ssi = SignalFdSigInfo::from(&i);
+			buf[count * SIZE..(count + 1) * SIZE].copy_from_slice(as_bytes(&ssi));
+			count += 1;
+			if count >= max {
+				break;
+			}
+			let mask = *self.mask.lock();
+			info = proc_mutex.lock().dequeue_signal(mask).map(|(_, i)| i);
+		}
+		Ok(count * SIZE)
+	}
+
+	fn write(&self, _file: &File, _off: u64, _buf: &[u8]) -> EResult<usize> {
+		Err(errno!(EINVAL))
+	}
+}
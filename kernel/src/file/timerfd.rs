@@ -0,0 +1,159 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A timerfd is a file descriptor notifying the expirations of a timer.
+//!
+//! Reading from the file returns the number of expirations since the last read.
+
+use crate::{
+	file::{wait_queue::WaitQueue, File, FileOps, Stat, O_NONBLOCK},
+	syscall::{ioctl, poll::POLLIN},
+	time::{timer::TimerManager, unit::TimerT},
+};
+use core::{ffi::c_void, fmt, intrinsics::unlikely, mem::size_of};
+use utils::{errno, errno::EResult, lock::IntMutex, ptr::arc::Arc};
+
+/// The state of a timerfd, shared with the underlying timer.
+#[derive(Debug, Default)]
+pub struct TimerFdState {
+	/// The number of expirations since the last read.
+	ticks: IntMutex<u64>,
+	/// The queue of processes waiting for the timer to expire.
+	queue: WaitQueue,
+}
+
+impl TimerFdState {
+	/// Records an expiration of the timer.
+	pub fn expire(&self) {
+		{
+			let mut ticks = self.ticks.lock();
+			*ticks = ticks.saturating_add(1);
+		}
+		self.queue.wake_all();
+	}
+
+	/// Resets the number of expirations.
+	pub fn reset(&self) {
+		*self.ticks.lock() = 0;
+	}
+}
+
+/// A timerfd.
+pub struct TimerFd {
+	/// The manager holding the underlying timer.
+	manager: Arc<IntMutex<TimerManager>>,
+	/// The ID of the underlying timer.
+	timer_id: TimerT,
+	/// The state shared with the timer.
+	state: Arc<TimerFdState>,
+}
+
+impl TimerFd {
+	/// Creates a timerfd on the timer with ID `timer_id` of `manager`.
+	pub fn new(
+		manager: Arc<IntMutex<TimerManager>>,
+		timer_id: TimerT,
+		state: Arc<TimerFdState>,
+	) -> Self {
+		Self {
+			manager,
+			timer_id,
+			state,
+		}
+	}
+
+	/// Returns the manager holding the underlying timer.
+	pub fn get_manager(&self) -> &Arc<IntMutex<TimerManager>> {
+		&self.manager
+	}
+
+	/// Returns the ID of the underlying timer.
+	pub fn get_timer_id(&self) -> TimerT {
+		self.timer_id
+	}
+
+	/// Returns the state shared with the timer.
+	pub fn get_state(&self) -> &TimerFdState {
+		&self.state
+	}
+}
+
+impl fmt::Debug for TimerFd {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("TimerFd")
+			.field("timer_id", &self.timer_id)
+			.field("state", &self.state)
+			.finish()
+	}
+}
+
+impl FileOps for TimerFd {
+	fn get_stat(&self, _file: &File) -> EResult<Stat> {
+		Ok(Stat {
+			mode: 0o600,
+			..Default::default()
+		})
+	}
+
+	fn acquire(&self, _file: &File) {}
+
+	fn release(&self, _file: &File) {
+		let _ = self.manager.lock().delete_timer(self.timer_id);
+	}
+
+	fn poll(&self, _file: &File, mask: u32) -> EResult<u32> {
+		let ready = *self.state.ticks.lock() > 0;
+		Ok(if ready { POLLIN } else { 0 } & mask)
+	}
+
+	fn wait_queues(
+		&self,
+		_file: &File,
+		f: &mut dyn FnMut(&WaitQueue) -> EResult<()>,
+	) -> EResult<()> {
+		f(&self.state.queue)
+	}
+
+	fn ioctl(&self, _file: &File, _request: ioctl::Request, _argp: *const c_void) -> EResult<u32> {
+		Err(errno!(ENOTTY))
+	}
+
+	fn read(&self, file: &File, _off: u64, buf: &mut [u8]) -> EResult<usize> {
+		if unlikely(buf.len() < size_of::<u64>()) {
+			return Err(errno!(EINVAL));
+		}
+		let nonblock = file.get_flags() & O_NONBLOCK != 0;
+		let ticks = self.state.queue.wait_until(|| {
+			let mut ticks = self.state.ticks.lock();
+			match *ticks {
+				0 if nonblock => Some(Err(errno!(EAGAIN))),
+				0 => None,
+				n => {
+					*ticks = 0;
+					Some(Ok(n))
+				}
+			}
+		})??;
+		buf[..size_of::<u64>()].copy_from_slice(&ticks.to_ne_bytes());
+		Ok(size_of::<u64>())
+	}
+
+	fn write(&self, _file: &File, _off: u64, _buf: &[u8]) -> EResult<usize> {
+		Err(errno!(EINVAL))
+	}
+}
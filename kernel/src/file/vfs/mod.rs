@@ -30,8 +30,14 @@ use super::{
 	File, FileLocation, FileType, Stat,
 };
 use crate::{
-	device, device::DeviceID, file::vfs::mountpoint::MountPoint, process::Process,
-	syscall::ioctl::Request,
+	device,
+	device::DeviceID,
+	file::{vfs::mountpoint::MountPoint, wait_queue::WaitQueue},
+	process::Process,
+	syscall::{
+		ioctl::Request,
+		poll::{POLLIN, POLLOUT, POLLRDNORM, POLLWRNORM},
+	},
 };
 use core::{
 	borrow::Borrow,
@@ -769,10 +775,30 @@ impl super::FileOps for FileOps {
 			.ok_or_else(|| errno!(ENODEV))?
 			.get_io()
 			.poll(mask),
-			None => todo!(),
+			// Regular files and directories are always ready
+			None => Ok(mask & (POLLIN | POLLOUT | POLLRDNORM | POLLWRNORM)),
 		}
 	}
 
+	fn wait_queues(
+		&self,
+		file: &File,
+		f: &mut dyn FnMut(&WaitQueue) -> EResult<()>,
+	) -> EResult<()> {
+		let stat = self.get_stat(file)?;
+		let Some(dev_type) = stat.get_type().and_then(FileType::to_device_type) else {
+			return Ok(());
+		};
+		device::get(&DeviceID {
+			dev_type,
+			major: stat.dev_major,
+			minor: stat.dev_minor,
+		})
+		.ok_or_else(|| errno!(ENODEV))?
+		.get_io()
+		.wait_queues(f)
+	}
+
 	fn ioctl(&self, file: &File, request: Request, argp: *const c_void) -> EResult<u32> {
 		let stat = self.get_stat(file)?;
 		let dev_type = stat
@@ -28,6 +28,7 @@ use crate::{
 		vfs::{node, node::Node, EntryChild, ResolutionSettings},
 		FileLocation, FileType,
 	},
+	process::namespace,
 };
 use core::fmt;
 use utils::{
@@ -35,10 +36,11 @@ use utils::{
 		hashmap::HashMap,
 		path::{Path, PathBuf},
 		string::String,
+		vec::Vec,
 	},
 	errno,
 	errno::{AllocResult, EResult},
-	lock::Mutex,
+	lock::{once::OnceInit, Mutex},
 	ptr::arc::Arc,
 	TryClone,
 };
@@ -190,9 +192,6 @@ pub struct MountPoint {
 	pub source: MountSource,
 	/// The filesystem associated with the mountpoint.
 	pub fs: Arc<dyn Filesystem>,
-
-	/// The root entry of the mountpoint.
-	pub root_entry: Arc<vfs::Entry>,
 }
 
 impl MountPoint {
@@ -227,9 +226,147 @@ impl Drop for MountPoint {
 }
 
 /// The list of mountpoints with their respective ID.
+///
+/// A mountpoint remains in this list as long as it is present in at least one mount namespace.
 pub static MOUNT_POINTS: Mutex<HashMap<u32, Arc<MountPoint>>> = Mutex::new(HashMap::new());
 
+/// Removes the mountpoints that are not present in any mount namespace anymore.
+fn remove_unused() {
+	MOUNT_POINTS
+		.lock()
+		.retain(|_, mp| Arc::strong_count(mp) > 1);
+}
+
+/// A mountpoint in a mount namespace.
+#[derive(Debug)]
+pub struct Mount {
+	/// The mountpoint.
+	pub mp: Arc<MountPoint>,
+	/// The root entry of the mountpoint in the namespace's tree.
+	pub root_entry: Arc<vfs::Entry>,
+}
+
+/// A mount namespace.
+///
+/// Each namespace has its own tree of entries, in which its mountpoints are attached. Processes
+/// in the namespace resolve paths from the root of this tree.
+#[derive(Debug)]
+pub struct MountNamespace {
+	/// The ID of the namespace.
+	id: u32,
+	/// The root entry of the namespace's tree.
+	root: Arc<vfs::Entry>,
+	/// The mountpoints of the namespace, by ID.
+	pub mounts: Mutex<HashMap<u32, Mount>>,
+}
+
+impl MountNamespace {
+	/// Returns the ID of the namespace.
+	pub fn get_id(&self) -> u32 {
+		self.id
+	}
+
+	/// Returns the root entry of the namespace's tree.
+	pub fn root(&self) -> Arc<vfs::Entry> {
+		self.root.clone()
+	}
+
+	/// Creates a copy of the namespace, with a new tree in which the same mountpoints are
+	/// attached at the same paths.
+	pub fn try_clone(&self) -> EResult<Self> {
+		let mounts = self.mounts.lock();
+		// Sort mountpoints by path so that parents are attached before their children
+		let mut paths = Vec::with_capacity(mounts.len())?;
+		for (id, mount) in mounts.iter() {
+			let path = vfs::Entry::get_path(&mount.root_entry)?;
+			paths.push((path, *id))?;
+		}
+		paths.sort_unstable_by_key(|(path, _)| path.len());
+		let root = Arc::new(vfs::Entry::from_node(self.root.node().clone()))?;
+		let mut new_mounts = HashMap::new();
+		for (path, id) in paths {
+			// Unwrap cannot fail since the mountpoint comes from the list
+			let mount = mounts.get(&id).unwrap();
+			let root_entry = if mount.root_entry.parent.is_none() {
+				root.clone()
+			} else {
+				let rs = ResolutionSettings {
+					root: root.clone(),
+					follow_link: false,
+					..ResolutionSettings::kernel_nofollow()
+				};
+				let target = vfs::get_file_from_path(&path, &rs)?;
+				attach(&target, mount.root_entry.node().clone())?
+			};
+			new_mounts.insert(
+				id,
+				Mount {
+					mp: mount.mp.clone(),
+					root_entry,
+				},
+			)?;
+		}
+		Ok(Self {
+			id: namespace::alloc_id(),
+			root,
+			mounts: Mutex::new(new_mounts),
+		})
+	}
+
+	/// Returns the entry in the namespace's tree at the same path as `entry`, which belongs to
+	/// another namespace.
+	///
+	/// If no such entry exists, the function returns the root of the namespace.
+	pub fn translate(&self, entry: &Arc<vfs::Entry>) -> Arc<vfs::Entry> {
+		let rs = ResolutionSettings {
+			root: self.root.clone(),
+			..ResolutionSettings::kernel_follow()
+		};
+		vfs::Entry::get_path(entry)
+			.and_then(|path| vfs::get_file_from_path(&path, &rs))
+			.unwrap_or_else(|_| self.root.clone())
+	}
+}
+
+impl Drop for MountNamespace {
+	fn drop(&mut self) {
+		self.mounts.lock().clear();
+		remove_unused();
+	}
+}
+
+/// Attaches a mountpoint root `node` in place of the `target` entry, in the tree `target`
+/// belongs to.
+///
+/// The function returns the created entry.
+fn attach(target: &Arc<vfs::Entry>, node: Arc<Node>) -> EResult<Arc<vfs::Entry>> {
+	let root_entry = Arc::new(vfs::Entry {
+		name: target.name.try_clone()?,
+		parent: target.parent.clone(),
+		children: Default::default(),
+		node: Some(node),
+	})?;
+	// Replace `target` with the mountpoint's root in the tree
+	if let Some(target_parent) = &target.parent {
+		target_parent
+			.children
+			.lock()
+			.insert(EntryChild(root_entry.clone()))?;
+	}
+	Ok(root_entry)
+}
+
+/// The initial mount namespace.
+static INIT_NS: OnceInit<Arc<MountNamespace>> = unsafe { OnceInit::new() };
+
+/// Returns the initial mount namespace.
+pub fn init_ns() -> Arc<MountNamespace> {
+	INIT_NS.get().clone()
+}
+
 /// Creates the root mountpoint and returns the newly created root entry of the VFS.
+///
+/// The mountpoint is attached to the initial mount namespace.
 pub(crate) fn create_root(source: MountSource) -> EResult<Arc<vfs::Entry>> {
 	let fs = get_fs(&source, None, PathBuf::root()?, false)?;
 	// Get filesystem root node
@@ -250,10 +387,24 @@ pub(crate) fn create_root(source: MountSource) -> EResult<Arc<vfs::Entry>> {
 
 		source,
 		fs,
-
-		root_entry: root_entry.clone(),
 	})?;
-	MOUNT_POINTS.lock().insert(0, mountpoint)?;
+	MOUNT_POINTS.lock().insert(0, mountpoint.clone())?;
+	let mut mounts = HashMap::new();
+	mounts.insert(
+		0,
+		Mount {
+			mp: mountpoint,
+			root_entry: root_entry.clone(),
+		},
+	)?;
+	let ns = Arc::new(MountNamespace {
+		id: namespace::alloc_id(),
+		root: root_entry.clone(),
+		mounts: Mutex::new(mounts),
+	})?;
+	unsafe {
+		INIT_NS.init(ns);
+	}
 	Ok(root_entry)
 }
 
@@ -266,6 +417,7 @@ pub(crate) fn create_root(source: MountSource) -> EResult<Arc<vfs::Entry>> {
 /// - `fs_type` is the filesystem type. If `None`, the function tries to detect it automatically
 /// - `flags` are the mount flags
 /// - `target` is the target directory
+/// - `ns` is the mount namespace `target` belongs to
 ///
 /// The function returns the ID of the newly created mountpoint.
 pub fn create(
@@ -273,6 +425,7 @@ pub fn create(
 	fs_type: Option<Arc<dyn FilesystemType>>,
 	flags: u32,
 	target: Arc<vfs::Entry>,
+	ns: &MountNamespace,
 ) -> EResult<()> {
 	// Get filesystem
 	let target_path = vfs::Entry::get_path(&target)?;
@@ -290,13 +443,6 @@ pub fn create(
 		},
 		ops: fs.node_from_inode(root_inode)?,
 	})?;
-	// Create an entry for the root of the mountpoint
-	let root_entry = Arc::new(vfs::Entry {
-		name: target.name.try_clone()?,
-		parent: target.parent.clone(),
-		children: Default::default(),
-		node: Some(node),
-	})?;
 	// Create mountpoint
 	let mountpoint = Arc::new(MountPoint {
 		id,
@@ -304,29 +450,35 @@ pub fn create(
 
 		source,
 		fs,
-
-		root_entry: root_entry.clone(),
 	})?;
 	// If the next insertion fails, this will be undone by the implementation of `Drop`
-	mps.insert(id, mountpoint)?;
-	// Replace `target` with the mountpoint's root in the tree
-	if let Some(target_parent) = &target.parent {
-		target_parent
-			.children
-			.lock()
-			.insert(EntryChild(root_entry))?;
+	mps.insert(id, mountpoint.clone())?;
+	drop(mps);
+	let res = (|| {
+		let root_entry = attach(&target, node)?;
+		ns.mounts.lock().insert(
+			id,
+			Mount {
+				mp: mountpoint,
+				root_entry,
+			},
+		)?;
+		Ok(())
+	})();
+	if res.is_err() {
+		remove_unused();
 	}
-	Ok(())
+	res
 }
 
-/// Removes the mountpoint at the given `target` entry.
+/// Removes the mountpoint at the given `target` entry from the mount namespace `ns`.
 ///
 /// Data is synchronized to the associated storage device, if any, before removing the mountpoint.
 ///
 /// If `target` is not a mountpoint, the function returns [`errno::EINVAL`].
 ///
 /// If the mountpoint is busy, the function returns [`errno::EBUSY`].
-pub fn remove(target: Arc<vfs::Entry>) -> EResult<()> {
+pub fn remove(target: Arc<vfs::Entry>, ns: &MountNamespace) -> EResult<()> {
 	let Some(mp) = target.get_mountpoint() else {
 		return Err(errno!(EINVAL));
 	};
@@ -339,11 +491,10 @@ pub fn remove(target: Arc<vfs::Entry>) -> EResult<()> {
 		return Err(errno!(EINVAL));
 	};
 	parent.children.lock().remove(target.name.as_bytes());
+	ns.mounts.lock().remove(&mp.id);
 	// If this was the last reference to the mountpoint, remove it
-	let mut mps = MOUNT_POINTS.lock();
-	if Arc::strong_count(&mp) <= 2 {
-		mps.remove(&mp.id);
-	}
+	drop(mp);
+	remove_unused();
 	Ok(())
 }
 
@@ -23,31 +23,66 @@ use crate::{
 	process,
 	process::{pid::Pid, scheduler, Process},
 };
-use core::mem;
+use core::{fmt::Debug, mem};
 use utils::{
 	collections::vec::Vec,
 	errno,
-	errno::EResult,
+	errno::{AllocResult, EResult},
 	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
 };
 
+/// An object notified each time a [`WaitQueue`] it is registered on is woken up.
+///
+/// Notifications may happen in interrupt context, while the resource the queue belongs to is
+/// locked. Thus, implementations must neither sleep nor access that resource.
+pub trait Watcher: Debug {
+	/// Notifies the watcher.
+	///
+	/// If the function returns `false`, the watcher is unregistered from the queue.
+	fn notify(&self) -> bool;
+}
+
 /// A queue of processes waiting on a resource.
 ///
 /// Wait processes shall sleep, and be woken up when the resource is available.
 ///
 /// **Note**: dropping this structure while processes are waiting on it makes them starve.
 #[derive(Debug, Default)]
-pub struct WaitQueue(IntMutex<Vec<Pid>>); // TODO use a VecDeque
+pub struct WaitQueue {
+	/// The processes waiting on the resource.
+	procs: IntMutex<Vec<Pid>>, // TODO use a VecDeque
+	/// The watchers registered on the queue.
+	watchers: IntMutex<Vec<Arc<dyn Watcher>>>,
+}
 
 impl WaitQueue {
 	/// Creates a new empty queue.
 	pub const fn new() -> Self {
-		Self(Mutex::new(Vec::new()))
+		Self {
+			procs: Mutex::new(Vec::new()),
+			watchers: Mutex::new(Vec::new()),
+		}
+	}
+
+	/// Tells whether no process is waiting on the queue and no watcher is registered.
+	pub fn is_empty(&self) -> bool {
+		self.procs.lock().is_empty() && self.watchers.lock().is_empty()
+	}
+
+	/// Registers a watcher to be notified each time the queue is woken up.
+	pub fn add_watcher(&self, watcher: Arc<dyn Watcher>) -> AllocResult<()> {
+		self.watchers.lock().push(watcher)
+	}
+
+	/// Notifies the registered watchers, unregistering those that are no longer interested.
+	fn notify_watchers(&self) {
+		self.watchers.lock().retain(|w| w.notify());
 	}
 
 	/// Makes the current process wait until the given closure returns `Some`.
 	///
-	/// If waiting is interrupted by a signal handler, the function returns [`errno::EINTR`].
+	/// If waiting is interrupted by a signal, the function returns [`errno::ERESTARTSYS`].
 	pub fn wait_until<F: FnMut() -> Option<T>, T>(&self, mut f: F) -> EResult<T> {
 		loop {
 			if let Some(val) = f() {
@@ -57,7 +92,7 @@ impl WaitQueue {
 			{
 				let proc_mutex = Process::current();
 				let mut proc = proc_mutex.lock();
-				self.0.lock().push(proc.get_pid())?;
+				self.procs.lock().push(proc.get_pid())?;
 				proc.set_state(process::State::Sleeping);
 			}
 			// Yield
@@ -69,7 +104,7 @@ impl WaitQueue {
 				let proc_mutex = Process::current();
 				let mut proc = proc_mutex.lock();
 				if proc.next_signal(true).is_some() {
-					return Err(errno!(EINTR));
+					return Err(errno!(ERESTARTSYS));
 				}
 			}
 		}
@@ -77,10 +112,11 @@ impl WaitQueue {
 
 	/// Wakes the next process in queue.
 	pub fn wake_next(&self) {
+		self.notify_watchers();
 		let proc = loop {
 			// TODO: inefficient, must use a linked list
 			let pid = {
-				let mut pids = self.0.lock();
+				let mut pids = self.procs.lock();
 				if pids.is_empty() {
 					// No process to wake, stop
 					return;
@@ -98,7 +134,8 @@ impl WaitQueue {
 
 	/// Wakes all processes.
 	pub fn wake_all(&self) {
-		let mut pids = self.0.lock();
+		self.notify_watchers();
+		let mut pids = self.procs.lock();
 		for pid in mem::take(&mut *pids) {
 			let Some(proc) = Process::get_by_pid(pid) else {
 				// Process does not exist, try next
@@ -0,0 +1,391 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Inter-Process Communication.
+//!
+//! System V IPC objects (shared memory segments, semaphore sets and message queues) live in
+//! namespaces, one per kind of object. An object is designated by a key chosen by userspace, and
+//! identified by an ID allocated by the kernel.
+//!
+//! POSIX message queues are implemented in [`mqueue`].
+//!
+//! All these objects belong to an [`IpcNamespace`], which isolates them from processes of other
+//! IPC namespaces.
+
+pub mod mqueue;
+pub mod msg;
+pub mod sem;
+pub mod shm;
+
+use crate::{
+	file::{
+		perm::{AccessProfile, Gid, Uid, CAP_IPC_OWNER, CAP_SYS_ADMIN},
+		wait_queue::WaitQueue,
+	},
+	process::{namespace, scheduler, Process},
+	time::{
+		clock,
+		clock::{CLOCK_MONOTONIC, CLOCK_REALTIME},
+		unit::{Timestamp, TimestampScale},
+	},
+};
+use core::ffi::c_int;
+use utils::{
+	collections::{btreemap::BTreeMap, string::String},
+	errno,
+	errno::EResult,
+	lock::Mutex,
+	ptr::arc::Arc,
+};
+
+/// Type representing an IPC key.
+pub type KeyT = c_int;
+
+/// Key requesting the creation of a new object that cannot be retrieved by key.
+pub const IPC_PRIVATE: KeyT = 0;
+
+/// `*get` flag: Create the object if it does not exist.
+pub const IPC_CREAT: c_int = 0o1000;
+/// `*get` flag: Fail if the object already exists.
+pub const IPC_EXCL: c_int = 0o2000;
+/// Operation flag: Return an error instead of blocking.
+pub const IPC_NOWAIT: c_int = 0o4000;
+
+/// `*ctl` command: Remove the object.
+pub const IPC_RMID: c_int = 0;
+/// `*ctl` command: Set the object's ownership and permissions.
+pub const IPC_SET: c_int = 1;
+/// `*ctl` command: Get the object's status.
+pub const IPC_STAT: c_int = 2;
+/// `*ctl` command flag: Use the 64 bit version of structures. This is the only supported version,
+/// so the flag is ignored.
+pub const IPC_64: c_int = 0x100;
+
+/// Access to an object: Read.
+pub const IPC_READ: u16 = 0o4;
+/// Access to an object: Write (or alter, for semaphores).
+pub const IPC_WRITE: u16 = 0o2;
+
+/// The maximum number of objects in a namespace.
+const IPC_MNI: usize = 4096;
+
+/// Ownership and permissions of an IPC object.
+///
+/// The layout of this structure matches the one of `ipc64_perm`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IpcPerm {
+	/// The key of the object.
+	pub key: KeyT,
+	/// The effective user ID of the owner.
+	pub uid: u32,
+	/// The effective group ID of the owner.
+	pub gid: u32,
+	/// The effective user ID of the creator.
+	pub cuid: u32,
+	/// The effective group ID of the creator.
+	pub cgid: u32,
+	/// The permissions of the object.
+	pub mode: u16,
+	/// Padding.
+	_pad1: u16,
+	/// The sequence number of the object.
+	pub seq: u16,
+	/// Padding.
+	_pad2: u16,
+	/// Unused.
+	_unused1: u32,
+	/// Unused.
+	_unused2: u32,
+}
+
+impl IpcPerm {
+	/// Creates the permissions for an object created by the agent `ap`.
+	///
+	/// `mode` is the set of permissions, only the lower 9 bits being taken into account.
+	fn new(key: KeyT, mode: c_int, ap: &AccessProfile) -> Self {
+		Self {
+			key,
+			uid: ap.euid as _,
+			gid: ap.egid as _,
+			cuid: ap.euid as _,
+			cgid: ap.egid as _,
+			mode: (mode & 0o777) as _,
+			..Default::default()
+		}
+	}
+
+	/// Updates the ownership and permissions of the object from `new`, as the `IPC_SET` command
+	/// does.
+	///
+	/// If the agent `ap` is not allowed to perform the change, the function returns an error.
+	pub fn set(&mut self, new: &IpcPerm, ap: &AccessProfile) -> EResult<()> {
+		if !ap.is_ipc_owner(self) {
+			return Err(errno!(EPERM));
+		}
+		self.uid = new.uid as Uid as _;
+		self.gid = new.gid as Gid as _;
+		self.mode = (self.mode & !0o777) | (new.mode & 0o777);
+		Ok(())
+	}
+}
+
+impl AccessProfile {
+	/// Tells whether the agent can access the IPC object with the given permissions.
+	///
+	/// `access` is the requested access, made of [`IPC_READ`] and [`IPC_WRITE`].
+	pub fn can_access_ipc(&self, perm: &IpcPerm, access: u16) -> bool {
+		if self.has_cap(CAP_IPC_OWNER) {
+			return true;
+		}
+		let euid = self.euid as u32;
+		let in_group = |gid: u32| Gid::try_from(gid).is_ok_and(|gid| self.is_in_group(gid, true));
+		let granted = if euid == perm.uid || euid == perm.cuid {
+			perm.mode >> 6
+		} else if in_group(perm.gid) || in_group(perm.cgid) {
+			perm.mode >> 3
+		} else {
+			perm.mode
+		};
+		access & !granted & 0o7 == 0
+	}
+
+	/// Tells whether the agent is allowed to change or remove the IPC object with the given
+	/// permissions.
+	pub fn is_ipc_owner(&self, perm: &IpcPerm) -> bool {
+		let euid = self.euid as u32;
+		self.has_cap(CAP_SYS_ADMIN) || euid == perm.uid || euid == perm.cuid
+	}
+}
+
+/// Returns the current time in seconds, used to timestamp operations on IPC objects.
+fn now() -> Timestamp {
+	clock::current_time(CLOCK_REALTIME, TimestampScale::Second).unwrap_or(0)
+}
+
+/// An IPC object.
+pub trait IpcObject {
+	/// Returns the ownership and permissions of the object.
+	fn perm(&self) -> &IpcPerm;
+}
+
+/// A namespace of IPC objects of the same kind.
+#[derive(Debug)]
+pub struct Namespace<T: IpcObject> {
+	/// The objects, by ID.
+	objs: BTreeMap<c_int, Arc<Mutex<T>>>,
+	/// The ID of the next object to be created.
+	next_id: c_int,
+}
+
+impl<T: IpcObject> Default for Namespace<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: IpcObject> Namespace<T> {
+	/// Creates a new empty namespace.
+	pub const fn new() -> Self {
+		Self {
+			objs: BTreeMap::new(),
+			next_id: 0,
+		}
+	}
+
+	/// Returns the object with the given ID.
+	///
+	/// If the object does not exist, the function returns [`errno::EINVAL`].
+	pub fn get(&self, id: c_int) -> EResult<Arc<Mutex<T>>> {
+		self.objs.get(&id).cloned().ok_or_else(|| errno!(EINVAL))
+	}
+
+	/// Returns the ID of the object with the given key, creating it if necessary, as `*get`
+	/// system calls do.
+	///
+	/// Arguments:
+	/// - `key` is the key of the object.
+	/// - `flags` is the set of `IPC_*` flags with the permissions of the object to create.
+	/// - `ap` is the access profile of the agent.
+	/// - `check` validates the arguments against an existing object.
+	/// - `create` creates the object with the given permissions.
+	pub fn get_or_create<C, F>(
+		&mut self,
+		key: KeyT,
+		flags: c_int,
+		ap: &AccessProfile,
+		check: C,
+		create: F,
+	) -> EResult<c_int>
+	where
+		C: FnOnce(&T) -> EResult<()>,
+		F: FnOnce(IpcPerm) -> EResult<T>,
+	{
+		if key != IPC_PRIVATE {
+			let existing = self
+				.objs
+				.iter()
+				.find(|(_, obj)| obj.lock().perm().key == key);
+			if let Some((id, obj)) = existing {
+				if flags & IPC_CREAT != 0 && flags & IPC_EXCL != 0 {
+					return Err(errno!(EEXIST));
+				}
+				let obj = obj.lock();
+				let access = (flags & 0o777) as u16;
+				let access = (access | access >> 3 | access >> 6) & 0o7;
+				if !ap.can_access_ipc(obj.perm(), access) {
+					return Err(errno!(EACCES));
+				}
+				check(&obj)?;
+				return Ok(*id);
+			}
+			if flags & IPC_CREAT == 0 {
+				return Err(errno!(ENOENT));
+			}
+		}
+		if self.objs.len() >= IPC_MNI {
+			return Err(errno!(ENOSPC));
+		}
+		// Find a free ID
+		while self.objs.contains_key(&self.next_id) {
+			self.next_id = self.next_id.checked_add(1).unwrap_or(0);
+		}
+		let id = self.next_id;
+		self.next_id = self.next_id.checked_add(1).unwrap_or(0);
+		let obj = create(IpcPerm::new(key, flags, ap))?;
+		self.objs.insert(id, Arc::new(Mutex::new(obj))?)?;
+		Ok(id)
+	}
+
+	/// Removes the object with the given ID, returning it.
+	pub fn remove(&mut self, id: c_int) -> Option<Arc<Mutex<T>>> {
+		self.objs.remove(&id)
+	}
+
+	/// Returns an iterator over the objects of the namespace.
+	pub fn iter(&self) -> impl Iterator<Item = (&c_int, &Arc<Mutex<T>>)> {
+		self.objs.iter()
+	}
+}
+
+/// An IPC namespace, holding a set of IPC objects isolated from other namespaces.
+#[derive(Debug)]
+pub struct IpcNamespace {
+	/// The ID of the namespace.
+	id: u32,
+	/// The shared memory segments.
+	pub shm: Mutex<Namespace<shm::Shm>>,
+	/// The semaphore sets.
+	pub sem: Mutex<Namespace<sem::SemSet>>,
+	/// The System V message queues.
+	pub msg: Mutex<Namespace<msg::MsgQueue>>,
+	/// The POSIX message queues, by name.
+	pub mqueue: Mutex<BTreeMap<String, Arc<mqueue::MessageQueue>>>,
+}
+
+impl Default for IpcNamespace {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl IpcNamespace {
+	/// Creates a new empty namespace.
+	pub fn new() -> Self {
+		Self {
+			id: namespace::alloc_id(),
+			shm: Mutex::new(Namespace::new()),
+			sem: Mutex::new(Namespace::new()),
+			msg: Mutex::new(Namespace::new()),
+			mqueue: Mutex::new(BTreeMap::new()),
+		}
+	}
+
+	/// Returns the ID of the namespace.
+	pub fn get_id(&self) -> u32 {
+		self.id
+	}
+}
+
+/// Splits the given timestamp into its lower and higher 32 bits, as stored in the status
+/// structures of IPC objects.
+fn split_time(ts: Timestamp) -> (u32, u32) {
+	(ts as u32, (ts >> 32) as u32)
+}
+
+/// Makes the current process wait on `queue` until `f` returns `Some`.
+///
+/// `timeout` is the maximum duration to wait for, in nanoseconds. If elapsed, the function
+/// returns [`errno::EAGAIN`].
+///
+/// System V IPC operations are never restarted after being interrupted by a signal, regardless of
+/// `SA_RESTART`. Thus, the function returns [`errno::EINTR`] in this case.
+pub fn wait_until<F: FnMut() -> EResult<Option<T>>, T>(
+	queue: &WaitQueue,
+	timeout: Option<Timestamp>,
+	mut f: F,
+) -> EResult<T> {
+	let Some(timeout) = timeout else {
+		return queue.wait_until(|| f().transpose()).map_err(|e| {
+			if e.as_int() == errno::ERESTARTSYS {
+				errno!(EINTR)
+			} else {
+				e
+			}
+		})?;
+	};
+	let start = clock::current_time(CLOCK_MONOTONIC, TimestampScale::Nanosecond)?;
+	loop {
+		if let Some(val) = f()? {
+			return Ok(val);
+		}
+		let now = clock::current_time(CLOCK_MONOTONIC, TimestampScale::Nanosecond)?;
+		if now >= start + timeout {
+			return Err(errno!(EAGAIN));
+		}
+		if Process::current().lock().next_signal(true).is_some() {
+			return Err(errno!(EINTR));
+		}
+		scheduler::end_tick();
+	}
+}
+
+/// Converts a `*ctl` command into the corresponding command without version flag.
+pub fn ctl_cmd(cmd: c_int) -> c_int {
+	cmd & !IPC_64
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn ipc_access() {
+		let owner = AccessProfile::new(1000, 1000);
+		let group = AccessProfile::new(1001, 1000);
+		let other = AccessProfile::new(1002, 1002);
+		let perm = IpcPerm::new(42, 0o640, &owner);
+		assert!(owner.can_access_ipc(&perm, IPC_READ | IPC_WRITE));
+		assert!(group.can_access_ipc(&perm, IPC_READ));
+		assert!(!group.can_access_ipc(&perm, IPC_WRITE));
+		assert!(!other.can_access_ipc(&perm, IPC_READ));
+		assert!(owner.is_ipc_owner(&perm));
+		assert!(!group.is_ipc_owner(&perm));
+		assert!(AccessProfile::KERNEL.can_access_ipc(&perm, IPC_WRITE));
+	}
+}
@@ -0,0 +1,699 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! POSIX message queues.
+//!
+//! Queues are identified by a name and are visible through the `mqueue` filesystem. Messages are
+//! received in decreasing order of priority, and in order of arrival for a same priority.
+//!
+//! The memory used by queues is accounted to the user who created them, and is limited by
+//! [`RLIMIT_MSGQUEUE`](crate::process::rlimit::RLIMIT_MSGQUEUE).
+
+use super::{now, IpcNamespace};
+use crate::{
+	file,
+	file::{
+		fs::StatSet,
+		perm::{AccessProfile, Gid, Uid, CAP_FOWNER, CAP_SYS_RESOURCE},
+		wait_queue::WaitQueue,
+		File, FileOps, FileType, Mode, Stat, O_NONBLOCK,
+	},
+	format_content,
+	process::{
+		pid::Pid,
+		scheduler,
+		signal::{SigInfo, SigVal, Signal},
+		Process,
+	},
+	syscall::{
+		ioctl,
+		poll::{POLLIN, POLLOUT, POLLRDNORM, POLLWRNORM},
+	},
+	time::{
+		clock,
+		clock::CLOCK_REALTIME,
+		unit::{Timestamp, TimestampScale},
+	},
+};
+use core::{
+	ffi::{c_long, c_void},
+	intrinsics::unlikely,
+	mem::size_of,
+};
+use utils::{
+	collections::{btreemap::BTreeMap, string::String, vec::Vec},
+	errno,
+	errno::EResult,
+	lock::Mutex,
+	ptr::arc::Arc,
+	TryClone,
+};
+
+/// The maximum priority of a message, exclusive.
+pub const MQ_PRIO_MAX: u32 = 32768;
+/// The default limit on the amount of memory used by the message queues of a user, in bytes.
+pub const MQ_BYTES_MAX: u64 = 819200;
+
+/// The maximum length of a queue's name.
+const NAME_MAX: usize = 255;
+/// The default maximum number of messages in a queue.
+const DFLT_MAXMSG: c_long = 10;
+/// The default maximum size of a message in bytes.
+const DFLT_MSGSIZE: c_long = 8192;
+/// The maximum number of messages in a queue, for a privileged process.
+const HARD_MAXMSG: c_long = 65536;
+/// The maximum size of a message in bytes, for a privileged process.
+const HARD_MSGSIZE: c_long = 16 * 1024 * 1024;
+
+/// The attributes of a message queue, as exchanged with userspace.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MqAttr {
+	/// The flags of the open queue description. Only `O_NONBLOCK` is relevant.
+	pub mq_flags: c_long,
+	/// The maximum number of messages in the queue.
+	pub mq_maxmsg: c_long,
+	/// The maximum size of a message in bytes.
+	pub mq_msgsize: c_long,
+	/// The number of messages currently in the queue.
+	pub mq_curmsgs: c_long,
+	/// Reserved.
+	__reserved: [c_long; 4],
+}
+
+/// A message in a queue.
+#[derive(Debug)]
+struct Message {
+	/// The priority of the message.
+	prio: u32,
+	/// The content of the message.
+	data: Vec<u8>,
+}
+
+/// A registration for notification of message arrival.
+#[derive(Clone, Copy, Debug)]
+struct Notify {
+	/// The process to notify.
+	pid: Pid,
+	/// The signal to send along with its value. If `None`, no signal is sent.
+	sig: Option<(Signal, SigVal)>,
+}
+
+/// The mutable state of a message queue.
+#[derive(Debug)]
+struct QueueInner {
+	/// The messages, sorted by decreasing priority.
+	messages: Vec<Message>,
+	/// The total size of the messages in bytes.
+	bytes: usize,
+
+	/// The permissions of the queue.
+	mode: Mode,
+	/// The owner's user ID.
+	uid: Uid,
+	/// The owner's group ID.
+	gid: Gid,
+	/// Timestamp of the last modification of the metadata.
+	ctime: Timestamp,
+	/// Timestamp of the last message sent.
+	mtime: Timestamp,
+	/// Timestamp of the last message received.
+	atime: Timestamp,
+
+	/// The current notification registration.
+	notify: Option<Notify>,
+	/// The number of processes blocked on receiving a message.
+	receivers: usize,
+}
+
+/// The amount of memory used by message queues, per user.
+static USAGE: Mutex<BTreeMap<Uid, u64>> = Mutex::new(BTreeMap::new());
+
+/// A POSIX message queue.
+#[derive(Debug)]
+pub struct MessageQueue {
+	/// The maximum number of messages in the queue.
+	maxmsg: usize,
+	/// The maximum size of a message in bytes.
+	msgsize: usize,
+	/// The state of the queue.
+	inner: Mutex<QueueInner>,
+	/// The queue of processes waiting to send or receive.
+	queue: WaitQueue,
+
+	/// The user to whom the queue's memory is accounted.
+	charged_uid: Uid,
+	/// The amount of memory accounted, in bytes.
+	charged: u64,
+}
+
+impl MessageQueue {
+	/// Creates a queue.
+	///
+	/// Arguments:
+	/// - `mode` is the permissions of the queue.
+	/// - `attr` is the attributes of the queue. If `None`, default values are used.
+	/// - `ap` is the access profile of the creator.
+	/// - `limit` is the creator's limit on memory used by message queues.
+	///
+	/// If the limit would be exceeded, the function returns [`errno::EMFILE`].
+	pub fn new(
+		mode: Mode,
+		attr: Option<&MqAttr>,
+		ap: &AccessProfile,
+		limit: u64,
+	) -> EResult<Self> {
+		let (maxmsg, msgsize) = match attr {
+			Some(attr) => {
+				if attr.mq_maxmsg <= 0 || attr.mq_msgsize <= 0 {
+					return Err(errno!(EINVAL));
+				}
+				let (max_maxmsg, max_msgsize) = if ap.has_cap(CAP_SYS_RESOURCE) {
+					(HARD_MAXMSG, HARD_MSGSIZE)
+				} else {
+					(DFLT_MAXMSG, DFLT_MSGSIZE)
+				};
+				if attr.mq_maxmsg > max_maxmsg || attr.mq_msgsize > max_msgsize {
+					return Err(errno!(EINVAL));
+				}
+				(attr.mq_maxmsg as usize, attr.mq_msgsize as usize)
+			}
+			None => (DFLT_MAXMSG as usize, DFLT_MSGSIZE as usize),
+		};
+		// Account the memory to the user. The size is computed on 64 bits since it may not fit in
+		// a `usize`
+		let charged = (msgsize as u64)
+			.checked_add(size_of::<Message>() as u64)
+			.and_then(|size| size.checked_mul(maxmsg as u64))
+			.ok_or_else(|| errno!(EMFILE))?;
+		{
+			let mut usage = USAGE.lock();
+			let used = usage.get(&ap.uid).copied().unwrap_or(0);
+			let total = used
+				.checked_add(charged)
+				.filter(|total| *total <= limit)
+				.ok_or_else(|| errno!(EMFILE))?;
+			usage.insert(ap.uid, total)?;
+		}
+		let ts = now();
+		Ok(Self {
+			maxmsg,
+			msgsize,
+			inner: Mutex::new(QueueInner {
+				messages: Vec::new(),
+				bytes: 0,
+
+				mode: mode & 0o7777,
+				uid: ap.euid,
+				gid: ap.egid,
+				ctime: ts,
+				mtime: ts,
+				atime: ts,
+
+				notify: None,
+				receivers: 0,
+			}),
+			queue: WaitQueue::new(),
+
+			charged_uid: ap.uid,
+			charged,
+		})
+	}
+
+	/// Returns the maximum size of a message in bytes.
+	pub fn get_msgsize(&self) -> usize {
+		self.msgsize
+	}
+
+	/// Returns the attributes of the queue.
+	///
+	/// `flags` is the flags of the open queue description.
+	pub fn get_attr(&self, flags: i32) -> MqAttr {
+		MqAttr {
+			mq_flags: (flags & O_NONBLOCK) as _,
+			mq_maxmsg: self.maxmsg as _,
+			mq_msgsize: self.msgsize as _,
+			mq_curmsgs: self.inner.lock().messages.len() as _,
+			__reserved: [0; 4],
+		}
+	}
+
+	/// Returns the status of the queue, as a file.
+	pub fn stat(&self) -> Stat {
+		let inner = self.inner.lock();
+		Stat {
+			mode: FileType::Regular.to_mode() | inner.mode,
+			nlink: 1,
+			uid: inner.uid,
+			gid: inner.gid,
+			ctime: inner.ctime,
+			mtime: inner.mtime,
+			atime: inner.atime,
+			..Default::default()
+		}
+	}
+
+	/// Sets the status of the queue.
+	pub fn set_stat(&self, set: &StatSet) {
+		let mut inner = self.inner.lock();
+		if let Some(mode) = set.mode {
+			inner.mode = mode & 0o7777;
+		}
+		if let Some(uid) = set.uid {
+			inner.uid = uid;
+		}
+		if let Some(gid) = set.gid {
+			inner.gid = gid;
+		}
+		if let Some(ctime) = set.ctime {
+			inner.ctime = ctime;
+		}
+		if let Some(mtime) = set.mtime {
+			inner.mtime = mtime;
+		}
+		if let Some(atime) = set.atime {
+			inner.atime = atime;
+		}
+	}
+
+	/// Writes the textual status of the queue into `buf`, starting at offset `off`.
+	///
+	/// The function returns the number of bytes written.
+	pub fn read_status(&self, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let (bytes, notify) = {
+			let inner = self.inner.lock();
+			(inner.bytes, inner.notify)
+		};
+		let (pid, method, signo) = match notify {
+			Some(Notify {
+				pid,
+				sig: Some((sig, _)),
+			}) => (pid, 0, sig.get_id()),
+			Some(Notify {
+				pid,
+				sig: None,
+			}) => (pid, 1, 0),
+			None => (0, 0, 0),
+		};
+		format_content!(
+			off,
+			buf,
+			"QSIZE:{bytes:<10} NOTIFY:{method:<5} SIGNO:{signo:<5} NOTIFY_PID:{pid:<6}\n"
+		)
+	}
+
+	/// Waits until `f` returns a value.
+	///
+	/// `deadline` is the timestamp on `CLOCK_REALTIME`, in nanoseconds, after which the function
+	/// fails with [`errno::ETIMEDOUT`]. If `None`, the function may wait indefinitely.
+	fn wait_until<F: FnMut(&mut QueueInner) -> EResult<Option<T>>, T>(
+		&self,
+		deadline: Option<Timestamp>,
+		mut f: F,
+	) -> EResult<T> {
+		let Some(deadline) = deadline else {
+			return self
+				.queue
+				.wait_until(|| f(&mut self.inner.lock()).transpose())?;
+		};
+		loop {
+			if let Some(val) = f(&mut self.inner.lock())? {
+				return Ok(val);
+			}
+			let now = clock::current_time(CLOCK_REALTIME, TimestampScale::Nanosecond)?;
+			if now >= deadline {
+				return Err(errno!(ETIMEDOUT));
+			}
+			if Process::current().lock().next_signal(true).is_some() {
+				return Err(errno!(ERESTARTSYS));
+			}
+			scheduler::end_tick();
+		}
+	}
+
+	/// Sends the message `data` with the priority `prio`.
+	///
+	/// Arguments:
+	/// - `nonblock` tells whether the function fails with [`errno::EAGAIN`] instead of waiting
+	///   when the queue is full.
+	/// - `deadline` is the absolute timeout on `CLOCK_REALTIME`, in nanoseconds.
+	/// - `pid` and `uid` identify the sender.
+	///
+	/// If the message arrives on an empty queue while no process is blocked on receiving, the
+	/// registered process is notified and the registration is removed.
+	pub fn send(
+		&self,
+		data: Vec<u8>,
+		prio: u32,
+		nonblock: bool,
+		deadline: Option<Timestamp>,
+		pid: Pid,
+		uid: Uid,
+	) -> EResult<()> {
+		if unlikely(data.len() > self.msgsize) {
+			return Err(errno!(EMSGSIZE));
+		}
+		if unlikely(prio >= MQ_PRIO_MAX) {
+			return Err(errno!(EINVAL));
+		}
+		let mut msg = Some(Message {
+			prio,
+			data,
+		});
+		let notify = self.wait_until(deadline, |inner| {
+			if inner.messages.len() >= self.maxmsg {
+				return if nonblock {
+					Err(errno!(EAGAIN))
+				} else {
+					Ok(None)
+				};
+			}
+			// Unwrap cannot fail since the message is taken only once
+			let msg = msg.take().unwrap();
+			let notify = if inner.messages.is_empty() && inner.receivers == 0 {
+				inner.notify.take()
+			} else {
+				None
+			};
+			// Insert after the messages with the same priority or higher
+			let i = inner.messages.partition_point(|m| m.prio >= msg.prio);
+			inner.bytes += msg.data.len();
+			inner.messages.insert(i, msg)?;
+			inner.mtime = now();
+			Ok(Some(notify))
+		})?;
+		self.queue.wake_all();
+		if let Some(Notify {
+			pid: target,
+			sig: Some((sig, value)),
+		}) = notify
+		{
+			if let Some(proc) = Process::get_by_pid(target) {
+				proc.lock()
+					.kill_info(sig, SigInfo::mesgq(sig, pid, uid, value));
+			}
+		}
+		Ok(())
+	}
+
+	/// Receives the oldest message with the highest priority.
+	///
+	/// Arguments:
+	/// - `len` is the size of the receiving buffer. If smaller than the maximum size of a message,
+	///   the function fails with [`errno::EMSGSIZE`].
+	/// - `nonblock` tells whether the function fails with [`errno::EAGAIN`] instead of waiting
+	///   when the queue is empty.
+	/// - `deadline` is the absolute timeout on `CLOCK_REALTIME`, in nanoseconds.
+	///
+	/// The function returns the content of the message along with its priority.
+	pub fn receive(
+		&self,
+		len: usize,
+		nonblock: bool,
+		deadline: Option<Timestamp>,
+	) -> EResult<(Vec<u8>, u32)> {
+		if unlikely(len < self.msgsize) {
+			return Err(errno!(EMSGSIZE));
+		}
+		// Tells whether the process is counted as a blocked receiver
+		let mut waiting = false;
+		let res = self.wait_until(deadline, |inner| {
+			if inner.messages.is_empty() {
+				if nonblock {
+					return Err(errno!(EAGAIN));
+				}
+				if !waiting {
+					inner.receivers += 1;
+					waiting = true;
+				}
+				return Ok(None);
+			}
+			let msg = inner.messages.remove(0);
+			inner.bytes -= msg.data.len();
+			inner.atime = now();
+			Ok(Some((msg.data, msg.prio)))
+		});
+		if waiting {
+			self.inner.lock().receivers -= 1;
+		}
+		let res = res?;
+		self.queue.wake_all();
+		Ok(res)
+	}
+
+	/// Registers or unregisters the process `pid` for notification of message arrival.
+	///
+	/// If `sig` is `None`, the process is unregistered. Else, it contains `None` if no signal has
+	/// to be sent, or the signal to send along with its value.
+	///
+	/// If another process is already registered, the function returns [`errno::EBUSY`].
+	pub fn notify(&self, pid: Pid, sig: Option<Option<(Signal, SigVal)>>) -> EResult<()> {
+		let mut inner = self.inner.lock();
+		match sig {
+			Some(sig) => {
+				if inner.notify.is_some() {
+					return Err(errno!(EBUSY));
+				}
+				inner.notify = Some(Notify {
+					pid,
+					sig,
+				});
+			}
+			None => {
+				if inner.notify.is_some_and(|n| n.pid == pid) {
+					inner.notify = None;
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+impl FileOps for MessageQueue {
+	fn get_stat(&self, _file: &File) -> EResult<Stat> {
+		Ok(self.stat())
+	}
+
+	fn acquire(&self, _file: &File) {}
+
+	fn release(&self, _file: &File) {
+		// Closing the queue removes the registration of the process, if any
+		if let Some(proc) = Process::current_opt() {
+			let pid = proc.lock().get_pid();
+			let _ = self.notify(pid, None);
+		}
+	}
+
+	fn poll(&self, _file: &File, mask: u32) -> EResult<u32> {
+		let len = self.inner.lock().messages.len();
+		let mut res = 0;
+		if len > 0 {
+			res |= POLLIN | POLLRDNORM;
+		}
+		if len < self.maxmsg {
+			res |= POLLOUT | POLLWRNORM;
+		}
+		Ok(res & mask)
+	}
+
+	fn wait_queues(
+		&self,
+		_file: &File,
+		f: &mut dyn FnMut(&WaitQueue) -> EResult<()>,
+	) -> EResult<()> {
+		f(&self.queue)
+	}
+
+	fn ioctl(&self, _file: &File, _request: ioctl::Request, _argp: *const c_void) -> EResult<u32> {
+		Err(errno!(ENOTTY))
+	}
+
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		self.read_status(off, buf)
+	}
+
+	fn write(&self, _file: &File, _off: u64, _buf: &[u8]) -> EResult<usize> {
+		Err(errno!(EINVAL))
+	}
+}
+
+impl Drop for MessageQueue {
+	fn drop(&mut self) {
+		let mut usage = USAGE.lock();
+		if let Some(used) = usage.get_mut(&self.charged_uid) {
+			*used = used.saturating_sub(self.charged);
+			if *used == 0 {
+				usage.remove(&self.charged_uid);
+			}
+		}
+	}
+}
+
+/// Checks the given queue name is valid.
+fn check_name(name: &[u8]) -> EResult<()> {
+	if name.is_empty() {
+		return Err(errno!(ENOENT));
+	}
+	if name.len() > NAME_MAX {
+		return Err(errno!(ENAMETOOLONG));
+	}
+	if name.contains(&b'/') {
+		return Err(errno!(EACCES));
+	}
+	Ok(())
+}
+
+/// Opens the queue with the given `name`, creating it if necessary.
+///
+/// Arguments:
+/// - `ns` is the IPC namespace in which the queue is looked for.
+/// - `flags` is the set of open flags. `O_CREAT` and `O_EXCL` have the same meaning as for regular
+///   files.
+/// - `mode` is the permissions of the queue if created.
+/// - `attr` is the attributes of the queue if created. If `None`, default values are used.
+/// - `ap` is the access profile of the calling process.
+/// - `limit` is the creator's limit on memory used by message queues.
+pub fn open(
+	ns: &IpcNamespace,
+	name: &[u8],
+	flags: i32,
+	mode: Mode,
+	attr: Option<&MqAttr>,
+	ap: &AccessProfile,
+	limit: u64,
+) -> EResult<Arc<MessageQueue>> {
+	check_name(name)?;
+	let (read, write) = match flags & 0b11 {
+		file::O_RDONLY => (true, false),
+		file::O_WRONLY => (false, true),
+		file::O_RDWR => (true, true),
+		_ => return Err(errno!(EINVAL)),
+	};
+	let mut queues = ns.mqueue.lock();
+	if let Some(queue) = queues.get(name) {
+		if flags & (file::O_CREAT | file::O_EXCL) == file::O_CREAT | file::O_EXCL {
+			return Err(errno!(EEXIST));
+		}
+		let stat = queue.stat();
+		if (read && !ap.can_read_file(&stat)) || (write && !ap.can_write_file(&stat)) {
+			return Err(errno!(EACCES));
+		}
+		return Ok(queue.clone());
+	}
+	if flags & file::O_CREAT == 0 {
+		return Err(errno!(ENOENT));
+	}
+	let queue = Arc::new(MessageQueue::new(mode, attr, ap, limit)?)?;
+	queues.insert(String::try_from(name)?, queue.clone())?;
+	Ok(queue)
+}
+
+/// Returns the queue of the namespace `ns` with the given `name`.
+pub fn get(ns: &IpcNamespace, name: &[u8]) -> Option<Arc<MessageQueue>> {
+	ns.mqueue.lock().get(name).cloned()
+}
+
+/// Returns the name of the queue of the namespace `ns` at index `i`, in alphabetical order.
+pub fn name_at(ns: &IpcNamespace, i: usize) -> EResult<Option<String>> {
+	let queues = ns.mqueue.lock();
+	let name = queues.iter().nth(i).map(|(name, _)| name.try_clone());
+	Ok(name.transpose()?)
+}
+
+/// Removes the queue of the namespace `ns` with the given `name`.
+///
+/// The queue is destroyed once all descriptors referring to it are closed.
+///
+/// If the queue does not exist, the function returns [`errno::ENOENT`].
+pub fn remove(ns: &IpcNamespace, name: &[u8]) -> EResult<()> {
+	ns.mqueue
+		.lock()
+		.remove(name)
+		.map(|_| ())
+		.ok_or_else(|| errno!(ENOENT))
+}
+
+/// Removes the queue of the namespace `ns` with the given `name`, checking the calling process is
+/// allowed to.
+///
+/// Since queues live in a sticky directory owned by the superuser, only the owner of the queue
+/// or a privileged process may remove it.
+pub fn unlink(ns: &IpcNamespace, name: &[u8], ap: &AccessProfile) -> EResult<()> {
+	check_name(name)?;
+	let queue = get(ns, name).ok_or_else(|| errno!(ENOENT))?;
+	let root = Stat {
+		mode: FileType::Directory.to_mode() | 0o1777,
+		..Default::default()
+	};
+	let stat = queue.stat();
+	if !ap.can_write_directory(&root) || (ap.euid != stat.uid && !ap.has_cap(CAP_FOWNER)) {
+		return Err(errno!(EACCES));
+	}
+	remove(ns, name)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::file::perm::{ROOT_GID, ROOT_UID};
+
+	#[test_case]
+	fn mqueue_priority() {
+		let ap = AccessProfile::new(1000, 1000);
+		let queue = MessageQueue::new(0o600, None, &ap, u64::MAX).unwrap();
+		let send = |data: &[u8], prio| {
+			queue.send(Vec::try_from(data).unwrap(), prio, true, None, 1, 1000)
+		};
+		send(b"a", 1).unwrap();
+		send(b"b", 5).unwrap();
+		send(b"c", 1).unwrap();
+		let recv = || queue.receive(DFLT_MSGSIZE as _, true, None);
+		assert_eq!(
+			recv().map(|(data, prio)| (data[0], prio)).unwrap(),
+			(b'b', 5)
+		);
+		assert_eq!(
+			recv().map(|(data, prio)| (data[0], prio)).unwrap(),
+			(b'a', 1)
+		);
+		assert_eq!(
+			recv().map(|(data, prio)| (data[0], prio)).unwrap(),
+			(b'c', 1)
+		);
+		assert_eq!(recv().unwrap_err().as_int(), errno::EAGAIN);
+		assert_eq!(
+			queue.receive(1, true, None).unwrap_err().as_int(),
+			errno::EMSGSIZE
+		);
+	}
+
+	#[test_case]
+	fn mqueue_charge_overflow() {
+		// The size of the largest queue does not fit in 32 bits
+		let ap = AccessProfile::new(ROOT_UID, ROOT_GID);
+		let attr = MqAttr {
+			mq_flags: 0,
+			mq_maxmsg: HARD_MAXMSG,
+			mq_msgsize: HARD_MSGSIZE,
+			mq_curmsgs: 0,
+			__reserved: [0; 4],
+		};
+		let res = MessageQueue::new(0o600, Some(&attr), &ap, 819200);
+		assert_eq!(res.err().map(|e| e.as_int()), Some(errno::EMFILE));
+	}
+}
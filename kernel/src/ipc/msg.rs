@@ -0,0 +1,297 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! System V message queues.
+
+use super::{now, split_time, IpcNamespace, IpcObject, IpcPerm};
+use crate::{
+	file::{
+		perm::{AccessProfile, CAP_SYS_RESOURCE},
+		wait_queue::WaitQueue,
+	},
+	process::pid::Pid,
+	time::unit::Timestamp,
+};
+use core::ffi::{c_int, c_long};
+use utils::{collections::vec::Vec, errno, errno::EResult, ptr::arc::Arc};
+
+/// `msgrcv` flag: Truncate messages that are too long instead of failing.
+pub const MSG_NOERROR: c_int = 0o10000;
+/// `msgrcv` flag: Receive the first message whose type is different from the given one.
+pub const MSG_EXCEPT: c_int = 0o20000;
+
+/// The maximum size of a message in bytes.
+pub const MSGMAX: usize = 8192;
+/// The default maximum size of a queue in bytes.
+const MSGMNB: usize = 16384;
+
+/// The status of a message queue, as returned to userspace.
+///
+/// The layout of this structure matches the one of `msqid64_ds`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MsqidDs {
+	/// Ownership and permissions.
+	pub msg_perm: IpcPerm,
+	/// Last send time.
+	pub msg_stime: u32,
+	/// Last send time, higher bits.
+	pub msg_stime_high: u32,
+	/// Last receive time.
+	pub msg_rtime: u32,
+	/// Last receive time, higher bits.
+	pub msg_rtime_high: u32,
+	/// Last change time.
+	pub msg_ctime: u32,
+	/// Last change time, higher bits.
+	pub msg_ctime_high: u32,
+	/// The number of bytes in the queue.
+	pub msg_cbytes: u32,
+	/// The number of messages in the queue.
+	pub msg_qnum: u32,
+	/// The maximum number of bytes in the queue.
+	pub msg_qbytes: u32,
+	/// The PID of the last process to send a message.
+	pub msg_lspid: c_int,
+	/// The PID of the last process to receive a message.
+	pub msg_lrpid: c_int,
+	/// Unused.
+	_unused4: u32,
+	/// Unused.
+	_unused5: u32,
+}
+
+/// A message.
+#[derive(Debug)]
+pub struct Message {
+	/// The type of the message.
+	pub mtype: c_long,
+	/// The content of the message.
+	pub data: Vec<u8>,
+}
+
+/// A message queue.
+#[derive(Debug)]
+pub struct MsgQueue {
+	/// Ownership and permissions.
+	perm: IpcPerm,
+	/// The messages, in the order they have been sent.
+	msgs: Vec<Message>,
+	/// The number of bytes in the queue.
+	cbytes: usize,
+	/// The maximum number of bytes in the queue.
+	qbytes: usize,
+
+	/// Last send time.
+	stime: Timestamp,
+	/// Last receive time.
+	rtime: Timestamp,
+	/// Last change time.
+	ctime: Timestamp,
+	/// The PID of the last process to send a message.
+	lspid: Pid,
+	/// The PID of the last process to receive a message.
+	lrpid: Pid,
+
+	/// The queue of processes waiting for the message queue to change.
+	queue: Arc<WaitQueue>,
+	/// Tells whether the message queue has been removed.
+	removed: bool,
+}
+
+impl MsgQueue {
+	/// Creates an empty message queue.
+	fn new(perm: IpcPerm) -> EResult<Self> {
+		Ok(Self {
+			perm,
+			msgs: Vec::new(),
+			cbytes: 0,
+			qbytes: MSGMNB,
+
+			stime: 0,
+			rtime: 0,
+			ctime: now(),
+			lspid: 0,
+			lrpid: 0,
+
+			queue: Arc::new(WaitQueue::new())?,
+			removed: false,
+		})
+	}
+
+	/// Returns the queue of processes waiting for the message queue to change.
+	pub fn get_queue(&self) -> Arc<WaitQueue> {
+		self.queue.clone()
+	}
+
+	/// Tries to append the message `msg`, sent by the process `pid`.
+	///
+	/// If the queue is full, the function gives the message back.
+	pub fn try_send(&mut self, msg: Message, pid: Pid) -> EResult<Option<Message>> {
+		if self.removed {
+			return Err(errno!(EIDRM));
+		}
+		let len = msg.data.len();
+		if self.cbytes + len > self.qbytes || self.msgs.len() >= self.qbytes {
+			return Ok(Some(msg));
+		}
+		self.msgs.push(msg)?;
+		self.cbytes += len;
+		self.stime = now();
+		self.lspid = pid;
+		self.queue.wake_all();
+		Ok(None)
+	}
+
+	/// Tries to remove a message from the queue, on behalf of the process `pid`.
+	///
+	/// Arguments:
+	/// - `max` is the maximum size of the message.
+	/// - `msgtyp` selects the message: if zero, the first message is taken. If positive, the first
+	///   message of that type. If negative, the first message with the lowest type lower than or
+	///   equal to the absolute value.
+	/// - `flags` is the set of `MSG_*` flags.
+	///
+	/// If no message matches, the function returns `None`.
+	pub fn try_receive(
+		&mut self,
+		max: usize,
+		msgtyp: c_long,
+		flags: c_int,
+		pid: Pid,
+	) -> EResult<Option<Message>> {
+		if self.removed {
+			return Err(errno!(EIDRM));
+		}
+		let except = flags & MSG_EXCEPT != 0;
+		let i = match msgtyp {
+			0 => (!self.msgs.is_empty()).then_some(0),
+			t if t > 0 => self.msgs.iter().position(|m| (m.mtype == t) != except),
+			t => self
+				.msgs
+				.iter()
+				.enumerate()
+				.filter(|(_, m)| m.mtype <= -t)
+				.min_by_key(|(_, m)| m.mtype)
+				.map(|(i, _)| i),
+		};
+		let Some(i) = i else {
+			return Ok(None);
+		};
+		if self.msgs[i].data.len() > max && flags & MSG_NOERROR == 0 {
+			return Err(errno!(E2BIG));
+		}
+		let mut msg = self.msgs.remove(i);
+		self.cbytes -= msg.data.len();
+		msg.data.truncate(max);
+		self.rtime = now();
+		self.lrpid = pid;
+		self.queue.wake_all();
+		Ok(Some(msg))
+	}
+
+	/// Returns the status of the message queue.
+	pub fn stat(&self) -> MsqidDs {
+		let (msg_stime, msg_stime_high) = split_time(self.stime);
+		let (msg_rtime, msg_rtime_high) = split_time(self.rtime);
+		let (msg_ctime, msg_ctime_high) = split_time(self.ctime);
+		MsqidDs {
+			msg_perm: self.perm,
+			msg_stime,
+			msg_stime_high,
+			msg_rtime,
+			msg_rtime_high,
+			msg_ctime,
+			msg_ctime_high,
+			msg_cbytes: self.cbytes as _,
+			msg_qnum: self.msgs.len() as _,
+			msg_qbytes: self.qbytes as _,
+			msg_lspid: self.lspid as _,
+			msg_lrpid: self.lrpid as _,
+			..Default::default()
+		}
+	}
+
+	/// Updates the queue's ownership, permissions and size from `ds`, as `IPC_SET` does.
+	pub fn set(&mut self, ds: &MsqidDs, ap: &AccessProfile) -> EResult<()> {
+		let qbytes = ds.msg_qbytes as usize;
+		if qbytes > MSGMNB && !ap.has_cap(CAP_SYS_RESOURCE) {
+			return Err(errno!(EPERM));
+		}
+		self.perm.set(&ds.msg_perm, ap)?;
+		self.qbytes = qbytes;
+		self.ctime = now();
+		// Senders may now fit in the queue
+		self.queue.wake_all();
+		Ok(())
+	}
+
+	/// Marks the message queue as removed, waking up processes waiting on it.
+	pub fn remove(&mut self) {
+		self.removed = true;
+		self.queue.wake_all();
+	}
+}
+
+impl IpcObject for MsgQueue {
+	fn perm(&self) -> &IpcPerm {
+		&self.perm
+	}
+}
+
+/// Returns the ID of the message queue with the given key, creating it if necessary.
+///
+/// Arguments:
+/// - `ns` is the IPC namespace in which the queue is looked for.
+/// - `key` is the key of the queue.
+/// - `flags` is the set of `IPC_*` flags with the permissions of the queue to create.
+/// - `ap` is the access profile of the agent.
+pub fn get(ns: &IpcNamespace, key: c_int, flags: c_int, ap: &AccessProfile) -> EResult<c_int> {
+	ns.msg
+		.lock()
+		.get_or_create(key, flags, ap, |_| Ok(()), MsgQueue::new)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn msg(mtype: c_long, data: &[u8]) -> Message {
+		Message {
+			mtype,
+			data: Vec::try_from(data).unwrap(),
+		}
+	}
+
+	#[test_case]
+	fn msg_select() {
+		let mut queue = MsgQueue::new(IpcPerm::default()).unwrap();
+		for (mtype, data) in [(3, b"c"), (1, b"a"), (2, b"b")] {
+			assert!(queue.try_send(msg(mtype, data), 1).unwrap().is_none());
+		}
+		let m = queue.try_receive(16, 2, 0, 1).unwrap().unwrap();
+		assert_eq!(m.mtype, 2);
+		let m = queue.try_receive(16, -3, 0, 1).unwrap().unwrap();
+		assert_eq!(m.mtype, 1);
+		let m = queue.try_receive(16, 3, MSG_EXCEPT, 1).unwrap();
+		assert!(m.is_none());
+		let m = queue.try_receive(16, 0, 0, 1).unwrap().unwrap();
+		assert_eq!(m.data.as_slice(), b"c");
+		assert!(queue.try_receive(16, 0, 0, 1).unwrap().is_none());
+	}
+}
@@ -0,0 +1,451 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! System V semaphore sets.
+//!
+//! A set of operations on a semaphore set is applied atomically: either all of them are
+//! performed, or none is and the caller waits until they can be.
+
+use super::{now, split_time, IpcNamespace, IpcObject, IpcPerm, IPC_NOWAIT};
+use crate::{
+	file::{perm::AccessProfile, wait_queue::WaitQueue},
+	process::pid::Pid,
+	time::unit::Timestamp,
+};
+use core::ffi::c_int;
+use utils::{
+	collections::vec::Vec,
+	errno,
+	errno::{AllocResult, EResult},
+	lock::Mutex,
+	ptr::arc::Arc,
+};
+
+/// Operation flag: Undo the operation when the process exits.
+pub const SEM_UNDO: i16 = 0x1000;
+
+/// `semctl` command: Get the PID of the last process to operate on a semaphore.
+pub const GETPID: c_int = 11;
+/// `semctl` command: Get the value of a semaphore.
+pub const GETVAL: c_int = 12;
+/// `semctl` command: Get the values of all semaphores.
+pub const GETALL: c_int = 13;
+/// `semctl` command: Get the number of processes waiting for a semaphore to increase.
+pub const GETNCNT: c_int = 14;
+/// `semctl` command: Get the number of processes waiting for a semaphore to become zero.
+pub const GETZCNT: c_int = 15;
+/// `semctl` command: Set the value of a semaphore.
+pub const SETVAL: c_int = 16;
+/// `semctl` command: Set the values of all semaphores.
+pub const SETALL: c_int = 17;
+
+/// The maximum number of semaphores in a set.
+const SEMMSL: usize = 32000;
+/// The maximum number of operations per call.
+pub const SEMOPM: usize = 500;
+/// The maximum value of a semaphore.
+const SEMVMX: u16 = 32767;
+
+/// An operation on a semaphore, as given by userspace.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct SemBuf {
+	/// The index of the semaphore in the set.
+	pub sem_num: u16,
+	/// The operation.
+	pub sem_op: i16,
+	/// The operation's flags.
+	pub sem_flg: i16,
+}
+
+/// The status of a semaphore set, as returned to userspace.
+///
+/// The layout of this structure matches the one of `semid64_ds`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SemidDs {
+	/// Ownership and permissions.
+	pub sem_perm: IpcPerm,
+	/// Last operation time.
+	pub sem_otime: u32,
+	/// Last operation time, higher bits.
+	pub sem_otime_high: u32,
+	/// Last change time.
+	pub sem_ctime: u32,
+	/// Last change time, higher bits.
+	pub sem_ctime_high: u32,
+	/// The number of semaphores in the set.
+	pub sem_nsems: u32,
+	/// Unused.
+	_unused3: u32,
+	/// Unused.
+	_unused4: u32,
+}
+
+/// A semaphore.
+#[derive(Clone, Debug, Default)]
+pub struct Sem {
+	/// The value of the semaphore.
+	pub val: u16,
+	/// The PID of the last process to operate on the semaphore.
+	pub pid: Pid,
+	/// The number of processes waiting for the value to increase.
+	pub ncnt: u16,
+	/// The number of processes waiting for the value to become zero.
+	pub zcnt: u16,
+}
+
+/// A semaphore set.
+#[derive(Debug)]
+pub struct SemSet {
+	/// Ownership and permissions.
+	perm: IpcPerm,
+	/// The semaphores.
+	sems: Vec<Sem>,
+
+	/// Last operation time.
+	otime: Timestamp,
+	/// Last change time.
+	ctime: Timestamp,
+
+	/// The queue of processes waiting for the set to change.
+	queue: Arc<WaitQueue>,
+	/// Tells whether the set has been removed.
+	removed: bool,
+}
+
+impl SemSet {
+	/// Creates a set of `nsems` semaphores initialized to zero.
+	fn new(perm: IpcPerm, nsems: usize) -> EResult<Self> {
+		let mut sems = Vec::new();
+		sems.resize(nsems, Sem::default())?;
+		Ok(Self {
+			perm,
+			sems,
+
+			otime: 0,
+			ctime: now(),
+
+			queue: Arc::new(WaitQueue::new())?,
+			removed: false,
+		})
+	}
+
+	/// Returns the queue of processes waiting for the set to change.
+	pub fn get_queue(&self) -> Arc<WaitQueue> {
+		self.queue.clone()
+	}
+
+	/// Tells whether the set has been removed.
+	pub fn is_removed(&self) -> bool {
+		self.removed
+	}
+
+	/// Returns the semaphores of the set.
+	pub fn get_sems(&self) -> &[Sem] {
+		&self.sems
+	}
+
+	/// Returns the semaphore at index `num`.
+	///
+	/// If the semaphore does not exist, the function returns [`errno::EINVAL`].
+	pub fn get_sem(&self, num: usize) -> EResult<&Sem> {
+		self.sems.get(num).ok_or_else(|| errno!(EINVAL))
+	}
+
+	/// Returns a mutable reference to the semaphore at index `num`.
+	///
+	/// If the semaphore does not exist, the function returns [`errno::EINVAL`].
+	pub fn get_sem_mut(&mut self, num: usize) -> EResult<&mut Sem> {
+		self.sems.get_mut(num).ok_or_else(|| errno!(EINVAL))
+	}
+
+	/// Registers a process waiting on the semaphore at index `num`.
+	///
+	/// `zero` tells whether the process waits for the value to become zero. If not, it waits for
+	/// the value to increase.
+	pub fn add_waiter(&mut self, num: usize, zero: bool) {
+		let sem = &mut self.sems[num];
+		if zero {
+			sem.zcnt += 1;
+		} else {
+			sem.ncnt += 1;
+		}
+	}
+
+	/// Unregisters a process registered with [`Self::add_waiter`].
+	pub fn remove_waiter(&mut self, num: usize, zero: bool) {
+		let sem = &mut self.sems[num];
+		if zero {
+			sem.zcnt -= 1;
+		} else {
+			sem.ncnt -= 1;
+		}
+	}
+
+	/// Tries to apply the operations `ops` atomically, on behalf of the process `pid`.
+	///
+	/// If the operations cannot be applied without blocking, the function returns the index of the
+	/// semaphore to wait for, along with a boolean telling whether the wait is for the value to
+	/// become zero.
+	pub fn try_apply(&mut self, ops: &[SemBuf], pid: Pid) -> EResult<Option<(usize, bool)>> {
+		if self.removed {
+			return Err(errno!(EIDRM));
+		}
+		let mut vals = Vec::new();
+		for sem in &self.sems {
+			vals.push(sem.val)?;
+		}
+		for op in ops {
+			let num = op.sem_num as usize;
+			let val = vals.get_mut(num).ok_or_else(|| errno!(EFBIG))?;
+			let block = match op.sem_op {
+				0 => *val != 0,
+				o if o > 0 => {
+					*val = val
+						.checked_add(o as u16)
+						.filter(|v| *v <= SEMVMX)
+						.ok_or_else(|| errno!(ERANGE))?;
+					false
+				}
+				o => match val.checked_sub(o.unsigned_abs()) {
+					Some(v) => {
+						*val = v;
+						false
+					}
+					None => true,
+				},
+			};
+			if block {
+				if op.sem_flg as c_int & IPC_NOWAIT != 0 {
+					return Err(errno!(EAGAIN));
+				}
+				return Ok(Some((num, op.sem_op == 0)));
+			}
+		}
+		// Commit
+		for op in ops {
+			self.sems[op.sem_num as usize].pid = pid;
+		}
+		for (sem, val) in self.sems.iter_mut().zip(vals) {
+			sem.val = val;
+		}
+		self.otime = now();
+		if ops.iter().any(|op| op.sem_op != 0) {
+			self.queue.wake_all();
+		}
+		Ok(None)
+	}
+
+	/// Sets the value of the semaphore at index `num`.
+	pub fn set_val(&mut self, num: usize, val: c_int, pid: Pid) -> EResult<()> {
+		if !(0..=SEMVMX as c_int).contains(&val) {
+			return Err(errno!(ERANGE));
+		}
+		let sem = self.get_sem_mut(num)?;
+		sem.val = val as _;
+		sem.pid = pid;
+		self.ctime = now();
+		self.queue.wake_all();
+		Ok(())
+	}
+
+	/// Sets the values of all semaphores.
+	pub fn set_all(&mut self, vals: &[u16], pid: Pid) -> EResult<()> {
+		if vals.iter().any(|v| *v > SEMVMX) {
+			return Err(errno!(ERANGE));
+		}
+		for (sem, val) in self.sems.iter_mut().zip(vals) {
+			sem.val = *val;
+			sem.pid = pid;
+		}
+		self.ctime = now();
+		self.queue.wake_all();
+		Ok(())
+	}
+
+	/// Returns the status of the set.
+	pub fn stat(&self) -> SemidDs {
+		let (sem_otime, sem_otime_high) = split_time(self.otime);
+		let (sem_ctime, sem_ctime_high) = split_time(self.ctime);
+		SemidDs {
+			sem_perm: self.perm,
+			sem_otime,
+			sem_otime_high,
+			sem_ctime,
+			sem_ctime_high,
+			sem_nsems: self.sems.len() as _,
+			..Default::default()
+		}
+	}
+
+	/// Updates the set's ownership and permissions from `ds`, as `IPC_SET` does.
+	pub fn set(&mut self, ds: &SemidDs, ap: &AccessProfile) -> EResult<()> {
+		self.perm.set(&ds.sem_perm, ap)?;
+		self.ctime = now();
+		Ok(())
+	}
+
+	/// Marks the set as removed, waking up processes waiting on it.
+	pub fn remove(&mut self) {
+		self.removed = true;
+		self.queue.wake_all();
+	}
+}
+
+impl IpcObject for SemSet {
+	fn perm(&self) -> &IpcPerm {
+		&self.perm
+	}
+}
+
+/// Returns the ID of the semaphore set with the given key, creating it if necessary.
+///
+/// Arguments:
+/// - `ns` is the IPC namespace in which the set is looked for.
+/// - `key` is the key of the set.
+/// - `nsems` is the minimum number of semaphores in the set.
+/// - `flags` is the set of `IPC_*` flags with the permissions of the set to create.
+/// - `ap` is the access profile of the agent.
+pub fn get(
+	ns: &IpcNamespace,
+	key: c_int,
+	nsems: c_int,
+	flags: c_int,
+	ap: &AccessProfile,
+) -> EResult<c_int> {
+	let nsems: usize = nsems.try_into().map_err(|_| errno!(EINVAL))?;
+	if nsems > SEMMSL {
+		return Err(errno!(EINVAL));
+	}
+	ns.sem.lock().get_or_create(
+		key,
+		flags,
+		ap,
+		|set| {
+			if nsems > set.sems.len() {
+				return Err(errno!(EINVAL));
+			}
+			Ok(())
+		},
+		|perm| {
+			if nsems == 0 {
+				return Err(errno!(EINVAL));
+			}
+			SemSet::new(perm, nsems)
+		},
+	)
+}
+
+/// An adjustment to apply to a semaphore when a process exits, accumulated by operations made with
+/// [`SEM_UNDO`].
+#[derive(Clone, Debug)]
+pub struct SemUndo {
+	/// The semaphore set.
+	set: Arc<Mutex<SemSet>>,
+	/// The index of the semaphore in the set.
+	num: u16,
+	/// The adjustment.
+	adj: i32,
+}
+
+/// Records the undo adjustments for operations `ops`, applied on `set`, into `undo`.
+pub fn record_undo(
+	undo: &mut Vec<SemUndo>,
+	set: &Arc<Mutex<SemSet>>,
+	ops: &[SemBuf],
+) -> AllocResult<()> {
+	for op in ops {
+		if op.sem_flg & SEM_UNDO == 0 || op.sem_op == 0 {
+			continue;
+		}
+		let entry = undo
+			.iter_mut()
+			.find(|u| u.set.as_ptr() == set.as_ptr() && u.num == op.sem_num);
+		match entry {
+			Some(entry) => entry.adj -= op.sem_op as i32,
+			None => undo.push(SemUndo {
+				set: set.clone(),
+				num: op.sem_num,
+				adj: -(op.sem_op as i32),
+			})?,
+		}
+	}
+	Ok(())
+}
+
+/// Applies the adjustments `undo` of the exiting process `pid`.
+pub fn apply_undo(undo: &[SemUndo], pid: Pid) {
+	for u in undo {
+		let mut set = u.set.lock();
+		if set.removed {
+			continue;
+		}
+		let Ok(sem) = set.get_sem_mut(u.num as _) else {
+			continue;
+		};
+		sem.val = (sem.val as i32 + u.adj).clamp(0, SEMVMX as i32) as _;
+		sem.pid = pid;
+		set.queue.wake_all();
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn op(sem_num: u16, sem_op: i16, sem_flg: i16) -> SemBuf {
+		SemBuf {
+			sem_num,
+			sem_op,
+			sem_flg,
+		}
+	}
+
+	#[test_case]
+	fn sem_atomic() {
+		let mut set = SemSet::new(IpcPerm::default(), 2).unwrap();
+		assert_eq!(set.try_apply(&[op(0, 2, 0)], 1), Ok(None));
+		// The second operation would block, so the first must not be applied
+		assert_eq!(
+			set.try_apply(&[op(0, -1, 0), op(1, -1, 0)], 1),
+			Ok(Some((1, false)))
+		);
+		assert_eq!(set.get_sems()[0].val, 2);
+		assert_eq!(
+			set.try_apply(&[op(0, 0, IPC_NOWAIT as _)], 1),
+			Err(errno!(EAGAIN))
+		);
+		assert_eq!(set.try_apply(&[op(0, -2, 0)], 1), Ok(None));
+		assert_eq!(set.try_apply(&[op(0, 0, 0)], 1), Ok(None));
+		assert_eq!(set.try_apply(&[op(2, 1, 0)], 1), Err(errno!(EFBIG)));
+	}
+
+	#[test_case]
+	fn sem_undo() {
+		let set = Arc::new(Mutex::new(SemSet::new(IpcPerm::default(), 1).unwrap())).unwrap();
+		let ops = [op(0, 3, SEM_UNDO)];
+		set.lock().try_apply(&ops, 1).unwrap();
+		let mut undo = Vec::new();
+		record_undo(&mut undo, &set, &ops).unwrap();
+		record_undo(&mut undo, &set, &[op(0, -1, SEM_UNDO)]).unwrap();
+		set.lock().try_apply(&[op(0, -1, 0)], 1).unwrap();
+		apply_undo(&undo, 1);
+		assert_eq!(set.lock().get_sems()[0].val, 0);
+	}
+}
@@ -0,0 +1,268 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! System V shared memory segments.
+//!
+//! The pages of a segment are allocated on creation, and attached to memory spaces as shared
+//! mappings. A segment is freed once it has been removed and is no longer attached anywhere.
+
+use super::{now, split_time, IpcNamespace, IpcObject, IpcPerm};
+use crate::{
+	file::perm::AccessProfile,
+	memory::buddy,
+	process::{
+		mem_space::residence::{MapResidence, Page, ResidencePage},
+		pid::Pid,
+	},
+	time::unit::Timestamp,
+};
+use core::ffi::c_int;
+use utils::{
+	collections::vec::Vec,
+	errno,
+	errno::{AllocResult, CollectResult, EResult},
+	limits::PAGE_SIZE,
+	lock::Mutex,
+	ptr::arc::Arc,
+};
+
+/// `shmat` flag: Attach the segment read-only.
+pub const SHM_RDONLY: c_int = 0o10000;
+/// `shmat` flag: Round the attach address down to a multiple of [`SHMLBA`].
+pub const SHM_RND: c_int = 0o20000;
+/// `shmat` flag: Replace any existing mapping in the attached range.
+pub const SHM_REMAP: c_int = 0o40000;
+/// `shmat` flag: Allow executing the segment's content.
+pub const SHM_EXEC: c_int = 0o100000;
+
+/// `shmctl` command: Prevent swapping of the segment.
+pub const SHM_LOCK: c_int = 11;
+/// `shmctl` command: Allow swapping of the segment.
+pub const SHM_UNLOCK: c_int = 12;
+
+/// The alignment of attach addresses.
+pub const SHMLBA: usize = PAGE_SIZE;
+/// The minimum size of a segment in bytes.
+const SHMMIN: usize = 1;
+/// The maximum size of a segment in bytes.
+const SHMMAX: usize = 0x2000000;
+
+/// The status of a shared memory segment, as returned to userspace.
+///
+/// The layout of this structure matches the one of `shmid64_ds`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShmidDs {
+	/// Ownership and permissions.
+	pub shm_perm: IpcPerm,
+	/// The size of the segment in bytes.
+	pub shm_segsz: usize,
+	/// Last attach time.
+	pub shm_atime: u32,
+	/// Last attach time, higher bits.
+	pub shm_atime_high: u32,
+	/// Last detach time.
+	pub shm_dtime: u32,
+	/// Last detach time, higher bits.
+	pub shm_dtime_high: u32,
+	/// Last change time.
+	pub shm_ctime: u32,
+	/// Last change time, higher bits.
+	pub shm_ctime_high: u32,
+	/// The PID of the creator.
+	pub shm_cpid: c_int,
+	/// The PID of the last process to attach or detach the segment.
+	pub shm_lpid: c_int,
+	/// The number of current attaches.
+	pub shm_nattch: u32,
+	/// Unused.
+	_unused4: u32,
+	/// Unused.
+	_unused5: u32,
+}
+
+/// A shared memory segment.
+#[derive(Debug)]
+pub struct Shm {
+	/// Ownership and permissions.
+	perm: IpcPerm,
+	/// The size of the segment in bytes.
+	size: usize,
+	/// The pages of the segment.
+	pages: Arc<Vec<Arc<ResidencePage>>>,
+
+	/// Last attach time.
+	atime: Timestamp,
+	/// Last detach time.
+	dtime: Timestamp,
+	/// Last change time.
+	ctime: Timestamp,
+	/// The PID of the creator.
+	cpid: Pid,
+	/// The PID of the last process to attach or detach the segment.
+	lpid: Pid,
+}
+
+impl Shm {
+	/// Creates a segment of `size` bytes, filled with zeros.
+	///
+	/// `pid` is the PID of the creator.
+	fn new(perm: IpcPerm, size: usize, pid: Pid) -> EResult<Self> {
+		let pages = (0..size.div_ceil(PAGE_SIZE))
+			.map(|_| {
+				let physaddr = buddy::alloc(0, buddy::FLAG_ZONE_TYPE_KERNEL)?;
+				let virtaddr = physaddr.kernel_to_virtual().unwrap();
+				unsafe {
+					(*virtaddr.as_ptr::<Page>()).fill(0);
+				}
+				Arc::new(ResidencePage::new(physaddr))
+			})
+			.collect::<AllocResult<CollectResult<_>>>()?
+			.0?;
+		Ok(Self {
+			perm,
+			size,
+			pages: Arc::new(pages)?,
+
+			atime: 0,
+			dtime: 0,
+			ctime: now(),
+			cpid: pid,
+			lpid: 0,
+		})
+	}
+
+	/// Returns the size of the segment in bytes.
+	pub fn get_size(&self) -> usize {
+		self.size
+	}
+
+	/// Returns the number of pages of the segment.
+	pub fn get_pages_count(&self) -> usize {
+		self.pages.len()
+	}
+
+	/// Returns the residence to use to map the segment.
+	pub fn get_residence(&self) -> MapResidence {
+		MapResidence::Static {
+			pages: self.pages.clone(),
+		}
+	}
+
+	/// Tells whether the given residence maps the segment.
+	pub fn is_residence(&self, residence: &MapResidence) -> bool {
+		matches!(residence, MapResidence::Static { pages } if pages.as_ptr() == self.pages.as_ptr())
+	}
+
+	/// Returns the number of mappings of the segment.
+	pub fn get_nattch(&self) -> usize {
+		// Do not count the reference held by the segment itself
+		Arc::strong_count(&self.pages) - 1
+	}
+
+	/// Records an attach by the process with PID `pid`.
+	pub fn attach(&mut self, pid: Pid) {
+		self.atime = now();
+		self.lpid = pid;
+	}
+
+	/// Records a detach by the process with PID `pid`.
+	pub fn detach(&mut self, pid: Pid) {
+		self.dtime = now();
+		self.lpid = pid;
+	}
+
+	/// Returns the status of the segment.
+	pub fn stat(&self) -> ShmidDs {
+		let (shm_atime, shm_atime_high) = split_time(self.atime);
+		let (shm_dtime, shm_dtime_high) = split_time(self.dtime);
+		let (shm_ctime, shm_ctime_high) = split_time(self.ctime);
+		ShmidDs {
+			shm_perm: self.perm,
+			shm_segsz: self.size,
+			shm_atime,
+			shm_atime_high,
+			shm_dtime,
+			shm_dtime_high,
+			shm_ctime,
+			shm_ctime_high,
+			shm_cpid: self.cpid as _,
+			shm_lpid: self.lpid as _,
+			shm_nattch: self.get_nattch() as _,
+			..Default::default()
+		}
+	}
+
+	/// Updates the segment's ownership and permissions from `ds`, as `IPC_SET` does.
+	pub fn set(&mut self, ds: &ShmidDs, ap: &AccessProfile) -> EResult<()> {
+		self.perm.set(&ds.shm_perm, ap)?;
+		self.ctime = now();
+		Ok(())
+	}
+}
+
+impl IpcObject for Shm {
+	fn perm(&self) -> &IpcPerm {
+		&self.perm
+	}
+}
+
+/// Returns the ID of the segment with the given key, creating it if necessary.
+///
+/// Arguments:
+/// - `ns` is the IPC namespace in which the segment is looked for.
+/// - `key` is the key of the segment.
+/// - `size` is the minimum size of the segment in bytes.
+/// - `flags` is the set of `IPC_*` flags with the permissions of the segment to create.
+/// - `ap` is the access profile of the agent.
+/// - `pid` is the PID of the agent.
+pub fn get(
+	ns: &IpcNamespace,
+	key: c_int,
+	size: usize,
+	flags: c_int,
+	ap: &AccessProfile,
+	pid: Pid,
+) -> EResult<c_int> {
+	ns.shm.lock().get_or_create(
+		key,
+		flags,
+		ap,
+		|shm| {
+			if size > shm.size {
+				return Err(errno!(EINVAL));
+			}
+			Ok(())
+		},
+		|perm| {
+			if !(SHMMIN..=SHMMAX).contains(&size) {
+				return Err(errno!(EINVAL));
+			}
+			Shm::new(perm, size, pid)
+		},
+	)
+}
+
+/// Returns the segment of the namespace `ns` mapped with the given residence, if any.
+pub fn find_by_residence(ns: &IpcNamespace, residence: &MapResidence) -> Option<Arc<Mutex<Shm>>> {
+	ns.shm
+		.lock()
+		.iter()
+		.find(|(_, shm)| shm.lock().is_residence(residence))
+		.map(|(_, shm)| shm.clone())
+}
@@ -64,6 +64,7 @@ pub mod gdt;
 #[macro_use]
 pub mod idt;
 pub mod io;
+pub mod ipc;
 pub mod logger;
 pub mod memory;
 pub mod module;
@@ -81,10 +82,15 @@ pub mod time;
 pub mod tty;
 
 use crate::{
-	file::{fs::initramfs, vfs, vfs::ResolutionSettings},
+	file::{fs::initramfs, vfs::ResolutionSettings},
 	logger::LOGGER,
 	memory::vmem,
-	process::{exec, exec::ExecInfo, Process},
+	process::{
+		exec,
+		exec::{BinPrm, ExecInfo},
+		rlimit::RLimits,
+		Process,
+	},
 	tty::TTY,
 };
 use core::{arch::asm, ffi::c_void};
@@ -92,7 +98,6 @@ pub use utils;
 use utils::{
 	collections::{path::Path, string::String, vec::Vec},
 	errno::EResult,
-	lock::Mutex,
 	vec,
 };
 
@@ -107,9 +112,6 @@ pub const ARCH: &str = "x86";
 /// The path to the init process binary.
 const INIT_PATH: &[u8] = b"/sbin/init";
 
-/// The current hostname of the system.
-pub static HOSTNAME: Mutex<Vec<u8>> = Mutex::new(Vec::new());
-
 /// Makes the kernel wait for an interrupt, then returns.
 /// This function enables interruptions.
 #[inline(always)]
@@ -139,15 +141,16 @@ fn init(init_path: String) -> EResult<()> {
 
 	let rs = ResolutionSettings::kernel_follow();
 
-	let path = Path::new(&init_path)?;
-	let file = vfs::get_file_from_path(path, &rs)?;
-
+	let path = Path::new(&init_path)?.to_path_buf()?;
 	let exec_info = ExecInfo {
 		path_resolution: &rs,
 		argv: vec![init_path]?,
 		envp: env,
+		rlimits: RLimits::default(),
+		personality: 0,
 	};
-	let program_image = exec::build_image(&file, exec_info)?;
+	let mut prm = BinPrm::new(&path, exec_info)?;
+	let program_image = exec::build_image(&mut prm)?;
 
 	let proc_mutex = Process::new()?;
 	let mut proc = proc_mutex.lock();
@@ -238,6 +241,7 @@ fn kernel_main_inner(magic: u32, multiboot_ptr: *const c_void) {
 	let init_path = args_parser.get_init_path().unwrap_or(INIT_PATH);
 	let init_path = String::try_from(init_path).unwrap();
 	init(init_path).unwrap_or_else(|e| panic!("Cannot execute init process: {e}"));
+	process::workqueue::init().unwrap_or_else(|e| panic!("Failed to start kernel workers! ({e})"));
 }
 
 /// This is the main function of the Rust source code, responsible for the
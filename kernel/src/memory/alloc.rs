@@ -28,6 +28,9 @@
 //!   overlaps with the user zone which allocates the physical memory.
 //! - User: Memory used for userspace mappings. This zone doesn't require virtual memory to
 //!   correspond with the physical memory, thus it can be located outside the kernelspace.
+//! - High: Memory located above 4 GiB, which is available only with Physical Address Extension.
+//!   Like the user zone, it is used for userspace mappings. Allocations for userspace are
+//!   attempted in this zone first.
 
 use crate::memory::{buddy, memmap, KERNELSPACE_SIZE};
 use core::cmp::min;
@@ -38,12 +41,14 @@ pub(crate) fn init() {
 	let phys_map = memmap::get_info();
 	// The number of available physical memory pages
 	let mut available_pages = phys_map.phys_main_pages;
+	// The number of available physical memory pages above 4 GiB
+	let high_pages = phys_map.phys_high_pages;
 
 	// The pointer to the beginning of the buddy allocator's metadata
 	let metadata_begin = phys_map.phys_main_begin.align_to(PAGE_SIZE);
 	let metadata_begin_virt = metadata_begin.kernel_to_virtual().unwrap();
 	// The size of the buddy allocator's metadata
-	let metadata_size = available_pages * buddy::FRAME_METADATA_SIZE;
+	let metadata_size = (available_pages + high_pages) * buddy::FRAME_METADATA_SIZE;
 	// The end of the buddy allocator's metadata
 	let metadata_end = metadata_begin + metadata_size;
 
@@ -53,7 +58,7 @@ pub(crate) fn init() {
 	// The beginning of the kernel's zone
 	let kernel_zone_begin = metadata_end.align_to(PAGE_SIZE);
 	// The maximum number of pages the kernel zone can hold.
-	let kernel_max = (KERNELSPACE_SIZE - metadata_end.0) / PAGE_SIZE;
+	let kernel_max = (KERNELSPACE_SIZE - metadata_end.0 as usize) / PAGE_SIZE;
 	// The number of frames the kernel zone holds.
 	let kernel_zone_frames = min(available_pages, kernel_max);
 	// The kernel's zone
@@ -77,9 +82,19 @@ pub(crate) fn init() {
 		available_pages as _,
 	);
 
+	// The beginning of the high zone's metadata, after the metadata of the main block
+	let high_metadata_begin =
+		metadata_begin_virt + phys_map.phys_main_pages * buddy::FRAME_METADATA_SIZE;
+	let high_zone = buddy::Zone::new(
+		high_metadata_begin,
+		phys_map.phys_high_begin,
+		high_pages as _,
+	);
+
 	// TODO MMIO zone
 
 	*buddy::ZONES.lock() = [
+		high_zone,
 		user_zone,
 		unsafe { core::mem::zeroed() }, // TODO MMIO
 		kernel_zone,
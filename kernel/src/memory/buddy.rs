@@ -47,17 +47,17 @@ type FrameID = u32;
 pub const MAX_ORDER: FrameOrder = 17;
 
 /// The number of memory zones.
-pub const ZONES_COUNT: usize = 3;
+pub const ZONES_COUNT: usize = 4;
 
 /// The mask for the zone ID in buddy allocator flags.
 const ZONE_TYPE_MASK: Flags = 0b11;
 
-/// Buddy allocator flag: allocate in user zone
+/// Buddy allocator flag: allocate in user zone, trying memory above 4 GiB first
 pub const FLAG_ZONE_TYPE_USER: Flags = 0b00;
 /// Buddy allocator flag: allocate in MMIO zone
-pub const FLAG_ZONE_TYPE_MMIO: Flags = 0b01;
+pub const FLAG_ZONE_TYPE_MMIO: Flags = 0b10;
 /// Buddy allocator flag: allocate in kernel zone
-pub const FLAG_ZONE_TYPE_KERNEL: Flags = 0b10;
+pub const FLAG_ZONE_TYPE_KERNEL: Flags = 0b11;
 
 /// The size of the metadata for one frame.
 pub const FRAME_METADATA_SIZE: usize = size_of::<Frame>();
@@ -137,10 +137,10 @@ impl Zone {
 		z
 	}
 
-	/// Returns the size in bytes of the allocatable memory.
+	/// Returns the physical address to the end of the allocatable memory.
 	#[inline]
-	fn get_size(&self) -> usize {
-		(self.pages_count as usize) * PAGE_SIZE
+	fn end(&self) -> PhysAddr {
+		PhysAddr(self.begin.0 + self.pages_count as u64 * PAGE_SIZE as u64)
 	}
 
 	/// Returns an available frame owned by this zone, with an order of at least
@@ -153,7 +153,7 @@ impl Zone {
 		let f = unsafe { frame.as_mut() };
 		debug_assert!(!f.is_used());
 		debug_assert!(f.addr(self) >= self.begin);
-		debug_assert!(f.addr(self) < self.begin + self.get_size());
+		debug_assert!(f.addr(self) < self.end());
 		Some(frame)
 	}
 
@@ -161,7 +161,7 @@ impl Zone {
 	///
 	/// The pointer must point to the frame itself, not the Frame structure.
 	fn get_frame_id_from_addr(&self, addr: PhysAddr) -> FrameID {
-		((addr.0 - self.begin.0) / PAGE_SIZE) as _
+		((addr.0 - self.begin.0) / PAGE_SIZE as u64) as _
 	}
 
 	/// Returns a mutable slice over the metadata of the zone's frames.
@@ -181,7 +181,6 @@ impl Zone {
 	/// panicking.
 	#[cfg(debug_assertions)]
 	fn check_free_list(&self) {
-		let zone_size = (self.pages_count as usize) * PAGE_SIZE;
 		let frames = self.frames();
 		for (order, list) in self.free_list.iter().enumerate() {
 			let Some(mut first) = *list else {
@@ -199,7 +198,7 @@ impl Zone {
 
 				let frame_ptr = frame.addr(self);
 				debug_assert!(frame_ptr >= self.begin);
-				debug_assert!(frame_ptr + frame.get_size() <= self.begin + zone_size);
+				debug_assert!(frame_ptr + frame.get_size() <= self.end());
 
 				if frame.next == id {
 					break;
@@ -253,7 +252,7 @@ impl Frame {
 
 	/// Returns the address of the associated physical memory.
 	fn addr(&self, zone: &Zone) -> PhysAddr {
-		zone.begin + self.get_id(zone) as usize * PAGE_SIZE
+		PhysAddr(zone.begin.0 + self.get_id(zone) as u64 * PAGE_SIZE as u64)
 	}
 
 	/// Tells whether the frame is used or not.
@@ -451,6 +450,7 @@ pub(crate) static ZONES: IntMutex<[Zone; ZONES_COUNT]> = IntMutex::new([
 	Zone::placeholder(),
 	Zone::placeholder(),
 	Zone::placeholder(),
+	Zone::placeholder(),
 ]);
 
 /// The size in bytes of a frame with the given order `order`.
@@ -474,10 +474,9 @@ pub fn get_order(pages: usize) -> FrameOrder {
 ///
 /// `zones` is the list of zones.
 fn get_zone_for_addr(zones: &mut [Zone; ZONES_COUNT], phys_addr: PhysAddr) -> Option<&mut Zone> {
-	zones.iter_mut().find(|z| {
-		let end = z.begin + z.get_size();
-		(z.begin..end).contains(&phys_addr)
-	})
+	zones
+		.iter_mut()
+		.find(|z| (z.begin..z.end()).contains(&phys_addr))
 }
 
 /// Allocates a frame of memory using the buddy allocator.
@@ -507,14 +506,19 @@ pub fn alloc(order: FrameOrder, flags: Flags) -> AllocResult<PhysAddr> {
 	frame.split(zone, order);
 	let addr = frame.addr(zone);
 	debug_assert!(addr.is_aligned_to(PAGE_SIZE));
-	debug_assert!(addr >= zone.begin && addr < zone.begin + zone.get_size());
+	debug_assert!(addr >= zone.begin && addr < zone.end());
 	frame.mark_used();
 	// Statistics
 	let pages_count = math::pow2(order as usize);
 	zone.allocated_pages += pages_count;
 	stats::MEM_INFO.lock().mem_free -= pages_count * 4;
 	#[cfg(feature = "memtrace")]
-	super::trace::sample("buddy", super::trace::SampleOp::Alloc, addr.0, pages_count);
+	super::trace::sample(
+		"buddy",
+		super::trace::SampleOp::Alloc,
+		addr.0 as _,
+		pages_count,
+	);
 	Ok(addr)
 }
 
@@ -560,7 +564,12 @@ pub unsafe fn free(addr: PhysAddr, order: FrameOrder) {
 	zone.allocated_pages -= pages_count;
 	stats::MEM_INFO.lock().mem_free += pages_count * 4;
 	#[cfg(feature = "memtrace")]
-	super::trace::sample("buddy", super::trace::SampleOp::Free, addr.0, pages_count);
+	super::trace::sample(
+		"buddy",
+		super::trace::SampleOp::Free,
+		addr.0 as _,
+		pages_count,
+	);
 }
 
 /// Frees the given memory frame.
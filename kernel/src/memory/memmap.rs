@@ -22,11 +22,14 @@
 //!
 //! This data is meant to be used by the memory allocators.
 
-use super::{stats, PhysAddr, VirtAddr};
-use crate::{elf::kernel::sections, multiboot, multiboot::BootInfo};
+use super::{buddy, stats, vmem, PhysAddr, VirtAddr};
+use crate::{cpu, elf::kernel::sections, multiboot, multiboot::BootInfo};
 use core::{cmp::*, iter, ptr::null};
 use utils::{limits::PAGE_SIZE, lock::once::OnceInit};
 
+/// The physical address at which memory becomes unreachable without Physical Address Extension.
+const HIGH_MEMORY_BEGIN: u64 = 1 << 32;
+
 /// Physical memory map information.
 #[derive(Debug)]
 pub struct PhysMapInfo {
@@ -41,6 +44,12 @@ pub struct PhysMapInfo {
 	pub phys_main_begin: PhysAddr,
 	/// The size of the main block of physical allocatable memory, in pages.
 	pub phys_main_pages: usize,
+
+	/// Physical address to the beginning of the block of allocatable memory located above 4 GiB,
+	/// page aligned.
+	pub phys_high_begin: PhysAddr,
+	/// The size of the block of physical allocatable memory located above 4 GiB, in pages.
+	pub phys_high_pages: usize,
 }
 
 impl Default for PhysMapInfo {
@@ -52,6 +61,9 @@ impl Default for PhysMapInfo {
 
 			phys_main_begin: PhysAddr::default(),
 			phys_main_pages: 0,
+
+			phys_high_begin: PhysAddr::default(),
+			phys_high_pages: 0,
 		}
 	}
 }
@@ -117,11 +129,43 @@ fn get_phys_main(boot_info: &BootInfo) -> (PhysAddr, usize) {
 		.into_iter()
 		.max()
 		.unwrap();
-	// TODO Handle 64-bits systems
-	// The size of the physical memory in pages
+	// The size of the physical memory in pages. Memory above 4 GiB is handled separately
 	let memory_size = min((1000 + boot_info.mem_upper) / 4, 1024 * 1024) as usize;
 	// The number of physical page available for memory allocation
-	let pages = memory_size - begin.0.div_ceil(PAGE_SIZE);
+	let pages = memory_size - begin.0.div_ceil(PAGE_SIZE as u64) as usize;
+	(begin, pages)
+}
+
+/// Returns the pointer to the beginning of the largest block of available physical memory
+/// located above 4 GiB and its size in number of pages.
+///
+/// `main_pages` is the size of the main block of memory in pages. Since the metadata of the
+/// buddy allocator for the returned block is stored in the main block, the size of the returned
+/// block is limited so that its metadata does not take more than a quarter of the main block.
+///
+/// This memory can be mapped only when Physical Address Extension is enabled. Otherwise, the
+/// function returns an empty block.
+fn get_phys_high(boot_info: &BootInfo, main_pages: usize) -> (PhysAddr, usize) {
+	if !vmem::pae::is_enabled() || boot_info.memory_maps.is_null() {
+		return (PhysAddr::default(), 0);
+	}
+	// The end of the physical memory the CPU is able to address
+	let phys_end = 1u64 << cpu::phys_addr_bits();
+	let (begin, pages) = (0..boot_info.memory_maps_size)
+		.step_by(boot_info.memory_maps_entry_size)
+		// Safe because in range
+		.map(|off| unsafe { &*boot_info.memory_maps.byte_add(off) })
+		.filter(|entry| entry.type_ == multiboot::MEMORY_AVAILABLE)
+		.filter_map(|entry| {
+			let begin = max(entry.addr, HIGH_MEMORY_BEGIN).next_multiple_of(PAGE_SIZE as u64);
+			let end = min(entry.addr.saturating_add(entry.len), phys_end);
+			let pages = end.checked_sub(begin)? / PAGE_SIZE as u64;
+			Some((PhysAddr(begin), pages))
+		})
+		.max_by_key(|(_, pages)| *pages)
+		.unwrap_or_default();
+	let max_pages = main_pages / 4 * (PAGE_SIZE / buddy::FRAME_METADATA_SIZE);
+	let pages = min(pages, max_pages as u64) as usize;
 	(begin, pages)
 }
 
@@ -129,6 +173,7 @@ fn get_phys_main(boot_info: &BootInfo) -> (PhysAddr, usize) {
 pub(crate) fn init(boot_info: &BootInfo) {
 	// Set memory information
 	let (phys_main_begin, phys_main_pages) = get_phys_main(boot_info);
+	let (phys_high_begin, phys_high_pages) = get_phys_high(boot_info, phys_main_pages);
 	let phys_map = PhysMapInfo {
 		memory_maps_size: boot_info.memory_maps_size,
 		memory_maps_entry_size: boot_info.memory_maps_entry_size,
@@ -136,12 +181,15 @@ pub(crate) fn init(boot_info: &BootInfo) {
 
 		phys_main_begin,
 		phys_main_pages,
+
+		phys_high_begin,
+		phys_high_pages,
 	};
 	unsafe {
 		MAP.init(phys_map);
 	}
 	// Update memory stats
 	let mut stats = stats::MEM_INFO.lock();
-	stats.mem_total = min(boot_info.mem_upper, 4194304) as _; // TODO Handle 64-bits systems
-	stats.mem_free = phys_main_pages * 4;
+	stats.mem_total = min(boot_info.mem_upper, 4194304) as usize + phys_high_pages * 4;
+	stats.mem_free = (phys_main_pages + phys_high_pages) * 4;
 }
@@ -55,16 +55,50 @@ pub const PROCESS_END: VirtAddr = VirtAddr(0xc0000000);
 pub const KERNELSPACE_SIZE: usize = usize::MAX - PROCESS_END.0 + 1;
 
 /// An address on physical memory.
+///
+/// The address is 64 bits wide since, with Physical Address Extension, the physical memory can be
+/// larger than the virtual address space.
 #[repr(transparent)]
 #[derive(Clone, Copy, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub struct PhysAddr(pub usize);
+pub struct PhysAddr(pub u64);
 
 impl PhysAddr {
 	/// Converts the kernel physical address to a virtual address.
 	///
 	/// If the address is outside the kernelspace, the function returns `None`.
 	pub fn kernel_to_virtual(self) -> Option<VirtAddr> {
-		self.0.checked_add(PROCESS_END.0).map(VirtAddr)
+		let addr = usize::try_from(self.0).ok()?;
+		addr.checked_add(PROCESS_END.0).map(VirtAddr)
+	}
+
+	/// Tells whether the pointer is aligned to `align`.
+	pub fn is_aligned_to(self, align: usize) -> bool {
+		self.0 % align as u64 == 0
+	}
+
+	/// Computes and returns the next address to be aligned to `align`.
+	///
+	/// If `self` is already aligned, the function returns `self`.
+	pub fn align_to(self, align: usize) -> Self {
+		Self(self.0.next_multiple_of(align as u64))
+	}
+}
+
+impl Add<usize> for PhysAddr {
+	type Output = Self;
+
+	/// Adds the given offset in bytes, wrapping on overflow.
+	fn add(self, off: usize) -> Self::Output {
+		Self(self.0.wrapping_add(off as u64))
+	}
+}
+
+impl Sub<usize> for PhysAddr {
+	type Output = Self;
+
+	/// Subtracts the given offset in bytes, wrapping on overflow.
+	fn sub(self, off: usize) -> Self::Output {
+		Self(self.0.wrapping_sub(off as u64))
 	}
 }
 
@@ -105,7 +139,9 @@ impl VirtAddr {
 	///
 	/// If the address is outside the kernelspace, the function returns `None`.
 	pub fn kernel_to_physical(self) -> Option<PhysAddr> {
-		self.0.checked_sub(PROCESS_END.0).map(PhysAddr)
+		self.0
+			.checked_sub(PROCESS_END.0)
+			.map(|addr| PhysAddr(addr as _))
 	}
 
 	/// Returns a mutable pointer to the virtual address.
@@ -114,6 +150,36 @@ impl VirtAddr {
 	pub fn as_ptr<T>(self) -> *mut T {
 		ptr::with_exposed_provenance_mut(self.0)
 	}
+
+	/// Tells whether the pointer is aligned to `align`.
+	pub fn is_aligned_to(self, align: usize) -> bool {
+		self.0 % align == 0
+	}
+
+	/// Computes and returns the next address to be aligned to `align`.
+	///
+	/// If `self` is already aligned, the function returns `self`.
+	pub fn align_to(self, align: usize) -> Self {
+		Self(self.0.next_multiple_of(align))
+	}
+}
+
+impl Add<usize> for VirtAddr {
+	type Output = Self;
+
+	/// Adds the given offset in bytes, wrapping on overflow.
+	fn add(self, off: usize) -> Self::Output {
+		Self(self.0.wrapping_add(off))
+	}
+}
+
+impl Sub<usize> for VirtAddr {
+	type Output = Self;
+
+	/// Subtracts the given offset in bytes, wrapping on overflow.
+	fn sub(self, off: usize) -> Self::Output {
+		Self(self.0.wrapping_sub(off))
+	}
 }
 
 macro_rules! addr_impl {
@@ -123,36 +189,6 @@ macro_rules! addr_impl {
 			pub fn is_null(self) -> bool {
 				self.0 == 0
 			}
-
-			/// Tells whether the pointer is aligned to `align`.
-			pub fn is_aligned_to(self, align: usize) -> bool {
-				self.0 % align == 0
-			}
-
-			/// Computes and returns the next address to be aligned to `align`.
-			///
-			/// If `self` is already aligned, the function returns `self`.
-			pub fn align_to(self, align: usize) -> Self {
-				Self(self.0.next_multiple_of(align))
-			}
-		}
-
-		impl Add<usize> for $name {
-			type Output = Self;
-
-			/// Adds the given offset in bytes, wrapping on overflow.
-			fn add(self, off: usize) -> Self::Output {
-				Self(self.0.wrapping_add(off))
-			}
-		}
-
-		impl Sub<usize> for $name {
-			type Output = Self;
-
-			/// Subtracts the given offset in bytes, wrapping on overflow.
-			fn sub(self, off: usize) -> Self::Output {
-				Self(self.0.wrapping_sub(off))
-			}
 		}
 
 		impl fmt::Debug for $name {
@@ -19,6 +19,8 @@
 //! The virtual memory makes the kernel able to isolate processes, which is
 //! essential for modern systems.
 
+#[cfg(target_arch = "x86")]
+pub mod pae;
 #[cfg(target_arch = "x86")]
 pub mod x86;
 
@@ -49,6 +51,35 @@ fn is_kernelspace(virtaddr: VirtAddr, pages: usize) -> bool {
 	end > memory::PROCESS_END.0
 }
 
+/// The root element of an architecture-dependent paging tree.
+#[cfg(target_arch = "x86")]
+enum Root {
+	/// The page directory, with 32 bits paging.
+	Legacy(NonNull<x86::Table>),
+	/// The Page Directory Pointer Table, with PAE paging.
+	Pae(NonNull<pae::Table>),
+}
+
+#[cfg(target_arch = "x86")]
+impl Root {
+	/// Allocates a paging tree for the paging mode enabled on the CPU.
+	fn alloc() -> AllocResult<Self> {
+		if pae::is_enabled() {
+			Ok(Self::Pae(pae::alloc()?))
+		} else {
+			Ok(Self::Legacy(x86::alloc()?))
+		}
+	}
+
+	/// Returns the virtual address of the root element.
+	fn addr(&self) -> VirtAddr {
+		match self {
+			Self::Legacy(page_dir) => VirtAddr::from(page_dir.as_ptr()),
+			Self::Pae(pdpt) => VirtAddr::from(pdpt.as_ptr()),
+		}
+	}
+}
+
 /// A virtual memory context.
 ///
 /// This structure implements operations to modify virtual memory in an architecture-independent
@@ -58,7 +89,7 @@ fn is_kernelspace(virtaddr: VirtAddr, pages: usize) -> bool {
 /// results in an error.
 pub struct VMem<const KERNEL: bool = false> {
 	#[cfg(target_arch = "x86")]
-	page_dir: NonNull<x86::Table>,
+	root: Root,
 }
 
 impl VMem<false> {
@@ -66,7 +97,7 @@ impl VMem<false> {
 	pub fn new() -> AllocResult<Self> {
 		Ok(Self {
 			#[cfg(target_arch = "x86")]
-			page_dir: x86::alloc()?,
+			root: Root::alloc()?,
 		})
 	}
 }
@@ -81,31 +112,22 @@ impl VMem<true> {
 	pub unsafe fn new_kernel() -> AllocResult<Self> {
 		Ok(Self {
 			#[cfg(target_arch = "x86")]
-			page_dir: x86::alloc()?,
+			root: Root::alloc()?,
 		})
 	}
 }
 
 impl<const KERNEL: bool> VMem<KERNEL> {
-	/// Returns an immutable reference to the **architecture-dependent** inner representation.
-	#[cfg(target_arch = "x86")]
-	pub fn inner(&self) -> &x86::Table {
-		unsafe { self.page_dir.as_ref() }
-	}
-
-	/// Returns a mutable reference to the architecture-dependent inner representation.
-	#[cfg(target_arch = "x86")]
-	pub fn inner_mut(&mut self) -> &mut x86::Table {
-		unsafe { self.page_dir.as_mut() }
-	}
-
 	/// Translates the given virtual address `addr` to the corresponding physical
 	/// address.
 	///
 	/// If the address is not mapped, the function returns `None`.
 	pub fn translate(&self, addr: VirtAddr) -> Option<PhysAddr> {
 		#[cfg(target_arch = "x86")]
-		x86::translate(self.inner(), addr)
+		match self.root {
+			Root::Legacy(page_dir) => x86::translate(unsafe { page_dir.as_ref() }, addr),
+			Root::Pae(pdpt) => pae::translate(unsafe { pdpt.as_ref() }, addr),
+		}
 	}
 
 	/// Begins a transaction.
@@ -118,9 +140,7 @@ impl<const KERNEL: bool> VMem<KERNEL> {
 
 	/// Binds the virtual memory context to the current CPU.
 	pub fn bind(&self) {
-		let phys_addr = VirtAddr::from(self.page_dir.as_ptr())
-			.kernel_to_physical()
-			.unwrap();
+		let phys_addr = self.root.addr().kernel_to_physical().unwrap();
 		unsafe {
 			#[cfg(target_arch = "x86")]
 			x86::bind(phys_addr);
@@ -129,7 +149,7 @@ impl<const KERNEL: bool> VMem<KERNEL> {
 
 	/// Tells whether the context is bound to the current CPU.
 	pub fn is_bound(&self) -> bool {
-		x86::is_bound(self.page_dir)
+		x86::is_bound(self.root.addr())
 	}
 }
 
@@ -140,7 +160,32 @@ impl<const KERNEL: bool> Drop for VMem<KERNEL> {
 		}
 		#[cfg(target_arch = "x86")]
 		unsafe {
-			x86::free(self.page_dir);
+			match self.root {
+				Root::Legacy(page_dir) => x86::free(page_dir),
+				Root::Pae(pdpt) => pae::free(pdpt),
+			}
+		}
+	}
+}
+
+/// Handle allowing to roll back a single operation on a virtual memory context.
+#[cfg(target_arch = "x86")]
+enum Rollback {
+	/// Operation with 32 bits paging.
+	Legacy(x86::Rollback),
+	/// Operation with PAE paging.
+	Pae(pae::Rollback),
+}
+
+#[cfg(target_arch = "x86")]
+impl Rollback {
+	/// Rollbacks the operation on the paging tree `root`.
+	fn rollback(self, root: &mut Root) {
+		match (self, root) {
+			(Self::Legacy(r), Root::Legacy(page_dir)) => r.rollback(unsafe { page_dir.as_mut() }),
+			(Self::Pae(r), Root::Pae(pdpt)) => r.rollback(unsafe { pdpt.as_mut() }),
+			// A context never changes its paging mode
+			_ => unreachable!(),
 		}
 	}
 }
@@ -154,7 +199,7 @@ pub struct VMemTransaction<'v, const KERNEL: bool> {
 	pub vmem: &'v mut VMem<KERNEL>,
 	/// The vector of handles to roll back the whole transaction.
 	#[cfg(target_arch = "x86")]
-	rollback: Vec<x86::Rollback>,
+	rollback: Vec<Rollback>,
 }
 
 impl<'v, const KERNEL: bool> VMemTransaction<'v, KERNEL> {
@@ -164,8 +209,15 @@ impl<'v, const KERNEL: bool> VMemTransaction<'v, KERNEL> {
 		physaddr: PhysAddr,
 		virtaddr: VirtAddr,
 		flags: u32,
-	) -> AllocResult<x86::Rollback> {
-		let res = unsafe { x86::map(self.vmem.inner_mut(), physaddr, virtaddr, flags) };
+	) -> AllocResult<Rollback> {
+		let res = match &mut self.vmem.root {
+			Root::Legacy(page_dir) => unsafe {
+				x86::map(page_dir.as_mut(), physaddr, virtaddr, flags).map(Rollback::Legacy)
+			},
+			Root::Pae(pdpt) => unsafe {
+				pae::map(pdpt.as_mut(), physaddr, virtaddr, flags).map(Rollback::Pae)
+			},
+		};
 		invalidate_page_current(virtaddr);
 		res
 	}
@@ -220,8 +272,13 @@ impl<'v, const KERNEL: bool> VMemTransaction<'v, KERNEL> {
 	}
 
 	#[cfg(target_arch = "x86")]
-	fn unmap_impl(&mut self, virtaddr: VirtAddr) -> AllocResult<x86::Rollback> {
-		let res = unsafe { x86::unmap(self.vmem.inner_mut(), virtaddr) };
+	fn unmap_impl(&mut self, virtaddr: VirtAddr) -> AllocResult<Rollback> {
+		let res = match &mut self.vmem.root {
+			Root::Legacy(page_dir) => unsafe {
+				x86::unmap(page_dir.as_mut(), virtaddr).map(Rollback::Legacy)
+			},
+			Root::Pae(pdpt) => unsafe { pae::unmap(pdpt.as_mut(), virtaddr).map(Rollback::Pae) },
+		};
 		invalidate_page_current(virtaddr);
 		res
 	}
@@ -278,7 +335,7 @@ impl<const KERNEL: bool> Drop for VMemTransaction<'_, KERNEL> {
 		rollback
 			.into_iter()
 			.rev()
-			.for_each(|r| r.rollback(self.vmem.inner_mut()));
+			.for_each(|r| r.rollback(&mut self.vmem.root));
 	}
 }
 
@@ -353,7 +410,7 @@ pub unsafe fn switch<F: FnOnce() -> T, T>(vmem: &VMem, f: F) -> T {
 			f()
 		} else {
 			// Get current vmem
-			let page_dir = PhysAddr(register_get!("cr3"));
+			let page_dir = PhysAddr(register_get!("cr3") as _);
 			// Bind temporary vmem
 			vmem.bind();
 			let result = f();
@@ -376,7 +433,9 @@ pub fn kernel() -> &'static Mutex<VMem<true>> {
 pub(crate) fn init() -> AllocResult<()> {
 	// Architecture-specific init
 	#[cfg(target_arch = "x86")]
-	{
+	if pae::is_enabled() {
+		pae::init()?;
+	} else {
 		x86::init()?;
 	}
 	// Kernel context init
@@ -395,11 +454,15 @@ pub(crate) fn init() -> AllocResult<()> {
 	let iter = elf::kernel::sections().filter(|s| s.sh_addralign as usize == PAGE_SIZE);
 	for section in iter {
 		let write = section.sh_flags & elf::SHF_WRITE != 0;
+		let exec = section.sh_flags & elf::SHF_EXECINSTR != 0;
 		let user = elf::kernel::get_section_name(section) == Some(b".user");
 		let mut flags = x86::FLAG_GLOBAL;
 		if write {
 			flags |= x86::FLAG_WRITE;
 		}
+		if !exec {
+			flags |= x86::FLAG_NO_EXEC;
+		}
 		if user {
 			flags |= x86::FLAG_USER;
 		}
@@ -446,7 +509,10 @@ mod test {
 	fn vmem_basic1() {
 		let vmem = VMem::new().unwrap();
 		for i in (0..0x40000000).step_by(PAGE_SIZE) {
-			assert_eq!(vmem.translate(memory::PROCESS_END + i), Some(PhysAddr(i)));
+			assert_eq!(
+				vmem.translate(memory::PROCESS_END + i),
+				Some(PhysAddr(i as _))
+			);
 		}
 	}
 
@@ -462,7 +528,7 @@ mod test {
 		for i in (0usize..0xc0000000).step_by(PAGE_SIZE) {
 			let res = vmem.translate(VirtAddr(i));
 			if (0x100000..0x101000).contains(&i) {
-				assert_eq!(res, Some(PhysAddr(i)));
+				assert_eq!(res, Some(PhysAddr(i as _)));
 			} else {
 				assert_eq!(res, None);
 			}
@@ -484,7 +550,7 @@ mod test {
 		for i in (0usize..0xc0000000).step_by(PAGE_SIZE) {
 			let res = vmem.translate(VirtAddr(i));
 			if (0x100000..0x101000).contains(&i) {
-				assert_eq!(res, Some(PhysAddr(0x100000 + i)));
+				assert_eq!(res, Some(PhysAddr(0x100000 + i as u64)));
 			} else {
 				assert_eq!(res, None);
 			}
@@ -505,4 +571,34 @@ mod test {
 			assert_eq!(vmem.translate(VirtAddr(i)), None);
 		}
 	}
+
+	#[test_case]
+	fn vmem_map_no_exec() {
+		let mut vmem = VMem::new().unwrap();
+		let mut transaction = vmem.transaction();
+		transaction
+			.map(PhysAddr(0x100000), VirtAddr(0x100000), x86::FLAG_NO_EXEC)
+			.unwrap();
+		transaction.commit();
+		drop(transaction);
+		assert_eq!(vmem.translate(VirtAddr(0x100000)), Some(PhysAddr(0x100000)));
+	}
+
+	#[test_case]
+	fn vmem_map_high() {
+		if !pae::is_enabled() {
+			return;
+		}
+		let mut vmem = VMem::new().unwrap();
+		let mut transaction = vmem.transaction();
+		transaction
+			.map(PhysAddr(0x100000000), VirtAddr(0x100000), 0)
+			.unwrap();
+		transaction.commit();
+		drop(transaction);
+		assert_eq!(
+			vmem.translate(VirtAddr(0x100010)),
+			Some(PhysAddr(0x100000010))
+		);
+	}
 }
@@ -0,0 +1,489 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Physical Address Extension (PAE) is an x86 paging mode using 64 bits long entries.
+//!
+//! It allows to map physical memory above 4 GiB and to forbid instruction fetches from a page
+//! with the Execute Disable (XD) bit.
+//!
+//! The following elements are available:
+//! - Page Directory Pointer Table (PDPT): The main element, contains 4 page directories
+//! - Page directory: Represents a block of 1 GB, contains page tables
+//! - Page table: Represents a block of 2 MB, each entry is a page
+//!
+//! Page directories and page tables contain 512 entries.
+//!
+//! The CPU caches the entries of the PDPT when the context is bound. To avoid having to bind
+//! it again when a page directory is added or removed, all page directories are allocated along
+//! with the PDPT and are freed with it.
+//!
+//! The last page directory maps the kernelspace. It is shared by every context.
+//!
+//! Flags are the same as the ones used with 32 bits paging. The [`FLAG_NO_EXEC`] flag is
+//! translated to the XD bit if the CPU supports it.
+
+use super::x86::{FLAGS_MASK, FLAG_NO_EXEC, FLAG_PAGE_SIZE, FLAG_PRESENT, FLAG_USER, FLAG_WRITE};
+use crate::{
+	cpu,
+	memory::{buddy, PhysAddr, VirtAddr},
+	register_get,
+};
+use core::{
+	alloc::AllocError,
+	ptr::{null_mut, NonNull},
+	sync::atomic::{AtomicBool, Ordering::Relaxed},
+};
+use utils::{errno::AllocResult, limits::PAGE_SIZE, lock::Mutex};
+
+/// PAE paging flag. If set, instructions cannot be fetched from the page.
+const FLAG_XD: u64 = 1 << 63;
+/// Address mask in an entry.
+const ADDR_MASK: u64 = 0x000ffffffffff000;
+
+/// The number of entries in a table.
+pub(super) const ENTRIES_PER_TABLE: usize = 512;
+/// The number of page directories reserved for the userspace.
+///
+/// Those directories start at the beginning of the PDPT. The remaining directory is reserved for
+/// the kernel.
+const USERSPACE_DIRS: usize = 3;
+/// Paging table.
+pub(super) type Table = [u64; ENTRIES_PER_TABLE];
+/// The flags of entries pointing to a page table.
+///
+/// Execution is not forbidden at this level, since it would apply to every page in the table.
+const TABLE_FLAGS_MASK: u32 = FLAG_WRITE | FLAG_USER;
+
+/// Tells whether the XD bit can be used.
+static NX: AtomicBool = AtomicBool::new(false);
+/// Kernel space page directory, common to every context.
+static KERNEL_DIR: Mutex<*mut Table> = Mutex::new(null_mut());
+
+/// Tells whether PAE paging is enabled on the current CPU.
+///
+/// The paging mode is selected at boot, before the kernel is remapped to higher memory.
+#[inline]
+pub fn is_enabled() -> bool {
+	register_get!("cr4") & (1 << 5) != 0
+}
+
+/// Allocates a table and returns its virtual address.
+///
+/// If the allocation fails, the function returns an error.
+fn alloc_table() -> AllocResult<NonNull<Table>> {
+	let mut table = buddy::alloc_kernel(0)?.cast::<Table>();
+	unsafe {
+		table.as_mut().fill(0);
+	}
+	Ok(table)
+}
+
+/// Frees a table.
+///
+/// # Safety
+///
+/// Further accesses to the table after this function are undefined.
+unsafe fn free_table(table: NonNull<Table>) {
+	buddy::free_kernel(table.as_ptr() as _, 0);
+}
+
+/// Turns the given object/flags pair into an entry for another object.
+///
+/// Invalid flags are ignored and the [`FLAG_PRESENT`] flag is inserted automatically.
+#[inline]
+fn to_entry(addr: PhysAddr, flags: u32) -> u64 {
+	// Sanitize flags
+	let mut entry =
+		(addr.0 & ADDR_MASK) | (flags & FLAGS_MASK & !FLAG_NO_EXEC | FLAG_PRESENT) as u64;
+	if flags & FLAG_NO_EXEC != 0 && NX.load(Relaxed) {
+		entry |= FLAG_XD;
+	}
+	entry
+}
+
+/// Returns the flags of the given entry.
+#[inline]
+fn entry_flags(entry: u64) -> u32 {
+	let mut flags = entry as u32 & FLAGS_MASK;
+	if entry & FLAG_XD != 0 {
+		flags |= FLAG_NO_EXEC;
+	}
+	flags
+}
+
+/// Turns an entry back into the object it points to.
+///
+/// # Safety
+///
+/// If the object's address in the entry is invalid, the behaviour is undefined.
+#[inline]
+unsafe fn unwrap_entry(entry: u64) -> NonNull<Table> {
+	let table = PhysAddr(entry & ADDR_MASK)
+		.kernel_to_virtual()
+		.unwrap()
+		.as_ptr();
+	NonNull::new(table).unwrap()
+}
+
+/// Returns the index of the element corresponding to the given virtual
+/// address `addr` for element at level `level` in the tree.
+///
+/// The level represents the depth in the tree. `0` is the deepest.
+#[inline]
+fn get_addr_element_index(addr: VirtAddr, level: usize) -> usize {
+	(addr.0 >> (12 + level * 9)) & 0x1ff
+}
+
+/// Tells whether the given virtual address `addr` is located in the kernel's page directory.
+#[inline]
+fn is_kernel_dir(addr: VirtAddr) -> bool {
+	get_addr_element_index(addr, 2) >= USERSPACE_DIRS
+}
+
+/// Returns the page directory of `pdpt` covering the virtual address `addr`.
+///
+/// # Safety
+///
+/// The PDPT must be valid and the caller must ensure no other reference to the page directory
+/// exists.
+#[inline]
+unsafe fn get_dir<'t>(pdpt: &Table, addr: VirtAddr) -> &'t mut Table {
+	unwrap_entry(pdpt[get_addr_element_index(addr, 2)]).as_mut()
+}
+
+/// Page tables manipulation.
+mod table {
+	use super::*;
+
+	/// Replaces the large page entry at index `index` in the page directory `parent` by a page
+	/// table.
+	///
+	/// This function allocates a new page table and fills it so that the memory mapping keeps the
+	/// same behavior.
+	pub fn expand(parent: &mut Table, index: usize) -> AllocResult<()> {
+		let entry = parent[index];
+		if entry & FLAG_PRESENT as u64 == 0 || entry & FLAG_PAGE_SIZE as u64 == 0 {
+			return Ok(());
+		}
+		// Sanitize
+		let flags = entry_flags(entry) & !FLAG_PAGE_SIZE;
+		let base_addr =
+			PhysAddr(entry & ADDR_MASK & !((ENTRIES_PER_TABLE * PAGE_SIZE) as u64 - 1));
+		// Create table
+		let mut new_table = alloc_table()?;
+		let table = unsafe { new_table.as_mut() };
+		table.iter_mut().enumerate().for_each(|(i, e)| {
+			*e = to_entry(base_addr + i * PAGE_SIZE, flags);
+		});
+		let addr = VirtAddr::from(new_table).kernel_to_physical().unwrap();
+		parent[index] = to_entry(addr, flags & TABLE_FLAGS_MASK);
+		Ok(())
+	}
+
+	/// Tells whether the given table is empty.
+	pub fn is_empty(table: &Table) -> bool {
+		// TODO Use a counter instead. Increment it when mapping a page in the table and
+		// decrement it when unmapping. Then return `true` if the counter has the value
+		// `0`
+		table.iter().all(|e| e & FLAG_PRESENT as u64 == 0)
+	}
+}
+
+/// Allocates and initializes a new PDPT, along with its page directories.
+///
+/// The kernel memory is mapped into the context by default.
+pub(super) fn alloc() -> AllocResult<NonNull<Table>> {
+	let pdpt = alloc_table()?;
+	let entries = unsafe { &mut *pdpt.as_ptr() };
+	for entry in &mut entries[..USERSPACE_DIRS] {
+		let Ok(dir) = alloc_table() else {
+			unsafe {
+				free(pdpt);
+			}
+			return Err(AllocError);
+		};
+		let addr = VirtAddr::from(dir).kernel_to_physical().unwrap();
+		// Entries of the PDPT only support the present and caching flags
+		*entry = to_entry(addr, 0);
+	}
+	// Init kernel entry
+	let kernel_dir = KERNEL_DIR.lock();
+	let addr = VirtAddr::from(*kernel_dir).kernel_to_physical().unwrap();
+	entries[USERSPACE_DIRS] = to_entry(addr, 0);
+	Ok(pdpt)
+}
+
+/// Returns the corresponding entry for [`translate`].
+fn translate_impl(pdpt: &Table, addr: VirtAddr) -> Option<u64> {
+	// Page directory
+	let page_dir = unsafe { get_dir(pdpt, addr) };
+	let entry = page_dir[get_addr_element_index(addr, 1)];
+	if entry & FLAG_PRESENT as u64 == 0 {
+		return None;
+	}
+	if entry & FLAG_PAGE_SIZE as u64 != 0 {
+		return Some(entry);
+	}
+	// Page table
+	let table = unsafe { unwrap_entry(entry).as_ref() };
+	let entry = table[get_addr_element_index(addr, 0)];
+	if entry & FLAG_PRESENT as u64 == 0 {
+		return None;
+	}
+	Some(entry)
+}
+
+/// Translates the given virtual address to the corresponding physical address using `pdpt`.
+pub(super) fn translate(pdpt: &Table, addr: VirtAddr) -> Option<PhysAddr> {
+	let entry = translate_impl(pdpt, addr)?;
+	let remain_mask = if entry & FLAG_PAGE_SIZE as u64 == 0 {
+		PAGE_SIZE - 1
+	} else {
+		ENTRIES_PER_TABLE * PAGE_SIZE - 1
+	};
+	let physaddr = (entry & ADDR_MASK & !(remain_mask as u64)) | (addr.0 & remain_mask) as u64;
+	Some(PhysAddr(physaddr))
+}
+
+/// Inner version of [`super::Rollback`] for PAE.
+pub(super) struct Rollback {
+	/// The virtual address of the affected page.
+	virtaddr: VirtAddr,
+	/// If `true`, `previous_entry` is an entry of the page directory. Else, it is an entry of the
+	/// page table.
+	dir_entry: bool,
+	/// Previous value of the entry.
+	previous_entry: u64,
+	/// The table that was deleted, if any.
+	table: Option<NonNull<Table>>,
+}
+
+impl Rollback {
+	/// Rollbacks the operation on `pdpt`.
+	#[cold]
+	pub(super) fn rollback(mut self, pdpt: &mut Table) {
+		let page_dir = unsafe { get_dir(pdpt, self.virtaddr) };
+		let pd_index = get_addr_element_index(self.virtaddr, 1);
+		if self.dir_entry {
+			let entry = page_dir[pd_index];
+			page_dir[pd_index] = self.previous_entry;
+			// If the entry pointed to a table that was created by the operation, free it. A table
+			// referenced by the previous entry is in use again
+			let created = entry & (FLAG_PRESENT | FLAG_PAGE_SIZE) as u64 == FLAG_PRESENT as u64
+				&& entry & ADDR_MASK != self.previous_entry & ADDR_MASK;
+			self.table = created.then(|| unsafe { unwrap_entry(entry) });
+			return;
+		}
+		// Restore the table that was deleted
+		if let Some(table) = self.table.take() {
+			let addr = VirtAddr::from(table).kernel_to_physical().unwrap();
+			let flags = entry_flags(self.previous_entry) & TABLE_FLAGS_MASK;
+			page_dir[pd_index] = to_entry(addr, flags);
+		}
+		// If no table is present, stop here
+		if page_dir[pd_index] & FLAG_PRESENT as u64 == 0 {
+			return;
+		}
+		// A table is present, set entry with previous value
+		let mut table_ptr = unsafe { unwrap_entry(page_dir[pd_index]) };
+		let table = unsafe { table_ptr.as_mut() };
+		let pt_index = get_addr_element_index(self.virtaddr, 0);
+		table[pt_index] = self.previous_entry;
+		// If the table is now empty, delete it
+		// `is_empty` is expensive. Call it only if the entry has been set to "not present"
+		if !is_kernel_dir(self.virtaddr)
+			&& table[pt_index] & FLAG_PRESENT as u64 == 0
+			&& table::is_empty(table)
+		{
+			// The table will be freed when dropping `self`
+			self.table = Some(table_ptr);
+			page_dir[pd_index] = 0;
+		}
+	}
+}
+
+impl Drop for Rollback {
+	fn drop(&mut self) {
+		if let Some(table) = self.table {
+			unsafe {
+				free_table(table);
+			}
+		}
+	}
+}
+
+/// Inner implementation of [`super::VMem::map`] for PAE.
+///
+/// # Safety
+///
+/// In case the mapped memory is in kernelspace, the caller must ensure the code and stack of the
+/// kernel remain accessible and valid.
+pub(super) unsafe fn map(
+	pdpt: &mut Table,
+	physaddr: PhysAddr,
+	virtaddr: VirtAddr,
+	flags: u32,
+) -> AllocResult<Rollback> {
+	// Sanitize
+	let physaddr = PhysAddr(physaddr.0 & !(PAGE_SIZE as u64 - 1));
+	let virtaddr = VirtAddr(virtaddr.0 & !(PAGE_SIZE - 1));
+	let flags = (flags & FLAGS_MASK) | FLAG_PRESENT;
+	// Page directory
+	let page_dir = get_dir(pdpt, virtaddr);
+	let pd_index = get_addr_element_index(virtaddr, 1);
+	let previous_entry = page_dir[pd_index];
+	// If using large pages, set entry and stop
+	if flags & FLAG_PAGE_SIZE != 0 {
+		page_dir[pd_index] = to_entry(physaddr, flags);
+		let table = (previous_entry & (FLAG_PRESENT | FLAG_PAGE_SIZE) as u64
+			== FLAG_PRESENT as u64)
+			.then(|| unsafe { unwrap_entry(previous_entry) });
+		return Ok(Rollback {
+			virtaddr,
+			dir_entry: true,
+			previous_entry,
+			table,
+		});
+	}
+	let mut expanded = false;
+	if previous_entry & FLAG_PRESENT as u64 == 0 {
+		// No table is present, allocate one
+		let table = alloc_table()?;
+		let addr = VirtAddr::from(table).kernel_to_physical().unwrap();
+		page_dir[pd_index] = to_entry(addr, 0);
+	} else if previous_entry & FLAG_PAGE_SIZE as u64 != 0 {
+		// A large page is present, need to expand it for the mapping
+		table::expand(page_dir, pd_index)?;
+		expanded = true;
+	}
+	// Set the table's flags
+	page_dir[pd_index] |= (flags & TABLE_FLAGS_MASK) as u64;
+	// Page table
+	let table = unwrap_entry(page_dir[pd_index]).as_mut();
+	let pt_index = get_addr_element_index(virtaddr, 0);
+	let previous_entry = if expanded {
+		previous_entry
+	} else {
+		table[pt_index]
+	};
+	table[pt_index] = to_entry(physaddr, flags);
+	Ok(Rollback {
+		virtaddr,
+		dir_entry: expanded,
+		previous_entry,
+		table: None,
+	})
+}
+
+/// Inner implementation of [`super::VMem::unmap`] for PAE.
+///
+/// # Safety
+///
+/// In case the unmapped memory is in kernelspace, the caller must ensure the code and stack of the
+/// kernel remain accessible and valid.
+pub(super) unsafe fn unmap(pdpt: &mut Table, virtaddr: VirtAddr) -> AllocResult<Rollback> {
+	// Sanitize
+	let virtaddr = VirtAddr(virtaddr.0 & !(PAGE_SIZE - 1));
+	// Page directory
+	let page_dir = get_dir(pdpt, virtaddr);
+	let pd_index = get_addr_element_index(virtaddr, 1);
+	let previous_entry = page_dir[pd_index];
+	if previous_entry & FLAG_PRESENT as u64 == 0 {
+		// The entry does not exist, do nothing
+		return Ok(Rollback {
+			virtaddr,
+			dir_entry: true,
+			previous_entry,
+			table: None,
+		});
+	}
+	if previous_entry & FLAG_PAGE_SIZE as u64 != 0 {
+		// The entry is a large page, remove it and stop here
+		page_dir[pd_index] = 0;
+		return Ok(Rollback {
+			virtaddr,
+			dir_entry: true,
+			previous_entry,
+			table: None,
+		});
+	}
+	// Page table
+	let mut table_ptr = unwrap_entry(previous_entry);
+	let table = table_ptr.as_mut();
+	let pt_index = get_addr_element_index(virtaddr, 0);
+	let previous_entry = table[pt_index];
+	table[pt_index] = 0;
+	// Remove the table if it is empty and if not a kernel space table
+	let table = if !is_kernel_dir(virtaddr)
+		&& previous_entry & FLAG_PRESENT as u64 != 0
+		&& table::is_empty(table)
+	{
+		page_dir[pd_index] = 0;
+		Some(table_ptr)
+	} else {
+		None
+	};
+	Ok(Rollback {
+		virtaddr,
+		dir_entry: false,
+		previous_entry,
+		table,
+	})
+}
+
+/// Destroys the given PDPT, including its children elements.
+///
+/// The kernel's page directory is not freed since it is shared with other contexts.
+///
+/// # Safety
+///
+/// It is assumed the context is not being used.
+///
+/// Subsequent uses of `pdpt` are undefined.
+pub(super) unsafe fn free(mut pdpt: NonNull<Table>) {
+	let entries = pdpt.as_mut();
+	for entry in &entries[..USERSPACE_DIRS] {
+		if entry & FLAG_PRESENT as u64 == 0 {
+			continue;
+		}
+		let dir = unwrap_entry(*entry);
+		for entry in dir.as_ref() {
+			if entry & (FLAG_PRESENT | FLAG_PAGE_SIZE) as u64 == FLAG_PRESENT as u64 {
+				free_table(unwrap_entry(*entry));
+			}
+		}
+		free_table(dir);
+	}
+	free_table(pdpt);
+}
+
+/// Initializes virtual memory management with PAE.
+pub(super) fn init() -> AllocResult<()> {
+	super::x86::init_features();
+	// Enable the XD bit
+	if cpu::supports_nx() {
+		unsafe {
+			let efer = cpu::rdmsr(cpu::MSR_EFER);
+			cpu::wrmsr(cpu::MSR_EFER, efer | (1 << 11));
+		}
+		NX.store(true, Relaxed);
+	}
+	// Allocate the kernel's page directory
+	*KERNEL_DIR.lock() = alloc_table()?.as_ptr();
+	Ok(())
+}
@@ -52,6 +52,11 @@ use core::{
 };
 use utils::{errno::AllocResult, limits::PAGE_SIZE, lock::Mutex};
 
+/// x86 paging flag. If set, instructions cannot be fetched from the page.
+///
+/// This flag uses a bit that is ignored by the CPU. It is enforced only with PAE paging (see
+/// [`super::pae`]).
+pub const FLAG_NO_EXEC: u32 = 0b1000000000;
 /// x86 paging flag. If set, prevents the CPU from updating the associated
 /// addresses when the TLB is flushed.
 pub const FLAG_GLOBAL: u32 = 0b100000000;
@@ -148,7 +153,7 @@ fn to_entry(addr: PhysAddr, flags: u32) -> u32 {
 /// If the object's address in the entry is invalid, the behaviour is undefined.
 #[inline]
 unsafe fn unwrap_entry(entry: u32) -> (NonNull<Table>, u32) {
-	let table = PhysAddr((entry & ADDR_MASK) as usize)
+	let table = PhysAddr((entry & ADDR_MASK) as _)
 		.kernel_to_virtual()
 		.unwrap()
 		.as_ptr();
@@ -253,7 +258,7 @@ pub(super) fn translate(page_dir: &Table, addr: VirtAddr) -> Option<PhysAddr> {
 	};
 	let mut virtptr = (entry & ADDR_MASK) as usize;
 	virtptr |= addr.0 & remain_mask;
-	Some(PhysAddr(virtptr))
+	Some(PhysAddr(virtptr as _))
 }
 
 /// Inner version of [`super::Rollback`] for x86.
@@ -327,9 +332,9 @@ pub(super) unsafe fn map(
 	flags: u32,
 ) -> AllocResult<Rollback> {
 	// Sanitize
-	let physaddr = PhysAddr(physaddr.0 & !(PAGE_SIZE - 1));
+	let physaddr = PhysAddr(physaddr.0 & !(PAGE_SIZE as u64 - 1));
 	let virtaddr = VirtAddr(virtaddr.0 & !(PAGE_SIZE - 1));
-	let flags = (flags & FLAGS_MASK) | FLAG_PRESENT;
+	let flags = (flags & FLAGS_MASK & !FLAG_NO_EXEC) | FLAG_PRESENT;
 	// First level
 	let pd_index = get_addr_element_index(virtaddr, 1);
 	let mut previous_entry = page_dir[pd_index];
@@ -438,16 +443,18 @@ pub(super) unsafe fn bind(page_dir: PhysAddr) {
 		"mov {tmp}, cr0",
 		"or {tmp}, 0x80010000",
 		"mov cr0, {tmp}",
-		dir = in(reg) page_dir.0,
+		dir = in(reg) page_dir.0 as usize,
 		tmp = out(reg) _,
 	)
 }
 
-/// Tells whether the given page directory is bound on the current CPU.
+/// Tells whether the given paging context is bound on the current CPU.
+///
+/// `root` is the virtual address of the root element of the context.
 #[inline]
-pub(super) fn is_bound(page_dir: NonNull<Table>) -> bool {
-	let physaddr = VirtAddr::from(page_dir).kernel_to_physical().unwrap();
-	register_get!("cr3") == physaddr.0
+pub(super) fn is_bound(root: VirtAddr) -> bool {
+	let physaddr = root.kernel_to_physical().unwrap();
+	register_get!("cr3") as u64 == physaddr.0
 }
 
 /// Invalidate the page at the given address on the current CPU.
@@ -487,8 +494,8 @@ pub(super) unsafe fn free(mut page_dir: NonNull<Table>) {
 	free_table(page_dir);
 }
 
-/// Initializes virtual memory management.
-pub(super) fn init() -> AllocResult<()> {
+/// Enables paging features that are common to 32 bits and PAE paging.
+pub(super) fn init_features() {
 	// Set cr4 flags
 	// Enable GLOBAL flag
 	let mut cr4 = register_get!("cr4") | 1 << 7;
@@ -502,6 +509,11 @@ pub(super) fn init() -> AllocResult<()> {
 	unsafe {
 		register_set!("cr4", cr4);
 	}
+}
+
+/// Initializes virtual memory management.
+pub(super) fn init() -> AllocResult<()> {
+	init_features();
 	// Allocate kernel tables
 	let mut tables = KERNEL_TABLES.lock();
 	for table in &mut *tables {
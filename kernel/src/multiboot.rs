@@ -246,7 +246,7 @@ fn handle_tag(boot_info: &mut BootInfo, tag: &Tag) {
 			boot_info.elf_num = t.num;
 			boot_info.elf_entsize = t.entsize;
 			boot_info.elf_shndx = t.shndx;
-			boot_info.elf_sections = PhysAddr(t.sections.as_ptr() as usize);
+			boot_info.elf_sections = PhysAddr(t.sections.as_ptr() as _);
 		}
 		_ => {}
 	}
@@ -28,11 +28,12 @@ pub mod sockaddr;
 pub mod tcp;
 
 use crate::{
-	file::perm::AccessProfile,
+	file::perm::{AccessProfile, CAP_NET_RAW},
 	net::sockaddr::{SockAddrIn, SockAddrIn6},
+	process::namespace,
 };
 use buff::BuffList;
-use core::{cmp::Ordering, mem::size_of};
+use core::{cmp::Ordering, fmt, fmt::Formatter, mem::size_of};
 use utils::{
 	collections::{hashmap::HashMap, string::String, vec::Vec},
 	errno,
@@ -183,47 +184,78 @@ impl Route {
 	}
 }
 
-/// The list of network interfaces.
-pub static INTERFACES: Mutex<HashMap<String, Arc<Mutex<dyn Interface>>>> =
-	Mutex::new(HashMap::new());
-/// The routing table.
-pub static ROUTING_TABLE: Mutex<Vec<Route>> = Mutex::new(Vec::new());
-
-/// Registers the given network interface.
-///
-/// Arguments:
-/// - `name` is the name of the interface.
-/// - `iface` is the interface to register.
-pub fn register_iface<I: 'static + Interface>(name: String, iface: I) -> EResult<()> {
-	let mut interfaces = INTERFACES.lock();
-
-	let i = Arc::new(Mutex::new(iface))?;
-	interfaces.insert(name, i)?;
-
-	Ok(())
+/// A network namespace, holding its own set of network interfaces and routing table.
+pub struct NetNamespace {
+	/// The ID of the namespace.
+	id: u32,
+	/// The list of network interfaces.
+	interfaces: Mutex<HashMap<String, Arc<Mutex<dyn Interface>>>>,
+	/// The routing table.
+	routing_table: Mutex<Vec<Route>>,
 }
 
-/// Unregisters the network interface with the given name.
-pub fn unregister_iface(name: &[u8]) {
-	let mut interfaces = INTERFACES.lock();
-	interfaces.remove(name);
+impl fmt::Debug for NetNamespace {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("NetNamespace")
+			.field("id", &self.id)
+			.finish_non_exhaustive()
+	}
 }
 
-/// Returns the network interface with the given name.
-///
-/// If the interface doesn't exist, thhe function returns `None`.
-pub fn get_iface(name: &[u8]) -> Option<Arc<Mutex<dyn Interface>>> {
-	INTERFACES.lock().get(name).cloned()
+impl Default for NetNamespace {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
-/// Returns the network interface to be used to transmit a packet to the given destination address.
-pub fn get_iface_for(addr: Address) -> Option<Arc<Mutex<dyn Interface>>> {
-	let routing_table = ROUTING_TABLE.lock();
-	let route = routing_table
-		.iter()
-		.filter(|route| route.is_matching(&addr))
-		.max_by(|a, b| a.cmp_for(b, &addr))?;
-	get_iface(&route.iface)
+impl NetNamespace {
+	/// Creates a new empty namespace.
+	pub fn new() -> Self {
+		Self {
+			id: namespace::alloc_id(),
+			interfaces: Mutex::new(HashMap::new()),
+			routing_table: Mutex::new(Vec::new()),
+		}
+	}
+
+	/// Returns the ID of the namespace.
+	pub fn get_id(&self) -> u32 {
+		self.id
+	}
+
+	/// Registers the given network interface.
+	///
+	/// Arguments:
+	/// - `name` is the name of the interface.
+	/// - `iface` is the interface to register.
+	pub fn register_iface<I: 'static + Interface>(&self, name: String, iface: I) -> EResult<()> {
+		let i = Arc::new(Mutex::new(iface))?;
+		self.interfaces.lock().insert(name, i)?;
+		Ok(())
+	}
+
+	/// Unregisters the network interface with the given name.
+	pub fn unregister_iface(&self, name: &[u8]) {
+		self.interfaces.lock().remove(name);
+	}
+
+	/// Returns the network interface with the given name.
+	///
+	/// If the interface doesn't exist, the function returns `None`.
+	pub fn get_iface(&self, name: &[u8]) -> Option<Arc<Mutex<dyn Interface>>> {
+		self.interfaces.lock().get(name).cloned()
+	}
+
+	/// Returns the network interface to be used to transmit a packet to the given destination
+	/// address.
+	pub fn get_iface_for(&self, addr: Address) -> Option<Arc<Mutex<dyn Interface>>> {
+		let routing_table = self.routing_table.lock();
+		let route = routing_table
+			.iter()
+			.filter(|route| route.is_matching(&addr))
+			.max_by(|a, b| a.cmp_for(b, &addr))?;
+		self.get_iface(&route.iface)
+	}
 }
 
 /// Enumeration of socket domains.
@@ -284,7 +316,7 @@ impl AccessProfile {
 	/// Tells whether the agent has the permission to use the socket domain.
 	pub fn can_use_sock_domain(&self, domain: &SocketDomain) -> bool {
 		match domain {
-			SocketDomain::AfPacket => self.is_privileged(),
+			SocketDomain::AfPacket => self.has_cap(CAP_NET_RAW),
 			_ => true,
 		}
 	}
@@ -340,7 +372,7 @@ impl AccessProfile {
 	/// Tells whether the agent has the permission to use the socket type.
 	pub fn can_use_sock_type(&self, sock_type: &SocketType) -> bool {
 		match sock_type {
-			SocketType::SockRaw => self.is_privileged(),
+			SocketType::SockRaw => self.has_cap(CAP_NET_RAW),
 			_ => true,
 		}
 	}
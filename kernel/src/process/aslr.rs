@@ -0,0 +1,83 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Address Space Layout Randomization (ASLR) places the regions of a program's memory space at
+//! random addresses, making the exploitation of memory corruption bugs harder.
+//!
+//! The level of randomization is set through `/proc/sys/kernel/randomize_va_space`:
+//! - `0`: no randomization
+//! - `1`: the stack, the mappings area, the vDSO, PIE programs and interpreters are randomized
+//! - `2`: the `brk` area is randomized as well
+//!
+//! A process can disable randomization for the programs it executes with the
+//! [`ADDR_NO_RANDOMIZE`] personality flag.
+
+use crate::crypto::rand;
+use core::{
+	mem::size_of,
+	sync::atomic::{AtomicU8, Ordering::Relaxed},
+};
+use utils::{errno, errno::EResult, limits::PAGE_SIZE};
+
+/// Personality flag disabling address space layout randomization.
+pub const ADDR_NO_RANDOMIZE: u32 = 0x0040000;
+
+/// The maximum level of randomization.
+pub const LEVEL_MAX: u8 = 2;
+
+/// The number of pages over which the mappings area is randomized.
+pub const MMAP_RND_PAGES: usize = 0x1000;
+/// The number of pages over which the top of the stack is randomized.
+pub const STACK_RND_PAGES: usize = 0x800;
+/// The number of pages over which the beginning of the `brk` area is randomized.
+pub const BRK_RND_PAGES: usize = 0x2000;
+
+/// The current level of randomization.
+static LEVEL: AtomicU8 = AtomicU8::new(LEVEL_MAX);
+
+/// Returns the current level of randomization.
+pub fn get_level() -> u8 {
+	LEVEL.load(Relaxed)
+}
+
+/// Sets the level of randomization.
+///
+/// If the level is invalid, the function returns [`errno::EINVAL`].
+pub fn set_level(level: u8) -> EResult<()> {
+	if level > LEVEL_MAX {
+		return Err(errno!(EINVAL));
+	}
+	LEVEL.store(level, Relaxed);
+	Ok(())
+}
+
+/// Returns the level of randomization for a program executed with the given `personality`.
+pub fn level_for(personality: u32) -> u8 {
+	if personality & ADDR_NO_RANDOMIZE != 0 {
+		0
+	} else {
+		get_level()
+	}
+}
+
+/// Returns a random page-aligned offset in bytes, lower than `pages` pages.
+pub fn random_offset(pages: usize) -> usize {
+	let mut buf = [0; size_of::<usize>()];
+	rand::fill(&mut buf);
+	(usize::from_ne_bytes(buf) % pages) * PAGE_SIZE
+}
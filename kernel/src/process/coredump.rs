@@ -0,0 +1,607 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Core dumps, used for post-mortem debugging.
+//!
+//! When a process is killed by a signal whose default action is [`SignalAction::Abort`], the
+//! kernel writes a *core file* describing the state of the process at the time of death.
+//!
+//! The core file is an ELF file of type [`ET_CORE`], containing:
+//! - a `PT_NOTE` segment with the status of the process, its information and its auxiliary vector
+//! - a `PT_LOAD` segment for each memory mapping of the process
+//!
+//! The path to the core file is given by the *core pattern*, which is relative to the working
+//! directory of the process.
+//!
+//! [`SignalAction::Abort`]: super::signal::SignalAction::Abort
+
+use crate::{
+	elf::{
+		ELF32ELFHeader, ELF32ProgramHeader, EI_CLASS, EI_DATA, EI_NIDENT, EI_VERSION, ELFCLASS32,
+		ELFDATA2LSB, EM_386, ET_CORE, PF_R, PF_W, PF_X, PT_LOAD, PT_NOTE,
+	},
+	file::{
+		perm::{Gid, Uid},
+		vfs,
+		vfs::{ResolutionSettings, Resolved},
+		File, FileType, Stat, O_WRONLY,
+	},
+	memory::VirtAddr,
+	process::{
+		mem_space::{MAPPING_FLAG_EXEC, MAPPING_FLAG_WRITE},
+		pid::Pid,
+		ptrace::UserRegs,
+		rlimit::RLIMIT_CORE,
+		signal::Signal,
+		workqueue, Process,
+	},
+	time::{
+		clock::{current_time, CLOCK_REALTIME},
+		unit::TimestampScale,
+	},
+};
+use core::{mem::size_of, num::NonZeroUsize};
+use macros::AnyRepr;
+use utils::{
+	bytes::as_bytes,
+	collections::{path::PathBuf, string::String, vec::Vec},
+	errno,
+	errno::{AllocResult, CollectResult, EResult},
+	format,
+	limits::{PAGE_SIZE, PATH_MAX},
+	lock::Mutex,
+	ptr::arc::Arc,
+	vec, TryClone,
+};
+
+/// The maximum length of the core pattern in bytes.
+pub const CORE_PATTERN_MAX: usize = 128;
+/// The core pattern used when none has been set.
+const DEFAULT_CORE_PATTERN: &[u8] = b"core";
+
+/// Note type: status of the process.
+const NT_PRSTATUS: u32 = 1;
+/// Note type: information about the process.
+const NT_PRPSINFO: u32 = 3;
+/// Note type: auxiliary vector.
+const NT_AUXV: u32 = 6;
+/// The name of the notes' owner.
+const NOTE_NAME: &[u8] = b"CORE\0";
+
+/// The pattern of the path to core files. If empty, [`DEFAULT_CORE_PATTERN`] is used.
+static CORE_PATTERN: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+/// The status of a process, stored in a [`NT_PRSTATUS`] note.
+#[derive(AnyRepr)]
+#[repr(C)]
+struct ELFPrStatus {
+	/// The signal number.
+	si_signo: i32,
+	/// The signal code.
+	si_code: i32,
+	/// The errno associated with the signal.
+	si_errno: i32,
+	/// The current signal.
+	pr_cursig: i16,
+	/// Padding.
+	_pad: u16,
+	/// The set of pending signals.
+	pr_sigpend: u32,
+	/// The set of blocked signals.
+	pr_sighold: u32,
+	/// The process's PID.
+	pr_pid: i32,
+	/// The process's parent PID.
+	pr_ppid: i32,
+	/// The process's group ID.
+	pr_pgrp: i32,
+	/// The process's session ID.
+	pr_sid: i32,
+	/// The user CPU time, in seconds and microseconds.
+	pr_utime: [i32; 2],
+	/// The system CPU time, in seconds and microseconds.
+	pr_stime: [i32; 2],
+	/// The cumulative user CPU time of children.
+	pr_cutime: [i32; 2],
+	/// The cumulative system CPU time of children.
+	pr_cstime: [i32; 2],
+	/// The registers of the process.
+	pr_reg: UserRegs,
+	/// Tells whether the floating point registers are present.
+	pr_fpvalid: i32,
+}
+
+/// Information about a process, stored in a [`NT_PRPSINFO`] note.
+#[derive(AnyRepr)]
+#[repr(C)]
+struct ELFPrPsInfo {
+	/// The numeric state of the process.
+	pr_state: u8,
+	/// The character representing the state of the process.
+	pr_sname: u8,
+	/// Tells whether the process is a zombie.
+	pr_zomb: u8,
+	/// The nice value of the process.
+	pr_nice: i8,
+	/// The process's flags.
+	pr_flag: u32,
+	/// The process's user ID.
+	pr_uid: u16,
+	/// The process's group ID.
+	pr_gid: u16,
+	/// The process's PID.
+	pr_pid: i32,
+	/// The process's parent PID.
+	pr_ppid: i32,
+	/// The process's group ID.
+	pr_pgrp: i32,
+	/// The process's session ID.
+	pr_sid: i32,
+	/// The name of the executable, nul-terminated.
+	pr_fname: [u8; 16],
+	/// The command line, nul-terminated.
+	pr_psargs: [u8; 80],
+}
+
+/// Returns the current core pattern.
+pub fn get_pattern() -> AllocResult<Vec<u8>> {
+	let pattern = CORE_PATTERN.lock();
+	if pattern.is_empty() {
+		Vec::try_from(DEFAULT_CORE_PATTERN)
+	} else {
+		pattern.try_clone()
+	}
+}
+
+/// Sets the core pattern.
+///
+/// If the pattern is longer than [`CORE_PATTERN_MAX`], the function returns [`errno::EINVAL`].
+pub fn set_pattern(pattern: &[u8]) -> EResult<()> {
+	if pattern.len() > CORE_PATTERN_MAX {
+		return Err(errno!(EINVAL));
+	}
+	let pattern = Vec::try_from(pattern)?;
+	*CORE_PATTERN.lock() = pattern;
+	Ok(())
+}
+
+/// Copies `src` into `dst`, truncating it if necessary so that `dst` remains nul-terminated.
+fn copy_cstr(dst: &mut [u8], src: impl Iterator<Item = u8>) {
+	let len = dst.len() - 1;
+	for (d, s) in dst[..len].iter_mut().zip(src) {
+		*d = s;
+	}
+}
+
+/// The values substituted for the specifiers of the core pattern.
+struct PatternValues<'a> {
+	/// The PID of the process.
+	pid: Pid,
+	/// The TID of the process.
+	tid: Pid,
+	/// The real user ID of the process.
+	uid: Uid,
+	/// The real group ID of the process.
+	gid: Gid,
+	/// The signal that killed the process.
+	sig: Signal,
+	/// The hostname.
+	hostname: &'a [u8],
+	/// The name of the executable.
+	exec_name: &'a [u8],
+}
+
+/// Expands the core pattern `pattern` with `values`.
+///
+/// The following specifiers are supported:
+/// - `%%`: a single `%`
+/// - `%p`: the PID of the process
+/// - `%i`: the TID of the process
+/// - `%u`: the real user ID of the process
+/// - `%g`: the real group ID of the process
+/// - `%s`: the number of the signal
+/// - `%t`: the time of the dump, in seconds since the Epoch
+/// - `%h`: the hostname
+/// - `%e`: the name of the executable
+///
+/// Unknown specifiers are dropped.
+///
+/// If the resulting path is longer than [`PATH_MAX`], the function returns
+/// [`errno::ENAMETOOLONG`].
+fn expand(pattern: &[u8], values: &PatternValues) -> EResult<PathBuf> {
+	let mut path = Vec::new();
+	let mut iter = pattern.iter();
+	while let Some(c) = iter.next() {
+		if *c != b'%' {
+			path.push(*c)?;
+			continue;
+		}
+		let Some(spec) = iter.next() else {
+			break;
+		};
+		match spec {
+			b'%' => path.push(b'%')?,
+			b'p' => path.extend_from_slice(format!("{}", values.pid)?.as_bytes())?,
+			b'i' => path.extend_from_slice(format!("{}", values.tid)?.as_bytes())?,
+			b'u' => path.extend_from_slice(format!("{}", values.uid)?.as_bytes())?,
+			b'g' => path.extend_from_slice(format!("{}", values.gid)?.as_bytes())?,
+			b's' => path.extend_from_slice(format!("{}", values.sig.get_id())?.as_bytes())?,
+			b't' => {
+				let ts = current_time(CLOCK_REALTIME, TimestampScale::Second)?;
+				path.extend_from_slice(format!("{ts}")?.as_bytes())?;
+			}
+			b'h' => path.extend_from_slice(values.hostname)?,
+			b'e' => path.extend_from_slice(values.exec_name)?,
+			_ => {}
+		}
+		if path.len() > PATH_MAX {
+			return Err(errno!(ENAMETOOLONG));
+		}
+	}
+	PathBuf::try_from(String::from(path))
+}
+
+/// Expands the core pattern for the process `proc`, killed by `sig`.
+fn expand_pattern(proc: &Process, sig: Signal) -> EResult<PathBuf> {
+	let pattern = get_pattern()?;
+	let hostname = proc.namespaces.uts.hostname.lock();
+	let values = PatternValues {
+		pid: proc.get_pid(),
+		tid: proc.tid,
+		uid: proc.access_profile.uid,
+		gid: proc.access_profile.gid,
+		sig,
+		hostname: &hostname,
+		exec_name: proc.exec_path.file_name().unwrap_or_default(),
+	};
+	expand(&pattern, &values)
+}
+
+/// Appends a note of type `ty` with the descriptor `desc` to `notes`.
+fn push_note(notes: &mut Vec<u8>, ty: u32, desc: &[u8]) -> AllocResult<()> {
+	let header = [NOTE_NAME.len() as u32, desc.len() as u32, ty];
+	notes.extend_from_slice(as_bytes(&header))?;
+	notes.extend_from_slice(NOTE_NAME)?;
+	notes.resize(notes.len().next_multiple_of(4), 0)?;
+	notes.extend_from_slice(desc)?;
+	notes.resize(notes.len().next_multiple_of(4), 0)?;
+	Ok(())
+}
+
+/// Builds the notes of the core file for the process `proc`, killed by `sig`.
+fn build_notes(proc: &Process, sig: Signal) -> EResult<Vec<u8>> {
+	let pid = proc.get_pid() as _;
+	let ppid = proc.get_parent_pid() as _;
+	let pgrp = proc.pgid as _;
+	let sid = proc.sid as _;
+	let mut notes = Vec::new();
+	// Status
+	let utime = &proc.rusage.ru_utime;
+	let stime = &proc.rusage.ru_stime;
+	let prstatus = ELFPrStatus {
+		si_signo: sig.get_id() as _,
+		si_code: 0,
+		si_errno: 0,
+		pr_cursig: sig.get_id() as _,
+		_pad: 0,
+		pr_sigpend: proc.get_pending_signals().0 as _,
+		pr_sighold: proc.sigmask.0 as _,
+		pr_pid: pid,
+		pr_ppid: ppid,
+		pr_pgrp: pgrp,
+		pr_sid: sid,
+		pr_utime: [utime.tv_sec as _, utime.tv_usec as _],
+		pr_stime: [stime.tv_sec as _, stime.tv_usec as _],
+		pr_cutime: [0; 2],
+		pr_cstime: [0; 2],
+		pr_reg: UserRegs::new(&proc.regs, usize::MAX),
+		pr_fpvalid: 0,
+	};
+	push_note(&mut notes, NT_PRSTATUS, as_bytes(&prstatus))?;
+	// Information
+	let mut prpsinfo = ELFPrPsInfo {
+		// The process was running when it received the signal
+		pr_state: 0,
+		pr_sname: b'R',
+		pr_zomb: 0,
+		pr_nice: proc.nice as _,
+		pr_flag: 0,
+		pr_uid: proc.access_profile.uid as _,
+		pr_gid: proc.access_profile.gid as _,
+		pr_pid: pid,
+		pr_ppid: ppid,
+		pr_pgrp: pgrp,
+		pr_sid: sid,
+		pr_fname: [0; 16],
+		pr_psargs: [0; 80],
+	};
+	let name = proc.exec_path.file_name().unwrap_or_default();
+	copy_cstr(&mut prpsinfo.pr_fname, name.iter().cloned());
+	let args = proc.argv.iter().enumerate().flat_map(|(i, arg)| {
+		let sep = (i > 0).then_some(b' ');
+		sep.into_iter().chain(arg.as_bytes().iter().cloned())
+	});
+	copy_cstr(&mut prpsinfo.pr_psargs, args);
+	push_note(&mut notes, NT_PRPSINFO, as_bytes(&prpsinfo))?;
+	// Auxiliary vector
+	if let Some(mem_space) = proc.get_mem_space() {
+		let mem_space = mem_space.lock();
+		let (addr, size) = mem_space.get_auxv();
+		let mut auxv = vec![0u8; size]?;
+		mem_space.peek(addr, &mut auxv);
+		push_note(&mut notes, NT_AUXV, &auxv)?;
+	}
+	Ok(notes)
+}
+
+/// Opens the core file at `path`, creating it if necessary.
+///
+/// An existing file is reused only if it is a regular file owned by the user, in which case it
+/// is truncated.
+fn open_core_file(path: &PathBuf, rs: &ResolutionSettings) -> EResult<Arc<File>> {
+	let ap = &rs.access_profile;
+	let entry = match vfs::resolve_path(path, rs)? {
+		Resolved::Found(entry) => {
+			let stat = entry.stat()?;
+			if stat.get_type() != Some(FileType::Regular) || stat.uid != ap.euid {
+				return Err(errno!(EPERM));
+			}
+			if !ap.can_write_file(&stat) {
+				return Err(errno!(EACCES));
+			}
+			entry
+		}
+		Resolved::Creatable {
+			parent,
+			name,
+		} => {
+			let ts = current_time(CLOCK_REALTIME, TimestampScale::Second)?;
+			vfs::create_file(
+				parent,
+				name,
+				ap,
+				Stat {
+					mode: FileType::Regular.to_mode() | 0o600,
+					ctime: ts,
+					mtime: ts,
+					atime: ts,
+					..Default::default()
+				},
+			)?
+		}
+	};
+	let file = File::open_entry(entry, O_WRONLY)?;
+	file.truncate(0)?;
+	Ok(file)
+}
+
+/// Returns the maximum size of a core file written for `proc`, in bytes.
+///
+/// A limit of zero disables core dumps.
+fn core_limit(proc: &Process) -> u64 {
+	// Unwrap cannot fail since the resource exists
+	proc.rlimits.get(RLIMIT_CORE).unwrap().rlim_cur
+}
+
+/// Writes the core file of the process with PID `pid`, killed by `sig`.
+///
+/// The file is truncated if it exceeds the `RLIMIT_CORE` limit of the process.
+fn dump(pid: Pid, sig: Signal) -> EResult<()> {
+	let proc_mutex = Process::get_by_pid(pid).ok_or_else(|| errno!(ESRCH))?;
+	let (limit, mem_space, path, rs, notes) = {
+		let proc = proc_mutex.lock();
+		let limit = core_limit(&proc);
+		let mem_space = proc
+			.get_mem_space()
+			.cloned()
+			.ok_or_else(|| errno!(EINVAL))?;
+		let path = expand_pattern(&proc, sig)?;
+		let rs = ResolutionSettings {
+			create: true,
+			..ResolutionSettings::for_process(&proc, false)
+		};
+		let notes = build_notes(&proc, sig)?;
+		(limit, mem_space, path, rs, notes)
+	};
+	// The list of mappings, with their address, size and flags
+	let mappings = mem_space
+		.lock()
+		.iter_mappings()
+		.map(|m| (VirtAddr::from(m.get_begin()), m.get_size(), m.get_flags()))
+		.collect::<CollectResult<Vec<(VirtAddr, NonZeroUsize, u8)>>>()
+		.0?;
+	// Compute the layout of the file
+	let phnum = mappings.len() + 1;
+	let notes_off = size_of::<ELF32ELFHeader>() + phnum * size_of::<ELF32ProgramHeader>();
+	let data_off = (notes_off + notes.len()).next_multiple_of(PAGE_SIZE);
+	let file = open_core_file(&path, &rs)?;
+	// The file is truncated to the size limit
+	let write = |off: usize, buf: &[u8]| -> EResult<()> {
+		let len = limit.saturating_sub(off as u64).min(buf.len() as u64) as usize;
+		let buf = &buf[..len];
+		if buf.is_empty() {
+			return Ok(());
+		}
+		let len = file.ops.write(&file, off as _, buf)?;
+		if len < buf.len() {
+			return Err(errno!(ENOSPC));
+		}
+		Ok(())
+	};
+	// Headers
+	let mut e_ident = [0u8; EI_NIDENT];
+	e_ident[..4].copy_from_slice(b"\x7fELF");
+	e_ident[EI_CLASS] = ELFCLASS32;
+	e_ident[EI_DATA] = ELFDATA2LSB;
+	e_ident[EI_VERSION] = 1;
+	let ehdr = ELF32ELFHeader {
+		e_ident,
+		e_type: ET_CORE,
+		e_machine: EM_386,
+		e_version: 1,
+		e_entry: 0,
+		e_phoff: size_of::<ELF32ELFHeader>() as _,
+		e_shoff: 0,
+		e_flags: 0,
+		e_ehsize: size_of::<ELF32ELFHeader>() as _,
+		e_phentsize: size_of::<ELF32ProgramHeader>() as _,
+		e_phnum: phnum as _,
+		e_shentsize: 0,
+		e_shnum: 0,
+		e_shstrndx: 0,
+	};
+	write(0, as_bytes(&ehdr))?;
+	let mut off = size_of::<ELF32ELFHeader>();
+	let note_phdr = ELF32ProgramHeader {
+		p_type: PT_NOTE,
+		p_offset: notes_off as _,
+		p_vaddr: 0,
+		p_paddr: 0,
+		p_filesz: notes.len() as _,
+		p_memsz: 0,
+		p_flags: 0,
+		p_align: 4,
+	};
+	write(off, as_bytes(&note_phdr))?;
+	off += size_of::<ELF32ProgramHeader>();
+	let mut seg_off = data_off;
+	for (addr, size, flags) in &mappings {
+		let size = size.get() * PAGE_SIZE;
+		let mut p_flags = PF_R;
+		if flags & MAPPING_FLAG_WRITE != 0 {
+			p_flags |= PF_W;
+		}
+		if flags & MAPPING_FLAG_EXEC != 0 {
+			p_flags |= PF_X;
+		}
+		let phdr = ELF32ProgramHeader {
+			p_type: PT_LOAD,
+			p_offset: seg_off as _,
+			p_vaddr: addr.0 as _,
+			p_paddr: 0,
+			p_filesz: size as _,
+			p_memsz: size as _,
+			p_flags,
+			p_align: PAGE_SIZE as _,
+		};
+		write(off, as_bytes(&phdr))?;
+		off += size_of::<ELF32ProgramHeader>();
+		seg_off += size;
+	}
+	// Notes
+	write(notes_off, &notes)?;
+	// Segments, page by page since the memory space cannot be locked while writing
+	let mut page = vec![0u8; PAGE_SIZE]?;
+	let mut seg_off = data_off;
+	for (addr, size, _) in &mappings {
+		for i in 0..size.get() {
+			if seg_off as u64 >= limit {
+				return Ok(());
+			}
+			mem_space.lock().peek(*addr + i * PAGE_SIZE, &mut page);
+			write(seg_off, &page)?;
+			seg_off += PAGE_SIZE;
+		}
+	}
+	Ok(())
+}
+
+/// Schedules the dump of the core of `proc`, killed by `sig`, which must be a zombie.
+///
+/// Since writing a file requires sleeping, the dump is deferred to a work queue. The process is
+/// made waitable once the dump is over.
+///
+/// If no core file is to be written, the function returns `false` and the caller is responsible
+/// for making the process waitable.
+pub(super) fn schedule(proc: &mut Process, sig: Signal) -> bool {
+	let limit = core_limit(proc);
+	if limit == 0 || !proc.dumpable || proc.is_kernel_thread() || proc.get_mem_space().is_none() {
+		return false;
+	}
+	let pid = proc.get_pid();
+	let res = workqueue::schedule(move || {
+		let res = dump(pid, sig);
+		if let Some(proc) = Process::get_by_pid(pid) {
+			let mut proc = proc.lock();
+			proc.coredump = res.is_ok();
+			proc.set_waitable(sig.get_id());
+		}
+	});
+	if res.is_err() {
+		return false;
+	}
+	// Do not let the parent collect the process before the dump is over
+	proc.clear_waitable();
+	true
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use core::array;
+
+	const VALUES: PatternValues = PatternValues {
+		pid: 42,
+		tid: 43,
+		uid: 1000,
+		gid: 100,
+		sig: Signal::SIGSEGV,
+		hostname: b"maestro",
+		exec_name: b"prog",
+	};
+
+	#[test_case]
+	fn core_pattern_expand() {
+		let path = expand(b"core.%p.%s.%e", &VALUES).unwrap();
+		assert_eq!(path, PathBuf::try_from(b"core.42.11.prog").unwrap());
+		let path = expand(b"%%p-%i-%u-%g-%h", &VALUES).unwrap();
+		assert_eq!(path, PathBuf::try_from(b"%p-43-1000-100-maestro").unwrap());
+		// Unknown and truncated specifiers are dropped
+		let path = expand(b"core%z%", &VALUES).unwrap();
+		assert_eq!(path, PathBuf::try_from(b"core").unwrap());
+	}
+
+	#[test_case]
+	fn core_pattern_too_long() {
+		let name = [b'a'; 255];
+		let values = PatternValues {
+			exec_name: &name,
+			..VALUES
+		};
+		let pattern: [u8; CORE_PATTERN_MAX] = array::from_fn(|i| b"%e"[i % 2]);
+		assert_eq!(
+			expand(&pattern, &values).unwrap_err().as_int(),
+			errno::ENAMETOOLONG
+		);
+	}
+
+	#[test_case]
+	fn core_notes() {
+		let mut notes = Vec::new();
+		push_note(&mut notes, NT_AUXV, &[1, 2, 3, 4, 5]).unwrap();
+		// Header, then the name and the descriptor, each padded to 4 bytes
+		assert_eq!(notes.len(), 12 + 8 + 8);
+		assert_eq!(&notes[..12], as_bytes(&[5u32, 5, NT_AUXV]));
+		assert_eq!(&notes[12..20], b"CORE\0\0\0\0");
+		assert_eq!(&notes[20..], &[1, 2, 3, 4, 5, 0, 0, 0]);
+		// Names longer than the destination are truncated and nul-terminated
+		let mut buf = [0u8; 4];
+		copy_cstr(&mut buf[..], b"abcdef".iter().cloned());
+		assert_eq!(buf, *b"abc\0");
+	}
+}
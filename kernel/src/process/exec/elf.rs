@@ -20,19 +20,25 @@
 
 use super::vdso;
 use crate::{
-	cpu, elf,
+	cpu,
+	crypto::rand,
+	elf,
 	elf::{
 		parser::ELFParser,
 		relocation::{ELF32Rel, ELF32Rela, Relocation, GOT_SYM},
 		ELF32ProgramHeader,
 	},
 	file::{perm::AccessProfile, vfs, FileType},
+	memory,
 	memory::{vmem, VirtAddr},
 	process,
 	process::{
-		exec::{vdso::MappedVDSO, ExecInfo, Executor, ProgramImage},
+		aslr,
+		exec::{vdso::MappedVDSO, BinFmt, BinPrm, ExecInfo, ProgramImage},
 		mem_space,
 		mem_space::{residence::MapResidence, MapConstraint, MemSpace},
+		rlimit::RLIMIT_STACK,
+		rusage, signal,
 	},
 };
 use core::{
@@ -106,6 +112,11 @@ const AT_EXECFN: i32 = 31;
 const AT_SYSINFO: i32 = 32;
 /// A pointer to the beginning of the vDSO ELF image.
 const AT_SYSINFO_EHDR: i32 = 33;
+/// The minimal stack size required to deliver a signal.
+const AT_MINSIGSTKSZ: i32 = 51;
+
+/// The address at which position-independent executables are loaded, before randomization.
+const ET_DYN_BASE: usize = 0x400000;
 
 /// Information returned after loading an ELF program used to finish
 /// initialization.
@@ -145,6 +156,8 @@ enum AuxEntryDescValue {
 	Number(usize),
 	/// A string of bytes.
 	String(&'static [u8]),
+	/// Random bytes, written as is.
+	Random([u8; 16]),
 }
 
 /// Structure describing an auxiliary vector entry.
@@ -168,11 +181,13 @@ impl AuxEntryDesc {
 /// Builds an auxiliary vector.
 ///
 /// Arguments:
-/// - `exec_info` is the set of execution information.
+/// - `ap` is the access profile the program runs with.
+/// - `secure` tells whether the program runs in secure mode.
 /// - `load_info` is the set of ELF load information.
 /// - `vdso` is the set of vDSO information.
 fn build_auxiliary(
-	exec_info: &ExecInfo,
+	ap: &AccessProfile,
+	secure: bool,
 	load_info: &ELFLoadInfo,
 	vdso: &MappedVDSO,
 ) -> EResult<Vec<AuxEntryDesc>> {
@@ -213,23 +228,23 @@ fn build_auxiliary(
 	aux.push(AuxEntryDesc::new(AT_NOTELF, AuxEntryDescValue::Number(0)))?;
 	aux.push(AuxEntryDesc::new(
 		AT_UID,
-		AuxEntryDescValue::Number(exec_info.path_resolution.access_profile.uid as _),
+		AuxEntryDescValue::Number(ap.uid as _),
 	))?;
 	aux.push(AuxEntryDesc::new(
 		AT_EUID,
-		AuxEntryDescValue::Number(exec_info.path_resolution.access_profile.euid as _),
+		AuxEntryDescValue::Number(ap.euid as _),
 	))?;
 	aux.push(AuxEntryDesc::new(
 		AT_GID,
-		AuxEntryDescValue::Number(exec_info.path_resolution.access_profile.gid as _),
+		AuxEntryDescValue::Number(ap.gid as _),
 	))?;
 	aux.push(AuxEntryDesc::new(
 		AT_EGID,
-		AuxEntryDescValue::Number(exec_info.path_resolution.access_profile.egid as _),
+		AuxEntryDescValue::Number(ap.egid as _),
 	))?;
 	aux.push(AuxEntryDesc::new(
 		AT_PLATFORM,
-		AuxEntryDescValue::String(crate::NAME.as_bytes()),
+		AuxEntryDescValue::String(cpu::get_platform()),
 	))?;
 
 	let hwcap = cpu::get_hwcap();
@@ -237,16 +252,29 @@ fn build_auxiliary(
 		AT_HWCAP,
 		AuxEntryDescValue::Number(hwcap as _),
 	))?;
+	aux.push(AuxEntryDesc::new(
+		AT_HWCAP2,
+		AuxEntryDescValue::Number(cpu::get_hwcap2() as _),
+	))?;
+	aux.push(AuxEntryDesc::new(
+		AT_CLKTCK,
+		AuxEntryDescValue::Number(rusage::CLK_TCK as _),
+	))?;
 
-	aux.push(AuxEntryDesc::new(AT_SECURE, AuxEntryDescValue::Number(0)))?; // TODO
+	aux.push(AuxEntryDesc::new(
+		AT_SECURE,
+		AuxEntryDescValue::Number(secure as _),
+	))?;
 	aux.push(AuxEntryDesc::new(
 		AT_BASE_PLATFORM,
 		AuxEntryDescValue::String(crate::NAME.as_bytes()),
 	))?;
+	let mut random = [0; 16];
+	rand::fill(&mut random);
 	aux.push(AuxEntryDesc::new(
 		AT_RANDOM,
-		AuxEntryDescValue::String(&[0; 16]),
-	))?; // TODO
+		AuxEntryDescValue::Random(random),
+	))?;
 	aux.push(AuxEntryDesc::new(
 		AT_EXECFN,
 		AuxEntryDescValue::String("TODO\0".as_bytes()),
@@ -261,6 +289,10 @@ fn build_auxiliary(
 		AT_SYSINFO_EHDR,
 		AuxEntryDescValue::Number(vdso.begin.0),
 	))?;
+	aux.push(AuxEntryDesc::new(
+		AT_MINSIGSTKSZ,
+		AuxEntryDescValue::Number(signal::frame_size()),
+	))?;
 
 	// End
 	aux.push(AuxEntryDesc::new(AT_NULL, AuxEntryDescValue::Number(0)))?;
@@ -286,20 +318,27 @@ fn read_exec_file(file: &vfs::Entry, ap: &AccessProfile) -> EResult<Vec<u8>> {
 }
 
 /// The program executor for ELF files.
-pub struct ELFExecutor<'s> {
+struct ELFExecutor<'s> {
 	/// Execution information.
-	info: ExecInfo<'s>,
+	info: &'s ExecInfo<'s>,
+	/// The access profile the program runs with.
+	access_profile: &'s AccessProfile,
+	/// Tells whether the program runs in secure mode.
+	secure: bool,
+	/// The level of address space layout randomization.
+	aslr_level: u8,
 }
 
 impl<'s> ELFExecutor<'s> {
-	/// Creates a new instance to execute the given program.
+	/// Creates a new instance to execute the program described by `prm`.
 	///
-	/// Arguments:
-	/// - `uid` is the User ID of the executing user.
-	/// - `gid` is the Group ID of the executing user.
-	pub fn new(info: ExecInfo<'s>) -> EResult<Self> {
+	/// The credentials of the program must have been computed with [`BinPrm::prepare_creds`].
+	fn new(prm: &'s BinPrm<'s>) -> EResult<Self> {
 		Ok(Self {
-			info,
+			info: &prm.info,
+			access_profile: &prm.access_profile,
+			secure: prm.secure,
+			aslr_level: aslr::level_for(prm.info.personality),
 		})
 	}
 
@@ -316,8 +355,10 @@ impl<'s> ELFExecutor<'s> {
 		// The size of the block storing the arguments and environment
 		let mut info_block_size = 0;
 		for a in aux {
-			if let AuxEntryDescValue::String(slice) = a.a_val {
-				info_block_size += slice.len() + 1;
+			match &a.a_val {
+				AuxEntryDescValue::Number(_) => {}
+				AuxEntryDescValue::String(slice) => info_block_size += slice.len() + 1,
+				AuxEntryDescValue::Random(bytes) => info_block_size += bytes.len(),
 			}
 		}
 		for e in envp {
@@ -420,6 +461,13 @@ impl<'s> ELFExecutor<'s> {
 					info_off += len + 1;
 					val
 				}
+				AuxEntryDescValue::Random(bytes) => {
+					let val = &info_slice[info_off] as *const _ as _;
+					let len = bytes.len();
+					info_slice[info_off..(info_off + len)].copy_from_slice(&bytes);
+					info_off += len;
+					val
+				}
 			};
 			// Set the entry
 			stack_slice[stack_off] = a.a_type as _;
@@ -565,7 +613,11 @@ impl<'s> ELFExecutor<'s> {
 			let interp_image =
 				read_exec_file(&interp_file, &self.info.path_resolution.access_profile)?;
 			let interp_elf = ELFParser::new(interp_image.as_slice())?;
-			let i_load_base = load_end as _; // TODO ASLR
+			let mut i_load_base = VirtAddr::from(load_end).align_to(PAGE_SIZE);
+			if self.aslr_level >= 1 {
+				i_load_base = i_load_base + aslr::random_offset(aslr::MMAP_RND_PAGES);
+			}
+			let i_load_base = i_load_base.as_ptr();
 			let load_info = self.load_elf(&interp_elf, mem_space, i_load_base, true)?;
 			interp_load_base = Some(i_load_base);
 			interp_entry = Some(load_base.wrapping_add(elf.hdr().e_entry as _));
@@ -628,12 +680,10 @@ impl<'s> ELFExecutor<'s> {
 			interp_entry,
 		})
 	}
-}
 
-impl<'s> Executor for ELFExecutor<'s> {
+	/// Builds a program image from the ELF file `file`.
 	// TODO Ensure there is no way to write in kernel space (check segments position
 	// and relocations)
-	// TODO Handle suid and sgid
 	fn build_image(&self, file: &vfs::Entry) -> EResult<ProgramImage> {
 		// The ELF file image
 		let image = read_exec_file(file, &self.info.path_resolution.access_profile)?;
@@ -642,25 +692,45 @@ impl<'s> Executor for ELFExecutor<'s> {
 
 		// The process's new memory space
 		let mut mem_space = MemSpace::new()?;
+		mem_space.set_limits(&self.info.rlimits);
+		let randomize = self.aslr_level >= 1;
+		let random_offset = |pages| {
+			if randomize {
+				aslr::random_offset(pages)
+			} else {
+				0
+			}
+		};
+		mem_space.set_mmap_base(memory::ALLOC_BEGIN + random_offset(aslr::MMAP_RND_PAGES));
 
-		// Load the ELF
-		let load_info = self.load_elf(&parser, &mut mem_space, null_mut(), false)?;
-
-		// The user stack
+		// Load the ELF. Position-independent executables are relocated
+		let load_base = if parser.hdr().e_type == elf::ET_DYN {
+			ptr::with_exposed_provenance_mut(ET_DYN_BASE + random_offset(aslr::MMAP_RND_PAGES))
+		} else {
+			null_mut()
+		};
+		let load_info = self.load_elf(&parser, &mut mem_space, load_base, false)?;
+
+		// The size of the user stack in pages, according to the soft limit
+		let stack_size = self.info.rlimits.get(RLIMIT_STACK).unwrap().rlim_cur / PAGE_SIZE as u64;
+		let stack_size = stack_size.clamp(1, process::USER_STACK_MAX_SIZE as _) as usize;
+		// The user stack, placed at the top of the userspace
+		let stack_begin =
+			mem_space::STACK_TOP - stack_size * PAGE_SIZE - random_offset(aslr::STACK_RND_PAGES);
 		let user_stack = mem_space
 			.map(
-				MapConstraint::None,
-				process::USER_STACK_SIZE.try_into().unwrap(),
+				MapConstraint::Hint(stack_begin),
+				stack_size.try_into().unwrap(),
 				process::USER_STACK_FLAGS,
 				MapResidence::Normal,
 			)?
-			.wrapping_add(process::USER_STACK_SIZE * PAGE_SIZE);
+			.wrapping_add(stack_size * PAGE_SIZE);
 
 		// Map the vDSO
-		let vdso = vdso::map(&mut mem_space)?;
+		let vdso = vdso::map(&mut mem_space, randomize)?;
 
 		// The auxiliary vector
-		let aux = build_auxiliary(&self.info, &load_info, &vdso)?;
+		let aux = build_auxiliary(self.access_profile, self.secure, &load_info, &vdso)?;
 		// The size in bytes of the initial data on the stack
 		let init_stack_size = Self::get_init_stack_size(&self.info.argv, &self.info.envp, &aux).1;
 		// Pre-allocate pages on the user stack to write the initial data
@@ -668,7 +738,7 @@ impl<'s> Executor for ELFExecutor<'s> {
 			// The number of pages to allocate on the user stack to write the initial data
 			let pages_count = init_stack_size.div_ceil(PAGE_SIZE);
 			// Check the data does not exceed the stack's size
-			if unlikely(pages_count >= process::USER_STACK_SIZE) {
+			if unlikely(pages_count >= stack_size) {
 				return Err(errno!(ENOMEM));
 			}
 			// Allocate the pages on the stack to write the initial data
@@ -677,8 +747,15 @@ impl<'s> Executor for ELFExecutor<'s> {
 			mem_space.alloc(begin, len)?;
 		}
 		// The initial address for `brk`
-		let brk = VirtAddr::from(load_info.load_end).align_to(PAGE_SIZE);
+		let mut brk = VirtAddr::from(load_info.load_end).align_to(PAGE_SIZE);
+		if self.aslr_level >= 2 {
+			brk = brk + aslr::random_offset(aslr::BRK_RND_PAGES);
+		}
 		mem_space.set_brk_init(brk);
+		// The auxiliary vector is located after argc, argv and envp, which are null-terminated
+		let auxv_off = (self.info.argv.len() + self.info.envp.len() + 3) * size_of::<u32>();
+		let auxv_addr = VirtAddr::from(user_stack) - init_stack_size + auxv_off;
+		mem_space.set_auxv(auxv_addr, aux.len() * size_of::<AuxEntry>());
 		// Initialize the userspace stack
 		unsafe {
 			vmem::switch(mem_space.get_vmem(), move || {
@@ -706,3 +783,21 @@ impl<'s> Executor for ELFExecutor<'s> {
 		})
 	}
 }
+
+/// The ELF binary format.
+pub struct ELFFormat;
+
+impl BinFmt for ELFFormat {
+	fn get_name(&self) -> &'static [u8] {
+		b"elf"
+	}
+
+	fn load(&self, prm: &mut BinPrm) -> EResult<Option<ProgramImage>> {
+		if !prm.header().starts_with(b"\x7fELF") {
+			return Err(errno!(ENOEXEC));
+		}
+		prm.prepare_creds()?;
+		let exec = ELFExecutor::new(prm)?;
+		exec.build_image(prm.get_file()).map(Some)
+	}
+}
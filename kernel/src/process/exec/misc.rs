@@ -0,0 +1,319 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Miscellaneous binary formats, associating programs with an interpreter according to rules
+//! registered at runtime (`binfmt_misc`).
+//!
+//! A rule is registered with a string of the form
+//! `:name:type:offset:magic:mask:interpreter:flags`, where the first character is the delimiter,
+//! which may be any character:
+//! - `name` is the unique name of the rule
+//! - `type` is `M` to recognize programs by a magic value, or `E` to recognize them by their file
+//!   extension
+//! - `offset` is the offset of the magic value in the file, in decimal. If empty, the offset is
+//!   zero
+//! - `magic` is the magic value, or the file extension without the dot
+//! - `mask` is an optional mask applied to the file's content before comparing it with the magic
+//!   value. It must have the same length as the magic value
+//! - `interpreter` is the path of the interpreter
+//! - `flags` is a set of flags. The only supported flag is `P`, which passes the original first
+//!   argument of the program to the interpreter
+//!
+//! The magic value and the mask may contain bytes written as `\xHH`, in hexadecimal.
+
+use super::{BinFmt, BinPrm, ProgramImage, BINPRM_BUF_SIZE};
+use utils::{
+	collections::{
+		path::{Path, PathBuf},
+		string::String,
+		vec::Vec,
+	},
+	errno,
+	errno::EResult,
+	lock::Mutex,
+	TryClone,
+};
+
+/// The maximum length of a registration string.
+pub const REGISTER_MAX: usize = 1920;
+
+/// The way a rule recognizes programs.
+#[derive(Debug)]
+enum Matcher {
+	/// The program is recognized by a magic value in its content.
+	Magic {
+		/// The offset of the magic value in the file.
+		offset: usize,
+		/// The magic value.
+		magic: Vec<u8>,
+		/// The mask applied to the content of the file before comparison.
+		mask: Option<Vec<u8>>,
+	},
+	/// The program is recognized by its file extension, without the dot.
+	Extension(Vec<u8>),
+}
+
+/// A rule associating programs with an interpreter.
+#[derive(Debug)]
+pub struct Rule {
+	/// The name of the rule.
+	name: String,
+	/// The way the rule recognizes programs.
+	matcher: Matcher,
+	/// The path to the interpreter.
+	interpreter: PathBuf,
+	/// Tells whether the original first argument of the program is passed to the interpreter.
+	preserve_argv0: bool,
+}
+
+/// Decodes the `\xHH` escape sequences in `s`.
+fn unescape(s: &[u8]) -> EResult<Vec<u8>> {
+	let mut res = Vec::with_capacity(s.len())?;
+	let mut i = 0;
+	while i < s.len() {
+		if let Some(hex) = s[i..].strip_prefix(b"\\x") {
+			let byte = hex
+				.get(..2)
+				.and_then(|h| core::str::from_utf8(h).ok())
+				.and_then(|h| u8::from_str_radix(h, 16).ok())
+				.ok_or_else(|| errno!(EINVAL))?;
+			res.push(byte)?;
+			i += 4;
+		} else {
+			res.push(s[i])?;
+			i += 1;
+		}
+	}
+	Ok(res)
+}
+
+impl Rule {
+	/// Parses a rule from its registration string `s`.
+	///
+	/// If the string is invalid, the function returns [`errno::EINVAL`].
+	pub fn parse(s: &[u8]) -> EResult<Self> {
+		let s = s.strip_suffix(b"\n").unwrap_or(s);
+		if s.len() > REGISTER_MAX {
+			return Err(errno!(EINVAL));
+		}
+		let (delim, s) = s.split_first().ok_or_else(|| errno!(EINVAL))?;
+		let mut fields = s.split(|c| c == delim);
+		let mut next = || fields.next().ok_or_else(|| errno!(EINVAL));
+		let (name, kind, offset, magic, mask, interpreter, flags) = (
+			next()?,
+			next()?,
+			next()?,
+			next()?,
+			next()?,
+			next()?,
+			next()?,
+		);
+		if fields.next().is_some() {
+			return Err(errno!(EINVAL));
+		}
+		if name.is_empty() || name == b"." || name == b".." || name.contains(&b'/') {
+			return Err(errno!(EINVAL));
+		}
+		let matcher = match kind {
+			b"M" => {
+				let offset: usize = if offset.is_empty() {
+					0
+				} else {
+					core::str::from_utf8(offset)
+						.ok()
+						.and_then(|o| o.parse().ok())
+						.ok_or_else(|| errno!(EINVAL))?
+				};
+				let magic = unescape(magic)?;
+				let mask = (!mask.is_empty()).then(|| unescape(mask)).transpose()?;
+				let invalid_mask = mask.as_ref().is_some_and(|m| m.len() != magic.len());
+				let end = offset.checked_add(magic.len());
+				if magic.is_empty() || invalid_mask || end.is_none_or(|e| e > BINPRM_BUF_SIZE) {
+					return Err(errno!(EINVAL));
+				}
+				Matcher::Magic {
+					offset,
+					magic,
+					mask,
+				}
+			}
+			b"E" => {
+				if magic.is_empty() || magic.contains(&b'/') {
+					return Err(errno!(EINVAL));
+				}
+				Matcher::Extension(Vec::try_from(magic)?)
+			}
+			_ => return Err(errno!(EINVAL)),
+		};
+		if interpreter.is_empty() {
+			return Err(errno!(EINVAL));
+		}
+		let mut preserve_argv0 = false;
+		for f in flags {
+			match f {
+				b'P' => preserve_argv0 = true,
+				_ => return Err(errno!(EINVAL)),
+			}
+		}
+		Ok(Self {
+			name: String::try_from(name)?,
+			matcher,
+			interpreter: PathBuf::try_from(interpreter)?,
+			preserve_argv0,
+		})
+	}
+
+	/// Tells whether the rule recognizes the program at `path`, whose content begins with
+	/// `header`.
+	fn matches(&self, path: &Path, header: &[u8]) -> bool {
+		match &self.matcher {
+			Matcher::Magic {
+				offset,
+				magic,
+				mask,
+			} => {
+				let Some(content) = header.get(*offset..(*offset + magic.len())) else {
+					return false;
+				};
+				match mask {
+					Some(mask) => content
+						.iter()
+						.zip(magic.iter())
+						.zip(mask.iter())
+						.all(|((c, m), k)| c & k == m & k),
+					None => content == magic.as_slice(),
+				}
+			}
+			Matcher::Extension(ext) => path
+				.file_name()
+				.and_then(|name| {
+					name.iter()
+						.rposition(|c| *c == b'.')
+						.map(|i| &name[i + 1..])
+				})
+				.is_some_and(|e| e == ext.as_slice()),
+		}
+	}
+}
+
+/// The state of miscellaneous binary formats.
+struct MiscState {
+	/// Tells whether rules are applied.
+	enabled: bool,
+	/// The registered rules.
+	rules: Vec<Rule>,
+}
+
+/// The state of miscellaneous binary formats.
+static STATE: Mutex<MiscState> = Mutex::new(MiscState {
+	enabled: true,
+	rules: Vec::new(),
+});
+
+/// Registers the rule `rule`.
+///
+/// If a rule with the same name already exists, the function returns [`errno::EEXIST`].
+pub fn register(rule: Rule) -> EResult<()> {
+	let mut state = STATE.lock();
+	if state.rules.iter().any(|r| r.name == rule.name) {
+		return Err(errno!(EEXIST));
+	}
+	state.rules.push(rule)?;
+	Ok(())
+}
+
+/// Removes every registered rule.
+pub fn clear() {
+	STATE.lock().rules.clear();
+}
+
+/// Tells whether rules are applied.
+pub fn is_enabled() -> bool {
+	STATE.lock().enabled
+}
+
+/// Sets whether rules are applied.
+pub fn set_enabled(enabled: bool) {
+	STATE.lock().enabled = enabled;
+}
+
+/// The miscellaneous binary format.
+pub struct MiscFormat;
+
+impl BinFmt for MiscFormat {
+	fn get_name(&self) -> &'static [u8] {
+		b"misc"
+	}
+
+	fn load(&self, prm: &mut BinPrm) -> EResult<Option<ProgramImage>> {
+		let (interpreter, preserve_argv0) = {
+			let state = STATE.lock();
+			if !state.enabled {
+				return Err(errno!(ENOEXEC));
+			}
+			let rule = state
+				.rules
+				.iter()
+				.find(|r| r.matches(prm.get_path(), prm.header()))
+				.ok_or_else(|| errno!(ENOEXEC))?;
+			(rule.interpreter.try_clone()?, rule.preserve_argv0)
+		};
+		prm.set_interpreter(&interpreter, &[], preserve_argv0)?;
+		Ok(None)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn misc_rule_magic() {
+		let rule = Rule::parse(b":wasm:M::\\x00asm::/usr/bin/wasm-run:\n").unwrap();
+		assert!(rule.matches(Path::new(b"a.out").unwrap(), b"\0asm\x01\0\0\0"));
+		assert!(!rule.matches(Path::new(b"a.out").unwrap(), b"\0as"));
+		let rule = Rule::parse(b"|masked|M|1|\\x10|\\xf0|/bin/interp|P").unwrap();
+		assert!(rule.preserve_argv0);
+		assert!(rule.matches(Path::new(b"x").unwrap(), b"\0\x1f"));
+		assert!(!rule.matches(Path::new(b"x").unwrap(), b"\x1f\0"));
+	}
+
+	#[test_case]
+	fn misc_rule_extension() {
+		let rule = Rule::parse(b":py:E::py::/usr/bin/python3:").unwrap();
+		assert!(rule.matches(Path::new(b"/tmp/script.py").unwrap(), b""));
+		assert!(!rule.matches(Path::new(b"/tmp/script.pyc").unwrap(), b""));
+		assert!(!rule.matches(Path::new(b"/tmp/py").unwrap(), b""));
+	}
+
+	#[test_case]
+	fn misc_rule_invalid() {
+		for s in [
+			b"".as_slice(),
+			b":a:M::::/bin/x:",
+			b":a:X::y::/bin/x:",
+			b":a:M:1:ab:a:/bin/x:",
+			b":a:E::py:::",
+			b":a:E::py::/bin/x:Z",
+			b":a/b:E::py::/bin/x:",
+			b":a:M:300:ab::/bin/x:",
+		] {
+			assert_eq!(Rule::parse(s).unwrap_err().as_int(), errno::EINVAL);
+		}
+	}
+}
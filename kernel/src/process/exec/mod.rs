@@ -20,25 +20,78 @@
 //!
 //! Program execution is done in several stages:
 //! - Read the program
-//! - Parse the program
+//! - Find the binary format handler able to load the program
 //! - Build the memory image according to the program
 //! - Replace the process's memory with the newly created image to run it
+//!
+//! Binary formats are registered at runtime, which allows kernel modules to add new ones. A format
+//! may also delegate the execution to an interpreter, such as scripts starting with a shebang.
 
 pub mod elf;
+pub mod misc;
+pub mod script;
 pub mod vdso;
 
 use crate::{
-	file::{vfs, vfs::ResolutionSettings},
+	file::{
+		perm::{AccessProfile, FileCaps},
+		vfs,
+		vfs::{mountpoint::FLAG_NOSUID, ResolutionSettings},
+		FileType,
+	},
 	memory::VirtAddr,
-	process::{mem_space::MemSpace, regs::Regs, signal::SignalHandler, Process},
+	process::{
+		aslr, mem_space::MemSpace, ptrace, regs::Regs, rlimit::RLimits, signal::SignalHandler,
+		Process,
+	},
 };
+use core::intrinsics::unlikely;
 use utils::{
-	collections::{string::String, vec::Vec},
-	errno::EResult,
+	collections::{
+		path::{Path, PathBuf},
+		string::String,
+		vec::Vec,
+	},
+	errno,
+	errno::{AllocResult, CollectResult, EResult},
 	lock::{IntMutex, Mutex},
 	ptr::arc::Arc,
+	TryClone,
 };
 
+/// The size of the beginning of the program's file passed to binary formats to recognize it.
+pub const BINPRM_BUF_SIZE: usize = 256;
+/// The maximum number of interpreters that can be used recursively for an execution.
+const INTERP_MAX: usize = 4;
+
+/// Environment variables that are removed when executing a program in secure mode, since they
+/// allow the caller to alter the behaviour of the dynamic linker or of the C library.
+const UNSECURE_ENVVARS: &[&[u8]] = &[
+	b"GCONV_PATH",
+	b"GETCONF_DIR",
+	b"HOSTALIASES",
+	b"LD_AUDIT",
+	b"LD_DEBUG",
+	b"LD_DEBUG_OUTPUT",
+	b"LD_DYNAMIC_WEAK",
+	b"LD_HWCAP_MASK",
+	b"LD_LIBRARY_PATH",
+	b"LD_ORIGIN_PATH",
+	b"LD_PRELOAD",
+	b"LD_PROFILE",
+	b"LD_SHOW_AUXV",
+	b"LD_USE_LOAD_BIAS",
+	b"LOCALDOMAIN",
+	b"LOCPATH",
+	b"MALLOC_TRACE",
+	b"NIS_PATH",
+	b"NLSPATH",
+	b"RESOLV_HOST_CONF",
+	b"RES_OPTIONS",
+	b"TMPDIR",
+	b"TZDIR",
+];
+
 /// Information to prepare a program image to be executed.
 pub struct ExecInfo<'s> {
 	/// Path resolution settings.
@@ -47,44 +100,253 @@ pub struct ExecInfo<'s> {
 	pub argv: Vec<String>,
 	/// The list of environment variables.
 	pub envp: Vec<String>,
+	/// The resource limits of the process.
+	pub rlimits: RLimits,
+	/// The personality of the process.
+	pub personality: u32,
 }
 
 /// A built program image.
 pub struct ProgramImage {
 	/// The argv of the program.
-	argv: Vec<String>,
+	pub argv: Vec<String>,
 	/// The environment variables of the program.
-	envp: String,
+	pub envp: String,
 
 	/// The image's memory space.
-	mem_space: MemSpace,
+	pub mem_space: MemSpace,
 
 	/// A pointer to the entry point of the program.
-	entry_point: VirtAddr,
+	pub entry_point: VirtAddr,
 	/// A pointer to the initial value of the user stack pointer.
-	user_stack: VirtAddr,
+	pub user_stack: VirtAddr,
 }
 
-/// A program executor, whose role is to load a program and to prepare it for execution.
-pub trait Executor {
-	/// Builds a program image.
-	/// `file` is the program's file.
-	fn build_image(&self, file: &vfs::Entry) -> EResult<ProgramImage>;
+/// A program being prepared for execution.
+pub struct BinPrm<'s> {
+	/// The file to be executed.
+	file: Arc<vfs::Entry>,
+	/// The path of the program passed to the execution, given to interpreters.
+	path: PathBuf,
+	/// The beginning of the file's content.
+	buf: [u8; BINPRM_BUF_SIZE],
+	/// The length of the data in `buf`.
+	buf_len: usize,
+	/// The number of interpreters used so far.
+	depth: usize,
+
+	/// Execution information.
+	pub info: ExecInfo<'s>,
+	/// The credentials the program runs with, computed by [`Self::prepare_creds`].
+	pub access_profile: AccessProfile,
+	/// Tells whether the program runs in secure mode, because it gained privileges.
+	pub secure: bool,
 }
 
-/// Builds a program image from the given executable file.
-///
-/// Arguments:
-/// - `file` is the program's file
-/// - `info` is the set execution information for the program
+impl<'s> BinPrm<'s> {
+	/// Prepares the execution of the program at `path`.
+	///
+	/// If the file is not a regular file or cannot be executed, the function returns
+	/// [`errno::EACCES`].
+	pub fn new(path: &Path, info: ExecInfo<'s>) -> EResult<Self> {
+		let file = vfs::get_file_from_path(path, info.path_resolution)?;
+		let mut prm = Self {
+			file,
+			path: path.to_path_buf()?,
+			buf: [0; BINPRM_BUF_SIZE],
+			buf_len: 0,
+			depth: 0,
+
+			access_profile: info.path_resolution.access_profile,
+			secure: false,
+			info,
+		};
+		prm.read_header()?;
+		Ok(prm)
+	}
+
+	/// Checks the current file can be executed and reads its beginning into the buffer.
+	fn read_header(&mut self) -> EResult<()> {
+		let stat = self.file.stat()?;
+		if unlikely(stat.get_type() != Some(FileType::Regular)) {
+			return Err(errno!(EACCES));
+		}
+		if unlikely(
+			!self
+				.info
+				.path_resolution
+				.access_profile
+				.can_execute_file(&stat),
+		) {
+			return Err(errno!(EACCES));
+		}
+		let node = self.file.node();
+		self.buf_len = node.ops.read_content(&node.location, 0, &mut self.buf)?;
+		Ok(())
+	}
+
+	/// Returns the file to be executed.
+	pub fn get_file(&self) -> &Arc<vfs::Entry> {
+		&self.file
+	}
+
+	/// Returns the path of the program passed to the execution.
+	pub fn get_path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Returns the beginning of the file's content, up to [`BINPRM_BUF_SIZE`] bytes.
+	pub fn header(&self) -> &[u8] {
+		&self.buf[..self.buf_len]
+	}
+
+	/// Computes the credentials the current file runs with, according to its set-user-ID and
+	/// set-group-ID bits and to its capabilities.
+	///
+	/// The set-ID bits are ignored if the file is located on a `nosuid` mountpoint or if the
+	/// process is being traced.
+	///
+	/// If the program gains privileges, it runs in secure mode and unsecure environment variables
+	/// are removed.
+	///
+	/// This function must be called by binary formats before loading the file.
+	pub fn prepare_creds(&mut self) -> EResult<()> {
+		let stat = self.file.stat()?;
+		let node = self.file.node();
+		let nosuid = node
+			.location
+			.get_mountpoint()
+			.is_some_and(|mp| mp.flags & FLAG_NOSUID != 0);
+		let traced = Process::current_opt().is_some_and(|proc| proc.lock().get_trace().is_some());
+		let file_caps = if nosuid {
+			None
+		} else {
+			node.ops
+				.get_xattr(&node.location, FileCaps::XATTR_NAME)?
+				.and_then(|caps| FileCaps::from_xattr(&caps))
+		};
+		let mut ap = self.info.path_resolution.access_profile;
+		ap.exec_ids(&stat, nosuid || traced);
+		self.secure = ap.exec_caps(file_caps);
+		self.access_profile = ap;
+		if self.secure {
+			self.info.personality &= !aslr::ADDR_NO_RANDOMIZE;
+			self.info.envp.retain(|var| {
+				let name = var
+					.as_bytes()
+					.split(|c| *c == b'=')
+					.next()
+					.unwrap_or_default();
+				!UNSECURE_ENVVARS.contains(&name)
+			});
+		}
+		Ok(())
+	}
+
+	/// Replaces the program to execute with the interpreter at `interp`, which receives the path
+	/// of the program as an argument.
+	///
+	/// The arguments of the interpreter are, in order:
+	/// - `interp`
+	/// - `args`
+	/// - the path of the program
+	/// - the arguments of the program. The first one is dropped, unless `keep_argv0` is set
+	///
+	/// If the maximum number of nested interpreters is reached, the function returns
+	/// [`errno::ELOOP`].
+	pub fn set_interpreter(
+		&mut self,
+		interp: &Path,
+		args: &[&[u8]],
+		keep_argv0: bool,
+	) -> EResult<()> {
+		if self.depth >= INTERP_MAX {
+			return Err(errno!(ELOOP));
+		}
+		let skip = if keep_argv0 { 0 } else { 1 };
+		let argv = self.info.argv.iter().skip(skip).map(|a| a.try_clone());
+		let argv = [interp.as_bytes()]
+			.into_iter()
+			.chain(args.iter().copied())
+			.chain([self.path.as_bytes()])
+			.map(String::try_from)
+			.chain(argv)
+			.collect::<AllocResult<CollectResult<Vec<String>>>>()?
+			.0?;
+		self.file = vfs::get_file_from_path(interp, self.info.path_resolution)?;
+		self.path = interp.to_path_buf()?;
+		self.info.argv = argv;
+		self.depth += 1;
+		self.read_header()
+	}
+}
+
+/// A binary format, able to load programs of a given kind.
+pub trait BinFmt {
+	/// Returns the name of the format.
+	fn get_name(&self) -> &'static [u8];
+
+	/// Loads the program described by `prm`.
+	///
+	/// If the program is not of this format, the function returns [`errno::ENOEXEC`].
+	///
+	/// If the program has to be executed by an interpreter, the function updates `prm`
+	/// accordingly with [`BinPrm::set_interpreter`] and returns `None`.
+	fn load(&self, prm: &mut BinPrm) -> EResult<Option<ProgramImage>>;
+}
+
+/// The list of registered binary formats, in the order in which they are tried.
+static FORMATS: Mutex<Vec<Arc<dyn BinFmt>>> = Mutex::new(Vec::new());
+
+/// Registers a new binary format.
 ///
-/// The function returns a memory space containing the program image and the
-/// pointer to the entry point.
-pub fn build_image(file: &vfs::Entry, info: ExecInfo) -> EResult<ProgramImage> {
-	// TODO Support other formats than ELF (wasm?)
+/// If a format with the same name is already registered, the function returns
+/// [`errno::EEXIST`].
+pub fn register<F: 'static + BinFmt>(fmt: F) -> EResult<()> {
+	let mut formats = FORMATS.lock();
+	if formats.iter().any(|f| f.get_name() == fmt.get_name()) {
+		return Err(errno!(EEXIST));
+	}
+	formats.push(Arc::new(fmt)?)?;
+	Ok(())
+}
 
-	let exec = elf::ELFExecutor::new(info)?;
-	exec.build_image(file)
+/// Unregisters the binary format with the given name.
+pub fn unregister(name: &[u8]) {
+	FORMATS.lock().retain(|f| f.get_name() != name);
+}
+
+/// Registers the binary formats supported by default.
+pub fn register_defaults() -> EResult<()> {
+	// Rules set by the user take precedence
+	register(misc::MiscFormat)?;
+	register(script::ScriptFormat)?;
+	register(elf::ELFFormat)?;
+	Ok(())
+}
+
+/// Builds a program image from the program described by `prm`.
+///
+/// Registered binary formats are tried in order until one recognizes the program. When a format
+/// delegates the execution to an interpreter, the search starts over with the interpreter. Once
+/// the function returns, `prm` describes the file that has actually been loaded.
+///
+/// If no format recognizes the program, the function returns [`errno::ENOEXEC`].
+pub fn build_image(prm: &mut BinPrm) -> EResult<ProgramImage> {
+	'search: loop {
+		// Do not keep the list locked while loading, since formats may be registered meanwhile
+		let formats = FORMATS.lock().try_clone()?;
+		for fmt in formats.iter() {
+			match fmt.load(prm) {
+				Ok(Some(image)) => return Ok(image),
+				// Search the interpreter's format
+				Ok(None) => continue 'search,
+				Err(e) if e.as_int() == errno::ENOEXEC => continue,
+				Err(e) => return Err(e),
+			}
+		}
+		return Err(errno!(ENOEXEC));
+	}
 }
 
 /// Executes the program image `image` on the process `proc`.
@@ -106,6 +368,7 @@ pub fn exec(proc: &mut Process, image: ProgramImage) -> EResult<()> {
 		.transpose()?;
 	// Reset signals
 	proc.signal_handlers.lock().fill(SignalHandler::Default);
+	proc.altstack = Default::default();
 	proc.reset_vfork();
 	proc.tls_entries = Default::default();
 	proc.update_tss();
@@ -115,5 +378,9 @@ pub fn exec(proc: &mut Process, image: ProgramImage) -> EResult<()> {
 		eip: image.entry_point.0,
 		..Default::default()
 	};
+	// The program starts with the initial extended state
+	proc.xstate.reset();
+	proc.xstate.restore();
+	ptrace::exec(proc);
 	Ok(())
 }
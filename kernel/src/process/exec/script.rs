@@ -0,0 +1,101 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Scripts are programs starting with a shebang (`#!`), followed by the path of the interpreter
+//! to run them and an optional argument.
+//!
+//! The interpreter is executed with, as arguments, the optional argument, then the path of the
+//! script, then the arguments of the script excluding the first one.
+
+use super::{BinFmt, BinPrm, ProgramImage, BINPRM_BUF_SIZE};
+use utils::{collections::path::Path, errno, errno::EResult};
+
+/// Tells whether the given character is a whitespace in a shebang.
+fn is_space(c: &u8) -> bool {
+	matches!(c, b' ' | b'\t')
+}
+
+/// Parses the shebang at the beginning of `header`.
+///
+/// The function returns the path to the interpreter and its optional argument.
+///
+/// If the header does not start with a valid shebang, the function returns
+/// [`errno::ENOEXEC`].
+fn parse(header: &[u8]) -> EResult<(&[u8], Option<&[u8]>)> {
+	let Some(line) = header.strip_prefix(b"#!") else {
+		return Err(errno!(ENOEXEC));
+	};
+	let newline = line.iter().position(|c| *c == b'\n');
+	let line = &line[..newline.unwrap_or(line.len())];
+	// Trim whitespaces
+	let start = line.iter().position(|c| !is_space(c)).unwrap_or(line.len());
+	let end = line
+		.iter()
+		.rposition(|c| !is_space(c))
+		.map_or(start, |i| i + 1);
+	let line = &line[start..end];
+	let interp_end = line.iter().position(is_space).unwrap_or(line.len());
+	let (interp, arg) = line.split_at(interp_end);
+	// If the line is truncated, the interpreter's path may be incomplete
+	let truncated = newline.is_none() && header.len() >= BINPRM_BUF_SIZE;
+	if interp.is_empty() || (truncated && arg.is_empty()) {
+		return Err(errno!(ENOEXEC));
+	}
+	let arg_start = arg.iter().position(|c| !is_space(c));
+	let arg = arg_start.map(|i| &arg[i..]);
+	Ok((interp, arg))
+}
+
+/// The script binary format.
+pub struct ScriptFormat;
+
+impl BinFmt for ScriptFormat {
+	fn get_name(&self) -> &'static [u8] {
+		b"script"
+	}
+
+	fn load(&self, prm: &mut BinPrm) -> EResult<Option<ProgramImage>> {
+		// Copy the header since `prm` is updated afterwards
+		let mut buf = [0; BINPRM_BUF_SIZE];
+		let len = prm.header().len();
+		buf[..len].copy_from_slice(prm.header());
+		let (interp, arg) = parse(&buf[..len])?;
+		let interp = Path::new(interp)?;
+		prm.set_interpreter(interp, arg.as_slice(), false)?;
+		Ok(None)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn shebang_parse() {
+		assert_eq!(
+			parse(b"#!/bin/sh\n").unwrap(),
+			(b"/bin/sh".as_slice(), None)
+		);
+		assert_eq!(
+			parse(b"#! /usr/bin/env  python3 -u \nprint()").unwrap(),
+			(b"/usr/bin/env".as_slice(), Some(b"python3 -u".as_slice()))
+		);
+		assert_eq!(parse(b"#!\n").unwrap_err().as_int(), errno::ENOEXEC);
+		assert_eq!(parse(b"\x7fELF").unwrap_err().as_int(), errno::ENOEXEC);
+	}
+}
@@ -21,9 +21,10 @@
 
 use crate::{
 	elf::parser::ELFParser,
+	memory,
 	memory::{buddy, VirtAddr},
 	process::{
-		mem_space,
+		aslr, mem_space,
 		mem_space::{
 			residence::{MapResidence, Page, ResidencePage},
 			MapConstraint, MemSpace,
@@ -97,19 +98,26 @@ fn load_image() -> EResult<Vdso> {
 
 /// Maps the vDSO into the given memory space.
 ///
+/// If `randomize` is set, the vDSO is mapped at a random address.
+///
 /// The function returns the virtual pointer to the mapped vDSO.
-pub fn map(mem_space: &mut MemSpace) -> EResult<MappedVDSO> {
+pub fn map(mem_space: &mut MemSpace, randomize: bool) -> EResult<MappedVDSO> {
 	let mut elf_image = VDSO.lock();
 	let img = elf_image.get_or_insert_with(|| load_image().expect("Failed to load vDSO"));
 	let vdso_pages = img.len.div_ceil(PAGE_SIZE);
 	let Some(vdso_pages) = NonZeroUsize::new(vdso_pages) else {
 		panic!("Invalid vDSO image");
 	};
-	// TODO ASLR
+	let constraint = if randomize {
+		let addr = memory::ALLOC_BEGIN + aslr::random_offset(aslr::MMAP_RND_PAGES);
+		MapConstraint::Hint(addr)
+	} else {
+		MapConstraint::None
+	};
 	let begin = mem_space.map(
-		MapConstraint::None,
+		constraint,
 		vdso_pages,
-		mem_space::MAPPING_FLAG_USER,
+		mem_space::MAPPING_FLAG_USER | mem_space::MAPPING_FLAG_EXEC,
 		MapResidence::Static {
 			pages: img.pages.clone(),
 		},
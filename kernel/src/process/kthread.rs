@@ -0,0 +1,201 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Kernel threads are processes running exclusively in kernelspace.
+//!
+//! They do not have a userspace memory space, file descriptors or signal handlers, and are used
+//! to run background jobs (deferred work, memory reclaim, etc...).
+//!
+//! A kernel thread is scheduled like any other process. When its entry point returns, the thread
+//! exits and is removed from the scheduler.
+
+use crate::{
+	file::{perm::AccessProfile, vfs, vfs::ResolutionSettings, wait_queue::WaitQueue},
+	gdt,
+	memory::buddy,
+	process::{
+		namespace::Namespaces,
+		oom,
+		pid::PidHandle,
+		regs::{Regs, XState},
+		rlimit::RLimits,
+		rusage,
+		rusage::RUsage,
+		scheduler,
+		scheduler::SCHEDULER,
+		seccomp::Seccomp,
+		signal::SignalHandler,
+		workqueue, Process, State, VForkState, DEFAULT_UMASK, KERNEL_STACK_ORDER,
+		TLS_ENTRIES_COUNT,
+	},
+	time::timer::TimerManager,
+};
+use core::{array, ptr};
+use utils::{
+	boxed::Box,
+	collections::{
+		path::{Path, PathBuf},
+		string::String,
+		vec::Vec,
+	},
+	errno::EResult,
+	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
+	vec,
+};
+
+/// The entry point of a kernel thread.
+type Entry = Box<dyn FnMut()>;
+
+/// The first function executed by a kernel thread.
+///
+/// `entry` is the boxed entry point of the thread. When it returns, the thread exits.
+extern "C" fn kthread_entry(entry: *mut Entry) -> ! {
+	{
+		let mut entry = unsafe { Box::from_raw(entry) };
+		(*entry)();
+	}
+	let pid = {
+		let proc_mutex = Process::current();
+		let mut proc = proc_mutex.lock();
+		proc.exit(0);
+		proc.get_pid()
+	};
+	// Nobody waits on a kernel thread, thus it has to be reaped by another context, since its
+	// kernel stack is in use until the next context switch
+	oom::wrap(|| workqueue::schedule(move || SCHEDULER.get().lock().remove_process(pid)));
+	scheduler::end_tick();
+	unreachable!();
+}
+
+/// Spawns a new kernel thread and places it into the scheduler's queue.
+///
+/// Arguments:
+/// - `name` is the name of the thread, as shown in the process's `argv`.
+/// - `f` is the function executed by the thread. When it returns, the thread exits.
+///
+/// The thread is set to state [`State::Running`] by default.
+pub fn spawn<F: FnOnce() + 'static>(name: &[u8], f: F) -> EResult<Arc<IntMutex<Process>>> {
+	let mut f = Some(f);
+	let entry: Entry = Box::new(move || {
+		if let Some(f) = f.take() {
+			f();
+		}
+	})?;
+	let entry = Box::new(entry)?;
+	let rs = ResolutionSettings::kernel_follow();
+	let root_dir = vfs::get_file_from_path(Path::root(), &rs)?;
+	let namespaces = Namespaces::initial()?;
+	let pid = PidHandle::unique(&namespaces.pid_for_children)?;
+	let pid_int = pid.get();
+	let mut process = Process {
+		pid,
+		pgid: 0,
+		sid: 0,
+		tid: pid_int,
+
+		argv: Arc::new(vec![String::try_from(name)?]?)?,
+		envp: Arc::new(String::new())?,
+		exec_path: Arc::new(PathBuf::root()?)?,
+
+		access_profile: AccessProfile::KERNEL,
+		dumpable: false,
+		umask: DEFAULT_UMASK,
+		personality: 0,
+
+		state: State::Running,
+		vfork_state: VForkState::None,
+
+		priority: 0,
+		nice: 0,
+		quantum_count: 0,
+
+		parent: None,
+		children: Vec::new(),
+		process_group: Vec::new(),
+		trace: None,
+		tracees: Vec::new(),
+
+		regs: Regs::default(),
+		xstate: XState::new()?,
+		// A kernel thread is always resumed in kernelspace
+		syscalling: true,
+
+		waitable: false,
+
+		timer_manager: Arc::new(Mutex::new(TimerManager::new(pid_int)?))?,
+
+		kernel_thread: true,
+		mem_space: None,
+		kernel_stack: buddy::alloc_kernel(KERNEL_STACK_ORDER)?,
+
+		cwd: root_dir.clone(),
+		chroot: root_dir,
+		file_descriptors: None,
+		namespaces,
+
+		sigmask: Default::default(),
+		sigpending: Default::default(),
+		saved_sigmask: None,
+		sigwait: None,
+		altstack: Default::default(),
+		restart_block: None,
+		signalfd_queue: Arc::new(WaitQueue::new())?,
+		exit_queue: Arc::new(WaitQueue::new())?,
+		signal_handlers: Arc::new(Mutex::new(array::from_fn(|_| SignalHandler::Ignore)))?,
+
+		tls_entries: [gdt::Entry::default(); TLS_ENTRIES_COUNT],
+
+		sem_undo: Vec::new(),
+		seccomp: Seccomp::default(),
+
+		rusage: RUsage::default(),
+		children_rusage: RUsage::default(),
+		start_time: rusage::timestamp(),
+		cpu_ts: 0,
+		xcpu_next: 0,
+		rlimits: RLimits::default(),
+		nproc_uid: None,
+		oom_score_adj: 0,
+
+		exit_status: 0,
+		termsig: 0,
+		coredump: false,
+	};
+	// Prepare the stack for the call to `kthread_entry`: the return address, then the argument
+	let entry = unsafe { Box::into_raw(entry) };
+	let esp = unsafe {
+		let stack = process
+			.kernel_stack
+			.as_ptr()
+			.add(buddy::get_frame_size(KERNEL_STACK_ORDER))
+			.cast::<usize>()
+			.sub(2);
+		ptr::write(stack, 0);
+		ptr::write(stack.add(1), entry as usize);
+		stack
+	};
+	process.regs.esp = esp as usize;
+	process.regs.eip = kthread_entry as usize;
+	let proc = SCHEDULER
+		.get()
+		.lock()
+		.add_process(process)
+		.inspect_err(|_| unsafe { drop(Box::from_raw(entry)) })?;
+	Ok(proc)
+}
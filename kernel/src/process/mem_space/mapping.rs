@@ -103,6 +103,16 @@ impl MemMapping {
 		self.flags
 	}
 
+	/// Returns the residence of the mapping.
+	pub fn get_residence(&self) -> &MapResidence {
+		&self.residence
+	}
+
+	/// Returns the number of pages of the mapping that are backed by physical memory.
+	pub fn get_resident_pages(&self) -> usize {
+		self.phys_pages.iter().filter(|p| p.is_some()).count()
+	}
+
 	/// Tells whether the given `page` is in COW mode.
 	///
 	/// An offset is in COW mode if the mapping is not shared, and the number of references to the
@@ -134,6 +144,12 @@ impl MemMapping {
 				flags |= vmem::x86::FLAG_USER;
 			}
 		}
+		if self.flags & super::MAPPING_FLAG_EXEC == 0 {
+			#[cfg(target_arch = "x86")]
+			{
+				flags |= vmem::x86::FLAG_NO_EXEC;
+			}
+		}
 		flags
 	}
 
@@ -239,6 +255,26 @@ impl MemMapping {
 		Ok(())
 	}
 
+	/// Returns a new mapping covering `size` pages of the current mapping starting at page
+	/// `begin`, with the flags `flags`.
+	///
+	/// If `size` is zero, the function returns `None`.
+	fn sub(&self, begin: usize, size: usize, flags: u8) -> AllocResult<Option<Self>> {
+		let Some(size) = NonZeroUsize::new(size) else {
+			return Ok(None);
+		};
+		let mut residence = self.residence.clone();
+		residence.offset_add(begin);
+		Ok(Some(Self {
+			begin: self.begin.wrapping_add(begin * PAGE_SIZE),
+			size,
+			flags,
+			residence,
+
+			phys_pages: Vec::try_from(&self.phys_pages[begin..(begin + size.get())])?,
+		}))
+	}
+
 	/// Splits the current mapping, creating up to two new mappings and one gap.
 	///
 	/// Arguments:
@@ -257,45 +293,41 @@ impl MemMapping {
 		begin: usize,
 		size: usize,
 	) -> AllocResult<(Option<Self>, Option<MemGap>, Option<Self>)> {
-		let prev = NonZeroUsize::new(begin)
-			.map(|size| {
-				Ok(MemMapping {
-					begin: self.begin,
-					size,
-					flags: self.flags,
-					residence: self.residence.clone(),
-
-					phys_pages: Vec::try_from(&self.phys_pages[..size.get()])?,
-				})
-			})
-			.transpose()?;
+		let prev = self.sub(0, begin, self.flags)?;
 		let gap = NonZeroUsize::new(size).map(|size| {
 			let begin = VirtAddr::from(self.begin) + begin * PAGE_SIZE;
 			MemGap::new(begin, size)
 		});
 		// The gap's end
 		let end = begin + size;
-		let next = self
-			.size
-			.get()
-			.checked_sub(end)
-			.and_then(NonZeroUsize::new)
-			.map(|size| {
-				let mut residence = self.residence.clone();
-				residence.offset_add(end);
-				Ok(Self {
-					begin: self.begin.wrapping_add(end * PAGE_SIZE),
-					size,
-					flags: self.flags,
-					residence,
-
-					phys_pages: Vec::try_from(&self.phys_pages[end..])?,
-				})
-			})
-			.transpose()?;
+		let next = self.sub(end, self.size.get().saturating_sub(end), self.flags)?;
 		Ok((prev, gap, next))
 	}
 
+	/// Splits the current mapping to change the flags of a range of pages.
+	///
+	/// Arguments:
+	/// - `begin` is the index of the first page to be modified.
+	/// - `size` is the number of pages to modify. It must not be zero.
+	/// - `flags` is the new set of flags of the modified pages.
+	///
+	/// The range must be within the bounds of the mapping.
+	///
+	/// The function returns the mappings before the range, the range itself and after the range.
+	pub fn protect(
+		&self,
+		begin: usize,
+		size: usize,
+		flags: u8,
+	) -> AllocResult<(Option<Self>, Self, Option<Self>)> {
+		let end = begin + size;
+		debug_assert!(size > 0 && end <= self.size.get());
+		let prev = self.sub(0, begin, self.flags)?;
+		let mid = self.sub(begin, size, flags)?.unwrap();
+		let next = self.sub(end, self.size.get() - end, self.flags)?;
+		Ok((prev, mid, next))
+	}
+
 	/// Synchronizes the data on the memory mapping back to the filesystem.
 	///
 	/// `vmem` is the virtual memory context to read from.
@@ -25,7 +25,7 @@
 
 pub mod copy;
 mod gap;
-mod mapping;
+pub mod mapping;
 pub mod residence;
 mod transaction;
 
@@ -33,6 +33,10 @@ use crate::{
 	file::perm::AccessProfile,
 	memory,
 	memory::{vmem, vmem::VMem, VirtAddr, PROCESS_END},
+	process::{
+		oom,
+		rlimit::{RLimits, RLIMIT_AS, RLIMIT_DATA},
+	},
 };
 use core::{
 	alloc::AllocError,
@@ -42,6 +46,7 @@ use core::{
 	intrinsics::unlikely,
 	mem,
 	num::NonZeroUsize,
+	ptr,
 };
 use gap::MemGap;
 use mapping::MemMapping;
@@ -49,6 +54,7 @@ use residence::MapResidence;
 use transaction::MemSpaceTransaction;
 use utils::{
 	collections::{btreemap::BTreeMap, vec::Vec},
+	errno,
 	errno::{AllocResult, CollectResult, EResult},
 	limits::PAGE_SIZE,
 	TryClone,
@@ -70,12 +76,23 @@ pub const MAPPING_FLAG_SHARED: u8 = 0b1000;
 /// The virtual address of the buffer used to map pages for copy.
 const COPY_BUFFER: VirtAddr = VirtAddr(PROCESS_END.0 - PAGE_SIZE);
 
+/// The virtual address of the top of the user stack, before randomization.
+pub const STACK_TOP: VirtAddr = COPY_BUFFER;
+
 /// Tells whether the address is in bound of the userspace.
 pub fn bound_check(addr: usize, n: usize) -> bool {
 	addr >= PAGE_SIZE && addr.saturating_add(n) <= COPY_BUFFER.0
 }
 
-// TODO Add a variant for ASLR
+/// The kind of a page fault that has been resolved.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PageFault {
+	/// The fault has been resolved without any I/O.
+	Minor,
+	/// The fault required to read the page from a file.
+	Major,
+}
+
 /// Enumeration of constraints for the selection of the virtual address for a memory mapping.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MapConstraint {
@@ -93,7 +110,8 @@ pub enum MapConstraint {
 	/// If not available, the constraint is ignored and another address is selected.
 	Hint(VirtAddr),
 
-	/// No constraint.
+	/// No constraint. The mapping is placed in the first large enough gap located after the
+	/// memory space's mapping base, if any.
 	None,
 }
 
@@ -180,19 +198,42 @@ struct MemSpaceState {
 	brk_init: VirtAddr,
 	/// The current pointer of the `[s]brk` system calls.
 	brk_addr: VirtAddr,
+	/// The address from which mappings without constraint are placed.
+	mmap_base: VirtAddr,
+
+	/// The address of the auxiliary vector on the user stack.
+	auxv_addr: VirtAddr,
+	/// The size of the auxiliary vector in bytes.
+	auxv_size: usize,
 }
 
 impl MemSpaceState {
-	/// Returns a reference to a gap with at least size `size`.
+	/// Returns a reference to a gap with at least size `size`, along with the offset in pages in
+	/// the gap at which a mapping of this size can be placed.
 	///
-	/// `size` is the minimum size of the gap to be returned.
+	/// Gaps are searched from the address `from`. If no large enough gap is available after it,
+	/// the search starts over from the beginning of the memory space.
 	///
 	/// If no gap large enough is available, the function returns `None`.
-	fn get_gap(&self, size: NonZeroUsize) -> Option<&MemGap> {
+	fn get_gap(&self, from: VirtAddr, size: NonZeroUsize) -> Option<(&MemGap, usize)> {
 		self.gaps
 			.iter()
 			.map(|(_, g)| g)
-			.find(|g| g.get_size() >= size)
+			.find_map(|g| {
+				let end = g.get_begin() + g.get_size().get() * PAGE_SIZE;
+				if end <= from {
+					return None;
+				}
+				let off = from.0.saturating_sub(g.get_begin().0) / PAGE_SIZE;
+				(g.get_size().get() - off >= size.get()).then_some((g, off))
+			})
+			.or_else(|| {
+				self.gaps
+					.iter()
+					.map(|(_, g)| g)
+					.find(|g| g.get_size() >= size)
+					.map(|g| (g, 0))
+			})
 	}
 
 	/// Comparison function to search for the object containing the address `addr`.
@@ -246,6 +287,11 @@ pub struct MemSpace {
 	state: MemSpaceState,
 	/// Architecture-specific virtual memory context handler.
 	vmem: VMem,
+
+	/// The maximum size of the memory space in bytes (`RLIMIT_AS`).
+	as_limit: usize,
+	/// The maximum size of the data segment in bytes (`RLIMIT_DATA`).
+	data_limit: usize,
 }
 
 impl MemSpace {
@@ -254,6 +300,9 @@ impl MemSpace {
 		let mut s = Self {
 			state: MemSpaceState::default(),
 			vmem: VMem::new()?,
+
+			as_limit: usize::MAX,
+			data_limit: usize::MAX,
 		};
 		// Create the default gap of memory which is present at the beginning
 		let begin = memory::ALLOC_BEGIN;
@@ -277,6 +326,29 @@ impl MemSpace {
 		self.state.vmem_usage
 	}
 
+	/// Returns the number of physical memory pages used by the memory space.
+	///
+	/// Pages shared with other memory spaces are counted as well.
+	pub fn get_rss(&self) -> usize {
+		self.state
+			.mappings
+			.iter()
+			.map(|(_, m)| m.get_resident_pages())
+			.sum()
+	}
+
+	/// Updates the limits on the memory space's size from the soft limits in `rlimits`.
+	///
+	/// Existing mappings are not affected.
+	pub fn set_limits(&mut self, rlimits: &RLimits) {
+		let get = |resource| {
+			let limit = rlimits.get(resource).unwrap().rlim_cur;
+			limit.try_into().unwrap_or(usize::MAX)
+		};
+		self.as_limit = get(RLIMIT_AS);
+		self.data_limit = get(RLIMIT_DATA);
+	}
+
 	/// Returns an immutable reference to the memory mapping containing the given virtual
 	/// address.
 	///
@@ -301,7 +373,8 @@ impl MemSpace {
 	///
 	/// On success, the function returns a pointer to the newly mapped virtual memory.
 	///
-	/// If the given pointer is not page-aligned, the function returns an error.
+	/// If the given pointer is not page-aligned, or if the mapping would make the memory space
+	/// exceed its size limit, the function returns an error.
 	pub fn map(
 		&mut self,
 		map_constraint: MapConstraint,
@@ -338,19 +411,17 @@ impl MemSpace {
 					})
 					// Hint cannot be satisfied. Get a large enough gap
 					.or_else(|| {
-						let gap = transaction.mem_space_state.get_gap(size)?;
-						Some((gap.clone(), 0))
+						let state = &transaction.mem_space_state;
+						let (gap, off) = state.get_gap(state.mmap_base, size)?;
+						Some((gap.clone(), off))
 					})
 					.ok_or(AllocError)?
 					.clone()
 			}
 			MapConstraint::None => {
-				let gap = transaction
-					.mem_space_state
-					.get_gap(size)
-					.ok_or(AllocError)?
-					.clone();
-				(gap, 0)
+				let state = &transaction.mem_space_state;
+				let (gap, off) = state.get_gap(state.mmap_base, size).ok_or(AllocError)?;
+				(gap.clone(), off)
 			}
 		};
 		let addr = (gap.get_begin() + off * PAGE_SIZE).as_ptr();
@@ -366,6 +437,10 @@ impl MemSpace {
 		// Create the mapping
 		let m = MemMapping::new(addr, size, flags, residence)?;
 		transaction.insert_mapping(m)?;
+		// Enforce the size limit
+		if transaction.get_vmem_usage().saturating_mul(PAGE_SIZE) > self.as_limit {
+			return Err(AllocError);
+		}
 		transaction.commit();
 		Ok(addr)
 	}
@@ -512,8 +587,15 @@ impl MemSpace {
 
 				brk_init: self.state.brk_init,
 				brk_addr: self.state.brk_addr,
+				mmap_base: self.state.mmap_base,
+
+				auxv_addr: self.state.auxv_addr,
+				auxv_size: self.state.auxv_size,
 			},
 			vmem: new_vmem,
+
+			as_limit: self.as_limit,
+			data_limit: self.data_limit,
 		})
 	}
 
@@ -542,6 +624,74 @@ impl MemSpace {
 		Ok(())
 	}
 
+	/// Copies data between the memory space and the buffer `buf`, regardless of whether the
+	/// memory space is bound.
+	///
+	/// This is used to access the memory of another process, for example when tracing it.
+	///
+	/// Arguments:
+	/// - `addr` is the virtual address of the beginning of the range to access.
+	/// - `buf` is the buffer to read into, or to write from.
+	/// - `write` tells whether the data is written to the memory space. Writing ignores the
+	///   protection of mappings, which allows setting breakpoints in code.
+	///
+	/// If a part of the range is not mapped, the function returns [`errno::EIO`].
+	pub fn access(&mut self, addr: VirtAddr, buf: &mut [u8], write: bool) -> EResult<()> {
+		let end = addr.0.checked_add(buf.len()).ok_or_else(|| errno!(EIO))?;
+		let begin = VirtAddr(addr.0 & !(PAGE_SIZE - 1));
+		// Check the whole range is mapped
+		for page in (begin.0..end).step_by(PAGE_SIZE) {
+			self.get_mapping_for_addr(VirtAddr(page))
+				.ok_or_else(|| errno!(EIO))?;
+		}
+		// Make pages present, breaking Copy-On-Write so that other processes are not affected
+		self.alloc(begin, end - begin.0)?;
+		unsafe {
+			vmem::switch(&self.vmem, || {
+				vmem::write_ro(|| {
+					vmem::smap_disable(|| {
+						if write {
+							ptr::copy_nonoverlapping(buf.as_ptr(), addr.as_ptr(), buf.len());
+						} else {
+							ptr::copy_nonoverlapping(addr.as_ptr(), buf.as_mut_ptr(), buf.len());
+						}
+					});
+				});
+			});
+		}
+		Ok(())
+	}
+
+	/// Reads the memory at `addr` into `buf`, without modifying the memory space.
+	///
+	/// Contrary to [`Self::access`], pages that are not present in memory are not allocated and
+	/// are read as zeros instead. Unmapped pages are also read as zeros.
+	pub fn peek(&self, addr: VirtAddr, buf: &mut [u8]) {
+		unsafe {
+			vmem::switch(&self.vmem, || {
+				vmem::smap_disable(|| {
+					let mut off = 0;
+					while off < buf.len() {
+						let page = addr + off;
+						let len = min(PAGE_SIZE - page.0 % PAGE_SIZE, buf.len() - off);
+						let dst = &mut buf[off..(off + len)];
+						if self.vmem.translate(page).is_some() {
+							ptr::copy_nonoverlapping(page.as_ptr(), dst.as_mut_ptr(), len);
+						} else {
+							dst.fill(0);
+						}
+						off += len;
+					}
+				});
+			});
+		}
+	}
+
+	/// Returns an iterator over the memory mappings, sorted by address.
+	pub fn iter_mappings(&self) -> impl Iterator<Item = &MemMapping> {
+		self.state.mappings.iter().map(|(_, m)| m)
+	}
+
 	/// Sets protection for the given range of memory.
 	///
 	/// Arguments:
@@ -552,19 +702,66 @@ impl MemSpace {
 	///
 	/// If a mapping to be modified is associated with a file, and the file doesn't have the
 	/// matching permissions, the function returns an error.
+	///
+	/// If a page in the range is not mapped, the function returns [`errno::ENOMEM`].
 	pub fn set_prot(
 		&mut self,
-		_addr: *mut c_void,
-		_len: usize,
-		_prot: u8,
-		_access_profile: &AccessProfile,
+		addr: *mut c_void,
+		len: usize,
+		prot: u8,
+		access_profile: &AccessProfile,
 	) -> EResult<()> {
-		// TODO Iterate on mappings in the range:
-		//		If the mapping is shared and associated to a file, check file permissions match
-		// `prot` (only write)
-		//		Split the mapping if needed
-		//		Set permissions
-		//		Update vmem
+		let addr = VirtAddr::from(addr);
+		let size = len.div_ceil(PAGE_SIZE);
+		let mut transaction = MemSpaceTransaction::new(&mut self.state, &mut self.vmem);
+		let mut i = 0;
+		while i < size {
+			// The current page's beginning
+			let page_addr = addr + i * PAGE_SIZE;
+			// The mapping containing the page
+			let mapping = transaction
+				.mem_space_state
+				.get_mapping_for_addr(page_addr)
+				.ok_or_else(|| errno!(ENOMEM))?;
+			// The pointer to the beginning of the mapping
+			let mapping_begin = mapping.get_begin();
+			// The offset in the mapping to the beginning of pages to modify
+			let inner_off = (page_addr.0 - mapping_begin as usize) / PAGE_SIZE;
+			// The number of pages to modify in the mapping
+			let pages = min(size - i, mapping.get_size().get() - inner_off);
+			i += pages;
+			let old_flags = mapping.get_flags();
+			let flags = (old_flags & !(MAPPING_FLAG_WRITE | MAPPING_FLAG_EXEC)) | prot;
+			if flags == old_flags {
+				continue;
+			}
+			// Writing to a shared mapping writes to the file
+			let shared = old_flags & MAPPING_FLAG_SHARED != 0;
+			let write = flags & MAPPING_FLAG_WRITE != 0;
+			if let (
+				true,
+				true,
+				MapResidence::File {
+					file, ..
+				},
+			) = (shared, write, mapping.get_residence())
+			{
+				if !access_profile.can_write_file(&file.stat()?) {
+					return Err(errno!(EACCES));
+				}
+			}
+			// Replace the mapping
+			let (prev, mid, next) = mapping.protect(inner_off, pages, flags)?;
+			transaction.remove_mapping(mapping_begin)?;
+			if let Some(m) = prev {
+				transaction.insert_mapping(m)?;
+			}
+			transaction.insert_mapping(mid)?;
+			if let Some(m) = next {
+				transaction.insert_mapping(m)?;
+			}
+		}
+		transaction.commit();
 		Ok(())
 	}
 
@@ -584,9 +781,30 @@ impl MemSpace {
 		self.state.brk_addr = addr;
 	}
 
+	/// Sets the address from which mappings without constraint are placed.
+	///
+	/// This function MUST be called before the program starts.
+	pub fn set_mmap_base(&mut self, addr: VirtAddr) {
+		self.state.mmap_base = addr;
+	}
+
+	/// Returns the address and size in bytes of the auxiliary vector on the user stack.
+	pub fn get_auxv(&self) -> (VirtAddr, usize) {
+		(self.state.auxv_addr, self.state.auxv_size)
+	}
+
+	/// Sets the address and size in bytes of the auxiliary vector on the user stack.
+	///
+	/// This function MUST be called before the program starts.
+	pub fn set_auxv(&mut self, addr: VirtAddr, size: usize) {
+		self.state.auxv_addr = addr;
+		self.state.auxv_size = size;
+	}
+
 	/// Sets the address for the `brk` syscall.
 	///
-	/// If the memory cannot be allocated, the function returns an error.
+	/// If the memory cannot be allocated or if the data segment would exceed its size limit, the
+	/// function returns an error.
 	#[allow(clippy::not_unsafe_ptr_arg_deref)]
 	pub fn set_brk(&mut self, addr: VirtAddr) -> AllocResult<()> {
 		if addr >= self.state.brk_addr {
@@ -594,6 +812,10 @@ impl MemSpace {
 			if addr > COPY_BUFFER {
 				return Err(AllocError);
 			}
+			// Enforce the data segment's size limit
+			if addr.0 - self.state.brk_init.0 > self.data_limit {
+				return Err(AllocError);
+			}
 			// Allocate memory
 			let begin = self.state.brk_addr.align_to(PAGE_SIZE);
 			let pages = (addr.0 - begin.0).div_ceil(PAGE_SIZE);
@@ -635,35 +857,39 @@ impl MemSpace {
 	/// - `addr` is the virtual address of the wrong memory access that caused the fault.
 	/// - `code` is the error code given along with the error.
 	///
-	/// If the process should continue, the function returns `true`, else `false`.
-	pub fn handle_page_fault(&mut self, addr: VirtAddr, code: u32) -> bool {
+	/// If the process should continue, the function returns the kind of the resolved fault, else
+	/// `None`.
+	pub fn handle_page_fault(&mut self, addr: VirtAddr, code: u32) -> Option<PageFault> {
 		if code & vmem::x86::PAGE_FAULT_PRESENT == 0 {
-			return false;
+			return None;
 		}
-		let Some(mapping) = self.state.get_mut_mapping_for_addr(addr) else {
-			return false;
-		};
+		let mapping = self.state.get_mut_mapping_for_addr(addr)?;
 		// Check permissions
 		let code_write = code & vmem::x86::PAGE_FAULT_WRITE != 0;
 		let mapping_write = mapping.get_flags() & MAPPING_FLAG_WRITE != 0;
 		if code_write && !mapping_write {
-			return false;
+			return None;
+		}
+		let code_exec = code & vmem::x86::PAGE_FAULT_INSTRUCTION != 0;
+		let mapping_exec = mapping.get_flags() & MAPPING_FLAG_EXEC != 0;
+		if code_exec && !mapping_exec {
+			return None;
 		}
-		// TODO check exec
 		let code_userspace = code & vmem::x86::PAGE_FAULT_USER != 0;
 		let mapping_userspace = mapping.get_flags() & MAPPING_FLAG_USER != 0;
 		if code_userspace && !mapping_userspace {
-			return false;
+			return None;
 		}
 		// Map the accessed page
 		let page_offset = (addr.0 - mapping.get_begin() as usize) / PAGE_SIZE;
 		let mut transaction = self.vmem.transaction();
-		// TODO use OOM killer
-		mapping
-			.alloc(page_offset, &mut transaction)
-			.expect("Out of memory!");
+		oom::wrap(|| mapping.alloc(page_offset, &mut transaction));
 		transaction.commit();
-		true
+		if matches!(mapping.get_residence(), MapResidence::File { .. }) {
+			Some(PageFault::Major)
+		} else {
+			Some(PageFault::Minor)
+		}
 	}
 }
 
@@ -710,4 +936,57 @@ mod test {
 		mem_space.unmap(addr, size, false).unwrap();
 		//assert!(!mem_space.can_access(addr as _, PAGE_SIZE, true, true));
 	}
+
+	#[test_case]
+	fn mmap_base() {
+		let mut mem_space = MemSpace::new().unwrap();
+		let base = memory::ALLOC_BEGIN + 0x10 * PAGE_SIZE;
+		mem_space.set_mmap_base(base);
+		let size = NonZeroUsize::new(2).unwrap();
+		let map = |mem_space: &mut MemSpace| {
+			let addr = mem_space
+				.map(
+					MapConstraint::None,
+					size,
+					MAPPING_FLAG_USER,
+					MapResidence::Normal,
+				)
+				.unwrap();
+			VirtAddr::from(addr)
+		};
+		assert_eq!(map(&mut mem_space), base);
+		assert_eq!(map(&mut mem_space), base + 2 * PAGE_SIZE);
+		// When the space after the base is exhausted, mappings are placed before it
+		mem_space.set_mmap_base(memory::PROCESS_END);
+		assert_eq!(map(&mut mem_space), memory::ALLOC_BEGIN);
+	}
+
+	#[test_case]
+	fn access_cow() {
+		let mut parent = MemSpace::new().unwrap();
+		let addr = VirtAddr(0x1000);
+		// The mapping is read-only, which does not prevent writing through `access`
+		parent
+			.map(
+				MapConstraint::Fixed(addr),
+				NonZeroUsize::new(2).unwrap(),
+				MAPPING_FLAG_USER,
+				MapResidence::Normal,
+			)
+			.unwrap();
+		// Access across the page boundary
+		let off = addr + PAGE_SIZE - 2;
+		parent.access(off, &mut [1, 2, 3, 4], true).unwrap();
+		let mut child = parent.fork().unwrap();
+		// Writing to the child breaks Copy-On-Write
+		child.access(off, &mut [5, 6, 7, 8], true).unwrap();
+		let mut buf = [0; 4];
+		parent.access(off, &mut buf, false).unwrap();
+		assert_eq!(buf, [1, 2, 3, 4]);
+		child.access(off, &mut buf, false).unwrap();
+		assert_eq!(buf, [5, 6, 7, 8]);
+		// Unmapped memory cannot be accessed
+		let end = addr + 2 * PAGE_SIZE;
+		assert_eq!(parent.access(end - 2, &mut buf, false), Err(errno!(EIO)));
+	}
 }
@@ -127,6 +127,12 @@ impl<'m, 'v> MemSpaceTransaction<'m, 'v> {
 		}
 	}
 
+	/// Returns the number of virtual memory pages in the memory space, as it would be after
+	/// commit.
+	pub fn get_vmem_usage(&self) -> usize {
+		self.vmem_usage
+	}
+
 	/// Inserts the given gap into the state.
 	///
 	/// On failure, the transaction is dropped and rolled back.
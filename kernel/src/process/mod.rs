@@ -25,18 +25,26 @@
 // TODO Do not reallocate a PID of used as a pgid
 // TODO When a process receives a signal or exits, log it if the `strace` feature is enabled
 
+pub mod aslr;
+pub mod coredump;
 pub mod exec;
 pub mod iovec;
+pub mod kthread;
 pub mod mem_space;
+pub mod namespace;
 pub mod oom;
 pub mod pid;
+pub mod ptrace;
 pub mod regs;
+pub mod rlimit;
 pub mod rusage;
 pub mod scheduler;
+pub mod seccomp;
 pub mod signal;
 #[cfg(target_arch = "x86")]
 pub mod tss;
 pub mod user_desc;
+pub mod workqueue;
 
 use crate::{
 	event,
@@ -44,24 +52,34 @@ use crate::{
 	file,
 	file::{
 		fd::{FileDescriptorTable, NewFDConstraint},
-		perm::AccessProfile,
+		perm::{AccessProfile, Uid, CAP_KILL, CAP_SYS_ADMIN, CAP_SYS_RESOURCE},
 		vfs,
 		vfs::ResolutionSettings,
+		wait_queue::WaitQueue,
 		File, O_RDWR,
 	},
 	gdt,
-	memory::{buddy, buddy::FrameOrder, VirtAddr},
+	ipc::{sem, sem::SemUndo},
+	memory::{buddy, buddy::FrameOrder, vmem, VirtAddr},
 	process::{
-		mem_space::{copy, copy::SyscallPtr},
-		pid::PidHandle,
+		mem_space::{copy, copy::SyscallPtr, PageFault},
+		namespace::Namespaces,
+		pid::{PidHandle, PidNamespace},
+		ptrace::TraceState,
 		scheduler::SCHEDULER,
 		signal::SigSet,
 	},
 	register_get,
-	syscall::FromSyscallArg,
-	time::timer::TimerManager,
+	syscall::{restart_syscall::RestartBlock, FromSyscallArg},
+	time::{
+		timer::TimerManager,
+		unit::{TimeUnit, Timeval},
+	},
+	tty::TTY,
 };
 use core::{
+	array,
+	cmp::max,
 	ffi::c_int,
 	fmt,
 	fmt::Formatter,
@@ -72,9 +90,11 @@ use core::{
 };
 use mem_space::MemSpace;
 use pid::Pid;
-use regs::Regs;
+use regs::{Regs, XState};
+use rlimit::{RLim, RLimit, RLimits, RLIM_INFINITY};
 use rusage::RUsage;
-use signal::{Signal, SignalAction, SignalHandler};
+use seccomp::Seccomp;
+use signal::{SigAltStack, SigInfo, SigPending, Signal, SignalAction, SignalHandler};
 #[cfg(target_arch = "x86")]
 use tss::TSS;
 use utils::{
@@ -84,9 +104,11 @@ use utils::{
 		vec::Vec,
 	},
 	errno,
-	errno::{AllocResult, EResult},
+	errno::{AllocResult, CollectResult, EResult},
+	limits::PAGE_SIZE,
 	lock::{IntMutex, Mutex},
 	ptr::arc::Arc,
+	TryClone,
 };
 
 /// The opcode of the `hlt` instruction.
@@ -98,8 +120,11 @@ const TTY_DEVICE_PATH: &str = "/dev/tty";
 /// The default file creation mask.
 const DEFAULT_UMASK: file::Mode = 0o022;
 
-/// The size of the userspace stack of a process in number of pages.
+/// The default size of the userspace stack of a process in number of pages.
 const USER_STACK_SIZE: usize = 2048;
+/// The maximum size of the userspace stack of a process in number of pages, whatever its
+/// `RLIMIT_STACK` limit.
+const USER_STACK_MAX_SIZE: usize = 32768;
 /// The flags for the userspace stack mapping.
 const USER_STACK_FLAGS: u8 = mem_space::MAPPING_FLAG_WRITE | mem_space::MAPPING_FLAG_USER;
 /// The size of the kernelspace stack of a process in number of pages.
@@ -183,6 +208,10 @@ pub struct ForkOptions {
 	/// This is useful in order to avoid an unnecessary clone of the memory space in case the
 	/// child process executes a program or exits quickly.
 	pub vfork: bool,
+
+	/// The set of `CLONE_NEW*` flags selecting the namespaces the child process is created in a
+	/// new instance of.
+	pub new_namespaces: c_int,
 }
 
 /// The vfork operation is similar to the fork operation except the parent
@@ -211,6 +240,8 @@ pub struct Process {
 	pid: PidHandle,
 	/// The ID of the process group.
 	pub pgid: Pid,
+	/// The ID of the session.
+	pub sid: Pid,
 	/// The thread ID of the process.
 	pub tid: Pid,
 
@@ -223,8 +254,15 @@ pub struct Process {
 
 	/// The process's access profile, containing user and group IDs.
 	pub access_profile: AccessProfile,
+	/// Tells whether the process can be dumped or traced by an unprivileged agent. This is
+	/// cleared when the process changes its credentials, since its memory may then contain
+	/// privileged data.
+	pub dumpable: bool,
 	/// The process's current umask.
 	pub umask: file::Mode,
+	/// The execution domain of the process, along with flags altering the behaviour of program
+	/// execution.
+	pub personality: u32,
 
 	/// The current state of the process.
 	state: State,
@@ -245,9 +283,15 @@ pub struct Process {
 	children: Vec<Pid>,
 	/// The list of processes in the process group.
 	process_group: Vec<Pid>,
+	/// The tracing state of the process. If `None`, the process is not traced.
+	trace: Option<TraceState>,
+	/// The list of processes traced by the process.
+	tracees: Vec<Pid>,
 
 	/// The last saved registers state.
 	pub regs: Regs,
+	/// The last saved extended processor state.
+	pub xstate: XState,
 	/// Tells whether the process was executing a system call.
 	pub syscalling: bool,
 
@@ -257,8 +301,10 @@ pub struct Process {
 
 	/// Structure managing the process's timers. This manager is shared between all threads of the
 	/// same process.
-	timer_manager: Arc<Mutex<TimerManager>>,
+	timer_manager: Arc<IntMutex<TimerManager>>,
 
+	/// Tells whether the process is a kernel thread, which runs exclusively in kernelspace.
+	kernel_thread: bool,
 	/// The virtual memory of the process.
 	mem_space: Option<Arc<IntMutex<MemSpace>>>,
 	/// A pointer to the kernelspace stack.
@@ -272,24 +318,69 @@ pub struct Process {
 	pub chroot: Arc<vfs::Entry>,
 	/// The list of open file descriptors with their respective ID.
 	pub file_descriptors: Option<Arc<Mutex<FileDescriptorTable>>>,
+	/// The namespaces the process belongs to.
+	pub namespaces: Namespaces,
 
 	/// A bitfield storing the set of blocked signals.
 	pub sigmask: SigSet,
-	/// A bitfield storing the set of pending signals.
-	sigpending: SigSet,
+	/// The pending signals, along with their information.
+	sigpending: SigPending,
+	/// The signal mask to restore after handling a signal, when temporarily replaced by
+	/// `rt_sigsuspend`.
+	pub saved_sigmask: Option<SigSet>,
+	/// The set of signals the process waits for while sleeping in `rt_sigtimedwait`.
+	pub sigwait: Option<SigSet>,
+	/// The alternate stack on which signal handlers can be executed.
+	pub altstack: SigAltStack,
+	/// The state required to resume the last system call interrupted with
+	/// [`errno::ERESTART_RESTARTBLOCK`].
+	pub restart_block: Option<RestartBlock>,
+	/// The queue of signalfd readers and watchers, notified when a blocked signal becomes
+	/// pending.
+	pub signalfd_queue: Arc<WaitQueue>,
+	/// The queue of pidfd watchers, notified when the process terminates.
+	///
+	/// Since the queue is unique to the process, it also identifies it regardless of PID reuse.
+	pub exit_queue: Arc<WaitQueue>,
 	/// The list of signal handlers.
 	pub signal_handlers: Arc<Mutex<[SignalHandler; signal::SIGNALS_COUNT]>>,
 
 	/// TLS entries.
 	pub tls_entries: [gdt::Entry; TLS_ENTRIES_COUNT],
 
+	/// The adjustments to apply to System V semaphores when the process exits.
+	pub sem_undo: Vec<SemUndo>,
+	/// The seccomp state of the process, restricting the system calls it may perform.
+	pub seccomp: Seccomp,
+
 	/// The process's resources usage.
 	rusage: RUsage,
+	/// The accumulated resources usage of the terminated children of the process that have been
+	/// waited for.
+	children_rusage: RUsage,
+	/// The timestamp at which the process has been created, in nanoseconds.
+	start_time: u64,
+	/// The timestamp at which CPU time has been charged to the process for the last time, in
+	/// nanoseconds.
+	cpu_ts: u64,
+	/// The amount of CPU time, in nanoseconds, at which the next `SIGXCPU` is sent if the soft
+	/// `RLIMIT_CPU` limit is exceeded.
+	xcpu_next: u64,
+	/// The process's resource limits.
+	pub rlimits: RLimits,
+	/// The real user ID the process is counted for in the number of processes of the user,
+	/// checked against `RLIMIT_NPROC`. `None` for kernel threads and terminated processes.
+	nproc_uid: Option<Uid>,
+	/// The adjustment of the OOM score of the process, between [`oom::OOM_SCORE_ADJ_MIN`] and
+	/// [`oom::OOM_SCORE_ADJ_MAX`].
+	pub oom_score_adj: i16,
 
 	/// The exit status of the process after exiting.
 	exit_status: ExitStatus,
 	/// The terminating signal.
 	termsig: u8,
+	/// Tells whether the process dumped a core file when terminated.
+	coredump: bool,
 }
 
 /// Initializes processes system. This function must be called only once, at
@@ -305,15 +396,34 @@ pub(crate) fn init() -> EResult<()> {
 		// Get process
 		let proc_mutex = Process::current();
 		let mut proc = proc_mutex.lock();
+		let pc = VirtAddr(regs.eip);
 		match id {
 			// Divide-by-zero
+			0x00 => proc.kill_info(
+				Signal::SIGFPE,
+				SigInfo::fault(Signal::SIGFPE, signal::FPE_INTDIV, pc),
+			),
 			// x87 Floating-Point Exception
 			// SIMD Floating-Point Exception
-			0x00 | 0x10 | 0x13 => proc.kill(Signal::SIGFPE),
+			0x10 | 0x13 => proc.kill_info(
+				Signal::SIGFPE,
+				SigInfo::fault(Signal::SIGFPE, signal::SI_KERNEL, pc),
+			),
+			// Debug (single-step)
+			0x01 => proc.kill_info(
+				Signal::SIGTRAP,
+				SigInfo::fault(Signal::SIGTRAP, signal::TRAP_TRACE, pc),
+			),
 			// Breakpoint
-			0x03 => proc.kill(Signal::SIGTRAP),
+			0x03 => proc.kill_info(
+				Signal::SIGTRAP,
+				SigInfo::fault(Signal::SIGTRAP, signal::TRAP_BRKPT, pc),
+			),
 			// Invalid Opcode
-			0x06 => proc.kill(Signal::SIGILL),
+			0x06 => proc.kill_info(
+				Signal::SIGILL,
+				SigInfo::fault(Signal::SIGILL, signal::ILL_ILLOPN, pc),
+			),
 			// General Protection Fault
 			0x0d => {
 				// Get the instruction opcode
@@ -327,7 +437,10 @@ pub(crate) fn init() -> EResult<()> {
 				}
 			}
 			// Alignment Check
-			0x11 => proc.kill(Signal::SIGBUS),
+			0x11 => proc.kill_info(
+				Signal::SIGBUS,
+				SigInfo::fault(Signal::SIGBUS, signal::BUS_ADRALN, pc),
+			),
 			_ => {}
 		}
 		CallbackResult::Continue
@@ -341,36 +454,44 @@ pub(crate) fn init() -> EResult<()> {
 		};
 		let mut curr_proc = curr_proc.lock();
 		// Check access
-		let success = {
+		let fault = {
 			let Some(mem_space_mutex) = curr_proc.get_mem_space() else {
 				return CallbackResult::Panic;
 			};
 			let mut mem_space = mem_space_mutex.lock();
 			mem_space.handle_page_fault(accessed_addr, code)
 		};
-		if !success {
-			if ring < 3 {
-				// Check if the fault was caused by a user <-> kernel copy
-				if (copy::raw_copy as usize..copy::copy_fault as usize).contains(&pc) {
-					// Jump to `copy_fault`
-					let mut regs = regs.clone();
-					regs.eip = copy::copy_fault as usize;
-					// TODO cleanup
-					drop(curr_proc);
-					unsafe {
-						unlock_callbacks(0x0e);
-						regs.switch(false);
-					}
-				} else {
-					return CallbackResult::Panic;
+		if let Some(fault) = fault {
+			curr_proc.count_page_fault(fault);
+		} else if ring < 3 {
+			// Check if the fault was caused by a user <-> kernel copy
+			if (copy::raw_copy as usize..copy::copy_fault as usize).contains(&pc) {
+				// Jump to `copy_fault`
+				let mut regs = regs.clone();
+				regs.eip = copy::copy_fault as usize;
+				// TODO cleanup
+				drop(curr_proc);
+				unsafe {
+					unlock_callbacks(0x0e);
+					regs.switch(false);
 				}
 			} else {
-				curr_proc.kill(Signal::SIGSEGV);
+				return CallbackResult::Panic;
 			}
+		} else {
+			// Bit 0 of the error code is set if the page is present
+			let code = if code & 1 != 0 {
+				signal::SEGV_ACCERR
+			} else {
+				signal::SEGV_MAPERR
+			};
+			let info = SigInfo::fault(Signal::SIGSEGV, code, accessed_addr);
+			curr_proc.kill_info(Signal::SIGSEGV, info);
 		}
 		CallbackResult::Continue
 	};
 	let _ = ManuallyDrop::new(event::register_callback(0x00, callback)?);
+	let _ = ManuallyDrop::new(event::register_callback(0x01, callback)?);
 	let _ = ManuallyDrop::new(event::register_callback(0x03, callback)?);
 	let _ = ManuallyDrop::new(event::register_callback(0x06, callback)?);
 	let _ = ManuallyDrop::new(event::register_callback(0x0d, callback)?);
@@ -378,6 +499,7 @@ pub(crate) fn init() -> EResult<()> {
 	let _ = ManuallyDrop::new(event::register_callback(0x10, callback)?);
 	let _ = ManuallyDrop::new(event::register_callback(0x11, callback)?);
 	let _ = ManuallyDrop::new(event::register_callback(0x13, callback)?);
+	exec::register_defaults()?;
 	Ok(())
 }
 
@@ -437,9 +559,10 @@ impl Process {
 		};
 		let root_dir = vfs::get_file_from_path(Path::root(), &rs)?;
 		let pid = PidHandle::init()?;
-		let process = Self {
+		let mut process = Self {
 			pid,
 			pgid: pid::INIT_PID,
+			sid: pid::INIT_PID,
 			tid: pid::INIT_PID,
 
 			argv: Arc::new(Vec::new())?,
@@ -447,7 +570,9 @@ impl Process {
 			exec_path: Arc::new(PathBuf::root()?)?,
 
 			access_profile: rs.access_profile,
+			dumpable: true,
 			umask: DEFAULT_UMASK,
+			personality: 0,
 
 			state: State::Running,
 			vfork_state: VForkState::None,
@@ -459,52 +584,131 @@ impl Process {
 			parent: None,
 			children: Vec::new(),
 			process_group: Vec::new(),
+			trace: None,
+			tracees: Vec::new(),
 
 			regs: Regs::default(),
+			xstate: XState::new()?,
 			syscalling: false,
 
 			waitable: false,
 
 			timer_manager: Arc::new(Mutex::new(TimerManager::new(pid::INIT_PID)?))?,
 
+			kernel_thread: false,
 			mem_space: None,
 			kernel_stack: buddy::alloc_kernel(KERNEL_STACK_ORDER)?,
 
 			cwd: root_dir.clone(),
 			chroot: root_dir,
 			file_descriptors: Some(Arc::new(Mutex::new(file_descriptors))?),
+			namespaces: Namespaces::initial()?,
 
 			sigmask: Default::default(),
 			sigpending: Default::default(),
-			signal_handlers: Arc::new(Mutex::new(Default::default()))?,
+			saved_sigmask: None,
+			sigwait: None,
+			altstack: Default::default(),
+			restart_block: None,
+			signalfd_queue: Arc::new(WaitQueue::new())?,
+			exit_queue: Arc::new(WaitQueue::new())?,
+			signal_handlers: Arc::new(Mutex::new(array::from_fn(|_| SignalHandler::Default)))?,
 
 			tls_entries: [gdt::Entry::default(); TLS_ENTRIES_COUNT],
 
+			sem_undo: Vec::new(),
+			seccomp: Seccomp::default(),
+
 			rusage: RUsage::default(),
+			children_rusage: RUsage::default(),
+			start_time: rusage::timestamp(),
+			cpu_ts: 0,
+			xcpu_next: 0,
+			rlimits: RLimits::default(),
+			nproc_uid: None,
+			oom_score_adj: 0,
 
 			exit_status: 0,
 			termsig: 0,
+			coredump: false,
 		};
+		process.charge_nproc(None)?;
 		Ok(SCHEDULER.get().lock().add_process(process)?)
 	}
 
+	/// Returns the process with PID `pid` in the namespace `ns`.
+	///
+	/// If the process doesn't exist, the function returns `None`.
+	pub fn get_by_ns_pid(ns: &PidNamespace, pid: Pid) -> Option<Arc<IntMutex<Self>>> {
+		Self::get_by_pid(ns.to_global(pid)?)
+	}
+
 	/// Returns the process's ID.
 	pub fn get_pid(&self) -> u16 {
 		self.pid.get()
 	}
 
+	/// Returns the PID namespace of the process.
+	pub fn pid_ns(&self) -> &Arc<PidNamespace> {
+		self.pid.ns()
+	}
+
+	/// Returns the process's ID in its own PID namespace.
+	pub fn get_ns_pid(&self) -> Pid {
+		self.get_pid_in(self.pid.ns())
+	}
+
+	/// Returns the process's ID in the PID namespace `ns`.
+	///
+	/// If the process is not visible from `ns`, the function returns `0`.
+	pub fn get_pid_in(&self, ns: &PidNamespace) -> Pid {
+		self.pid.get_in(ns).unwrap_or(0)
+	}
+
 	/// Tells whether the process is the init process.
 	#[inline(always)]
 	pub fn is_init(&self) -> bool {
 		self.pid.get() == pid::INIT_PID
 	}
 
+	/// Tells whether the process is the init process of its PID namespace.
+	#[inline(always)]
+	pub fn is_ns_init(&self) -> bool {
+		self.pid.is_ns_init()
+	}
+
+	/// Returns the PID of the process that adopts the children of the process when it exits.
+	///
+	/// This is the init process of the closest PID namespace, other than the process itself.
+	fn get_reaper(&self) -> Pid {
+		let mut ns = Some(self.pid.ns());
+		while let Some(n) = ns {
+			if let Some(init) = n.get_init().filter(|init| *init != self.pid.get()) {
+				return init;
+			}
+			ns = n.get_parent();
+		}
+		pid::INIT_PID
+	}
+
+	/// Tells whether the process is a kernel thread.
+	#[inline(always)]
+	pub fn is_kernel_thread(&self) -> bool {
+		self.kernel_thread
+	}
+
 	/// Tells whether the process is among a group and is not its owner.
 	#[inline(always)]
 	pub fn is_in_group(&self) -> bool {
 		self.pgid != 0 && self.pgid != self.pid.get()
 	}
 
+	/// Tells whether the process is the leader of its session.
+	#[inline(always)]
+	pub fn is_session_leader(&self) -> bool {
+		self.sid == self.pid.get()
+	}
+
 	/// Sets the process's group ID to the given value `pgid`, updating the associated group.
 	pub fn set_pgid(&mut self, pgid: Pid) -> EResult<()> {
 		let old_pgid = self.pgid;
@@ -554,6 +758,16 @@ impl Process {
 		Process::get_by_pid(self.pgid).is_none()
 	}
 
+	/// Returns the parent process's PID in the process's PID namespace.
+	///
+	/// If the parent is not visible from the namespace, the function returns `0`.
+	pub fn get_parent_ns_pid(&self) -> Pid {
+		self.parent
+			.as_ref()
+			.map(|parent| parent.lock().get_pid_in(self.pid.ns()))
+			.unwrap_or_else(|| self.get_ns_pid())
+	}
+
 	/// Returns the parent process's PID.
 	pub fn get_parent_pid(&self) -> Pid {
 		self.parent
@@ -584,11 +798,19 @@ impl Process {
 			if self.is_init() {
 				panic!("Terminated init process!");
 			}
+			self.update_maxrss();
+			// A terminated process no longer counts against `RLIMIT_NPROC`
+			if let Some(uid) = self.nproc_uid.take() {
+				rlimit::nproc_release(uid);
+			}
 			// Remove the memory space and file descriptors table to save memory
 			//self.mem_space = None; // TODO Handle the case where the memory space is bound
 			self.file_descriptors = None;
-			// Attach every child to the init process
-			let init_proc_mutex = Process::get_by_pid(pid::INIT_PID).unwrap();
+			// The process groups that may become orphaned
+			let mut groups = Vec::new();
+			oom::wrap(|| groups.push(self.pgid));
+			// Attach every child to the init process of the namespace
+			let init_proc_mutex = Process::get_by_pid(self.get_reaper()).unwrap();
 			let mut init_proc = init_proc_mutex.lock();
 			let children = mem::take(&mut self.children);
 			for child_pid in children {
@@ -597,11 +819,61 @@ impl Process {
 					continue;
 				}
 				if let Some(child_mutex) = Process::get_by_pid(child_pid) {
-					child_mutex.lock().parent = Some(init_proc_mutex.clone());
+					let mut child = child_mutex.lock();
+					child.parent = Some(init_proc_mutex.clone());
+					if !groups.contains(&child.pgid) {
+						oom::wrap(|| groups.push(child.pgid));
+					}
 					oom::wrap(|| init_proc.add_child(child_pid));
 				}
 			}
 			self.waitable = true;
+			// Job control requires locking other processes, thus it is deferred
+			if !self.kernel_thread {
+				let sid = self.sid;
+				let session_leader = self.is_session_leader();
+				oom::wrap(|| {
+					let groups = groups.try_clone()?;
+					workqueue::schedule(move || exit_job_control(sid, session_leader, &groups))
+				});
+			}
+			// The same applies to tracing
+			let tracer = self.trace.as_ref().map(|trace| trace.tracer);
+			let tracees = mem::take(&mut self.tracees);
+			if tracer.is_some() || !tracees.is_empty() {
+				let pid = self.pid.get();
+				oom::wrap(|| {
+					let tracees = tracees.try_clone()?;
+					workqueue::schedule(move || ptrace::exit_notify(pid, tracer, &tracees))
+				});
+			}
+			// And to the termination of the other processes of the namespace, when exiting its
+			// init process
+			if self.is_ns_init() && self.pid.ns().get_parent().is_some() {
+				let ns = self.pid.ns().clone();
+				let pid = self.pid.get();
+				oom::wrap(|| {
+					let ns = ns.clone();
+					workqueue::schedule(move || kill_namespace(&ns, pid))
+				});
+			}
+			// And to semaphores adjustments
+			let sem_undo = mem::take(&mut self.sem_undo);
+			if !sem_undo.is_empty() {
+				let pid = self.pid.get();
+				oom::wrap(|| {
+					let sem_undo = sem_undo.try_clone()?;
+					workqueue::schedule(move || sem::apply_undo(&sem_undo, pid))
+				});
+			}
+			// And to pidfd watchers, which may include the current process
+			if !self.exit_queue.is_empty() {
+				let queue = self.exit_queue.clone();
+				oom::wrap(|| {
+					let queue = queue.clone();
+					workqueue::schedule(move || queue.wake_all())
+				});
+			}
 		}
 	}
 
@@ -641,7 +913,7 @@ impl Process {
 	}
 
 	/// Returns the process's timer manager.
-	pub fn timer_manager(&self) -> Arc<Mutex<TimerManager>> {
+	pub fn timer_manager(&self) -> Arc<IntMutex<TimerManager>> {
 		self.timer_manager.clone()
 	}
 
@@ -672,6 +944,43 @@ impl Process {
 		}
 	}
 
+	/// Returns the tracing state of the process.
+	///
+	/// If the process is not traced, the function returns `None`.
+	#[inline(always)]
+	pub fn get_trace(&self) -> Option<&TraceState> {
+		self.trace.as_ref()
+	}
+
+	/// Returns a mutable reference to the tracing state of the process.
+	///
+	/// If the process is not traced, the function returns `None`.
+	#[inline(always)]
+	pub fn get_trace_mut(&mut self) -> Option<&mut TraceState> {
+		self.trace.as_mut()
+	}
+
+	/// Returns an immutable slice of the PIDs of the processes traced by the process.
+	#[inline(always)]
+	pub fn get_tracees(&self) -> &[Pid] {
+		&self.tracees
+	}
+
+	/// Adds the process with the given PID `pid` to the processes traced by the process.
+	pub fn add_tracee(&mut self, pid: Pid) -> AllocResult<()> {
+		let Err(i) = self.tracees.binary_search(&pid) else {
+			return Ok(());
+		};
+		self.tracees.insert(i, pid)
+	}
+
+	/// Removes the process with the given PID `pid` from the processes traced by the process.
+	pub fn remove_tracee(&mut self, pid: Pid) {
+		if let Ok(i) = self.tracees.binary_search(&pid) {
+			self.tracees.remove(i);
+		}
+	}
+
 	/// Returns a reference to the process's memory space.
 	///
 	/// If the process is terminated, the function returns `None`.
@@ -715,15 +1024,20 @@ impl Process {
 		if !matches!(self.state, State::Running) {
 			return;
 		}
+		// CPU time is charged from now on
+		self.cpu_ts = rusage::timestamp();
 		// If the process is not in a syscall and a signal is pending on the process,
 		// execute it
 		if !self.syscalling {
-			if let Some(sig) = self.next_signal(false) {
+			// A traced process has to report the signal to its tracer before handling it
+			if self.trace.is_some() && self.next_signal(true).is_some() {
+				ptrace::redirect_signal(self);
+			} else if let Some((sig, info)) = self.dequeue_signal(self.get_deliverable_signals()) {
 				// Prepare signal for execution
 				let signal_handlers = self.signal_handlers.clone();
 				let signal_handlers = signal_handlers.lock();
 				let sig_handler = &signal_handlers[sig.get_id() as usize];
-				sig_handler.exec(sig, &mut *self);
+				sig_handler.exec(sig, &info, &mut *self);
 				// If the process has been killed by the signal, abort switching
 				if !matches!(self.state, State::Running) {
 					return;
@@ -737,8 +1051,12 @@ impl Process {
 			self.update_tls(i);
 		}
 		gdt::flush();
-		// Bind the memory space
-		self.get_mem_space().unwrap().lock().bind();
+		// Bind the memory space. Kernel threads do not have one and use the kernel's context
+		if self.kernel_thread {
+			vmem::kernel().lock().bind();
+		} else {
+			self.get_mem_space().unwrap().lock().bind();
+		}
 		// Increment the number of ticks the process had
 		self.quantum_count = self.quantum_count.saturating_add(1);
 	}
@@ -755,6 +1073,11 @@ impl Process {
 		self.termsig
 	}
 
+	/// Tells whether the process dumped a core file when terminated.
+	pub fn is_coredump(&self) -> bool {
+		self.coredump
+	}
+
 	/// Forks the current process.
 	///
 	/// The internal state of the process (registers and memory) are always copied.
@@ -771,9 +1094,10 @@ impl Process {
 	) -> EResult<Arc<IntMutex<Self>>> {
 		let mut proc = this.lock();
 		debug_assert!(matches!(proc.get_state(), State::Running));
+		// The process is the current one, so its extended state is in the CPU's registers
+		proc.xstate.save();
 		// Handle vfork
 		let vfork_state = if fork_options.vfork {
-			proc.vfork_state = VForkState::Waiting; // TODO Cancel if the following code fails
 			VForkState::Executing
 		} else {
 			VForkState::None
@@ -806,11 +1130,28 @@ impl Process {
 		} else {
 			Arc::new(Mutex::new(proc.signal_handlers.lock().clone()))?
 		};
-		let pid = PidHandle::unique()?;
+		// Clone namespaces
+		let namespaces = if fork_options.new_namespaces != 0 {
+			proc.namespaces
+				.unshare(fork_options.new_namespaces, proc.pid.ns())?
+		} else {
+			proc.namespaces.clone()
+		};
+		// Paths are resolved in the tree of the new mount namespace
+		let (cwd, chroot) = if namespaces.mnt.get_id() == proc.namespaces.mnt.get_id() {
+			(proc.cwd.clone(), proc.chroot.clone())
+		} else {
+			(
+				namespaces.mnt.translate(&proc.cwd),
+				namespaces.mnt.translate(&proc.chroot),
+			)
+		};
+		let pid = PidHandle::unique(&namespaces.pid_for_children)?;
 		let pid_int = pid.get();
-		let process = Self {
+		let mut process = Self {
 			pid,
 			pgid: proc.pgid,
+			sid: proc.sid,
 			tid: pid_int,
 
 			argv: proc.argv.clone(),
@@ -818,7 +1159,9 @@ impl Process {
 			exec_path: proc.exec_path.clone(),
 
 			access_profile: proc.access_profile,
+			dumpable: proc.dumpable,
 			umask: proc.umask,
+			personality: proc.personality,
 
 			state: State::Running,
 			vfork_state,
@@ -830,8 +1173,11 @@ impl Process {
 			parent: Some(this.clone()),
 			children: Vec::new(),
 			process_group: Vec::new(),
+			trace: None,
+			tracees: Vec::new(),
 
 			regs: proc.regs.clone(),
+			xstate: proc.xstate.try_clone()?,
 			syscalling: false,
 
 			waitable: false,
@@ -839,43 +1185,114 @@ impl Process {
 			// TODO if creating a thread: timer_manager: proc.timer_manager.clone(),
 			timer_manager: Arc::new(Mutex::new(TimerManager::new(pid_int)?))?,
 
+			kernel_thread: false,
 			mem_space: Some(mem_space),
 			kernel_stack: buddy::alloc_kernel(KERNEL_STACK_ORDER)?,
 
-			cwd: proc.cwd.clone(),
-			chroot: proc.chroot.clone(),
+			cwd,
+			chroot,
 			file_descriptors,
+			namespaces,
 
 			sigmask: proc.sigmask,
 			sigpending: Default::default(),
+			saved_sigmask: None,
+			sigwait: None,
+			altstack: proc.altstack,
+			restart_block: None,
+			signalfd_queue: Arc::new(WaitQueue::new())?,
+			exit_queue: Arc::new(WaitQueue::new())?,
 			signal_handlers,
 
 			tls_entries: proc.tls_entries,
 
+			sem_undo: Vec::new(),
+			seccomp: proc.seccomp.clone(),
+
 			rusage: RUsage::default(),
+			children_rusage: RUsage::default(),
+			start_time: rusage::timestamp(),
+			cpu_ts: 0,
+			xcpu_next: 0,
+			rlimits: proc.rlimits.clone(),
+			nproc_uid: None,
+			oom_score_adj: proc.oom_score_adj,
 
 			exit_status: proc.exit_status,
 			termsig: 0,
+			coredump: false,
 		};
+		// Enforce the limit on the number of processes of the user
+		let ap = &proc.access_profile;
+		let nproc_limit = proc.rlimits.get(rlimit::RLIMIT_NPROC).unwrap().rlim_cur;
+		let privileged = ap.has_cap(CAP_SYS_RESOURCE) || ap.has_cap(CAP_SYS_ADMIN);
+		let nproc_limit = (nproc_limit != RLIM_INFINITY && !privileged).then_some(nproc_limit);
+		process.charge_nproc(nproc_limit)?;
 		proc.add_child(pid_int)?;
-		Ok(SCHEDULER.get().lock().add_process(process)?)
+		let child = SCHEDULER.get().lock().add_process(process)?;
+		// The parent waits for the child only once the child exists
+		if fork_options.vfork {
+			proc.vfork_state = VForkState::Waiting;
+		}
+		let event = if fork_options.vfork {
+			ptrace::PTRACE_EVENT_VFORK
+		} else if fork_options.share_memory {
+			ptrace::PTRACE_EVENT_CLONE
+		} else {
+			ptrace::PTRACE_EVENT_FORK
+		};
+		ptrace::fork(&mut proc, &mut child.lock(), event);
+		Ok(child)
 	}
 
-	/// Kills the process with the given signal `sig`.
+	/// Kills the process with the given signal `sig`, sent by the kernel.
 	///
 	/// If the process doesn't have a signal handler, the default action for the signal is
 	/// executed.
 	pub fn kill(&mut self, sig: Signal) {
+		self.kill_info(sig, SigInfo::new(sig, signal::SI_KERNEL));
+	}
+
+	/// Kills the process with the given signal `sig`, along with the information `info` that is
+	/// passed to the signal handler.
+	///
+	/// If the process doesn't have a signal handler, the default action for the signal is
+	/// executed.
+	pub fn kill_info(&mut self, sig: Signal, info: SigInfo) {
 		// Cannot kill a zombie process
 		if unlikely(self.state == State::Zombie) {
 			return;
 		}
-		// Ignore blocked signals
+		// Statistics
+		self.rusage.ru_nsignals = self.rusage.ru_nsignals.saturating_add(1);
+		// Blocked signals remain pending until unblocked or waited for
 		if sig.can_catch() && self.sigmask.is_set(sig.get_id() as _) {
+			self.queue_signal(sig, info);
+			// The signal cannot be delivered, so only wake the process if it waits for it
+			if self
+				.sigwait
+				.is_some_and(|set| set.is_set(sig.get_id() as _))
+			{
+				self.wake();
+			}
+			// Waiters may include the current process, which is locked: defer the notification
+			if !self.signalfd_queue.is_empty() {
+				let queue = self.signalfd_queue.clone();
+				let _ = workqueue::schedule(move || queue.wake_all());
+			}
+			return;
+		}
+		// A traced process reports the signal to its tracer before handling it
+		if let Some(trace) = self.trace.as_ref().filter(|_| sig != Signal::SIGKILL) {
+			let cont = self.state == State::Stopped
+				&& !trace.is_stopped()
+				&& sig.get_default_action() == SignalAction::Continue;
+			if self.state == State::Sleeping || cont {
+				self.set_state(State::Running);
+			}
+			self.queue_signal(sig, info);
 			return;
 		}
-		// Statistics
-		self.rusage.ru_nsignals = self.rusage.ru_nsignals.saturating_add(1);
 		// If the signal's action can be executed now, do it
 		{
 			let handlers = self.signal_handlers.clone();
@@ -885,6 +1302,7 @@ impl Process {
 				SignalHandler::Ignore => return,
 				SignalHandler::Default
 					if self.state != State::Stopped
+						|| sig == Signal::SIGKILL
 						|| sig.get_default_action() == SignalAction::Continue =>
 				{
 					sig.get_default_action().exec(sig, self);
@@ -901,21 +1319,20 @@ impl Process {
 			self.set_state(State::Running);
 		}
 		// Set the signal as pending
-		self.sigpending.set(sig.get_id() as _);
+		self.queue_signal(sig, info);
 	}
 
-	/// Kills every process in the process group.
-	pub fn kill_group(&mut self, sig: Signal) {
-		self.process_group
-			.iter()
-			// Avoid deadlock
-			.filter(|pid| **pid != self.pid.get())
-			.filter_map(|pid| Process::get_by_pid(*pid))
-			.for_each(|proc_mutex| {
-				let mut proc = proc_mutex.lock();
-				proc.kill(sig);
-			});
-		self.kill(sig);
+	/// Sets the signal `sig` as pending, with the information `info`.
+	///
+	/// A standard signal that is already pending is not queued a second time.
+	fn queue_signal(&mut self, sig: Signal, info: SigInfo) {
+		// Unwrap cannot fail since the resource exists
+		let limit = self
+			.rlimits
+			.get(rlimit::RLIMIT_SIGPENDING)
+			.unwrap()
+			.rlim_cur;
+		self.sigpending.push(sig, info, limit);
 	}
 
 	/// Tells whether the given signal is blocked by the process.
@@ -923,28 +1340,71 @@ impl Process {
 		self.sigmask.is_set(sig.get_id() as _)
 	}
 
+	/// Returns the set of pending signals.
+	pub fn get_pending_signals(&self) -> SigSet {
+		self.sigpending.get_set()
+	}
+
+	/// Returns the set of signals that can be delivered to the process, which are the ones that
+	/// are not blocked.
+	fn get_deliverable_signals(&self) -> SigSet {
+		let mut set = SigSet(!self.sigmask.0);
+		for sig in [
+			Signal::SIGKILL,
+			Signal::SIGSEGV,
+			Signal::SIGSTOP,
+			Signal::SIGSYS,
+		] {
+			set.set(sig.get_id() as _);
+		}
+		set
+	}
+
 	/// Returns the ID of the next signal to be handled.
 	///
 	/// If `peek` is `false`, the signal is cleared from the bitfield.
 	///
 	/// If no signal is pending, the function returns `None`.
 	pub fn next_signal(&mut self, peek: bool) -> Option<Signal> {
-		let sig = self
-			.sigpending
-			.iter()
-			.enumerate()
-			.filter(|(_, b)| *b)
-			.filter_map(|(i, _)| {
-				let s = Signal::try_from(i as c_int).ok()?;
-				(!s.can_catch() || !self.sigmask.is_set(i)).then_some(s)
-			})
-			.next();
-		if !peek {
-			if let Some(id) = sig {
-				self.sigpending.clear(id.get_id() as _);
-			}
+		if peek {
+			let set = SigSet(self.sigpending.get_set().0 & self.get_deliverable_signals().0);
+			let id = set.iter().next()?;
+			Signal::try_from(id as c_int).ok()
+		} else {
+			self.dequeue_signal(self.get_deliverable_signals())
+				.map(|(sig, _)| sig)
+		}
+	}
+
+	/// Removes the next pending signal in `set` and returns it, along with its information.
+	///
+	/// Signals are dequeued by increasing number, and real-time signals with the same number in
+	/// the order they have been received.
+	///
+	/// If no signal in `set` is pending, the function returns `None`.
+	pub fn dequeue_signal(&mut self, set: SigSet) -> Option<(Signal, SigInfo)> {
+		self.sigpending.pop(set)
+	}
+
+	/// Restores the signal mask saved by `rt_sigsuspend`, if any.
+	pub fn restore_sigmask(&mut self) {
+		if let Some(mask) = self.saved_sigmask.take() {
+			self.sigmask = mask;
 		}
-		sig
+	}
+
+	/// Kills every process in the process group.
+	pub fn kill_group(&mut self, sig: Signal) {
+		self.process_group
+			.iter()
+			// Avoid deadlock
+			.filter(|pid| **pid != self.pid.get())
+			.filter_map(|pid| Process::get_by_pid(*pid))
+			.for_each(|proc_mutex| {
+				let mut proc = proc_mutex.lock();
+				proc.kill(sig);
+			});
+		self.kill(sig);
 	}
 
 	/// Updates the `n`th TLS entry in the GDT.
@@ -966,6 +1426,162 @@ impl Process {
 		&self.rusage
 	}
 
+	/// Returns the accumulated resources usage of the terminated children of the process that
+	/// have been waited for.
+	pub fn get_children_rusage(&self) -> &RUsage {
+		&self.children_rusage
+	}
+
+	/// Returns the timestamp at which the process has been created, in nanoseconds.
+	pub fn get_start_time(&self) -> u64 {
+		self.start_time
+	}
+
+	/// Accumulates the resources usage of the terminated child process `child`, including the
+	/// usage of its own children, into the process's children usage.
+	pub fn add_child_rusage(&mut self, child: &Process) {
+		self.children_rusage.add(&child.rusage);
+		self.children_rusage.add(&child.children_rusage);
+	}
+
+	/// Updates the maximum resident set size of the process with its current usage.
+	///
+	/// If the memory space is in use, the value is not updated.
+	pub fn update_maxrss(&mut self) {
+		let rss = self
+			.mem_space
+			.as_ref()
+			.and_then(|mem_space| Some(mem_space.try_lock()?.get_rss()))
+			.unwrap_or(0);
+		let rss = (rss * PAGE_SIZE / 1024).min(i32::MAX as usize) as i32;
+		self.rusage.ru_maxrss = max(self.rusage.ru_maxrss, rss);
+	}
+
+	/// Counts a page fault of the given kind in the process's resources usage.
+	pub fn count_page_fault(&mut self, fault: PageFault) {
+		let count = match fault {
+			PageFault::Minor => &mut self.rusage.ru_minflt,
+			PageFault::Major => &mut self.rusage.ru_majflt,
+		};
+		*count = count.saturating_add(1);
+	}
+
+	/// Counts a context switch in the process's resources usage.
+	///
+	/// `voluntary` tells whether the process gave up the CPU by itself, by going to sleep.
+	pub fn count_context_switch(&mut self, voluntary: bool) {
+		let count = if voluntary {
+			&mut self.rusage.ru_nvcsw
+		} else {
+			&mut self.rusage.ru_nivcsw
+		};
+		*count = count.saturating_add(1);
+	}
+
+	/// Sets the limit for the given `resource`, and applies it to the structures of the process
+	/// that enforce it.
+	///
+	/// If the resource does not exist, the function returns [`errno::EINVAL`].
+	pub fn set_rlimit(&mut self, resource: c_int, limit: RLimit) -> EResult<()> {
+		*self
+			.rlimits
+			.get_mut(resource)
+			.ok_or_else(|| errno!(EINVAL))? = limit;
+		match resource {
+			rlimit::RLIMIT_NOFILE => {
+				if let Some(fds) = &self.file_descriptors {
+					fds.lock().set_limit(limit.rlim_cur);
+				}
+			}
+			rlimit::RLIMIT_AS | rlimit::RLIMIT_DATA => {
+				if let Some(mem_space) = &self.mem_space {
+					mem_space.lock().set_limits(&self.rlimits);
+				}
+			}
+			// The signal is sent again as soon as the new soft limit is reached
+			rlimit::RLIMIT_CPU => self.xcpu_next = 0,
+			_ => {}
+		}
+		Ok(())
+	}
+
+	/// Counts the process for its current real user ID in the number of processes of the user,
+	/// which is checked against `RLIMIT_NPROC`. If the process was counted for another user ID,
+	/// it is moved.
+	///
+	/// If `limit` is set and the user has already reached it, the function returns
+	/// [`errno::EAGAIN`].
+	pub fn charge_nproc(&mut self, limit: Option<RLim>) -> EResult<()> {
+		let uid = self.access_profile.uid;
+		if self.nproc_uid == Some(uid) {
+			return Ok(());
+		}
+		rlimit::nproc_charge(uid, limit)?;
+		if let Some(old) = self.nproc_uid.replace(uid) {
+			rlimit::nproc_release(old);
+		}
+		Ok(())
+	}
+
+	/// Updates the dumpable state of the process after a change of its credentials from `old`.
+	///
+	/// If any user or group ID changed, the process is not dumpable anymore.
+	pub fn update_dumpable(&mut self, old: &AccessProfile) {
+		if !self.access_profile.same_ids(old) {
+			self.dumpable = false;
+		}
+	}
+
+	/// Charges the CPU time elapsed since the last accounting to the process.
+	///
+	/// `user` tells whether the time has been spent in userspace.
+	pub fn account_cpu(&mut self, user: bool) {
+		let now = rusage::timestamp();
+		let delta = now.saturating_sub(self.cpu_ts);
+		self.cpu_ts = now;
+		self.account_cpu_time(delta, user);
+	}
+
+	/// Charges `delta` nanoseconds of CPU time to the process.
+	///
+	/// `user` tells whether the time has been spent in userspace.
+	///
+	/// The time is also charged to the process's `ITIMER_VIRTUAL` and `ITIMER_PROF` timers, then
+	/// the `RLIMIT_CPU` limit is enforced.
+	pub fn account_cpu_time(&mut self, delta: u64, user: bool) {
+		let time = if user {
+			&mut self.rusage.ru_utime
+		} else {
+			&mut self.rusage.ru_stime
+		};
+		*time = Timeval::from_nano(time.to_nano().saturating_add(delta));
+		let expired = self.timer_manager.lock().consume_cpu_time(delta, user);
+		for sig in expired.into_iter().flatten() {
+			self.kill(sig);
+		}
+		self.check_cpu_limit();
+	}
+
+	/// Enforces the `RLIMIT_CPU` limit on the CPU time consumed by the process.
+	///
+	/// Past the soft limit, `SIGXCPU` is sent once per second of CPU time. Past the hard limit,
+	/// the process is killed.
+	fn check_cpu_limit(&mut self) {
+		const SEC: u64 = 1_000_000_000;
+		let Some(limit) = self.rlimits.get(rlimit::RLIMIT_CPU) else {
+			return;
+		};
+		let to_nano = |lim: RLim| (lim != RLIM_INFINITY).then(|| lim.saturating_mul(SEC));
+		let (soft, hard) = (to_nano(limit.rlim_cur), to_nano(limit.rlim_max));
+		let cpu_time = self.rusage.cpu_time();
+		if hard.is_some_and(|hard| cpu_time >= hard) {
+			self.kill(Signal::SIGKILL);
+		} else if let Some(soft) = soft.filter(|soft| cpu_time >= max(*soft, self.xcpu_next)) {
+			self.kill(Signal::SIGXCPU);
+			self.xcpu_next = max(soft, cpu_time).saturating_add(SEC);
+		}
+	}
+
 	/// If the process is a vfork child, resets its state and its parent's
 	/// state.
 	pub fn reset_vfork(&mut self) {
@@ -1005,20 +1621,22 @@ impl Process {
 		}
 	}
 
+	/// Returns the number of physical memory pages used by the process.
+	pub fn get_rss(&self) -> usize {
+		if let Some(mem_space_mutex) = &self.mem_space {
+			let mem_space = mem_space_mutex.lock();
+			mem_space.get_rss()
+		} else {
+			0
+		}
+	}
+
 	/// Returns the OOM score, used by the OOM killer to determine the process
 	/// to kill in case the system runs out of memory.
 	///
 	/// A higher score means a higher probability of getting killed.
 	pub fn get_oom_score(&self) -> u16 {
-		let mut score: u16 = 0;
-		// TODO Compute the score using physical memory usage
-		// TODO Take into account userspace-set values (oom may be disabled for this
-		// process, an absolute score or a bonus might be given, etc...)
-		// If the process is owned by the superuser, give it a bonus
-		if self.access_profile.is_privileged() {
-			score = score.saturating_sub(100);
-		}
-		score
+		oom::score(self.get_rss(), self.oom_score_adj)
 	}
 }
 
@@ -1034,7 +1652,7 @@ impl AccessProfile {
 	/// Tells whether the agent can kill the process.
 	pub fn can_kill(&self, proc: &Process) -> bool {
 		// if privileged
-		if self.is_privileged() {
+		if self.has_cap(CAP_KILL) {
 			return true;
 		}
 		// if sender's `uid` or `euid` equals receiver's `uid` or `suid`
@@ -1050,6 +1668,9 @@ impl Drop for Process {
 		if self.is_init() {
 			panic!("Terminated init process!");
 		}
+		if let Some(uid) = self.nproc_uid.take() {
+			rlimit::nproc_release(uid);
+		}
 		// Free kernel stack
 		unsafe {
 			buddy::free_kernel(self.kernel_stack.as_ptr(), KERNEL_STACK_ORDER);
@@ -1057,6 +1678,80 @@ impl Drop for Process {
 	}
 }
 
+/// Kills the processes of the PID namespace `ns` after the exit of its init process, of PID
+/// `init`.
+fn kill_namespace(ns: &PidNamespace, init: Pid) {
+	let pids = oom::wrap(|| ns.get_pids());
+	for pid in pids {
+		if pid == init {
+			continue;
+		}
+		if let Some(proc) = Process::get_by_pid(pid) {
+			proc.lock().kill(Signal::SIGKILL);
+		}
+	}
+}
+
+/// Handles job control after the exit of a process.
+///
+/// Arguments:
+/// - `sid` is the session of the exited process.
+/// - `session_leader` tells whether the exited process was the leader of its session.
+/// - `groups` is the list of process groups that may have been orphaned by the exit.
+///
+/// If the session leader exits, its controlling terminal is hung up. Then, every process group
+/// that became orphaned and has a stopped member receives `SIGHUP`, followed by `SIGCONT`.
+///
+/// This function locks processes, thus it must be called in a context where no process is locked.
+fn exit_job_control(sid: Pid, session_leader: bool, groups: &[Pid]) {
+	if session_leader {
+		let mut tty = TTY.display.lock();
+		if tty.get_sid() == sid {
+			tty.hangup();
+		}
+	}
+	let procs = oom::wrap(|| {
+		SCHEDULER
+			.get()
+			.lock()
+			.iter_process()
+			.map(|(_, proc)| proc.clone())
+			.collect::<CollectResult<Vec<_>>>()
+			.0
+	});
+	for pgid in groups.iter().copied().filter(|pgid| *pgid != 0) {
+		let members = || {
+			procs.iter().filter(move |proc| {
+				let proc = proc.lock();
+				proc.pgid == pgid && proc.state != State::Zombie
+			})
+		};
+		// A group is orphaned if no member has a parent in another group of the same session
+		let mut orphaned = true;
+		let mut stopped = false;
+		for proc_mutex in members() {
+			let (parent, sid, state) = {
+				let proc = proc_mutex.lock();
+				(proc.parent.clone(), proc.sid, proc.get_state())
+			};
+			stopped |= state == State::Stopped;
+			if let Some(parent) = parent {
+				let parent = parent.lock();
+				if parent.state != State::Zombie && parent.pgid != pgid && parent.sid == sid {
+					orphaned = false;
+				}
+			}
+		}
+		if orphaned && stopped {
+			for proc_mutex in members() {
+				let mut proc = proc_mutex.lock();
+				proc.kill(Signal::SIGHUP);
+				proc.kill(Signal::SIGCONT);
+			}
+		}
+	}
+}
+
 fn yield_current_impl(regs: &mut Regs) -> bool {
 	let proc_mutex = Process::current();
 	let mut proc = proc_mutex.lock();
@@ -1065,9 +1760,25 @@ fn yield_current_impl(regs: &mut Regs) -> bool {
 		return true;
 	}
 	// If no signal is pending, return
-	let Some(sig) = proc.next_signal(false) else {
+	let deliverable = proc.get_deliverable_signals();
+	let Some((mut sig, mut info)) = proc.dequeue_signal(deliverable) else {
+		proc.restore_sigmask();
 		return false;
 	};
+	// A traced process stops to report the signal. The tracer decides which signal, if any, is
+	// delivered
+	if proc.trace.is_some() && sig != Signal::SIGKILL {
+		drop(proc);
+		let Some(s) = ptrace::stop(regs, sig.get_id(), 0, 0) else {
+			proc_mutex.lock().restore_sigmask();
+			return false;
+		};
+		if s != sig {
+			info = SigInfo::new(s, signal::SI_USER);
+			sig = s;
+		}
+		proc = proc_mutex.lock();
+	}
 	// Prepare signal for execution
 	let handlers = proc.signal_handlers.clone();
 	let handlers = handlers.lock();
@@ -1075,7 +1786,9 @@ fn yield_current_impl(regs: &mut Regs) -> bool {
 	// Update registers with the ones passed to the system call so that `sigreturn` returns to
 	// the correct location
 	proc.regs = regs.clone();
-	handler.exec(sig, &mut proc);
+	proc.xstate.save();
+	handler.exec(sig, &info, &mut proc);
+	proc.restore_sigmask();
 	// Alter the execution flow of the current context according to the new state of the
 	// process
 	match proc.state {
@@ -0,0 +1,217 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Namespaces isolate a process's view of a global resource.
+//!
+//! The following kinds of namespaces are supported:
+//! - mount: the tree of mountpoints
+//! - UTS: the hostname
+//! - IPC: System V IPC objects and POSIX message queues
+//! - network: network interfaces and the routing table
+//! - PID: the process IDs (see [`super::pid`])
+//!
+//! A new namespace is created with `clone` or `unshare`, and a process can enter an existing
+//! one with `setns`.
+
+use super::pid::PidNamespace;
+use crate::{
+	file::vfs::{mountpoint, mountpoint::MountNamespace},
+	ipc::IpcNamespace,
+	net::NetNamespace,
+};
+use core::{
+	ffi::c_int,
+	sync::atomic::{AtomicU32, Ordering::Relaxed},
+};
+use utils::{
+	collections::vec::Vec,
+	errno::{AllocResult, EResult},
+	lock::Mutex,
+	ptr::arc::Arc,
+	TryClone,
+};
+
+/// Clone flag: create a new mount namespace.
+pub const CLONE_NEWNS: c_int = 0x20000;
+/// Clone flag: create a new UTS namespace.
+pub const CLONE_NEWUTS: c_int = 0x4000000;
+/// Clone flag: create a new IPC namespace.
+pub const CLONE_NEWIPC: c_int = 0x8000000;
+/// Clone flag: create a new PID namespace.
+pub const CLONE_NEWPID: c_int = 0x20000000;
+/// Clone flag: create a new network namespace.
+pub const CLONE_NEWNET: c_int = 0x40000000;
+/// The set of flags creating a namespace supported by the kernel.
+pub const CLONE_NEW_MASK: c_int =
+	CLONE_NEWNS | CLONE_NEWUTS | CLONE_NEWIPC | CLONE_NEWPID | CLONE_NEWNET;
+
+/// The next namespace ID to be allocated.
+///
+/// Like on Linux, IDs start at `0xf0000000`.
+static NEXT_ID: AtomicU32 = AtomicU32::new(0xf0000000);
+
+/// Allocates a new namespace ID.
+pub fn alloc_id() -> u32 {
+	NEXT_ID.fetch_add(1, Relaxed)
+}
+
+/// A UTS namespace.
+#[derive(Debug)]
+pub struct UtsNamespace {
+	/// The ID of the namespace.
+	id: u32,
+	/// The hostname.
+	pub hostname: Mutex<Vec<u8>>,
+}
+
+impl UtsNamespace {
+	/// Returns the ID of the namespace.
+	pub fn get_id(&self) -> u32 {
+		self.id
+	}
+
+	/// Creates a copy of the namespace.
+	pub fn try_clone(&self) -> AllocResult<Self> {
+		Ok(Self {
+			id: alloc_id(),
+			hostname: Mutex::new(self.hostname.lock().try_clone()?),
+		})
+	}
+}
+
+/// The set of namespaces a process belongs to.
+///
+/// The PID namespace of the process itself is stored in its PID handle, since it cannot change.
+#[derive(Clone, Debug)]
+pub struct Namespaces {
+	/// The mount namespace.
+	pub mnt: Arc<MountNamespace>,
+	/// The UTS namespace.
+	pub uts: Arc<UtsNamespace>,
+	/// The IPC namespace.
+	pub ipc: Arc<IpcNamespace>,
+	/// The network namespace.
+	pub net: Arc<NetNamespace>,
+	/// The PID namespace in which the children of the process are created.
+	pub pid_for_children: Arc<PidNamespace>,
+}
+
+/// The initial set of namespaces.
+static INITIAL: Mutex<Option<Namespaces>> = Mutex::new(None);
+
+impl Namespaces {
+	/// Returns the initial set of namespaces, used by the first process and kernel threads.
+	///
+	/// The VFS must have been initialized before calling this function.
+	pub fn initial() -> AllocResult<Self> {
+		let mut initial = INITIAL.lock();
+		if let Some(ns) = &*initial {
+			return Ok(ns.clone());
+		}
+		let ns = Self {
+			mnt: mountpoint::init_ns(),
+			uts: Arc::new(UtsNamespace {
+				id: alloc_id(),
+				hostname: Mutex::new(Vec::new()),
+			})?,
+			ipc: Arc::new(IpcNamespace::new())?,
+			net: Arc::new(NetNamespace::new())?,
+			pid_for_children: PidNamespace::root()?,
+		};
+		Ok(initial.insert(ns).clone())
+	}
+
+	/// Returns a copy of the set in which a new namespace replaces each kind of namespace
+	/// selected by `flags` (a combination of `CLONE_NEW*` flags).
+	///
+	/// `pid_ns` is the PID namespace of the process, in which a new PID namespace is nested.
+	pub fn unshare(&self, flags: c_int, pid_ns: &Arc<PidNamespace>) -> EResult<Self> {
+		let mut ns = self.clone();
+		if flags & CLONE_NEWNS != 0 {
+			ns.mnt = Arc::new(MountNamespace::try_clone(&self.mnt)?)?;
+		}
+		if flags & CLONE_NEWUTS != 0 {
+			ns.uts = Arc::new(UtsNamespace::try_clone(&self.uts)?)?;
+		}
+		if flags & CLONE_NEWIPC != 0 {
+			ns.ipc = Arc::new(IpcNamespace::new())?;
+		}
+		if flags & CLONE_NEWNET != 0 {
+			ns.net = Arc::new(NetNamespace::new())?;
+		}
+		if flags & CLONE_NEWPID != 0 {
+			ns.pid_for_children = PidNamespace::new(pid_ns.clone())?;
+		}
+		Ok(ns)
+	}
+}
+
+/// A kind of namespace, as exposed in `/proc/<pid>/ns`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NsType {
+	/// Mount namespace.
+	Mnt,
+	/// UTS namespace.
+	Uts,
+	/// IPC namespace.
+	Ipc,
+	/// Network namespace.
+	Net,
+	/// PID namespace of the process.
+	Pid,
+	/// PID namespace of the process's future children.
+	PidForChildren,
+}
+
+impl NsType {
+	/// Returns the `CLONE_NEW*` flag corresponding to the kind of namespace.
+	pub fn get_flag(&self) -> c_int {
+		match self {
+			Self::Mnt => CLONE_NEWNS,
+			Self::Uts => CLONE_NEWUTS,
+			Self::Ipc => CLONE_NEWIPC,
+			Self::Net => CLONE_NEWNET,
+			Self::Pid | Self::PidForChildren => CLONE_NEWPID,
+		}
+	}
+
+	/// Returns the name of the kind of namespace.
+	pub fn get_name(&self) -> &'static str {
+		match self {
+			Self::Mnt => "mnt",
+			Self::Uts => "uts",
+			Self::Ipc => "ipc",
+			Self::Net => "net",
+			Self::Pid | Self::PidForChildren => "pid",
+		}
+	}
+
+	/// Returns the ID of the namespace of this kind among `ns`.
+	///
+	/// `pid_ns` is the PID namespace of the process owning `ns`.
+	pub fn get_id(&self, ns: &Namespaces, pid_ns: &PidNamespace) -> u32 {
+		match self {
+			Self::Mnt => ns.mnt.get_id(),
+			Self::Uts => ns.uts.get_id(),
+			Self::Ipc => ns.ipc.get_id(),
+			Self::Net => ns.net.get_id(),
+			Self::Pid => pid_ns.get_id(),
+			Self::PidForChildren => ns.pid_for_children.get_id(),
+		}
+	}
+}
@@ -22,14 +22,32 @@
 //! The OOM killer terminates one or more processes according to a score computed for
 //! each of them.
 //!
+//! The score of a process is the proportion of physical memory it uses, adjusted by the
+//! userspace-tunable `oom_score_adj` value.
+//!
 //! This is an emergency procedure which is not supposed to be used under normal conditions.
 
-use utils::{errno::AllocResult, lock::Mutex};
+use crate::{
+	memory::stats::MEM_INFO,
+	println,
+	process::{pid::INIT_PID, scheduler::SCHEDULER, signal::Signal, Process, State},
+};
+use utils::{
+	errno::AllocResult,
+	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
+	DisplayableStr,
+};
 
 /// The maximum number of times the kernel tries to kill a process to retrieve
 /// memory.
 const MAX_TRIES: u32 = 5;
 
+/// The minimum value of `oom_score_adj`, which prevents the process from being killed.
+pub const OOM_SCORE_ADJ_MIN: i16 = -1000;
+/// The maximum value of `oom_score_adj`, which makes the process the first to be killed.
+pub const OOM_SCORE_ADJ_MAX: i16 = 1000;
+
 /// Variable telling whether the OOM killer is enabled.
 static KILLER_ENABLE: Mutex<bool> = Mutex::new(true);
 
@@ -43,13 +61,81 @@ pub fn set_killer_enabled(enable: bool) {
 	*KILLER_ENABLE.lock() = enable;
 }
 
+/// Returns the OOM score of a process, between `0` and `1000`.
+///
+/// Arguments:
+/// - `rss` is the number of physical memory pages used by the process.
+/// - `adj` is the process's `oom_score_adj`.
+pub fn score(rss: usize, adj: i16) -> u16 {
+	// Memory sizes are in KiB
+	let total = MEM_INFO.lock().mem_total / 4;
+	score_of(rss, total, adj)
+}
+
+/// Returns the OOM score of a process using `rss` out of `total` physical memory pages, with the
+/// `oom_score_adj` value `adj`.
+fn score_of(rss: usize, total: usize, adj: i16) -> u16 {
+	let usage = (rss.saturating_mul(1000) / total.max(1)).min(1000) as i32;
+	(usage + adj as i32).clamp(0, 1000) as u16
+}
+
+/// Selects the process to be killed.
+///
+/// Processes that are locked by the caller cannot be examined, thus they are ignored.
+///
+/// The function returns the process, along with its score and the number of physical memory
+/// pages it uses.
+fn select_victim() -> Option<(Arc<IntMutex<Process>>, u16, usize)> {
+	let sched = SCHEDULER.get().try_lock()?;
+	sched
+		.iter_process()
+		.filter_map(|(_, proc_mutex)| {
+			let proc = proc_mutex.try_lock()?;
+			if proc.get_pid() == INIT_PID
+				|| proc.kernel_thread
+				|| proc.get_state() == State::Zombie
+				|| proc.oom_score_adj == OOM_SCORE_ADJ_MIN
+			{
+				return None;
+			}
+			let rss = proc.mem_space.as_ref()?.try_lock()?.get_rss();
+			let score = score(rss, proc.oom_score_adj);
+			Some((proc_mutex.clone(), score, rss))
+		})
+		.max_by_key(|(_, score, rss)| (*score, *rss))
+}
+
 /// Runs the OOM killer.
+///
+/// The process with the highest score is killed with `SIGKILL`. Its memory is released
+/// immediately, unless it is in use by the current context.
 pub fn kill() {
 	if !is_killer_enabled() {
 		panic!("Out of memory");
 	}
-
-	// TODO Get the process with the highest OOM score (ignore init process)
+	let Some((proc_mutex, score, rss)) = select_victim() else {
+		return;
+	};
+	let Some(mut proc) = proc_mutex.try_lock() else {
+		return;
+	};
+	let name = proc.argv.first().map(|s| s.as_bytes()).unwrap_or(b"?");
+	println!(
+		"Out of memory: killing process {pid} ({name}), score {score}, resident {rss} kB",
+		pid = proc.get_pid(),
+		name = DisplayableStr(name),
+		rss = rss * 4,
+	);
+	proc.kill(Signal::SIGKILL);
+	// Release the memory of the process
+	let bound = proc
+		.mem_space
+		.as_ref()
+		.map(|mem_space| mem_space.try_lock().is_none_or(|m| m.is_bound()))
+		.unwrap_or(true);
+	if !bound {
+		proc.mem_space = None;
+	}
 }
 
 /// Executes the given function.
@@ -69,3 +155,27 @@ pub fn wrap<T, F: FnMut() -> AllocResult<T>>(mut f: F) -> T {
 
 	panic!("OOM killer is unable to free up space for new allocations!");
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn oom_score() {
+		// Proportional to the share of memory used
+		assert_eq!(score_of(0, 1000, 0), 0);
+		assert_eq!(score_of(250, 1000, 0), 250);
+		assert_eq!(score_of(1000, 1000, 0), 1000);
+		assert_eq!(score_of(1, 4, 0), 250);
+		// Adjusted, then clamped
+		assert_eq!(score_of(250, 1000, 100), 350);
+		assert_eq!(score_of(250, 1000, -100), 150);
+		assert_eq!(score_of(250, 1000, OOM_SCORE_ADJ_MAX), 1000);
+		assert_eq!(score_of(250, 1000, OOM_SCORE_ADJ_MIN), 0);
+		assert_eq!(score_of(0, 1000, OOM_SCORE_ADJ_MAX), 1000);
+		// A small process with a high adjustment is preferred over a large one
+		assert!(score_of(10, 1000, 500) > score_of(400, 1000, 0));
+		// Unknown total memory
+		assert_eq!(score_of(5, 0, 0), 1000);
+	}
+}
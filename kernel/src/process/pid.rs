@@ -20,8 +20,19 @@
 //!
 //! Each process must have a unique PID, thus they have to be allocated.
 //! A bitfield is used to store the used PIDs.
+//!
+//! PID namespaces are nested: a process has a PID in its own namespace and in each of its
+//! ancestors. The PID in the root namespace is the one used by the kernel to identify the process.
 
-use utils::{collections::id_allocator::IDAllocator, errno::AllocResult, lock::Mutex};
+use super::namespace;
+use core::{fmt, fmt::Formatter};
+use utils::{
+	collections::{btreemap::BTreeMap, id_allocator::IDAllocator, vec::Vec},
+	errno,
+	errno::{AllocResult, EResult},
+	lock::Mutex,
+	ptr::arc::Arc,
+};
 
 /// Type representing a Process ID. This ID is unique for every running
 /// processes.
@@ -31,53 +42,280 @@ pub type Pid = u16;
 const MAX_PID: Pid = 32768;
 /// The PID of the init process.
 pub const INIT_PID: Pid = 1;
+/// The maximum nesting level of PID namespaces.
+const MAX_LEVEL: usize = 32;
+
+/// The root PID namespace.
+static ROOT: Mutex<Option<Arc<PidNamespace>>> = Mutex::new(None);
+
+/// The state of a PID namespace.
+struct PidNamespaceInner {
+	/// The PID allocator.
+	allocator: Option<IDAllocator>,
+	/// The global PID of each process in the namespace, by PID in the namespace.
+	///
+	/// This is not filled for the root namespace, in which both PIDs are equal.
+	pids: BTreeMap<Pid, Pid>,
+}
+
+/// A PID namespace.
+pub struct PidNamespace {
+	/// The ID of the namespace.
+	id: u32,
+	/// The parent namespace. If `None`, this is the root namespace.
+	parent: Option<Arc<PidNamespace>>,
+	/// The nesting level of the namespace. The root namespace has level `0`.
+	level: usize,
+	/// The state of the namespace.
+	inner: Mutex<PidNamespaceInner>,
+}
+
+impl fmt::Debug for PidNamespace {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("PidNamespace")
+			.field("id", &self.id)
+			.field("level", &self.level)
+			.finish_non_exhaustive()
+	}
+}
+
+impl PidNamespace {
+	/// Creates a namespace with the given parent.
+	fn new_inner(parent: Option<Arc<Self>>) -> Self {
+		let level = parent.as_ref().map(|p| p.level + 1).unwrap_or(0);
+		Self {
+			id: namespace::alloc_id(),
+			parent,
+			level,
+			inner: Mutex::new(PidNamespaceInner {
+				allocator: None,
+				pids: BTreeMap::new(),
+			}),
+		}
+	}
+
+	/// Returns the root namespace.
+	pub fn root() -> AllocResult<Arc<Self>> {
+		let mut root = ROOT.lock();
+		if let Some(root) = &*root {
+			return Ok(root.clone());
+		}
+		Ok(root.insert(Arc::new(Self::new_inner(None))?).clone())
+	}
+
+	/// Creates a namespace nested in `parent`.
+	///
+	/// If the maximum nesting level is reached, the function returns [`errno::EUSERS`].
+	pub fn new(parent: Arc<Self>) -> EResult<Arc<Self>> {
+		if parent.level + 1 >= MAX_LEVEL {
+			return Err(errno!(EUSERS));
+		}
+		Ok(Arc::new(Self::new_inner(Some(parent)))?)
+	}
+
+	/// Returns the ID of the namespace.
+	pub fn get_id(&self) -> u32 {
+		self.id
+	}
+
+	/// Returns the parent namespace. If `None`, this is the root namespace.
+	pub fn get_parent(&self) -> Option<&Arc<Self>> {
+		self.parent.as_ref()
+	}
+
+	/// Tells whether `self` is `other` or one of its ancestors.
+	pub fn is_ancestor_of(&self, other: &Self) -> bool {
+		let mut cur = Some(other);
+		while let Some(ns) = cur {
+			if ns.id == self.id {
+				return true;
+			}
+			cur = ns.parent.as_deref();
+		}
+		false
+	}
+
+	/// Returns the global PID of the process with PID `pid` in the namespace.
+	///
+	/// If no such process exists, the function returns `None`.
+	pub fn to_global(&self, pid: Pid) -> Option<Pid> {
+		if self.parent.is_none() {
+			return Some(pid);
+		}
+		self.inner.lock().pids.get(&pid).copied()
+	}
+
+	/// Returns the PID in the namespace of the process with the global PID `global`.
+	///
+	/// If the process is not visible from the namespace, the function returns `None`.
+	pub fn to_local(&self, global: Pid) -> Option<Pid> {
+		if self.parent.is_none() {
+			return Some(global);
+		}
+		self.inner
+			.lock()
+			.pids
+			.iter()
+			.find(|(_, g)| **g == global)
+			.map(|(pid, _)| *pid)
+	}
 
-/// The PID allocator.
-static ALLOCATOR: Mutex<Option<IDAllocator>> = Mutex::new(None);
-
-/// Perform an operation with the allocator.
-fn allocator_do<F: Fn(&mut IDAllocator) -> AllocResult<T>, T>(f: F) -> AllocResult<T> {
-	let mut allocator = ALLOCATOR.lock();
-	let allocator = match &mut *allocator {
-		Some(a) => a,
-		None => allocator.insert(IDAllocator::new(MAX_PID as _)?),
-	};
-	f(allocator)
+	/// Returns the global PID of the init process of the namespace, if any.
+	pub fn get_init(&self) -> Option<Pid> {
+		self.to_global(INIT_PID)
+	}
+
+	/// Returns the global PIDs of all the processes in the namespace, excluding the processes
+	/// of nested namespaces that do not belong to it.
+	///
+	/// This function must not be called on the root namespace, for which the list is not kept.
+	pub fn get_pids(&self) -> AllocResult<Vec<Pid>> {
+		let inner = self.inner.lock();
+		let mut pids = Vec::with_capacity(inner.pids.len())?;
+		for (_, pid) in inner.pids.iter() {
+			pids.push(*pid)?;
+		}
+		Ok(pids)
+	}
+
+	/// Allocates a PID in the namespace.
+	///
+	/// `global` is the global PID of the process. It is required for all namespaces except the
+	/// root.
+	fn alloc(&self, global: Option<Pid>) -> AllocResult<Pid> {
+		let mut inner = self.inner.lock();
+		let allocator = match &mut inner.allocator {
+			Some(a) => a,
+			None => inner.allocator.insert(IDAllocator::new(MAX_PID as _)?),
+		};
+		let pid = (allocator.alloc(None)? + 1) as Pid;
+		if let Some(global) = global {
+			if let Err(e) = inner.pids.insert(pid, global) {
+				// Unwrap cannot fail since the allocator has been created above
+				inner.allocator.as_mut().unwrap().free((pid - 1) as _);
+				return Err(e);
+			}
+		}
+		Ok(pid)
+	}
+
+	/// Frees the given PID in the namespace.
+	fn free(&self, pid: Pid) {
+		let mut inner = self.inner.lock();
+		if let Some(allocator) = &mut inner.allocator {
+			allocator.free((pid - 1) as _);
+		}
+		inner.pids.remove(&pid);
+	}
 }
 
 /// Wrapper for a PID, freeing it on drop.
 #[derive(Debug)]
-pub struct PidHandle(Pid);
+pub struct PidHandle {
+	/// The PID of the process in each namespace, from the root namespace to the process's
+	/// namespace.
+	pids: Vec<(Arc<PidNamespace>, Pid)>,
+}
 
 impl PidHandle {
 	/// Returns the init PID.
 	///
 	/// This function **must not** be used outside the creation of the first process.
 	pub(super) fn init() -> AllocResult<Self> {
-		allocator_do(|a| {
-			a.set_used((INIT_PID - 1) as _);
-			Ok(())
-		})?;
-		Ok(Self(INIT_PID))
+		let root = PidNamespace::root()?;
+		{
+			let mut inner = root.inner.lock();
+			let allocator = match &mut inner.allocator {
+				Some(a) => a,
+				None => inner.allocator.insert(IDAllocator::new(MAX_PID as _)?),
+			};
+			allocator.set_used((INIT_PID - 1) as _);
+		}
+		Ok(Self {
+			pids: Vec::try_from([(root, INIT_PID)])?,
+		})
 	}
 
-	/// Returns an unused PID and marks it as used.
-	pub fn unique() -> AllocResult<PidHandle> {
-		allocator_do(|allocator| allocator.alloc(None)).map(|i| PidHandle((i + 1) as _))
+	/// Returns an unused PID in the namespace `ns` (and its ancestors) and marks it as used.
+	pub fn unique(ns: &Arc<PidNamespace>) -> AllocResult<PidHandle> {
+		// The namespaces from the root to `ns`
+		let mut namespaces = Vec::with_capacity(ns.level + 1)?;
+		let mut cur = Some(ns);
+		while let Some(ns) = cur {
+			namespaces.push(ns.clone())?;
+			cur = ns.parent.as_ref();
+		}
+		namespaces.reverse();
+		// If an allocation fails, the PIDs allocated so far are freed on drop
+		let mut handle = Self {
+			pids: Vec::with_capacity(namespaces.len())?,
+		};
+		let mut global = None;
+		for ns in namespaces {
+			let pid = ns.alloc(global)?;
+			global.get_or_insert(pid);
+			// Cannot fail since the capacity has been reserved
+			handle.pids.push((ns, pid))?;
+		}
+		Ok(handle)
 	}
 
-	/// Returns the actual PID.
+	/// Returns the actual PID, in the root namespace.
 	pub fn get(&self) -> Pid {
-		self.0
+		self.pids[0].1
+	}
+
+	/// Returns the namespace of the process.
+	pub fn ns(&self) -> &Arc<PidNamespace> {
+		// Cannot fail since the handle contains at least the root namespace
+		&self.pids.last().unwrap().0
+	}
+
+	/// Returns the PID in the namespace `ns`.
+	///
+	/// If the process is not visible from `ns`, the function returns `None`.
+	pub fn get_in(&self, ns: &PidNamespace) -> Option<Pid> {
+		self.pids
+			.iter()
+			.find(|(n, _)| n.id == ns.id)
+			.map(|(_, pid)| *pid)
+	}
+
+	/// Tells whether the process is the init process of its namespace.
+	pub fn is_ns_init(&self) -> bool {
+		// Cannot fail since the handle contains at least the root namespace
+		self.pids.last().unwrap().1 == INIT_PID
 	}
 }
 
 impl Drop for PidHandle {
 	fn drop(&mut self) {
-		// Cannot fail
-		let _ = allocator_do(|a| {
-			a.free((self.0 - 1) as _);
-			Ok(())
-		});
+		for (ns, pid) in self.pids.iter().rev() {
+			ns.free(*pid);
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn pid_namespace_nested() {
+		let root = PidNamespace::root().unwrap();
+		let ns = PidNamespace::new(root.clone()).unwrap();
+		let first = PidHandle::unique(&ns).unwrap();
+		let second = PidHandle::unique(&ns).unwrap();
+		assert_eq!(first.get_in(&ns), Some(INIT_PID));
+		assert_eq!(second.get_in(&ns), Some(2));
+		assert!(first.is_ns_init());
+		assert_eq!(ns.to_global(2), Some(second.get()));
+		assert_eq!(ns.to_local(second.get()), Some(2));
+		assert_eq!(root.to_local(second.get()), Some(second.get()));
+		assert_eq!(second.get_in(&root), Some(second.get()));
+		assert!(root.is_ancestor_of(&ns));
+		assert!(!ns.is_ancestor_of(&root));
+		drop(first);
+		assert_eq!(ns.get_init(), None);
 	}
 }
@@ -0,0 +1,591 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Process tracing, used by debuggers and system call tracers.
+//!
+//! A tracer attaches to a tracee using the `ptrace` system call. Then, the tracee stops on
+//! specific events (signal delivery, system calls, fork, exec, ...) and the tracer is notified
+//! through `wait4`.
+//!
+//! While the tracee is stopped (*ptrace-stop*), the tracer can inspect and modify its registers
+//! and memory, before resuming it.
+
+use crate::{
+	gdt,
+	memory::buddy,
+	process::{
+		oom,
+		pid::Pid,
+		regs::Regs,
+		scheduler,
+		signal::{SigInfo, Signal, SI_KERNEL},
+		Process, State, KERNEL_STACK_ORDER,
+	},
+};
+use core::{mem::size_of, ptr};
+use macros::AnyRepr;
+use utils::{errno, errno::EResult};
+
+/// Option: set bit 7 of the signal number when reporting a system call stop.
+pub const PTRACE_O_TRACESYSGOOD: u32 = 0x1;
+/// Option: stop the tracee at the next `fork` and trace the child.
+pub const PTRACE_O_TRACEFORK: u32 = 0x2;
+/// Option: stop the tracee at the next `vfork` and trace the child.
+pub const PTRACE_O_TRACEVFORK: u32 = 0x4;
+/// Option: stop the tracee at the next `clone` and trace the child.
+pub const PTRACE_O_TRACECLONE: u32 = 0x8;
+/// Option: stop the tracee at the next `execve`.
+pub const PTRACE_O_TRACEEXEC: u32 = 0x10;
+/// Option: stop the tracee when it exits.
+pub const PTRACE_O_TRACEEXIT: u32 = 0x40;
+/// Option: send `SIGKILL` to the tracee if the tracer exits.
+pub const PTRACE_O_EXITKILL: u32 = 0x100000;
+/// The set of supported options.
+pub const PTRACE_O_MASK: u32 = PTRACE_O_TRACESYSGOOD
+	| PTRACE_O_TRACEFORK
+	| PTRACE_O_TRACEVFORK
+	| PTRACE_O_TRACECLONE
+	| PTRACE_O_TRACEEXEC
+	| PTRACE_O_TRACEEXIT
+	| PTRACE_O_EXITKILL;
+
+/// Event: the tracee called `fork`.
+pub const PTRACE_EVENT_FORK: u8 = 1;
+/// Event: the tracee called `vfork`.
+pub const PTRACE_EVENT_VFORK: u8 = 2;
+/// Event: the tracee called `clone`.
+pub const PTRACE_EVENT_CLONE: u8 = 3;
+/// Event: the tracee called `execve`.
+pub const PTRACE_EVENT_EXEC: u8 = 4;
+/// Event: the tracee is exiting.
+pub const PTRACE_EVENT_EXIT: u8 = 6;
+
+/// The trap flag of the `eflags` register, enabling single-stepping.
+const EFLAGS_TF: usize = 0x100;
+/// The flags of the `eflags` register that the tracer is allowed to modify.
+const EFLAGS_USER_MASK: usize = 0x40dd5;
+
+/// The tracing state of a traced process.
+#[derive(Debug)]
+pub struct TraceState {
+	/// The PID of the tracer.
+	pub tracer: Pid,
+	/// The set of enabled `PTRACE_O_*` options.
+	pub options: u32,
+	/// Tells whether the tracee has been attached with `PTRACE_SEIZE`.
+	pub seized: bool,
+	/// Tells whether the tracee stops at the next system call entry or exit.
+	pub syscall: bool,
+	/// Tells whether the tracer detached while the tracee was stopped.
+	detached: bool,
+	/// The userspace registers of the tracee. If `None`, the tracee is not in ptrace-stop.
+	regs: Option<Regs>,
+	/// The ID of the system call being executed, or `-1` if not in a system call.
+	orig_eax: usize,
+	/// The signal to deliver when resuming from a signal-delivery-stop.
+	inject: Option<Signal>,
+	/// The event that caused the current stop, or `0`.
+	pub event: u8,
+	/// The message associated with the event, retrieved with `PTRACE_GETEVENTMSG`.
+	pub message: usize,
+	/// An event to report at the end of the current system call, along with its message.
+	pending_event: Option<(u8, usize)>,
+}
+
+impl TraceState {
+	/// Creates a new state for a process traced by `tracer`.
+	pub fn new(tracer: Pid, options: u32, seized: bool) -> Self {
+		Self {
+			tracer,
+			options,
+			seized,
+			syscall: false,
+			detached: false,
+
+			regs: None,
+			orig_eax: usize::MAX,
+			inject: None,
+
+			event: 0,
+			message: 0,
+			pending_event: None,
+		}
+	}
+
+	/// Tells whether the tracee is in ptrace-stop.
+	pub fn is_stopped(&self) -> bool {
+		self.regs.is_some()
+	}
+
+	/// Returns the signal number reported on system call stops.
+	fn syscall_sig(&self) -> u8 {
+		let sig = Signal::SIGTRAP.get_id();
+		if self.options & PTRACE_O_TRACESYSGOOD != 0 {
+			sig | 0x80
+		} else {
+			sig
+		}
+	}
+}
+
+/// The registers of a tracee, as exposed to its tracer (`struct user_regs_struct`).
+#[derive(AnyRepr, Clone, Debug, Default)]
+#[repr(C)]
+#[allow(missing_docs)]
+#[cfg(target_arch = "x86")]
+pub struct UserRegs {
+	pub ebx: u32,
+	pub ecx: u32,
+	pub edx: u32,
+	pub esi: u32,
+	pub edi: u32,
+	pub ebp: u32,
+	pub eax: u32,
+	pub xds: u32,
+	pub xes: u32,
+	pub xfs: u32,
+	pub xgs: u32,
+	pub orig_eax: u32,
+	pub eip: u32,
+	pub xcs: u32,
+	pub eflags: u32,
+	pub esp: u32,
+	pub xss: u32,
+}
+
+impl UserRegs {
+	/// Creates an instance from the registers `regs` and the ID of the current system call
+	/// `orig_eax`.
+	pub fn new(regs: &Regs, orig_eax: usize) -> Self {
+		let data_selector = (gdt::USER_DS | 3) as _;
+		Self {
+			ebx: regs.ebx as _,
+			ecx: regs.ecx as _,
+			edx: regs.edx as _,
+			esi: regs.esi as _,
+			edi: regs.edi as _,
+			ebp: regs.ebp as _,
+			eax: regs.eax as _,
+			xds: data_selector,
+			xes: data_selector,
+			xfs: regs.fs as _,
+			xgs: regs.gs as _,
+			orig_eax: orig_eax as _,
+			eip: regs.eip as _,
+			xcs: (gdt::USER_CS | 3) as _,
+			eflags: regs.eflags as _,
+			esp: regs.esp as _,
+			xss: data_selector,
+		}
+	}
+
+	/// Writes the registers to `regs` and returns the new ID of the current system call.
+	///
+	/// Segment selectors cannot be modified, except `fs` and `gs`. Only the status flags and the
+	/// trap flag of `eflags` can be modified.
+	fn apply(&self, regs: &mut Regs) -> usize {
+		regs.ebx = self.ebx as _;
+		regs.ecx = self.ecx as _;
+		regs.edx = self.edx as _;
+		regs.esi = self.esi as _;
+		regs.edi = self.edi as _;
+		regs.ebp = self.ebp as _;
+		regs.eax = self.eax as _;
+		regs.fs = self.xfs as _;
+		regs.gs = self.xgs as _;
+		regs.eip = self.eip as _;
+		regs.eflags =
+			(regs.eflags & !EFLAGS_USER_MASK) | (self.eflags as usize & EFLAGS_USER_MASK);
+		regs.esp = self.esp as _;
+		self.orig_eax as i32 as _
+	}
+}
+
+/// Returns the registers of the stopped tracee `proc`.
+///
+/// If the tracee is not in ptrace-stop, the function returns [`errno::ESRCH`].
+pub fn get_regs(proc: &Process) -> EResult<UserRegs> {
+	let trace = proc.trace.as_ref().ok_or_else(|| errno!(ESRCH))?;
+	let regs = trace.regs.as_ref().ok_or_else(|| errno!(ESRCH))?;
+	Ok(UserRegs::new(regs, trace.orig_eax))
+}
+
+/// Sets the registers of the stopped tracee `proc`.
+///
+/// If the tracee is not in ptrace-stop, the function returns [`errno::ESRCH`].
+pub fn set_regs(proc: &mut Process, user_regs: &UserRegs) -> EResult<()> {
+	let trace = proc.trace.as_mut().ok_or_else(|| errno!(ESRCH))?;
+	let regs = trace.regs.as_mut().ok_or_else(|| errno!(ESRCH))?;
+	trace.orig_eax = user_regs.apply(regs);
+	Ok(())
+}
+
+/// Makes `tracer` trace `tracee`.
+///
+/// Arguments:
+/// - `options` is the set of initial options.
+/// - `seized` tells whether the tracee is attached with `PTRACE_SEIZE`.
+pub fn attach(
+	tracer: &mut Process,
+	tracee: &mut Process,
+	options: u32,
+	seized: bool,
+) -> EResult<()> {
+	tracer.add_tracee(tracee.get_pid())?;
+	tracee.trace = Some(TraceState::new(tracer.get_pid(), options, seized));
+	Ok(())
+}
+
+/// Stops tracing `proc`, resuming it with the signal `sig` if it is in ptrace-stop.
+///
+/// It is the caller's responsibility to remove the process from its tracer's list of tracees.
+pub fn detach(proc: &mut Process, sig: Option<Signal>) {
+	let Some(trace) = &mut proc.trace else {
+		return;
+	};
+	match &mut trace.regs {
+		// The tracee finishes detaching itself when resuming
+		Some(regs) if proc.state != State::Zombie => {
+			regs.eflags &= !EFLAGS_TF;
+			trace.inject = sig;
+			trace.detached = true;
+			proc.clear_waitable();
+			proc.set_state(State::Running);
+		}
+		_ => proc.trace = None,
+	}
+}
+
+/// Resumes the stopped tracee `proc`.
+///
+/// Arguments:
+/// - `sig` is the signal to deliver, if resuming from a signal-delivery-stop.
+/// - `syscall` tells whether the tracee stops at the next system call entry or exit.
+/// - `step` tells whether the tracee stops after executing the next instruction.
+pub fn resume(proc: &mut Process, sig: Option<Signal>, syscall: bool, step: bool) {
+	let Some(trace) = &mut proc.trace else {
+		return;
+	};
+	trace.syscall = syscall;
+	trace.inject = sig;
+	if let Some(regs) = &mut trace.regs {
+		if step {
+			regs.eflags |= EFLAGS_TF;
+		} else {
+			regs.eflags &= !EFLAGS_TF;
+		}
+	}
+	proc.clear_waitable();
+	proc.set_state(State::Running);
+}
+
+/// Stops the current process until its tracer resumes it (ptrace-stop).
+///
+/// Arguments:
+/// - `regs` is the set of userspace registers of the process. The tracer may modify them.
+/// - `sig` is the signal number reported to the tracer.
+/// - `event` is the event reported to the tracer, or `0`.
+/// - `message` is the message associated with the event.
+///
+/// The function returns the signal the tracer requested to deliver, if any.
+///
+/// If the process is not traced, the function returns immediately.
+///
+/// The function locks the mutex of the current process. Thus, the caller must ensure the mutex
+/// isn't already locked to prevent a deadlock.
+pub fn stop(regs: &mut Regs, sig: u8, event: u8, message: usize) -> Option<Signal> {
+	let proc_mutex = Process::current();
+	{
+		let mut proc = proc_mutex.lock();
+		let trace = proc.trace.as_mut()?;
+		trace.regs = Some(regs.clone());
+		trace.inject = None;
+		trace.event = event;
+		trace.message = message;
+		let tracer = trace.tracer;
+		proc.termsig = sig;
+		proc.waitable = true;
+		proc.set_state(State::Stopped);
+		// Notify the tracer
+		if let Some(tracer) = Process::get_by_pid(tracer) {
+			let mut tracer = tracer.lock();
+			tracer.kill(Signal::SIGCHLD);
+			tracer.wake();
+		}
+	}
+	scheduler::end_tick();
+	// The tracer resumed the process
+	let mut proc = proc_mutex.lock();
+	let trace = proc.trace.as_mut()?;
+	if let Some(r) = trace.regs.take() {
+		*regs = r;
+	}
+	let sig = trace.inject.take();
+	if trace.detached {
+		proc.trace = None;
+	}
+	sig
+}
+
+/// Reports the entry of a system call to the tracer of the current process, if requested.
+///
+/// `regs` is the set of registers of the process at the time of the system call.
+///
+/// The function returns the ID of the system call to execute. If the tracer cancelled the system
+/// call, the function returns `None`.
+pub fn syscall_entry(regs: &mut Regs) -> Option<usize> {
+	let id = regs.get_syscall_id();
+	let sig = {
+		let proc_mutex = Process::current();
+		let mut proc = proc_mutex.lock();
+		let Some(trace) = proc.trace.as_mut().filter(|trace| trace.syscall) else {
+			return Some(id);
+		};
+		trace.orig_eax = id;
+		trace.syscall_sig()
+	};
+	regs.set_syscall_return(Err(errno!(ENOSYS)));
+	stop(regs, sig, 0, 0);
+	// The tracer may have changed the system call
+	let proc_mutex = Process::current();
+	let proc = proc_mutex.lock();
+	let id = proc
+		.trace
+		.as_ref()
+		.map(|trace| trace.orig_eax)
+		.unwrap_or(id);
+	(id != usize::MAX).then_some(id)
+}
+
+/// Reports the end of a system call to the tracer of the current process, if requested, along
+/// with the events that occurred during the system call.
+///
+/// `regs` is the set of registers of the process, with the return value of the system call.
+pub fn syscall_exit(regs: &mut Regs) {
+	let (event, sig) = {
+		let proc_mutex = Process::current();
+		let mut proc = proc_mutex.lock();
+		let Some(trace) = &mut proc.trace else {
+			return;
+		};
+		let sig = trace.syscall.then(|| trace.syscall_sig());
+		(trace.pending_event.take(), sig)
+	};
+	if let Some((event, message)) = event {
+		stop(regs, Signal::SIGTRAP.get_id(), event, message);
+	}
+	if let Some(sig) = sig {
+		stop(regs, sig, 0, 0);
+	}
+	let proc_mutex = Process::current();
+	let mut proc = proc_mutex.lock();
+	if let Some(trace) = &mut proc.trace {
+		trace.orig_eax = usize::MAX;
+	}
+}
+
+/// Propagates the tracing of `parent` to its new child `child`, according to the tracer's
+/// options.
+///
+/// `event` is the kind of fork that created the child. The event is reported to the tracer at
+/// the end of the system call.
+pub(super) fn fork(parent: &mut Process, child: &mut Process, event: u8) {
+	let Some(trace) = &mut parent.trace else {
+		return;
+	};
+	let option = match event {
+		PTRACE_EVENT_FORK => PTRACE_O_TRACEFORK,
+		PTRACE_EVENT_VFORK => PTRACE_O_TRACEVFORK,
+		_ => PTRACE_O_TRACECLONE,
+	};
+	if trace.options & option == 0 {
+		return;
+	}
+	let child_pid = child.get_pid();
+	let tracer = Process::get_by_pid(trace.tracer);
+	// The message is the PID of the child in the namespace of the tracer
+	let ns_pid = tracer
+		.as_ref()
+		.map(|tracer| child.get_pid_in(tracer.lock().pid_ns()))
+		.unwrap_or(0);
+	trace.pending_event = Some((event, ns_pid as _));
+	child.trace = Some(TraceState::new(trace.tracer, trace.options, trace.seized));
+	// The child starts by reporting a stop to the tracer
+	child.queue_signal(Signal::SIGSTOP, SigInfo::new(Signal::SIGSTOP, SI_KERNEL));
+	if let Some(tracer) = tracer {
+		let mut tracer = tracer.lock();
+		oom::wrap(|| tracer.add_tracee(child_pid));
+	}
+}
+
+/// Reports a successful `execve` on `proc` to its tracer.
+///
+/// If the tracer did not request the event, the process receives a `SIGTRAP` instead, unless it
+/// was attached with `PTRACE_SEIZE`.
+pub(super) fn exec(proc: &mut Process) {
+	let Some(trace) = &mut proc.trace else {
+		return;
+	};
+	if trace.options & PTRACE_O_TRACEEXEC != 0 {
+		// The message is the PID of the process in the namespace of the tracer
+		let pid = Process::get_by_pid(trace.tracer)
+			.and_then(|tracer| proc.pid.get_in(tracer.lock().pid_ns()))
+			.unwrap_or(0);
+		trace.pending_event = Some((PTRACE_EVENT_EXEC, pid as _));
+	} else if !trace.seized {
+		proc.kill(Signal::SIGTRAP);
+	}
+}
+
+/// Reports the exit of the current process to its tracer, if requested.
+///
+/// Arguments:
+/// - `regs` is the set of userspace registers of the process.
+/// - `status` is the exit status.
+pub fn exit(regs: &Regs, status: u32) {
+	let requested = Process::current()
+		.lock()
+		.trace
+		.as_ref()
+		.is_some_and(|trace| trace.options & PTRACE_O_TRACEEXIT != 0);
+	if requested {
+		let mut regs = regs.clone();
+		stop(
+			&mut regs,
+			Signal::SIGTRAP.get_id(),
+			PTRACE_EVENT_EXIT,
+			status as _,
+		);
+	}
+}
+
+/// Updates tracing relationships after the exit of a process.
+///
+/// Arguments:
+/// - `pid` is the PID of the exited process.
+/// - `tracer` is the PID of the process that was tracing the exited process, if any.
+/// - `tracees` is the list of processes that were traced by the exited process.
+///
+/// The tracer is notified of the exit, and the tracees are detached, or killed if the
+/// [`PTRACE_O_EXITKILL`] option is set.
+///
+/// This function locks processes, thus it must be called in a context where no process is locked.
+pub(super) fn exit_notify(pid: Pid, tracer: Option<Pid>, tracees: &[Pid]) {
+	if let Some(tracer) = tracer.and_then(Process::get_by_pid) {
+		let mut tracer = tracer.lock();
+		tracer.kill(Signal::SIGCHLD);
+		tracer.wake();
+	}
+	for tracee in tracees.iter().filter_map(|pid| Process::get_by_pid(*pid)) {
+		let mut tracee = tracee.lock();
+		let Some(trace) = tracee.trace.as_ref().filter(|trace| trace.tracer == pid) else {
+			continue;
+		};
+		if trace.options & PTRACE_O_EXITKILL != 0 {
+			tracee.trace = None;
+			tracee.kill(Signal::SIGKILL);
+		} else {
+			detach(&mut tracee, None);
+		}
+	}
+}
+
+/// The function executed by a traced process that was interrupted in userspace while a signal
+/// is pending.
+///
+/// `regs` is a pointer to the userspace registers of the process.
+extern "C" fn report_signal_entry(regs: *const Regs) -> ! {
+	let mut regs = unsafe { ptr::read(regs) };
+	super::yield_current(3, &mut regs);
+	unsafe {
+		regs.switch(true);
+	}
+}
+
+/// Redirects the execution of the traced process `proc`, which was interrupted in userspace, to
+/// the kernel so that it can report its pending signal to its tracer.
+///
+/// Signals cannot be reported from the scheduler since the process has to stop until the tracer
+/// resumes it.
+pub(super) fn redirect_signal(proc: &mut Process) {
+	// The kernel stack of a process interrupted in userspace is empty, so its top can be used to
+	// store the userspace registers, followed by the argument and return address of the call
+	let esp = unsafe {
+		let regs_ptr = proc
+			.kernel_stack
+			.as_ptr()
+			.add(buddy::get_frame_size(KERNEL_STACK_ORDER) - size_of::<Regs>())
+			.cast::<Regs>();
+		ptr::write(regs_ptr, proc.regs.clone());
+		let stack = regs_ptr.cast::<usize>().sub(2);
+		ptr::write(stack, 0);
+		ptr::write(stack.add(1), regs_ptr as usize);
+		stack
+	};
+	proc.regs = Regs {
+		esp: esp as usize,
+		eip: report_signal_entry as usize,
+		fxstate: proc.regs.fxstate,
+		..Default::default()
+	};
+	proc.syscalling = true;
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn ptrace_user_regs() {
+		assert_eq!(size_of::<UserRegs>(), 17 * 4);
+		let mut regs = Regs {
+			eax: 1,
+			ebx: 2,
+			eip: 0x1000,
+			esp: 0x2000,
+			eflags: 0x202,
+			..Default::default()
+		};
+		let user_regs = UserRegs::new(&regs, 42);
+		assert_eq!(user_regs.eax, 1);
+		assert_eq!(user_regs.ebx, 2);
+		assert_eq!(user_regs.orig_eax, 42);
+		assert_eq!(user_regs.xcs, (gdt::USER_CS | 3) as u32);
+		// Writing back the same values changes nothing
+		assert_eq!(user_regs.apply(&mut regs), 42);
+		assert_eq!(regs.eip, 0x1000);
+		assert_eq!(regs.eflags, 0x202);
+		// The tracer cannot change the privileged flags, nor the code segment
+		let user_regs = UserRegs {
+			eflags: (0x3000 | EFLAGS_TF | 0x1) as _,
+			xcs: 0,
+			orig_eax: -1i32 as _,
+			..user_regs
+		};
+		assert_eq!(user_regs.apply(&mut regs), usize::MAX);
+		assert_eq!(regs.eflags, 0x202 | EFLAGS_TF | 0x1);
+	}
+
+	#[test_case]
+	fn ptrace_syscall_sig() {
+		let state = TraceState::new(1, 0, false);
+		assert_eq!(state.syscall_sig(), Signal::SIGTRAP.get_id());
+		let state = TraceState::new(1, PTRACE_O_TRACESYSGOOD, false);
+		assert_eq!(state.syscall_sig(), Signal::SIGTRAP.get_id() | 0x80);
+	}
+}
@@ -18,9 +18,13 @@
 
 //! Registers state save and restore.
 
-use crate::{gdt, memory::VirtAddr};
-use core::fmt;
-use utils::errno::EResult;
+use crate::{cpu::sse, gdt, memory::VirtAddr};
+use core::{arch::asm, fmt};
+use utils::{
+	collections::vec::Vec,
+	errno::{AllocResult, EResult},
+	TryClone,
+};
 
 extern "C" {
 	/// Switches to an userspace context.
@@ -201,3 +205,210 @@ impl fmt::Debug for Regs {
 			.finish()
 	}
 }
+
+/// The required alignment of the XSAVE area.
+pub const XSAVE_ALIGN: usize = 64;
+/// The offset of the MXCSR in the XSAVE area.
+const XSAVE_MXCSR_OFF: usize = 24;
+/// The offset of the XSAVE header in the XSAVE area.
+const XSAVE_HEADER_OFF: usize = 512;
+/// The size of the XSAVE header in bytes.
+const XSAVE_HEADER_SIZE: usize = 64;
+
+/// Sanitizes the XSAVE area `area`, which comes from an untrusted source, so that restoring it
+/// cannot fail.
+///
+/// Arguments:
+/// - `mxcsr_mask` is the mask of the MXCSR bits supported by the CPU.
+/// - `xfeatures` is the set of state components enabled in XCR0.
+fn sanitize_xsave(area: &mut [u8], mxcsr_mask: u32, xfeatures: u64) {
+	// Clear unsupported MXCSR bits
+	let mxcsr = &mut area[XSAVE_MXCSR_OFF..(XSAVE_MXCSR_OFF + 4)];
+	let val = u32::from_le_bytes((&*mxcsr).try_into().unwrap()) & mxcsr_mask;
+	mxcsr.copy_from_slice(&val.to_le_bytes());
+	// Only enabled components can be restored, in the standard format
+	let header = &mut area[XSAVE_HEADER_OFF..(XSAVE_HEADER_OFF + XSAVE_HEADER_SIZE)];
+	let xstate_bv = u64::from_le_bytes(header[..8].try_into().unwrap()) & xfeatures;
+	header.fill(0);
+	header[..8].copy_from_slice(&xstate_bv.to_le_bytes());
+}
+
+/// The extended processor state of an execution context (AVX, AVX-512, etc...), saved with the
+/// XSAVE feature set.
+///
+/// Since the kernel uses the x87 FPU, the x87 FPU, MMX and SSE state is saved in [`Regs`] on each
+/// interruption. The extended state is not used by the kernel, so it remains in the registers
+/// until it is saved when switching to another process.
+///
+/// The size of the area depends on the state components supported by the CPU. If the CPU does
+/// not support XSAVE, the area is empty and saving or restoring it does nothing.
+pub struct XState {
+	/// The buffer containing the XSAVE area. It is larger than the area so that it can be
+	/// aligned.
+	buf: Vec<u8>,
+}
+
+impl XState {
+	/// Creates a new area, representing the initial state.
+	pub fn new() -> AllocResult<Self> {
+		let size = sse::xsave_size();
+		let mut buf = Vec::new();
+		if size > 0 {
+			buf.resize(size + XSAVE_ALIGN - 1, 0)?;
+		}
+		let mut s = Self {
+			buf,
+		};
+		s.reset();
+		Ok(s)
+	}
+
+	/// Returns the offset of the area in the buffer.
+	#[inline]
+	fn offset(&self) -> usize {
+		self.buf.as_ptr().align_offset(XSAVE_ALIGN)
+	}
+
+	/// Returns the XSAVE area.
+	pub fn as_slice(&self) -> &[u8] {
+		if self.buf.is_empty() {
+			return &[];
+		}
+		let off = self.offset();
+		&self.buf[off..(off + sse::xsave_size())]
+	}
+
+	/// Returns the XSAVE area as mutable.
+	fn as_mut_slice(&mut self) -> &mut [u8] {
+		if self.buf.is_empty() {
+			return &mut [];
+		}
+		let off = self.offset();
+		&mut self.buf[off..(off + sse::xsave_size())]
+	}
+
+	/// Resets the area to the initial state.
+	pub fn reset(&mut self) {
+		let area = self.as_mut_slice();
+		if area.is_empty() {
+			return;
+		}
+		area.fill(0);
+		// Every component is in its initial state, except MXCSR which is always restored
+		area[XSAVE_MXCSR_OFF..(XSAVE_MXCSR_OFF + 4)]
+			.copy_from_slice(&x86::DEFAULT_MXCSR.to_le_bytes());
+	}
+
+	/// Replaces the area with `data`, which comes from an untrusted source.
+	///
+	/// Values that would make restoring the state fail are sanitized. If the area is empty or if
+	/// `data` does not have the size of the area, the function does nothing.
+	pub fn load(&mut self, data: &[u8]) {
+		let area = self.as_mut_slice();
+		if area.is_empty() || area.len() != data.len() {
+			return;
+		}
+		area.copy_from_slice(data);
+		sanitize_xsave(area, sse::mxcsr_mask(), sse::xfeatures());
+	}
+
+	/// Saves the extended state of the current CPU into the area.
+	pub fn save(&mut self) {
+		let area = self.as_mut_slice();
+		if area.is_empty() {
+			return;
+		}
+		unsafe {
+			if sse::has_xsaveopt() {
+				asm!(
+					"xsaveopt [{}]",
+					in(reg) area.as_mut_ptr(),
+					in("eax") u32::MAX,
+					in("edx") u32::MAX,
+					options(nostack)
+				);
+			} else {
+				asm!(
+					"xsave [{}]",
+					in(reg) area.as_mut_ptr(),
+					in("eax") u32::MAX,
+					in("edx") u32::MAX,
+					options(nostack)
+				);
+			}
+		}
+	}
+
+	/// Restores the extended state from the area to the current CPU.
+	pub fn restore(&self) {
+		let area = self.as_slice();
+		if area.is_empty() {
+			return;
+		}
+		unsafe {
+			asm!(
+				"xrstor [{}]",
+				in(reg) area.as_ptr(),
+				in("eax") u32::MAX,
+				in("edx") u32::MAX,
+				options(nostack)
+			);
+		}
+	}
+}
+
+impl TryClone for XState {
+	fn try_clone(&self) -> AllocResult<Self> {
+		let mut new = Self::new()?;
+		new.as_mut_slice().copy_from_slice(self.as_slice());
+		Ok(new)
+	}
+}
+
+impl fmt::Debug for XState {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("XState")
+			.field("size", &self.as_slice().len())
+			.finish()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn xsave_sanitize() {
+		let mut area = [0xffu8; 576];
+		sanitize_xsave(&mut area, 0xffbf, 0b111);
+		// Unsupported MXCSR bits are cleared
+		let mxcsr = u32::from_le_bytes(
+			area[XSAVE_MXCSR_OFF..(XSAVE_MXCSR_OFF + 4)]
+				.try_into()
+				.unwrap(),
+		);
+		assert_eq!(mxcsr, 0xffbf);
+		// Only enabled components remain, and the rest of the header is cleared (which
+		// excludes the compacted format)
+		let header = &area[XSAVE_HEADER_OFF..(XSAVE_HEADER_OFF + XSAVE_HEADER_SIZE)];
+		assert_eq!(u64::from_le_bytes(header[..8].try_into().unwrap()), 0b111);
+		assert!(header[8..].iter().all(|b| *b == 0));
+		// The rest of the area is left untouched
+		assert!(area[..XSAVE_MXCSR_OFF].iter().all(|b| *b == 0xff));
+		assert!(area[(XSAVE_MXCSR_OFF + 4)..XSAVE_HEADER_OFF]
+			.iter()
+			.all(|b| *b == 0xff));
+		// Valid values are kept
+		sanitize_xsave(&mut area, 0xffbf, 0b11);
+		let header = &area[XSAVE_HEADER_OFF..(XSAVE_HEADER_OFF + XSAVE_HEADER_SIZE)];
+		assert_eq!(u64::from_le_bytes(header[..8].try_into().unwrap()), 0b11);
+		assert_eq!(
+			u32::from_le_bytes(
+				area[XSAVE_MXCSR_OFF..(XSAVE_MXCSR_OFF + 4)]
+					.try_into()
+					.unwrap()
+			),
+			0xffbf
+		);
+	}
+}
@@ -0,0 +1,263 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Resource limits allow to restrict the amount of resources a process may use.
+
+use crate::{
+	file::{perm::Uid, File, FileType},
+	ipc::mqueue::MQ_BYTES_MAX,
+	process::{
+		signal::{Signal, SIGQUEUE_MAX},
+		Process, USER_STACK_SIZE,
+	},
+};
+use utils::{
+	collections::hashmap::HashMap,
+	errno,
+	errno::EResult,
+	limits::{OPEN_MAX, PAGE_SIZE},
+	lock::IntMutex,
+};
+
+/// The amount of seconds of CPU time the process can consume.
+pub const RLIMIT_CPU: i32 = 0;
+/// The maximum size of a file the process may create, in bytes.
+pub const RLIMIT_FSIZE: i32 = 1;
+/// The maximum size of the process's data segment in bytes, rounded down to the
+/// page size.
+pub const RLIMIT_DATA: i32 = 2;
+/// The maximum size of the process stack, in bytes.
+pub const RLIMIT_STACK: i32 = 3;
+/// The maximum size of a core file the process may dump in bytes.
+pub const RLIMIT_CORE: i32 = 4;
+/// A limit on the process's resident set (the number of virtual pages resident in RAM).
+pub const RLIMIT_RSS: i32 = 5;
+/// The limit on the number of threads for the real user ID of the calling process.
+pub const RLIMIT_NPROC: i32 = 6;
+/// A value one greater than the maximum number of file descriptors that can be
+/// open by the process.
+pub const RLIMIT_NOFILE: i32 = 7;
+/// The maximum number of bytes of memory that may be locked into RAM.
+pub const RLIMIT_MEMLOCK: i32 = 8;
+/// The maximum size of the memory space in bytes, rounded down to the page
+/// size.
+pub const RLIMIT_AS: i32 = 9;
+/// The limit on the combined number of flock(2) locks and fcntl(2) leases the
+/// process may establish.
+pub const RLIMIT_LOCKS: i32 = 10;
+/// The limit on the number of signals that may be queued for the real user ID of the calling
+/// process.
+pub const RLIMIT_SIGPENDING: i32 = 11;
+/// The limit on the number of bytes that can be allocated for POSIX message queues for the real
+/// user ID of the calling process.
+pub const RLIMIT_MSGQUEUE: i32 = 12;
+/// The ceiling to which the process's nice value can be raised.
+pub const RLIMIT_NICE: i32 = 13;
+/// The ceiling on the real-time priority that may be set for this process.
+pub const RLIMIT_RTPRIO: i32 = 14;
+/// The limit (in microseconds) on the amount of CPU that a process scheduled under a real-time
+/// scheduling policy may consume without masking a blocking system call.
+pub const RLIMIT_RTTIME: i32 = 15;
+/// The number of resources that can be limited.
+pub const RLIMIT_NLIMITS: usize = 16;
+
+/// A resource limit.
+pub type RLim = u64;
+
+/// Value of a limit telling that the resource is not limited.
+pub const RLIM_INFINITY: RLim = !0;
+
+/// A resource limit.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct RLimit {
+	/// Soft limit
+	pub rlim_cur: RLim,
+	/// Hard limit (ceiling for [`Self::rlim_cur`])
+	pub rlim_max: RLim,
+}
+
+impl RLimit {
+	/// A limit that does not restrict anything.
+	pub const INFINITY: Self = Self {
+		rlim_cur: RLIM_INFINITY,
+		rlim_max: RLIM_INFINITY,
+	};
+}
+
+/// A resource limit, as used by the 32 bit `getrlimit` and `setrlimit` system calls.
+///
+/// On this structure, [`u32::MAX`] stands for [`RLIM_INFINITY`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct RLimit32 {
+	/// Soft limit
+	pub rlim_cur: u32,
+	/// Hard limit (ceiling for [`Self::rlim_cur`])
+	pub rlim_max: u32,
+}
+
+impl From<RLimit> for RLimit32 {
+	fn from(limit: RLimit) -> Self {
+		let conv = |val: RLim| val.try_into().unwrap_or(u32::MAX);
+		Self {
+			rlim_cur: conv(limit.rlim_cur),
+			rlim_max: conv(limit.rlim_max),
+		}
+	}
+}
+
+impl From<RLimit32> for RLimit {
+	fn from(limit: RLimit32) -> Self {
+		let conv = |val: u32| {
+			if val == u32::MAX {
+				RLIM_INFINITY
+			} else {
+				val as _
+			}
+		};
+		Self {
+			rlim_cur: conv(limit.rlim_cur),
+			rlim_max: conv(limit.rlim_max),
+		}
+	}
+}
+
+/// The set of resource limits of a process, indexed by resource.
+#[derive(Clone, Debug)]
+pub struct RLimits(pub [RLimit; RLIMIT_NLIMITS]);
+
+impl Default for RLimits {
+	fn default() -> Self {
+		let mut limits = [RLimit::INFINITY; RLIMIT_NLIMITS];
+		// Core dumps are disabled by default
+		limits[RLIMIT_CORE as usize].rlim_cur = 0;
+		limits[RLIMIT_STACK as usize].rlim_cur = (USER_STACK_SIZE * PAGE_SIZE) as _;
+		limits[RLIMIT_NOFILE as usize] = RLimit {
+			rlim_cur: OPEN_MAX as _,
+			rlim_max: OPEN_MAX as _,
+		};
+		limits[RLIMIT_SIGPENDING as usize] = RLimit {
+			rlim_cur: SIGQUEUE_MAX as _,
+			rlim_max: SIGQUEUE_MAX as _,
+		};
+		limits[RLIMIT_MSGQUEUE as usize] = RLimit {
+			rlim_cur: MQ_BYTES_MAX,
+			rlim_max: MQ_BYTES_MAX,
+		};
+		Self(limits)
+	}
+}
+
+impl RLimits {
+	/// Returns the limit for the given `resource`.
+	///
+	/// If the resource does not exist, the function returns `None`.
+	pub fn get(&self, resource: i32) -> Option<&RLimit> {
+		usize::try_from(resource)
+			.ok()
+			.and_then(|resource| self.0.get(resource))
+	}
+
+	/// Returns a mutable reference to the limit for the given `resource`.
+	///
+	/// If the resource does not exist, the function returns `None`.
+	pub fn get_mut(&mut self, resource: i32) -> Option<&mut RLimit> {
+		usize::try_from(resource)
+			.ok()
+			.and_then(|resource| self.0.get_mut(resource))
+	}
+}
+
+/// Checks a write of `len` bytes at offset `off` in `file` against the `RLIMIT_FSIZE` limit of the
+/// current process.
+///
+/// The limit only applies to regular files. The function returns the number of bytes that may be
+/// written.
+///
+/// If not a single byte may be written, the process receives a `SIGXFSZ` signal and the function
+/// returns [`errno::EFBIG`].
+pub fn check_fsize(file: &File, off: u64, len: usize) -> EResult<usize> {
+	if len == 0 || file.get_type()? != FileType::Regular {
+		return Ok(len);
+	}
+	let proc_mutex = Process::current();
+	let mut proc = proc_mutex.lock();
+	let limit = proc.rlimits.get(RLIMIT_FSIZE).unwrap().rlim_cur;
+	if off >= limit {
+		proc.kill(Signal::SIGXFSZ);
+		return Err(errno!(EFBIG));
+	}
+	let max = (limit - off).try_into().unwrap_or(usize::MAX);
+	Ok(len.min(max))
+}
+
+/// The number of live user processes of each real user ID, checked against `RLIMIT_NPROC`.
+static NPROC: IntMutex<HashMap<Uid, usize>> = IntMutex::new(HashMap::new());
+
+/// Counts a new process for the real user ID `uid`.
+///
+/// If `limit` is set and the user already has that many processes, the function returns
+/// [`errno::EAGAIN`].
+pub fn nproc_charge(uid: Uid, limit: Option<RLim>) -> EResult<()> {
+	let mut nproc = NPROC.lock();
+	let count = nproc.get(&uid).copied().unwrap_or(0);
+	if limit.is_some_and(|limit| count as RLim >= limit) {
+		return Err(errno!(EAGAIN));
+	}
+	nproc.insert(uid, count + 1)?;
+	Ok(())
+}
+
+/// Removes a process from the count of the real user ID `uid`.
+pub fn nproc_release(uid: Uid) {
+	let mut nproc = NPROC.lock();
+	if let Some(count) = nproc.get_mut(&uid) {
+		*count -= 1;
+		if *count == 0 {
+			nproc.remove(&uid);
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn nproc_count() {
+		// A user ID no process runs as
+		let uid = 54321;
+		nproc_charge(uid, Some(2)).unwrap();
+		nproc_charge(uid, Some(2)).unwrap();
+		assert_eq!(nproc_charge(uid, Some(2)), Err(errno!(EAGAIN)));
+		// Without a limit, the count still increases
+		nproc_charge(uid, None).unwrap();
+		nproc_release(uid);
+		nproc_release(uid);
+		nproc_charge(uid, Some(2)).unwrap();
+		// The count is per user
+		nproc_charge(uid + 1, Some(1)).unwrap();
+		assert_eq!(nproc_charge(uid + 1, Some(1)), Err(errno!(EAGAIN)));
+		nproc_release(uid + 1);
+		nproc_release(uid);
+		nproc_release(uid);
+		assert!(NPROC.lock().get(&uid).is_none());
+		assert!(NPROC.lock().get(&(uid + 1)).is_none());
+	}
+}
@@ -18,7 +18,24 @@
 
 //! Monitoring of the resource usage of processes.
 
-use crate::time::unit::Timeval;
+use crate::time::{
+	clock,
+	unit::{TimeUnit, Timeval},
+};
+use core::cmp::max;
+
+/// The number of clock ticks per second, used to report times to userspace.
+pub const CLK_TCK: u64 = 100;
+
+/// Returns the current timestamp used for CPU time accounting, in nanoseconds.
+pub fn timestamp() -> u64 {
+	clock::boottime()
+}
+
+/// Converts the duration `ns` in nanoseconds to clock ticks.
+pub fn to_clock_ticks(ns: u64) -> u64 {
+	ns / (1_000_000_000 / CLK_TCK)
+}
 
 /// Usage of each resource by a process.
 #[derive(Clone, Default, Debug)]
@@ -57,4 +74,82 @@ pub struct RUsage {
 	pub ru_nivcsw: i32,
 }
 
-// TODO Place calls in kernel's code to update usage
+impl RUsage {
+	/// Returns the total CPU time used, in nanoseconds.
+	pub fn cpu_time(&self) -> u64 {
+		self.ru_utime
+			.to_nano()
+			.saturating_add(self.ru_stime.to_nano())
+	}
+
+	/// Adds the usage `other` to `self`.
+	///
+	/// The maximum resident set size is the maximum of both.
+	pub fn add(&mut self, other: &RUsage) {
+		self.ru_utime = Timeval::from_nano(self.ru_utime.to_nano() + other.ru_utime.to_nano());
+		self.ru_stime = Timeval::from_nano(self.ru_stime.to_nano() + other.ru_stime.to_nano());
+		self.ru_maxrss = max(self.ru_maxrss, other.ru_maxrss);
+		for (a, b) in [
+			(&mut self.ru_ixrss, other.ru_ixrss),
+			(&mut self.ru_idrss, other.ru_idrss),
+			(&mut self.ru_isrss, other.ru_isrss),
+			(&mut self.ru_minflt, other.ru_minflt),
+			(&mut self.ru_majflt, other.ru_majflt),
+			(&mut self.ru_nswap, other.ru_nswap),
+			(&mut self.ru_inblock, other.ru_inblock),
+			(&mut self.ru_oublock, other.ru_oublock),
+			(&mut self.ru_msgsnd, other.ru_msgsnd),
+			(&mut self.ru_msgrcv, other.ru_msgrcv),
+			(&mut self.ru_nsignals, other.ru_nsignals),
+			(&mut self.ru_nvcsw, other.ru_nvcsw),
+			(&mut self.ru_nivcsw, other.ru_nivcsw),
+		] {
+			*a = a.saturating_add(b);
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn rusage_fold() {
+		let child = RUsage {
+			ru_utime: Timeval::from_nano(1_500_000_000),
+			ru_stime: Timeval::from_nano(250_000_000),
+			ru_maxrss: 100,
+			ru_minflt: 10,
+			ru_nvcsw: i32::MAX - 1,
+			..Default::default()
+		};
+		assert_eq!(child.cpu_time(), 1_750_000_000);
+		// The usage of a reaped child and of its own reaped children are folded together
+		let grandchildren = RUsage {
+			ru_utime: Timeval::from_nano(500_000_000),
+			ru_maxrss: 300,
+			ru_minflt: 5,
+			ru_nvcsw: 2,
+			..Default::default()
+		};
+		let mut usage = RUsage::default();
+		usage.add(&child);
+		usage.add(&grandchildren);
+		assert_eq!(usage.ru_utime.to_nano(), 2_000_000_000);
+		assert_eq!(usage.ru_stime.to_nano(), 250_000_000);
+		assert_eq!(usage.cpu_time(), 2_250_000_000);
+		// The maximum resident set size is not summed
+		assert_eq!(usage.ru_maxrss, 300);
+		assert_eq!(usage.ru_minflt, 15);
+		// Counters saturate
+		assert_eq!(usage.ru_nvcsw, i32::MAX);
+	}
+
+	#[test_case]
+	fn clock_ticks() {
+		assert_eq!(to_clock_ticks(0), 0);
+		assert_eq!(to_clock_ticks(9_999_999), 0);
+		assert_eq!(to_clock_ticks(10_000_000), 1);
+		assert_eq!(to_clock_ticks(2_345_000_000), 234);
+	}
+}
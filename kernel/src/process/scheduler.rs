@@ -255,16 +255,26 @@ impl Scheduler {
 		// Disable interrupts so that they remain disabled between the time the scheduler is
 		// unlocked and the context is switched to the next process
 		cli();
+		// Charge the current process for the time elapsed since the last accounting. This is done
+		// without the scheduler locked since it may send a signal to the process
+		let curr_proc = {
+			let mut sched = sched_mutex.lock();
+			sched.total_ticks = sched.total_ticks.saturating_add(1);
+			sched.get_current_process()
+		};
+		// The PID of the paused process and whether it is still runnable
+		let prev = curr_proc.map(|curr_proc| {
+			let mut curr_proc = curr_proc.lock();
+			curr_proc.regs = regs.clone();
+			curr_proc.xstate.save();
+			curr_proc.syscalling = ring < 3;
+			curr_proc.account_cpu(ring == 3);
+			let running = matches!(curr_proc.get_state(), State::Running);
+			(curr_proc.get_pid(), running)
+		});
 		// Use a scope to drop mutex guards
 		let (switch_info, tmp_stack) = {
 			let mut sched = sched_mutex.lock();
-			sched.total_ticks = sched.total_ticks.saturating_add(1);
-			// If a process is running, save its registers
-			if let Some(curr_proc) = sched.get_current_process() {
-				let mut curr_proc = curr_proc.lock();
-				curr_proc.regs = regs.clone();
-				curr_proc.syscalling = ring < 3;
-			}
 			// Loop until a runnable process is found
 			let (proc, switch_info) = loop {
 				let Some((pid, proc_mutex)) = sched.get_next_process() else {
@@ -280,9 +290,19 @@ impl Scheduler {
 				}
 				let regs = proc.regs.clone();
 				let syscalling = proc.syscalling;
+				proc.xstate.restore();
 				drop(proc);
 				break (Some((pid, proc_mutex)), Some((regs, syscalling)));
 			};
+			// Count the context switch for the paused process
+			if let Some((prev_pid, running)) = prev {
+				let next_pid = proc.as_ref().map(|(pid, _)| *pid);
+				if !running || next_pid != Some(prev_pid) {
+					if let Some(prev) = sched.processes.get(&prev_pid) {
+						prev.lock().count_context_switch(!running);
+					}
+				}
+			}
 			// Set current running process
 			sched.curr_proc = proc;
 			let tmp_stack = sched.get_tmp_stack();
@@ -0,0 +1,545 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! seccomp (secure computing) restricts the system calls a process is allowed to perform.
+//!
+//! A process may enter one of the following modes:
+//! - strict: only `read`, `write`, `exit` and `sigreturn` are allowed. Any other system call kills
+//!   the process
+//! - filter: each system call is submitted to the classic BPF programs installed by the process,
+//!   which decide of the action to take
+//!
+//! The mode and filters are inherited across `fork` and `execve`, and cannot be removed.
+
+use crate::{
+	process::{
+		regs::Regs,
+		signal::{SigInfo, Signal, SignalAction},
+		Process,
+	},
+	syscall::SIGRETURN_ID,
+};
+use core::cmp::min;
+use utils::{collections::vec::Vec, errno, errno::EResult, ptr::arc::Arc};
+
+/// seccomp mode: system calls are not restricted.
+pub const SECCOMP_MODE_DISABLED: u8 = 0;
+/// seccomp mode: only a fixed set of system calls is allowed.
+pub const SECCOMP_MODE_STRICT: u8 = 1;
+/// seccomp mode: system calls are filtered by BPF programs.
+pub const SECCOMP_MODE_FILTER: u8 = 2;
+
+/// Filter action: kill the process.
+pub const SECCOMP_RET_KILL_PROCESS: u32 = 0x80000000;
+/// Filter action: kill the thread.
+pub const SECCOMP_RET_KILL_THREAD: u32 = 0x00000000;
+/// Filter action: send `SIGSYS` to the thread without executing the system call.
+pub const SECCOMP_RET_TRAP: u32 = 0x00030000;
+/// Filter action: return an errno without executing the system call.
+pub const SECCOMP_RET_ERRNO: u32 = 0x00050000;
+/// Filter action: execute the system call after logging it.
+pub const SECCOMP_RET_LOG: u32 = 0x7ffc0000;
+/// Filter action: execute the system call.
+pub const SECCOMP_RET_ALLOW: u32 = 0x7fff0000;
+
+/// Mask of the action in a filter's return value.
+const SECCOMP_RET_ACTION_FULL: u32 = 0xffff0000;
+/// Mask of the action's data in a filter's return value.
+const SECCOMP_RET_DATA: u32 = 0x0000ffff;
+
+/// The largest errno value a filter may return.
+const MAX_ERRNO: u32 = 4095;
+/// The audit architecture value of the system calls, passed to filters.
+const AUDIT_ARCH_I386: u32 = 0x40000003;
+/// The system calls allowed in strict mode: `read`, `write` and `exit`, plus `sigreturn`.
+const STRICT_SYSCALLS: [usize; 4] = [0x003, 0x004, 0x001, SIGRETURN_ID];
+
+/// The maximum number of instructions in a BPF program.
+const BPF_MAXINSNS: usize = 4096;
+/// The maximum number of instructions of all the filters of a process. Each filter counts for four
+/// more instructions.
+const MAX_INSNS_PER_PATH: usize = 32768;
+/// The number of words in the scratch memory of a BPF program.
+const BPF_MEMWORDS: u32 = 16;
+/// The size of [`SeccompData`] in bytes.
+const SECCOMP_DATA_SIZE: u32 = 64;
+
+// Classic BPF instructions classes
+const BPF_LD: u16 = 0x00;
+const BPF_LDX: u16 = 0x01;
+const BPF_ST: u16 = 0x02;
+const BPF_STX: u16 = 0x03;
+const BPF_ALU: u16 = 0x04;
+const BPF_JMP: u16 = 0x05;
+const BPF_RET: u16 = 0x06;
+const BPF_MISC: u16 = 0x07;
+
+// Load modes (the only supported size being a word, which is zero)
+const BPF_IMM: u16 = 0x00;
+const BPF_ABS: u16 = 0x20;
+const BPF_MEM: u16 = 0x60;
+const BPF_LEN: u16 = 0x80;
+
+// ALU operations
+const BPF_ADD: u16 = 0x00;
+const BPF_SUB: u16 = 0x10;
+const BPF_MUL: u16 = 0x20;
+const BPF_DIV: u16 = 0x30;
+const BPF_OR: u16 = 0x40;
+const BPF_AND: u16 = 0x50;
+const BPF_LSH: u16 = 0x60;
+const BPF_RSH: u16 = 0x70;
+const BPF_NEG: u16 = 0x80;
+const BPF_MOD: u16 = 0x90;
+const BPF_XOR: u16 = 0xa0;
+
+// Jump operations
+const BPF_JA: u16 = 0x00;
+const BPF_JEQ: u16 = 0x10;
+const BPF_JGT: u16 = 0x20;
+const BPF_JGE: u16 = 0x30;
+const BPF_JSET: u16 = 0x40;
+
+// Operand sources
+const BPF_K: u16 = 0x00;
+const BPF_X: u16 = 0x08;
+const BPF_A: u16 = 0x10;
+
+// Miscellaneous operations
+const BPF_TAX: u16 = 0x00;
+const BPF_TXA: u16 = 0x80;
+
+/// A classic BPF instruction.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct SockFilter {
+	/// The opcode.
+	pub code: u16,
+	/// The offset of the jump if the condition is true.
+	pub jt: u8,
+	/// The offset of the jump if the condition is false.
+	pub jf: u8,
+	/// The operand.
+	pub k: u32,
+}
+
+/// A classic BPF program, as passed by userspace.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct SockFprog {
+	/// The number of instructions.
+	pub len: u16,
+	/// The instructions.
+	pub filter: *const SockFilter,
+}
+
+/// The data a filter operates on, describing a system call.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct SeccompData {
+	/// The ID of the system call.
+	pub nr: i32,
+	/// The architecture, as an `AUDIT_ARCH_*` value.
+	pub arch: u32,
+	/// The address of the instruction performing the system call.
+	pub instruction_pointer: u64,
+	/// The arguments of the system call.
+	pub args: [u64; 6],
+}
+
+impl SeccompData {
+	/// Returns the data for the system call `id`, performed with the registers `regs`.
+	fn new(id: usize, regs: &Regs) -> Self {
+		Self {
+			nr: id as _,
+			arch: AUDIT_ARCH_I386,
+			instruction_pointer: regs.eip as _,
+			args: core::array::from_fn(|i| regs.get_syscall_arg(i as _) as _),
+		}
+	}
+
+	/// Returns the word at the offset `off` in bytes in the structure.
+	///
+	/// `off` must be aligned to four bytes and be in bounds.
+	fn load(&self, off: u32) -> u32 {
+		let split = |val: u64| [val as u32, (val >> 32) as u32];
+		let word = (off / 4) as usize;
+		match word {
+			0 => self.nr as _,
+			1 => self.arch,
+			2 | 3 => split(self.instruction_pointer)[word - 2],
+			_ => split(self.args[(word - 4) / 2])[word % 2],
+		}
+	}
+}
+
+/// Checks that the given program is valid to be used as a seccomp filter.
+///
+/// A valid program only uses supported instructions, only jumps forward within its bounds, and
+/// ends with a return instruction.
+///
+/// If the program is invalid, the function returns [`errno::EINVAL`].
+fn validate(prog: &[SockFilter]) -> EResult<()> {
+	if prog.is_empty() || prog.len() > BPF_MAXINSNS {
+		return Err(errno!(EINVAL));
+	}
+	// `pc` is always in bounds, and the offset is checked without overflowing
+	let in_bounds = |pc: usize, off: u32| (off as usize) < prog.len() - pc - 1;
+	for (pc, insn) in prog.iter().enumerate() {
+		let k = insn.k;
+		let valid = match insn.code {
+			c if c == BPF_LD | BPF_ABS => k % 4 == 0 && k < SECCOMP_DATA_SIZE,
+			c if c == BPF_LD | BPF_LEN || c == BPF_LDX | BPF_LEN => true,
+			c if c == BPF_LD | BPF_IMM || c == BPF_LDX | BPF_IMM => true,
+			c if c == BPF_LD | BPF_MEM || c == BPF_LDX | BPF_MEM => k < BPF_MEMWORDS,
+			BPF_ST | BPF_STX => k < BPF_MEMWORDS,
+			c if c == BPF_ALU | BPF_NEG => true,
+			c if c & 0x7 == BPF_ALU && c <= 0xff => match (c & 0xf0, c & BPF_X) {
+				(BPF_ADD | BPF_SUB | BPF_MUL | BPF_OR | BPF_AND | BPF_XOR, _) => true,
+				(BPF_DIV | BPF_MOD, BPF_K) => k != 0,
+				(BPF_LSH | BPF_RSH, BPF_K) => k < 32,
+				(BPF_DIV | BPF_MOD | BPF_LSH | BPF_RSH, _) => true,
+				_ => false,
+			},
+			c if c == BPF_JMP | BPF_JA => in_bounds(pc, k),
+			c if c & 0x7 == BPF_JMP && c <= 0xff => {
+				matches!(c & 0xf0, BPF_JEQ | BPF_JGT | BPF_JGE | BPF_JSET)
+					&& in_bounds(pc, insn.jt as _)
+					&& in_bounds(pc, insn.jf as _)
+			}
+			c if c == BPF_RET | BPF_K || c == BPF_RET | BPF_A => true,
+			c if c == BPF_MISC | BPF_TAX || c == BPF_MISC | BPF_TXA => true,
+			_ => false,
+		};
+		if !valid {
+			return Err(errno!(EINVAL));
+		}
+	}
+	// The program must end with a return instruction
+	let last = prog[prog.len() - 1].code;
+	if last != BPF_RET | BPF_K && last != BPF_RET | BPF_A {
+		return Err(errno!(EINVAL));
+	}
+	Ok(())
+}
+
+/// Runs the program `prog` on `data`, returning the program's return value.
+///
+/// The program must have been checked with [`validate`] beforehand.
+fn run(prog: &[SockFilter], data: &SeccompData) -> u32 {
+	let mut a: u32 = 0;
+	let mut x: u32 = 0;
+	let mut mem = [0u32; BPF_MEMWORDS as usize];
+	let mut pc = 0;
+	while let Some(insn) = prog.get(pc) {
+		pc += 1;
+		let k = insn.k;
+		let code = insn.code;
+		match code & 0x7 {
+			BPF_LD | BPF_LDX => {
+				let val = match code & 0xe0 {
+					BPF_ABS => data.load(k),
+					BPF_LEN => SECCOMP_DATA_SIZE,
+					BPF_MEM => mem[k as usize],
+					_ => k,
+				};
+				if code & 0x7 == BPF_LD {
+					a = val;
+				} else {
+					x = val;
+				}
+			}
+			BPF_ST => mem[k as usize] = a,
+			BPF_STX => mem[k as usize] = x,
+			BPF_ALU => {
+				let src = if code & BPF_X != 0 { x } else { k };
+				a = match code & 0xf0 {
+					BPF_ADD => a.wrapping_add(src),
+					BPF_SUB => a.wrapping_sub(src),
+					BPF_MUL => a.wrapping_mul(src),
+					// A division by zero terminates the program
+					BPF_DIV | BPF_MOD if src == 0 => return 0,
+					BPF_DIV => a / src,
+					BPF_MOD => a % src,
+					BPF_OR => a | src,
+					BPF_AND => a & src,
+					BPF_LSH => a.wrapping_shl(src),
+					BPF_RSH => a.wrapping_shr(src),
+					BPF_NEG => a.wrapping_neg(),
+					_ => a ^ src,
+				};
+			}
+			BPF_JMP => {
+				let src = if code & BPF_X != 0 { x } else { k };
+				let cond = match code & 0xf0 {
+					BPF_JA => {
+						pc += k as usize;
+						continue;
+					}
+					BPF_JEQ => a == src,
+					BPF_JGT => a > src,
+					BPF_JGE => a >= src,
+					_ => a & src != 0,
+				};
+				pc += if cond { insn.jt } else { insn.jf } as usize;
+			}
+			BPF_RET => return if code & BPF_A != 0 { a } else { k },
+			_ => {
+				if code & BPF_TXA != 0 {
+					a = x;
+				} else {
+					x = a;
+				}
+			}
+		}
+	}
+	// Unreachable with a valid program
+	SECCOMP_RET_KILL_PROCESS
+}
+
+/// A seccomp filter.
+#[derive(Debug)]
+struct Filter {
+	/// The BPF program of the filter.
+	prog: Vec<SockFilter>,
+	/// The filter installed before this one, if any.
+	prev: Option<Arc<Filter>>,
+}
+
+/// The seccomp state of a process.
+#[derive(Clone, Debug, Default)]
+pub struct Seccomp {
+	/// The current mode.
+	mode: u8,
+	/// The last installed filter. Other filters are reachable through it.
+	filter: Option<Arc<Filter>>,
+}
+
+impl Seccomp {
+	/// Returns the current mode.
+	pub fn get_mode(&self) -> u8 {
+		self.mode
+	}
+
+	/// Returns an iterator over the installed filters, from the last installed to the first.
+	fn filters(&self) -> impl Iterator<Item = &Filter> {
+		let mut cur = self.filter.as_deref();
+		core::iter::from_fn(move || {
+			let filter = cur?;
+			cur = filter.prev.as_deref();
+			Some(filter)
+		})
+	}
+
+	/// Returns the number of installed filters.
+	pub fn filters_count(&self) -> usize {
+		self.filters().count()
+	}
+
+	/// Enters the strict mode.
+	///
+	/// If filters are installed, the function returns [`errno::EINVAL`].
+	pub fn set_strict(&mut self) -> EResult<()> {
+		if self.mode == SECCOMP_MODE_FILTER {
+			return Err(errno!(EINVAL));
+		}
+		self.mode = SECCOMP_MODE_STRICT;
+		Ok(())
+	}
+
+	/// Installs the filter with the program `prog`, entering the filter mode.
+	///
+	/// If the program is invalid or if the process is in strict mode, the function returns
+	/// [`errno::EINVAL`].
+	pub fn add_filter(&mut self, prog: Vec<SockFilter>) -> EResult<()> {
+		if self.mode == SECCOMP_MODE_STRICT {
+			return Err(errno!(EINVAL));
+		}
+		validate(&prog)?;
+		// Limit the cost of the evaluation of the filters
+		let total = self
+			.filters()
+			.map(|filter| filter.prog.len() + 4)
+			.sum::<usize>();
+		if total + prog.len() + 4 > MAX_INSNS_PER_PATH {
+			return Err(errno!(ENOMEM));
+		}
+		self.filter = Some(Arc::new(Filter {
+			prog,
+			prev: self.filter.take(),
+		})?);
+		self.mode = SECCOMP_MODE_FILTER;
+		Ok(())
+	}
+
+	/// Runs all the installed filters on `data` and returns the action to take.
+	///
+	/// When filters disagree, the action with the highest precedence is kept.
+	fn run_filters(&self, data: &SeccompData) -> u32 {
+		self.filters()
+			.map(|filter| run(&filter.prog, data))
+			.min_by_key(|ret| (ret & SECCOMP_RET_ACTION_FULL) as i32)
+			.unwrap_or(SECCOMP_RET_ALLOW)
+	}
+}
+
+/// Tells whether the given filter action is supported.
+pub fn is_action_available(action: u32) -> bool {
+	matches!(
+		action,
+		SECCOMP_RET_KILL_PROCESS
+			| SECCOMP_RET_KILL_THREAD
+			| SECCOMP_RET_TRAP
+			| SECCOMP_RET_ERRNO
+			| SECCOMP_RET_LOG
+			| SECCOMP_RET_ALLOW
+	)
+}
+
+/// Checks the system call with ID `id` against the seccomp state of the current process, and
+/// applies the resulting action.
+///
+/// `regs` is the set of registers of the process at the time of the system call. If the system
+/// call is not executed, its return value is set in `regs`.
+///
+/// The function returns `true` if the system call may be executed.
+pub fn syscall_entry(id: usize, regs: &mut Regs) -> bool {
+	let proc_mutex = Process::current();
+	let mut proc = proc_mutex.lock();
+	let ret = match proc.seccomp.mode {
+		SECCOMP_MODE_DISABLED => return true,
+		SECCOMP_MODE_STRICT => {
+			if STRICT_SYSCALLS.contains(&id) {
+				return true;
+			}
+			SignalAction::Terminate.exec(Signal::SIGKILL, &mut proc);
+			return false;
+		}
+		_ => proc.seccomp.run_filters(&SeccompData::new(id, regs)),
+	};
+	let data = ret & SECCOMP_RET_DATA;
+	match ret & SECCOMP_RET_ACTION_FULL {
+		SECCOMP_RET_ALLOW | SECCOMP_RET_LOG => return true,
+		SECCOMP_RET_ERRNO => {
+			let errno = min(data, MAX_ERRNO) as isize;
+			regs.set_syscall_return(Ok(-errno as _));
+		}
+		SECCOMP_RET_TRAP => {
+			regs.set_syscall_return(Err(errno!(ENOSYS)));
+			let info = SigInfo::seccomp(regs.eip, id as _, AUDIT_ARCH_I386, data as _);
+			proc.kill_info(Signal::SIGSYS, info);
+		}
+		SECCOMP_RET_KILL_THREAD => SignalAction::Terminate.exec(Signal::SIGSYS, &mut proc),
+		// Unknown actions kill the process as well
+		_ => SignalAction::Abort.exec(Signal::SIGSYS, &mut proc),
+	}
+	false
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use utils::vec;
+
+	/// Returns a BPF instruction.
+	const fn insn(code: u16, jt: u8, jf: u8, k: u32) -> SockFilter {
+		SockFilter {
+			code,
+			jt,
+			jf,
+			k,
+		}
+	}
+
+	/// Returns the data for the system call `nr` with the first argument `arg`.
+	fn data(nr: i32, arg: u64) -> SeccompData {
+		SeccompData {
+			nr,
+			arch: AUDIT_ARCH_I386,
+			instruction_pointer: 0,
+			args: [arg, 0, 0, 0, 0, 0],
+		}
+	}
+
+	#[test_case]
+	fn seccomp_validate() {
+		// Empty program
+		assert!(validate(&[]).is_err());
+		// Missing return
+		assert!(validate(&[insn(BPF_LD | BPF_ABS, 0, 0, 0)]).is_err());
+		// Jump out of bounds
+		assert!(validate(&[
+			insn(BPF_JMP | BPF_JEQ, 1, 0, 0),
+			insn(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_ALLOW),
+		])
+		.is_err());
+		// Unaligned load
+		assert!(validate(&[
+			insn(BPF_LD | BPF_ABS, 0, 0, 2),
+			insn(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_ALLOW),
+		])
+		.is_err());
+		// Huge jump offsets must not overflow
+		for k in [u32::MAX, u32::MAX - 1, u32::MAX / 2 + 1] {
+			assert!(validate(&[
+				insn(BPF_JMP | BPF_JA, 0, 0, k),
+				insn(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_ALLOW),
+			])
+			.is_err());
+		}
+		// The furthest valid jump
+		assert!(validate(&[
+			insn(BPF_JMP | BPF_JA, 0, 0, 1),
+			insn(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_KILL_THREAD),
+			insn(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_ALLOW),
+		])
+		.is_ok());
+		assert!(validate(&[insn(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_ALLOW)]).is_ok());
+	}
+
+	#[test_case]
+	fn seccomp_run() {
+		// Deny system call 42 with EPERM if its first argument is 7, allow everything else
+		let prog = [
+			insn(BPF_LD | BPF_ABS, 0, 0, 0),
+			insn(BPF_JMP | BPF_JEQ | BPF_K, 0, 3, 42),
+			insn(BPF_LD | BPF_ABS, 0, 0, 16),
+			insn(BPF_JMP | BPF_JEQ | BPF_K, 0, 1, 7),
+			insn(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_ERRNO | 1),
+			insn(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_ALLOW),
+		];
+		validate(&prog).unwrap();
+		assert_eq!(run(&prog, &data(42, 7)), SECCOMP_RET_ERRNO | 1);
+		assert_eq!(run(&prog, &data(42, 8)), SECCOMP_RET_ALLOW);
+		assert_eq!(run(&prog, &data(3, 7)), SECCOMP_RET_ALLOW);
+	}
+
+	#[test_case]
+	fn seccomp_precedence() {
+		let mut seccomp = Seccomp::default();
+		let ret = |action| vec![insn(BPF_RET | BPF_K, 0, 0, action)].unwrap();
+		seccomp.add_filter(ret(SECCOMP_RET_ALLOW)).unwrap();
+		seccomp.add_filter(ret(SECCOMP_RET_ERRNO | 1)).unwrap();
+		seccomp.add_filter(ret(SECCOMP_RET_TRAP)).unwrap();
+		assert_eq!(seccomp.filters_count(), 3);
+		assert_eq!(seccomp.run_filters(&data(0, 0)), SECCOMP_RET_TRAP);
+		seccomp.add_filter(ret(SECCOMP_RET_KILL_PROCESS)).unwrap();
+		assert_eq!(seccomp.run_filters(&data(0, 0)), SECCOMP_RET_KILL_PROCESS);
+		assert!(seccomp.set_strict().is_err());
+	}
+}
@@ -20,22 +20,27 @@
 
 mod signal_trampoline;
 
-use super::{oom, Process, State, REDZONE_SIZE};
+use super::{coredump, oom, Process, State, REDZONE_SIZE};
 use crate::{
+	cpu::sse,
 	file::perm::Uid,
-	memory::VirtAddr,
-	process::{pid::Pid, regs::Regs, signal::signal_trampoline::signal_trampoline},
-	time::unit::ClockIdT,
+	memory::{VirtAddr, PROCESS_END},
+	process::{
+		pid::Pid,
+		regs::{Regs, XSAVE_ALIGN},
+		signal::signal_trampoline::signal_trampoline,
+	},
 };
 use core::{
 	ffi::{c_int, c_void},
 	fmt,
 	mem::{size_of, transmute},
 	ptr,
-	ptr::{null_mut, NonNull},
+	ptr::null_mut,
 	slice,
 };
-use utils::{errno, errno::Errno};
+use macros::AnyRepr;
+use utils::{collections::vec::Vec, errno, errno::Errno};
 
 /// Signal handler value: Ignoring the signal.
 pub const SIG_IGN: usize = 0x0;
@@ -45,6 +50,8 @@ pub const SIG_DFL: usize = 0x1;
 // TODO implement all flags
 /// [`SigAction`] flag: If set, use `sa_sigaction` instead of `sa_handler`.
 pub const SA_SIGINFO: i32 = 0x00000004;
+/// [`SigAction`] flag: If set, the handler is executed on the alternate signal stack, if any.
+pub const SA_ONSTACK: i32 = 0x08000000;
 /// [`SigAction`] flag: If set, the system call must restart after being interrupted by a signal.
 pub const SA_RESTART: i32 = 0x10000000;
 /// [`SigAction`] flag: If set, the signal is not added to the signal mask of the process when
@@ -55,12 +62,71 @@ pub const SA_NODEFER: i32 = 0x40000000;
 pub const SIGEV_SIGNAL: c_int = 0;
 /// Notify method: do nothing
 pub const SIGEV_NONE: c_int = 1;
-/// Notify method: starts a function as a new thread
+/// Notify method: starts a function as a new thread. This method is implemented by the libc
 pub const SIGEV_THREAD: c_int = 2;
+/// Notify method: generate a signal, targeting a specific thread
+pub const SIGEV_THREAD_ID: c_int = 4;
+
+/// The first real-time signal.
+pub const SIGRTMIN: u8 = 32;
+/// The last real-time signal.
+pub const SIGRTMAX: u8 = 64;
 
 /// The size of the signal handlers table (the number of signals + 1, since
 /// indexing begins at 1 instead of 0).
-pub const SIGNALS_COUNT: usize = 32;
+pub const SIGNALS_COUNT: usize = SIGRTMAX as usize + 1;
+/// The default limit on the number of signals that can be queued on a process.
+pub const SIGQUEUE_MAX: usize = 1024;
+
+/// [`SigAltStack`] flag: the process is currently executing on the alternate signal stack.
+pub const SS_ONSTACK: i32 = 1;
+/// [`SigAltStack`] flag: the alternate signal stack is disabled.
+pub const SS_DISABLE: i32 = 2;
+/// The minimum size of an alternate signal stack.
+pub const MINSIGSTKSZ: usize = 2048;
+
+/// Returns the maximum size in bytes of the data pushed on the stack to execute a signal
+/// handler.
+///
+/// The size depends on the size of the extended processor state.
+pub fn frame_size() -> usize {
+	frame_size_for(sse::xsave_size())
+}
+
+/// Returns the maximum size in bytes of the data pushed on the stack to execute a signal
+/// handler, with an extended processor state of `xstate_size` bytes.
+fn frame_size_for(xstate_size: usize) -> usize {
+	let xstate_size = match xstate_size {
+		0 => 0,
+		// Account for alignment
+		size => size + XSAVE_ALIGN - 1,
+	};
+	size_of::<UContext>() + xstate_size + size_of::<SigInfo>() + size_of::<usize>() * 5
+}
+
+/// Returns the layout of the data pushed on the stack to execute a signal handler, below
+/// `stack_addr`, with an extended processor state of `xstate_size` bytes.
+///
+/// The function returns the addresses of the context, of the extended state, of the signal
+/// information and the stack pointer of the handler, in that order.
+///
+/// Addresses wrap around if the stack is too close to zero, in which case the stack pointer is
+/// above `stack_addr`.
+fn frame_layout(
+	stack_addr: VirtAddr,
+	xstate_size: usize,
+) -> (VirtAddr, VirtAddr, VirtAddr, VirtAddr) {
+	let ctx_addr = stack_addr - size_of::<UContext>();
+	// The extended state is placed below the context, aligned for XSAVE
+	let xstate_addr = if xstate_size > 0 {
+		VirtAddr(ctx_addr.0.wrapping_sub(xstate_size) & !(XSAVE_ALIGN - 1))
+	} else {
+		ctx_addr
+	};
+	let info_addr = xstate_addr - size_of::<SigInfo>();
+	let signal_esp = info_addr - size_of::<usize>() * 5;
+	(ctx_addr, xstate_addr, info_addr, signal_esp)
+}
 
 /// Enumeration representing the action to perform for a signal.
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -81,7 +147,6 @@ impl SignalAction {
 	/// Executes the signal action for the given process.
 	pub fn exec(self, sig: Signal, process: &mut Process) {
 		match self {
-			// TODO when `Abort`ing, dump core
 			SignalAction::Terminate | SignalAction::Abort => {
 				#[cfg(feature = "strace")]
 				println!(
@@ -90,7 +155,10 @@ impl SignalAction {
 					signal = sig.get_id()
 				);
 				process.set_state(State::Zombie);
-				process.set_waitable(sig.get_id() as _);
+				// If dumping core, the process becomes waitable once the dump is over
+				if self != SignalAction::Abort || !coredump::schedule(process, sig) {
+					process.set_waitable(sig.get_id() as _);
+				}
 			}
 			SignalAction::Ignore => {}
 			SignalAction::Stop => {
@@ -112,83 +180,261 @@ impl SignalAction {
 /// A signal handler value.
 pub type SigVal = usize;
 
-// FIXME: fields are incorrect (check musl source)
-/// Signal information.
+/// Signal code: sent by `kill`.
+pub const SI_USER: i32 = 0;
+/// Signal code: sent by the kernel.
+pub const SI_KERNEL: i32 = 0x80;
+/// Signal code: sent by `sigqueue`.
+pub const SI_QUEUE: i32 = -1;
+/// Signal code: sent by the expiration of a timer.
+pub const SI_TIMER: i32 = -2;
+/// Signal code: sent by the arrival of a message on an empty POSIX message queue.
+pub const SI_MESGQ: i32 = -3;
+/// Signal code: sent by `tkill` or `tgkill`.
+pub const SI_TKILL: i32 = -6;
+
+/// `SIGILL` code: illegal operand.
+pub const ILL_ILLOPN: i32 = 2;
+/// `SIGFPE` code: integer divide by zero.
+pub const FPE_INTDIV: i32 = 1;
+/// `SIGSEGV` code: address not mapped.
+pub const SEGV_MAPERR: i32 = 1;
+/// `SIGSEGV` code: invalid permissions for mapped object.
+pub const SEGV_ACCERR: i32 = 2;
+/// `SIGBUS` code: invalid address alignment.
+pub const BUS_ADRALN: i32 = 1;
+/// `SIGTRAP` code: process breakpoint.
+pub const TRAP_BRKPT: i32 = 1;
+/// `SIGTRAP` code: process trace trap.
+pub const TRAP_TRACE: i32 = 2;
+/// `SIGSYS` code: system call denied by a seccomp filter.
+pub const SYS_SECCOMP: i32 = 1;
+/// `SIGCHLD` code: child has exited.
+pub const CLD_EXITED: i32 = 1;
+/// `SIGCHLD` code: child was killed.
+pub const CLD_KILLED: i32 = 2;
+/// `SIGCHLD` code: child terminated abnormally and dumped core.
+pub const CLD_DUMPED: i32 = 3;
+/// `SIGCHLD` code: traced child has trapped.
+pub const CLD_TRAPPED: i32 = 4;
+/// `SIGCHLD` code: child has stopped.
+pub const CLD_STOPPED: i32 = 5;
+/// `SIGCHLD` code: stopped child has continued.
+pub const CLD_CONTINUED: i32 = 6;
+
+/// Signal information, passed to handlers registered with [`SA_SIGINFO`].
+///
+/// The layout matches the one of `siginfo_t`. The fields following `si_code` are a union whose
+/// interpretation depends on the signal and the signal code.
 #[repr(C)]
+#[derive(AnyRepr, Clone, Copy, Debug, Default)]
 pub struct SigInfo {
 	/// Signal number.
-	si_signo: i32,
+	pub si_signo: i32,
 	/// An errno value.
-	si_errno: i32,
+	pub si_errno: i32,
 	/// Signal code.
-	si_code: i32,
-	/// Trap number that caused hardware-generated signal.
-	si_trapno: i32,
-	/// Sending process ID.
-	si_pid: Pid,
-	/// Real user ID of sending process.
-	si_uid: Uid,
-	/// Exit value or signal.
-	si_status: i32,
-	/// User time consumed.
-	si_utime: ClockIdT,
-	/// System time consumed.
-	si_stime: ClockIdT,
-	/// Signal value
-	si_value: SigVal,
-	/// POSIX.1b signal.
-	si_int: i32,
-	/// POSIX.1b signal.
-	si_ptr: *mut c_void,
-	/// Timer overrun count.
-	si_overrun: i32,
-	/// Timer ID.
-	si_timerid: i32,
-	/// Memory location which caused fault.
-	si_addr: *mut c_void,
-	/// Band event.
-	si_band: i32, // FIXME long (64bits?)
-	/// File descriptor.
-	si_fd: i32,
-	/// Least significant bit of address.
-	si_addr_lsb: i16,
-	/// Lower bound when address violation.
-	si_lower: *mut c_void,
-	/// Upper bound when address violation.
-	si_upper: *mut c_void,
-	/// Protection key on PTE that caused fault.
-	si_pkey: i32,
-	/// Address of system call instruction.
-	si_call_addr: *mut c_void,
-	/// Number of attempted system call.
-	si_syscall: i32,
-	/// Architecture of attempted system call.
-	si_arch: u32,
+	pub si_code: i32,
+	/// The content of the union.
+	fields: [u32; 29],
+}
+
+impl SigInfo {
+	/// Creates an instance for `sig` with the given `code`, all other fields being zero.
+	pub fn new(sig: Signal, code: i32) -> Self {
+		Self {
+			si_signo: sig.get_id() as _,
+			si_errno: 0,
+			si_code: code,
+			fields: [0; 29],
+		}
+	}
+
+	/// Creates an instance for a signal sent by the process with PID `pid` and real user ID
+	/// `uid`.
+	pub fn user(sig: Signal, code: i32, pid: Pid, uid: Uid) -> Self {
+		let mut info = Self::new(sig, code);
+		info.fields[0] = pid as _;
+		info.fields[1] = uid as _;
+		info
+	}
+
+	/// Creates an instance for a signal sent by the expiration of the timer with ID `timerid`.
+	///
+	/// `value` is the value specified by the creator of the timer.
+	pub fn timer(sig: Signal, timerid: i32, overrun: i32, value: SigVal) -> Self {
+		let mut info = Self::new(sig, SI_TIMER);
+		info.fields[0] = timerid as _;
+		info.fields[1] = overrun as _;
+		info.fields[2] = value as _;
+		info
+	}
+
+	/// Creates an instance for a signal sent by the arrival of a message on an empty POSIX message
+	/// queue.
+	///
+	/// `pid` and `uid` identify the sender of the message, and `value` is the value specified at
+	/// registration.
+	pub fn mesgq(sig: Signal, pid: Pid, uid: Uid, value: SigVal) -> Self {
+		let mut info = Self::user(sig, SI_MESGQ, pid, uid);
+		info.fields[2] = value as _;
+		info
+	}
+
+	/// Creates an instance for a `SIGCHLD` signal reporting a change of state of the child process
+	/// with PID `pid` and real user ID `uid`.
+	///
+	/// Arguments:
+	/// - `code` is the kind of change, as a `CLD_*` value
+	/// - `status` is the exit status, or the signal that caused the change
+	/// - `utime` and `stime` are the user and system CPU time consumed by the child, in clock
+	///   ticks
+	pub fn child(code: i32, pid: Pid, uid: Uid, status: i32, utime: u32, stime: u32) -> Self {
+		let mut info = Self::user(Signal::SIGCHLD, code, pid, uid);
+		info.fields[2] = status as _;
+		info.fields[3] = utime;
+		info.fields[4] = stime;
+		info
+	}
+
+	/// Creates an instance for a signal triggered by a fault at the address `addr`.
+	pub fn fault(sig: Signal, code: i32, addr: VirtAddr) -> Self {
+		let mut info = Self::new(sig, code);
+		info.fields[0] = addr.0 as _;
+		info
+	}
+
+	/// Creates an instance for a `SIGSYS` signal triggered by a seccomp filter.
+	///
+	/// Arguments:
+	/// - `call_addr` is the address of the instruction performing the system call
+	/// - `syscall` is the ID of the system call
+	/// - `arch` is the architecture of the system call, as an `AUDIT_ARCH_*` value
+	/// - `data` is the data returned by the filter
+	pub fn seccomp(call_addr: usize, syscall: i32, arch: u32, data: i32) -> Self {
+		let mut info = Self::new(Signal::SIGSYS, SYS_SECCOMP);
+		info.si_errno = data;
+		info.fields[0] = call_addr as _;
+		info.fields[1] = syscall as _;
+		info.fields[2] = arch;
+		info
+	}
+
+	/// Returns the PID of the sending process.
+	pub fn si_pid(&self) -> Pid {
+		self.fields[0] as _
+	}
+
+	/// Returns the real user ID of the sending process.
+	pub fn si_uid(&self) -> Uid {
+		self.fields[1] as _
+	}
+
+	/// Returns the ID of the timer that sent the signal.
+	pub fn si_timerid(&self) -> i32 {
+		self.fields[0] as _
+	}
+
+	/// Returns the overrun count of the timer that sent the signal.
+	pub fn si_overrun(&self) -> i32 {
+		self.fields[1] as _
+	}
+
+	/// Returns the signal value.
+	pub fn si_value(&self) -> SigVal {
+		self.fields[2] as _
+	}
+
+	/// Returns the faulting address.
+	pub fn si_addr(&self) -> VirtAddr {
+		VirtAddr(self.fields[0] as _)
+	}
 }
 
 /// A bits signal mask.
+///
+/// Bit `n - 1` represents the signal number `n`.
+#[repr(transparent)]
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct SigSet(pub u64);
 
 impl SigSet {
-	/// Tells whether the `n`th bit is set.
+	/// Tells whether the signal `n` is in the set.
 	pub fn is_set(&self, n: usize) -> bool {
-		self.0 & (1 << n) != 0
+		self.0 & (1 << (n - 1)) != 0
 	}
 
-	/// Sets the `n`th bit.
+	/// Adds the signal `n` to the set.
 	pub fn set(&mut self, n: usize) {
-		self.0 |= (1 << n) as u64;
+		self.0 |= 1 << (n - 1);
 	}
 
-	/// Sets the `n`th bit.
+	/// Removes the signal `n` from the set.
 	pub fn clear(&mut self, n: usize) {
-		self.0 &= !((1 << n) as u64);
+		self.0 &= !(1 << (n - 1));
+	}
+
+	/// Returns an iterator over the signal numbers in the set, in increasing order.
+	pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+		(1..SIGNALS_COUNT).filter(|n| self.is_set(*n))
+	}
+}
+
+/// The pending signals of a process, along with their information.
+#[derive(Debug, Default)]
+pub struct SigPending {
+	/// The set of pending signals.
+	set: SigSet,
+	/// Information about pending signals, in the order they have been received.
+	///
+	/// A pending standard signal has at most one entry, while real-time signals are queued.
+	queue: Vec<SigInfo>,
+}
+
+impl SigPending {
+	/// Returns the set of pending signals.
+	pub fn get_set(&self) -> SigSet {
+		self.set
+	}
+
+	/// Sets the signal `sig` as pending, with the information `info`.
+	///
+	/// A standard signal that is already pending is not queued a second time. At most `limit`
+	/// signals can have their information queued.
+	pub fn push(&mut self, sig: Signal, info: SigInfo, limit: u64) {
+		let id = sig.get_id() as usize;
+		if !sig.is_realtime() && self.set.is_set(id) {
+			return;
+		}
+		let queued = (self.queue.len() as u64) < limit && self.queue.push(info).is_ok();
+		// Real-time signals are lost if they cannot be queued. Standard signals remain
+		// pending, without their information
+		if queued || !sig.is_realtime() {
+			self.set.set(id);
+		}
 	}
 
-	/// Returns an iterator over the bitset's values
-	pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
-		(0..64).map(|n| self.is_set(n))
+	/// Removes the next pending signal in `set` and returns it, along with its information.
+	///
+	/// Signals are dequeued by increasing number, and real-time signals with the same number in
+	/// the order they have been received.
+	///
+	/// If no signal in `set` is pending, the function returns `None`.
+	pub fn pop(&mut self, set: SigSet) -> Option<(Signal, SigInfo)> {
+		let pending = SigSet(self.set.0 & set.0);
+		let id = pending.iter().next()?;
+		let sig = Signal::try_from(id as c_int).ok()?;
+		let info = self
+			.queue
+			.iter()
+			.position(|info| info.si_signo == id as i32)
+			.map(|i| self.queue.remove(i))
+			.unwrap_or_else(|| SigInfo::new(sig, SI_KERNEL));
+		// The signal remains pending if another instance is queued
+		if !self.queue.iter().any(|info| info.si_signo == id as i32) {
+			self.set.clear(id);
+		}
+		Some((sig, info))
 	}
 }
 
@@ -224,34 +470,71 @@ pub struct SigAction {
 
 /// Notification from asynchronous routines.
 #[repr(C)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct SigEvent {
-	/// Notification method.
-	pub sigev_notify: c_int,
+	/// Data passed with the notification.
+	pub sigev_value: SigVal,
 	/// Notification signal.
 	pub sigev_signo: c_int,
-	/// TODO doc
-	pub sigev_value: SigVal,
-	/// Function used for thread notification.
-	pub sigev_notify_function: Option<NonNull<extern "C" fn(SigVal)>>,
-	/// Data passed with notification.
-	pub sigev_notify_attributes: Option<NonNull<c_void>>,
-	/// ID of thread to signal.
-	pub sigev_notify_thread_id: Pid,
+	/// Notification method.
+	pub sigev_notify: c_int,
+	/// ID of thread to signal, for [`SIGEV_THREAD_ID`].
+	///
+	/// For [`SIGEV_THREAD`], the libc stores the function and attributes of the thread here.
+	pub sigev_notify_thread_id: c_int,
+	/// Padding to the size of the userspace structure.
+	pub sigev_pad: [c_int; 12],
 }
 
 impl SigEvent {
 	/// Tells whether the structure is valid.
+	///
+	/// [`SIGEV_THREAD`] is not valid since it is emulated by the libc on top of
+	/// [`SIGEV_THREAD_ID`].
 	pub fn is_valid(&self) -> bool {
-		if !matches!(self.sigev_notify, SIGEV_SIGNAL | SIGEV_NONE | SIGEV_THREAD) {
-			return false;
+		match self.sigev_notify {
+			SIGEV_NONE => true,
+			SIGEV_SIGNAL | SIGEV_THREAD_ID => Signal::try_from(self.sigev_signo).is_ok(),
+			_ => false,
 		}
-		if Signal::try_from(self.sigev_signo).is_err() {
-			return false;
+	}
+}
+
+/// Description of an alternate signal stack (`stack_t`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct SigAltStack {
+	/// The address of the bottom of the stack.
+	pub ss_sp: *mut c_void,
+	/// The stack's flags.
+	pub ss_flags: i32,
+	/// The size of the stack in bytes.
+	pub ss_size: usize,
+}
+
+impl Default for SigAltStack {
+	fn default() -> Self {
+		Self {
+			ss_sp: null_mut(),
+			ss_flags: SS_DISABLE,
+			ss_size: 0,
 		}
-		// TODO check sigev_notify_thread_id
+	}
+}
 
-		true
+impl SigAltStack {
+	/// Returns the address of the end of the stack.
+	///
+	/// If the range of the stack overflows, the function returns `None`.
+	pub fn end(&self) -> Option<usize> {
+		(self.ss_sp as usize).checked_add(self.ss_size)
+	}
+
+	/// Tells whether the given stack pointer `esp` is located on the stack.
+	pub fn contains(&self, esp: usize) -> bool {
+		let begin = self.ss_sp as usize;
+		self.ss_flags & SS_DISABLE == 0
+			&& self.end().is_some_and(|end| (begin..end).contains(&esp))
 	}
 }
 
@@ -270,6 +553,8 @@ pub struct UContext {
 	// FIXME: `Regs` does not match the actual layout of mcontext_t
 	/// Saved registers.
 	pub uc_mcontext: Regs,
+	/// Pointer to the saved extended processor state. If null, the state is not saved.
+	pub uc_xstate: *mut c_void,
 }
 
 /// Enumeration containing the different possibilities for signal handling.
@@ -341,21 +626,39 @@ impl SignalHandler {
 	}
 
 	/// Executes the action for `signal` on `process`.
-	pub fn exec(&self, signal: Signal, process: &mut Process) {
+	///
+	/// `info` is the information about the signal, passed to the handler.
+	pub fn exec(&self, signal: Signal, info: &SigInfo, process: &mut Process) {
 		let process_state = process.get_state();
 		if matches!(process_state, State::Zombie) {
 			return;
 		}
 		match self {
 			Self::Ignore => {}
-			// TODO handle SA_SIGINFO
 			Self::Handler(action) if signal.can_catch() => {
-				// Prepare the signal handler stack
-				// TODO Handle the case where an alternate stack is specified (sigaltstack + flag
-				// SA_ONSTACK)
-				let stack_addr = VirtAddr(process.regs.esp) - REDZONE_SIZE;
-				let signal_data_size = size_of::<UContext>() + size_of::<usize>() * 4;
-				let signal_esp = stack_addr - signal_data_size;
+				// Prepare the signal handler stack, on the alternate stack if requested and not
+				// already on it
+				let esp = process.regs.esp;
+				let altstack = &process.altstack;
+				let stack_addr = if action.sa_flags & SA_ONSTACK != 0
+					&& altstack.ss_flags & SS_DISABLE == 0
+					&& !altstack.contains(esp)
+				{
+					// The range has been checked by `sigaltstack`
+					VirtAddr(altstack.end().unwrap_or(0))
+				} else {
+					VirtAddr(esp) - REDZONE_SIZE
+				};
+				let xstate_size = process.xstate.as_slice().len();
+				let (ctx_addr, xstate_addr, info_addr, signal_esp) =
+					frame_layout(stack_addr, xstate_size);
+				// The frame must lie entirely in userspace
+				if stack_addr > PROCESS_END || signal_esp > stack_addr {
+					let segv = Signal::SIGSEGV;
+					segv.get_default_action().exec(segv, process);
+					return;
+				}
+				let signal_data_size = stack_addr.0 - signal_esp.0;
 				{
 					let mem_space = process.get_mem_space().unwrap();
 					let mut mem_space = mem_space.lock();
@@ -367,17 +670,30 @@ impl SignalHandler {
 				let ctx = UContext {
 					uc_flags: 0, // TODO
 					uc_link: null_mut(),
-					uc_sigmask: process.sigmask,
+					// If the mask has been replaced temporarily, restore the original one after
+					// the handler
+					uc_sigmask: process.saved_sigmask.take().unwrap_or(process.sigmask),
 					uc_stack: stack_addr.as_ptr(),
 					uc_mcontext: process.regs.clone(),
+					uc_xstate: if xstate_size > 0 {
+						xstate_addr.as_ptr()
+					} else {
+						null_mut()
+					},
 				};
 				unsafe {
 					// Write `ctx`
-					let ctx_addr = stack_addr - size_of::<UContext>();
 					ptr::write_volatile(ctx_addr.as_ptr(), ctx);
-					let args = slice::from_raw_parts_mut(signal_esp.as_ptr::<usize>(), 4);
-					// Pointer to  `ctx`
-					args[3] = ctx_addr.0;
+					// Write the extended state
+					let xstate = process.xstate.as_slice();
+					ptr::copy_nonoverlapping(xstate.as_ptr(), xstate_addr.as_ptr(), xstate.len());
+					// Write `info`
+					ptr::write_volatile(info_addr.as_ptr(), *info);
+					let args = slice::from_raw_parts_mut(signal_esp.as_ptr::<usize>(), 5);
+					// Pointer to `ctx`
+					args[4] = ctx_addr.0;
+					// Pointer to `info`
+					args[3] = info_addr.0;
 					// Signal number
 					args[2] = signal.get_id() as usize;
 					// Pointer to the handler
@@ -398,9 +714,9 @@ impl SignalHandler {
 			}
 			// Execute default action
 			_ => {
-				// Signals on the init process can be executed only if the process has set a
-				// signal handler
-				if signal.can_catch() && process.is_init() {
+				// Signals on the init process of a namespace can be executed only if the process
+				// has set a signal handler
+				if signal.can_catch() && process.is_ns_init() {
 					return;
 				}
 				signal.get_default_action().exec(signal, process)
@@ -470,6 +786,8 @@ pub enum Signal {
 	SIGPOLL,
 	/// Bad system call.
 	SIGSYS,
+	/// Real-time signal, with its number between [`SIGRTMIN`] and [`SIGRTMAX`].
+	SIGRT(u8),
 }
 
 impl TryFrom<c_int> for Signal {
@@ -507,6 +825,7 @@ impl TryFrom<c_int> for Signal {
 			28 => Ok(Self::SIGWINCH),
 			29 => Ok(Self::SIGPOLL),
 			31 => Ok(Self::SIGSYS),
+			32..=64 => Ok(Self::SIGRT(id as _)),
 			_ => Err(errno!(EINVAL)),
 		}
 	}
@@ -545,6 +864,7 @@ impl Signal {
 			Self::SIGWINCH => 28,
 			Self::SIGPOLL => 29,
 			Self::SIGSYS => 31,
+			Self::SIGRT(id) => *id,
 		}
 	}
 
@@ -580,9 +900,15 @@ impl Signal {
 			Self::SIGWINCH => SignalAction::Ignore,
 			Self::SIGPOLL => SignalAction::Terminate,
 			Self::SIGSYS => SignalAction::Abort,
+			Self::SIGRT(_) => SignalAction::Terminate,
 		}
 	}
 
+	/// Tells whether the signal is a real-time signal, which can be queued several times.
+	pub fn is_realtime(&self) -> bool {
+		matches!(self, Self::SIGRT(_))
+	}
+
 	/// Tells whether the signal can be caught.
 	pub fn can_catch(&self) -> bool {
 		!matches!(
@@ -591,3 +917,111 @@ impl Signal {
 		)
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Returns the real-time signal `SIGRTMIN + n`.
+	fn rt(n: u8) -> Signal {
+		Signal::try_from((SIGRTMIN + n) as c_int).unwrap()
+	}
+
+	/// Returns the information of a signal `sig` queued by the process `pid`.
+	fn info(sig: Signal, pid: Pid) -> SigInfo {
+		SigInfo::user(sig, SI_QUEUE, pid, 0)
+	}
+
+	/// Returns a set containing every signal.
+	fn all() -> SigSet {
+		SigSet(!0)
+	}
+
+	#[test_case]
+	fn sigqueue_standard() {
+		let mut pending = SigPending::default();
+		pending.push(Signal::SIGUSR1, info(Signal::SIGUSR1, 1), u64::MAX);
+		// A pending standard signal is not queued twice
+		pending.push(Signal::SIGUSR1, info(Signal::SIGUSR1, 2), u64::MAX);
+		assert!(pending.get_set().is_set(Signal::SIGUSR1.get_id() as _));
+		let (sig, i) = pending.pop(all()).unwrap();
+		assert_eq!(sig, Signal::SIGUSR1);
+		assert_eq!(i.fields[0], 1);
+		assert!(pending.pop(all()).is_none());
+		assert_eq!(pending.get_set(), SigSet::default());
+	}
+
+	#[test_case]
+	fn sigqueue_realtime_order() {
+		let mut pending = SigPending::default();
+		pending.push(rt(1), info(rt(1), 1), u64::MAX);
+		pending.push(rt(0), info(rt(0), 2), u64::MAX);
+		pending.push(rt(1), info(rt(1), 3), u64::MAX);
+		// Lower numbers first, then in order of arrival
+		let order = [(rt(0), 2), (rt(1), 1), (rt(1), 3)];
+		for (sig, pid) in order {
+			let (s, i) = pending.pop(all()).unwrap();
+			assert_eq!(s, sig);
+			assert_eq!(i.fields[0], pid);
+		}
+		assert!(pending.pop(all()).is_none());
+	}
+
+	#[test_case]
+	fn sigqueue_mask() {
+		let mut pending = SigPending::default();
+		pending.push(Signal::SIGUSR1, info(Signal::SIGUSR1, 1), u64::MAX);
+		pending.push(Signal::SIGUSR2, info(Signal::SIGUSR2, 2), u64::MAX);
+		let mut set = SigSet::default();
+		set.set(Signal::SIGUSR2.get_id() as _);
+		assert_eq!(pending.pop(set).unwrap().0, Signal::SIGUSR2);
+		assert!(pending.pop(set).is_none());
+		assert_eq!(pending.pop(all()).unwrap().0, Signal::SIGUSR1);
+	}
+
+	#[test_case]
+	fn sigqueue_limit() {
+		let mut pending = SigPending::default();
+		pending.push(Signal::SIGUSR1, info(Signal::SIGUSR1, 1), 1);
+		// Past the limit, real-time signals are lost
+		pending.push(rt(0), info(rt(0), 2), 1);
+		assert!(!pending.get_set().is_set(rt(0).get_id() as _));
+		// ...while standard signals remain pending without information
+		pending.push(Signal::SIGUSR2, info(Signal::SIGUSR2, 3), 1);
+		assert_eq!(pending.pop(all()).unwrap().1.fields[0], 1);
+		let (sig, i) = pending.pop(all()).unwrap();
+		assert_eq!(sig, Signal::SIGUSR2);
+		assert_eq!(i.si_code, SI_KERNEL);
+		assert!(pending.pop(all()).is_none());
+	}
+
+	#[test_case]
+	fn signal_frame() {
+		// Sizes of the XSAVE area with x87/SSE only, with AVX, and with AVX-512
+		for xstate_size in [0, 576, 832, 2696] {
+			let max = frame_size_for(xstate_size);
+			// The frame must fit whatever the alignment of the stack
+			for misalign in [0, 1, 4, 17, 63] {
+				let stack_addr = VirtAddr(0x10000 - misalign);
+				let (ctx_addr, xstate_addr, info_addr, signal_esp) =
+					frame_layout(stack_addr, xstate_size);
+				assert_eq!(ctx_addr + size_of::<UContext>(), stack_addr);
+				if xstate_size > 0 {
+					assert_eq!(xstate_addr.0 % XSAVE_ALIGN, 0);
+					assert!(xstate_addr + xstate_size <= ctx_addr);
+				} else {
+					assert_eq!(xstate_addr, ctx_addr);
+				}
+				assert_eq!(info_addr + size_of::<SigInfo>(), xstate_addr);
+				assert!(signal_esp < info_addr);
+				assert!(stack_addr.0 - signal_esp.0 <= max);
+			}
+		}
+		// A stack too close to zero makes the frame wrap around
+		let (.., signal_esp) = frame_layout(VirtAddr(16), 576);
+		assert!(signal_esp > VirtAddr(16));
+		// Without extended state, the frame has a fixed size
+		let (.., signal_esp) = frame_layout(VirtAddr(0x10000), 0);
+		assert_eq!(0x10000 - signal_esp.0, frame_size_for(0));
+	}
+}
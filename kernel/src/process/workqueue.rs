@@ -0,0 +1,169 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Work queues allow to defer work to a kernel thread.
+//!
+//! Some contexts, such as interrupt handlers or callbacks registered with
+//! [`crate::event::register_callback`], cannot sleep or perform long operations. Instead, they can
+//! push work onto a queue. Each queue has a kernel thread (the worker) which executes the pushed
+//! works in order, in a context where sleeping is allowed.
+
+use crate::process::{kthread, scheduler, Process, State};
+use core::mem;
+use utils::{
+	boxed::Box,
+	collections::vec::Vec,
+	errno::{AllocResult, EResult},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+/// A deferred work.
+type Work = Box<dyn FnMut()>;
+
+/// The system's default work queue.
+static SYSTEM: WorkQueue = WorkQueue::new();
+
+/// Inner state of a [`WorkQueue`].
+struct WorkQueueInner {
+	/// The pending works, in the order they have been pushed.
+	works: Vec<Work>,
+	/// The worker thread.
+	worker: Option<Arc<IntMutex<Process>>>,
+}
+
+/// A queue of deferred works, executed by a dedicated kernel thread.
+pub struct WorkQueue(IntMutex<WorkQueueInner>);
+
+impl WorkQueue {
+	/// Creates a new work queue.
+	///
+	/// The queue does not execute works until [`Self::start`] is called.
+	pub const fn new() -> Self {
+		Self(IntMutex::new(WorkQueueInner {
+			works: Vec::new(),
+			worker: None,
+		}))
+	}
+
+	/// Spawns the worker thread of the queue, with the given `name`.
+	///
+	/// If the worker is already running, the function does nothing.
+	pub fn start(&'static self, name: &[u8]) -> EResult<()> {
+		if self.0.lock().worker.is_some() {
+			return Ok(());
+		}
+		let worker = kthread::spawn(name, || self.run())?;
+		self.0.lock().worker = Some(worker);
+		Ok(())
+	}
+
+	/// Pushes the work `f` onto the queue, waking up the worker.
+	///
+	/// This function can be called from any context, including interrupt handlers.
+	pub fn push<F: FnOnce() + 'static>(&self, f: F) -> AllocResult<()> {
+		let mut f = Some(f);
+		let work: Work = Box::new(move || {
+			if let Some(f) = f.take() {
+				f();
+			}
+		})?;
+		let mut inner = self.0.lock();
+		inner.works.push(work)?;
+		if let Some(worker) = &inner.worker {
+			worker.lock().wake();
+		}
+		Ok(())
+	}
+
+	/// The worker's loop.
+	fn run(&self) {
+		loop {
+			let works = {
+				let mut inner = self.0.lock();
+				if inner.works.is_empty() {
+					// Sleep while the queue is locked, so that a work cannot be pushed without
+					// waking up the worker
+					Process::current().lock().set_state(State::Sleeping);
+					drop(inner);
+					scheduler::end_tick();
+					continue;
+				}
+				mem::take(&mut inner.works)
+			};
+			for mut work in works {
+				work();
+			}
+		}
+	}
+}
+
+impl Default for WorkQueue {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Pushes the work `f` onto the system's default work queue.
+///
+/// This function can be called from any context, including interrupt handlers.
+pub fn schedule<F: FnOnce() + 'static>(f: F) -> AllocResult<()> {
+	SYSTEM.push(f)
+}
+
+/// Starts the workers of the kernel's work queues.
+///
+/// This function must be called after the creation of the init process.
+pub(crate) fn init() -> EResult<()> {
+	SYSTEM.start(b"kworker")
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn workqueue_order() {
+		let queue = WorkQueue::new();
+		let log = Arc::new(IntMutex::new(Vec::new())).unwrap();
+		for i in 0..3 {
+			let log = log.clone();
+			queue.push(move || log.lock().push(i).unwrap()).unwrap();
+		}
+		// The queue has no worker, so works remain pending
+		let works = mem::take(&mut queue.0.lock().works);
+		assert_eq!(works.len(), 3);
+		assert!(log.lock().is_empty());
+		for mut work in works {
+			work();
+		}
+		assert_eq!(log.lock().as_slice(), &[0, 1, 2]);
+	}
+
+	#[test_case]
+	fn workqueue_once() {
+		let queue = WorkQueue::new();
+		let count = Arc::new(IntMutex::new(0)).unwrap();
+		let c = count.clone();
+		queue.push(move || *c.lock() += 1).unwrap();
+		let mut works = mem::take(&mut queue.0.lock().works);
+		works[0]();
+		works[0]();
+		assert_eq!(*count.lock(), 1);
+	}
+}
@@ -20,7 +20,7 @@
 //! status code.
 
 use super::Args;
-use crate::process::{scheduler, Process};
+use crate::process::{ptrace, regs::Regs, scheduler, Process};
 use core::ffi::c_int;
 use utils::{errno::EResult, lock::IntMutexGuard};
 
@@ -29,8 +29,9 @@ use utils::{errno::EResult, lock::IntMutexGuard};
 /// Arguments:
 /// - `status` is the exit status.
 /// - `thread_group`: if `true`, the function exits the whole process group.
-/// - `proc` is the current process.
-pub fn do_exit(status: u32, thread_group: bool) -> ! {
+/// - `regs` is the set of registers of the process, reported to its tracer if any.
+pub fn do_exit(status: u32, thread_group: bool, regs: &Regs) -> ! {
+	ptrace::exit(regs, status);
 	{
 		let proc_mutex = Process::current();
 		let mut proc = proc_mutex.lock();
@@ -47,6 +48,6 @@ pub fn do_exit(status: u32, thread_group: bool) -> ! {
 	unreachable!();
 }
 
-pub fn _exit(Args(status): Args<c_int>) -> EResult<usize> {
-	do_exit(status as _, false);
+pub fn _exit(Args(status): Args<c_int>, regs: &Regs) -> EResult<usize> {
+	do_exit(status as _, false, regs);
 }
@@ -0,0 +1,52 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `alarm` system call arranges for a `SIGALRM` signal to be delivered to the process after
+//! a given number of seconds.
+
+use crate::{
+	process::Process,
+	syscall::Args,
+	time::{
+		timer::ITIMER_REAL,
+		unit::{ITimerval, Timeval},
+	},
+};
+use core::ffi::c_uint;
+use utils::{errno::EResult, lock::IntMutex, ptr::arc::Arc};
+
+pub fn alarm(Args(seconds): Args<c_uint>, proc: Arc<IntMutex<Process>>) -> EResult<usize> {
+	let manager_mutex = proc.lock().timer_manager();
+	let old = manager_mutex.lock().set_itimer(
+		ITIMER_REAL,
+		ITimerval {
+			it_interval: Default::default(),
+			it_value: Timeval {
+				tv_sec: seconds as _,
+				tv_usec: 0,
+			},
+		},
+	)?;
+	// Round the remaining time to the nearest second, without returning zero if an alarm was
+	// pending
+	let mut remaining = old.it_value.tv_sec;
+	if old.it_value.tv_usec >= 500_000 || (remaining == 0 && old.it_value.tv_usec > 0) {
+		remaining += 1;
+	}
+	Ok(remaining as _)
+}
@@ -0,0 +1,118 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `capget` system call returns the capabilities of a process.
+
+use crate::{
+	file::perm::{AccessProfile, CapSet},
+	process::{
+		mem_space::copy::{SyscallPtr, SyscallSlice},
+		Process,
+	},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+/// Version 1 of the interface, with 32 bit capability sets.
+const LINUX_CAPABILITY_VERSION_1: u32 = 0x19980330;
+/// Version 2 of the interface, with 64 bit capability sets.
+const LINUX_CAPABILITY_VERSION_2: u32 = 0x20071026;
+/// Version 3 of the interface, with 64 bit capability sets.
+const LINUX_CAPABILITY_VERSION_3: u32 = 0x20080522;
+
+/// The header of the `capget` and `capset` system calls.
+#[repr(C)]
+#[derive(Debug)]
+pub struct CapUserHeader {
+	/// The version of the interface.
+	version: u32,
+	/// The PID of the target process.
+	pub pid: c_int,
+}
+
+/// The capability sets, as exchanged with userspace.
+///
+/// Each set is split into 32 bit words, one per element of the array passed to the system call.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CapUserData {
+	/// Effective capabilities.
+	pub effective: u32,
+	/// Permitted capabilities.
+	pub permitted: u32,
+	/// Inheritable capabilities.
+	pub inheritable: u32,
+}
+
+/// Reads the header `hdrp` and returns it along with the number of elements in the data array.
+///
+/// If the version is not supported, the function writes the preferred version in the header and
+/// returns [`errno::EINVAL`].
+pub fn read_header(hdrp: &SyscallPtr<CapUserHeader>) -> EResult<(CapUserHeader, usize)> {
+	let mut hdr = hdrp.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	let len = match hdr.version {
+		LINUX_CAPABILITY_VERSION_1 => 1,
+		LINUX_CAPABILITY_VERSION_2 | LINUX_CAPABILITY_VERSION_3 => 2,
+		_ => {
+			hdr.version = LINUX_CAPABILITY_VERSION_3;
+			hdrp.copy_to_user(hdr)?;
+			return Err(errno!(EINVAL));
+		}
+	};
+	if hdr.pid < 0 {
+		return Err(errno!(EINVAL));
+	}
+	Ok((hdr, len))
+}
+
+/// Splits the capability sets of `ap` into 32 bit words.
+fn to_user(ap: &AccessProfile) -> [CapUserData; 2] {
+	let word = |set: CapSet, i: usize| (set.0 >> (i * 32)) as u32;
+	core::array::from_fn(|i| CapUserData {
+		effective: word(ap.cap_effective, i),
+		permitted: word(ap.cap_permitted, i),
+		inheritable: word(ap.cap_inheritable, i),
+	})
+}
+
+pub fn capget(
+	Args((hdrp, datap)): Args<(SyscallPtr<CapUserHeader>, SyscallSlice<CapUserData>)>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	let (hdr, len) = match read_header(&hdrp) {
+		// Probing the supported version is not an error
+		Err(e) if e.as_int() == errno::EINVAL && datap.0.is_none() => return Ok(0),
+		r => r?,
+	};
+	let ap = if hdr.pid == 0 {
+		proc.lock().access_profile
+	} else {
+		let ns = proc.lock().pid_ns().clone();
+		let target = Process::get_by_ns_pid(&ns, hdr.pid as _).ok_or_else(|| errno!(ESRCH))?;
+		let ap = target.lock().access_profile;
+		ap
+	};
+	datap.copy_to_user(0, &to_user(&ap)[..len])?;
+	Ok(0)
+}
@@ -0,0 +1,79 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `capset` system call sets the capabilities of the current process.
+
+use super::capget::{read_header, CapUserData, CapUserHeader};
+use crate::{
+	file::perm::{CapSet, CAP_SETPCAP},
+	process::{
+		mem_space::copy::{SyscallPtr, SyscallSlice},
+		Process,
+	},
+	syscall::Args,
+};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn capset(
+	Args((hdrp, datap)): Args<(SyscallPtr<CapUserHeader>, SyscallSlice<CapUserData>)>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	let (hdr, len) = read_header(&hdrp)?;
+	let data = datap.copy_from_user(..len)?.ok_or_else(|| errno!(EFAULT))?;
+	let mut proc = proc.lock();
+	// Only the capabilities of the current process can be changed
+	if hdr.pid != 0 && hdr.pid as u32 != proc.get_ns_pid() as u32 {
+		return Err(errno!(EPERM));
+	}
+	// Assemble sets from 32 bit words
+	let set = |f: fn(&CapUserData) -> u32| {
+		let val = data
+			.iter()
+			.enumerate()
+			.fold(0, |val, (i, d)| val | ((f(d) as u64) << (i * 32)));
+		CapSet(val).intersect(CapSet::FULL)
+	};
+	let effective = set(|d| d.effective);
+	let permitted = set(|d| d.permitted);
+	let inheritable = set(|d| d.inheritable);
+	// Check the transition is allowed
+	let ap = &mut proc.access_profile;
+	let inheritable_limit = if ap.has_cap(CAP_SETPCAP) {
+		CapSet::FULL
+	} else {
+		ap.cap_inheritable.union(ap.cap_permitted)
+	};
+	if !inheritable.is_subset_of(inheritable_limit)
+		|| !inheritable.is_subset_of(ap.cap_inheritable.union(ap.cap_bounding))
+		|| !permitted.is_subset_of(ap.cap_permitted)
+		|| !effective.is_subset_of(permitted)
+	{
+		return Err(errno!(EPERM));
+	}
+	ap.cap_effective = effective;
+	ap.cap_permitted = permitted;
+	ap.cap_inheritable = inheritable;
+	// Ambient capabilities must remain both permitted and inheritable
+	ap.cap_ambient = ap.cap_ambient.intersect(permitted).intersect(inheritable);
+	Ok(0)
+}
@@ -19,7 +19,7 @@
 //! The `chown` system call changes the owner of a file.
 
 use crate::{
-	file::{fs::StatSet, vfs, vfs::ResolutionSettings},
+	file::{fs::StatSet, perm::CAP_CHOWN, vfs, vfs::ResolutionSettings},
 	process::{mem_space::copy::SyscallString, Process},
 	syscall::Args,
 };
@@ -46,7 +46,7 @@ pub fn do_chown(
 	// Get file
 	let file = vfs::get_file_from_path(&path, &rs)?;
 	// TODO allow changing group to any group whose owner is member
-	if !rs.access_profile.is_privileged() {
+	if !rs.access_profile.has_cap(CAP_CHOWN) {
 		return Err(errno!(EPERM));
 	}
 	file.node().ops.set_stat(
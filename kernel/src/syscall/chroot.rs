@@ -21,6 +21,7 @@
 
 use crate::{
 	file::{
+		perm::CAP_SYS_CHROOT,
 		vfs,
 		vfs::{mountpoint, ResolutionSettings},
 		FileType,
@@ -42,7 +43,7 @@ pub fn chroot(
 	rs: ResolutionSettings,
 ) -> EResult<usize> {
 	// Check permission
-	if !rs.access_profile.is_privileged() {
+	if !rs.access_profile.has_cap(CAP_SYS_CHROOT) {
 		return Err(errno!(EPERM));
 	}
 	let path = path.copy_from_user()?.ok_or(errno!(EFAULT))?;
@@ -0,0 +1,69 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `clock_nanosleep` system call allows to make the current process sleep until a clock
+//! reaches a given time.
+
+use crate::{
+	process::mem_space::copy::SyscallPtr,
+	syscall::{nanosleep::sleep_until, Args},
+	time::{
+		clock,
+		clock::{
+			CLOCK_MONOTONIC_COARSE, CLOCK_MONOTONIC_RAW, CLOCK_REALTIME_COARSE,
+			CLOCK_THREAD_CPUTIME_ID,
+		},
+		timer::TIMER_ABSTIME,
+		unit::{ClockIdT, TimeUnit, Timespec, Timespec32, TimestampScale},
+	},
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+};
+
+pub fn clock_nanosleep(
+	Args((clockid, flags, request, remain)): Args<(
+		ClockIdT,
+		c_int,
+		SyscallPtr<Timespec32>,
+		SyscallPtr<Timespec32>,
+	)>,
+) -> EResult<usize> {
+	match clockid {
+		CLOCK_MONOTONIC_RAW | CLOCK_REALTIME_COARSE | CLOCK_MONOTONIC_COARSE => {
+			return Err(errno!(EOPNOTSUPP));
+		}
+		CLOCK_THREAD_CPUTIME_ID => return Err(errno!(EINVAL)),
+		_ => {}
+	}
+	let request = request.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	if request.tv_nsec >= 1_000_000_000 {
+		return Err(errno!(EINVAL));
+	}
+	let curr = clock::current_time(clockid, TimestampScale::Nanosecond)?;
+	if flags & TIMER_ABSTIME != 0 {
+		// The remaining time is not reported for an absolute time
+		let end = Timespec::from_nano(request.to_nano());
+		sleep_until(clockid, end, SyscallPtr(None))
+	} else {
+		let end = Timespec::from_nano(curr.saturating_add(request.to_nano()));
+		sleep_until(clockid, end, remain)
+	}
+}
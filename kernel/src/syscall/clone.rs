@@ -19,14 +19,20 @@
 //! The `clone` system call creates a child process.
 
 use crate::{
+	file::perm::{AccessProfile, CAP_SYS_ADMIN},
 	process::{
-		mem_space::copy::SyscallPtr, regs::Regs, scheduler, user_desc::UserDesc, ForkOptions,
-		Process,
+		mem_space::copy::SyscallPtr, namespace, regs::Regs, scheduler, user_desc::UserDesc,
+		ForkOptions, Process,
 	},
-	syscall::{Args, FromSyscallArg},
+	syscall::{pidfd_open::create_pidfd, Args, FromSyscallArg},
 };
 use core::ffi::{c_int, c_ulong, c_void};
-use utils::{errno::EResult, lock::IntMutex, ptr::arc::Arc};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
 
 /// TODO doc
 const CLONE_IO: c_ulong = -0x80000000 as _;
@@ -40,7 +46,8 @@ const CLONE_FILES: c_ulong = 0x400;
 /// If specified, the parent and child processes share the same signal handlers
 /// table.
 const CLONE_SIGHAND: c_ulong = 0x800;
-/// TODO doc
+/// If specified, a pidfd referring to the child process is created in the parent, and its ID is
+/// written at the `parent_tid` address.
 const CLONE_PIDFD: c_ulong = 0x1000;
 /// TODO doc
 const CLONE_PTRACE: c_ulong = 0x2000;
@@ -51,8 +58,6 @@ const CLONE_PARENT: c_ulong = 0x8000;
 /// TODO doc
 const CLONE_THREAD: c_ulong = 0x10000;
 /// TODO doc
-const CLONE_NEWNS: c_ulong = 0x20000;
-/// TODO doc
 const CLONE_SYSVSEM: c_ulong = 0x40000;
 /// TODO doc
 const CLONE_SETTLS: c_ulong = 0x80000;
@@ -69,19 +74,11 @@ const CLONE_CHILD_SETTID: c_ulong = 0x1000000;
 /// TODO doc
 const CLONE_NEWCGROUP: c_ulong = 0x2000000;
 /// TODO doc
-const CLONE_NEWUTS: c_ulong = 0x4000000;
-/// TODO doc
-const CLONE_NEWIPC: c_ulong = 0x8000000;
-/// TODO doc
 const CLONE_NEWUSER: c_ulong = 0x10000000;
-/// TODO doc
-const CLONE_NEWPID: c_ulong = 0x20000000;
-/// TODO doc
-const CLONE_NEWNET: c_ulong = 0x40000000;
 
 #[allow(clippy::type_complexity)]
 pub fn clone(
-	Args((flags, stack, _parent_tid, tls, _child_tid)): Args<(
+	Args((flags, stack, parent_tid, tls, _child_tid)): Args<(
 		c_ulong,
 		*mut c_void,
 		SyscallPtr<c_int>,
@@ -90,8 +87,27 @@ pub fn clone(
 	)>,
 	regs: &Regs,
 	proc_mutex: Arc<IntMutex<Process>>,
+	ap: AccessProfile,
 ) -> EResult<usize> {
-	let new_tid = {
+	if flags & (CLONE_NEWUSER | CLONE_NEWCGROUP) != 0 {
+		return Err(errno!(EINVAL));
+	}
+	// The pidfd is returned through `parent_tid`, and refers to a whole process
+	if flags & CLONE_PIDFD != 0
+		&& flags & (CLONE_PARENT_SETTID | CLONE_THREAD | CLONE_DETACHED) != 0
+	{
+		return Err(errno!(EINVAL));
+	}
+	let new_namespaces = flags as c_int & namespace::CLONE_NEW_MASK;
+	if new_namespaces != 0 && !ap.has_cap(CAP_SYS_ADMIN) {
+		return Err(errno!(EPERM));
+	}
+	// The PID of the child is returned in the namespace of the parent
+	let (pid_ns, fds) = {
+		let proc = proc_mutex.lock();
+		(proc.pid_ns().clone(), proc.file_descriptors.clone())
+	};
+	let new_pid = {
 		if flags & CLONE_PARENT_SETTID != 0 {
 			// TODO
 			todo!();
@@ -104,6 +120,8 @@ pub fn clone(
 				share_sighand: flags & CLONE_SIGHAND != 0,
 
 				vfork: flags & CLONE_VFORK != 0,
+
+				new_namespaces,
 			},
 		)?;
 		let mut new_proc = new_mutex.lock();
@@ -132,12 +150,20 @@ pub fn clone(
 			// TODO
 			todo!();
 		}
-		new_proc.tid
+		if flags & CLONE_PIDFD != 0 {
+			let fds = fds.as_ref().ok_or_else(|| errno!(EINVAL))?;
+			let fd = create_pidfd(&new_proc, 0, fds)?;
+			if let Err(e) = parent_tid.copy_to_user(fd) {
+				let _ = fds.lock().close_fd(fd);
+				return Err(e);
+			}
+		}
+		new_proc.get_pid_in(&pid_ns)
 	};
 	if flags & CLONE_VFORK != 0 {
 		// Let another process run instead of the current. Because the current
 		// process must now wait for the child process to terminate or execute a program
 		scheduler::end_tick();
 	}
-	Ok(new_tid as _)
+	Ok(new_pid as _)
 }
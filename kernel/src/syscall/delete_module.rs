@@ -19,7 +19,10 @@
 //! The `delete_module` system call allows to unload a module from the kernel.
 
 use crate::{
-	file::{perm::AccessProfile, vfs::ResolutionSettings},
+	file::{
+		perm::{AccessProfile, CAP_SYS_MODULE},
+		vfs::ResolutionSettings,
+	},
 	module,
 	process::{mem_space::copy::SyscallString, Process},
 	syscall::Args,
@@ -36,7 +39,7 @@ pub fn delete_module(
 	Args((name, _flags)): Args<(SyscallString, c_uint)>,
 	ap: AccessProfile,
 ) -> EResult<usize> {
-	if !ap.is_privileged() {
+	if !ap.has_cap(CAP_SYS_MODULE) {
 		return Err(errno!(EPERM));
 	}
 	let name = name.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
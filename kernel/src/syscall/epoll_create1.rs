@@ -0,0 +1,59 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `epoll_create1` system call creates an epoll instance.
+
+use crate::{
+	file,
+	file::{
+		epoll::EventPoll,
+		fd::{FileDescriptorTable, FD_CLOEXEC},
+		File,
+	},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::Mutex,
+	ptr::arc::Arc,
+};
+
+/// Creates an epoll instance and returns its file descriptor.
+pub fn do_epoll_create1(flags: c_int, fds: &Mutex<FileDescriptorTable>) -> EResult<usize> {
+	if flags & !file::O_CLOEXEC != 0 {
+		return Err(errno!(EINVAL));
+	}
+	let epoll = Arc::new(EventPoll::new()?)?;
+	let file = File::open_floating(epoll, file::O_RDWR)?;
+	let fd_flags = if flags & file::O_CLOEXEC != 0 {
+		FD_CLOEXEC
+	} else {
+		0
+	};
+	let (fd_id, _) = fds.lock().create_fd(fd_flags, file)?;
+	Ok(fd_id as _)
+}
+
+pub fn epoll_create1(
+	Args(flags): Args<c_int>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_epoll_create1(flags, &fds)
+}
@@ -0,0 +1,88 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `epoll_ctl` system call adds, modifies or removes a file in the interest list of an epoll
+//! instance.
+
+use crate::{
+	file::{
+		epoll::{
+			EpollEvent, EventPoll, EPOLLEXCLUSIVE, EPOLLONESHOT, EPOLL_CTL_ADD, EPOLL_CTL_DEL,
+			EPOLL_CTL_MOD,
+		},
+		fd::FileDescriptorTable,
+		FileType,
+	},
+	process::mem_space::copy::SyscallPtr,
+	syscall::Args,
+};
+use core::{ffi::c_int, intrinsics::unlikely};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::Mutex,
+	ptr::arc::Arc,
+};
+
+pub fn epoll_ctl(
+	Args((epfd, op, fd, event)): Args<(c_int, c_int, c_int, SyscallPtr<EpollEvent>)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let (epoll_file, file) = {
+		let fds = fds.lock();
+		let epoll_file = fds.get_fd(epfd)?.get_file().clone();
+		let file = fds.get_fd(fd)?.get_file().clone();
+		(epoll_file, file)
+	};
+	let epoll = epoll_file
+		.get_buffer::<EventPoll>()
+		.ok_or_else(|| errno!(EINVAL))?;
+	// An instance cannot monitor itself
+	if unlikely(epoll_file.as_ptr() == file.as_ptr()) {
+		return Err(errno!(EINVAL));
+	}
+	// Regular files and directories are always ready, thus cannot be monitored
+	if file.vfs_entry.is_some()
+		&& matches!(file.get_type()?, FileType::Regular | FileType::Directory)
+	{
+		return Err(errno!(EPERM));
+	}
+	let event = if op != EPOLL_CTL_DEL {
+		let event = event.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+		let events = event.events;
+		// Exclusive wakeups can only be requested when adding a file, and never with one-shot
+		if events & EPOLLEXCLUSIVE != 0 && (op != EPOLL_CTL_ADD || events & EPOLLONESHOT != 0) {
+			return Err(errno!(EINVAL));
+		}
+		event
+	} else {
+		Default::default()
+	};
+	match op {
+		EPOLL_CTL_ADD => {
+			if let Some(target) = file.get_buffer::<EventPoll>() {
+				epoll.check_loop(&fds.lock(), target)?;
+			}
+			epoll.add(fd, &file, event)?;
+		}
+		EPOLL_CTL_MOD => epoll.modify(fd, event)?,
+		EPOLL_CTL_DEL => epoll.remove(fd)?,
+		_ => return Err(errno!(EINVAL)),
+	}
+	Ok(0)
+}
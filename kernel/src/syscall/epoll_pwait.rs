@@ -0,0 +1,72 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `epoll_pwait` is similar to `epoll_wait`, but atomically replaces the signal mask of the
+//! process while waiting.
+
+use super::epoll_wait::do_epoll_wait;
+use crate::{
+	file::{epoll::EpollEvent, fd::FileDescriptorTable},
+	process::{
+		mem_space::copy::{SyscallPtr, SyscallSlice},
+		signal::{SigSet, Signal},
+		Process,
+	},
+	syscall::Args,
+};
+use core::{ffi::c_int, intrinsics::unlikely, mem::size_of};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
+};
+
+#[allow(clippy::type_complexity)]
+pub fn epoll_pwait(
+	Args((epfd, events, maxevents, timeout, sigmask, sigsetsize)): Args<(
+		c_int,
+		SyscallSlice<EpollEvent>,
+		c_int,
+		c_int,
+		SyscallPtr<SigSet>,
+		usize,
+	)>,
+	proc: Arc<IntMutex<Process>>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let Some(mut mask) = sigmask.copy_from_user()? else {
+		return do_epoll_wait(&fds, epfd, events, maxevents, timeout);
+	};
+	if unlikely(sigsetsize != size_of::<SigSet>()) {
+		return Err(errno!(EINVAL));
+	}
+	mask.clear(Signal::SIGKILL.get_id() as _);
+	mask.clear(Signal::SIGSTOP.get_id() as _);
+	{
+		let mut proc = proc.lock();
+		proc.saved_sigmask = Some(proc.sigmask);
+		proc.sigmask = mask;
+	}
+	let res = do_epoll_wait(&fds, epfd, events, maxevents, timeout);
+	// If interrupted, the original mask is restored once the signal has been handled
+	if !matches!(&res, Err(e) if e.as_int() == errno::EINTR) {
+		proc.lock().restore_sigmask();
+	}
+	res
+}
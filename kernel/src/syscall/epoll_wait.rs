@@ -0,0 +1,76 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `epoll_wait` system call waits for events on the files monitored by an epoll instance.
+
+use crate::{
+	file::{
+		epoll::{EpollEvent, EventPoll},
+		fd::FileDescriptorTable,
+	},
+	process::mem_space::copy::SyscallSlice,
+	syscall::Args,
+	time::unit::Timestamp,
+};
+use core::{ffi::c_int, mem::size_of};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::Mutex,
+	ptr::arc::Arc,
+};
+
+/// Performs the `epoll_wait` operation.
+///
+/// Arguments:
+/// - `fds` is the file descriptors table of the process.
+/// - `epfd` is the file descriptor of the epoll instance.
+/// - `events` is the buffer to write events to.
+/// - `maxevents` is the capacity of `events`.
+/// - `timeout` is the timeout in milliseconds. If negative, the call blocks indefinitely.
+pub fn do_epoll_wait(
+	fds: &Mutex<FileDescriptorTable>,
+	epfd: c_int,
+	events: SyscallSlice<EpollEvent>,
+	maxevents: c_int,
+	timeout: c_int,
+) -> EResult<usize> {
+	if maxevents <= 0 || maxevents as usize > c_int::MAX as usize / size_of::<EpollEvent>() {
+		return Err(errno!(EINVAL));
+	}
+	let epoll_file = fds.lock().get_fd(epfd)?.get_file().clone();
+	let epoll = epoll_file
+		.get_buffer::<EventPoll>()
+		.ok_or_else(|| errno!(EINVAL))?;
+	let timeout = (timeout >= 0).then_some(timeout as Timestamp);
+	let res = epoll.wait(fds, maxevents as _, timeout)?;
+	events.copy_to_user(0, &res)?;
+	Ok(res.len())
+}
+
+pub fn epoll_wait(
+	Args((epfd, events, maxevents, timeout)): Args<(
+		c_int,
+		SyscallSlice<EpollEvent>,
+		c_int,
+		c_int,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_epoll_wait(&fds, epfd, events, maxevents, timeout)
+}
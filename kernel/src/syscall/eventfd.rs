@@ -0,0 +1,31 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `eventfd` system call creates an eventfd. It is the same as `eventfd2` without flags.
+
+use super::eventfd2::do_eventfd2;
+use crate::{file::fd::FileDescriptorTable, syscall::Args};
+use core::ffi::c_uint;
+use utils::{errno::EResult, lock::Mutex, ptr::arc::Arc};
+
+pub fn eventfd(
+	Args(initval): Args<c_uint>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_eventfd2(initval, 0, &fds)
+}
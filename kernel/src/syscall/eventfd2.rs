@@ -0,0 +1,63 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `eventfd2` system call creates an eventfd.
+
+use crate::{
+	file,
+	file::{
+		eventfd::{EventFd, EFD_SEMAPHORE},
+		fd::{FileDescriptorTable, FD_CLOEXEC},
+		File,
+	},
+	syscall::Args,
+};
+use core::ffi::{c_int, c_uint};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::Mutex,
+	ptr::arc::Arc,
+};
+
+/// Creates an eventfd with the initial value `initval` and returns its file descriptor.
+pub fn do_eventfd2(
+	initval: c_uint,
+	flags: c_int,
+	fds: &Mutex<FileDescriptorTable>,
+) -> EResult<usize> {
+	if flags & !(EFD_SEMAPHORE | file::O_CLOEXEC | file::O_NONBLOCK) != 0 {
+		return Err(errno!(EINVAL));
+	}
+	let eventfd = Arc::new(EventFd::new(initval, flags & EFD_SEMAPHORE != 0))?;
+	let file = File::open_floating(eventfd, file::O_RDWR | (flags & file::O_NONBLOCK))?;
+	let fd_flags = if flags & file::O_CLOEXEC != 0 {
+		FD_CLOEXEC
+	} else {
+		0
+	};
+	let (fd_id, _) = fds.lock().create_fd(fd_flags, file)?;
+	Ok(fd_id as _)
+}
+
+pub fn eventfd2(
+	Args((initval, flags)): Args<(c_uint, c_int)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_eventfd2(initval, flags, &fds)
+}
@@ -20,12 +20,14 @@
 
 use super::Args;
 use crate::{
-	file::{vfs, vfs::ResolutionSettings, File},
+	file::vfs::ResolutionSettings,
 	memory::stack,
+	process,
 	process::{
 		exec,
-		exec::{ExecInfo, ProgramImage},
+		exec::{BinPrm, ExecInfo},
 		mem_space::copy::{SyscallArray, SyscallString},
+		ptrace,
 		regs::Regs,
 		scheduler::SCHEDULER,
 		Process,
@@ -38,167 +40,66 @@ use utils::{
 		vec::Vec,
 	},
 	errno,
-	errno::{CollectResult, EResult, Errno},
+	errno::{CollectResult, EResult},
 	interrupt::cli,
-	lock::Mutex,
-	ptr::arc::Arc,
 };
 
-/// The maximum length of the shebang.
-const SHEBANG_MAX: usize = 256;
-/// The maximum number of interpreters that can be used recursively for an
-/// execution.
-const INTERP_MAX: usize = 4;
-
 // TODO Use ARG_MAX
 
-/// A buffer containing a shebang.
-struct ShebangBuffer {
-	/// The before to store the shebang read from file.
-	buf: [u8; SHEBANG_MAX],
-	/// The index of the end of the shebang in the buffer.
-	end: usize,
-}
-
-impl Default for ShebangBuffer {
-	fn default() -> Self {
-		Self {
-			buf: [0; SHEBANG_MAX],
-			end: 0,
-		}
-	}
-}
-
-/// Returns the file for the given `path`.
-///
-/// The function also parses and eventual shebang string and builds the resulting **argv**.
-///
-/// Arguments:
-/// - `path` is the path of the executable file.
-/// - `rs` is the resolution settings to be used to open files.
-/// - `argv` is an iterator over the arguments passed to the system call.
-fn get_file<A: Iterator<Item = EResult<String>>>(
-	path: &Path,
-	rs: &ResolutionSettings,
-	argv: A,
-) -> EResult<(Arc<vfs::Entry>, Vec<String>)> {
-	let mut shebangs: [ShebangBuffer; INTERP_MAX] = Default::default();
-	// Read and parse shebangs
-	let mut file = vfs::get_file_from_path(path, rs)?;
-	let mut i = 0;
-	loop {
-		let shebang = &mut shebangs[i];
-		// Read file
-		let len = {
-			// Check permission
-			let stat = file.stat()?;
-			if !rs.access_profile.can_execute_file(&stat) {
-				return Err(errno!(EACCES));
-			}
-			file.node()
-				.ops
-				.read_content(&file.node().location, 0, &mut shebang.buf)?
-		};
-		// Parse shebang
-		shebang.end = shebang.buf[..len]
-			.iter()
-			.position(|b| *b == b'\n')
-			.unwrap_or(len);
-		if !matches!(shebang.buf[..shebang.end], [b'#', b'!', _, ..]) {
-			break;
-		}
-		i += 1;
-		// If there is still an interpreter but the limit has been reached
-		if i >= INTERP_MAX {
-			return Err(errno!(ELOOP));
-		}
-		// Get interpreter path
-		let interp_end = shebang.buf[2..shebang.end]
-			.iter()
-			.position(|b| (*b as char).is_ascii_whitespace())
-			.unwrap_or(shebang.end);
-		let interp_path = Path::new(&shebang.buf[2..(2 + interp_end)])?;
-		// Read interpreter
-		file = vfs::get_file_from_path(interp_path, rs)?;
-	}
-	// Build arguments
-	let final_argv = shebangs[..i]
-		.iter()
-		.rev()
-		.enumerate()
-		.flat_map(|(i, shebang)| {
-			let mut words =
-				shebang.buf[2..shebang.end].split(|b| (*b as char).is_ascii_whitespace());
-			// Skip interpreters, except the first
-			if i > 0 {
-				words.next();
-			}
-			words
-		})
-		.map(|s| Ok(String::try_from(s)?))
-		.chain(argv)
-		.collect::<EResult<CollectResult<Vec<String>>>>()?
-		.0?;
-	Ok((file, final_argv))
-}
-
 /// Performs the execution on the current process.
 fn do_exec(
-	file: &vfs::Entry,
+	path: &Path,
 	rs: &ResolutionSettings,
 	argv: Vec<String>,
 	envp: Vec<String>,
 ) -> EResult<Regs> {
-	let program_image = build_image(file, rs, argv, envp)?;
+	let (rlimits, personality) = {
+		let proc = Process::current();
+		let proc = proc.lock();
+		(proc.rlimits.clone(), proc.personality)
+	};
+	let exec_info = ExecInfo {
+		path_resolution: rs,
+		argv,
+		envp,
+		rlimits,
+		personality,
+	};
+	let mut prm = BinPrm::new(path, exec_info)?;
+	let program_image = exec::build_image(&mut prm)?;
 	let proc_mutex = Process::current();
 	let mut proc = proc_mutex.lock();
 	// Execute the program
 	exec::exec(&mut proc, program_image)?;
+	proc.access_profile = prm.access_profile;
+	// A program that gained privileges cannot be dumped or traced by its former user
+	proc.dumpable = !prm.secure;
+	proc.personality = prm.info.personality;
 	Ok(proc.regs.clone())
 }
 
-/// Builds a program image.
-///
-/// Arguments:
-/// - `file` is the executable file
-/// - `path_resolution` is settings for path resolution
-/// - `argv` is the arguments list
-/// - `envp` is the environment variables list
-fn build_image(
-	file: &vfs::Entry,
-	path_resolution: &ResolutionSettings,
-	argv: Vec<String>,
-	envp: Vec<String>,
-) -> EResult<ProgramImage> {
-	let exec_info = ExecInfo {
-		path_resolution,
-		argv,
-		envp,
-	};
-	exec::build_image(file, exec_info)
-}
-
 pub fn execve(
 	Args((pathname, argv, envp)): Args<(SyscallString, SyscallArray, SyscallArray)>,
 	rs: ResolutionSettings,
 ) -> EResult<usize> {
-	let (file, argv, envp) = {
+	let (path, argv, envp) = {
 		let path = pathname.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
 		let path = PathBuf::try_from(path)?;
-		let argv = argv.iter();
-		let (file, argv) = get_file(&path, &rs, argv)?;
+		let argv = argv.iter().collect::<EResult<CollectResult<Vec<_>>>>()?.0?;
 		let envp = envp.iter().collect::<EResult<CollectResult<Vec<_>>>>()?.0?;
-		(file, argv, envp)
+		(path, argv, envp)
 	};
 	// Disable interrupt to prevent stack switching while using a temporary stack,
 	// preventing this temporary stack from being used as a signal handling stack
 	cli();
 	let tmp_stack = SCHEDULER.get().lock().get_tmp_stack();
-	let exec = move || {
-		let regs = do_exec(&file, &rs, argv, envp)?;
-		unsafe {
-			regs.switch(true);
-		}
-	};
-	unsafe { stack::switch(tmp_stack as _, exec) }
+	let exec = move || do_exec(&path, &rs, argv, envp);
+	let mut regs = unsafe { stack::switch(tmp_stack as _, exec) }?;
+	// The system call does not return to the syscall handler, so report the end of the system
+	// call to the tracer here
+	ptrace::syscall_exit(&mut regs);
+	process::yield_current(3, &mut regs);
+	unsafe {
+		regs.switch(true);
+	}
 }
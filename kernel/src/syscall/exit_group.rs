@@ -19,13 +19,16 @@
 //! The `exit_group` syscall allows to terminate every process in the current
 //! thread group.
 
-use crate::{process::Process, syscall::Args};
+use crate::{
+	process::{regs::Regs, Process},
+	syscall::Args,
+};
 use core::ffi::c_int;
 use utils::{
 	errno::{EResult, Errno},
 	lock::IntMutexGuard,
 };
 
-pub fn exit_group(Args(status): Args<c_int>) -> EResult<usize> {
-	super::_exit::do_exit(status as _, true);
+pub fn exit_group(Args(status): Args<c_int>, regs: &Regs) -> EResult<usize> {
+	super::_exit::do_exit(status as _, true, regs);
 }
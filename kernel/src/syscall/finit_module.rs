@@ -19,7 +19,10 @@
 //! The `finit_module` system call allows to load a module on the kernel.
 
 use crate::{
-	file::{fd::FileDescriptorTable, perm::AccessProfile},
+	file::{
+		fd::FileDescriptorTable,
+		perm::{AccessProfile, CAP_SYS_MODULE},
+	},
 	module,
 	module::Module,
 	process::{mem_space::copy::SyscallString, Process},
@@ -39,7 +42,7 @@ pub fn finit_module(
 	ap: AccessProfile,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
-	if !ap.is_privileged() {
+	if !ap.has_cap(CAP_SYS_MODULE) {
 		return Err(errno!(EPERM));
 	}
 	// Read file
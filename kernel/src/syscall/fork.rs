@@ -28,6 +28,8 @@ use utils::{
 };
 
 pub fn fork(proc: Arc<IntMutex<Process>>, regs: &Regs) -> EResult<usize> {
+	// The PID of the child is returned in the namespace of the parent
+	let pid_ns = proc.lock().pid_ns().clone();
 	let new_mutex = Process::fork(proc, ForkOptions::default())?;
 	let mut new_proc = new_mutex.lock();
 	// Set child's return value to `0`
@@ -35,5 +37,5 @@ pub fn fork(proc: Arc<IntMutex<Process>>, regs: &Regs) -> EResult<usize> {
 	regs.set_syscall_return(Ok(0));
 	new_proc.regs = regs;
 	// Set parent's return value to the child's PID
-	Ok(new_proc.get_pid() as _)
+	Ok(new_proc.get_pid_in(&pid_ns) as _)
 }
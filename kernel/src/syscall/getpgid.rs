@@ -25,17 +25,21 @@ use crate::{
 use utils::{
 	errno,
 	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
 };
 
-pub fn getpgid(Args(pid): Args<Pid>) -> EResult<usize> {
-	if pid == 0 {
-		let pgid = Process::current().lock().pgid;
-		Ok(pgid as _)
+pub fn getpgid(Args(pid): Args<Pid>, proc: Arc<IntMutex<Process>>) -> EResult<usize> {
+	let ns = proc.lock().pid_ns().clone();
+	let pgid = if pid == 0 {
+		proc.lock().pgid
 	} else {
-		let Some(proc) = Process::get_by_pid(pid) else {
+		let Some(proc) = Process::get_by_ns_pid(&ns, pid) else {
 			return Err(errno!(ESRCH));
 		};
 		let pgid = proc.lock().pgid;
-		Ok(pgid as _)
-	}
+		pgid
+	};
+	// The ID is returned in the namespace of the current process
+	Ok(ns.to_local(pgid).unwrap_or(0) as _)
 }
@@ -26,5 +26,5 @@ use utils::{
 };
 
 pub fn getpid(proc: Arc<IntMutex<Process>>) -> EResult<usize> {
-	Ok(proc.lock().get_pid() as _)
+	Ok(proc.lock().get_ns_pid() as _)
 }
@@ -26,5 +26,5 @@ use utils::{
 };
 
 pub fn getppid(proc: Arc<IntMutex<Process>>) -> EResult<usize> {
-	Ok(proc.lock().get_parent_pid() as _)
+	Ok(proc.lock().get_parent_ns_pid() as _)
 }
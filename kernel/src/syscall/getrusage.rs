@@ -33,15 +33,23 @@ use utils::{
 const RUSAGE_SELF: i32 = 0;
 /// Returns the resource usage of the process's children.
 const RUSAGE_CHILDREN: i32 = -1;
+/// Returns the resource usage of the calling thread.
+const RUSAGE_THREAD: i32 = 1;
 
 pub fn getrusage(Args((who, usage)): Args<(c_int, SyscallPtr<RUsage>)>) -> EResult<usize> {
-	let rusage = match who {
-		RUSAGE_SELF => Process::current().lock().get_rusage().clone(),
-		RUSAGE_CHILDREN => {
-			// TODO Return resources of terminated children
-			RUsage::default()
+	let rusage = {
+		let proc_mutex = Process::current();
+		let mut proc = proc_mutex.lock();
+		match who {
+			// Each process has a single thread
+			RUSAGE_SELF | RUSAGE_THREAD => {
+				proc.account_cpu(false);
+				proc.update_maxrss();
+				proc.get_rusage().clone()
+			}
+			RUSAGE_CHILDREN => proc.get_children_rusage().clone(),
+			_ => return Err(errno!(EINVAL)),
 		}
-		_ => return Err(errno!(EINVAL)),
 	};
 	usage.copy_to_user(rusage)?;
 	Ok(0)
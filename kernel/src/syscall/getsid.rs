@@ -0,0 +1,45 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `getsid` system call, which allows to get the session ID of a process.
+
+use crate::{
+	process::{pid::Pid, Process},
+	syscall::Args,
+};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn getsid(Args(pid): Args<Pid>, proc: Arc<IntMutex<Process>>) -> EResult<usize> {
+	let ns = proc.lock().pid_ns().clone();
+	let sid = if pid == 0 {
+		proc.lock().sid
+	} else {
+		let Some(proc) = Process::get_by_ns_pid(&ns, pid) else {
+			return Err(errno!(ESRCH));
+		};
+		let sid = proc.lock().sid;
+		sid
+	};
+	// The ID is returned in the namespace of the current process
+	Ok(ns.to_local(sid).unwrap_or(0) as _)
+}
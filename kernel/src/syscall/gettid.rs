@@ -26,5 +26,6 @@ use utils::{
 };
 
 pub fn gettid(proc: Arc<IntMutex<Process>>) -> EResult<usize> {
-	Ok(proc.lock().tid as _)
+	// Threads are not supported, thus the thread ID is the PID
+	Ok(proc.lock().get_ns_pid() as _)
 }
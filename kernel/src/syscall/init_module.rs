@@ -19,7 +19,7 @@
 //! The `init_module` system call allows to load a module on the kernel.
 
 use crate::{
-	file::perm::AccessProfile,
+	file::perm::{AccessProfile, CAP_SYS_MODULE},
 	module,
 	module::Module,
 	process::{
@@ -38,7 +38,7 @@ pub fn init_module(
 	Args((module_image, len, _param_values)): Args<(SyscallSlice<u8>, c_ulong, SyscallString)>,
 	ap: AccessProfile,
 ) -> EResult<usize> {
-	if !ap.is_privileged() {
+	if !ap.has_cap(CAP_SYS_MODULE) {
 		return Err(errno!(EPERM));
 	}
 	let image = module_image
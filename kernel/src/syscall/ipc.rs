@@ -0,0 +1,185 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `ipc` system call is the common entry point for System V IPC operations.
+//!
+//! Each operation is also available as a dedicated system call.
+
+use super::{
+	msgctl::msgctl, msgget::msgget, msgrcv::msgrcv, msgsnd::msgsnd, semctl::do_semctl,
+	semget::semget, semtimedop_time64::do_semtimedop, shmat::do_shmat, shmctl::shmctl,
+	shmdt::shmdt, shmget::shmget,
+};
+use crate::{
+	file::perm::AccessProfile,
+	memory::VirtAddr,
+	process::{
+		mem_space::{
+			copy::{SyscallPtr, SyscallSlice},
+			MemSpace,
+		},
+		Process,
+	},
+	syscall::{Args, FromSyscallArg},
+	time::unit::{TimeUnit, Timespec32},
+};
+use core::ffi::{c_int, c_long};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+/// Operation: `semop`.
+const SEMOP: c_int = 1;
+/// Operation: `semget`.
+const SEMGET: c_int = 2;
+/// Operation: `semctl`.
+const SEMCTL: c_int = 3;
+/// Operation: `semtimedop`.
+const SEMTIMEDOP: c_int = 4;
+/// Operation: `msgsnd`.
+const MSGSND: c_int = 11;
+/// Operation: `msgrcv`.
+const MSGRCV: c_int = 12;
+/// Operation: `msgget`.
+const MSGGET: c_int = 13;
+/// Operation: `msgctl`.
+const MSGCTL: c_int = 14;
+/// Operation: `shmat`.
+const SHMAT: c_int = 21;
+/// Operation: `shmdt`.
+const SHMDT: c_int = 22;
+/// Operation: `shmget`.
+const SHMGET: c_int = 23;
+/// Operation: `shmctl`.
+const SHMCTL: c_int = 24;
+
+/// The arguments of the first version of the `msgrcv` operation, which do not fit in registers.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct IpcKludge {
+	/// The buffer to receive the message into.
+	msgp: usize,
+	/// The type of the message to receive.
+	msgtyp: c_long,
+}
+
+pub fn ipc(
+	Args((call, first, second, third, ptr, fifth)): Args<(
+		c_int,
+		c_int,
+		c_int,
+		c_int,
+		usize,
+		usize,
+	)>,
+	proc: Arc<IntMutex<Process>>,
+	ap: AccessProfile,
+	mem_space: Arc<IntMutex<MemSpace>>,
+) -> EResult<usize> {
+	// The higher bits hold the version of the operation's calling convention
+	let version = call >> 16;
+	match call & 0xffff {
+		SEMOP => do_semtimedop(
+			first,
+			SyscallSlice::from_syscall_arg(ptr),
+			second as _,
+			None,
+			&proc,
+			&ap,
+		),
+		SEMGET => semget(Args((first, second, third)), proc, ap),
+		SEMCTL => {
+			// The `semun` argument is passed by pointer
+			let arg = SyscallPtr::<usize>::from_syscall_arg(ptr)
+				.copy_from_user()?
+				.ok_or_else(|| errno!(EINVAL))?;
+			do_semctl(first, second, third, arg, &proc, &ap)
+		}
+		SEMTIMEDOP => {
+			let timeout = SyscallPtr::<Timespec32>::from_syscall_arg(fifth)
+				.copy_from_user()?
+				.map(|ts| ts.to_nano());
+			do_semtimedop(
+				first,
+				SyscallSlice::from_syscall_arg(ptr),
+				second as _,
+				timeout,
+				&proc,
+				&ap,
+			)
+		}
+		MSGSND => msgsnd(
+			Args((
+				first,
+				SyscallSlice::from_syscall_arg(ptr),
+				second as _,
+				third,
+			)),
+			proc,
+			ap,
+		),
+		MSGRCV => {
+			let (msgp, msgtyp) = if version == 0 {
+				let kludge = SyscallPtr::<IpcKludge>::from_syscall_arg(ptr)
+					.copy_from_user()?
+					.ok_or_else(|| errno!(EINVAL))?;
+				(kludge.msgp, kludge.msgtyp)
+			} else {
+				(ptr, fifth as _)
+			};
+			msgrcv(
+				Args((
+					first,
+					SyscallSlice::from_syscall_arg(msgp),
+					second as _,
+					msgtyp,
+					third,
+				)),
+				proc,
+				ap,
+			)
+		}
+		MSGGET => msgget(Args((first, second)), proc, ap),
+		MSGCTL => msgctl(
+			Args((first, second, SyscallPtr::from_syscall_arg(ptr))),
+			proc,
+			ap,
+		),
+		SHMAT => {
+			// The iBCS2 calling convention is not supported
+			if version == 1 {
+				return Err(errno!(EINVAL));
+			}
+			let addr = do_shmat(first, VirtAddr(ptr), second, &proc, &ap, &mem_space)?;
+			// The address is returned through `third`
+			SyscallPtr::<usize>::from_syscall_arg(third as _).copy_to_user(addr.0)?;
+			Ok(0)
+		}
+		SHMDT => shmdt(Args(VirtAddr(ptr)), proc, mem_space),
+		SHMGET => shmget(Args((first, second as _, third)), proc, ap),
+		SHMCTL => shmctl(
+			Args((first, second, SyscallPtr::from_syscall_arg(ptr))),
+			proc,
+			ap,
+		),
+		_ => Err(errno!(ENOSYS)),
+	}
+}
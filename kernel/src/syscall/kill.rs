@@ -21,7 +21,13 @@
 use super::{util, Args};
 use crate::{
 	process,
-	process::{pid::Pid, regs::Regs, scheduler::SCHEDULER, signal::Signal, Process, State},
+	process::{
+		pid::Pid,
+		regs::Regs,
+		scheduler::SCHEDULER,
+		signal::{SigInfo, Signal, SI_USER},
+		Process, State,
+	},
 };
 use core::ffi::c_int;
 use utils::{
@@ -38,6 +44,7 @@ fn try_kill(pid: Pid, sig: Option<Signal>) -> EResult<()> {
 	let proc_mutex = Process::current();
 	let mut proc = proc_mutex.lock();
 	let ap = proc.access_profile;
+	let sender = proc.get_pid();
 	// Closure sending the signal
 	let f = |target: &mut Process| {
 		if matches!(target.get_state(), State::Zombie) {
@@ -47,7 +54,7 @@ fn try_kill(pid: Pid, sig: Option<Signal>) -> EResult<()> {
 			return Err(errno!(EPERM));
 		}
 		if let Some(sig) = sig {
-			target.kill(sig);
+			target.kill_info(sig, SigInfo::user(sig, SI_USER, sender, ap.uid));
 		}
 		Ok(())
 	};
@@ -76,8 +83,11 @@ fn try_kill_group(pid: i32, sig: Option<Signal>) -> EResult<()> {
 			let proc = proc_mutex.lock();
 			proc.pgid
 		}
-		i if i < 0 => -pid as Pid,
-		_ => pid as Pid,
+		i => {
+			let ns = Process::current().lock().pid_ns().clone();
+			ns.to_global(i.unsigned_abs() as _)
+				.ok_or_else(|| errno!(ESRCH))?
+		}
 	};
 	// Kill process group
 	Process::get_by_pid(pgid)
@@ -100,14 +110,21 @@ fn try_kill_group(pid: i32, sig: Option<Signal>) -> EResult<()> {
 fn send_signal(pid: i32, sig: Option<Signal>) -> EResult<()> {
 	match pid {
 		// Kill the process with the given PID
-		1.. => try_kill(pid as _, sig),
+		1.. => {
+			let ns = Process::current().lock().pid_ns().clone();
+			let pid = ns.to_global(pid as _).ok_or_else(|| errno!(ESRCH))?;
+			try_kill(pid, sig)
+		}
 		// Kill all processes in the current process group
 		0 => try_kill_group(0, sig),
 		// Kill all processes for which the current process has the permission
 		-1 => {
+			let ns = Process::current().lock().pid_ns().clone();
 			let sched = SCHEDULER.get().lock();
-			for (pid, _) in sched.iter_process() {
-				if *pid == process::pid::INIT_PID {
+			for (pid, proc) in sched.iter_process() {
+				// Only processes visible from the namespace, except its init process
+				let ns_pid = proc.lock().get_pid_in(&ns);
+				if ns_pid == 0 || ns_pid == process::pid::INIT_PID {
 					continue;
 				}
 				// TODO Check permission
@@ -21,7 +21,7 @@
 use crate::{
 	device::id,
 	file,
-	file::{vfs, vfs::ResolutionSettings, FileType, Stat},
+	file::{perm::CAP_MKNOD, vfs, vfs::ResolutionSettings, FileType, Stat},
 	process::{mem_space::copy::SyscallString, Process},
 	syscall::{Args, Umask},
 	time::{
@@ -50,7 +50,7 @@ pub fn mknod(
 	// Check file type and permissions
 	let mode = mode & !umask.0;
 	let file_type = FileType::from_mode(mode).ok_or(errno!(EPERM))?;
-	let privileged = rs.access_profile.is_privileged();
+	let privileged = rs.access_profile.has_cap(CAP_MKNOD);
 	match (file_type, privileged) {
 		(FileType::Regular | FileType::Fifo | FileType::Socket, _) => {}
 		(FileType::BlockDevice | FileType::CharDevice, true) => {}
@@ -27,16 +27,20 @@ mod _exit;
 mod _llseek;
 mod _newselect;
 mod access;
+mod alarm;
 mod arch_prctl;
 mod bind;
 mod r#break;
 mod brk;
+mod capget;
+mod capset;
 mod chdir;
 mod chmod;
 mod chown;
 mod chroot;
 mod clock_gettime;
 mod clock_gettime64;
+mod clock_nanosleep;
 mod clone;
 mod close;
 mod connect;
@@ -44,6 +48,13 @@ mod creat;
 mod delete_module;
 mod dup;
 mod dup2;
+mod epoll_create;
+mod epoll_create1;
+mod epoll_ctl;
+mod epoll_pwait;
+mod epoll_wait;
+mod eventfd;
+mod eventfd2;
 mod execve;
 mod exit_group;
 mod faccessat;
@@ -66,19 +77,25 @@ mod getdents64;
 mod getegid;
 mod geteuid;
 mod getgid;
+mod getgroups;
+mod getgroups32;
+mod getitimer;
 mod getpgid;
 mod getpid;
 mod getppid;
 mod getrandom;
 mod getresgid;
 mod getresuid;
+mod getrlimit;
 mod getrusage;
+mod getsid;
 mod getsockname;
 mod getsockopt;
 mod gettid;
 mod getuid;
 mod init_module;
 pub mod ioctl;
+mod ipc;
 mod kill;
 mod lchown;
 mod link;
@@ -90,18 +107,36 @@ mod mmap;
 mod mmap2;
 mod mount;
 mod mprotect;
+mod mq_getsetattr;
+mod mq_notify;
+mod mq_open;
+mod mq_timedreceive;
+mod mq_timedreceive_time64;
+mod mq_timedsend;
+mod mq_timedsend_time64;
+mod mq_unlink;
+mod msgctl;
+mod msgget;
+mod msgrcv;
+mod msgsnd;
 mod msync;
 mod munmap;
 mod nanosleep;
 mod open;
 mod openat;
+mod personality;
+mod pidfd_getfd;
+mod pidfd_open;
+mod pidfd_send_signal;
 mod pipe;
 mod pipe2;
 pub mod poll;
+mod prctl;
 mod preadv;
 mod preadv2;
 mod prlimit64;
 mod pselect6;
+mod ptrace;
 mod pwritev;
 mod pwritev2;
 mod read;
@@ -110,25 +145,47 @@ mod readv;
 mod reboot;
 mod rename;
 mod renameat2;
+pub mod restart_syscall;
 mod rmdir;
 mod rt_sigaction;
+mod rt_sigpending;
 mod rt_sigprocmask;
+mod rt_sigqueueinfo;
+mod rt_sigsuspend;
+mod rt_sigtimedwait;
 mod sched_yield;
+mod seccomp;
 mod select;
+mod semctl;
+mod semget;
+mod semtimedop_time64;
 mod sendto;
 mod set_thread_area;
 mod set_tid_address;
 mod setgid;
+mod setgroups;
+mod setgroups32;
 mod sethostname;
+mod setitimer;
+mod setns;
 mod setpgid;
 mod setregid;
 mod setresgid;
 mod setresuid;
 mod setreuid;
+mod setrlimit;
+mod setsid;
 mod setsockopt;
 mod setuid;
+mod shmat;
+mod shmctl;
+mod shmdt;
+mod shmget;
 mod shutdown;
+mod sigaltstack;
 mod signal;
+mod signalfd;
+mod signalfd4;
 mod sigreturn;
 mod socket;
 mod socketpair;
@@ -141,19 +198,29 @@ mod syncfs;
 mod time;
 mod timer_create;
 mod timer_delete;
+mod timer_getoverrun;
+mod timer_gettime;
 mod timer_settime;
+mod timerfd_create;
+mod timerfd_gettime;
+mod timerfd_settime;
+mod times;
 mod tkill;
 mod truncate;
+mod ugetrlimit;
 mod umask;
 mod umount;
 mod uname;
 mod unlink;
 mod unlinkat;
+mod unshare;
 mod util;
 mod utimensat;
 mod vfork;
+mod vhangup;
 mod wait;
 mod wait4;
+mod waitid;
 mod waitpid;
 mod write;
 mod writev;
@@ -163,21 +230,30 @@ use crate::{
 	file,
 	file::{fd::FileDescriptorTable, perm::AccessProfile, vfs::ResolutionSettings},
 	process,
-	process::{mem_space::MemSpace, regs::Regs, signal::Signal, Process},
+	process::{
+		mem_space::MemSpace,
+		regs::Regs,
+		signal::{Signal, SignalHandler, SA_RESTART},
+		Process,
+	},
 };
 use _exit::_exit;
 use _llseek::_llseek;
 use _newselect::_newselect;
 use access::access;
+use alarm::alarm;
 use arch_prctl::arch_prctl;
 use bind::bind;
 use brk::brk;
+use capget::capget;
+use capset::capset;
 use chdir::chdir;
 use chmod::chmod;
 use chown::chown;
 use chroot::chroot;
 use clock_gettime::clock_gettime;
 use clock_gettime64::clock_gettime64;
+use clock_nanosleep::clock_nanosleep;
 use clone::clone;
 use close::close;
 use connect::connect;
@@ -186,6 +262,13 @@ use creat::creat;
 use delete_module::delete_module;
 use dup::dup;
 use dup2::dup2;
+use epoll_create::epoll_create;
+use epoll_create1::epoll_create1;
+use epoll_ctl::epoll_ctl;
+use epoll_pwait::epoll_pwait;
+use epoll_wait::epoll_wait;
+use eventfd::eventfd;
+use eventfd2::eventfd2;
 use execve::execve;
 use exit_group::exit_group;
 use faccessat::faccessat;
@@ -208,19 +291,25 @@ use getdents64::getdents64;
 use getegid::getegid;
 use geteuid::geteuid;
 use getgid::getgid;
+use getgroups::getgroups;
+use getgroups32::getgroups32;
+use getitimer::getitimer;
 use getpgid::getpgid;
 use getpid::getpid;
 use getppid::getppid;
 use getrandom::getrandom;
 use getresgid::getresgid;
 use getresuid::getresuid;
+use getrlimit::getrlimit;
 use getrusage::getrusage;
+use getsid::getsid;
 use getsockname::getsockname;
 use getsockopt::getsockopt;
 use gettid::gettid;
 use getuid::getuid;
 use init_module::init_module;
 use ioctl::ioctl;
+use ipc::ipc;
 use kill::kill;
 use lchown::lchown;
 use link::link;
@@ -232,18 +321,36 @@ use mmap::mmap;
 use mmap2::mmap2;
 use mount::mount;
 use mprotect::mprotect;
+use mq_getsetattr::mq_getsetattr;
+use mq_notify::mq_notify;
+use mq_open::mq_open;
+use mq_timedreceive::mq_timedreceive;
+use mq_timedreceive_time64::mq_timedreceive_time64;
+use mq_timedsend::mq_timedsend;
+use mq_timedsend_time64::mq_timedsend_time64;
+use mq_unlink::mq_unlink;
+use msgctl::msgctl;
+use msgget::msgget;
+use msgrcv::msgrcv;
+use msgsnd::msgsnd;
 use msync::msync;
 use munmap::munmap;
 use nanosleep::nanosleep;
 use open::open;
 use openat::openat;
+use personality::personality;
+use pidfd_getfd::pidfd_getfd;
+use pidfd_open::pidfd_open;
+use pidfd_send_signal::pidfd_send_signal;
 use pipe::pipe;
 use pipe2::pipe2;
 use poll::poll;
+use prctl::prctl;
 use preadv::preadv;
 use preadv2::preadv2;
 use prlimit64::prlimit64;
 use pselect6::pselect6;
+use ptrace::ptrace;
 use pwritev::pwritev;
 use pwritev2::pwritev2;
 use r#break::r#break;
@@ -253,25 +360,47 @@ use readv::readv;
 use reboot::reboot;
 use rename::rename;
 use renameat2::renameat2;
+use restart_syscall::restart_syscall;
 use rmdir::rmdir;
 use rt_sigaction::rt_sigaction;
+use rt_sigpending::rt_sigpending;
 use rt_sigprocmask::rt_sigprocmask;
+use rt_sigqueueinfo::rt_sigqueueinfo;
+use rt_sigsuspend::rt_sigsuspend;
+use rt_sigtimedwait::rt_sigtimedwait;
 use sched_yield::sched_yield;
+use seccomp::seccomp;
 use select::select;
+use semctl::semctl;
+use semget::semget;
+use semtimedop_time64::semtimedop_time64;
 use sendto::sendto;
 use set_thread_area::set_thread_area;
 use set_tid_address::set_tid_address;
 use setgid::setgid;
+use setgroups::setgroups;
+use setgroups32::setgroups32;
 use sethostname::sethostname;
+use setitimer::setitimer;
+use setns::setns;
 use setpgid::setpgid;
 use setregid::setregid;
 use setresgid::setresgid;
 use setresuid::setresuid;
 use setreuid::setreuid;
+use setrlimit::setrlimit;
+use setsid::setsid;
 use setsockopt::setsockopt;
 use setuid::setuid;
+use shmat::shmat;
+use shmctl::shmctl;
+use shmdt::shmdt;
+use shmget::shmget;
 use shutdown::shutdown;
+use sigaltstack::sigaltstack;
 use signal::signal;
+use signalfd::signalfd;
+use signalfd4::signalfd4;
 use sigreturn::sigreturn;
 use socket::socket;
 use socketpair::socketpair;
@@ -284,26 +413,39 @@ use syncfs::syncfs;
 use time::time;
 use timer_create::timer_create;
 use timer_delete::timer_delete;
+use timer_getoverrun::timer_getoverrun;
+use timer_gettime::timer_gettime;
 use timer_settime::timer_settime;
+use timerfd_create::timerfd_create;
+use timerfd_gettime::timerfd_gettime;
+use timerfd_settime::timerfd_settime;
+use times::times;
 use tkill::tkill;
 use truncate::truncate;
+use ugetrlimit::ugetrlimit;
 use umask::umask;
 use umount::umount;
 use uname::uname;
 use unlink::unlink;
 use unlinkat::unlinkat;
+use unshare::unshare;
 use utils::{
+	errno,
 	errno::EResult,
 	lock::{IntMutex, Mutex},
 	ptr::arc::Arc,
 };
 use utimensat::utimensat;
 use vfork::vfork;
+use vhangup::vhangup;
 use wait4::wait4;
+use waitid::waitid;
 use waitpid::waitpid;
 use write::write;
 use writev::writev;
 
+/// The ID of the `restart_syscall` system call.
+const RESTART_SYSCALL_ID: usize = 0x000;
 /// The ID of the `sigreturn` system call, for use by the signal trampoline.
 pub const SIGRETURN_ID: usize = 0x077;
 
@@ -508,6 +650,7 @@ macro_rules! syscall {
 #[inline]
 fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 	match id {
+		RESTART_SYSCALL_ID => Some(syscall!(restart_syscall, regs)),
 		0x001 => Some(syscall!(_exit, regs)),
 		0x002 => Some(syscall!(fork, regs)),
 		0x003 => Some(syscall!(read, regs)),
@@ -533,8 +676,8 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		0x017 => Some(syscall!(setuid, regs)),
 		0x018 => Some(syscall!(getuid, regs)),
 		// TODO 0x019 => Some(syscall!(stime, regs)),
-		// TODO 0x01a => Some(syscall!(ptrace, regs)),
-		// TODO 0x01b => Some(syscall!(alarm, regs)),
+		0x01a => Some(syscall!(ptrace, regs)),
+		0x01b => Some(syscall!(alarm, regs)),
 		// TODO 0x01c => Some(syscall!(oldfstat, regs)),
 		// TODO 0x01d => Some(syscall!(pause, regs)),
 		// TODO 0x01e => Some(syscall!(utime, regs)),
@@ -550,7 +693,7 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		0x028 => Some(syscall!(rmdir, regs)),
 		0x029 => Some(syscall!(dup, regs)),
 		0x02a => Some(syscall!(pipe, regs)),
-		// TODO 0x02b => Some(syscall!(times, regs)),
+		0x02b => Some(syscall!(times, regs)),
 		// TODO 0x02c => Some(syscall!(prof, regs)),
 		0x02d => Some(syscall!(brk, regs)),
 		0x02e => Some(syscall!(setgid, regs)),
@@ -573,7 +716,7 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		0x03f => Some(syscall!(dup2, regs)),
 		0x040 => Some(syscall!(getppid, regs)),
 		// TODO 0x041 => Some(syscall!(getpgrp, regs)),
-		// TODO 0x042 => Some(syscall!(setsid, regs)),
+		0x042 => Some(syscall!(setsid, regs)),
 		// TODO 0x043 => Some(syscall!(sigaction, regs)),
 		// TODO 0x044 => Some(syscall!(sgetmask, regs)),
 		// TODO 0x045 => Some(syscall!(ssetmask, regs)),
@@ -582,13 +725,13 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		// TODO 0x048 => Some(syscall!(sigsuspend, regs)),
 		// TODO 0x049 => Some(syscall!(sigpending, regs)),
 		0x04a => Some(syscall!(sethostname, regs)),
-		// TODO 0x04b => Some(syscall!(setrlimit, regs)),
-		// TODO 0x04c => Some(syscall!(getrlimit, regs)),
+		0x04b => Some(syscall!(setrlimit, regs)),
+		0x04c => Some(syscall!(getrlimit, regs)),
 		0x04d => Some(syscall!(getrusage, regs)),
 		// TODO 0x04e => Some(syscall!(gettimeofday, regs)),
 		// TODO 0x04f => Some(syscall!(settimeofday, regs)),
-		// TODO 0x050 => Some(syscall!(getgroups, regs)),
-		// TODO 0x051 => Some(syscall!(setgroups, regs)),
+		0x050 => Some(syscall!(getgroups, regs)),
+		0x051 => Some(syscall!(setgroups, regs)),
 		0x052 => Some(syscall!(select, regs)),
 		0x053 => Some(syscall!(symlink, regs)),
 		// TODO 0x054 => Some(syscall!(oldlstat, regs)),
@@ -611,20 +754,20 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		// TODO 0x065 => Some(syscall!(ioperm, regs)),
 		// TODO 0x066 => Some(syscall!(socketcall, regs)),
 		// TODO 0x067 => Some(syscall!(syslog, regs)),
-		// TODO 0x068 => Some(syscall!(setitimer, regs)),
-		// TODO 0x069 => Some(syscall!(getitimer, regs)),
+		0x068 => Some(syscall!(setitimer, regs)),
+		0x069 => Some(syscall!(getitimer, regs)),
 		// TODO 0x06a => Some(syscall!(stat, regs)),
 		// TODO 0x06b => Some(syscall!(lstat, regs)),
 		// TODO 0x06c => Some(syscall!(fstat, regs)),
 		// TODO 0x06d => Some(syscall!(olduname, regs)),
 		// TODO 0x06e => Some(syscall!(iopl, regs)),
-		// TODO 0x06f => Some(syscall!(vhangup, regs)),
+		0x06f => Some(syscall!(vhangup, regs)),
 		// TODO 0x070 => Some(syscall!(idle, regs)),
 		// TODO 0x071 => Some(syscall!(vm86old, regs)),
 		0x072 => Some(syscall!(wait4, regs)),
 		// TODO 0x073 => Some(syscall!(swapoff, regs)),
 		// TODO 0x074 => Some(syscall!(sysinfo, regs)),
-		// TODO 0x075 => Some(syscall!(ipc, regs)),
+		0x075 => Some(syscall!(ipc, regs)),
 		0x076 => Some(syscall!(fsync, regs)),
 		SIGRETURN_ID => Some(syscall!(sigreturn, regs)),
 		0x078 => Some(syscall!(clone, regs)),
@@ -641,7 +784,7 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		0x085 => Some(syscall!(fchdir, regs)),
 		// TODO 0x086 => Some(syscall!(bdflush, regs)),
 		// TODO 0x087 => Some(syscall!(sysfs, regs)),
-		// TODO 0x088 => Some(syscall!(personality, regs)),
+		0x088 => Some(syscall!(personality, regs)),
 		// TODO 0x089 => Some(syscall!(afs_syscall, regs)),
 		// TODO 0x08a => Some(syscall!(setfsuid, regs)),
 		// TODO 0x08b => Some(syscall!(setfsgid, regs)),
@@ -652,7 +795,7 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		0x090 => Some(syscall!(msync, regs)),
 		0x091 => Some(syscall!(readv, regs)),
 		0x092 => Some(syscall!(writev, regs)),
-		// TODO 0x093 => Some(syscall!(getsid, regs)),
+		0x093 => Some(syscall!(getsid, regs)),
 		// TODO 0x094 => Some(syscall!(fdatasync, regs)),
 		// TODO 0x095 => Some(syscall!(_sysctl, regs)),
 		// TODO 0x096 => Some(syscall!(mlock, regs)),
@@ -677,26 +820,26 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		// TODO 0x0a9 => Some(syscall!(nfsservctl, regs)),
 		0x0aa => Some(syscall!(setresgid, regs)),
 		0x0ab => Some(syscall!(getresgid, regs)),
-		// TODO 0x0ac => Some(syscall!(prctl, regs)),
+		0x0ac => Some(syscall!(prctl, regs)),
 		// TODO 0x0ad => Some(syscall!(rt_sigreturn, regs)),
 		0x0ae => Some(syscall!(rt_sigaction, regs)),
 		0x0af => Some(syscall!(rt_sigprocmask, regs)),
-		// TODO 0x0b0 => Some(syscall!(rt_sigpending, regs)),
-		// TODO 0x0b1 => Some(syscall!(rt_sigtimedwait, regs)),
-		// TODO 0x0b2 => Some(syscall!(rt_sigqueueinfo, regs)),
-		// TODO 0x0b3 => Some(syscall!(rt_sigsuspend, regs)),
+		0x0b0 => Some(syscall!(rt_sigpending, regs)),
+		0x0b1 => Some(syscall!(rt_sigtimedwait, regs)),
+		0x0b2 => Some(syscall!(rt_sigqueueinfo, regs)),
+		0x0b3 => Some(syscall!(rt_sigsuspend, regs)),
 		// TODO 0x0b4 => Some(syscall!(pread64, regs)),
 		// TODO 0x0b5 => Some(syscall!(pwrite64, regs)),
 		0x0b6 => Some(syscall!(chown, regs)),
 		0x0b7 => Some(syscall!(getcwd, regs)),
-		// TODO 0x0b8 => Some(syscall!(capget, regs)),
-		// TODO 0x0b9 => Some(syscall!(capset, regs)),
-		// TODO 0x0ba => Some(syscall!(sigaltstack, regs)),
+		0x0b8 => Some(syscall!(capget, regs)),
+		0x0b9 => Some(syscall!(capset, regs)),
+		0x0ba => Some(syscall!(sigaltstack, regs)),
 		// TODO 0x0bb => Some(syscall!(sendfile, regs)),
 		// TODO 0x0bc => Some(syscall!(getpmsg, regs)),
 		// TODO 0x0bd => Some(syscall!(putpmsg, regs)),
 		0x0be => Some(syscall!(vfork, regs)),
-		// TODO 0x0bf => Some(syscall!(ugetrlimit, regs)),
+		0x0bf => Some(syscall!(ugetrlimit, regs)),
 		0x0c0 => Some(syscall!(mmap2, regs)),
 		// TODO 0x0c1 => Some(syscall!(truncate64, regs)),
 		// TODO 0x0c2 => Some(syscall!(ftruncate64, regs)),
@@ -710,8 +853,8 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		0x0ca => Some(syscall!(getegid, regs)),  // getegid32
 		0x0cb => Some(syscall!(setreuid, regs)), // setreuid32
 		0x0cc => Some(syscall!(setregid, regs)), // setregid32
-		// TODO 0x0cd => Some(syscall!(getgroups32, regs)),
-		// TODO 0x0ce => Some(syscall!(setgroups32, regs)),
+		0x0cd => Some(syscall!(getgroups32, regs)),
+		0x0ce => Some(syscall!(setgroups32, regs)),
 		// TODO 0x0cf => Some(syscall!(fchown32, regs)),
 		0x0d0 => Some(syscall!(setresuid, regs)), // setresuid32
 		0x0d1 => Some(syscall!(getresuid, regs)), // getresuid32
@@ -756,20 +899,20 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		// TODO 0x0fa => Some(syscall!(fadvise64, regs)),
 		0x0fc => Some(syscall!(exit_group, regs)),
 		// TODO 0x0fd => Some(syscall!(lookup_dcookie, regs)),
-		// TODO 0x0fe => Some(syscall!(epoll_create, regs)),
-		// TODO 0x0ff => Some(syscall!(epoll_ctl, regs)),
-		// TODO 0x100 => Some(syscall!(epoll_wait, regs)),
+		0x0fe => Some(syscall!(epoll_create, regs)),
+		0x0ff => Some(syscall!(epoll_ctl, regs)),
+		0x100 => Some(syscall!(epoll_wait, regs)),
 		// TODO 0x101 => Some(syscall!(remap_file_pages, regs)),
 		0x102 => Some(syscall!(set_tid_address, regs)),
 		0x103 => Some(syscall!(timer_create, regs)),
 		0x104 => Some(syscall!(timer_settime, regs)),
-		// TODO 0x105 => Some(syscall!(timer_gettime, regs)),
-		// TODO 0x106 => Some(syscall!(timer_getoverrun, regs)),
+		0x105 => Some(syscall!(timer_gettime, regs)),
+		0x106 => Some(syscall!(timer_getoverrun, regs)),
 		0x107 => Some(syscall!(timer_delete, regs)),
 		// TODO 0x108 => Some(syscall!(clock_settime, regs)),
 		0x109 => Some(syscall!(clock_gettime, regs)),
 		// TODO 0x10a => Some(syscall!(clock_getres, regs)),
-		// TODO 0x10b => Some(syscall!(clock_nanosleep, regs)),
+		0x10b => Some(syscall!(clock_nanosleep, regs)),
 		0x10c => Some(syscall!(statfs64, regs)),
 		0x10d => Some(syscall!(fstatfs64, regs)),
 		// TODO 0x10e => Some(syscall!(tgkill, regs)),
@@ -779,14 +922,14 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		// TODO 0x112 => Some(syscall!(mbind, regs)),
 		// TODO 0x113 => Some(syscall!(get_mempolicy, regs)),
 		// TODO 0x114 => Some(syscall!(set_mempolicy, regs)),
-		// TODO 0x115 => Some(syscall!(mq_open, regs)),
-		// TODO 0x116 => Some(syscall!(mq_unlink, regs)),
-		// TODO 0x117 => Some(syscall!(mq_timedsend, regs)),
-		// TODO 0x118 => Some(syscall!(mq_timedreceive, regs)),
-		// TODO 0x119 => Some(syscall!(mq_notify, regs)),
-		// TODO 0x11a => Some(syscall!(mq_getsetattr, regs)),
+		0x115 => Some(syscall!(mq_open, regs)),
+		0x116 => Some(syscall!(mq_unlink, regs)),
+		0x117 => Some(syscall!(mq_timedsend, regs)),
+		0x118 => Some(syscall!(mq_timedreceive, regs)),
+		0x119 => Some(syscall!(mq_notify, regs)),
+		0x11a => Some(syscall!(mq_getsetattr, regs)),
 		// TODO 0x11b => Some(syscall!(kexec_load, regs)),
-		// TODO 0x11c => Some(syscall!(waitid, regs)),
+		0x11c => Some(syscall!(waitid, regs)),
 		// TODO 0x11e => Some(syscall!(add_key, regs)),
 		// TODO 0x11f => Some(syscall!(request_key, regs)),
 		// TODO 0x120 => Some(syscall!(keyctl, regs)),
@@ -811,7 +954,7 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		0x133 => Some(syscall!(faccessat, regs)),
 		0x134 => Some(syscall!(pselect6, regs)),
 		// TODO 0x135 => Some(syscall!(ppoll, regs)),
-		// TODO 0x136 => Some(syscall!(unshare, regs)),
+		0x136 => Some(syscall!(unshare, regs)),
 		// TODO 0x137 => Some(syscall!(set_robust_list, regs)),
 		// TODO 0x138 => Some(syscall!(get_robust_list, regs)),
 		// TODO 0x139 => Some(syscall!(splice, regs)),
@@ -820,17 +963,17 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		// TODO 0x13c => Some(syscall!(vmsplice, regs)),
 		// TODO 0x13d => Some(syscall!(move_pages, regs)),
 		// TODO 0x13e => Some(syscall!(getcpu, regs)),
-		// TODO 0x13f => Some(syscall!(epoll_pwait, regs)),
+		0x13f => Some(syscall!(epoll_pwait, regs)),
 		0x140 => Some(syscall!(utimensat, regs)),
-		// TODO 0x141 => Some(syscall!(signalfd, regs)),
-		// TODO 0x142 => Some(syscall!(timerfd_create, regs)),
-		// TODO 0x143 => Some(syscall!(eventfd, regs)),
+		0x141 => Some(syscall!(signalfd, regs)),
+		0x142 => Some(syscall!(timerfd_create, regs)),
+		0x143 => Some(syscall!(eventfd, regs)),
 		// TODO 0x144 => Some(syscall!(fallocate, regs)),
-		// TODO 0x145 => Some(syscall!(timerfd_settime, regs)),
-		// TODO 0x146 => Some(syscall!(timerfd_gettime, regs)),
-		// TODO 0x147 => Some(syscall!(signalfd4, regs)),
-		// TODO 0x148 => Some(syscall!(eventfd2, regs)),
-		// TODO 0x149 => Some(syscall!(epoll_create1, regs)),
+		0x145 => Some(syscall!(timerfd_settime, regs)),
+		0x146 => Some(syscall!(timerfd_gettime, regs)),
+		0x147 => Some(syscall!(signalfd4, regs)),
+		0x148 => Some(syscall!(eventfd2, regs)),
+		0x149 => Some(syscall!(epoll_create1, regs)),
 		// TODO 0x14a => Some(syscall!(dup3, regs)),
 		0x14b => Some(syscall!(pipe2, regs)),
 		// TODO 0x14c => Some(syscall!(inotify_init1, regs)),
@@ -847,7 +990,7 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		// TODO 0x157 => Some(syscall!(clock_adjtime, regs)),
 		0x158 => Some(syscall!(syncfs, regs)),
 		// TODO 0x159 => Some(syscall!(sendmmsg, regs)),
-		// TODO 0x15a => Some(syscall!(setns, regs)),
+		0x15a => Some(syscall!(setns, regs)),
 		// TODO 0x15b => Some(syscall!(process_vm_readv, regs)),
 		// TODO 0x15c => Some(syscall!(process_vm_writev, regs)),
 		// TODO 0x15d => Some(syscall!(kcmp, regs)),
@@ -855,7 +998,7 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		// TODO 0x15f => Some(syscall!(sched_setattr, regs)),
 		// TODO 0x160 => Some(syscall!(sched_getattr, regs)),
 		0x161 => Some(syscall!(renameat2, regs)),
-		// TODO 0x162 => Some(syscall!(seccomp, regs)),
+		0x162 => Some(syscall!(seccomp, regs)),
 		0x163 => Some(syscall!(getrandom, regs)),
 		// TODO 0x164 => Some(syscall!(memfd_create, regs)),
 		// TODO 0x165 => Some(syscall!(bpf, regs)),
@@ -888,16 +1031,16 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		0x180 => Some(syscall!(arch_prctl, regs)),
 		// TODO 0x181 => Some(syscall!(io_pgetevents, regs)),
 		// TODO 0x182 => Some(syscall!(rseq, regs)),
-		// TODO 0x189 => Some(syscall!(semget, regs)),
-		// TODO 0x18a => Some(syscall!(semctl, regs)),
-		// TODO 0x18b => Some(syscall!(shmget, regs)),
-		// TODO 0x18c => Some(syscall!(shmctl, regs)),
-		// TODO 0x18d => Some(syscall!(shmat, regs)),
-		// TODO 0x18e => Some(syscall!(shmdt, regs)),
-		// TODO 0x18f => Some(syscall!(msgget, regs)),
-		// TODO 0x190 => Some(syscall!(msgsnd, regs)),
-		// TODO 0x191 => Some(syscall!(msgrcv, regs)),
-		// TODO 0x192 => Some(syscall!(msgctl, regs)),
+		0x189 => Some(syscall!(semget, regs)),
+		0x18a => Some(syscall!(semctl, regs)),
+		0x18b => Some(syscall!(shmget, regs)),
+		0x18c => Some(syscall!(shmctl, regs)),
+		0x18d => Some(syscall!(shmat, regs)),
+		0x18e => Some(syscall!(shmdt, regs)),
+		0x18f => Some(syscall!(msgget, regs)),
+		0x190 => Some(syscall!(msgsnd, regs)),
+		0x191 => Some(syscall!(msgrcv, regs)),
+		0x192 => Some(syscall!(msgctl, regs)),
 		0x193 => Some(syscall!(clock_gettime64, regs)),
 		// TODO 0x194 => Some(syscall!(clock_settime64, regs)),
 		// TODO 0x195 => Some(syscall!(clock_adjtime64, regs)),
@@ -912,13 +1055,13 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		// TODO 0x19e => Some(syscall!(ppoll_time64, regs)),
 		// TODO 0x1a0 => Some(syscall!(io_pgetevents_time64, regs)),
 		// TODO 0x1a1 => Some(syscall!(recvmmsg_time64, regs)),
-		// TODO 0x1a2 => Some(syscall!(mq_timedsend_time64, regs)),
-		// TODO 0x1a3 => Some(syscall!(mq_timedreceive_time64, regs)),
-		// TODO 0x1a4 => Some(syscall!(semtimedop_time64, regs)),
+		0x1a2 => Some(syscall!(mq_timedsend_time64, regs)),
+		0x1a3 => Some(syscall!(mq_timedreceive_time64, regs)),
+		0x1a4 => Some(syscall!(semtimedop_time64, regs)),
 		// TODO 0x1a5 => Some(syscall!(rt_sigtimedwait_time64, regs)),
 		// TODO 0x1a6 => Some(syscall!(futex_time64, regs)),
 		// TODO 0x1a7 => Some(syscall!(sched_rr_get_interval_time64, regs)),
-		// TODO 0x1a8 => Some(syscall!(pidfd_send_signal, regs)),
+		0x1a8 => Some(syscall!(pidfd_send_signal, regs)),
 		// TODO 0x1a9 => Some(syscall!(io_uring_setup, regs)),
 		// TODO 0x1aa => Some(syscall!(io_uring_enter, regs)),
 		// TODO 0x1ab => Some(syscall!(io_uring_register, regs)),
@@ -928,11 +1071,11 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 		// TODO 0x1af => Some(syscall!(fsconfig, regs)),
 		// TODO 0x1b0 => Some(syscall!(fsmount, regs)),
 		// TODO 0x1b1 => Some(syscall!(fspick, regs)),
-		// TODO 0x1b2 => Some(syscall!(pidfd_open, regs)),
+		0x1b2 => Some(syscall!(pidfd_open, regs)),
 		// TODO 0x1b3 => Some(syscall!(clone3, regs)),
 		// TODO 0x1b4 => Some(syscall!(close_range, regs)),
 		// TODO 0x1b5 => Some(syscall!(openat2, regs)),
-		// TODO 0x1b6 => Some(syscall!(pidfd_getfd, regs)),
+		0x1b6 => Some(syscall!(pidfd_getfd, regs)),
 		0x1b7 => Some(syscall!(faccessat2, regs)),
 		// TODO 0x1b8 => Some(syscall!(process_madvise, regs)),
 		// TODO 0x1b9 => Some(syscall!(epoll_pwait2, regs)),
@@ -949,26 +1092,158 @@ fn do_syscall(id: usize, regs: &Regs) -> Option<EResult<usize>> {
 	}
 }
 
+/// Handles the interruption by a signal of the system call `id`, which returned one of the
+/// `ERESTART*` errnos.
+///
+/// Depending on the errno and on the handler of the signal that is about to be delivered, the
+/// system call is either restarted or made to return [`errno::EINTR`].
+fn handle_restart(id: usize, regs: &mut Regs) {
+	let errno = -(regs.eax as isize) as i32;
+	if !matches!(
+		errno,
+		errno::ERESTARTSYS
+			| errno::ERESTARTNOINTR
+			| errno::ERESTARTNOHAND
+			| errno::ERESTART_RESTARTBLOCK
+	) {
+		return;
+	}
+	let proc_mutex = Process::current();
+	let mut proc = proc_mutex.lock();
+	// The flags of the handler to be executed, if any
+	let handler_flags = proc.next_signal(true).and_then(|sig| {
+		let handlers = proc.signal_handlers.lock();
+		match &handlers[sig.get_id() as usize] {
+			SignalHandler::Handler(action) if sig.can_catch() => Some(action.sa_flags),
+			_ => None,
+		}
+	});
+	if !restart_regs(id, errno, handler_flags, regs) {
+		proc.restart_block = None;
+	}
+}
+
+/// Updates the registers `regs` of the system call `id`, interrupted with the errno `errno`.
+///
+/// `handler_flags` is the set of flags of the signal handler to be executed, if any.
+///
+/// The function returns `true` if the system call is restarted, or `false` if it returns
+/// [`errno::EINTR`].
+fn restart_regs(id: usize, errno: i32, handler_flags: Option<i32>, regs: &mut Regs) -> bool {
+	let restart = match (errno, handler_flags) {
+		// No handler is executed: resume transparently
+		(errno::ERESTARTNOINTR, _) | (_, None) => true,
+		(errno::ERESTARTSYS, Some(flags)) => flags & SA_RESTART != 0,
+		_ => false,
+	};
+	if !restart {
+		regs.eax = (-errno::EINTR) as _;
+		return false;
+	}
+	regs.eax = if errno == errno::ERESTART_RESTARTBLOCK {
+		RESTART_SYSCALL_ID
+	} else {
+		id
+	};
+	// Execute the `int 0x80` instruction again
+	regs.eip -= 2;
+	true
+}
+
 /// Called whenever a system call is triggered.
 #[no_mangle]
 pub extern "C" fn syscall_handler(regs: &mut Regs) {
-	let id = regs.get_syscall_id();
-	match do_syscall(id, regs) {
-		// Success: Set the return value
-		Some(res) => regs.set_syscall_return(res),
-		// The system call does not exist: Kill the process with SIGSYS
-		None => {
-			let proc_mutex = Process::current();
-			let mut proc = proc_mutex.lock();
-			#[cfg(feature = "strace")]
-			crate::println!(
-				"[strace PID: {pid}] invalid syscall (ID: 0x{id:x})",
-				pid = proc.get_pid()
-			);
-			// SIGSYS cannot be caught, thus the process will be terminated
-			proc.kill(Signal::SIGSYS);
+	// Time spent until now has been spent in userspace
+	Process::current().lock().account_cpu(true);
+	// If the process is traced, the tracer may change or cancel the system call
+	let id = process::ptrace::syscall_entry(regs);
+	// The system call may be denied by seccomp
+	let id = id.filter(|id| process::seccomp::syscall_entry(*id, regs));
+	if let Some(id) = id {
+		match do_syscall(id, regs) {
+			// Success: Set the return value
+			Some(res) => regs.set_syscall_return(res),
+			// The system call does not exist: Kill the process with SIGSYS
+			None => {
+				let proc_mutex = Process::current();
+				let mut proc = proc_mutex.lock();
+				#[cfg(feature = "strace")]
+				crate::println!(
+					"[strace PID: {pid}] invalid syscall (ID: 0x{id:x})",
+					pid = proc.get_pid()
+				);
+				// SIGSYS cannot be caught, thus the process will be terminated
+				proc.kill(Signal::SIGSYS);
+			}
 		}
 	}
+	process::ptrace::syscall_exit(regs);
+	if let Some(id) = id {
+		handle_restart(id, regs);
+	}
+	// Time spent in the system call is charged as system time
+	Process::current().lock().account_cpu(false);
 	// If the process has been killed, handle it
 	process::yield_current(3, regs);
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Returns the registers after the interruption of the system call `id` with `errno`, and
+	/// whether it is restarted.
+	fn interrupt(id: usize, errno: i32, handler_flags: Option<i32>) -> (bool, Regs) {
+		let mut regs = Regs {
+			eax: (-errno) as _,
+			eip: 0x1002,
+			..Default::default()
+		};
+		let restart = restart_regs(id, errno, handler_flags, &mut regs);
+		(restart, regs)
+	}
+
+	/// Asserts that the system call `id` is restarted with the ID `new_id`.
+	fn assert_restart(id: usize, errno: i32, handler_flags: Option<i32>, new_id: usize) {
+		let (restart, regs) = interrupt(id, errno, handler_flags);
+		assert!(restart);
+		assert_eq!(regs.eax, new_id);
+		assert_eq!(regs.eip, 0x1000);
+	}
+
+	/// Asserts that the system call `id` returns [`errno::EINTR`].
+	fn assert_eintr(id: usize, errno: i32, handler_flags: Option<i32>) {
+		let (restart, regs) = interrupt(id, errno, handler_flags);
+		assert!(!restart);
+		assert_eq!(regs.eax, (-errno::EINTR) as usize);
+		assert_eq!(regs.eip, 0x1002);
+	}
+
+	#[test_case]
+	fn restart_sys() {
+		assert_restart(3, errno::ERESTARTSYS, None, 3);
+		assert_restart(3, errno::ERESTARTSYS, Some(SA_RESTART), 3);
+		assert_eintr(3, errno::ERESTARTSYS, Some(0));
+	}
+
+	#[test_case]
+	fn restart_nointr() {
+		assert_restart(3, errno::ERESTARTNOINTR, None, 3);
+		assert_restart(3, errno::ERESTARTNOINTR, Some(SA_RESTART), 3);
+		assert_restart(3, errno::ERESTARTNOINTR, Some(0), 3);
+	}
+
+	#[test_case]
+	fn restart_nohand() {
+		assert_restart(3, errno::ERESTARTNOHAND, None, 3);
+		assert_eintr(3, errno::ERESTARTNOHAND, Some(SA_RESTART));
+		assert_eintr(3, errno::ERESTARTNOHAND, Some(0));
+	}
+
+	#[test_case]
+	fn restart_block() {
+		assert_restart(162, errno::ERESTART_RESTARTBLOCK, None, RESTART_SYSCALL_ID);
+		assert_eintr(162, errno::ERESTART_RESTARTBLOCK, Some(SA_RESTART));
+		assert_eintr(162, errno::ERESTART_RESTARTBLOCK, Some(0));
+	}
+}
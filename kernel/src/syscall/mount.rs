@@ -20,7 +20,9 @@
 
 use crate::{
 	file::{
-		fs, vfs,
+		fs,
+		perm::CAP_SYS_ADMIN,
+		vfs,
 		vfs::{mountpoint, mountpoint::MountSource, ResolutionSettings},
 		FileType,
 	},
@@ -35,6 +37,8 @@ use utils::{
 	collections::path::PathBuf,
 	errno,
 	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
 };
 
 pub fn mount(
@@ -46,8 +50,9 @@ pub fn mount(
 		SyscallPtr<c_void>,
 	)>,
 	rs: ResolutionSettings,
+	proc: Arc<IntMutex<Process>>,
 ) -> EResult<usize> {
-	if !rs.access_profile.is_privileged() {
+	if !rs.access_profile.has_cap(CAP_SYS_ADMIN) {
 		return Err(errno!(EPERM));
 	}
 	// Read arguments
@@ -65,6 +70,7 @@ pub fn mount(
 	}
 	// TODO Use `data`
 	// Create mountpoint
-	mountpoint::create(mount_source, Some(fs_type), mountflags, target_file)?;
+	let ns = proc.lock().namespaces.mnt.clone();
+	mountpoint::create(mount_source, Some(fs_type), mountflags, target_file, &ns)?;
 	Ok(0)
 }
@@ -0,0 +1,61 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mq_getsetattr` system call gets and sets the attributes of a POSIX message queue.
+
+use crate::{
+	file,
+	file::fd::FileDescriptorTable,
+	ipc::mqueue::{MessageQueue, MqAttr},
+	process::mem_space::copy::SyscallPtr,
+	syscall::Args,
+};
+use core::ffi::{c_int, c_long};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::Mutex,
+	ptr::arc::Arc,
+};
+
+pub fn mq_getsetattr(
+	Args((mqdes, newattr, oldattr)): Args<(c_int, SyscallPtr<MqAttr>, SyscallPtr<MqAttr>)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let file = fds.lock().get_fd(mqdes)?.get_file().clone();
+	let queue = file
+		.get_buffer::<MessageQueue>()
+		.ok_or_else(|| errno!(EBADF))?;
+	let newattr = newattr.copy_from_user()?;
+	if newattr.is_some_and(|attr| attr.mq_flags & !(file::O_NONBLOCK as c_long) != 0) {
+		return Err(errno!(EINVAL));
+	}
+	let flags = file.get_flags();
+	let old = queue.get_attr(flags);
+	// Only the `O_NONBLOCK` flag can be changed
+	if let Some(attr) = newattr {
+		let nonblock = if attr.mq_flags & file::O_NONBLOCK as c_long != 0 {
+			file::O_NONBLOCK
+		} else {
+			0
+		};
+		file.set_flags((flags & !file::O_NONBLOCK) | nonblock, true);
+	}
+	oldattr.copy_to_user(old)?;
+	Ok(0)
+}
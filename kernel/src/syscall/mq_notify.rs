@@ -0,0 +1,61 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mq_notify` system call registers the calling process for notification of message arrival
+//! on a POSIX message queue.
+
+use crate::{
+	file::fd::FileDescriptorTable,
+	ipc::mqueue::MessageQueue,
+	process::{
+		mem_space::copy::SyscallPtr,
+		signal::{SigEvent, Signal, SIGEV_NONE, SIGEV_SIGNAL},
+		Process,
+	},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
+};
+
+pub fn mq_notify(
+	Args((mqdes, sevp)): Args<(c_int, SyscallPtr<SigEvent>)>,
+	proc: Arc<IntMutex<Process>>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let file = fds.lock().get_fd(mqdes)?.get_file().clone();
+	let queue = file
+		.get_buffer::<MessageQueue>()
+		.ok_or_else(|| errno!(EBADF))?;
+	let sig = match sevp.copy_from_user()? {
+		Some(sevp) => Some(match sevp.sigev_notify {
+			SIGEV_NONE => None,
+			SIGEV_SIGNAL => Some((Signal::try_from(sevp.sigev_signo)?, sevp.sigev_value)),
+			// TODO support SIGEV_THREAD
+			_ => return Err(errno!(EINVAL)),
+		}),
+		None => None,
+	};
+	let pid = proc.lock().get_pid();
+	queue.notify(pid, sig)?;
+	Ok(0)
+}
@@ -0,0 +1,76 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mq_open` system call opens a POSIX message queue, creating it if necessary.
+
+use crate::{
+	file,
+	file::{
+		fd::{FileDescriptorTable, FD_CLOEXEC},
+		perm::AccessProfile,
+		File,
+	},
+	ipc::{mqueue, mqueue::MqAttr},
+	process::{
+		mem_space::copy::{SyscallPtr, SyscallString},
+		rlimit::RLIMIT_MSGQUEUE,
+		Process,
+	},
+	syscall::{Args, Umask},
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
+};
+
+pub fn mq_open(
+	Args((name, oflag, mode, attr)): Args<(SyscallString, c_int, file::Mode, SyscallPtr<MqAttr>)>,
+	proc: Arc<IntMutex<Process>>,
+	ap: AccessProfile,
+	umask: Umask,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let name = name.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	// Attributes are relevant only when creating the queue
+	let attr = if oflag & file::O_CREAT != 0 {
+		attr.copy_from_user()?
+	} else {
+		None
+	};
+	let (ns, limit) = {
+		let proc = proc.lock();
+		// Unwrap cannot fail since the resource exists
+		let limit = proc.rlimits.get(RLIMIT_MSGQUEUE).unwrap().rlim_cur;
+		(proc.namespaces.ipc.clone(), limit)
+	};
+	let queue = mqueue::open(
+		&ns,
+		&name,
+		oflag,
+		mode & !umask.0,
+		attr.as_ref(),
+		&ap,
+		limit,
+	)?;
+	let file = File::open_floating(queue, oflag & (0b11 | file::O_NONBLOCK))?;
+	let (fd_id, _) = fds.lock().create_fd(FD_CLOEXEC, file)?;
+	Ok(fd_id as _)
+}
@@ -0,0 +1,80 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mq_timedreceive` system call receives a message from a POSIX message queue.
+
+use crate::{
+	file,
+	file::fd::FileDescriptorTable,
+	ipc::mqueue::MessageQueue,
+	process::mem_space::copy::{SyscallPtr, SyscallSlice},
+	syscall::Args,
+	time::unit::{TimeUnit, Timespec32, Timestamp},
+};
+use core::ffi::{c_int, c_uint};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::Mutex,
+	ptr::arc::Arc,
+};
+
+/// Performs the `mq_timedreceive` system call.
+///
+/// `deadline` is the absolute timeout on `CLOCK_REALTIME`, in nanoseconds. If `None`, the call
+/// may wait indefinitely.
+pub fn do_mq_timedreceive(
+	mqdes: c_int,
+	msg_ptr: SyscallSlice<u8>,
+	msg_len: usize,
+	msg_prio: SyscallPtr<c_uint>,
+	deadline: Option<Timestamp>,
+	fds: &Mutex<FileDescriptorTable>,
+) -> EResult<usize> {
+	let file = fds.lock().get_fd(mqdes)?.get_file().clone();
+	let queue = file
+		.get_buffer::<MessageQueue>()
+		.ok_or_else(|| errno!(EBADF))?;
+	if !file.can_read() {
+		return Err(errno!(EBADF));
+	}
+	let nonblock = file.get_flags() & file::O_NONBLOCK != 0;
+	let (data, prio) = queue.receive(msg_len, nonblock, deadline)?;
+	msg_ptr.copy_to_user(0, &data)?;
+	msg_prio.copy_to_user(prio)?;
+	Ok(data.len())
+}
+
+#[allow(clippy::type_complexity)]
+pub fn mq_timedreceive(
+	Args((mqdes, msg_ptr, msg_len, msg_prio, abs_timeout)): Args<(
+		c_int,
+		SyscallSlice<u8>,
+		usize,
+		SyscallPtr<c_uint>,
+		SyscallPtr<Timespec32>,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let abs_timeout = abs_timeout.copy_from_user()?;
+	if abs_timeout.is_some_and(|ts| ts.tv_nsec >= 1_000_000_000) {
+		return Err(errno!(EINVAL));
+	}
+	let deadline = abs_timeout.map(|ts| ts.to_nano());
+	do_mq_timedreceive(mqdes, msg_ptr, msg_len, msg_prio, deadline, &fds)
+}
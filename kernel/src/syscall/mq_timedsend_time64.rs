@@ -0,0 +1,57 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mq_timedsend_time64` system call sends a message to a POSIX message queue.
+
+use super::mq_timedsend::do_mq_timedsend;
+use crate::{
+	file::fd::FileDescriptorTable,
+	process::{
+		mem_space::copy::{SyscallPtr, SyscallSlice},
+		Process,
+	},
+	syscall::Args,
+	time::unit::{TimeUnit, Timespec},
+};
+use core::ffi::{c_int, c_uint};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
+};
+
+#[allow(clippy::type_complexity)]
+pub fn mq_timedsend_time64(
+	Args((mqdes, msg_ptr, msg_len, msg_prio, abs_timeout)): Args<(
+		c_int,
+		SyscallSlice<u8>,
+		usize,
+		c_uint,
+		SyscallPtr<Timespec>,
+	)>,
+	proc: Arc<IntMutex<Process>>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let abs_timeout = abs_timeout.copy_from_user()?;
+	if abs_timeout.is_some_and(|ts| !(0..1_000_000_000).contains(&ts.tv_nsec)) {
+		return Err(errno!(EINVAL));
+	}
+	let deadline = abs_timeout.map(|ts| ts.to_nano());
+	do_mq_timedsend(mqdes, msg_ptr, msg_len, msg_prio, deadline, &proc, &fds)
+}
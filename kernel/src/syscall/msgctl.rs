@@ -0,0 +1,66 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `msgctl` system call performs a control operation on a System V message queue.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{ctl_cmd, msg, msg::MsqidDs, IpcObject, IPC_READ, IPC_RMID, IPC_SET, IPC_STAT},
+	process::{mem_space::copy::SyscallPtr, Process},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn msgctl(
+	Args((msqid, cmd, buf)): Args<(c_int, c_int, SyscallPtr<MsqidDs>)>,
+	proc: Arc<IntMutex<Process>>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	let ns = proc.lock().namespaces.ipc.clone();
+	let mut queues = ns.msg.lock();
+	let queue_mutex = queues.get(msqid)?;
+	let mut queue = queue_mutex.lock();
+	match ctl_cmd(cmd) {
+		IPC_STAT => {
+			if !ap.can_access_ipc(queue.perm(), IPC_READ) {
+				return Err(errno!(EACCES));
+			}
+			buf.copy_to_user(queue.stat())?;
+		}
+		IPC_SET => {
+			let ds = buf.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+			queue.set(&ds, &ap)?;
+		}
+		IPC_RMID => {
+			if !ap.is_ipc_owner(queue.perm()) {
+				return Err(errno!(EPERM));
+			}
+			queue.remove();
+			drop(queue);
+			queues.remove(msqid);
+		}
+		_ => return Err(errno!(EINVAL)),
+	}
+	Ok(0)
+}
@@ -0,0 +1,39 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `msgget` system call returns the identifier of a System V message queue, creating it if
+//! necessary.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{msg, KeyT},
+	process::Process,
+	syscall::Args,
+};
+use core::ffi::c_int;
+use utils::{errno::EResult, lock::IntMutex, ptr::arc::Arc};
+
+pub fn msgget(
+	Args((key, msgflg)): Args<(KeyT, c_int)>,
+	proc: Arc<IntMutex<Process>>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	let ns = proc.lock().namespaces.ipc.clone();
+	let id = msg::get(&ns, key, msgflg, &ap)?;
+	Ok(id as _)
+}
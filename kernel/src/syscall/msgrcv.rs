@@ -0,0 +1,74 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `msgrcv` system call receives a message from a System V message queue.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc,
+	ipc::{msg, IpcObject, IPC_NOWAIT, IPC_READ},
+	process::{mem_space::copy::SyscallSlice, Process},
+	syscall::Args,
+};
+use core::{
+	ffi::{c_int, c_long},
+	mem::size_of,
+};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn msgrcv(
+	Args((msqid, msgp, msgsz, msgtyp, msgflg)): Args<(
+		c_int,
+		SyscallSlice<u8>,
+		usize,
+		c_long,
+		c_int,
+	)>,
+	proc: Arc<IntMutex<Process>>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	if (msgsz as isize) < 0 {
+		return Err(errno!(EINVAL));
+	}
+	let ns = proc.lock().namespaces.ipc.clone();
+	let queue_mutex = ns.msg.lock().get(msqid)?;
+	let queue = {
+		let queue = queue_mutex.lock();
+		if !ap.can_access_ipc(queue.perm(), IPC_READ) {
+			return Err(errno!(EACCES));
+		}
+		queue.get_queue()
+	};
+	let pid = proc.lock().get_pid();
+	let msg = ipc::wait_until(&queue, None, || {
+		let msg = queue_mutex.lock().try_receive(msgsz, msgtyp, msgflg, pid)?;
+		if msg.is_none() && msgflg & IPC_NOWAIT != 0 {
+			return Err(errno!(ENOMSG));
+		}
+		Ok(msg)
+	})?;
+	// TODO on failure, put the message back in the queue
+	msgp.copy_to_user(0, &msg.mtype.to_ne_bytes())?;
+	msgp.copy_to_user(size_of::<c_long>(), &msg.data)?;
+	Ok(msg.data.len())
+}
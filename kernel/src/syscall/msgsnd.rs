@@ -0,0 +1,87 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `msgsnd` system call sends a message on a System V message queue.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc,
+	ipc::{
+		msg,
+		msg::{Message, MSGMAX},
+		IpcObject, IPC_NOWAIT, IPC_WRITE,
+	},
+	process::{mem_space::copy::SyscallSlice, Process},
+	syscall::Args,
+};
+use core::{
+	ffi::{c_int, c_long},
+	mem::size_of,
+};
+use utils::{
+	collections::vec::Vec,
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn msgsnd(
+	Args((msqid, msgp, msgsz, msgflg)): Args<(c_int, SyscallSlice<u8>, usize, c_int)>,
+	proc: Arc<IntMutex<Process>>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	if msgsz > MSGMAX {
+		return Err(errno!(EINVAL));
+	}
+	// The buffer starts with the type of the message, followed by its content
+	let buf = msgp
+		.copy_from_user(..(size_of::<c_long>() + msgsz))?
+		.ok_or_else(|| errno!(EFAULT))?;
+	let (mtype, data) = buf.split_at(size_of::<c_long>());
+	let mtype = c_long::from_ne_bytes(mtype.try_into().unwrap());
+	if mtype <= 0 {
+		return Err(errno!(EINVAL));
+	}
+	let mut msg = Some(Message {
+		mtype,
+		data: Vec::try_from(data)?,
+	});
+	let ns = proc.lock().namespaces.ipc.clone();
+	let queue_mutex = ns.msg.lock().get(msqid)?;
+	let queue = {
+		let queue = queue_mutex.lock();
+		if !ap.can_access_ipc(queue.perm(), IPC_WRITE) {
+			return Err(errno!(EACCES));
+		}
+		queue.get_queue()
+	};
+	let pid = proc.lock().get_pid();
+	ipc::wait_until(&queue, None, || {
+		let m = msg.take().unwrap();
+		match queue_mutex.lock().try_send(m, pid)? {
+			Some(_) if msgflg & IPC_NOWAIT != 0 => Err(errno!(EAGAIN)),
+			Some(m) => {
+				msg = Some(m);
+				Ok(None)
+			}
+			None => Ok(Some(())),
+		}
+	})?;
+	Ok(0)
+}
@@ -20,32 +20,69 @@
 //! given delay.
 
 use crate::{
-	process::{mem_space::copy::SyscallPtr, Process},
-	syscall::Args,
-	time::{clock, clock::CLOCK_MONOTONIC, unit::Timespec32},
+	process::{mem_space::copy::SyscallPtr, scheduler, Process, State},
+	syscall::{restart_syscall::RestartBlock, Args},
+	time::{
+		clock,
+		clock::{CLOCK_BOOTTIME, CLOCK_MONOTONIC},
+		unit::{ClockIdT, TimeUnit, Timespec, Timespec32, TimestampScale},
+		wheel::Timeout,
+	},
 };
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 };
 
-// TODO Handle signal interruption (EINTR)
-
-pub fn nanosleep(
-	Args((req, rem)): Args<(SyscallPtr<Timespec32>, SyscallPtr<Timespec32>)>,
+/// Makes the current process sleep until the clock `clockid` reaches `end`.
+///
+/// If interrupted by a signal, the remaining time is written to `rem` and the function returns
+/// [`errno::ERESTART_RESTARTBLOCK`] so that the sleep can be resumed by `restart_syscall`.
+pub(super) fn sleep_until(
+	clockid: ClockIdT,
+	end: Timespec,
+	rem: SyscallPtr<Timespec32>,
 ) -> EResult<usize> {
-	let start_time = clock::current_time_struct::<Timespec32>(CLOCK_MONOTONIC)?;
-	let delay = req.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
-	// Loop until time is elapsed or the process is interrupted by a signal
+	let end_ns = end.to_nano();
 	loop {
-		let curr_time = clock::current_time_struct::<Timespec32>(CLOCK_MONOTONIC)?;
-		if curr_time >= start_time + delay {
+		let curr = clock::current_time(clockid, TimestampScale::Nanosecond)?;
+		if curr >= end_ns {
 			break;
 		}
-		// TODO Allow interruption by signal
-		// TODO Make the current process sleep
+		// The timer wheel follows its own clock
+		let boottime = clock::current_time(CLOCK_BOOTTIME, TimestampScale::Nanosecond)?;
+		let deadline = boottime.saturating_add(end_ns - curr);
+		let timeout = {
+			let proc_mutex = Process::current();
+			let mut proc = proc_mutex.lock();
+			if proc.next_signal(true).is_some() {
+				proc.restart_block = Some(RestartBlock::Nanosleep {
+					clockid,
+					end,
+					rem: SyscallPtr(rem.0),
+				});
+				drop(proc);
+				rem.copy_to_user(Timespec32::from_nano(end_ns - curr))?;
+				return Err(errno!(ERESTART_RESTARTBLOCK));
+			}
+			let timeout = Timeout::new(deadline, proc.get_pid())?;
+			proc.set_state(State::Sleeping);
+			timeout
+		};
+		scheduler::end_tick();
+		drop(timeout);
 	}
-	// Set remaining time to zero
-	rem.copy_to_user(Timespec32::default())?;
 	Ok(0)
 }
+
+pub fn nanosleep(
+	Args((req, rem)): Args<(SyscallPtr<Timespec32>, SyscallPtr<Timespec32>)>,
+) -> EResult<usize> {
+	let delay = req.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	if delay.tv_nsec >= 1_000_000_000 {
+		return Err(errno!(EINVAL));
+	}
+	let start = clock::current_time(CLOCK_MONOTONIC, TimestampScale::Nanosecond)?;
+	let end = Timespec::from_nano(start.saturating_add(delay.to_nano()));
+	sleep_until(CLOCK_MONOTONIC, end, rem)
+}
@@ -19,6 +19,8 @@
 //! The `openat` syscall allows to open a file.
 
 use crate::{
+	device,
+	device::DeviceID,
 	file,
 	file::{
 		fd::{FileDescriptorTable, FD_CLOEXEC},
@@ -140,6 +142,17 @@ pub fn do_openat(
 	if flags & O_DIRECTORY != 0 && file_type != Some(FileType::Directory) {
 		return Err(errno!(ENOTDIR));
 	}
+	// Notify the device, if any
+	if let Some(dev_type) = file_type.and_then(FileType::to_device_type) {
+		let dev = device::get(&DeviceID {
+			dev_type,
+			major: stat.dev_major,
+			minor: stat.dev_minor,
+		});
+		if let Some(dev) = dev {
+			dev.get_io().open(flags)?;
+		}
+	}
 	// Open file
 	const FLAGS_MASK: i32 =
 		!(O_CLOEXEC | O_CREAT | O_DIRECTORY | O_EXCL | O_NOCTTY | O_NOFOLLOW | O_TRUNC);
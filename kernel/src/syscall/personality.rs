@@ -0,0 +1,40 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `personality` system call sets the execution domain of the process.
+
+use crate::{process::Process, syscall::Args};
+use core::{ffi::c_uint, mem};
+use utils::{
+	errno::{EResult, Errno},
+	lock::{IntMutex, IntMutexGuard},
+	ptr::arc::Arc,
+};
+
+/// Value used to query the personality without changing it.
+const PERSONALITY_QUERY: c_uint = 0xffffffff;
+
+pub fn personality(Args(persona): Args<c_uint>, proc: Arc<IntMutex<Process>>) -> EResult<usize> {
+	let mut proc = proc.lock();
+	let prev = if persona == PERSONALITY_QUERY {
+		proc.personality
+	} else {
+		mem::replace(&mut proc.personality, persona)
+	};
+	Ok(prev as _)
+}
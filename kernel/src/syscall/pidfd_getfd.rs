@@ -0,0 +1,69 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `pidfd_getfd` system call duplicates a file descriptor of the process referred to by a
+//! pidfd into the current process.
+
+use crate::{
+	file::{
+		fd::{FileDescriptorTable, FD_CLOEXEC},
+		pidfd::PidFd,
+	},
+	process::{Process, State},
+	syscall::{pidfd_open::get_pidfd, Args},
+};
+use core::ffi::{c_int, c_uint};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
+};
+
+pub fn pidfd_getfd(
+	Args((pidfd, targetfd, flags)): Args<(c_int, c_int, c_uint)>,
+	proc: Arc<IntMutex<Process>>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	if flags != 0 {
+		return Err(errno!(EINVAL));
+	}
+	let file = get_pidfd(pidfd, &fds)?;
+	// Cannot fail since `get_pidfd` checked the type of the file
+	let pidfd = file.get_buffer::<PidFd>().unwrap();
+	let ap = proc.lock().access_profile;
+	let target_fds = {
+		let target_mutex = pidfd.get_process()?;
+		let target = target_mutex.lock();
+		if target.get_state() == State::Zombie {
+			return Err(errno!(ESRCH));
+		}
+		// The same permission as for tracing the process is required
+		if !ap.can_kill(&target) {
+			return Err(errno!(EPERM));
+		}
+		target
+			.file_descriptors
+			.clone()
+			.ok_or_else(|| errno!(ESRCH))?
+	};
+	let target_file = target_fds.lock().get_fd(targetfd)?.get_file().clone();
+	// The close-on-exec flag is always set
+	let (fd_id, _) = fds.lock().create_fd(FD_CLOEXEC, target_file)?;
+	Ok(fd_id as _)
+}
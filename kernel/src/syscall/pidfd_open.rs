@@ -0,0 +1,80 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `pidfd_open` system call creates a file descriptor referring to a process.
+
+use crate::{
+	file,
+	file::{
+		fd::{FileDescriptorTable, FD_CLOEXEC},
+		pidfd::{PidFd, PIDFD_NONBLOCK},
+		File,
+	},
+	process::Process,
+	syscall::Args,
+};
+use core::ffi::{c_int, c_uint};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
+};
+
+/// Creates a pidfd referring to `proc` in the file descriptors table `fds`, and returns its
+/// ID.
+///
+/// `flags` is the set of flags for the open file description.
+pub fn create_pidfd(
+	proc: &Process,
+	flags: c_int,
+	fds: &Mutex<FileDescriptorTable>,
+) -> EResult<c_int> {
+	let pidfd = Arc::new(PidFd::new(proc))?;
+	let file = File::open_floating(pidfd, file::O_RDWR | (flags & PIDFD_NONBLOCK))?;
+	// The close-on-exec flag is always set
+	let (fd_id, _) = fds.lock().create_fd(FD_CLOEXEC, file)?;
+	Ok(fd_id as _)
+}
+
+/// Returns the pidfd open file description for the file descriptor `fd`.
+///
+/// If the file descriptor does not refer to a pidfd, the function returns [`errno::EBADF`].
+pub fn get_pidfd(fd: c_int, fds: &Mutex<FileDescriptorTable>) -> EResult<Arc<File>> {
+	let file = fds.lock().get_fd(fd)?.get_file().clone();
+	if file.get_buffer::<PidFd>().is_none() {
+		return Err(errno!(EBADF));
+	}
+	Ok(file)
+}
+
+pub fn pidfd_open(
+	Args((pid, flags)): Args<(c_int, c_uint)>,
+	proc: Arc<IntMutex<Process>>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let flags = flags as c_int;
+	if flags & !PIDFD_NONBLOCK != 0 || pid <= 0 {
+		return Err(errno!(EINVAL));
+	}
+	let ns = proc.lock().pid_ns().clone();
+	let pid = ns.to_global(pid as _).ok_or_else(|| errno!(ESRCH))?;
+	let target = Process::get_by_pid(pid).ok_or_else(|| errno!(ESRCH))?;
+	let fd = create_pidfd(&target.lock(), flags, &fds)?;
+	Ok(fd as _)
+}
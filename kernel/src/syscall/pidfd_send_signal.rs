@@ -0,0 +1,83 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `pidfd_send_signal` system call sends a signal to the process referred to by a pidfd.
+
+use crate::{
+	file::{fd::FileDescriptorTable, pidfd::PidFd},
+	process::{
+		mem_space::copy::SyscallPtr,
+		signal::{SigInfo, Signal, SI_TKILL, SI_USER},
+		Process, State,
+	},
+	syscall::{pidfd_open::get_pidfd, Args},
+};
+use core::ffi::{c_int, c_uint};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
+};
+
+pub fn pidfd_send_signal(
+	Args((pidfd, sig, info, flags)): Args<(c_int, c_int, SyscallPtr<SigInfo>, c_uint)>,
+	proc: Arc<IntMutex<Process>>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	if flags != 0 {
+		return Err(errno!(EINVAL));
+	}
+	let sig = (sig != 0).then(|| Signal::try_from(sig)).transpose()?;
+	let file = get_pidfd(pidfd, &fds)?;
+	// Cannot fail since `get_pidfd` checked the type of the file
+	let pidfd = file.get_buffer::<PidFd>().unwrap();
+	let info = info.copy_from_user()?;
+	let (ap, sender) = {
+		let proc = proc.lock();
+		(proc.access_profile, proc.get_pid())
+	};
+	let self_target = pidfd.get_pid() == sender;
+	let info = match (info, sig) {
+		(Some(info), Some(sig)) => {
+			if info.si_signo != sig.get_id() as c_int {
+				return Err(errno!(EINVAL));
+			}
+			// A process cannot impersonate the kernel or `kill`, except to itself
+			if (info.si_code >= 0 || info.si_code == SI_TKILL) && !self_target {
+				return Err(errno!(EPERM));
+			}
+			Some(info)
+		}
+		(None, Some(sig)) => Some(SigInfo::user(sig, SI_USER, sender, ap.uid)),
+		(_, None) => None,
+	};
+	let target_mutex = Process::get_by_pid(pidfd.get_pid()).ok_or_else(|| errno!(ESRCH))?;
+	let mut target = target_mutex.lock();
+	// The PID may have been reused by another process
+	if !pidfd.is_process(&target) || target.get_state() == State::Zombie {
+		return Err(errno!(ESRCH));
+	}
+	if !ap.can_kill(&target) {
+		return Err(errno!(EPERM));
+	}
+	if let (Some(sig), Some(info)) = (sig, info) {
+		target.kill_info(sig, info);
+	}
+	Ok(0)
+}
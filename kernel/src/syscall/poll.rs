@@ -127,6 +127,10 @@ pub(super) fn poll(
 				return Ok(fd_event_count as _);
 			}
 		}
+		// Interrupted by a signal
+		if Process::current().lock().next_signal(true).is_some() {
+			return Err(errno!(ERESTARTNOHAND));
+		}
 		// TODO Make process sleep until an event occurs on a file descriptor in
 		// `fds`
 		scheduler::end_tick();
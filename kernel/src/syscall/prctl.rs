@@ -0,0 +1,168 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `prctl` system call performs operations on the current process.
+
+use crate::{
+	file::perm::{Cap, CAP_LAST_CAP, CAP_SETPCAP},
+	process::{
+		seccomp::{SECCOMP_MODE_FILTER, SECCOMP_MODE_STRICT},
+		Process,
+	},
+	syscall::{seccomp::set_mode_filter, Args, FromSyscallArg},
+};
+use core::ffi::{c_int, c_ulong};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+/// Option: Get whether the process is dumpable.
+const PR_GET_DUMPABLE: c_int = 3;
+/// Option: Set whether the process is dumpable.
+const PR_SET_DUMPABLE: c_int = 4;
+/// Option: Get the seccomp mode.
+const PR_GET_SECCOMP: c_int = 21;
+/// Option: Set the seccomp mode.
+const PR_SET_SECCOMP: c_int = 22;
+/// Option: Get whether capabilities are kept when all user IDs stop being root.
+const PR_GET_KEEPCAPS: c_int = 7;
+/// Option: Set whether capabilities are kept when all user IDs stop being root.
+const PR_SET_KEEPCAPS: c_int = 8;
+/// Option: Tell whether a capability is in the bounding set.
+const PR_CAPBSET_READ: c_int = 23;
+/// Option: Drop a capability from the bounding set.
+const PR_CAPBSET_DROP: c_int = 24;
+/// Option: Prevent the process from gaining privileges by executing programs.
+const PR_SET_NO_NEW_PRIVS: c_int = 38;
+/// Option: Get whether the process can gain privileges by executing programs.
+const PR_GET_NO_NEW_PRIVS: c_int = 39;
+/// Option: Operate on the ambient capabilities set.
+const PR_CAP_AMBIENT: c_int = 47;
+
+/// Ambient capabilities operation: Tell whether a capability is in the set.
+const PR_CAP_AMBIENT_IS_SET: c_ulong = 1;
+/// Ambient capabilities operation: Add a capability to the set.
+const PR_CAP_AMBIENT_RAISE: c_ulong = 2;
+/// Ambient capabilities operation: Remove a capability from the set.
+const PR_CAP_AMBIENT_LOWER: c_ulong = 3;
+/// Ambient capabilities operation: Clear the set.
+const PR_CAP_AMBIENT_CLEAR_ALL: c_ulong = 4;
+
+/// Converts the argument `arg` to a capability.
+///
+/// If the capability is invalid, the function returns [`errno::EINVAL`].
+fn to_cap(arg: c_ulong) -> EResult<Cap> {
+	Cap::try_from(arg)
+		.ok()
+		.filter(|cap| *cap <= CAP_LAST_CAP)
+		.ok_or_else(|| errno!(EINVAL))
+}
+
+#[allow(clippy::type_complexity)]
+pub fn prctl(
+	Args((option, arg2, arg3, arg4, arg5)): Args<(c_int, c_ulong, c_ulong, c_ulong, c_ulong)>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	// Installing a seccomp filter requires copying from userspace, which cannot be done with the
+	// process locked
+	match option {
+		PR_GET_SECCOMP => return Ok(proc.lock().seccomp.get_mode() as _),
+		PR_SET_SECCOMP => {
+			match u8::try_from(arg2) {
+				Ok(SECCOMP_MODE_STRICT) => proc.lock().seccomp.set_strict()?,
+				Ok(SECCOMP_MODE_FILTER) => {
+					set_mode_filter(&proc, FromSyscallArg::from_syscall_arg(arg3 as _))?
+				}
+				_ => return Err(errno!(EINVAL)),
+			}
+			return Ok(0);
+		}
+		_ => {}
+	}
+	let mut guard = proc.lock();
+	let proc = &mut *guard;
+	let ap = &mut proc.access_profile;
+	match option {
+		PR_GET_DUMPABLE => Ok(proc.dumpable as _),
+		PR_SET_DUMPABLE => {
+			if arg2 > 1 {
+				return Err(errno!(EINVAL));
+			}
+			proc.dumpable = arg2 != 0;
+			Ok(0)
+		}
+		PR_GET_KEEPCAPS => Ok(ap.keep_caps as _),
+		PR_SET_KEEPCAPS => {
+			if arg2 > 1 {
+				return Err(errno!(EINVAL));
+			}
+			ap.keep_caps = arg2 != 0;
+			Ok(0)
+		}
+		PR_SET_NO_NEW_PRIVS => {
+			if arg2 != 1 || arg3 != 0 || arg4 != 0 || arg5 != 0 {
+				return Err(errno!(EINVAL));
+			}
+			ap.no_new_privs = true;
+			Ok(0)
+		}
+		PR_GET_NO_NEW_PRIVS => Ok(ap.no_new_privs as _),
+		PR_CAPBSET_READ => Ok(ap.cap_bounding.has(to_cap(arg2)?) as _),
+		PR_CAPBSET_DROP => {
+			let cap = to_cap(arg2)?;
+			if !ap.has_cap(CAP_SETPCAP) {
+				return Err(errno!(EPERM));
+			}
+			ap.cap_bounding.remove(cap);
+			Ok(0)
+		}
+		PR_CAP_AMBIENT => {
+			if arg4 != 0 || arg5 != 0 {
+				return Err(errno!(EINVAL));
+			}
+			if arg2 == PR_CAP_AMBIENT_CLEAR_ALL {
+				if arg3 != 0 {
+					return Err(errno!(EINVAL));
+				}
+				ap.cap_ambient = Default::default();
+				return Ok(0);
+			}
+			let cap = to_cap(arg3)?;
+			match arg2 {
+				PR_CAP_AMBIENT_IS_SET => Ok(ap.cap_ambient.has(cap) as _),
+				PR_CAP_AMBIENT_RAISE => {
+					// The capability must be both permitted and inheritable
+					if !ap.cap_permitted.has(cap) || !ap.cap_inheritable.has(cap) {
+						return Err(errno!(EPERM));
+					}
+					ap.cap_ambient.add(cap);
+					Ok(0)
+				}
+				PR_CAP_AMBIENT_LOWER => {
+					ap.cap_ambient.remove(cap);
+					Ok(0)
+				}
+				_ => Err(errno!(EINVAL)),
+			}
+		}
+		_ => Err(errno!(EINVAL)),
+	}
+}
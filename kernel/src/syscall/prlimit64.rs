@@ -19,9 +19,11 @@
 //! The `prlimit64` syscall returns the limit for a given resource.
 
 use crate::{
+	file::perm::CAP_SYS_RESOURCE,
 	process::{
-		mem_space::{copy::SyscallPtr, MemSpace},
+		mem_space::copy::SyscallPtr,
 		pid::Pid,
+		rlimit::{RLimit, RLIMIT_NLIMITS, RLIMIT_NOFILE},
 		Process,
 	},
 	syscall::Args,
@@ -30,102 +32,70 @@ use core::ffi::c_int;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
+	limits::OPEN_MAX,
 	lock::IntMutex,
 	ptr::arc::Arc,
 };
 
-/// The amount of seconds of CPU time the process can consume.
-const RLIMIT_CPU: i32 = 0;
-/// The maximum size of a file the process may create, in bytes.
-const RLIMIT_FSIZE: i32 = 1;
-/// The maximum size of the process's data segment in bytes, rounded down to the
-/// page size.
-const RLIMIT_DATA: i32 = 2;
-/// The maximum size of the process stack, in bytes.
-const RLIMIT_STACK: i32 = 3;
-/// The maximum size of a kernel file the process may dump in bytes.
-const RLIMIT_CORE: i32 = 4;
-/// A limit on the process's resident set (the number of virtual pages resident in RAM).
-const RLIMIT_RSS: i32 = 5;
-/// The limit on the number of threads for the real user ID of the calling process.
-const RLIMIT_NPROC: i32 = 6;
-/// A value one greater than the maximum number of file descriptors that can be
-/// open by the process.
-const RLIMIT_NOFILE: i32 = 7;
-/// The maximum number of butes of memory that may be locked into RAM.
-const RLIMIT_MEMLOCK: i32 = 8;
-/// The maximum size of the memory space in bytes, rounded down to the page
-/// size.
-const RLIMIT_AS: i32 = 9;
-/// The limit on the combined number of flock(2) locks and fcntl(2) leases the
-/// process may establish.
-const RLIMIT_LOCKS: i32 = 10;
-/// The limit on the number of signals that may be queued for the real user ID of the calling
-/// process.
-const RLIMIT_SIGPENDING: i32 = 11;
-/// The limit on the number of bytes that can be allocated for POSIX message queues for the real
-/// user IF of the calling process.
-const RLIMIT_MSGQUEUE: i32 = 12;
-/// The ceiling to which the process's nice value can be raised.
-const RLIMIT_NICE: i32 = 13;
-/// The ceiling on the real-time priority that may be set for this process.
-const RLIMIT_RTPRIO: i32 = 14;
-/// The limit (in microseconds) on the amount of CPU that a process scheduled under a real-time
-/// scheduling policy may consume without masking a blocking system call.
-const RLIMIT_RTTIME: i32 = 15;
-/// TODO doc
-const RLIMIT_NLIMITS: i32 = 16;
-
-/// A resource limit.
-type RLim = u64;
-
-/// A resource limit.
-#[repr(C)]
-#[derive(Debug)]
-pub struct RLimit {
-	/// Soft limit
-	rlim_cur: RLim,
-	/// Hard limit (ceiling for [`rlim_cur`])
-	rlim_max: RLim,
+/// Returns the limit for the given `resource` of the process with PID `pid`, and replaces it with
+/// `new_limit` if not `None`.
+///
+/// If `pid` is zero, the current process `proc` is used.
+///
+/// The function returns the previous limit.
+pub fn do_prlimit(
+	pid: Pid,
+	resource: c_int,
+	new_limit: Option<RLimit>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<RLimit> {
+	if resource < 0 || resource as usize >= RLIMIT_NLIMITS {
+		return Err(errno!(EINVAL));
+	}
+	if let Some(new_limit) = &new_limit {
+		if new_limit.rlim_cur > new_limit.rlim_max {
+			return Err(errno!(EINVAL));
+		}
+		// The file descriptor table cannot grow past its maximum size
+		if resource == RLIMIT_NOFILE && new_limit.rlim_max > OPEN_MAX as _ {
+			return Err(errno!(EPERM));
+		}
+	}
+	// The target process
+	let target_mutex = if pid != 0 {
+		let ns = proc.lock().pid_ns().clone();
+		Process::get_by_ns_pid(&ns, pid).ok_or_else(|| errno!(ESRCH))?
+	} else {
+		proc.clone()
+	};
+	let ap = proc.lock().access_profile;
+	let mut target = target_mutex.lock();
+	if pid != 0 && !ap.can_kill(&target) {
+		return Err(errno!(EPERM));
+	}
+	// Unwrap cannot fail since the resource has been checked
+	let old = *target.rlimits.get(resource).unwrap();
+	if let Some(new_limit) = new_limit {
+		// Only a privileged process can raise the hard limit
+		if new_limit.rlim_max > old.rlim_max && !ap.has_cap(CAP_SYS_RESOURCE) {
+			return Err(errno!(EPERM));
+		}
+		target.set_rlimit(resource, new_limit)?;
+	}
+	Ok(old)
 }
 
 pub fn prlimit64(
-	Args((pid, resource, _new_limit, _old_limit)): Args<(
+	Args((pid, resource, new_limit, old_limit)): Args<(
 		Pid,
 		c_int,
 		SyscallPtr<RLimit>,
 		SyscallPtr<RLimit>,
 	)>,
-	mem_space: Arc<IntMutex<MemSpace>>,
+	proc: Arc<IntMutex<Process>>,
 ) -> EResult<usize> {
-	// The target process. If None, the current process is the target
-	let _target_proc = if pid != 0 {
-		// TODO Check permission
-		Some(Process::get_by_pid(pid).ok_or_else(|| errno!(ESRCH))?)
-	} else {
-		None
-	};
-	let _mem_space = mem_space.lock();
-	// TODO Implement all
-	match resource {
-		RLIMIT_CPU => {}
-		RLIMIT_FSIZE => {}
-		RLIMIT_DATA => {}
-		RLIMIT_STACK => {}
-		RLIMIT_CORE => {}
-		RLIMIT_RSS => {}
-		RLIMIT_NPROC => {}
-		RLIMIT_NOFILE => {}
-		RLIMIT_MEMLOCK => {}
-		RLIMIT_AS => {}
-		RLIMIT_LOCKS => {}
-		RLIMIT_SIGPENDING => {}
-		RLIMIT_MSGQUEUE => {}
-		RLIMIT_NICE => {}
-		RLIMIT_RTPRIO => {}
-		RLIMIT_RTTIME => {}
-		RLIMIT_NLIMITS => {}
-		_ => return Err(errno!(EINVAL)),
-	}
+	let new_limit = new_limit.copy_from_user()?;
+	let old = do_prlimit(pid, resource, new_limit, proc)?;
+	old_limit.copy_to_user(old)?;
 	Ok(0)
 }
@@ -0,0 +1,256 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `ptrace` system call allows a process (the tracer) to observe and control the execution
+//! of another process (the tracee).
+
+use crate::{
+	file::perm::CAP_SYS_PTRACE,
+	memory::VirtAddr,
+	process::{
+		mem_space::copy::SyscallPtr,
+		pid::Pid,
+		ptrace,
+		ptrace::{UserRegs, PTRACE_O_MASK},
+		signal::Signal,
+		Process, State,
+	},
+	syscall::{Args, FromSyscallArg},
+};
+use core::{ffi::c_int, mem::size_of};
+use utils::{
+	bytes, errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+/// Request: make the current process traced by its parent.
+const PTRACE_TRACEME: c_int = 0;
+/// Request: read a word in the tracee's code.
+const PTRACE_PEEKTEXT: c_int = 1;
+/// Request: read a word in the tracee's data.
+const PTRACE_PEEKDATA: c_int = 2;
+/// Request: read a word in the tracee's user area.
+const PTRACE_PEEKUSER: c_int = 3;
+/// Request: write a word in the tracee's code.
+const PTRACE_POKETEXT: c_int = 4;
+/// Request: write a word in the tracee's data.
+const PTRACE_POKEDATA: c_int = 5;
+/// Request: write a word in the tracee's user area.
+const PTRACE_POKEUSER: c_int = 6;
+/// Request: resume the tracee.
+const PTRACE_CONT: c_int = 7;
+/// Request: kill the tracee.
+const PTRACE_KILL: c_int = 8;
+/// Request: resume the tracee for a single instruction.
+const PTRACE_SINGLESTEP: c_int = 9;
+/// Request: read the tracee's registers.
+const PTRACE_GETREGS: c_int = 12;
+/// Request: write the tracee's registers.
+const PTRACE_SETREGS: c_int = 13;
+/// Request: trace a process, stopping it.
+const PTRACE_ATTACH: c_int = 16;
+/// Request: stop tracing the tracee and resume it.
+const PTRACE_DETACH: c_int = 17;
+/// Request: resume the tracee until the next system call entry or exit.
+const PTRACE_SYSCALL: c_int = 24;
+/// Request: set the tracing options.
+const PTRACE_SETOPTIONS: c_int = 0x4200;
+/// Request: read the message associated with the last event.
+const PTRACE_GETEVENTMSG: c_int = 0x4201;
+/// Request: trace a process, without stopping it.
+const PTRACE_SEIZE: c_int = 0x4206;
+
+/// Returns the signal to deliver to a resumed tracee, according to the `data` argument.
+fn get_signal(data: usize) -> EResult<Option<Signal>> {
+	(data != 0)
+		.then(|| Signal::try_from(data as c_int))
+		.transpose()
+}
+
+/// Returns the offset of a word in the user area, according to the `addr` argument.
+///
+/// Only the registers are accessible.
+fn get_user_offset(addr: VirtAddr) -> EResult<usize> {
+	let off = addr.0;
+	if !addr.is_aligned_to(size_of::<u32>()) || off >= size_of::<UserRegs>() {
+		return Err(errno!(EIO));
+	}
+	Ok(off)
+}
+
+/// Reads or writes the memory of `tracee`.
+///
+/// For details about the arguments, see [`crate::process::mem_space::MemSpace::access`].
+fn access_mem(tracee: &Process, addr: VirtAddr, buf: &mut [u8], write: bool) -> EResult<()> {
+	let mem_space = tracee.get_mem_space().ok_or_else(|| errno!(ESRCH))?;
+	let mut mem_space = mem_space.lock();
+	mem_space.access(addr, buf, write)
+}
+
+/// Attaches the current process `proc` to the process with PID `pid`.
+///
+/// Arguments:
+/// - `options` is the initial set of options.
+/// - `seize` tells whether the tracee is attached with `PTRACE_SEIZE`. If not, the tracee is
+///   stopped.
+fn attach(proc: &IntMutex<Process>, pid: Pid, options: u32, seize: bool) -> EResult<()> {
+	if options & !PTRACE_O_MASK != 0 {
+		return Err(errno!(EINVAL));
+	}
+	let mut proc = proc.lock();
+	// The PID is given in the namespace of the current process
+	let pid = proc.pid_ns().to_global(pid).ok_or_else(|| errno!(ESRCH))?;
+	let target_mutex = Process::get_by_pid(pid).ok_or_else(|| errno!(ESRCH))?;
+	// A process cannot trace itself
+	if pid == proc.get_pid() {
+		return Err(errno!(EPERM));
+	}
+	let mut target = target_mutex.lock();
+	if target.is_init()
+		|| target.is_kernel_thread()
+		|| target.get_state() == State::Zombie
+		|| target.get_trace().is_some()
+		|| !proc.access_profile.can_trace(&target.access_profile)
+		|| (!target.dumpable && !proc.access_profile.has_cap(CAP_SYS_PTRACE))
+	{
+		return Err(errno!(EPERM));
+	}
+	ptrace::attach(&mut proc, &mut target, options, seize)?;
+	if !seize {
+		target.kill(Signal::SIGSTOP);
+	}
+	Ok(())
+}
+
+pub fn ptrace(
+	Args((request, pid, addr, data)): Args<(c_int, c_int, VirtAddr, usize)>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	match request {
+		PTRACE_TRACEME => {
+			let mut proc = proc.lock();
+			if proc.get_trace().is_some() {
+				return Err(errno!(EPERM));
+			}
+			let parent = proc.get_parent().ok_or_else(|| errno!(EPERM))?;
+			ptrace::attach(&mut parent.lock(), &mut proc, 0, false)?;
+			return Ok(0);
+		}
+		PTRACE_ATTACH => {
+			attach(&proc, pid as _, 0, false)?;
+			return Ok(0);
+		}
+		PTRACE_SEIZE => {
+			attach(&proc, pid as _, data as _, true)?;
+			return Ok(0);
+		}
+		_ => {}
+	}
+	// Other requests apply to a tracee of the current process
+	let (curr_pid, pid) = {
+		let proc = proc.lock();
+		let pid = proc.pid_ns().to_global(pid as _);
+		(proc.get_pid(), pid.ok_or_else(|| errno!(ESRCH))?)
+	};
+	if pid == curr_pid {
+		return Err(errno!(ESRCH));
+	}
+	let target_mutex = Process::get_by_pid(pid).ok_or_else(|| errno!(ESRCH))?;
+	let mut target = target_mutex.lock();
+	let message = target
+		.get_trace()
+		.filter(|trace| trace.tracer == curr_pid)
+		.ok_or_else(|| errno!(ESRCH))?
+		.message;
+	if request == PTRACE_KILL {
+		target.kill(Signal::SIGKILL);
+		return Ok(0);
+	}
+	// The tracee must be stopped
+	if target.get_state() != State::Stopped {
+		return Err(errno!(ESRCH));
+	}
+	match request {
+		PTRACE_PEEKTEXT | PTRACE_PEEKDATA => {
+			let mut buf = [0; size_of::<usize>()];
+			access_mem(&target, addr, &mut buf, false)?;
+			drop(target);
+			SyscallPtr::<usize>::from_syscall_arg(data).copy_to_user(usize::from_ne_bytes(buf))?;
+		}
+		PTRACE_POKETEXT | PTRACE_POKEDATA => {
+			let mut buf = data.to_ne_bytes();
+			access_mem(&target, addr, &mut buf, true)?;
+		}
+		PTRACE_PEEKUSER => {
+			let off = get_user_offset(addr)?;
+			let regs = ptrace::get_regs(&target)?;
+			drop(target);
+			let mut buf = [0; size_of::<u32>()];
+			buf.copy_from_slice(&bytes::as_bytes(&regs)[off..(off + size_of::<u32>())]);
+			let val = u32::from_ne_bytes(buf) as usize;
+			SyscallPtr::<usize>::from_syscall_arg(data).copy_to_user(val)?;
+		}
+		PTRACE_POKEUSER => {
+			let off = get_user_offset(addr)?;
+			let mut regs = ptrace::get_regs(&target)?;
+			let buf = (data as u32).to_ne_bytes();
+			bytes::as_bytes_mut(&mut regs)[off..(off + buf.len())].copy_from_slice(&buf);
+			ptrace::set_regs(&mut target, &regs)?;
+		}
+		PTRACE_GETREGS => {
+			let regs = ptrace::get_regs(&target)?;
+			drop(target);
+			SyscallPtr::<UserRegs>::from_syscall_arg(data).copy_to_user(regs)?;
+		}
+		PTRACE_SETREGS => {
+			let regs = SyscallPtr::<UserRegs>::from_syscall_arg(data)
+				.copy_from_user()?
+				.ok_or_else(|| errno!(EFAULT))?;
+			ptrace::set_regs(&mut target, &regs)?;
+		}
+		PTRACE_CONT | PTRACE_SYSCALL | PTRACE_SINGLESTEP => {
+			let sig = get_signal(data)?;
+			let syscall = request == PTRACE_SYSCALL;
+			let step = request == PTRACE_SINGLESTEP;
+			ptrace::resume(&mut target, sig, syscall, step);
+		}
+		PTRACE_DETACH => {
+			let sig = get_signal(data)?;
+			ptrace::detach(&mut target, sig);
+			drop(target);
+			proc.lock().remove_tracee(pid as _);
+		}
+		PTRACE_SETOPTIONS => {
+			let options = data as u32;
+			if options & !PTRACE_O_MASK != 0 {
+				return Err(errno!(EINVAL));
+			}
+			if let Some(trace) = target.get_trace_mut() {
+				trace.options = options;
+			}
+		}
+		PTRACE_GETEVENTMSG => {
+			drop(target);
+			SyscallPtr::<usize>::from_syscall_arg(data).copy_to_user(message)?;
+		}
+		_ => return Err(errno!(EIO)),
+	}
+	Ok(0)
+}
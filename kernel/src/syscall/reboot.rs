@@ -19,7 +19,12 @@
 //! The `reboot` system call allows the superuser to power off, reboot, halt or
 //! suspend the system.
 
-use crate::{file::perm::AccessProfile, power, process::Process, syscall::Args};
+use crate::{
+	file::perm::{AccessProfile, CAP_SYS_BOOT},
+	power,
+	process::Process,
+	syscall::Args,
+};
 use core::ffi::{c_int, c_void};
 use utils::{
 	errno,
@@ -48,7 +53,7 @@ pub fn reboot(
 	if magic != MAGIC || magic2 != MAGIC2 {
 		return Err(errno!(EINVAL));
 	}
-	if !ap.is_privileged() {
+	if !ap.has_cap(CAP_SYS_BOOT) {
 		return Err(errno!(EPERM));
 	}
 	// Debug commands: shutdown with QEMU
@@ -0,0 +1,61 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `restart_syscall` system call resumes a system call that was interrupted by a signal,
+//! taking into account the time elapsed while the signal was being handled.
+//!
+//! Userspace never calls this system call directly: it is substituted to the interrupted system
+//! call when it returned [`errno::ERESTART_RESTARTBLOCK`].
+
+use crate::{
+	process::{mem_space::copy::SyscallPtr, Process},
+	syscall::nanosleep,
+	time::unit::{ClockIdT, Timespec, Timespec32},
+};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+/// The state required to resume an interrupted system call.
+pub enum RestartBlock {
+	/// Resumes `nanosleep` or `clock_nanosleep`.
+	Nanosleep {
+		/// The clock on which the sleep is measured.
+		clockid: ClockIdT,
+		/// The timestamp at which the sleep ends.
+		end: Timespec,
+		/// The pointer to which the remaining time is written on interruption.
+		rem: SyscallPtr<Timespec32>,
+	},
+}
+
+pub(super) fn restart_syscall(proc: Arc<IntMutex<Process>>) -> EResult<usize> {
+	let block = proc.lock().restart_block.take();
+	match block {
+		Some(RestartBlock::Nanosleep {
+			clockid,
+			end,
+			rem,
+		}) => nanosleep::sleep_until(clockid, end, rem),
+		// Nothing to resume
+		None => Err(errno!(EINTR)),
+	}
+}
@@ -0,0 +1,46 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `rt_sigpending` system call returns the set of signals that are pending while blocked.
+
+use crate::{
+	process::{mem_space::copy::SyscallPtr, signal::SigSet, Process},
+	syscall::Args,
+};
+use core::{intrinsics::unlikely, mem::size_of};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn rt_sigpending(
+	Args((set, sigsetsize)): Args<(SyscallPtr<SigSet>, usize)>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	if unlikely(sigsetsize != size_of::<SigSet>()) {
+		return Err(errno!(EINVAL));
+	}
+	let pending = {
+		let proc = proc.lock();
+		SigSet(proc.get_pending_signals().0 & proc.sigmask.0)
+	};
+	set.copy_to_user(pending)?;
+	Ok(0)
+}
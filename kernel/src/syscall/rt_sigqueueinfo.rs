@@ -0,0 +1,68 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `rt_sigqueueinfo` system call sends a signal to a process, along with information to be
+//! passed to its handler.
+
+use crate::{
+	process::{
+		mem_space::copy::SyscallPtr,
+		pid::Pid,
+		signal::{SigInfo, Signal, SI_TKILL},
+		Process, State,
+	},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn rt_sigqueueinfo(
+	Args((tgid, sig, info)): Args<(Pid, c_int, SyscallPtr<SigInfo>)>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	let sig = (sig != 0).then(|| Signal::try_from(sig)).transpose()?;
+	let mut info = info.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	let (ap, self_target, target_mutex) = {
+		let proc = proc.lock();
+		// The PID is given in the namespace of the current process
+		let tgid = proc.pid_ns().to_global(tgid).ok_or_else(|| errno!(ESRCH))?;
+		let target_mutex = Process::get_by_pid(tgid).ok_or_else(|| errno!(ESRCH))?;
+		(proc.access_profile, proc.get_pid() == tgid, target_mutex)
+	};
+	// A process cannot impersonate the kernel or `kill`, except to itself
+	if (info.si_code >= 0 || info.si_code == SI_TKILL) && !self_target {
+		return Err(errno!(EPERM));
+	}
+	let mut target = target_mutex.lock();
+	if target.get_state() == State::Zombie {
+		return Err(errno!(ESRCH));
+	}
+	if !ap.can_kill(&target) {
+		return Err(errno!(EPERM));
+	}
+	if let Some(sig) = sig {
+		info.si_signo = sig.get_id() as _;
+		target.kill_info(sig, info);
+	}
+	Ok(0)
+}
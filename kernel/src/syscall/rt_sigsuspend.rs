@@ -0,0 +1,65 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `rt_sigsuspend` system call temporarily replaces the signal mask of the process, then
+//! waits for a signal.
+
+use crate::{
+	process::{
+		mem_space::copy::SyscallPtr,
+		scheduler,
+		signal::{SigSet, Signal},
+		Process, State,
+	},
+	syscall::Args,
+};
+use core::{intrinsics::unlikely, mem::size_of};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn rt_sigsuspend(
+	Args((mask, sigsetsize)): Args<(SyscallPtr<SigSet>, usize)>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	if unlikely(sigsetsize != size_of::<SigSet>()) {
+		return Err(errno!(EINVAL));
+	}
+	let mut mask = mask.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	mask.clear(Signal::SIGKILL.get_id() as _);
+	mask.clear(Signal::SIGSTOP.get_id() as _);
+	// The original mask is restored once the signal has been handled
+	{
+		let mut proc = proc.lock();
+		proc.saved_sigmask = Some(proc.sigmask);
+		proc.sigmask = mask;
+	}
+	loop {
+		{
+			let mut proc = proc.lock();
+			if proc.next_signal(true).is_some() {
+				return Err(errno!(ERESTARTNOHAND));
+			}
+			proc.set_state(State::Sleeping);
+		}
+		scheduler::end_tick();
+	}
+}
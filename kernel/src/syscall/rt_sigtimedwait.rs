@@ -0,0 +1,107 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `rt_sigtimedwait` system call waits for one of the given signals to be pending, then
+//! removes it from the pending signals without handling it.
+
+use crate::{
+	process::{
+		mem_space::copy::SyscallPtr,
+		scheduler,
+		signal::{SigInfo, SigSet, Signal},
+		Process, State,
+	},
+	syscall::Args,
+	time::{
+		clock,
+		clock::CLOCK_MONOTONIC,
+		unit::{TimeUnit, Timespec32, TimestampScale},
+		wheel::Timeout,
+	},
+};
+use core::{ffi::c_int, intrinsics::unlikely, mem::size_of};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+#[allow(clippy::type_complexity)]
+pub fn rt_sigtimedwait(
+	Args((set, info, timeout, sigsetsize)): Args<(
+		SyscallPtr<SigSet>,
+		SyscallPtr<SigInfo>,
+		SyscallPtr<Timespec32>,
+		usize,
+	)>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	if unlikely(sigsetsize != size_of::<SigSet>()) {
+		return Err(errno!(EINVAL));
+	}
+	let mut set = set.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	// These signals cannot be waited for
+	set.clear(Signal::SIGKILL.get_id() as _);
+	set.clear(Signal::SIGSTOP.get_id() as _);
+	// The end timestamp in nanoseconds. If `None`, wait indefinitely
+	let end = match timeout.copy_from_user()? {
+		Some(timeout) => {
+			// The seconds are signed in userspace
+			if (timeout.tv_sec as i32) < 0 || timeout.tv_nsec >= 1_000_000_000 {
+				return Err(errno!(EINVAL));
+			}
+			let start = clock::current_time(CLOCK_MONOTONIC, TimestampScale::Nanosecond)?;
+			Some(start.saturating_add(timeout.to_nano()))
+		}
+		None => None,
+	};
+	loop {
+		let timeout = {
+			let mut proc = proc.lock();
+			proc.sigwait = None;
+			if let Some((sig, i)) = proc.dequeue_signal(set) {
+				drop(proc);
+				info.copy_to_user(i)?;
+				return Ok(sig.get_id() as c_int as _);
+			}
+			// Another signal interrupts the wait
+			if proc.next_signal(true).is_some() {
+				return Err(errno!(EINTR));
+			}
+			// The timer wheel wakes the process up at the end of the timeout
+			let timeout = end
+				.map(|end| -> EResult<_> {
+					let now = clock::current_time(CLOCK_MONOTONIC, TimestampScale::Nanosecond)?;
+					if now >= end {
+						return Err(errno!(EAGAIN));
+					}
+					// The timer wheel follows its own clock
+					let deadline = clock::boottime().saturating_add(end - now);
+					Ok(Timeout::new(deadline, proc.get_pid())?)
+				})
+				.transpose()?;
+			// Sending a signal in `set` wakes the process up
+			proc.sigwait = Some(set);
+			proc.set_state(State::Sleeping);
+			timeout
+		};
+		scheduler::end_tick();
+		drop(timeout);
+	}
+}
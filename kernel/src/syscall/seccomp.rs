@@ -0,0 +1,93 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `seccomp` system call restricts the system calls the current process may perform.
+
+use crate::{
+	file::perm::CAP_SYS_ADMIN,
+	process::{
+		mem_space::copy::{SyscallPtr, SyscallSlice},
+		seccomp,
+		seccomp::{SockFilter, SockFprog},
+		Process,
+	},
+	syscall::{Args, FromSyscallArg},
+};
+use core::ffi::c_uint;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+/// Operation: enter the strict mode.
+const SECCOMP_SET_MODE_STRICT: c_uint = 0;
+/// Operation: install a filter.
+const SECCOMP_SET_MODE_FILTER: c_uint = 1;
+/// Operation: tell whether a filter action is supported.
+const SECCOMP_GET_ACTION_AVAIL: c_uint = 2;
+
+/// Installs the filter whose program is pointed to by `fprog` on the process `proc`.
+///
+/// Unless the process has `no_new_privs` set, the operation requires `CAP_SYS_ADMIN`.
+pub fn set_mode_filter(proc: &IntMutex<Process>, fprog: SyscallPtr<SockFprog>) -> EResult<()> {
+	let ap = proc.lock().access_profile;
+	if !ap.no_new_privs && !ap.has_cap(CAP_SYS_ADMIN) {
+		return Err(errno!(EACCES));
+	}
+	let fprog = fprog.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	if fprog.len == 0 {
+		return Err(errno!(EINVAL));
+	}
+	let prog = SyscallSlice::<SockFilter>::from_syscall_arg(fprog.filter as usize)
+		.copy_from_user(..fprog.len as usize)?
+		.ok_or_else(|| errno!(EFAULT))?;
+	proc.lock().seccomp.add_filter(prog)
+}
+
+pub fn seccomp(
+	Args((operation, flags, args)): Args<(c_uint, c_uint, usize)>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	// No flag is supported
+	if flags != 0 {
+		return Err(errno!(EINVAL));
+	}
+	match operation {
+		SECCOMP_SET_MODE_STRICT => {
+			if args != 0 {
+				return Err(errno!(EINVAL));
+			}
+			proc.lock().seccomp.set_strict()?;
+		}
+		SECCOMP_SET_MODE_FILTER => {
+			set_mode_filter(&proc, SyscallPtr::from_syscall_arg(args))?;
+		}
+		SECCOMP_GET_ACTION_AVAIL => {
+			let action = SyscallPtr::<u32>::from_syscall_arg(args)
+				.copy_from_user()?
+				.ok_or_else(|| errno!(EFAULT))?;
+			if !seccomp::is_action_available(action) {
+				return Err(errno!(EOPNOTSUPP));
+			}
+		}
+		_ => return Err(errno!(EINVAL)),
+	}
+	Ok(0)
+}
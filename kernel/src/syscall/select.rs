@@ -179,6 +179,10 @@ pub fn do_select<T: TimeUnit>(
 		if curr >= end {
 			break 0;
 		}
+		// Interrupted by a signal
+		if Process::current().lock().next_signal(true).is_some() {
+			return Err(errno!(ERESTARTNOHAND));
+		}
 		// TODO Make the process sleep?
 		scheduler::end_tick();
 	};
@@ -0,0 +1,123 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `semctl` system call performs a control operation on a System V semaphore set.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{
+		ctl_cmd, sem,
+		sem::{SemidDs, GETALL, GETNCNT, GETPID, GETVAL, GETZCNT, SETALL, SETVAL},
+		IpcObject, IPC_READ, IPC_RMID, IPC_SET, IPC_STAT, IPC_WRITE,
+	},
+	process::{
+		mem_space::copy::{SyscallPtr, SyscallSlice},
+		Process,
+	},
+	syscall::{Args, FromSyscallArg},
+};
+use core::ffi::c_int;
+use utils::{
+	collections::vec::Vec,
+	errno,
+	errno::{CollectResult, EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+/// Performs the `semctl` system call.
+///
+/// `arg` is the value of the `semun` union, whose interpretation depends on `cmd`.
+pub fn do_semctl(
+	semid: c_int,
+	semnum: c_int,
+	cmd: c_int,
+	arg: usize,
+	proc: &IntMutex<Process>,
+	ap: &AccessProfile,
+) -> EResult<usize> {
+	let (pid, ns) = {
+		let proc = proc.lock();
+		(proc.get_pid(), proc.namespaces.ipc.clone())
+	};
+	let mut sets = ns.sem.lock();
+	let set_mutex = sets.get(semid)?;
+	let mut set = set_mutex.lock();
+	let cmd = ctl_cmd(cmd);
+	let access = match cmd {
+		IPC_STAT | GETVAL | GETPID | GETNCNT | GETZCNT | GETALL => IPC_READ,
+		SETVAL | SETALL => IPC_WRITE,
+		_ => 0,
+	};
+	if !ap.can_access_ipc(set.perm(), access) {
+		return Err(errno!(EACCES));
+	}
+	let semnum = semnum as usize;
+	match cmd {
+		IPC_STAT => {
+			let buf = SyscallPtr::<SemidDs>::from_syscall_arg(arg);
+			buf.copy_to_user(set.stat())?;
+		}
+		IPC_SET => {
+			let buf = SyscallPtr::<SemidDs>::from_syscall_arg(arg);
+			let ds = buf.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+			set.set(&ds, ap)?;
+		}
+		IPC_RMID => {
+			if !ap.is_ipc_owner(set.perm()) {
+				return Err(errno!(EPERM));
+			}
+			set.remove();
+			drop(set);
+			sets.remove(semid);
+		}
+		GETVAL => return Ok(set.get_sem(semnum)?.val as _),
+		GETPID => return Ok(set.get_sem(semnum)?.pid as _),
+		GETNCNT => return Ok(set.get_sem(semnum)?.ncnt as _),
+		GETZCNT => return Ok(set.get_sem(semnum)?.zcnt as _),
+		GETALL => {
+			let vals = set
+				.get_sems()
+				.iter()
+				.map(|sem| sem.val)
+				.collect::<CollectResult<Vec<_>>>()
+				.0?;
+			let array = SyscallSlice::<u16>::from_syscall_arg(arg);
+			array.copy_to_user(0, &vals)?;
+		}
+		// TODO clear the undo adjustments of processes for the semaphores being set
+		SETVAL => set.set_val(semnum, arg as _, pid)?,
+		SETALL => {
+			let array = SyscallSlice::<u16>::from_syscall_arg(arg);
+			let vals = array
+				.copy_from_user(..set.get_sems().len())?
+				.ok_or_else(|| errno!(EFAULT))?;
+			set.set_all(&vals, pid)?;
+		}
+		_ => return Err(errno!(EINVAL)),
+	}
+	Ok(0)
+}
+
+pub fn semctl(
+	Args((semid, semnum, cmd, arg)): Args<(c_int, c_int, c_int, usize)>,
+	proc: Arc<IntMutex<Process>>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	do_semctl(semid, semnum, cmd, arg, &proc, &ap)
+}
@@ -0,0 +1,123 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `semtimedop_time64` system call performs operations on a System V semaphore set, waiting
+//! at most for the given duration.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc,
+	ipc::{
+		sem,
+		sem::{SemBuf, SEMOPM},
+		IpcObject, IPC_READ, IPC_WRITE,
+	},
+	process::{
+		mem_space::copy::{SyscallPtr, SyscallSlice},
+		Process,
+	},
+	syscall::Args,
+	time::unit::{TimeUnit, Timespec, Timestamp},
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+/// Performs the `semtimedop` system call.
+///
+/// `timeout` is the maximum duration to wait for, in nanoseconds. If `None`, the call may wait
+/// indefinitely.
+pub fn do_semtimedop(
+	semid: c_int,
+	sops: SyscallSlice<SemBuf>,
+	nsops: usize,
+	timeout: Option<Timestamp>,
+	proc: &IntMutex<Process>,
+	ap: &AccessProfile,
+) -> EResult<usize> {
+	if nsops == 0 {
+		return Err(errno!(EINVAL));
+	}
+	if nsops > SEMOPM {
+		return Err(errno!(E2BIG));
+	}
+	let ops = sops
+		.copy_from_user(..nsops)?
+		.ok_or_else(|| errno!(EFAULT))?;
+	let ns = proc.lock().namespaces.ipc.clone();
+	let set_mutex = ns.sem.lock().get(semid)?;
+	let queue = {
+		let set = set_mutex.lock();
+		let access = if ops.iter().any(|op| op.sem_op != 0) {
+			IPC_WRITE
+		} else {
+			IPC_READ
+		};
+		if !ap.can_access_ipc(set.perm(), access) {
+			return Err(errno!(EACCES));
+		}
+		if ops
+			.iter()
+			.any(|op| op.sem_num as usize >= set.get_sems().len())
+		{
+			return Err(errno!(EFBIG));
+		}
+		set.get_queue()
+	};
+	let pid = proc.lock().get_pid();
+	// The semaphore the process is waiting on, if any
+	let mut waiting = None;
+	let res = ipc::wait_until(&queue, timeout, || {
+		let mut set = set_mutex.lock();
+		if let Some((num, zero)) = waiting.take() {
+			set.remove_waiter(num, zero);
+		}
+		match set.try_apply(&ops, pid)? {
+			Some((num, zero)) => {
+				set.add_waiter(num, zero);
+				waiting = Some((num, zero));
+				Ok(None)
+			}
+			None => Ok(Some(())),
+		}
+	});
+	if let Some((num, zero)) = waiting {
+		set_mutex.lock().remove_waiter(num, zero);
+	}
+	res?;
+	sem::record_undo(&mut proc.lock().sem_undo, &set_mutex, &ops)?;
+	Ok(0)
+}
+
+pub fn semtimedop_time64(
+	Args((semid, sops, nsops, timeout)): Args<(
+		c_int,
+		SyscallSlice<SemBuf>,
+		usize,
+		SyscallPtr<Timespec>,
+	)>,
+	proc: Arc<IntMutex<Process>>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	let timeout = timeout.copy_from_user()?.map(|ts| ts.to_nano());
+	do_semtimedop(semid, sops, nsops, timeout, &proc, &ap)
+}
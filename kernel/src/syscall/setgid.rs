@@ -26,6 +26,9 @@ use utils::{
 };
 
 pub fn setgid(Args(gid): Args<Gid>, proc: Arc<IntMutex<Process>>) -> EResult<usize> {
-	proc.lock().access_profile.set_gid(gid)?;
+	let mut proc = proc.lock();
+	let old = proc.access_profile;
+	proc.access_profile.set_gid(gid)?;
+	proc.update_dumpable(&old);
 	Ok(0)
 }
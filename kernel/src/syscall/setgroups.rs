@@ -0,0 +1,64 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `setgroups` system call sets the supplementary group IDs of the current process.
+
+use crate::{
+	file::perm::Gid,
+	process::{mem_space::copy::SyscallSlice, Process},
+	syscall::Args,
+};
+use core::fmt::Debug;
+use utils::{
+	collections::vec::Vec,
+	errno,
+	errno::{CollectResult, EResult, Errno},
+	limits::NGROUPS_MAX,
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+/// Performs the `setgroups` system call, with group IDs of type `T`.
+pub fn do_setgroups<T: Copy + Debug + TryInto<Gid>>(
+	size: usize,
+	list: SyscallSlice<T>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	if size > NGROUPS_MAX {
+		return Err(errno!(EINVAL));
+	}
+	let groups = if size > 0 {
+		list.copy_from_user(..size)?.ok_or_else(|| errno!(EFAULT))?
+	} else {
+		Vec::new()
+	};
+	let groups = groups
+		.into_iter()
+		.map(|gid| gid.try_into().map_err(|_| errno!(EINVAL)))
+		.collect::<EResult<CollectResult<Vec<Gid>>>>()?
+		.0?;
+	proc.lock().access_profile.set_groups(&groups)?;
+	Ok(0)
+}
+
+pub fn setgroups(
+	Args((size, list)): Args<(usize, SyscallSlice<Gid>)>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	do_setgroups(size, list, proc)
+}
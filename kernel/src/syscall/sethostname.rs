@@ -19,7 +19,7 @@
 //! The `sethostname` syscall sets the hostname of the system.
 
 use crate::{
-	file::perm::AccessProfile,
+	file::perm::{AccessProfile, CAP_SYS_ADMIN},
 	process::{mem_space::copy::SyscallSlice, Process},
 	syscall::Args,
 };
@@ -28,10 +28,13 @@ use utils::{
 	errno,
 	errno::{EResult, Errno},
 	limits::HOST_NAME_MAX,
+	lock::IntMutex,
+	ptr::arc::Arc,
 };
 
 pub fn sethostname(
 	Args((name, len)): Args<(SyscallSlice<u8>, usize)>,
+	proc: Arc<IntMutex<Process>>,
 	ap: AccessProfile,
 ) -> EResult<usize> {
 	// Check the size of the hostname is in bounds
@@ -39,10 +42,11 @@ pub fn sethostname(
 		return Err(errno!(EINVAL));
 	}
 	// Check permission
-	if !ap.is_privileged() {
+	if !ap.has_cap(CAP_SYS_ADMIN) {
 		return Err(errno!(EPERM));
 	}
-	let mut hostname = crate::HOSTNAME.lock();
-	*hostname = name.copy_from_user(..len)?.ok_or(errno!(EFAULT))?;
+	let name = name.copy_from_user(..len)?.ok_or(errno!(EFAULT))?;
+	let uts = proc.lock().namespaces.uts.clone();
+	*uts.hostname.lock() = name;
 	Ok(0)
 }
@@ -0,0 +1,46 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `setitimer` system call sets the state of an interval timer.
+
+use crate::{
+	process::{mem_space::copy::SyscallPtr, Process},
+	syscall::Args,
+	time::unit::ITimerval,
+};
+use core::ffi::c_int;
+use utils::{errno, errno::EResult, lock::IntMutex, ptr::arc::Arc};
+
+pub fn setitimer(
+	Args((which, new_value, old_value)): Args<(
+		c_int,
+		SyscallPtr<ITimerval>,
+		SyscallPtr<ITimerval>,
+	)>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	// A null value disarms the timer
+	let new_value = new_value.copy_from_user()?.unwrap_or_default();
+	if new_value.it_value.tv_usec >= 1_000_000 || new_value.it_interval.tv_usec >= 1_000_000 {
+		return Err(errno!(EINVAL));
+	}
+	let manager_mutex = proc.lock().timer_manager();
+	let old = manager_mutex.lock().set_itimer(which, new_value)?;
+	old_value.copy_to_user(old)?;
+	Ok(0)
+}
@@ -0,0 +1,91 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `setns` system call moves the current process into an existing namespace.
+
+use crate::{
+	file::{
+		fd::FileDescriptorTable,
+		fs::proc::proc_dir::ns::NsNode,
+		perm::{AccessProfile, CAP_SYS_ADMIN},
+	},
+	process::{namespace::NsType, Process},
+	syscall::Args,
+};
+use core::{any::Any, ffi::c_int, ops::Deref};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
+};
+
+pub fn setns(
+	Args((fd, nstype)): Args<(c_int, c_int)>,
+	proc: Arc<IntMutex<Process>>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	let file = fds.lock().get_fd(fd)?.get_file().clone();
+	// The file must be a namespace node
+	let node = file
+		.vfs_entry
+		.as_ref()
+		.ok_or_else(|| errno!(EINVAL))?
+		.node();
+	let ns_node = (node.ops.deref() as &dyn Any)
+		.downcast_ref::<NsNode>()
+		.ok_or_else(|| errno!(EINVAL))?;
+	if nstype != 0 && nstype != ns_node.ty.get_flag() {
+		return Err(errno!(EINVAL));
+	}
+	if !ap.has_cap(CAP_SYS_ADMIN) {
+		return Err(errno!(EPERM));
+	}
+	// Get the namespaces of the target process
+	let (target_ns, target_pid_ns) = {
+		let target = Process::get_by_pid(ns_node.pid).ok_or_else(|| errno!(ESRCH))?;
+		let target = target.lock();
+		(target.namespaces.clone(), target.pid_ns().clone())
+	};
+	let mut proc = proc.lock();
+	match ns_node.ty {
+		NsType::Mnt => {
+			proc.namespaces.mnt = target_ns.mnt;
+			// Paths are now resolved from the root of the namespace
+			proc.chroot = proc.namespaces.mnt.root();
+			proc.cwd = proc.chroot.clone();
+		}
+		NsType::Uts => proc.namespaces.uts = target_ns.uts,
+		NsType::Ipc => proc.namespaces.ipc = target_ns.ipc,
+		NsType::Net => proc.namespaces.net = target_ns.net,
+		NsType::Pid | NsType::PidForChildren => {
+			let pid_ns = if ns_node.ty == NsType::Pid {
+				target_pid_ns
+			} else {
+				target_ns.pid_for_children
+			};
+			// The process can only enter a namespace nested in its own
+			if !proc.pid_ns().is_ancestor_of(&pid_ns) {
+				return Err(errno!(EINVAL));
+			}
+			proc.namespaces.pid_for_children = pid_ns;
+		}
+	}
+	Ok(0)
+}
@@ -25,7 +25,7 @@ use crate::{
 use utils::{
 	errno,
 	errno::{EResult, Errno},
-	lock::{IntMutex, IntMutexGuard},
+	lock::IntMutex,
 	ptr::arc::Arc,
 };
 
@@ -33,23 +33,37 @@ pub fn setpgid(
 	Args((mut pid, mut pgid)): Args<(Pid, Pid)>,
 	proc: Arc<IntMutex<Process>>,
 ) -> EResult<usize> {
-	let mut proc = proc.lock();
-	// TODO Check processes SID
-	if pid == 0 {
-		pid = proc.get_pid();
+	// IDs are given in the namespace of the current process
+	let sid = {
+		let proc = proc.lock();
+		let ns = proc.pid_ns();
+		pid = match pid {
+			0 => proc.get_pid(),
+			pid => ns.to_global(pid).ok_or_else(|| errno!(ESRCH))?,
+		};
+		pgid = match pgid {
+			0 => pid,
+			pgid => ns.to_global(pgid).ok_or_else(|| errno!(EPERM))?,
+		};
+		proc.sid
+	};
+	// Joining an existing group requires it to be in the same session
+	if pgid != pid {
+		let leader = Process::get_by_pid(pgid).ok_or_else(|| errno!(EPERM))?;
+		if leader.lock().sid != sid {
+			return Err(errno!(EPERM));
+		}
 	}
-	if pgid == 0 {
-		pgid = pid;
-	}
-	if pid == proc.get_pid() {
-		proc.pgid = pgid;
+	let target = if pid == proc.lock().get_pid() {
+		proc
 	} else {
-		// Avoid deadlock
-		drop(proc);
-		Process::get_by_pid(pid)
-			.ok_or_else(|| errno!(ESRCH))?
-			.lock()
-			.set_pgid(pgid)?;
+		Process::get_by_pid(pid).ok_or_else(|| errno!(ESRCH))?
+	};
+	let mut target = target.lock();
+	// A session leader cannot change its group, and the target must be in the same session
+	if target.is_session_leader() || target.sid != sid {
+		return Err(errno!(EPERM));
 	}
+	target.set_pgid(pgid)?;
 	Ok(0)
 }
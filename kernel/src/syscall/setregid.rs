@@ -19,7 +19,7 @@
 //! `setreuid` sets the real and effective group ID of the current process.
 
 use crate::{
-	file::perm::{AccessProfile, Uid},
+	file::perm::{AccessProfile, Uid, CAP_SETGID},
 	process::Process,
 	syscall::Args,
 };
@@ -40,7 +40,7 @@ pub fn setregid(
 	if rgid < -1 || egid < -1 {
 		return Err(errno!(EINVAL));
 	}
-	if !ap.is_privileged()
+	if !ap.has_cap(CAP_SETGID)
 		&& (![-1, ap.gid as _, ap.egid as _].contains(&rgid)
 			|| ![-1, ap.gid as _, ap.egid as _, ap.sgid as _].contains(&egid))
 	{
@@ -61,5 +61,6 @@ pub fn setregid(
 	if new_rgid != ap.gid || new_egid != ap.gid {
 		proc.access_profile.sgid = new_egid;
 	}
+	proc.update_dumpable(&ap);
 	Ok(0)
 }
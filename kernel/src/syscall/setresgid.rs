@@ -19,7 +19,7 @@
 //! `setresgid` sets the real, effective and saved group ID of the current process.
 
 use crate::{
-	file::perm::{AccessProfile, Uid},
+	file::perm::{AccessProfile, Uid, CAP_SETGID},
 	process::Process,
 	syscall::Args,
 };
@@ -40,7 +40,7 @@ pub fn setresgid(
 	if rgid < -1 || egid < -1 || sgid < -1 {
 		return Err(errno!(EINVAL));
 	}
-	if !ap.is_privileged() {
+	if !ap.has_cap(CAP_SETGID) {
 		let allowed = [-1, ap.gid as _, ap.egid as _, ap.sgid as _];
 		if !allowed.contains(&rgid) || !allowed.contains(&egid) || !allowed.contains(&sgid) {
 			return Err(errno!(EPERM));
@@ -60,5 +60,6 @@ pub fn setresgid(
 		-1 => ap.sgid,
 		i => i as _,
 	};
+	proc.update_dumpable(&ap);
 	Ok(0)
 }
@@ -19,7 +19,7 @@
 //! `setresuid` sets the real, effective and saved user ID of the current process.
 
 use crate::{
-	file::perm::{AccessProfile, Uid},
+	file::perm::{AccessProfile, Uid, CAP_SETUID},
 	process::Process,
 	syscall::Args,
 };
@@ -40,7 +40,7 @@ pub fn setresuid(
 	if ruid < -1 || euid < -1 || suid < -1 {
 		return Err(errno!(EINVAL));
 	}
-	if !ap.is_privileged() {
+	if !ap.has_cap(CAP_SETUID) {
 		let allowed = [-1, ap.uid as _, ap.euid as _, ap.suid as _];
 		if !allowed.contains(&ruid) || !allowed.contains(&euid) || !allowed.contains(&suid) {
 			return Err(errno!(EPERM));
@@ -60,5 +60,8 @@ pub fn setresuid(
 		-1 => ap.suid,
 		i => i as _,
 	};
+	proc.access_profile.update_caps(&ap);
+	proc.update_dumpable(&ap);
+	proc.charge_nproc(None)?;
 	Ok(0)
 }
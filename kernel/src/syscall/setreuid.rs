@@ -19,7 +19,7 @@
 //! `setreuid` sets the real and effective user ID of the current process.
 
 use crate::{
-	file::perm::{AccessProfile, Uid},
+	file::perm::{AccessProfile, Uid, CAP_SETUID},
 	process::Process,
 	syscall::Args,
 };
@@ -40,7 +40,7 @@ pub fn setreuid(
 	if ruid < -1 || euid < -1 {
 		return Err(errno!(EINVAL));
 	}
-	if !ap.is_privileged() && ![-1, ap.uid as _, ap.euid as _].contains(&ruid)
+	if !ap.has_cap(CAP_SETUID) && ![-1, ap.uid as _, ap.euid as _].contains(&ruid)
 		|| ![-1, ap.uid as _, ap.euid as _, ap.suid as _].contains(&euid)
 	{
 		return Err(errno!(EPERM));
@@ -60,5 +60,8 @@ pub fn setreuid(
 	if new_ruid != ap.uid || new_euid != ap.uid {
 		proc.access_profile.suid = new_euid;
 	}
+	proc.access_profile.update_caps(&ap);
+	proc.update_dumpable(&ap);
+	proc.charge_nproc(None)?;
 	Ok(0)
 }
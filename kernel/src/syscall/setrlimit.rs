@@ -0,0 +1,41 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `setrlimit` system call sets the limit for a given resource of the current process.
+
+use super::prlimit64::do_prlimit;
+use crate::{
+	process::{mem_space::copy::SyscallPtr, rlimit::RLimit32, Process},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn setrlimit(
+	Args((resource, rlim)): Args<(c_int, SyscallPtr<RLimit32>)>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	let limit = rlim.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	do_prlimit(0, resource, Some(limit.into()), proc)?;
+	Ok(0)
+}
@@ -0,0 +1,50 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `setsid` system call creates a new session, of which the calling process is the leader.
+
+use crate::{process::Process, tty::TTY};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn setsid(proc: Arc<IntMutex<Process>>) -> EResult<usize> {
+	let pid = {
+		let mut proc = proc.lock();
+		let pid = proc.get_pid();
+		// The process cannot create a new session if a process group with its PID as ID exists,
+		// which includes the case where the process is a group leader
+		if proc.pgid == pid || !proc.get_group_processes().is_empty() {
+			return Err(errno!(EPERM));
+		}
+		// The process becomes the leader of a new process group
+		proc.set_pgid(pid)?;
+		proc.sid = pid;
+		pid
+	};
+	// The new session has no controlling terminal. If the TTY is still attached to a former
+	// session with the same ID, detach it
+	let mut tty = TTY.display.lock();
+	if tty.get_sid() == pid {
+		tty.detach();
+	}
+	Ok(pid as _)
+}
@@ -26,6 +26,10 @@ use utils::{
 };
 
 pub fn setuid(Args(uid): Args<Uid>, proc: Arc<IntMutex<Process>>) -> EResult<usize> {
-	proc.lock().access_profile.set_uid(uid)?;
+	let mut proc = proc.lock();
+	let old = proc.access_profile;
+	proc.access_profile.set_uid(uid)?;
+	proc.update_dumpable(&old);
+	proc.charge_nproc(None)?;
 	Ok(0)
 }
@@ -0,0 +1,123 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `shmat` system call attaches a System V shared memory segment to the memory space of the
+//! process.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{
+		shm,
+		shm::{SHMLBA, SHM_EXEC, SHM_RDONLY, SHM_REMAP, SHM_RND},
+		IpcObject, IPC_READ, IPC_WRITE,
+	},
+	memory,
+	memory::VirtAddr,
+	process::{
+		mem_space,
+		mem_space::{MapConstraint, MemSpace},
+		Process,
+	},
+	syscall::Args,
+};
+use core::{ffi::c_int, intrinsics::unlikely, num::NonZeroUsize};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	limits::PAGE_SIZE,
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+/// Performs the `shmat` system call, returning the address at which the segment is attached.
+pub fn do_shmat(
+	shmid: c_int,
+	shmaddr: VirtAddr,
+	shmflg: c_int,
+	proc: &IntMutex<Process>,
+	ap: &AccessProfile,
+	mem_space: &IntMutex<MemSpace>,
+) -> EResult<VirtAddr> {
+	let ns = proc.lock().namespaces.ipc.clone();
+	let shm = ns.shm.lock().get(shmid)?;
+	let mut shm = shm.lock();
+	let (access, mut flags) = if shmflg & SHM_RDONLY != 0 {
+		(
+			IPC_READ,
+			mem_space::MAPPING_FLAG_USER | mem_space::MAPPING_FLAG_SHARED,
+		)
+	} else {
+		(
+			IPC_READ | IPC_WRITE,
+			mem_space::MAPPING_FLAG_USER
+				| mem_space::MAPPING_FLAG_SHARED
+				| mem_space::MAPPING_FLAG_WRITE,
+		)
+	};
+	if shmflg & SHM_EXEC != 0 {
+		flags |= mem_space::MAPPING_FLAG_EXEC;
+	}
+	if !ap.can_access_ipc(shm.perm(), access) {
+		return Err(errno!(EACCES));
+	}
+	let pages = NonZeroUsize::new(shm.get_pages_count()).unwrap();
+	let mut mem_space = mem_space.lock();
+	let constraint = if shmaddr.is_null() {
+		MapConstraint::None
+	} else {
+		let addr = if shmflg & SHM_RND != 0 {
+			VirtAddr(shmaddr.0 & !(SHMLBA - 1))
+		} else if shmaddr.is_aligned_to(SHMLBA) {
+			shmaddr
+		} else {
+			return Err(errno!(EINVAL));
+		};
+		// Prevent from mapping kernel memory
+		let end = addr.0.checked_add(pages.get() * PAGE_SIZE);
+		if unlikely(end.map_or(true, |end| end > memory::PROCESS_END.0)) {
+			return Err(errno!(EINVAL));
+		}
+		if shmflg & SHM_REMAP == 0 {
+			let overlap = (0..pages.get()).any(|i| {
+				mem_space
+					.get_mapping_for_addr(addr + i * PAGE_SIZE)
+					.is_some()
+			});
+			if overlap {
+				return Err(errno!(EINVAL));
+			}
+		}
+		MapConstraint::Fixed(addr)
+	};
+	let addr = VirtAddr::from(mem_space.map(constraint, pages, flags, shm.get_residence())?);
+	// The segment's pages already exist. Map them right away so that the mapping keeps referring
+	// to the right pages if it is split later
+	mem_space.alloc(addr, pages.get() * PAGE_SIZE)?;
+	shm.attach(proc.lock().get_pid());
+	Ok(addr)
+}
+
+pub fn shmat(
+	Args((shmid, shmaddr, shmflg)): Args<(c_int, VirtAddr, c_int)>,
+	proc: Arc<IntMutex<Process>>,
+	ap: AccessProfile,
+	mem_space: Arc<IntMutex<MemSpace>>,
+) -> EResult<usize> {
+	let addr = do_shmat(shmid, shmaddr, shmflg, &proc, &ap, &mem_space)?;
+	Ok(addr.0)
+}
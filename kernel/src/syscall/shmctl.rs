@@ -0,0 +1,76 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `shmctl` system call performs a control operation on a System V shared memory segment.
+
+use crate::{
+	file::perm::{AccessProfile, CAP_IPC_LOCK},
+	ipc::{
+		ctl_cmd, shm,
+		shm::{ShmidDs, SHM_LOCK, SHM_UNLOCK},
+		IpcObject, IPC_READ, IPC_RMID, IPC_SET, IPC_STAT,
+	},
+	process::{mem_space::copy::SyscallPtr, Process},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn shmctl(
+	Args((shmid, cmd, buf)): Args<(c_int, c_int, SyscallPtr<ShmidDs>)>,
+	proc: Arc<IntMutex<Process>>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	let ns = proc.lock().namespaces.ipc.clone();
+	let mut shms = ns.shm.lock();
+	let shm_mutex = shms.get(shmid)?;
+	let mut shm = shm_mutex.lock();
+	match ctl_cmd(cmd) {
+		IPC_STAT => {
+			if !ap.can_access_ipc(shm.perm(), IPC_READ) {
+				return Err(errno!(EACCES));
+			}
+			buf.copy_to_user(shm.stat())?;
+		}
+		IPC_SET => {
+			let ds = buf.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+			shm.set(&ds, &ap)?;
+		}
+		IPC_RMID => {
+			if !ap.is_ipc_owner(shm.perm()) {
+				return Err(errno!(EPERM));
+			}
+			// The pages are freed once the segment is no longer attached anywhere
+			drop(shm);
+			shms.remove(shmid);
+		}
+		// Pages of segments are never swapped
+		SHM_LOCK | SHM_UNLOCK => {
+			if !ap.has_cap(CAP_IPC_LOCK) {
+				return Err(errno!(EPERM));
+			}
+		}
+		_ => return Err(errno!(EINVAL)),
+	}
+	Ok(0)
+}
@@ -0,0 +1,65 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `shmdt` system call detaches a System V shared memory segment from the memory space of the
+//! process.
+
+use crate::{
+	ipc::shm,
+	memory::VirtAddr,
+	process::{
+		mem_space,
+		mem_space::{residence::MapResidence, MemSpace},
+		Process,
+	},
+	syscall::Args,
+};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn shmdt(
+	Args(shmaddr): Args<VirtAddr>,
+	proc: Arc<IntMutex<Process>>,
+	mem_space: Arc<IntMutex<MemSpace>>,
+) -> EResult<usize> {
+	let residence = {
+		let mut mem_space = mem_space.lock();
+		// Segments are the only shared mappings with a static residence
+		let (size, residence) = mem_space
+			.get_mapping_for_addr(shmaddr)
+			.filter(|m| {
+				VirtAddr::from(m.get_begin()) == shmaddr
+					&& m.get_flags() & mem_space::MAPPING_FLAG_SHARED != 0
+					&& matches!(m.get_residence(), MapResidence::Static { .. })
+			})
+			.map(|m| (m.get_size(), m.get_residence().clone()))
+			.ok_or_else(|| errno!(EINVAL))?;
+		mem_space.unmap(shmaddr, size, false)?;
+		residence
+	};
+	// The segment may have been removed in the meantime
+	let ns = proc.lock().namespaces.ipc.clone();
+	if let Some(shm) = shm::find_by_residence(&ns, &residence) {
+		shm.lock().detach(proc.lock().get_pid());
+	}
+	Ok(0)
+}
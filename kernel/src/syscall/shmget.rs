@@ -0,0 +1,42 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `shmget` system call returns the identifier of a System V shared memory segment,
+//! creating it if necessary.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{shm, KeyT},
+	process::Process,
+	syscall::Args,
+};
+use core::ffi::c_int;
+use utils::{errno::EResult, lock::IntMutex, ptr::arc::Arc};
+
+pub fn shmget(
+	Args((key, size, shmflg)): Args<(KeyT, usize, c_int)>,
+	proc: Arc<IntMutex<Process>>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	let (pid, ns) = {
+		let proc = proc.lock();
+		(proc.get_pid(), proc.namespaces.ipc.clone())
+	};
+	let id = shm::get(&ns, key, size, shmflg, &ap, pid)?;
+	Ok(id as _)
+}
@@ -0,0 +1,75 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `sigaltstack` system call sets or returns the alternate stack on which signal handlers
+//! can be executed.
+
+use crate::{
+	memory::PROCESS_END,
+	process::{
+		mem_space::copy::SyscallPtr,
+		regs::Regs,
+		signal::{SigAltStack, MINSIGSTKSZ, SS_DISABLE, SS_ONSTACK},
+		Process,
+	},
+	syscall::Args,
+};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn sigaltstack(
+	Args((ss, old_ss)): Args<(SyscallPtr<SigAltStack>, SyscallPtr<SigAltStack>)>,
+	proc: Arc<IntMutex<Process>>,
+	regs: &Regs,
+) -> EResult<usize> {
+	let new = ss.copy_from_user()?;
+	let mut proc = proc.lock();
+	let on_stack = proc.altstack.contains(regs.esp);
+	let mut old = proc.altstack;
+	if on_stack {
+		old.ss_flags = SS_ONSTACK;
+	}
+	if let Some(mut new) = new {
+		// The stack cannot be changed while in use
+		if on_stack {
+			return Err(errno!(EPERM));
+		}
+		if new.ss_flags & !SS_DISABLE != 0 {
+			return Err(errno!(EINVAL));
+		}
+		if new.ss_flags & SS_DISABLE != 0 {
+			new = SigAltStack::default();
+		} else if new.ss_size < MINSIGSTKSZ {
+			return Err(errno!(ENOMEM));
+		} else {
+			// The stack must lie entirely in userspace
+			let end = new.end().ok_or_else(|| errno!(EINVAL))?;
+			if end > PROCESS_END.0 {
+				return Err(errno!(EFAULT));
+			}
+		}
+		proc.altstack = new;
+	}
+	drop(proc);
+	old_ss.copy_to_user(old)?;
+	Ok(0)
+}
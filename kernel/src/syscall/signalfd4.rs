@@ -0,0 +1,91 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `signalfd4` system call creates a signalfd, or changes the mask of an existing one.
+
+use crate::{
+	file,
+	file::{
+		fd::{FileDescriptorTable, FD_CLOEXEC},
+		signalfd::SignalFd,
+		File,
+	},
+	process::{
+		mem_space::copy::SyscallPtr,
+		signal::{SigSet, Signal},
+	},
+	syscall::Args,
+};
+use core::{ffi::c_int, intrinsics::unlikely, mem::size_of};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::Mutex,
+	ptr::arc::Arc,
+};
+
+/// Performs the `signalfd4` operation.
+///
+/// Arguments:
+/// - `fd` is the signalfd to modify. If `-1`, a new signalfd is created.
+/// - `mask` is the set of signals to accept.
+/// - `sizemask` is the size of `mask` in bytes.
+/// - `flags` is the set of flags for the file descriptor.
+/// - `fds` is the file descriptors table of the process.
+pub fn do_signalfd4(
+	fd: c_int,
+	mask: SyscallPtr<SigSet>,
+	sizemask: usize,
+	flags: c_int,
+	fds: &Mutex<FileDescriptorTable>,
+) -> EResult<usize> {
+	if unlikely(sizemask != size_of::<SigSet>()) {
+		return Err(errno!(EINVAL));
+	}
+	if flags & !(file::O_CLOEXEC | file::O_NONBLOCK) != 0 {
+		return Err(errno!(EINVAL));
+	}
+	let mut mask = mask.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	// Those signals cannot be accepted
+	mask.clear(Signal::SIGKILL.get_id() as _);
+	mask.clear(Signal::SIGSTOP.get_id() as _);
+	if fd != -1 {
+		let file = fds.lock().get_fd(fd)?.get_file().clone();
+		let signalfd = file
+			.get_buffer::<SignalFd>()
+			.ok_or_else(|| errno!(EINVAL))?;
+		signalfd.set_mask(mask);
+		return Ok(fd as _);
+	}
+	let signalfd = Arc::new(SignalFd::new(mask))?;
+	let file = File::open_floating(signalfd, file::O_RDONLY | (flags & file::O_NONBLOCK))?;
+	let fd_flags = if flags & file::O_CLOEXEC != 0 {
+		FD_CLOEXEC
+	} else {
+		0
+	};
+	let (fd_id, _) = fds.lock().create_fd(fd_flags, file)?;
+	Ok(fd_id as _)
+}
+
+pub fn signalfd4(
+	Args((fd, mask, sizemask, flags)): Args<(c_int, SyscallPtr<SigSet>, usize, c_int)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_signalfd4(fd, mask, sizemask, flags, &fds)
+}
@@ -24,14 +24,14 @@
 
 use crate::{
 	process::{
-		mem_space::copy::SyscallPtr,
+		mem_space::copy::{SyscallPtr, SyscallSlice},
 		regs::Regs,
 		signal::{Signal, UContext},
 		Process,
 	},
 	syscall::FromSyscallArg,
 };
-use core::{mem::size_of, ptr};
+use core::{mem::size_of, ptr, ptr::NonNull};
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -51,6 +51,12 @@ pub fn sigreturn(regs: &Regs) -> EResult<usize> {
 		let mut proc = proc_mutex.lock();
 		// Restores the state of the process before the signal handler
 		proc.sigmask = ctx.uc_sigmask;
+		let xstate = SyscallSlice::<u8>(NonNull::new(ctx.uc_xstate as _));
+		let len = proc.xstate.as_slice().len();
+		if let Some(xstate) = xstate.copy_from_user(..len)? {
+			proc.xstate.load(&xstate);
+			proc.xstate.restore();
+		}
 	}
 	// Do not handle the next pending signal here, to prevent signal spamming from completely
 	// blocking the process
@@ -0,0 +1,36 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `timer_getoverrun` system call returns the overrun count of a per-process timer, which is
+//! the number of expirations that were missed before the last one.
+
+use crate::{process::Process, syscall::Args, time::unit::TimerT};
+use utils::{errno, errno::EResult, lock::IntMutex, ptr::arc::Arc};
+
+pub fn timer_getoverrun(
+	Args(timerid): Args<TimerT>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
+	let manager_mutex = proc.lock().timer_manager();
+	let overrun = manager_mutex
+		.lock()
+		.get_timer_mut(timerid)
+		.ok_or_else(|| errno!(EINVAL))?
+		.get_overrun();
+	Ok(overrun as _)
+}
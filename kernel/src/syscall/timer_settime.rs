@@ -21,7 +21,10 @@
 use crate::{
 	process::{mem_space::copy::SyscallPtr, Process},
 	syscall::Args,
-	time::unit::{ITimerspec32, TimerT},
+	time::{
+		timer::TIMER_ABSTIME,
+		unit::{ITimerspec32, TimeUnit, TimerT, Timespec32},
+	},
 };
 use core::ffi::c_int;
 use utils::{
@@ -31,9 +34,6 @@ use utils::{
 	ptr::arc::Arc,
 };
 
-/// If set, the specified time is *not* relative to the timer's current counter.
-const TIMER_ABSTIME: c_int = 1;
-
 pub fn timer_settime(
 	Args((timerid, flags, new_value, old_value)): Args<(
 		TimerT,
@@ -43,6 +43,12 @@ pub fn timer_settime(
 	)>,
 	proc: Arc<IntMutex<Process>>,
 ) -> EResult<usize> {
+	let mut new_value_val = new_value.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	if new_value_val.it_value.tv_nsec >= 1_000_000_000
+		|| new_value_val.it_interval.tv_nsec >= 1_000_000_000
+	{
+		return Err(errno!(EINVAL));
+	}
 	let proc = proc.lock();
 	// Get timer
 	let manager_mutex = proc.timer_manager().clone();
@@ -51,12 +57,19 @@ pub fn timer_settime(
 		.get_timer_mut(timerid)
 		.ok_or_else(|| errno!(EINVAL))?;
 	// Write old value
-	let old = timer.get_time();
-	old_value.copy_to_user(old)?;
+	old_value.copy_to_user(timer.get_time())?;
 	// Set new value
-	let mut new_value_val = new_value.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
-	if (flags & TIMER_ABSTIME) == 0 {
-		new_value_val.it_value = new_value_val.it_value + old.it_value;
+	if flags & TIMER_ABSTIME != 0 && !new_value_val.it_value.is_zero() {
+		let now = timer.get_clock_time();
+		// An expiration time in the past makes the timer expire immediately
+		new_value_val.it_value = if new_value_val.it_value > now {
+			Timespec32::from_nano(new_value_val.it_value.to_nano() - now.to_nano())
+		} else {
+			Timespec32 {
+				tv_sec: 0,
+				tv_nsec: 1,
+			}
+		};
 	}
 	timer.set_time(new_value_val, proc.get_pid(), timerid)?;
 	Ok(0)
@@ -0,0 +1,83 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `timerfd_create` system call creates a timerfd.
+
+use crate::{
+	file,
+	file::{
+		fd::{FileDescriptorTable, FD_CLOEXEC},
+		timerfd::{TimerFd, TimerFdState},
+		File,
+	},
+	process::Process,
+	syscall::Args,
+	time::{
+		clock::{
+			CLOCK_BOOTTIME, CLOCK_BOOTTIME_ALARM, CLOCK_MONOTONIC, CLOCK_REALTIME,
+			CLOCK_REALTIME_ALARM,
+		},
+		unit::ClockIdT,
+	},
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
+};
+
+pub fn timerfd_create(
+	Args((clockid, flags)): Args<(ClockIdT, c_int)>,
+	proc: Arc<IntMutex<Process>>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	if !matches!(
+		clockid,
+		CLOCK_REALTIME
+			| CLOCK_MONOTONIC
+			| CLOCK_BOOTTIME
+			| CLOCK_REALTIME_ALARM
+			| CLOCK_BOOTTIME_ALARM
+	) {
+		return Err(errno!(EINVAL));
+	}
+	if flags & !(file::O_CLOEXEC | file::O_NONBLOCK) != 0 {
+		return Err(errno!(EINVAL));
+	}
+	let manager = proc.lock().timer_manager();
+	let state = Arc::new(TimerFdState::default())?;
+	let timer_id = manager.lock().create_timerfd(clockid, state.clone())?;
+	let timerfd = Arc::new(TimerFd::new(manager.clone(), timer_id as _, state));
+	let timerfd = match timerfd {
+		Ok(timerfd) => timerfd,
+		Err(e) => {
+			let _ = manager.lock().delete_timer(timer_id as _);
+			return Err(e.into());
+		}
+	};
+	let file = File::open_floating(timerfd, file::O_RDONLY | (flags & file::O_NONBLOCK))?;
+	let fd_flags = if flags & file::O_CLOEXEC != 0 {
+		FD_CLOEXEC
+	} else {
+		0
+	};
+	let (fd_id, _) = fds.lock().create_fd(fd_flags, file)?;
+	Ok(fd_id as _)
+}
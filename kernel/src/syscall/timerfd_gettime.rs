@@ -0,0 +1,47 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `timerfd_gettime` system call returns the current setting of a timerfd.
+
+use crate::{
+	file::{fd::FileDescriptorTable, timerfd::TimerFd},
+	process::mem_space::copy::SyscallPtr,
+	syscall::Args,
+	time::unit::ITimerspec32,
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::Mutex,
+	ptr::arc::Arc,
+};
+
+pub fn timerfd_gettime(
+	Args((fd, curr_value)): Args<(c_int, SyscallPtr<ITimerspec32>)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let file = fds.lock().get_fd(fd)?.get_file().clone();
+	let timerfd = file.get_buffer::<TimerFd>().ok_or_else(|| errno!(EINVAL))?;
+	let mut manager = timerfd.get_manager().lock();
+	let timer = manager
+		.get_timer_mut(timerfd.get_timer_id())
+		.ok_or_else(|| errno!(EINVAL))?;
+	curr_value.copy_to_user(timer.get_time())?;
+	Ok(0)
+}
@@ -0,0 +1,87 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `timerfd_settime` system call arms or disarms a timerfd.
+
+use crate::{
+	file::{fd::FileDescriptorTable, timerfd::TimerFd},
+	process::mem_space::copy::SyscallPtr,
+	syscall::Args,
+	time::unit::{ITimerspec32, TimeUnit, Timespec32},
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::Mutex,
+	ptr::arc::Arc,
+};
+
+/// If set, the specified time is an absolute value of the timer's clock.
+const TFD_TIMER_ABSTIME: c_int = 1;
+/// If set along with [`TFD_TIMER_ABSTIME`], the timer is cancelled when the realtime clock is
+/// changed.
+const TFD_TIMER_CANCEL_ON_SET: c_int = 2;
+
+pub fn timerfd_settime(
+	Args((fd, flags, new_value, old_value)): Args<(
+		c_int,
+		c_int,
+		SyscallPtr<ITimerspec32>,
+		SyscallPtr<ITimerspec32>,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	if flags & !(TFD_TIMER_ABSTIME | TFD_TIMER_CANCEL_ON_SET) != 0 {
+		return Err(errno!(EINVAL));
+	}
+	let mut new_value = new_value.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	if new_value.it_value.tv_nsec >= 1_000_000_000
+		|| new_value.it_interval.tv_nsec >= 1_000_000_000
+	{
+		return Err(errno!(EINVAL));
+	}
+	let file = fds.lock().get_fd(fd)?.get_file().clone();
+	let timerfd = file.get_buffer::<TimerFd>().ok_or_else(|| errno!(EINVAL))?;
+	let mut manager = timerfd.get_manager().lock();
+	let pid = manager.get_pid();
+	let timer_id = timerfd.get_timer_id();
+	let timer = manager
+		.get_timer_mut(timer_id)
+		.ok_or_else(|| errno!(EINVAL))?;
+	old_value.copy_to_user(timer.get_time())?;
+	timerfd.get_state().reset();
+	if new_value.it_value.is_zero() {
+		timer.disarm(pid, timer_id);
+		return Ok(0);
+	}
+	if flags & TFD_TIMER_ABSTIME != 0 {
+		let now = timer.get_clock_time();
+		// An expiration time in the past makes the timer expire immediately
+		new_value.it_value = if new_value.it_value > now {
+			new_value.it_value - now
+		} else {
+			Timespec32 {
+				tv_sec: 0,
+				tv_nsec: 1,
+			}
+		};
+	}
+	timer.set_time(new_value, pid, timer_id)?;
+	Ok(0)
+}
@@ -0,0 +1,64 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `times` system call returns the CPU time used by the current process and its children.
+
+use crate::{
+	process::{mem_space::copy::SyscallPtr, rusage, Process},
+	syscall::Args,
+	time::unit::TimeUnit,
+};
+use core::ffi::c_long;
+use utils::{errno::EResult, lock::IntMutex, ptr::arc::Arc};
+
+/// Process times, in clock ticks.
+#[derive(Clone, Debug, Default)]
+#[repr(C)]
+pub struct Tms {
+	/// User time.
+	tms_utime: c_long,
+	/// System time.
+	tms_stime: c_long,
+	/// User time of terminated children that have been waited for.
+	tms_cutime: c_long,
+	/// System time of terminated children that have been waited for.
+	tms_cstime: c_long,
+}
+
+/// Converts the given duration to clock ticks.
+fn to_ticks<T: TimeUnit>(time: &T) -> c_long {
+	rusage::to_clock_ticks(time.to_nano()) as _
+}
+
+pub fn times(Args(buf): Args<SyscallPtr<Tms>>, proc: Arc<IntMutex<Process>>) -> EResult<usize> {
+	let tms = {
+		let mut proc = proc.lock();
+		proc.account_cpu(false);
+		let rusage = proc.get_rusage();
+		let children_rusage = proc.get_children_rusage();
+		Tms {
+			tms_utime: to_ticks(&rusage.ru_utime),
+			tms_stime: to_ticks(&rusage.ru_stime),
+			tms_cutime: to_ticks(&children_rusage.ru_utime),
+			tms_cstime: to_ticks(&children_rusage.ru_stime),
+		}
+	};
+	buf.copy_to_user(tms)?;
+	// The elapsed time since an arbitrary point in the past: boot
+	Ok(rusage::to_clock_ticks(rusage::timestamp()) as _)
+}
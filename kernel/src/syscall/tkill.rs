@@ -19,7 +19,11 @@
 //! The `tkill` system call allows to send a signal to a specific thread.
 
 use crate::{
-	process::{pid::Pid, signal::Signal, Process},
+	process::{
+		pid::Pid,
+		signal::{SigInfo, Signal, SI_TKILL},
+		Process,
+	},
 	syscall::Args,
 };
 use core::ffi::c_int;
@@ -36,9 +40,10 @@ pub fn tkill(
 ) -> EResult<usize> {
 	let signal = Signal::try_from(sig)?;
 	let mut proc = proc.lock();
+	let info = SigInfo::user(signal, SI_TKILL, proc.get_pid(), proc.access_profile.uid);
 	// Check if the thread to kill is the current
 	if proc.tid == tid {
-		proc.kill(signal);
+		proc.kill_info(signal, info);
 	} else {
 		// Get the thread
 		let thread_mutex = Process::get_by_tid(tid).ok_or(errno!(ESRCH))?;
@@ -47,7 +52,7 @@ pub fn tkill(
 		if !proc.access_profile.can_kill(&thread) {
 			return Err(errno!(EPERM));
 		}
-		thread.kill(signal);
+		thread.kill_info(signal, info);
 	}
 	Ok(0)
 }
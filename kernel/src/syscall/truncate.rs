@@ -20,7 +20,7 @@
 
 use crate::{
 	file::{vfs, vfs::ResolutionSettings},
-	process::{mem_space::copy::SyscallString, Process},
+	process::{mem_space::copy::SyscallString, rlimit::RLIMIT_FSIZE, signal::Signal, Process},
 	syscall::Args,
 };
 use utils::{
@@ -31,7 +31,13 @@ use utils::{
 
 pub fn truncate(Args((path, length)): Args<(SyscallString, usize)>) -> EResult<usize> {
 	let proc_mutex = Process::current();
-	let proc = proc_mutex.lock();
+	let mut proc = proc_mutex.lock();
+	// Enforce the file size limit
+	let limit = proc.rlimits.get(RLIMIT_FSIZE).unwrap().rlim_cur;
+	if length as u64 > limit {
+		proc.kill(Signal::SIGXFSZ);
+		return Err(errno!(EFBIG));
+	}
 
 	let rs = ResolutionSettings::for_process(&proc, true);
 
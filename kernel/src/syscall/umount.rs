@@ -21,6 +21,7 @@
 
 use crate::{
 	file::{
+		perm::CAP_SYS_ADMIN,
 		vfs,
 		vfs::{mountpoint, ResolutionSettings},
 	},
@@ -31,11 +32,17 @@ use utils::{
 	collections::path::PathBuf,
 	errno,
 	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
 };
 
-pub fn umount(Args(target): Args<SyscallString>, rs: ResolutionSettings) -> EResult<usize> {
+pub fn umount(
+	Args(target): Args<SyscallString>,
+	rs: ResolutionSettings,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
 	// Check permission
-	if !rs.access_profile.is_privileged() {
+	if !rs.access_profile.has_cap(CAP_SYS_ADMIN) {
 		return Err(errno!(EPERM));
 	}
 	// Get target directory
@@ -43,6 +50,7 @@ pub fn umount(Args(target): Args<SyscallString>, rs: ResolutionSettings) -> ERes
 	let target_path = PathBuf::try_from(target_slice)?;
 	let target_file = vfs::get_file_from_path(&target_path, &rs)?;
 	// Remove mountpoint
-	mountpoint::remove(target_file)?;
+	let ns = proc.lock().namespaces.mnt.clone();
+	mountpoint::remove(target_file, &ns)?;
 	Ok(0)
 }
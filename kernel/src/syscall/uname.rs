@@ -25,6 +25,8 @@ use crate::{
 use utils::{
 	errno,
 	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
 };
 
 /// The length of a field of the utsname structure.
@@ -46,7 +48,10 @@ pub struct Utsname {
 	machine: [u8; UTSNAME_LENGTH],
 }
 
-pub fn uname(Args(buf): Args<SyscallPtr<Utsname>>) -> EResult<usize> {
+pub fn uname(
+	Args(buf): Args<SyscallPtr<Utsname>>,
+	proc: Arc<IntMutex<Process>>,
+) -> EResult<usize> {
 	let mut utsname = Utsname {
 		sysname: [0; UTSNAME_LENGTH],
 		nodename: [0; UTSNAME_LENGTH],
@@ -55,7 +60,8 @@ pub fn uname(Args(buf): Args<SyscallPtr<Utsname>>) -> EResult<usize> {
 		machine: [0; UTSNAME_LENGTH],
 	};
 	utils::slice_copy(crate::NAME.as_bytes(), &mut utsname.sysname);
-	utils::slice_copy(&crate::HOSTNAME.lock(), &mut utsname.nodename);
+	let uts = proc.lock().namespaces.uts.clone();
+	utils::slice_copy(&uts.hostname.lock(), &mut utsname.nodename);
 	utils::slice_copy(crate::VERSION.as_bytes(), &mut utsname.release);
 	utils::slice_copy(&[], &mut utsname.version);
 	utils::slice_copy(crate::ARCH.as_bytes(), &mut utsname.machine);
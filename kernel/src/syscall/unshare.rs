@@ -0,0 +1,71 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `unshare` system call disassociates parts of the execution context of the current process
+//! that are shared with other processes.
+
+use crate::{
+	file::perm::{AccessProfile, CAP_SYS_ADMIN},
+	process::{namespace, Process},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
+};
+
+/// Unshare the filesystem information. Each process has its own, so this is a no-op.
+const CLONE_FS: c_int = 0x200;
+/// Unshare the file descriptors table.
+const CLONE_FILES: c_int = 0x400;
+/// Unshare the System V semaphore adjustments. Each process has its own, so this is a no-op.
+const CLONE_SYSVSEM: c_int = 0x40000;
+
+pub fn unshare(
+	Args(flags): Args<c_int>,
+	proc: Arc<IntMutex<Process>>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	if flags & !(CLONE_FS | CLONE_FILES | CLONE_SYSVSEM | namespace::CLONE_NEW_MASK) != 0 {
+		return Err(errno!(EINVAL));
+	}
+	let new_namespaces = flags & namespace::CLONE_NEW_MASK;
+	if new_namespaces != 0 && !ap.has_cap(CAP_SYS_ADMIN) {
+		return Err(errno!(EPERM));
+	}
+	let mut proc = proc.lock();
+	if flags & CLONE_FILES != 0 {
+		if let Some(fds) = &proc.file_descriptors {
+			let fds = fds.lock().duplicate(false)?;
+			proc.file_descriptors = Some(Arc::new(Mutex::new(fds))?);
+		}
+	}
+	if new_namespaces != 0 {
+		let namespaces = proc.namespaces.unshare(new_namespaces, proc.pid_ns())?;
+		// Paths are now resolved in the tree of the new mount namespace
+		if namespaces.mnt.get_id() != proc.namespaces.mnt.get_id() {
+			proc.cwd = namespaces.mnt.translate(&proc.cwd);
+			proc.chroot = namespaces.mnt.translate(&proc.chroot);
+		}
+		proc.namespaces = namespaces;
+	}
+	Ok(0)
+}
@@ -29,6 +29,8 @@ use utils::{
 };
 
 pub fn vfork(proc: Arc<IntMutex<Process>>, regs: &Regs) -> EResult<usize> {
+	// The PID of the child is returned in the namespace of the parent
+	let pid_ns = proc.lock().pid_ns().clone();
 	let new_pid = {
 		let fork_options = ForkOptions {
 			vfork: true,
@@ -40,7 +42,7 @@ pub fn vfork(proc: Arc<IntMutex<Process>>, regs: &Regs) -> EResult<usize> {
 		let mut regs = regs.clone();
 		regs.set_syscall_return(Ok(0));
 		new_proc.regs = regs;
-		new_proc.get_pid()
+		new_proc.get_pid_in(&pid_ns)
 	};
 	// Let another process run instead of the current. Because the current
 	// process must now wait for the child process to terminate or execute a program
@@ -0,0 +1,43 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `vhangup` system call simulates a hangup on the controlling terminal of the calling
+//! process.
+
+use crate::{file::perm::CAP_SYS_TTY_CONFIG, process::Process, tty::TTY};
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::IntMutex,
+	ptr::arc::Arc,
+};
+
+pub fn vhangup(proc: Arc<IntMutex<Process>>) -> EResult<usize> {
+	let sid = {
+		let proc = proc.lock();
+		if !proc.access_profile.has_cap(CAP_SYS_TTY_CONFIG) {
+			return Err(errno!(EPERM));
+		}
+		proc.sid
+	};
+	let mut tty = TTY.display.lock();
+	if tty.get_sid() != 0 && tty.get_sid() == sid {
+		tty.hangup();
+	}
+	Ok(0)
+}
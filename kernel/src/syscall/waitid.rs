@@ -0,0 +1,173 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `waitid` system call waits for a process to change state, reporting it as a
+//! `siginfo_t`.
+
+use crate::{
+	file::{fd::FileDescriptorTable, pidfd::PidFd},
+	process::{
+		mem_space::copy::SyscallPtr,
+		rusage,
+		rusage::RUsage,
+		signal::{
+			SigInfo, Signal, CLD_CONTINUED, CLD_DUMPED, CLD_EXITED, CLD_KILLED, CLD_STOPPED,
+			CLD_TRAPPED,
+		},
+		Process, State,
+	},
+	syscall::{
+		pidfd_open::get_pidfd,
+		waitpid,
+		waitpid::{WCONTINUED, WEXITED, WNOHANG, WNOWAIT, WSTOPPED},
+		Args,
+	},
+	time::unit::TimeUnit,
+};
+use core::ffi::c_int;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	lock::{IntMutex, Mutex},
+	ptr::arc::Arc,
+};
+
+/// Wait for any child.
+const P_ALL: c_int = 0;
+/// Wait for the child with the given PID.
+const P_PID: c_int = 1;
+/// Wait for any child in the given process group.
+const P_PGID: c_int = 2;
+/// Wait for the child referred to by the given pidfd.
+const P_PIDFD: c_int = 3;
+
+/// Returns the `SIGCHLD` information reporting the state of `proc`, whose PID is `pid` in the
+/// namespace of the current process.
+fn get_siginfo(proc: &Process, pid: c_int) -> SigInfo {
+	let termsig = proc.get_termsig() as c_int;
+	let (code, status) = match proc.get_state() {
+		State::Zombie if termsig == 0 => (CLD_EXITED, proc.get_exit_status().unwrap_or(0) as _),
+		State::Zombie if proc.is_coredump() => (CLD_DUMPED, termsig),
+		State::Zombie => (CLD_KILLED, termsig),
+		State::Stopped if proc.get_trace().is_some_and(|trace| trace.is_stopped()) => {
+			(CLD_TRAPPED, termsig)
+		}
+		State::Stopped => (CLD_STOPPED, termsig),
+		State::Running | State::Sleeping => (CLD_CONTINUED, Signal::SIGCONT.get_id() as _),
+	};
+	let usage = proc.get_rusage();
+	SigInfo::child(
+		code,
+		pid as _,
+		proc.access_profile.uid,
+		status,
+		rusage::to_clock_ticks(usage.ru_utime.to_nano()) as _,
+		rusage::to_clock_ticks(usage.ru_stime.to_nano()) as _,
+	)
+}
+
+/// Checks the `options` given to `waitid`.
+///
+/// At least one of the state changes to wait for must be given.
+fn check_options(options: c_int) -> EResult<()> {
+	if options & !(WNOHANG | WEXITED | WSTOPPED | WCONTINUED | WNOWAIT) != 0
+		|| options & (WEXITED | WSTOPPED | WCONTINUED) == 0
+	{
+		return Err(errno!(EINVAL));
+	}
+	Ok(())
+}
+
+/// Translates the target `id` of type `idtype` into the semantics of `waitpid`.
+///
+/// [`P_PIDFD`] is not handled here since it requires a file descriptor lookup.
+fn to_waitpid_target(idtype: c_int, id: c_int) -> EResult<i32> {
+	match idtype {
+		P_ALL => Ok(-1),
+		P_PID if id > 0 => Ok(id),
+		P_PGID if id >= 0 => Ok(-id),
+		_ => Err(errno!(EINVAL)),
+	}
+}
+
+#[allow(clippy::type_complexity)]
+pub fn waitid(
+	Args((idtype, id, infop, options, rusage)): Args<(
+		c_int,
+		c_int,
+		SyscallPtr<SigInfo>,
+		c_int,
+		SyscallPtr<RUsage>,
+	)>,
+	proc: Arc<IntMutex<Process>>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	check_options(options)?;
+	// Translate the target into the semantics of `waitpid`
+	let pid = match idtype {
+		P_PIDFD => {
+			let file = get_pidfd(id, &fds)?;
+			// Cannot fail since `get_pidfd` checked the type of the file
+			let pidfd = file.get_buffer::<PidFd>().unwrap();
+			let target_mutex = pidfd.get_process().map_err(|_| errno!(ECHILD))?;
+			let ns = proc.lock().pid_ns().clone();
+			let pid = target_mutex.lock().get_pid_in(&ns);
+			if pid == 0 {
+				return Err(errno!(ECHILD));
+			}
+			pid as _
+		}
+		_ => to_waitpid_target(idtype, id)?,
+	};
+	let pid = waitpid::do_wait(pid, options, |proc, pid| {
+		infop.copy_to_user(get_siginfo(proc, pid as _))?;
+		rusage.copy_to_user(waitpid::get_rusage(proc))?;
+		Ok(())
+	})?;
+	// Nothing to report
+	if pid.is_none() {
+		infop.copy_to_user(SigInfo::default())?;
+		rusage.copy_to_user(RUsage::default())?;
+	}
+	Ok(0)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn waitid_args() {
+		// Options
+		assert_eq!(check_options(0), Err(errno!(EINVAL)));
+		assert_eq!(check_options(WNOHANG | WNOWAIT), Err(errno!(EINVAL)));
+		assert_eq!(check_options(WEXITED | 0x40), Err(errno!(EINVAL)));
+		check_options(WEXITED).unwrap();
+		check_options(WSTOPPED | WCONTINUED | WNOHANG | WNOWAIT).unwrap();
+		// ID types
+		assert_eq!(to_waitpid_target(P_ALL, 42), Ok(-1));
+		assert_eq!(to_waitpid_target(P_PID, 42), Ok(42));
+		assert_eq!(to_waitpid_target(P_PID, 0), Err(errno!(EINVAL)));
+		assert_eq!(to_waitpid_target(P_PID, -1), Err(errno!(EINVAL)));
+		assert_eq!(to_waitpid_target(P_PGID, 42), Ok(-42));
+		// Zero stands for the process group of the caller
+		assert_eq!(to_waitpid_target(P_PGID, 0), Ok(0));
+		assert_eq!(to_waitpid_target(P_PGID, -1), Err(errno!(EINVAL)));
+		assert_eq!(to_waitpid_target(4, 0), Err(errno!(EINVAL)));
+	}
+}
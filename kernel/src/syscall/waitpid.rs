@@ -21,8 +21,8 @@
 use crate::{
 	process,
 	process::{
-		mem_space::copy::SyscallPtr, pid::Pid, regs::Regs, rusage::RUsage, scheduler, Process,
-		State,
+		mem_space::copy::SyscallPtr, pid::Pid, ptrace, regs::Regs, rusage::RUsage, scheduler,
+		Process, State,
 	},
 	syscall::{waitpid::scheduler::SCHEDULER, Args},
 };
@@ -38,6 +38,8 @@ use utils::{
 pub const WNOHANG: i32 = 1;
 /// Wait flag. Returns if a child has stopped.
 pub const WUNTRACED: i32 = 2;
+/// Wait flag. Returns if a child has stopped. Used by `waitid`.
+pub const WSTOPPED: i32 = WUNTRACED;
 /// Wait flag. Returns if a child has terminated.
 pub const WEXITED: i32 = 4;
 /// Wait flag. Returns if a stopped child has been resumed by delivery of
@@ -60,9 +62,18 @@ fn iter_targets(curr_proc: &Process, pid: i32) -> impl Iterator<Item = Pid> + '_
 		let res = match pid {
 			// FIXME: must wait for any child process whose pgid is equal to -pid
 			..-1 => curr_proc.get_group_processes().get(i).cloned(),
-			-1 => curr_proc.get_children().get(i).cloned(),
+			// Children, then tracees
+			-1 => {
+				let children = curr_proc.get_children();
+				children
+					.get(i)
+					.or_else(|| curr_proc.get_tracees().get(i - children.len()))
+					.cloned()
+			}
 			0 => curr_proc.get_group_processes().get(i).cloned(),
-			_ => (i == 0).then_some(pid as _),
+			_ => (i == 0)
+				.then(|| curr_proc.pid_ns().to_global(pid as _))
+				.flatten(),
 		};
 		i += 1;
 		res
@@ -73,36 +84,70 @@ fn iter_targets(curr_proc: &Process, pid: i32) -> impl Iterator<Item = Pid> + '_
 fn get_wstatus(proc: &Process) -> i32 {
 	let status = proc.get_exit_status().unwrap_or(0);
 	let termsig = proc.get_termsig();
-	#[allow(clippy::let_and_return)]
-	let wstatus = match proc.get_state() {
+	let mut wstatus = match proc.get_state() {
 		State::Running | State::Sleeping => 0xffff,
-		State::Stopped => ((termsig as i32 & 0xff) << 8) | 0x7f,
+		State::Stopped => {
+			let event = proc
+				.get_trace()
+				.filter(|trace| trace.is_stopped())
+				.map(|trace| trace.event)
+				.unwrap_or(0);
+			((event as i32) << 16) | ((termsig as i32 & 0xff) << 8) | 0x7f
+		}
 		State::Zombie => ((status as i32 & 0xff) << 8) | (termsig as i32 & 0x7f),
 	};
-	// TODO
-	/*if coredump {
+	if proc.is_coredump() {
 		wstatus |= 0x80;
-	}*/
+	}
 	wstatus
 }
 
+/// Returns the resource usage of the given process, including the usage of its own terminated
+/// children.
+pub fn get_rusage(proc: &Process) -> RUsage {
+	let mut usage = proc.get_rusage().clone();
+	usage.add(proc.get_children_rusage());
+	usage
+}
+
+/// Tells whether the state of a process must be reported to a waiting process.
+///
+/// Arguments:
+/// - `state` is the state of the process.
+/// - `traced` tells whether the waiting process is the tracer of the process. If the process is
+///   not traced, the value is `None`.
+/// - `trace_stopped` tells whether the process is stopped by its tracer.
+/// - `options` is the set of flags given to the system call.
+fn is_reportable(state: &State, traced: Option<bool>, trace_stopped: bool, options: i32) -> bool {
+	let is_tracer = traced == Some(true);
+	// A stop caused by the tracer is reported only to the tracer
+	let stopped = matches!(state, State::Stopped)
+		&& ((is_tracer && trace_stopped) || (options & WUNTRACED != 0 && !trace_stopped));
+	// The exit of a tracee is reported to its tracer before its parent
+	let exited = options & WEXITED != 0
+		&& matches!(state, State::Zombie)
+		&& (traced.is_none() || is_tracer);
+	let continued = options & WCONTINUED != 0 && matches!(state, State::Running | State::Sleeping);
+	stopped || exited || continued
+}
+
 /// Waits upon a process and returns it. If no process can be waited upon, the function returns
 /// `None`.
 ///
 /// Arguments:
 /// - `curr_proc` is the current process.
 /// - `pid` is the constraint given to the system call.
-/// - `wstatus` is the pointer to the wait status.
 /// - `options` is a set of flags.
-/// - `rusage` is the pointer to the resource usage structure.
-fn get_waitable(
+/// - `report` is called with the process and its PID in the namespace of the current process, to
+///   write the information to be returned to userspace.
+fn get_waitable<F: FnMut(&Process, Pid) -> EResult<()>>(
 	curr_proc: &mut Process,
 	pid: i32,
-	wstatus: &SyscallPtr<i32>,
 	options: i32,
-	rusage: &SyscallPtr<RUsage>,
+	report: &mut F,
 ) -> EResult<Option<Pid>> {
 	let mut empty = true;
+	let curr_pid = curr_proc.get_pid();
 	let mut sched = SCHEDULER.get().lock();
 	// Find a waitable process
 	let proc = iter_targets(curr_proc, pid)
@@ -111,12 +156,9 @@ fn get_waitable(
 		// Select a waitable process
 		.find(|proc| {
 			let proc = proc.lock();
-			let state = proc.get_state();
-			let stopped = options & WUNTRACED != 0 && matches!(state, State::Stopped);
-			let exited = options & WEXITED != 0 && matches!(state, State::Zombie);
-			let continued =
-				options & WCONTINUED != 0 && matches!(state, State::Running | State::Sleeping);
-			proc.is_waitable() && (stopped || exited || continued)
+			let traced = proc.get_trace().map(|trace| trace.tracer == curr_pid);
+			let trace_stopped = proc.get_trace().is_some_and(|trace| trace.is_stopped());
+			proc.is_waitable() && is_reportable(&proc.get_state(), traced, trace_stopped, options)
 		});
 	let Some(proc) = proc else {
 		return if empty {
@@ -128,45 +170,69 @@ fn get_waitable(
 	};
 	let mut proc = proc.lock();
 	let pid = proc.get_pid();
+	// The PID returned to userspace is the one in the namespace of the current process
+	let ns_pid = proc.get_pid_in(curr_proc.pid_ns());
 	// Write values back
-	wstatus.copy_to_user(get_wstatus(&proc))?;
-	rusage.copy_to_user(proc.get_rusage().clone())?;
+	report(&proc, ns_pid)?;
 	// Clear the waitable flag if requested
 	if options & WNOWAIT == 0 {
+		let zombie = matches!(proc.get_state(), State::Zombie);
+		// The tracer of an exited process releases it, letting the parent wait on it
+		if zombie && proc.get_trace().is_some() {
+			ptrace::detach(&mut proc, None);
+			curr_proc.remove_tracee(pid);
+			if !curr_proc.get_children().contains(&pid) {
+				let parent = proc.get_parent();
+				drop(proc);
+				drop(sched);
+				if let Some(parent) = parent {
+					parent.lock().wake();
+				}
+				return Ok(Some(ns_pid));
+			}
+		}
 		proc.clear_waitable();
 		// If the process was a zombie, remove it
-		if matches!(proc.get_state(), State::Zombie) {
+		if zombie {
+			curr_proc.add_child_rusage(&proc);
 			drop(proc);
 			curr_proc.remove_child(pid);
 			sched.remove_process(pid);
 		}
 	}
-	Ok(Some(pid))
+	Ok(Some(ns_pid))
 }
 
-/// Executes the `waitpid` system call.
-pub fn do_waitpid(
+/// Waits for a process to change state.
+///
+/// Arguments:
+/// - `pid` is the constraint on the processes to wait for, with the semantics of `waitpid`.
+/// - `options` is a set of flags.
+/// - `report` is called with the process and its PID in the namespace of the current process, to
+///   write the information to be returned to userspace.
+///
+/// If [`WNOHANG`] is set and no process is waitable, the function returns `None`.
+pub fn do_wait<F: FnMut(&Process, Pid) -> EResult<()>>(
 	pid: i32,
-	wstatus: SyscallPtr<i32>,
 	options: i32,
-	rusage: SyscallPtr<RUsage>,
-) -> EResult<usize> {
+	mut report: F,
+) -> EResult<Option<Pid>> {
 	// Sleep until a target process is waitable
 	loop {
 		{
 			let proc_mutex = Process::current();
 			let mut proc = proc_mutex.lock();
 			if proc.next_signal(true).is_some() {
-				return Err(errno!(EINTR));
+				return Err(errno!(ERESTARTSYS));
 			}
-			let result = get_waitable(&mut proc, pid, &wstatus, options, &rusage)?;
+			let result = get_waitable(&mut proc, pid, options, &mut report)?;
 			// On success, return
-			if let Some(p) = result {
-				return Ok(p as _);
+			if result.is_some() {
+				return Ok(result);
 			}
 			// If the flag is set, do not wait
 			if options & WNOHANG != 0 {
-				return Ok(0);
+				return Ok(None);
 			}
 			// When a child process is paused or resumed by a signal or is terminated, it
 			// changes the state of the current process to wake it up
@@ -176,8 +242,69 @@ pub fn do_waitpid(
 	}
 }
 
+/// Executes the `waitpid` system call.
+pub fn do_waitpid(
+	pid: i32,
+	wstatus: SyscallPtr<i32>,
+	options: i32,
+	rusage: SyscallPtr<RUsage>,
+) -> EResult<usize> {
+	let pid = do_wait(pid, options, |proc, _| {
+		wstatus.copy_to_user(get_wstatus(proc))?;
+		rusage.copy_to_user(get_rusage(proc))?;
+		Ok(())
+	})?;
+	Ok(pid.unwrap_or(0) as _)
+}
+
 pub fn waitpid(
 	Args((pid, wstatus, options)): Args<(c_int, SyscallPtr<c_int>, c_int)>,
 ) -> EResult<usize> {
 	do_waitpid(pid, wstatus, options | WEXITED, SyscallPtr(None))
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn wait_reportable() {
+		let all = WEXITED | WUNTRACED | WCONTINUED;
+		// Each state is reported only with the corresponding option
+		assert!(is_reportable(&State::Zombie, None, false, WEXITED));
+		assert!(!is_reportable(
+			&State::Zombie,
+			None,
+			false,
+			WUNTRACED | WCONTINUED
+		));
+		assert!(is_reportable(&State::Stopped, None, false, WSTOPPED));
+		assert!(!is_reportable(
+			&State::Stopped,
+			None,
+			false,
+			WEXITED | WCONTINUED
+		));
+		assert!(is_reportable(&State::Running, None, false, WCONTINUED));
+		assert!(is_reportable(&State::Sleeping, None, false, WCONTINUED));
+		assert!(!is_reportable(
+			&State::Running,
+			None,
+			false,
+			WEXITED | WUNTRACED
+		));
+		// A stop caused by the tracer is reported to the tracer only, even without `WUNTRACED`
+		assert!(is_reportable(&State::Stopped, Some(true), true, WEXITED));
+		assert!(!is_reportable(&State::Stopped, Some(false), true, all));
+		// A stop caused by a signal is reported to the parent of a tracee
+		assert!(is_reportable(
+			&State::Stopped,
+			Some(false),
+			false,
+			WUNTRACED
+		));
+		// The exit of a tracee is reported to the tracer first
+		assert!(is_reportable(&State::Zombie, Some(true), false, WEXITED));
+		assert!(!is_reportable(&State::Zombie, Some(false), false, all));
+	}
+}
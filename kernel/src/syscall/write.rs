@@ -22,7 +22,7 @@ use super::Args;
 use crate::{
 	file::{fd::FileDescriptorTable, FileType},
 	idt,
-	process::{mem_space::copy::SyscallSlice, regs::Regs, scheduler, Process},
+	process::{mem_space::copy::SyscallSlice, regs::Regs, rlimit, scheduler, Process},
 	syscall::Signal,
 };
 use core::{cmp::min, ffi::c_int, sync::atomic};
@@ -50,10 +50,11 @@ pub fn write(
 	if file.get_type()? == FileType::Link {
 		return Err(errno!(EINVAL));
 	}
+	let off = file.off.load(atomic::Ordering::Acquire);
+	let len = rlimit::check_fsize(&file, off, len)?;
 	// TODO find a way to avoid allocating here
 	let buf_slice = buf.copy_from_user(..len)?.ok_or(errno!(EFAULT))?;
 	// Write file
-	let off = file.off.load(atomic::Ordering::Acquire);
 	let len = file.ops.write(&file, off, &buf_slice)?;
 	// Update offset
 	let new_off = off.saturating_add(len as u64);
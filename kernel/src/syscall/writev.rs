@@ -23,7 +23,7 @@ use crate::{
 	process::{
 		iovec::IOVec,
 		mem_space::{copy::SyscallSlice, MemSpace},
-		scheduler,
+		rlimit, scheduler,
 		signal::Signal,
 		Process,
 	},
@@ -55,9 +55,16 @@ fn write(
 ) -> EResult<usize> {
 	let mut off = 0;
 	let iov = iov.copy_from_user(..iovcnt)?.ok_or(errno!(EFAULT))?;
+	// The total size that may be written, according to the file size limit
+	let total = iov
+		.iter()
+		.fold(0, |total: usize, i| total.saturating_add(i.iov_len));
+	let start = offset.unwrap_or_else(|| file.off.load(atomic::Ordering::Acquire));
+	let max = rlimit::check_fsize(file, start, min(total, i32::MAX as usize))?;
 	for i in iov {
-		// The size to write. This is limited to avoid an overflow on the total length
-		let l = min(i.iov_len, i32::MAX as usize - off);
+		// The size to write. This is limited to avoid an overflow on the total length, and to
+		// respect the file size limit
+		let l = min(i.iov_len, max - off);
 		let ptr = SyscallSlice::<u8>::from_syscall_arg(i.iov_base as usize);
 		if let Some(buf) = ptr.copy_from_user(..l)? {
 			let len = if let Some(offset) = offset {
@@ -18,9 +18,12 @@
 
 //! This module implements system clocks.
 
-use crate::time::{
-	unit::{ClockIdT, TimeUnit},
-	Timestamp, TimestampScale,
+use crate::{
+	process::Process,
+	time::{
+		unit::{ClockIdT, TimeUnit},
+		Timestamp, TimestampScale,
+	},
 };
 use core::{cmp::max, sync::atomic};
 use utils::{errno, errno::EResult, lock::atomic::AtomicU64};
@@ -67,6 +70,14 @@ pub fn update(delta: Timestamp) {
 	BOOTTIME.fetch_add(delta as _, atomic::Ordering::Relaxed);
 }
 
+/// Returns the time elapsed since boot, in nanoseconds.
+///
+/// Contrary to [`current_time`], this function cannot fail and does not lock any process. Thus,
+/// it can be used from interrupt context.
+pub fn boottime() -> Timestamp {
+	BOOTTIME.load(atomic::Ordering::Relaxed)
+}
+
 /// Returns the current timestamp according to the clock with the given ID.
 ///
 /// Arguments:
@@ -74,6 +85,8 @@ pub fn update(delta: Timestamp) {
 /// - `scale` is the scale of the timestamp to return.
 ///
 /// If the clock is invalid, the function returns an error.
+///
+/// The CPU-time clocks lock the current process, thus the caller must not be holding it.
 pub fn current_time(clk: ClockIdT, scale: TimestampScale) -> EResult<Timestamp> {
 	// TODO implement all clocks
 	let raw_ts = match clk {
@@ -83,7 +96,15 @@ pub fn current_time(clk: ClockIdT, scale: TimestampScale) -> EResult<Timestamp>
 			let monotonic = MONOTONIC.load(atomic::Ordering::Relaxed);
 			max(realtime, monotonic)
 		}
-		CLOCK_BOOTTIME | CLOCK_BOOTTIME_ALARM => BOOTTIME.load(atomic::Ordering::Relaxed),
+		CLOCK_BOOTTIME | CLOCK_BOOTTIME_ALARM => boottime(),
+		// Each process has a single thread, thus both clocks are the same
+		CLOCK_PROCESS_CPUTIME_ID | CLOCK_THREAD_CPUTIME_ID => {
+			let proc_mutex = Process::current_opt().ok_or_else(|| errno!(EINVAL))?;
+			let mut proc = proc_mutex.lock();
+			// The clock is read from kernelspace
+			proc.account_cpu(false);
+			proc.get_rusage().cpu_time()
+		}
 		_ => return Err(errno!(EINVAL)),
 	};
 
@@ -28,6 +28,7 @@ pub mod clock;
 pub mod hw;
 pub mod timer;
 pub mod unit;
+pub mod wheel;
 
 use crate::{event, event::CallbackResult};
 use core::mem::ManuallyDrop;
@@ -58,6 +59,7 @@ pub(crate) fn init() -> EResult<()> {
 			// FIXME: the value is probably not right
 			clock::update(i64::from(freq * 1_000_000_000) as _);
 			timer::tick();
+			wheel::tick();
 
 			CallbackResult::Continue
 		})?;
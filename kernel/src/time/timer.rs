@@ -20,27 +20,46 @@
 
 use super::{
 	clock,
-	unit::{ClockIdT, ITimerspec32, TimeUnit, TimerT, Timespec, TimestampScale},
+	clock::{CLOCK_MONOTONIC, CLOCK_PROCESS_CPUTIME_ID, CLOCK_THREAD_CPUTIME_ID},
+	unit::{ClockIdT, ITimerspec32, ITimerval, TimeUnit, TimerT, Timespec, TimestampScale},
 };
 use crate::{
+	file::timerfd::TimerFdState,
 	process::{
 		oom,
 		pid::Pid,
-		signal::{SigEvent, Signal, SIGEV_SIGNAL, SIGEV_THREAD},
+		signal::{SigEvent, SigInfo, Signal, SIGEV_NONE, SIGEV_SIGNAL, SIGEV_THREAD_ID},
 		Process,
 	},
 	time::unit::Timespec32,
 };
+use core::ffi::c_int;
 use utils::{
 	collections::{btreemap::BTreeMap, hashmap::HashMap, id_allocator::IDAllocator},
 	errno,
 	errno::{AllocResult, EResult},
-	limits::TIMER_MAX,
+	limits::{DELAYTIMER_MAX, TIMER_MAX},
 	lock::IntMutex,
+	ptr::arc::Arc,
 };
 
-// TODO make sure a timer doesn't send a signal to a thread that do not belong to the manager's
-// process
+/// Timer flag: the specified expiration time is absolute, instead of relative to the current time
+/// of the clock.
+pub const TIMER_ABSTIME: c_int = 1;
+
+/// Interval timer decrementing in real time, and delivering `SIGALRM` on expiration.
+pub const ITIMER_REAL: c_int = 0;
+/// Interval timer decrementing when the process is executing in userspace, and delivering
+/// `SIGVTALRM` on expiration.
+pub const ITIMER_VIRTUAL: c_int = 1;
+/// Interval timer decrementing when the process is executing, either in userspace or in
+/// kernelspace, and delivering `SIGPROF` on expiration.
+pub const ITIMER_PROF: c_int = 2;
+
+/// The ID of the `ITIMER_REAL` timer in the timers queue.
+///
+/// This ID cannot be returned by the timers ID allocator.
+const ITIMER_REAL_ID: TimerT = TimerT::MAX;
 
 /// Structure representing a per-process timer.
 pub struct Timer {
@@ -48,11 +67,15 @@ pub struct Timer {
 	clockid: ClockIdT,
 	/// Definition of the action to perform when the timer is triggered.
 	sevp: SigEvent,
+	/// If the timer backs a timerfd, the state of the timerfd. In this case, `sevp` is ignored.
+	timerfd: Option<Arc<TimerFdState>>,
 
 	/// The timer's interval between firing.
 	interval: Timespec32,
 	/// The next timestamp at which the timer will expire. If `None`, the timer is unarmed.
 	next: Option<Timespec>,
+	/// The number of expirations that were missed before the last one.
+	overrun: u32,
 }
 
 impl Timer {
@@ -62,7 +85,10 @@ impl Timer {
 	/// - `clockid` is the ID of the clock to use.
 	/// - `sevp` describes the event to be triggered by the clock.
 	pub fn new(clockid: ClockIdT, sevp: SigEvent) -> EResult<Self> {
-		// Check arguments are valid
+		// Check arguments are valid. CPU-time clocks are not supported
+		if matches!(clockid, CLOCK_PROCESS_CPUTIME_ID | CLOCK_THREAD_CPUTIME_ID) {
+			return Err(errno!(EINVAL));
+		}
 		let _ = clock::current_time(clockid, TimestampScale::Nanosecond)?;
 		if !sevp.is_valid() {
 			return Err(errno!(EINVAL));
@@ -71,9 +97,11 @@ impl Timer {
 		Ok(Self {
 			clockid,
 			sevp,
+			timerfd: None,
 
 			interval: Default::default(),
 			next: Default::default(),
+			overrun: 0,
 		})
 	}
 
@@ -97,120 +125,150 @@ impl Timer {
 		self.interval.is_zero()
 	}
 
+	/// Returns the current time of the timer's clock.
+	#[inline]
+	pub fn get_clock_time(&self) -> Timespec32 {
+		clock::current_time_struct(self.clockid).unwrap()
+	}
+
 	/// Returns the current state of the timer.
 	#[inline]
 	pub fn get_time(&self) -> ITimerspec32 {
 		let ts: Timespec = clock::current_time_struct(self.clockid).unwrap();
-		let value = self.next.map(|next| next - ts).unwrap_or_default();
-
+		// A timer that has expired but has not been fired yet is still armed
+		let value = self
+			.next
+			.map(|next| next.to_nano().saturating_sub(ts.to_nano()).max(1))
+			.unwrap_or(0);
 		ITimerspec32 {
 			it_interval: self.interval,
-			it_value: Timespec32 {
-				tv_nsec: value.tv_nsec as _,
-				tv_sec: value.tv_sec as _,
-			},
+			it_value: Timespec32::from_nano(value),
 		}
 	}
 
-	/// Computes the timestamp at which the timer will fire next.
-	///
-	/// Arguments:
-	/// - `spec` is the timer's setting.
-	/// - `ts` is the current timestamp.
-	fn compute_next(spec: &ITimerspec32, ts: Timespec32) -> Timespec {
-		let time = if spec.it_value.is_zero() {
-			spec.it_interval
-		} else {
-			ts + spec.it_value
-		};
-
-		Timespec {
-			tv_nsec: time.tv_nsec as _,
-			tv_sec: time.tv_sec as _,
-		}
+	/// Returns the number of expirations that were missed before the last one.
+	#[inline]
+	pub fn get_overrun(&self) -> u32 {
+		self.overrun
 	}
 
 	/// Sets the timer's state.
 	///
 	/// Arguments:
-	/// - `spec` is the new setting of the timer.
+	/// - `spec` is the new setting of the timer. `it_value` is relative to the current time of the
+	///   timer's clock. If zero, the timer is disarmed.
 	/// - `pid` is the PID of the process associated with the timer.
 	/// - `timer_id` is the ID of the timer.
 	///
 	/// On allocation error, the function returns an error.
-	#[inline]
 	pub fn set_time(&mut self, spec: ITimerspec32, pid: Pid, timer_id: TimerT) -> EResult<()> {
-		let mut queue = TIMERS_QUEUE.lock();
-		if let Some(next) = self.next {
-			queue.remove(&(next, pid, timer_id));
-		}
-
-		let ts: Timespec32 = clock::current_time_struct(self.clockid).unwrap();
-		let next = Self::compute_next(&spec, ts);
+		self.disarm(pid, timer_id);
 		self.interval = spec.it_interval;
+		self.overrun = 0;
+		if spec.it_value.is_zero() {
+			return Ok(());
+		}
+		let ts: Timespec = clock::current_time_struct(self.clockid).unwrap();
+		let next = Timespec::from_nano(ts.to_nano().saturating_add(spec.it_value.to_nano()));
+		TIMERS_QUEUE.lock().insert((next, pid, timer_id), ())?;
 		self.next = Some(next);
-
-		queue.insert((next, pid, timer_id), ())?;
 		Ok(())
 	}
 
+	/// Disarms the timer.
+	///
+	/// Arguments:
+	/// - `pid` is the PID of the process associated with the timer.
+	/// - `timer_id` is the ID of the timer.
+	pub fn disarm(&mut self, pid: Pid, timer_id: TimerT) {
+		if let Some(next) = self.next.take() {
+			TIMERS_QUEUE.lock().remove(&(next, pid, timer_id));
+		}
+	}
+
 	/// Fires the timer.
 	///
-	/// `proc` is the process to which the timer is fired.
-	pub fn fire(&mut self, proc: &mut Process) {
+	/// Arguments:
+	/// - `proc` is the process to which the timer is fired.
+	/// - `timer_id` is the ID of the timer.
+	pub fn fire(&mut self, proc: &mut Process, timer_id: TimerT) {
 		match self.sevp.sigev_notify {
-			SIGEV_SIGNAL => {
+			// The target thread has been checked at creation
+			SIGEV_SIGNAL | SIGEV_THREAD_ID => {
 				let Ok(signal) = Signal::try_from(self.sevp.sigev_signo) else {
 					return;
 				};
-				// TODO on sigint_t, set si_code to SI_TIMER
-				proc.kill(signal);
+				let info = SigInfo::timer(
+					signal,
+					timer_id as _,
+					self.overrun as _,
+					self.sevp.sigev_value,
+				);
+				proc.kill_info(signal, info);
 			}
-			SIGEV_THREAD => todo!(), // TODO
 			_ => {}
 		}
 	}
 
-	/// Resets the timer to be fired again.
+	/// Updates the state of the timer after it expired at the timestamp `ts`.
 	///
-	/// Arguments:
-	/// - `queue` is the queue.
-	/// - `ts` is the timestamp.
-	/// - `pid` is the PID of the process associated with the timer.
-	/// - `timer_id` is the ID of the timer.
+	/// If the timer is periodic, it is rearmed for the first period ending after `ts`, and the
+	/// periods that have been skipped are counted as overrun.
 	///
-	/// On allocation error, the function returns an error.
-	fn reset(
-		&mut self,
-		queue: &mut BTreeMap<(Timespec, Pid, TimerT), ()>,
-		ts: Timespec,
-		pid: Pid,
-		timer_id: TimerT,
-	) -> AllocResult<()> {
-		if let Some(next) = self.next {
-			queue.remove(&(next, pid, timer_id));
-		}
-
-		if self.interval.is_zero() {
-			self.next = None;
-			return Ok(());
+	/// The function returns the next timestamp at which the timer will expire, if any.
+	fn expire(&mut self, ts: &Timespec) -> Option<Timespec> {
+		let next = self.next.take()?;
+		let interval = self.interval.to_nano();
+		if interval == 0 {
+			self.overrun = 0;
+			return None;
 		}
+		let missed = ts.to_nano().saturating_sub(next.to_nano()) / interval;
+		self.overrun = missed.min(DELAYTIMER_MAX as u64) as u32;
+		let next = next
+			.to_nano()
+			.saturating_add((missed + 1).saturating_mul(interval));
+		self.next = Some(Timespec::from_nano(next));
+		self.next
+	}
+}
 
-		let next = Self::compute_next(
-			&ITimerspec32 {
-				it_interval: self.interval,
-				it_value: self.interval,
-			},
-			Timespec32 {
-				tv_nsec: ts.tv_nsec as _,
-				tv_sec: ts.tv_sec as _,
-			},
-		);
-		queue.insert((next, pid, timer_id), ())?;
+/// An interval timer measuring the CPU time consumed by a process.
+#[derive(Default)]
+struct CpuTimer {
+	/// The remaining CPU time before expiration, in nanoseconds. If zero, the timer is unarmed.
+	value: u64,
+	/// The value in nanoseconds to which the timer is reloaded on expiration.
+	interval: u64,
+}
 
-		self.next = Some(next);
+impl CpuTimer {
+	/// Returns the state of the timer.
+	fn get(&self) -> ITimerval {
+		ITimerval {
+			it_interval: TimeUnit::from_nano(self.interval),
+			it_value: TimeUnit::from_nano(self.value),
+		}
+	}
 
-		Ok(())
+	/// Charges `delta` nanoseconds of CPU time to the timer.
+	///
+	/// The function returns `true` if the timer expired.
+	fn consume(&mut self, delta: u64) -> bool {
+		if self.value == 0 {
+			return false;
+		}
+		if delta < self.value {
+			self.value -= delta;
+			return false;
+		}
+		// The exceeding time is charged to the next period
+		let exceeding = delta - self.value;
+		self.value = match self.interval {
+			0 => 0,
+			interval => interval - exceeding % interval,
+		};
+		true
 	}
 }
 
@@ -223,6 +281,13 @@ pub struct TimerManager {
 	id_allocator: IDAllocator,
 	/// The list of timers for the process. The key is the ID of the timer.
 	timers: HashMap<u32, Timer>,
+
+	/// The `ITIMER_REAL` interval timer.
+	real: Timer,
+	/// The `ITIMER_VIRTUAL` interval timer.
+	virt: CpuTimer,
+	/// The `ITIMER_PROF` interval timer.
+	prof: CpuTimer,
 }
 
 impl TimerManager {
@@ -233,9 +298,30 @@ impl TimerManager {
 
 			id_allocator: IDAllocator::new(TIMER_MAX as _)?,
 			timers: HashMap::new(),
+
+			real: Timer {
+				clockid: CLOCK_MONOTONIC,
+				sevp: SigEvent {
+					sigev_notify: SIGEV_SIGNAL,
+					sigev_signo: Signal::SIGALRM.get_id() as _,
+					..Default::default()
+				},
+				timerfd: None,
+
+				interval: Default::default(),
+				next: None,
+				overrun: 0,
+			},
+			virt: Default::default(),
+			prof: Default::default(),
 		})
 	}
 
+	/// Returns the PID of the process associated with the manager.
+	pub fn get_pid(&self) -> Pid {
+		self.pid
+	}
+
 	/// Creates a timer.
 	///
 	/// Arguments:
@@ -244,7 +330,31 @@ impl TimerManager {
 	///
 	/// On success, the function returns the ID of the newly created timer.
 	pub fn create_timer(&mut self, clockid: ClockIdT, sevp: SigEvent) -> EResult<u32> {
+		// The thread to signal must belong to the process
+		if sevp.sigev_notify == SIGEV_THREAD_ID && sevp.sigev_notify_thread_id != self.pid as _ {
+			return Err(errno!(EINVAL));
+		}
 		let timer = Timer::new(clockid, sevp)?;
+		self.insert_timer(timer)
+	}
+
+	/// Creates a timer backing a timerfd, whose state is `state`.
+	///
+	/// On success, the function returns the ID of the newly created timer.
+	pub fn create_timerfd(&mut self, clockid: ClockIdT, state: Arc<TimerFdState>) -> EResult<u32> {
+		let mut timer = Timer::new(
+			clockid,
+			SigEvent {
+				sigev_notify: SIGEV_NONE,
+				..Default::default()
+			},
+		)?;
+		timer.timerfd = Some(state);
+		self.insert_timer(timer)
+	}
+
+	/// Inserts the given timer and returns its ID.
+	fn insert_timer(&mut self, timer: Timer) -> EResult<u32> {
 		let id = self.id_allocator.alloc(None)?;
 		if let Err(e) = self.timers.insert(id, timer) {
 			self.id_allocator.free(id);
@@ -265,11 +375,78 @@ impl TimerManager {
 	///
 	/// If the timer doesn't exist, the function returns an error.
 	pub fn delete_timer(&mut self, id: TimerT) -> EResult<()> {
-		self.timers
+		let mut timer = self
+			.timers
 			.remove(&(id as _))
 			.ok_or_else(|| errno!(EINVAL))?;
+		timer.disarm(self.pid, id);
+		self.id_allocator.free(id as _);
 		Ok(())
 	}
+
+	/// Returns the state of the interval timer `which`.
+	///
+	/// If `which` is invalid, the function returns an error.
+	pub fn get_itimer(&self, which: c_int) -> EResult<ITimerval> {
+		match which {
+			ITIMER_REAL => {
+				let spec = self.real.get_time();
+				Ok(ITimerval {
+					it_interval: TimeUnit::from_nano(spec.it_interval.to_nano()),
+					it_value: TimeUnit::from_nano(spec.it_value.to_nano()),
+				})
+			}
+			ITIMER_VIRTUAL => Ok(self.virt.get()),
+			ITIMER_PROF => Ok(self.prof.get()),
+			_ => Err(errno!(EINVAL)),
+		}
+	}
+
+	/// Sets the state of the interval timer `which` to `value`. If the value of `value` is zero,
+	/// the timer is disarmed.
+	///
+	/// The function returns the previous state of the timer.
+	///
+	/// If `which` is invalid, the function returns an error.
+	pub fn set_itimer(&mut self, which: c_int, value: ITimerval) -> EResult<ITimerval> {
+		let old = self.get_itimer(which)?;
+		let interval = value.it_interval.to_nano();
+		let val = value.it_value.to_nano();
+		match which {
+			ITIMER_REAL => self.real.set_time(
+				ITimerspec32 {
+					it_interval: Timespec32::from_nano(interval),
+					it_value: Timespec32::from_nano(val),
+				},
+				self.pid,
+				ITIMER_REAL_ID,
+			)?,
+			ITIMER_VIRTUAL => {
+				self.virt = CpuTimer {
+					value: val,
+					interval,
+				}
+			}
+			_ => {
+				self.prof = CpuTimer {
+					value: val,
+					interval,
+				}
+			}
+		}
+		Ok(old)
+	}
+
+	/// Charges `delta` nanoseconds of CPU time to the CPU interval timers.
+	///
+	/// `user` tells whether the time has been spent in userspace.
+	///
+	/// The function returns the signals to be sent to the process for timers that expired.
+	pub fn consume_cpu_time(&mut self, delta: u64, user: bool) -> [Option<Signal>; 2] {
+		let virt = (user && self.virt.consume(delta)).then_some(Signal::SIGVTALRM);
+		let prof = self.prof.consume(delta).then_some(Signal::SIGPROF);
+		[virt, prof]
+	}
 }
 
 impl Drop for TimerManager {
@@ -313,7 +490,12 @@ pub(super) fn tick() {
 		let mut timer_manager = timer_manager_mutex.lock();
 
 		// Get timer
-		let Some(timer) = timer_manager.get_timer_mut(timer_id) else {
+		let timer = if timer_id == ITIMER_REAL_ID {
+			Some(&mut timer_manager.real)
+		} else {
+			timer_manager.get_timer_mut(timer_id)
+		};
+		let Some(timer) = timer else {
 			// invalid timer, remove
 			queue.pop_first();
 			break;
@@ -334,12 +516,100 @@ pub(super) fn tick() {
 			break;
 		}
 
-		timer.fire(&mut proc);
-
-		if timer.is_oneshot() {
-			queue.pop_first();
+		queue.pop_first();
+		if let Some(next) = timer.expire(&ts) {
+			oom::wrap(|| queue.insert((next, pid, timer_id), ()));
+		}
+		if timer_id == ITIMER_REAL_ID {
+			proc.kill(Signal::SIGALRM);
 		} else {
-			oom::wrap(|| timer.reset(&mut queue, ts, pid, timer_id));
+			timer.fire(&mut proc, timer_id);
+		}
+		let timerfd = timer.timerfd.clone();
+
+		// Waiters may include the owner of the timer, which must be unlocked first
+		drop(timer_manager);
+		drop(proc);
+		if let Some(timerfd) = timerfd {
+			timerfd.expire();
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Returns a timer expiring at `next` nanoseconds, with an interval of `interval`
+	/// nanoseconds.
+	fn timer(next: u64, interval: u64) -> Timer {
+		Timer {
+			clockid: clock::CLOCK_MONOTONIC,
+			sevp: SigEvent::default(),
+			timerfd: None,
+
+			interval: Timespec32::from_nano(interval),
+			next: Some(Timespec::from_nano(next)),
+			overrun: 0,
+		}
+	}
+
+	#[test_case]
+	fn timer_expire_oneshot() {
+		let mut t = timer(1000, 0);
+		assert!(t.expire(&Timespec::from_nano(1500)).is_none());
+		assert!(!t.is_armed());
+		assert_eq!(t.get_overrun(), 0);
+		// A disarmed timer does not expire again
+		assert!(t.expire(&Timespec::from_nano(2000)).is_none());
+	}
+
+	#[test_case]
+	fn timer_expire_periodic() {
+		// Fired on time
+		let mut t = timer(1000, 100);
+		let next = t.expire(&Timespec::from_nano(1000)).unwrap();
+		assert_eq!(next.to_nano(), 1100);
+		assert_eq!(t.get_overrun(), 0);
+		// Three periods missed: rearmed for the first period ending after the current time
+		let next = t.expire(&Timespec::from_nano(1450)).unwrap();
+		assert_eq!(next.to_nano(), 1500);
+		assert_eq!(t.get_overrun(), 3);
+		// The overrun count is reset on the next expiration
+		t.expire(&Timespec::from_nano(1500)).unwrap();
+		assert_eq!(t.get_overrun(), 0);
+		// The overrun count is clamped
+		let mut t = timer(0, 10);
+		let next = t.expire(&Timespec::from_nano(10_005)).unwrap();
+		assert_eq!(next.to_nano(), 10_010);
+		assert_eq!(t.get_overrun(), DELAYTIMER_MAX as u32);
+	}
+
+	#[test_case]
+	fn cpu_timer_consume() {
+		// Unarmed
+		let mut t = CpuTimer::default();
+		assert!(!t.consume(1000));
+		// One-shot
+		let mut t = CpuTimer {
+			value: 100,
+			interval: 0,
+		};
+		assert!(!t.consume(60));
+		assert_eq!(t.value, 40);
+		assert!(t.consume(40));
+		assert_eq!(t.value, 0);
+		assert!(!t.consume(1000));
+		// Periodic: the exceeding time is charged to the next period
+		let mut t = CpuTimer {
+			value: 100,
+			interval: 50,
+		};
+		assert!(t.consume(120));
+		assert_eq!(t.value, 30);
+		// Several periods elapsed at once still expire only once
+		assert!(t.consume(30 + 50 * 3 + 10));
+		assert_eq!(t.value, 40);
+		assert_eq!(t.get().it_interval.to_nano(), 50);
+	}
+}
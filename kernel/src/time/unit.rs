@@ -294,6 +294,16 @@ impl PartialOrd for Timespec32 {
 	}
 }
 
+/// Structure specifying the state of an interval timer.
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct ITimerval {
+	/// The interval between each firing of the timer.
+	pub it_interval: Timeval,
+	/// The time remaining until the next firing of the timer.
+	pub it_value: Timeval,
+}
+
 /// Structure specifying a timer's state.
 #[derive(Clone, Copy, Debug, Default)]
 #[repr(C)]
@@ -0,0 +1,325 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The timer wheel wakes up sleeping processes when their deadline is reached.
+//!
+//! The wheel is made of several levels of slots, each level having a coarser granularity than the
+//! previous one. A timeout is placed on the level matching how far its deadline is, then moved
+//! down to lower levels (*cascading*) as its deadline approaches. Thus, inserting and removing a
+//! timeout, and expiring the timeouts of a tick, do not depend on the number of pending timeouts.
+//!
+//! The wheel runs on [`CLOCK_BOOTTIME`], which is not affected by changes to the system's time.
+
+use crate::{
+	process::{oom, pid::Pid, Process},
+	time::{clock, clock::CLOCK_BOOTTIME, unit::TimestampScale},
+};
+use core::mem;
+use utils::{collections::vec::Vec, errno::AllocResult, lock::IntMutex};
+
+/// The duration of a tick of the wheel, in nanoseconds.
+const TICK: u64 = 1_000_000;
+/// The number of bits of a slot index.
+const LEVEL_BITS: u32 = 6;
+/// The number of slots in a level.
+const LEVEL_SIZE: usize = 1 << LEVEL_BITS;
+/// The mask of a slot index.
+const LEVEL_MASK: u64 = LEVEL_SIZE as u64 - 1;
+/// The number of levels in the wheel.
+const LEVELS_COUNT: usize = 5;
+/// The maximum distance between the current tick and the expiration of a timeout, in ticks.
+///
+/// Timeouts further in the future expire at this distance, and have to be registered again.
+const MAX_DELTA: u64 = (1 << (LEVEL_BITS * LEVELS_COUNT as u32)) - 1;
+
+/// A timeout placed in the wheel.
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+	/// The ID of the timeout.
+	id: u64,
+	/// The tick at which the timeout expires.
+	expires: u64,
+	/// The PID of the process to wake up.
+	pid: Pid,
+}
+
+/// A hierarchical timer wheel.
+struct TimerWheel {
+	/// The next tick to be processed.
+	current: u64,
+	/// The ID of the next timeout to be inserted.
+	next_id: u64,
+	/// The number of timeouts in the wheel.
+	count: usize,
+	/// The slots of each level.
+	levels: [[Vec<Entry>; LEVEL_SIZE]; LEVELS_COUNT],
+}
+
+impl TimerWheel {
+	/// Creates an empty wheel.
+	const fn new() -> Self {
+		Self {
+			current: 0,
+			next_id: 0,
+			count: 0,
+			levels: [const { [const { Vec::new() }; LEVEL_SIZE] }; LEVELS_COUNT],
+		}
+	}
+
+	/// Returns the level and index of the slot for a timeout expiring at the tick `expires`.
+	fn slot(&self, expires: u64) -> (usize, usize) {
+		let delta = expires - self.current;
+		let level = (0..LEVELS_COUNT)
+			.find(|l| delta >> (LEVEL_BITS * (*l as u32 + 1)) == 0)
+			.unwrap_or(LEVELS_COUNT - 1);
+		let index = (expires >> (LEVEL_BITS * level as u32)) & LEVEL_MASK;
+		(level, index as usize)
+	}
+
+	/// Places the given entry in its slot.
+	fn place(&mut self, entry: Entry) -> AllocResult<()> {
+		let (level, index) = self.slot(entry.expires);
+		self.levels[level][index].push(entry)
+	}
+
+	/// Inserts a timeout waking up the process `pid` at the tick `expires`.
+	///
+	/// An expiration in the past is processed at the next tick. An expiration too far in the
+	/// future is brought back to [`MAX_DELTA`] ticks from now.
+	///
+	/// The function returns the ID of the timeout and its actual expiration tick.
+	fn insert(&mut self, expires: u64, pid: Pid) -> AllocResult<(u64, u64)> {
+		let expires = expires.clamp(self.current, self.current + MAX_DELTA);
+		let id = self.next_id;
+		self.place(Entry {
+			id,
+			expires,
+			pid,
+		})?;
+		self.next_id += 1;
+		self.count += 1;
+		Ok((id, expires))
+	}
+
+	/// Removes the timeout with the given `id`, expiring at the tick `expires`.
+	///
+	/// If the timeout is not in the wheel, the function does nothing.
+	fn remove(&mut self, id: u64, expires: u64) {
+		// The timeout has been placed in the slot matching its expiration on one of the levels
+		for level in 0..LEVELS_COUNT {
+			let index = (expires >> (LEVEL_BITS * level as u32)) & LEVEL_MASK;
+			let slot = &mut self.levels[level][index as usize];
+			if let Some(i) = slot.iter().position(|e| e.id == id) {
+				slot.remove(i);
+				self.count -= 1;
+				return;
+			}
+		}
+	}
+
+	/// Moves the timeouts of the current slot of `level` to lower levels.
+	///
+	/// The function returns the index of the slot.
+	fn cascade(&mut self, level: usize) -> usize {
+		let index = ((self.current >> (LEVEL_BITS * level as u32)) & LEVEL_MASK) as usize;
+		let entries = mem::take(&mut self.levels[level][index]);
+		for entry in entries {
+			oom::wrap(|| self.place(entry));
+		}
+		index
+	}
+
+	/// Processes the current tick, then moves to the next one.
+	///
+	/// The function returns the timeouts that expired.
+	fn next_tick(&mut self) -> Vec<Entry> {
+		let index = (self.current & LEVEL_MASK) as usize;
+		// When a level wraps around, the next slot of the level above is due
+		if index == 0 {
+			for level in 1..LEVELS_COUNT {
+				if self.cascade(level) != 0 {
+					break;
+				}
+			}
+		}
+		let expired = mem::take(&mut self.levels[0][index]);
+		self.count -= expired.len();
+		self.current += 1;
+		expired
+	}
+
+	/// If the wheel is empty, skips the ticks before `now` since they have nothing to process.
+	fn skip_idle(&mut self, now: u64) {
+		if self.count == 0 {
+			self.current = self.current.max(now);
+		}
+	}
+
+	/// If the wheel is late by more than [`MAX_DELTA`] ticks on `now`, every timeout has expired.
+	/// In this case, the function removes them all, moves to the tick `now` and returns them.
+	fn drain_late(&mut self, now: u64) -> Vec<Entry> {
+		let mut expired = Vec::new();
+		if now < self.current + MAX_DELTA {
+			return expired;
+		}
+		for slot in self.levels.iter_mut().flatten() {
+			oom::wrap(|| expired.extend_from_slice(slot));
+			slot.clear();
+		}
+		self.count = 0;
+		self.current = now;
+		expired
+	}
+}
+
+/// The timer wheel.
+static WHEEL: IntMutex<TimerWheel> = IntMutex::new(TimerWheel::new());
+
+/// Returns the current tick of the wheel.
+///
+/// If `round_up` is set, a partial tick is counted as a whole one.
+fn current_tick(round_up: bool) -> u64 {
+	// Unwrap cannot fail since the clock exists
+	let now = clock::current_time(CLOCK_BOOTTIME, TimestampScale::Nanosecond).unwrap();
+	to_tick(now, round_up)
+}
+
+/// Converts the timestamp `ts` in nanoseconds to a tick of the wheel.
+///
+/// If `round_up` is set, a partial tick is counted as a whole one.
+fn to_tick(ts: u64, round_up: bool) -> u64 {
+	if round_up {
+		ts.div_ceil(TICK)
+	} else {
+		ts / TICK
+	}
+}
+
+/// Wakes up the processes of the given expired timeouts.
+fn wake(expired: Vec<Entry>) {
+	for entry in expired {
+		if let Some(proc) = Process::get_by_pid(entry.pid) {
+			proc.lock().wake();
+		}
+	}
+}
+
+/// A timeout on the timer wheel, waking up a process when its deadline is reached.
+///
+/// The process is woken up by setting its state to [`crate::process::State::Running`] if it is
+/// sleeping. When dropped, the timeout is cancelled.
+#[derive(Debug)]
+pub struct Timeout {
+	/// The ID of the timeout.
+	id: u64,
+	/// The tick at which the timeout expires.
+	expires: u64,
+}
+
+impl Timeout {
+	/// Registers a timeout.
+	///
+	/// Arguments:
+	/// - `deadline` is the value of [`CLOCK_BOOTTIME`] at which the process is woken up, in
+	///   nanoseconds.
+	/// - `pid` is the PID of the process to wake up.
+	///
+	/// If the deadline is too far in the future, the process may be woken up earlier.
+	pub fn new(deadline: u64, pid: Pid) -> AllocResult<Self> {
+		let mut wheel = WHEEL.lock();
+		wheel.skip_idle(current_tick(false));
+		let (id, expires) = wheel.insert(to_tick(deadline, true), pid)?;
+		Ok(Self {
+			id,
+			expires,
+		})
+	}
+}
+
+impl Drop for Timeout {
+	fn drop(&mut self) {
+		WHEEL.lock().remove(self.id, self.expires);
+	}
+}
+
+/// Expires the timeouts whose deadline has been reached.
+pub(super) fn tick() {
+	let now = current_tick(false);
+	let expired = {
+		let mut wheel = WHEEL.lock();
+		wheel.skip_idle(now);
+		wheel.drain_late(now)
+	};
+	wake(expired);
+	loop {
+		let expired = {
+			let mut wheel = WHEEL.lock();
+			if wheel.current > now {
+				break;
+			}
+			wheel.next_tick()
+		};
+		wake(expired);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Processes the wheel up to the tick `now` and returns the IDs of the expired timeouts.
+	fn run_until(wheel: &mut TimerWheel, now: u64) -> Vec<u64> {
+		let mut ids = Vec::new();
+		while wheel.current <= now {
+			for entry in wheel.next_tick() {
+				assert_eq!(entry.expires, wheel.current - 1);
+				ids.push(entry.id).unwrap();
+			}
+		}
+		ids
+	}
+
+	#[test_case]
+	fn wheel_expire() {
+		let mut wheel = TimerWheel::new();
+		let deadlines = [0, 1, 63, 64, 65, 4095, 4096, 300_000];
+		for d in deadlines {
+			wheel.insert(d, 0).unwrap();
+		}
+		for (id, d) in deadlines.into_iter().enumerate() {
+			// Nothing expires early
+			if d > 0 {
+				assert!(run_until(&mut wheel, d - 1).is_empty());
+			}
+			assert_eq!(run_until(&mut wheel, d).as_slice(), &[id as u64]);
+		}
+		assert_eq!(wheel.count, 0);
+	}
+
+	#[test_case]
+	fn wheel_remove() {
+		let mut wheel = TimerWheel::new();
+		let (a, a_exp) = wheel.insert(5000, 0).unwrap();
+		let (b, _) = wheel.insert(5000, 0).unwrap();
+		// Cascade `a` before removing it
+		assert!(run_until(&mut wheel, 4999).is_empty());
+		wheel.remove(a, a_exp);
+		assert_eq!(run_until(&mut wheel, 5000).as_slice(), &[b]);
+		assert_eq!(wheel.count, 0);
+	}
+}
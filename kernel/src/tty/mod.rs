@@ -129,6 +129,9 @@ pub struct TTYDisplay {
 
 	/// The current foreground Program Group ID.
 	pgrp: Pid,
+	/// The ID of the session for which the TTY is the controlling terminal. If zero, the TTY is
+	/// not attached to a session.
+	sid: Pid,
 
 	/// Tells whether the cursor is currently visible on screen.
 	cursor_visible: bool,
@@ -379,6 +382,36 @@ impl TTYDisplay {
 		self.pgrp = pgrp;
 	}
 
+	/// Returns the ID of the session for which the TTY is the controlling terminal.
+	///
+	/// If the TTY is not attached to a session, the function returns `0`.
+	pub fn get_sid(&self) -> Pid {
+		self.sid
+	}
+
+	/// Makes the TTY the controlling terminal of the session `sid`, with `pgrp` as the foreground
+	/// process group.
+	pub fn set_session(&mut self, sid: Pid, pgrp: Pid) {
+		self.sid = sid;
+		self.pgrp = pgrp;
+	}
+
+	/// Detaches the TTY from its session, without notifying any process.
+	pub fn detach(&mut self) {
+		self.sid = 0;
+		self.pgrp = 0;
+	}
+
+	/// Hangs up the TTY.
+	///
+	/// The foreground process group receives `SIGHUP`, then `SIGCONT`. Then, the TTY is detached
+	/// from its session.
+	pub fn hangup(&mut self) {
+		send_signal(Signal::SIGHUP, self.pgrp);
+		send_signal(Signal::SIGCONT, self.pgrp);
+		self.detach();
+	}
+
 	/// Returns the window size of the TTY.
 	pub fn get_winsize(&self) -> &WinSize {
 		&self.winsize
@@ -448,6 +481,7 @@ pub static TTY: TTY = TTY {
 		ansi_buffer: ANSIBuffer::new(),
 
 		pgrp: 0,
+		sid: 0,
 
 		cursor_visible: true,
 		current_color: vga::DEFAULT_COLOR,
@@ -513,6 +547,11 @@ impl TTY {
 		})
 	}
 
+	/// Returns the queue of processes waiting for incoming data to read.
+	pub fn get_read_queue(&self) -> &WaitQueue {
+		&self.rd_queue
+	}
+
 	/// Tells whether the TTY has any data available to be read.
 	pub fn has_input_available(&self) -> bool {
 		let display = self.display.lock();
@@ -669,3 +708,27 @@ impl TTY {
 		self.rd_queue.wake_next();
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn tty_session() {
+		// Do not keep the TTY locked on assertion failure, since the panic handler uses it
+		let (attached, detached) = {
+			let mut tty = TTY.display.lock();
+			// Restore the state of the console afterward
+			let (sid, pgrp) = (tty.get_sid(), tty.get_pgrp());
+			tty.set_session(5, 7);
+			tty.set_pgrp(8);
+			let attached = (tty.get_sid(), tty.get_pgrp());
+			tty.detach();
+			let detached = (tty.get_sid(), tty.get_pgrp());
+			tty.set_session(sid, pgrp);
+			(attached, detached)
+		};
+		assert_eq!(attached, (5, 8));
+		assert_eq!(detached, (0, 0));
+	}
+}
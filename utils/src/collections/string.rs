@@ -25,6 +25,7 @@ use crate::{
 };
 use core::{
 	borrow::{Borrow, BorrowMut},
+	cmp::Ordering,
 	fmt,
 	fmt::{Arguments, Debug, Write},
 	hash::{Hash, Hasher},
@@ -243,6 +244,18 @@ impl PartialEq for String {
 	}
 }
 
+impl PartialOrd for String {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for String {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.as_bytes().cmp(other.as_bytes())
+	}
+}
+
 impl PartialEq<[u8]> for String {
 	fn eq(&self, other: &[u8]) -> bool {
 		if self.len() != other.len() {
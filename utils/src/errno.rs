@@ -247,6 +247,10 @@ impl Errno {
 			ERFKILL => "Operation not possible due to RF-kill",
 			EHWPOISON => "Memory page has hardware error",
 
+			ERESTARTSYS | ERESTARTNOINTR | ERESTARTNOHAND | ERESTART_RESTARTBLOCK => {
+				"Interrupted system call should be restarted"
+			}
+
 			_ => "Unknown error",
 		}
 	}
@@ -574,5 +578,17 @@ pub const ERFKILL: i32 = 132;
 /// Memory page has hardware error.
 pub const EHWPOISON: i32 = 133;
 
+// Kernel-internal errnos, never returned to userspace
+
+/// The system call must be restarted if the signal has no handler, or if the handler has the
+/// `SA_RESTART` flag.
+pub const ERESTARTSYS: i32 = 512;
+/// The system call must always be restarted.
+pub const ERESTARTNOINTR: i32 = 513;
+/// The system call must be restarted only if the signal has no handler.
+pub const ERESTARTNOHAND: i32 = 514;
+/// The system call must be restarted through `restart_syscall` if the signal has no handler.
+pub const ERESTART_RESTARTBLOCK: i32 = 516;
+
 /// An alias to [`Result`] with [`Errno`] as error type.
 pub type EResult<T> = Result<T, Errno>;
@@ -47,6 +47,8 @@ pub const LOGIN_NAME_MAX: usize = 255;
 pub const MQ_OPEN_MAX: usize = 8;
 /// The maximum number of message priorities supported by the implementation.
 pub const MQ_PRIO_MAX: usize = 32;
+/// Maximum number of simultaneous supplementary group IDs per process.
+pub const NGROUPS_MAX: usize = 32;
 /// A value one greater than the maximum value that the system may assign to a
 /// newly-created file descriptor.
 pub const OPEN_MAX: u32 = 1024;
@@ -146,6 +146,32 @@ impl<T: ?Sized, const INT: bool> Mutex<T, INT> {
 		}
 	}
 
+	/// Tries to lock the mutex, without waiting.
+	///
+	/// If the mutex is already locked, the function returns `None`.
+	pub fn try_lock(&self) -> Option<MutexGuard<T, INT>> {
+		let int_state = if !INT {
+			let enabled = interrupt::is_enabled();
+			cli();
+			enabled
+		} else {
+			// In this case, this value does not matter
+			false
+		};
+		// Safe because using the spinlock
+		let inner = unsafe { &mut *self.inner.get() };
+		if !inner.spin.try_lock() {
+			if !INT && int_state {
+				sti();
+			}
+			return None;
+		}
+		Some(MutexGuard {
+			mutex: self,
+			int_state,
+		})
+	}
+
 	/// Unlocks the mutex. This function should not be used directly since it is called when the
 	/// mutex guard is dropped.
 	///
@@ -47,6 +47,14 @@ impl Spinlock {
 		}
 	}
 
+	/// Tries to lock the spinlock, without spinning.
+	///
+	/// The function returns `true` if the spinlock has been locked.
+	#[inline(always)]
+	pub fn try_lock(&mut self) -> bool {
+		!self.0.swap(true, atomic::Ordering::Acquire)
+	}
+
 	/// Unlocks the spinlock.
 	#[inline(always)]
 	pub fn unlock(&mut self) {